@@ -0,0 +1,19 @@
+-- メッセージブロックが属するスレッド ID（既存データは NULL のまま）
+ALTER TABLE diary_message_blocks ADD COLUMN thread_id BIGINT;
+
+-- スレッド ID でのインデックス（同期状態の集計用）
+CREATE INDEX idx_diary_message_blocks_thread_id ON diary_message_blocks(thread_id);
+
+-- スレッドごとの同期状態を管理するテーブル
+CREATE TABLE diary_sync_states (
+    -- Discord スレッド ID
+    thread_id BIGINT PRIMARY KEY,
+    -- 最後に同期が成功した日時
+    last_synced_at TIMESTAMPTZ,
+    -- 同期に失敗した回数
+    failure_count INT NOT NULL DEFAULT 0,
+    -- 最後に同期が失敗した日時
+    last_failed_at TIMESTAMPTZ,
+    -- 最後に発生したエラーの内容
+    last_error TEXT
+);
@@ -0,0 +1,2 @@
+-- スレッドごとに同期済みの添付ファイルの合計バイト数（1 日あたりの上限判定用）
+ALTER TABLE diary_sync_states ADD COLUMN attachment_bytes BIGINT NOT NULL DEFAULT 0;
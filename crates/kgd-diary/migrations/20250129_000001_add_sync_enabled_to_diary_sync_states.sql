@@ -0,0 +1,2 @@
+-- スレッドごとの Notion 同期の有効/無効（/diary pause で一時停止、/diary resume で再開）
+ALTER TABLE diary_sync_states ADD COLUMN sync_enabled BOOLEAN NOT NULL DEFAULT TRUE;
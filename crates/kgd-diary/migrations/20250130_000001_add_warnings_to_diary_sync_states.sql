@@ -0,0 +1,4 @@
+-- 警告付きで同期した項目の件数（変換失敗や上限超過による添付ファイルのスキップなど）
+ALTER TABLE diary_sync_states ADD COLUMN warning_count INT NOT NULL DEFAULT 0;
+-- 最後に発生した警告の内容
+ALTER TABLE diary_sync_states ADD COLUMN last_warning TEXT;
@@ -0,0 +1,2 @@
+-- ブックマーク・埋め込みブロックの元になった URL（メッセージ編集時の差分検出用、既存データは NULL のまま）
+ALTER TABLE diary_message_blocks ADD COLUMN source_url TEXT;
@@ -0,0 +1,9 @@
+-- 同期したメッセージ本文の描画結果のハッシュ（内容が変わらない編集イベントで Notion を更新しないため）
+CREATE TABLE diary_message_contents (
+    -- Discord メッセージ ID
+    message_id BIGINT PRIMARY KEY,
+    -- 本文から生成したブロックの SHA-256 ハッシュ
+    content_hash TEXT NOT NULL,
+    -- 更新日時
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
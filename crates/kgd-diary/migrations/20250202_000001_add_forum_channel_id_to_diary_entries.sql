@@ -0,0 +1,8 @@
+-- 日報エントリが属するフォーラムチャンネル（複数の日報を運用するため）
+-- 既存のエントリは 0 とし、起動時に設定のフォーラムチャンネル ID を割り当てる
+ALTER TABLE diary_entries
+    ADD COLUMN forum_channel_id BIGINT NOT NULL DEFAULT 0;
+
+-- フォーラムチャンネルごとの日付での検索用インデックス
+CREATE INDEX IF NOT EXISTS idx_diary_entries_forum_channel_id_date
+    ON diary_entries(forum_channel_id, date);
@@ -0,0 +1,17 @@
+-- 日報スレッドのメッセージに付いたリアクションを管理するテーブル（絵文字の統計に使う）
+CREATE TABLE diary_reactions (
+    -- リアクションが付いたメッセージの ID
+    message_id BIGINT NOT NULL,
+    -- リアクションを付けたユーザーの ID
+    user_id BIGINT NOT NULL,
+    -- 絵文字（カスタム絵文字は `<:name:id>` の形式）
+    emoji TEXT NOT NULL,
+    -- メッセージが属する日報スレッドの ID
+    thread_id BIGINT NOT NULL,
+    -- リアクションが付いた日時
+    reacted_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (message_id, user_id, emoji)
+);
+
+-- 期間で絞り込んで集計するためのインデックス
+CREATE INDEX idx_diary_reactions_reacted_at ON diary_reactions(reacted_at);
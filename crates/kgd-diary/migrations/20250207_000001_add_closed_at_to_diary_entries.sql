@@ -0,0 +1,2 @@
+-- 日報スレッドをクローズした日時（クローズしていない場合は NULL）
+ALTER TABLE diary_entries ADD COLUMN closed_at TIMESTAMPTZ;
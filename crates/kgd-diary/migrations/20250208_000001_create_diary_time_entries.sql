@@ -0,0 +1,18 @@
+-- 日報スレッドの `!start` / `!stop` で記録した作業時間を管理するテーブル
+CREATE TABLE diary_time_entries (
+    -- `!start` のメッセージ ID
+    message_id BIGINT PRIMARY KEY,
+    -- 作業を記録した日報スレッドの ID
+    thread_id BIGINT NOT NULL,
+    -- 作業名
+    task TEXT NOT NULL,
+    -- 開始日時
+    started_at TIMESTAMPTZ NOT NULL,
+    -- 終了日時（作業中の場合は NULL）
+    stopped_at TIMESTAMPTZ,
+    -- 日報ページに書き込んだ to_do ブロックの ID
+    block_id TEXT
+);
+
+-- スレッドごとに作業を引くためのインデックス
+CREATE INDEX idx_diary_time_entries_thread_id ON diary_time_entries(thread_id);
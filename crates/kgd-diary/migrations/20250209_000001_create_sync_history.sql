@@ -0,0 +1,22 @@
+-- メッセージの同期履歴（障害調査用の監査ログ）を管理するテーブル
+CREATE TABLE sync_history (
+    id BIGSERIAL PRIMARY KEY,
+    -- メッセージが投稿された日報スレッドの ID
+    thread_id BIGINT NOT NULL,
+    -- 同期したメッセージの ID
+    message_id BIGINT NOT NULL,
+    -- 操作（create / update / delete）
+    action TEXT NOT NULL,
+    -- 結果（success / partial / failure）
+    status TEXT NOT NULL,
+    -- エラーや警告の内容
+    detail TEXT,
+    -- 記録した日時
+    recorded_at TIMESTAMPTZ NOT NULL
+);
+
+-- スレッドごとに新しい順で引くためのインデックス
+CREATE INDEX idx_sync_history_thread_id_recorded_at ON sync_history(thread_id, recorded_at);
+
+-- 全スレッドの履歴を新しい順で引くためのインデックス
+CREATE INDEX idx_sync_history_recorded_at ON sync_history(recorded_at);
@@ -0,0 +1,2 @@
+-- メッセージブロックを追加した Notion ページの ID（既存データは NULL のまま）
+ALTER TABLE diary_message_blocks ADD COLUMN page_id TEXT;
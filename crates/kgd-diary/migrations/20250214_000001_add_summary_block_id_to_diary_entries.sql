@@ -0,0 +1,2 @@
+-- 日報ページのサマリーの callout ブロック ID（既存データは NULL のまま）
+ALTER TABLE diary_entries ADD COLUMN summary_block_id TEXT;
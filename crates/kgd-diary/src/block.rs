@@ -0,0 +1,195 @@
+//! 同期で作成する Notion ブロックの種類を扱う。
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{Error, bail};
+use serde::{Deserialize, Serialize};
+
+/// 同期で作成する Notion ブロックの種類。
+///
+/// `diary_message_blocks.block_type` にはスネークケースの名前で保存する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockKind {
+    /// メッセージ本文の paragraph ブロック
+    Text,
+    /// 本文中の URL から生成したブックマークブロック
+    Bookmark,
+    /// 本文中の URL から生成した埋め込みブロック
+    Embed,
+    /// 本文中の X の投稿の URL から生成した引用ブロック
+    Quote,
+    /// 本文中の URL のリンク先のサムネイル（og:image）をアップロードした画像ブロック
+    OgpImage,
+    /// 添付画像・カスタム絵文字・動画サムネイルの画像ブロック
+    Image,
+    /// 添付ファイルのファイルブロック
+    File,
+    /// 添付動画の動画ブロック
+    Video,
+    /// 添付音声の音声ブロック
+    Audio,
+    /// スポイラー画像を折りたたむトグルブロック
+    Toggle,
+    /// 同期しなかった添付ファイルなどを知らせる注記ブロック
+    Notice,
+    /// 時間が空いたときに挟む時刻の見出しブロック
+    Heading,
+    /// `!start` / `!stop` で記録した作業の to_do ブロック
+    TimeEntry,
+    /// 本文中の Markdown の表から生成した table ブロック
+    Table,
+    /// 本文中の `>` で始まる行から生成した引用ブロック
+    Blockquote,
+    /// 本文中の `$$...$$` から生成した数式ブロック
+    Equation,
+    /// 本文中のコードブロック（```）から生成した code ブロック
+    Code,
+    /// 本文中の mermaid のコードブロックから描画した図の画像ブロック
+    Diagram,
+}
+
+impl BlockKind {
+    /// すべてのブロックの種類。
+    pub const ALL: [BlockKind; 18] = [
+        BlockKind::Text,
+        BlockKind::Bookmark,
+        BlockKind::Embed,
+        BlockKind::Quote,
+        BlockKind::OgpImage,
+        BlockKind::Image,
+        BlockKind::File,
+        BlockKind::Video,
+        BlockKind::Audio,
+        BlockKind::Toggle,
+        BlockKind::Notice,
+        BlockKind::Heading,
+        BlockKind::TimeEntry,
+        BlockKind::Table,
+        BlockKind::Blockquote,
+        BlockKind::Equation,
+        BlockKind::Code,
+        BlockKind::Diagram,
+    ];
+
+    /// 保存に使う名前を返す。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BlockKind::Text => "text",
+            BlockKind::Bookmark => "bookmark",
+            BlockKind::Embed => "embed",
+            BlockKind::Quote => "quote",
+            BlockKind::OgpImage => "ogp_image",
+            BlockKind::Image => "image",
+            BlockKind::File => "file",
+            BlockKind::Video => "video",
+            BlockKind::Audio => "audio",
+            BlockKind::Toggle => "toggle",
+            BlockKind::Notice => "notice",
+            BlockKind::Heading => "heading",
+            BlockKind::TimeEntry => "time_entry",
+            BlockKind::Table => "table",
+            BlockKind::Blockquote => "blockquote",
+            BlockKind::Equation => "equation",
+            BlockKind::Code => "code",
+            BlockKind::Diagram => "diagram",
+        }
+    }
+
+    /// メッセージの編集時に、ブロックを作り直さずに内容を更新できるかどうかを返す。
+    pub fn is_updatable(self) -> bool {
+        matches!(self, BlockKind::Text)
+    }
+
+    /// メッセージ本文から生成されるブロックかどうかを返す。
+    ///
+    /// メッセージの編集時は、本文から生成し直したブロックとの差分を反映する。
+    pub fn is_derived_from_text(self) -> bool {
+        matches!(
+            self,
+            BlockKind::Text
+                | BlockKind::Bookmark
+                | BlockKind::Embed
+                | BlockKind::Quote
+                | BlockKind::OgpImage
+                | BlockKind::Table
+                | BlockKind::Blockquote
+                | BlockKind::Equation
+                | BlockKind::Code
+                | BlockKind::Diagram
+        )
+    }
+
+    /// メッセージの他のブロックを残したまま、単独で削除してよいかどうかを返す。
+    ///
+    /// 本文から生成したブロックと注記ブロックは作り直せるため単独で削除できる。
+    /// 添付ファイルのブロックはアップロード済みのファイルと対応するため、メッセージごとにのみ削除する。
+    pub fn is_deletable_standalone(self) -> bool {
+        self.is_derived_from_text() || self == BlockKind::Notice
+    }
+}
+
+impl fmt::Display for BlockKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for BlockKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match BlockKind::ALL.into_iter().find(|kind| kind.as_str() == s) {
+            Some(kind) => Ok(kind),
+            None => bail!("Unknown block type: {}", s),
+        }
+    }
+}
+
+impl TryFrom<String> for BlockKind {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_kind_round_trip() {
+        for kind in BlockKind::ALL {
+            assert_eq!(kind.as_str().parse::<BlockKind>().unwrap(), kind);
+            assert_eq!(
+                serde_json::to_value(kind).unwrap(),
+                serde_json::json!(kind.as_str())
+            );
+        }
+        assert!("link".parse::<BlockKind>().is_err());
+    }
+
+    #[test]
+    fn test_block_kind_capabilities() {
+        assert!(BlockKind::Text.is_updatable());
+        assert!(!BlockKind::Bookmark.is_updatable());
+        assert!(BlockKind::Embed.is_deletable_standalone());
+        assert!(BlockKind::Notice.is_deletable_standalone());
+        assert!(!BlockKind::Image.is_deletable_standalone());
+        assert!(!BlockKind::Toggle.is_deletable_standalone());
+        assert!(BlockKind::Bookmark.is_derived_from_text());
+        assert!(BlockKind::Quote.is_derived_from_text());
+        assert!(!BlockKind::Quote.is_updatable());
+        assert!(!BlockKind::Notice.is_derived_from_text());
+        assert!(BlockKind::OgpImage.is_deletable_standalone());
+        assert!(BlockKind::Table.is_derived_from_text());
+        assert!(!BlockKind::Table.is_updatable());
+        assert!(BlockKind::Blockquote.is_deletable_standalone());
+        assert!(!BlockKind::Blockquote.is_updatable());
+        assert!(BlockKind::Equation.is_derived_from_text());
+        assert!(BlockKind::Code.is_derived_from_text());
+        assert!(!BlockKind::Code.is_updatable());
+        assert!(BlockKind::Diagram.is_deletable_standalone());
+    }
+}
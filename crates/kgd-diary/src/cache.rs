@@ -0,0 +1,106 @@
+//! Notion API の読み取り結果を一定時間保持する TTL キャッシュ。
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// 有効期限付きのインメモリキャッシュ。
+///
+/// 読み取り専用エンドポイントの結果を保持し、書き込み時には明示的に無効化する。
+/// TTL が 0 の場合はキャッシュを行わない。
+pub struct TtlCache<K, V> {
+    /// エントリの有効期間
+    ttl: Duration,
+    /// キーごとの値と保存時刻
+    entries: Mutex<HashMap<K, (Instant, V)>>,
+}
+
+impl<K, V> TtlCache<K, V> {
+    /// 指定した有効期間でキャッシュを作成する。
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<K, (Instant, V)>> {
+        // 保持しているのはキャッシュだけなので、poison されていても中身をそのまま使う
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Eq + Hash,
+    V: Clone,
+{
+    /// 有効期限内の値を取得する。期限切れのエントリは削除する。
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.lock();
+        match entries.get(key) {
+            Some((stored_at, value)) if stored_at.elapsed() < self.ttl => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// 値を保存する。
+    pub fn insert(&self, key: K, value: V) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        self.lock().insert(key, (Instant::now(), value));
+    }
+
+    /// 指定したキーのエントリを破棄する。
+    pub fn invalidate(&self, key: &K) {
+        self.lock().remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_returns_inserted_value() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        cache.insert("key", 1);
+        assert_eq!(cache.get(&"key"), Some(1));
+        assert_eq!(cache.get(&"missing"), None);
+    }
+
+    #[test]
+    fn test_expired_entry_is_not_returned() {
+        let cache = TtlCache::new(Duration::from_millis(1));
+        cache.insert("key", 1);
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.get(&"key"), None);
+    }
+
+    #[test]
+    fn test_zero_ttl_disables_cache() {
+        let cache = TtlCache::new(Duration::ZERO);
+        cache.insert("key", 1);
+        assert_eq!(cache.get(&"key"), None);
+    }
+
+    #[test]
+    fn test_invalidate_removes_only_target() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        cache.invalidate(&"a");
+        assert_eq!(cache.get(&"a"), None);
+        assert_eq!(cache.get(&"b"), Some(2));
+    }
+}
@@ -0,0 +1,300 @@
+//! メッセージ本文中のコードブロック（```）を Notion の code ブロックに変換する。
+//!
+//! Discord と同じく、開始の ``` の直後の行が空白を含まない 1 語であれば言語として扱う。
+//! Notion が対応していない言語は「plain text」にする。
+
+/// Notion の code ブロックが対応している言語（API で指定する名前）。
+const NOTION_LANGUAGES: &[&str] = &[
+    "abap",
+    "abc",
+    "agda",
+    "arduino",
+    "ascii art",
+    "assembly",
+    "bash",
+    "basic",
+    "bnf",
+    "c",
+    "c#",
+    "c++",
+    "clojure",
+    "coffeescript",
+    "coq",
+    "css",
+    "dart",
+    "dhall",
+    "diff",
+    "docker",
+    "ebnf",
+    "elixir",
+    "elm",
+    "erlang",
+    "f#",
+    "flow",
+    "fortran",
+    "gherkin",
+    "glsl",
+    "go",
+    "graphql",
+    "groovy",
+    "haskell",
+    "hcl",
+    "html",
+    "idris",
+    "java",
+    "javascript",
+    "json",
+    "julia",
+    "kotlin",
+    "latex",
+    "less",
+    "lisp",
+    "livescript",
+    "llvm ir",
+    "lua",
+    "makefile",
+    "markdown",
+    "markup",
+    "matlab",
+    "mathematica",
+    "mermaid",
+    "nix",
+    "notion formula",
+    "objective-c",
+    "ocaml",
+    "pascal",
+    "perl",
+    "php",
+    "plain text",
+    "powershell",
+    "prolog",
+    "protobuf",
+    "purescript",
+    "python",
+    "r",
+    "racket",
+    "reason",
+    "ruby",
+    "rust",
+    "sass",
+    "scala",
+    "scheme",
+    "scss",
+    "shell",
+    "smalltalk",
+    "solidity",
+    "sql",
+    "swift",
+    "toml",
+    "typescript",
+    "vb.net",
+    "verilog",
+    "vhdl",
+    "visual basic",
+    "webassembly",
+    "xml",
+    "yaml",
+    "java/c/c++/c#",
+];
+
+/// 本文をコードブロックとそれ以外のテキストに分けた部分。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodePart {
+    /// コードブロック以外のテキスト
+    Text(String),
+    /// コードブロック
+    Code(CodeBlock),
+}
+
+/// 本文から取り出したコードブロック。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    /// 開始の ``` の後ろに書かれた言語（小文字）
+    pub language: Option<String>,
+    /// コード
+    pub code: String,
+}
+
+impl CodeBlock {
+    /// Notion の code ブロックの言語を返す。
+    pub fn notion_language(&self) -> &'static str {
+        let Some(language) = self.language.as_deref() else {
+            return "plain text";
+        };
+        let language = match language {
+            "rs" => "rust",
+            "js" | "jsx" | "mjs" | "cjs" => "javascript",
+            "ts" | "tsx" => "typescript",
+            "py" => "python",
+            "rb" => "ruby",
+            "kt" | "kts" => "kotlin",
+            "golang" => "go",
+            "sh" | "zsh" | "console" | "shell-session" => "shell",
+            "ps1" | "pwsh" => "powershell",
+            "cpp" | "cxx" | "cc" | "hpp" => "c++",
+            "cs" | "csharp" => "c#",
+            "fs" | "fsharp" => "f#",
+            "objc" => "objective-c",
+            "yml" => "yaml",
+            "md" => "markdown",
+            "tex" => "latex",
+            "dockerfile" => "docker",
+            "proto" => "protobuf",
+            "make" => "makefile",
+            "asm" => "assembly",
+            "tf" | "terraform" => "hcl",
+            "patch" => "diff",
+            "text" | "txt" | "plaintext" => "plain text",
+            language => language,
+        };
+        NOTION_LANGUAGES
+            .iter()
+            .copied()
+            .find(|name| *name == language)
+            .unwrap_or("plain text")
+    }
+
+    /// code ブロックを作成する。
+    ///
+    /// コードは `rich_text` で rich_text の要素に変換する（長いコードを上限に収まるよう分けるため）。
+    pub fn to_block(
+        &self,
+        rich_text: &impl Fn(&str) -> Vec<serde_json::Value>,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "object": "block",
+            "type": "code",
+            "code": {
+                "rich_text": rich_text(&self.code),
+                "language": self.notion_language()
+            }
+        })
+    }
+}
+
+/// 本文をコードブロックとそれ以外のテキストに分ける。
+///
+/// コードブロックの前後の空行は取り除き、空になったテキストは含めない。
+/// コードブロックが無い場合は本文をそのまま 1 つのテキストとして返す。
+pub fn split_code_blocks(text: &str) -> Vec<CodePart> {
+    let mut parts = Vec::new();
+    let mut last = 0;
+    let mut search = 0;
+
+    while let Some(open) = text[search..].find("```").map(|offset| search + offset) {
+        let inner = open + 3;
+        let Some(close) = text[inner..].find("```").map(|offset| inner + offset) else {
+            break;
+        };
+        search = close + 3;
+        let Some(code) = parse_code_block(&text[inner..close]) else {
+            continue;
+        };
+        push_text(&mut parts, &text[last..open]);
+        parts.push(CodePart::Code(code));
+        last = search;
+    }
+
+    if last == 0 {
+        return vec![CodePart::Text(text.to_string())];
+    }
+    push_text(&mut parts, &text[last..]);
+    parts
+}
+
+/// ``` で囲まれた中身を、言語とコードに分ける。コードが空の場合は None を返す。
+fn parse_code_block(content: &str) -> Option<CodeBlock> {
+    let (language, code) = match content.split_once('\n') {
+        Some((first, rest)) if !first.trim().contains(char::is_whitespace) => {
+            let language = first.trim().to_lowercase();
+            ((!language.is_empty()).then_some(language), rest)
+        }
+        _ => (None, content),
+    };
+    let code = code.strip_suffix('\n').unwrap_or(code);
+    (!code.trim().is_empty()).then(|| CodeBlock {
+        language,
+        code: code.to_string(),
+    })
+}
+
+/// コードブロックの前後のテキストを、空行を取り除いて追加する。
+fn push_text(parts: &mut Vec<CodePart>, text: &str) {
+    let text = text.trim_matches('\n');
+    if !text.trim().is_empty() {
+        parts.push(CodePart::Text(text.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(language: Option<&str>, code: &str) -> CodePart {
+        CodePart::Code(CodeBlock {
+            language: language.map(str::to_string),
+            code: code.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_split_code_blocks() {
+        assert_eq!(
+            split_code_blocks("before\n```rust\nfn main() {}\n```\nafter ```inline``` end"),
+            vec![
+                CodePart::Text("before".to_string()),
+                code(Some("rust"), "fn main() {}"),
+                CodePart::Text("after ".to_string()),
+                code(None, "inline"),
+                CodePart::Text(" end".to_string()),
+            ]
+        );
+        assert_eq!(
+            split_code_blocks("```\nlet a = 1;\n\nlet b = 2;\n```"),
+            vec![code(None, "let a = 1;\n\nlet b = 2;")]
+        );
+        assert_eq!(
+            split_code_blocks("```a b\nc\n```"),
+            vec![code(None, "a b\nc")]
+        );
+    }
+
+    #[test]
+    fn test_split_code_blocks_without_code() {
+        for text in ["no code", "``` unclosed", "``````", "```rust\n```"] {
+            assert_eq!(
+                split_code_blocks(text),
+                vec![CodePart::Text(text.to_string())]
+            );
+        }
+    }
+
+    #[test]
+    fn test_notion_language() {
+        let language = |language: Option<&str>| {
+            CodeBlock {
+                language: language.map(str::to_string),
+                code: String::new(),
+            }
+            .notion_language()
+        };
+        assert_eq!(language(Some("rust")), "rust");
+        assert_eq!(language(Some("rs")), "rust");
+        assert_eq!(language(Some("cpp")), "c++");
+        assert_eq!(language(Some("mermaid")), "mermaid");
+        assert_eq!(language(Some("brainfuck")), "plain text");
+        assert_eq!(language(None), "plain text");
+    }
+
+    #[test]
+    fn test_code_block() {
+        let block = CodeBlock {
+            language: Some("py".to_string()),
+            code: "print(1)".to_string(),
+        }
+        .to_block(&|text| vec![serde_json::json!({ "text": { "content": text } })]);
+        assert_eq!(block["type"], "code");
+        assert_eq!(block["code"]["language"], "python");
+        assert_eq!(block["code"]["rich_text"][0]["text"]["content"], "print(1)");
+    }
+}
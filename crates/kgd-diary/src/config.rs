@@ -0,0 +1,138 @@
+//! 日報の同期エンジンの設定。
+//!
+//! 設定ファイルの一部としてデシリアライズされることを想定している。
+
+use serde::{Deserialize, Serialize};
+
+/// URL 変換ルール設定。
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct UrlRuleConfig {
+    /// マッチする URL パターン
+    pub pattern: PatternConfig,
+    /// 生成するブロックタイプのリスト（link, bookmark, embed, tweet, image）
+    pub convert_to: Vec<String>,
+    /// このパターンにマッチすべき URL の一覧（起動時バリデーション用）
+    #[serde(default)]
+    pub expect_matches: Vec<String>,
+    /// このパターンにマッチすべきでない URL の一覧（起動時バリデーション用）
+    #[serde(default)]
+    pub expect_no_matches: Vec<String>,
+    /// ブロックを生成する前に URL を書き換える置換テンプレート（`regex` パターンのみ、`$1` などでキャプチャを参照できる）
+    #[serde(default)]
+    pub rewrite: Option<String>,
+}
+
+/// URL マッチパターンの種類。
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternConfig {
+    /// glob 形式のパターン
+    Glob(String),
+    /// 正規表現パターン
+    Regex(String),
+    /// 前方一致パターン
+    Prefix(String),
+}
+
+/// サイズの上限を超える添付ファイルの扱い。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OversizePolicy {
+    /// 同期せず、同期しなかった旨の注意書きを残す
+    #[default]
+    Skip,
+    /// アップロードせず、Discord CDN の URL を参照する外部ファイルブロックにする
+    Link,
+    /// 画像・動画を圧縮してアップロードする（圧縮できない場合は外部ファイルブロックにする）
+    Compress,
+}
+
+/// 伏せ字ルール設定。
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RedactionRuleConfig {
+    /// 伏せ字にする内容の正規表現パターン
+    pub pattern: String,
+    /// マッチした部分の置換文字列（`$1` などでキャプチャを参照できる、デフォルト: "[REDACTED]"）
+    #[serde(default = "default_redaction_replacement")]
+    pub replacement: String,
+}
+
+/// 画像正規化ルール設定。
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ImageRuleConfig {
+    /// 変換元の画像形式（拡張子、例: "webp", "png", "tiff"）
+    pub from: String,
+    /// 変換後の画像形式（"png" または "jpeg"）
+    pub to: String,
+    /// このサイズ（バイト）を超える画像にのみ適用する（デフォルト: 0）
+    #[serde(default)]
+    pub min_size: u64,
+    /// JPEG に変換する場合の品質（1〜100、デフォルト: 85）
+    #[serde(default = "default_image_quality")]
+    pub quality: u8,
+    /// ルールを有効にするか（デフォルト: true）
+    #[serde(default = "default_image_rule_enabled")]
+    pub enabled: bool,
+}
+
+/// ページ作成時に設定する Notion プロパティの設定。
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct NotionPropertyConfig {
+    /// プロパティ名
+    pub property: String,
+    /// プロパティの種類と設定する値
+    #[serde(flatten)]
+    pub value: NotionPropertyValue,
+}
+
+/// Notion プロパティに設定する値（`type` で種類を指定する）。
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotionPropertyValue {
+    /// セレクト
+    Select { value: String },
+    /// マルチセレクト
+    MultiSelect { value: Vec<String> },
+    /// 日付（日報の日付を設定する）
+    Date,
+    /// 数値
+    Number { value: f64 },
+    /// チェックボックス
+    Checkbox { value: bool },
+    /// ユーザー（Notion のユーザー ID の一覧）
+    People { value: Vec<String> },
+}
+
+/// 使用する Notion API のバージョン（`Notion-Version` ヘッダーの値で指定する）。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum NotionApiVersion {
+    /// データベースに直接ページを作成・検索する API
+    #[default]
+    #[serde(rename = "2022-06-28")]
+    Legacy,
+    /// データベースのデータソースにページを作成・検索する API
+    #[serde(rename = "2025-09-03")]
+    DataSources,
+}
+
+impl NotionApiVersion {
+    /// `Notion-Version` ヘッダーの値を返す。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotionApiVersion::Legacy => "2022-06-28",
+            NotionApiVersion::DataSources => "2025-09-03",
+        }
+    }
+}
+
+fn default_redaction_replacement() -> String {
+    "[REDACTED]".to_string()
+}
+
+fn default_image_quality() -> u8 {
+    85
+}
+
+fn default_image_rule_enabled() -> bool {
+    true
+}
@@ -0,0 +1,286 @@
+//! Notion にアップロードする画像の形式変換・縮小・再圧縮を行う。
+
+use std::{io::Cursor, path::Path};
+
+use anyhow::{Context as _, Result, bail};
+use image::{
+    DynamicImage, ImageFormat,
+    codecs::{
+        jpeg::JpegEncoder,
+        png::{CompressionType, FilterType, PngEncoder},
+    },
+    imageops,
+};
+
+use crate::config::ImageRuleConfig;
+
+/// JPEG の品質が指定されていない場合に使う品質。
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+/// コンパイル済み画像正規化ルール一式。
+pub struct CompiledImageRules {
+    /// 有効なルール（設定の記述順）
+    rules: Vec<ImageRule>,
+}
+
+/// コンパイル済み画像正規化ルール。
+pub struct ImageRule {
+    /// 変換元の形式
+    from: ImageFormat,
+    /// 変換後の形式
+    to: ImageFormat,
+    /// このサイズ（バイト）を超えるファイルにのみ適用する
+    min_size: u64,
+    /// JPEG に変換する場合の品質
+    quality: u8,
+}
+
+/// 変換後の画像。
+pub struct NormalizedImage {
+    /// 変換後のデータ
+    pub data: Vec<u8>,
+    /// 変換後の拡張子
+    pub extension: &'static str,
+    /// 変換後の Content-Type
+    pub content_type: &'static str,
+}
+
+impl CompiledImageRules {
+    /// ファイル名とサイズに一致する最初のルールを返す。
+    pub fn find(&self, filename: &str, size: u64) -> Option<&ImageRule> {
+        let format = format_from_filename(filename)?;
+        self.rules
+            .iter()
+            .find(|rule| rule.from == format && size > rule.min_size)
+    }
+}
+
+impl ImageRule {
+    /// 画像を変換する。
+    pub fn apply(&self, data: &[u8]) -> Result<NormalizedImage> {
+        let image = image::load_from_memory_with_format(data, self.from)
+            .with_context(|| format!("Failed to decode {:?} image", self.from))?;
+        encode(&image, self.to, self.quality)
+    }
+
+    /// 変換元の形式名を返す。
+    pub fn source_name(&self) -> &'static str {
+        self.from.extensions_str()[0]
+    }
+
+    /// 変換後の形式名を返す。
+    pub fn target_name(&self) -> &'static str {
+        self.to.extensions_str()[0]
+    }
+}
+
+/// 設定からコンパイル済み画像正規化ルールを作成する。
+///
+/// 無効化されたルールは含めない。未対応の形式はエラーとして返す。
+pub fn compile_image_rules(rules: &[ImageRuleConfig]) -> Result<CompiledImageRules> {
+    let mut compiled = Vec::new();
+
+    for rule in rules.iter().filter(|rule| rule.enabled) {
+        let from = ImageFormat::from_extension(&rule.from)
+            .filter(|format| format.reading_enabled())
+            .with_context(|| format!("Unsupported source image format '{}'", rule.from))?;
+        let to = match ImageFormat::from_extension(&rule.to) {
+            Some(format @ (ImageFormat::Jpeg | ImageFormat::Png)) => format,
+            _ => bail!(
+                "Unsupported target image format '{}' (expected png or jpeg)",
+                rule.to
+            ),
+        };
+        if !(1..=100).contains(&rule.quality) {
+            bail!(
+                "JPEG quality must be between 1 and 100, got {}",
+                rule.quality
+            );
+        }
+
+        compiled.push(ImageRule {
+            from,
+            to,
+            min_size: rule.min_size,
+            quality: rule.quality,
+        });
+    }
+
+    Ok(CompiledImageRules { rules: compiled })
+}
+
+/// 画像を指定した品質の JPEG に再圧縮する。
+///
+/// 元の形式はデータの内容から判定する。
+pub fn compress_to_jpeg(data: &[u8], quality: u8) -> Result<NormalizedImage> {
+    let image = image::load_from_memory(data).context("Failed to decode image")?;
+    encode(&image, ImageFormat::Jpeg, quality)
+}
+
+/// 画像の長辺が `max_dimension` 以下になるよう縮小し、再圧縮する。
+///
+/// JPEG は `jpeg_quality` で JPEG に、それ以外の画像は PNG に再エンコードする。
+/// 縮小も再圧縮も不要な場合や、縮小せずに再圧縮した結果が元より大きくなる場合は `None` を返す。
+pub fn shrink_image(
+    data: &[u8],
+    max_dimension: Option<u32>,
+    jpeg_quality: Option<u8>,
+) -> Result<Option<NormalizedImage>> {
+    let format = image::guess_format(data).context("Failed to detect image format")?;
+    // GIF はアニメーションが失われるため対象外
+    if format == ImageFormat::Gif {
+        return Ok(None);
+    }
+
+    let image = image::load_from_memory_with_format(data, format)
+        .with_context(|| format!("Failed to decode {:?} image", format))?;
+    let resize_to = max_dimension.filter(|max| image.width() > *max || image.height() > *max);
+    let recompress = format == ImageFormat::Jpeg && jpeg_quality.is_some();
+    if resize_to.is_none() && !recompress {
+        return Ok(None);
+    }
+
+    let image = match resize_to {
+        // アスペクト比を保ったまま長辺を max に合わせる
+        Some(max) => image.resize(max, max, imageops::FilterType::Lanczos3),
+        None => image,
+    };
+    let to = if format == ImageFormat::Jpeg {
+        ImageFormat::Jpeg
+    } else {
+        ImageFormat::Png
+    };
+    let shrunk = encode(&image, to, jpeg_quality.unwrap_or(DEFAULT_JPEG_QUALITY))?;
+
+    if resize_to.is_none() && shrunk.data.len() >= data.len() {
+        return Ok(None);
+    }
+    Ok(Some(shrunk))
+}
+
+/// 画像を指定した形式でエンコードする。
+fn encode(image: &DynamicImage, format: ImageFormat, quality: u8) -> Result<NormalizedImage> {
+    let mut output = Cursor::new(Vec::new());
+    match format {
+        ImageFormat::Jpeg => {
+            // JPEG はアルファチャンネルを持てないため RGB に落とす
+            DynamicImage::ImageRgb8(image.to_rgb8())
+                .write_with_encoder(JpegEncoder::new_with_quality(&mut output, quality))
+                .context("Failed to encode JPEG image")?;
+        }
+        ImageFormat::Png => {
+            image
+                .write_with_encoder(PngEncoder::new_with_quality(
+                    &mut output,
+                    CompressionType::Best,
+                    FilterType::Adaptive,
+                ))
+                .context("Failed to encode PNG image")?;
+        }
+        format => bail!("Unsupported output image format: {:?}", format),
+    }
+
+    Ok(NormalizedImage {
+        data: output.into_inner(),
+        extension: format.extensions_str()[0],
+        content_type: format.to_mime_type(),
+    })
+}
+
+/// ファイル名の拡張子から画像形式を判定する。
+fn format_from_filename(filename: &str) -> Option<ImageFormat> {
+    let extension = Path::new(filename).extension()?.to_str()?;
+    ImageFormat::from_extension(extension.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::RgbaImage;
+
+    use super::*;
+
+    fn rule(from: &str, to: &str, min_size: u64) -> ImageRuleConfig {
+        ImageRuleConfig {
+            from: from.to_string(),
+            to: to.to_string(),
+            min_size,
+            quality: DEFAULT_JPEG_QUALITY,
+            enabled: true,
+        }
+    }
+
+    fn encode_sample(format: ImageFormat) -> Vec<u8> {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(4, 4));
+        let mut output = Cursor::new(Vec::new());
+        image.write_to(&mut output, format).unwrap();
+        output.into_inner()
+    }
+
+    #[test]
+    fn test_find_matches_format_and_size() {
+        let mut disabled = rule("gif", "png", 0);
+        disabled.enabled = false;
+        let rules = compile_image_rules(&[
+            rule("webp", "png", 0),
+            rule("png", "jpeg", 5 * 1024 * 1024),
+            disabled,
+        ])
+        .unwrap();
+
+        assert!(rules.find("sticker.WEBP", 10).is_some());
+        assert!(rules.find("screenshot.png", 1024).is_none());
+        assert!(rules.find("screenshot.png", 6 * 1024 * 1024).is_some());
+        assert!(rules.find("animation.gif", 10).is_none());
+        assert!(rules.find("noextension", 10).is_none());
+    }
+
+    #[test]
+    fn test_compile_rejects_unsupported_formats() {
+        assert!(compile_image_rules(&[rule("heic", "jpeg", 0)]).is_err());
+        assert!(compile_image_rules(&[rule("png", "gif", 0)]).is_err());
+
+        let mut invalid_quality = rule("png", "jpeg", 0);
+        invalid_quality.quality = 0;
+        assert!(compile_image_rules(&[invalid_quality]).is_err());
+    }
+
+    #[test]
+    fn test_apply_converts_png_to_jpeg() {
+        let rules = compile_image_rules(&[rule("png", "jpeg", 0)]).unwrap();
+        let image = rules
+            .find("image.png", 1)
+            .unwrap()
+            .apply(&encode_sample(ImageFormat::Png))
+            .unwrap();
+
+        assert_eq!(image.extension, "jpg");
+        assert_eq!(image.content_type, "image/jpeg");
+        assert_eq!(image::guess_format(&image.data).unwrap(), ImageFormat::Jpeg);
+    }
+
+    #[test]
+    fn test_compress_to_jpeg_detects_source_format() {
+        let image = compress_to_jpeg(&encode_sample(ImageFormat::WebP), 75).unwrap();
+
+        assert_eq!(image.extension, "jpg");
+        assert_eq!(image::guess_format(&image.data).unwrap(), ImageFormat::Jpeg);
+    }
+
+    #[test]
+    fn test_shrink_image_resizes_keeping_aspect_ratio() {
+        let data = {
+            let image = DynamicImage::ImageRgba8(RgbaImage::new(40, 20));
+            let mut output = Cursor::new(Vec::new());
+            image.write_to(&mut output, ImageFormat::Png).unwrap();
+            output.into_inner()
+        };
+
+        let shrunk = shrink_image(&data, Some(10), None).unwrap().unwrap();
+        let image = image::load_from_memory(&shrunk.data).unwrap();
+        assert_eq!((image.width(), image.height()), (10, 5));
+        assert_eq!(shrunk.extension, "png");
+
+        assert!(shrink_image(&data, Some(40), None).unwrap().is_none());
+        assert!(shrink_image(&data, None, Some(50)).unwrap().is_none());
+    }
+}
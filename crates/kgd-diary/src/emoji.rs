@@ -0,0 +1,112 @@
+//! メッセージ中の Discord カスタム絵文字（`<:name:id>`）を扱う。
+
+use std::borrow::Cow;
+
+use regex::{Captures, Regex};
+
+/// メッセージ中のカスタム絵文字。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomEmoji {
+    /// 絵文字名
+    pub name: String,
+    /// 絵文字 ID
+    pub id: u64,
+    /// アニメーション絵文字かどうか
+    pub animated: bool,
+}
+
+impl CustomEmoji {
+    /// Discord CDN 上の絵文字画像の URL を返す。
+    pub fn cdn_url(&self) -> String {
+        format!("https://cdn.discordapp.com/emojis/{}", self.filename())
+    }
+
+    /// アップロード時に使うファイル名を返す。
+    pub fn filename(&self) -> String {
+        format!("{}.{}", self.id, self.extension())
+    }
+
+    /// 絵文字画像の Content-Type を返す。
+    pub fn content_type(&self) -> &'static str {
+        if self.animated {
+            "image/gif"
+        } else {
+            "image/png"
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        if self.animated { "gif" } else { "png" }
+    }
+}
+
+/// テキストに含まれるカスタム絵文字を出現順に重複なく返す。
+pub fn parse_custom_emojis(text: &str) -> Vec<CustomEmoji> {
+    let mut emojis: Vec<CustomEmoji> = Vec::new();
+    for caps in custom_emoji_regex().captures_iter(text) {
+        let Ok(id) = caps[3].parse() else {
+            continue;
+        };
+        if emojis.iter().any(|emoji| emoji.id == id) {
+            continue;
+        }
+        emojis.push(CustomEmoji {
+            name: caps[2].to_string(),
+            id,
+            animated: !caps[1].is_empty(),
+        });
+    }
+    emojis
+}
+
+/// カスタム絵文字タグを `:name:` 形式の絵文字名に置換する。
+pub fn replace_custom_emojis(text: &str) -> Cow<'_, str> {
+    custom_emoji_regex().replace_all(text, |caps: &Captures| format!(":{}:", &caps[2]))
+}
+
+/// カスタム絵文字タグ（`<:name:id>` / アニメーション絵文字は `<a:name:id>`）にマッチする正規表現。
+fn custom_emoji_regex() -> Regex {
+    Regex::new(r"<(a?):(\w+):(\d+)>").unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_custom_emojis() {
+        let emojis = parse_custom_emojis("hi <:wave:123> <a:party:456> <:wave:123> <@789>");
+        assert_eq!(
+            emojis,
+            vec![
+                CustomEmoji {
+                    name: "wave".to_string(),
+                    id: 123,
+                    animated: false,
+                },
+                CustomEmoji {
+                    name: "party".to_string(),
+                    id: 456,
+                    animated: true,
+                },
+            ]
+        );
+        assert_eq!(
+            emojis[0].cdn_url(),
+            "https://cdn.discordapp.com/emojis/123.png"
+        );
+        assert_eq!(emojis[1].filename(), "456.gif");
+    }
+
+    #[test]
+    fn test_replace_custom_emojis() {
+        assert_eq!(
+            replace_custom_emojis("hi <:wave:123> and <a:party:456>!"),
+            "hi :wave: and :party:!"
+        );
+        assert!(matches!(
+            replace_custom_emojis("no emoji <@123>"),
+            Cow::Borrowed(_)
+        ));
+    }
+}
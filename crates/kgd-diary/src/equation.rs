@@ -0,0 +1,244 @@
+//! メッセージ本文中の `$...$` / `$$...$$` の数式（LaTeX）を Notion の数式に変換する。
+//!
+//! `$$...$$` は数式ブロックに、`$...$` は rich_text のインライン数式にする。
+//! 金額などの `$` を数式として扱わないよう、Pandoc と同じく開始の `$` の直後と終了の `$` の直前に
+//! 空白が無く、終了の `$` の直後が数字でないものだけをインライン数式とする。
+//! `\$` と、コード（`` ` `` で囲んだ部分とコードブロック）の中の `$` は数式として扱わない。
+
+use std::ops::Range;
+
+/// 数式の長さの上限（Notion API の equation の expression の制限）。
+const MAX_EXPRESSION_LENGTH: usize = 1000;
+
+/// 本文を数式ブロックとそれ以外のテキストに分けた部分。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MathPart {
+    /// 数式以外のテキスト
+    Text(String),
+    /// `$$...$$` の数式（区切りを除いた式）
+    Equation(String),
+}
+
+/// 本文を `$$...$$` の数式とそれ以外のテキストに分ける。
+///
+/// 数式の前後の空行は取り除き、空になったテキストは含めない。数式が無い場合は本文をそのまま 1 つのテキストとして返す。
+pub fn split_block_equations(text: &str) -> Vec<MathPart> {
+    let equations = find_equations(text, true);
+    if equations.is_empty() {
+        return vec![MathPart::Text(text.to_string())];
+    }
+
+    let mut parts = Vec::new();
+    let mut last = 0;
+    for (range, expression) in equations {
+        push_text(&mut parts, &text[last..range.start]);
+        parts.push(MathPart::Equation(expression.to_string()));
+        last = range.end;
+    }
+    push_text(&mut parts, &text[last..]);
+    parts
+}
+
+/// 数式の前後のテキストを、空行を取り除いて追加する。
+fn push_text(parts: &mut Vec<MathPart>, text: &str) {
+    let text = text.trim_matches('\n');
+    if !text.trim().is_empty() {
+        parts.push(MathPart::Text(text.to_string()));
+    }
+}
+
+/// rich_text の要素のうちリンクの無いテキストから、数式を equation の要素に分ける。
+///
+/// 書式などの他の属性は、分けた前後のテキストの要素に引き継ぐ。
+pub fn split_inline_equations(rich_text: &[serde_json::Value]) -> Vec<serde_json::Value> {
+    let mut result = Vec::with_capacity(rich_text.len());
+    for element in rich_text {
+        let content = match element["text"]["content"].as_str() {
+            Some(content) if element["text"]["link"].is_null() => content,
+            _ => {
+                result.push(element.clone());
+                continue;
+            }
+        };
+
+        let mut last = 0;
+        for (range, expression) in find_equations(content, false) {
+            if range.start > last {
+                result.push(text_element(element, &content[last..range.start]));
+            }
+            result.push(equation_rich_text_json(expression));
+            last = range.end;
+        }
+        if last == 0 {
+            result.push(element.clone());
+        } else if last < content.len() {
+            result.push(text_element(element, &content[last..]));
+        }
+    }
+    result
+}
+
+/// 元の要素の属性を保ったまま、テキストだけを差し替えた要素を作成する。
+fn text_element(element: &serde_json::Value, content: &str) -> serde_json::Value {
+    let mut element = element.clone();
+    element["text"]["content"] = serde_json::json!(content);
+    element
+}
+
+/// 数式ブロック JSON を生成する。
+pub fn equation_block_json(expression: &str) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "equation",
+        "equation": {
+            "expression": expression
+        }
+    })
+}
+
+/// インライン数式の rich_text JSON を生成する。
+fn equation_rich_text_json(expression: &str) -> serde_json::Value {
+    serde_json::json!({
+        "type": "equation",
+        "equation": {
+            "expression": expression
+        }
+    })
+}
+
+/// テキスト中の数式を探し、区切りを含む範囲と式を出現順に返す。
+///
+/// `block_only` の場合は `$$...$$` だけを探す。
+fn find_equations(text: &str, block_only: bool) -> Vec<(Range<usize>, &str)> {
+    let mut equations = Vec::new();
+    let mut index = 0;
+    while let Some(c) = text[index..].chars().next() {
+        let rest = &text[index..];
+        let skip = if let Some(code) = rest.strip_prefix("```") {
+            // コードブロックの終わりまで飛ばす
+            code.find("```").map(|end| end + 6)
+        } else if c == '`' {
+            rest[1..].find('`').map(|end| end + 2)
+        } else if c == '\\' {
+            // エスケープされた文字を飛ばす
+            rest[1..]
+                .chars()
+                .next()
+                .map(|escaped| 1 + escaped.len_utf8())
+        } else if c == '$' {
+            match match_equation(text, index, block_only) {
+                Some((end, expression)) => {
+                    equations.push((index..end, expression));
+                    Some(end - index)
+                }
+                // 対応しない `$$` を 1 文字ずつ見て `$` と誤認しないよう、まとめて飛ばす
+                None => Some(if rest.starts_with("$$") { 2 } else { 1 }),
+            }
+        } else {
+            None
+        };
+        index += skip.unwrap_or(c.len_utf8());
+    }
+    equations
+}
+
+/// `start` の `$` から始まる数式を解釈し、数式の終わりの位置と式を返す。
+fn match_equation(text: &str, start: usize, block_only: bool) -> Option<(usize, &str)> {
+    let (expression, end) = if text[start..].starts_with("$$") {
+        let inner = start + 2;
+        let close = text[inner..].find("$$")? + inner;
+        (text[inner..close].trim(), close + 2)
+    } else if block_only {
+        return None;
+    } else {
+        let inner = start + 1;
+        if text[inner..].chars().next()?.is_whitespace() {
+            return None;
+        }
+        // インライン数式は行をまたがない
+        let line_end = text[inner..]
+            .find('\n')
+            .map_or(text.len(), |end| inner + end);
+        let close = text[inner..line_end]
+            .match_indices('$')
+            .map(|(offset, _)| inner + offset)
+            .find(|&close| {
+                let before = text[..close].chars().next_back();
+                let after = text[close + 1..].chars().next();
+                close > inner
+                    && before.is_some_and(|c| !c.is_whitespace() && c != '\\')
+                    && !after.is_some_and(|c| c.is_ascii_digit())
+            })?;
+        (&text[inner..close], close + 1)
+    };
+
+    (!expression.is_empty() && expression.chars().count() <= MAX_EXPRESSION_LENGTH)
+        .then_some((end, expression))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(content: &str) -> serde_json::Value {
+        serde_json::json!({ "type": "text", "text": { "content": content } })
+    }
+
+    #[test]
+    fn test_split_block_equations() {
+        assert_eq!(
+            split_block_equations("energy:\n$$\nE = mc^2\n$$\ndone"),
+            vec![
+                MathPart::Text("energy:".to_string()),
+                MathPart::Equation("E = mc^2".to_string()),
+                MathPart::Text("done".to_string()),
+            ]
+        );
+        for text in ["$x$ only", "```\n$$x$$\n```", "$$ $$", "a $$b"] {
+            assert_eq!(
+                split_block_equations(text),
+                vec![MathPart::Text(text.to_string())]
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_inline_equations() {
+        let rich_text = split_inline_equations(&[text("area $\\pi r^2$ m")]);
+        assert_eq!(
+            rich_text,
+            vec![
+                text("area "),
+                equation_rich_text_json("\\pi r^2"),
+                text(" m")
+            ]
+        );
+
+        // 金額やコード、エスケープは数式にしない
+        for content in [
+            "$5 and $10",
+            "costs $5/$6",
+            "`$x$` is code",
+            "\\$x$ escaped",
+            "$ x$",
+            "a$\nb$",
+        ] {
+            assert_eq!(
+                split_inline_equations(&[text(content)]),
+                vec![text(content)],
+                "{}",
+                content
+            );
+        }
+
+        // リンクのテキストはそのまま残す
+        let link = serde_json::json!({
+            "type": "text",
+            "text": { "content": "$x$", "link": { "url": "https://example.com/$x$" } }
+        });
+        assert_eq!(
+            split_inline_equations(std::slice::from_ref(&link)),
+            vec![link]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! 外部の `ffmpeg` コマンドを使った動画処理を提供する。
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result, bail};
+use tokio::process::Command;
+
+/// `ffmpeg` コマンドの実行設定。
+#[derive(Debug, Clone)]
+pub struct Ffmpeg {
+    /// `ffmpeg` 実行ファイルのパス
+    path: PathBuf,
+    /// 1 回の実行のタイムアウト
+    timeout: Duration,
+}
+
+impl Ffmpeg {
+    /// 新しい Ffmpeg を作成する。
+    pub fn new(path: impl Into<PathBuf>, timeout: Duration) -> Self {
+        Self {
+            path: path.into(),
+            timeout,
+        }
+    }
+
+    /// 動画から代表的なフレームを 1 枚取り出し、JPEG として `output` に書き出す。
+    pub async fn extract_thumbnail(&self, input: &Path, output: &Path) -> Result<()> {
+        self.run([
+            OsStr::new("-i"),
+            input.as_os_str(),
+            // 先頭付近のフレームから黒画面などを避けた代表フレームを選ぶ
+            OsStr::new("-vf"),
+            OsStr::new("thumbnail"),
+            OsStr::new("-frames:v"),
+            OsStr::new("1"),
+            OsStr::new("-q:v"),
+            OsStr::new("3"),
+            output.as_os_str(),
+        ])
+        .await
+    }
+
+    /// 動画を H.264 / AAC の MP4 に変換して `output` に書き出す。
+    ///
+    /// `bitrate_kbps` は映像のビットレート（kbps）。
+    pub async fn transcode_to_h264(
+        &self,
+        input: &Path,
+        output: &Path,
+        bitrate_kbps: u32,
+    ) -> Result<()> {
+        let bitrate = format!("{}k", bitrate_kbps);
+        let buffer_size = format!("{}k", bitrate_kbps.saturating_mul(2));
+        self.run([
+            OsStr::new("-i"),
+            input.as_os_str(),
+            OsStr::new("-c:v"),
+            OsStr::new("libx264"),
+            OsStr::new("-preset"),
+            OsStr::new("veryfast"),
+            OsStr::new("-b:v"),
+            OsStr::new(&bitrate),
+            OsStr::new("-maxrate"),
+            OsStr::new(&bitrate),
+            OsStr::new("-bufsize"),
+            OsStr::new(&buffer_size),
+            // ブラウザで再生できるよう 4:2:0 に揃える
+            OsStr::new("-pix_fmt"),
+            OsStr::new("yuv420p"),
+            OsStr::new("-c:a"),
+            OsStr::new("aac"),
+            // 再生開始前に全体をダウンロードしなくて済むよう moov atom を先頭に置く
+            OsStr::new("-movflags"),
+            OsStr::new("+faststart"),
+            output.as_os_str(),
+        ])
+        .await
+    }
+
+    /// 共通のオプションを付けて `ffmpeg` を実行する。
+    async fn run<'a>(&self, args: impl IntoIterator<Item = &'a OsStr>) -> Result<()> {
+        let child = Command::new(&self.path)
+            .args(["-y", "-hide_banner", "-loglevel", "error"])
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to run ffmpeg: {}", self.path.display()))?;
+
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .with_context(|| format!("ffmpeg timed out after {:?}", self.timeout))?
+            .context("Failed to wait for ffmpeg")?;
+
+        if !output.status.success() {
+            bail!(
+                "ffmpeg exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+//! GitHub の issue・プルリクエスト・リポジトリのブックマークを GitHub API で展開する URL ハンドラー。
+
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use futures::future::BoxFuture;
+use regex::Regex;
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use serde::Deserialize;
+
+use crate::{block::BlockKind, ogp::OgpMetadata, url_handler::UrlHandler, url_parser};
+
+/// GitHub REST API のベース URL。
+const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// GitHub の URL のブックマークを展開する [`UrlHandler`]。
+///
+/// issue・プルリクエストは「#123 タイトル (open)」、リポジトリは「owner/repo」をタイトルに、
+/// 本文やリポジトリの説明を説明にしたキャプションを付ける。
+pub struct GitHubHandler {
+    http_client: reqwest::Client,
+}
+
+impl GitHubHandler {
+    /// 新しい GitHubHandler を作成する。
+    ///
+    /// # Arguments
+    /// * `timeout` - API リクエストのタイムアウト
+    /// * `token` - GitHub API のトークン（未指定の場合は認証なしで呼び出すため rate limit が厳しい）
+    pub fn new(timeout: Duration, token: Option<&str>) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("application/vnd.github+json"),
+        );
+        if let Some(token) = token {
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+                .context("Invalid GitHub token")?;
+            value.set_sensitive(true);
+            headers.insert(header::AUTHORIZATION, value);
+        }
+
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .user_agent("kgd-bot/1.0")
+            .default_headers(headers)
+            .build()
+            .context("Failed to create HTTP client for GitHub handler")?;
+
+        Ok(Self { http_client })
+    }
+
+    async fn fetch_inner(&self, resource: GitHubResource) -> Result<OgpMetadata> {
+        match resource {
+            GitHubResource::Issue {
+                owner,
+                repo,
+                number,
+            } => {
+                let issue: GitHubIssue = self
+                    .get(&format!("/repos/{}/{}/issues/{}", owner, repo, number))
+                    .await?;
+                Ok(issue.to_metadata(&issue.state))
+            }
+            GitHubResource::PullRequest {
+                owner,
+                repo,
+                number,
+            } => {
+                let pull: GitHubIssue = self
+                    .get(&format!("/repos/{}/{}/pulls/{}", owner, repo, number))
+                    .await?;
+                let state = if pull.merged { "merged" } else { &pull.state };
+                Ok(pull.to_metadata(state))
+            }
+            GitHubResource::Repository { owner, repo } => {
+                let repository: GitHubRepository =
+                    self.get(&format!("/repos/{}/{}", owner, repo)).await?;
+                Ok(OgpMetadata {
+                    title: Some(repository.full_name),
+                    description: repository.description.filter(|d| !d.trim().is_empty()),
+                    image: None,
+                    site_name: Some("GitHub".to_string()),
+                    og_type: None,
+                })
+            }
+        }
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self
+            .http_client
+            .get(format!("{}{}", GITHUB_API_URL, path))
+            .send()
+            .await
+            .context("HTTP request failed")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("HTTP status: {}", response.status());
+        }
+
+        response
+            .json()
+            .await
+            .context("Failed to parse GitHub API response")
+    }
+}
+
+impl UrlHandler for GitHubHandler {
+    fn name(&self) -> &'static str {
+        "github"
+    }
+
+    fn matches(&self, url: &str, kind: BlockKind) -> bool {
+        kind == BlockKind::Bookmark && GitHubResource::parse(url).is_some()
+    }
+
+    fn build_block<'a>(
+        &'a self,
+        url: &'a str,
+        block: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<serde_json::Value>> {
+        Box::pin(async move {
+            let resource = GitHubResource::parse(url).context("URL is not a GitHub URL")?;
+            let metadata = self.fetch_inner(resource).await?;
+            let mut block = block.clone();
+            url_parser::apply_ogp_to_bookmark(&mut block, &metadata);
+            Ok(block)
+        })
+    }
+}
+
+/// URL が指す GitHub のリソース。
+#[derive(Debug, PartialEq, Eq)]
+enum GitHubResource {
+    Issue {
+        owner: String,
+        repo: String,
+        number: u64,
+    },
+    PullRequest {
+        owner: String,
+        repo: String,
+        number: u64,
+    },
+    Repository {
+        owner: String,
+        repo: String,
+    },
+}
+
+impl GitHubResource {
+    /// GitHub の URL を解析する。issue・プルリクエスト・リポジトリのトップ以外は None を返す。
+    fn parse(url: &str) -> Option<Self> {
+        let re = Regex::new(
+            r"^https?://(?:www\.)?github\.com/([\w.-]+)/([\w.-]+)(?:/(issues|pull)/(\d+)(?:/[^?#]*)?)?/?(?:[?#].*)?$",
+        )
+        .unwrap();
+        let caps = re.captures(url)?;
+        let owner = caps[1].to_string();
+        let repo = caps[2].trim_end_matches(".git").to_string();
+
+        match (caps.get(3), caps.get(4)) {
+            (Some(kind), Some(number)) => {
+                let number = number.as_str().parse().ok()?;
+                Some(if kind.as_str() == "issues" {
+                    Self::Issue {
+                        owner,
+                        repo,
+                        number,
+                    }
+                } else {
+                    Self::PullRequest {
+                        owner,
+                        repo,
+                        number,
+                    }
+                })
+            }
+            _ => Some(Self::Repository { owner, repo }),
+        }
+    }
+}
+
+/// issue・プルリクエストの API レスポンス。
+#[derive(Debug, Deserialize)]
+struct GitHubIssue {
+    number: u64,
+    title: String,
+    /// open / closed
+    state: String,
+    body: Option<String>,
+    /// マージ済みかどうか（プルリクエストのみ）
+    #[serde(default)]
+    merged: bool,
+}
+
+impl GitHubIssue {
+    /// 「#123 タイトル (状態)」をタイトル、本文を説明とするメタデータに変換する。
+    fn to_metadata(&self, state: &str) -> OgpMetadata {
+        OgpMetadata {
+            title: Some(format!("#{} {} ({})", self.number, self.title, state)),
+            description: self
+                .body
+                .as_deref()
+                .map(str::trim)
+                .filter(|body| !body.is_empty())
+                .map(str::to_string),
+            image: None,
+            site_name: Some("GitHub".to_string()),
+            og_type: None,
+        }
+    }
+}
+
+/// リポジトリの API レスポンス。
+#[derive(Debug, Deserialize)]
+struct GitHubRepository {
+    full_name: String,
+    description: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_github_resource() {
+        assert_eq!(
+            GitHubResource::parse("https://github.com/ekuinox/kgd/issues/123"),
+            Some(GitHubResource::Issue {
+                owner: "ekuinox".to_string(),
+                repo: "kgd".to_string(),
+                number: 123,
+            })
+        );
+        assert_eq!(
+            GitHubResource::parse("https://github.com/ekuinox/kgd/pull/45/files?w=1"),
+            Some(GitHubResource::PullRequest {
+                owner: "ekuinox".to_string(),
+                repo: "kgd".to_string(),
+                number: 45,
+            })
+        );
+        assert_eq!(
+            GitHubResource::parse("https://github.com/ekuinox/kgd.git"),
+            Some(GitHubResource::Repository {
+                owner: "ekuinox".to_string(),
+                repo: "kgd".to_string(),
+            })
+        );
+        assert_eq!(
+            GitHubResource::parse("https://github.com/ekuinox/kgd/blob/main/README.md"),
+            None
+        );
+        assert_eq!(GitHubResource::parse("https://github.com/ekuinox"), None);
+        assert_eq!(
+            GitHubResource::parse("https://gitlab.com/ekuinox/kgd/issues/1"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_issue_to_metadata() {
+        let pull: GitHubIssue = serde_json::from_value(serde_json::json!({
+            "number": 123,
+            "title": "Fix bug",
+            "state": "closed",
+            "body": "  詳細\n",
+            "merged": true
+        }))
+        .unwrap();
+        let metadata = pull.to_metadata("merged");
+        assert_eq!(metadata.title.as_deref(), Some("#123 Fix bug (merged)"));
+        assert_eq!(metadata.description.as_deref(), Some("詳細"));
+
+        let issue: GitHubIssue = serde_json::from_value(serde_json::json!({
+            "number": 7,
+            "title": "Crash",
+            "state": "open",
+            "body": ""
+        }))
+        .unwrap();
+        let metadata = issue.to_metadata(&issue.state);
+        assert_eq!(metadata.title.as_deref(), Some("#7 Crash (open)"));
+        assert_eq!(metadata.description, None);
+    }
+}
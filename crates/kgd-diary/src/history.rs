@@ -0,0 +1,161 @@
+//! メッセージの同期履歴（障害調査用の監査ログ）を扱う。
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{Error, bail};
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+/// 同期履歴に記録する操作。
+///
+/// `sync_history.action` にはスネークケースの名前で保存する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncAction {
+    /// メッセージを同期した
+    Create,
+    /// メッセージの編集を反映した
+    Update,
+    /// メッセージの削除を反映した
+    Delete,
+}
+
+impl SyncAction {
+    /// すべての操作。
+    pub const ALL: [SyncAction; 3] = [SyncAction::Create, SyncAction::Update, SyncAction::Delete];
+
+    /// 保存に使う名前を返す。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SyncAction::Create => "create",
+            SyncAction::Update => "update",
+            SyncAction::Delete => "delete",
+        }
+    }
+}
+
+/// 同期履歴に記録する結果。
+///
+/// `sync_history.status` にはスネークケースの名前で保存する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncHistoryStatus {
+    /// すべて同期できた
+    Success,
+    /// 警告付きで同期した、または一部を同期できなかった
+    Partial,
+    /// 同期に失敗した
+    Failure,
+}
+
+impl SyncHistoryStatus {
+    /// すべての結果。
+    pub const ALL: [SyncHistoryStatus; 3] = [
+        SyncHistoryStatus::Success,
+        SyncHistoryStatus::Partial,
+        SyncHistoryStatus::Failure,
+    ];
+
+    /// 保存に使う名前を返す。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SyncHistoryStatus::Success => "success",
+            SyncHistoryStatus::Partial => "partial",
+            SyncHistoryStatus::Failure => "failure",
+        }
+    }
+}
+
+/// 同期履歴 1 件。
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct SyncHistoryRecord {
+    /// メッセージが投稿された日報スレッドの ID
+    #[sqlx(try_from = "i64")]
+    pub thread_id: u64,
+    /// 同期したメッセージの ID
+    #[sqlx(try_from = "i64")]
+    pub message_id: u64,
+    /// 操作
+    #[sqlx(try_from = "String")]
+    pub action: SyncAction,
+    /// 結果
+    #[sqlx(try_from = "String")]
+    pub status: SyncHistoryStatus,
+    /// エラーや警告の内容
+    pub detail: Option<String>,
+    /// 記録した日時
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl fmt::Display for SyncAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SyncAction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match SyncAction::ALL
+            .into_iter()
+            .find(|action| action.as_str() == s)
+        {
+            Some(action) => Ok(action),
+            None => bail!("Unknown sync action: {}", s),
+        }
+    }
+}
+
+impl TryFrom<String> for SyncAction {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for SyncHistoryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SyncHistoryStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match SyncHistoryStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+        {
+            Some(status) => Ok(status),
+            None => bail!("Unknown sync status: {}", s),
+        }
+    }
+}
+
+impl TryFrom<String> for SyncHistoryStatus {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for action in SyncAction::ALL {
+            assert_eq!(action.as_str().parse::<SyncAction>().unwrap(), action);
+        }
+        for status in SyncHistoryStatus::ALL {
+            assert_eq!(
+                status.as_str().parse::<SyncHistoryStatus>().unwrap(),
+                status
+            );
+        }
+        assert!("sync".parse::<SyncAction>().is_err());
+    }
+}
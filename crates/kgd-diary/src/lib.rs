@@ -0,0 +1,104 @@
+//! チャットのメッセージを Notion の日報ページに同期するエンジン。
+//!
+//! 同期元のチャットサービスには依存せず、次のインターフェースを通じて連携する。
+//!
+//! - [`SourceMessage`] - 同期するメッセージ（本文・添付ファイル・投稿者など）
+//! - [`MessageEvent`] - メッセージの投稿・編集・削除のイベント
+//! - [`MessageSource`] - メンションの名前解決など、同期元に固有の処理
+//! - [`DiarySink`] - ブロックの書き込み先（[`NotionClient`] が実装する）
+//!
+//! [`MessageSyncer`] がこれらを使ってイベントを処理し、メッセージをブロックに変換して書き込み、
+//! メッセージとブロックの対応を [`DiaryStorage`]（PostgreSQL の [`PostgresStore`] かメモリ上の [`MemoryStore`]）に記録する。
+//! URL のリンク化・ブロック化（[`compile_url_rules`]）、OGP の取得（[`OgpFetcher`]）、X の投稿の取得（[`TweetFetcher`]）は単体でも使える。
+//! URL から生成したブロックは、登録した [`UrlHandler`]（OGP・GitHub・YouTube・X など）でリッチ化する。
+
+mod block;
+mod cache;
+mod code;
+pub mod config;
+mod convert;
+mod emoji;
+mod equation;
+mod ffmpeg;
+mod github;
+mod history;
+mod mention;
+mod mermaid;
+mod message;
+mod notion;
+mod ogp;
+mod quote;
+mod redaction;
+mod report;
+mod retry;
+mod sink;
+mod store;
+mod summary;
+mod sync;
+mod table;
+mod template;
+mod time_tracking;
+mod tweet;
+mod url_handler;
+mod url_parser;
+mod workspace;
+mod youtube;
+
+pub use block::BlockKind;
+pub use convert::compile_image_rules;
+pub use github::GitHubHandler;
+pub use history::{SyncAction, SyncHistoryRecord, SyncHistoryStatus};
+pub use mention::Mention;
+pub use message::{MessageEvent, MessageSource, SourceAttachment, SourceMessage};
+pub use notion::{NotionClient, NotionError, UploadData, parse_page_id};
+pub use ogp::{OgpFetcher, OgpMetadata};
+pub use redaction::compile_redaction_rules;
+pub use report::{ReportOutcome, ReportPeriod, due_report_periods, publish_report};
+pub use retry::{RetryError, RetryPolicy};
+pub use sink::DiarySink;
+pub use store::{
+    DiaryEntry, DiaryEntryStats, DiaryStorage, DiaryStore, DiarySyncStatus, EmojiCount,
+    MemoryStore, MessageBlock, PostgresStore, ThreadBlockStats,
+};
+pub use summary::{PageSummary, is_summary_block, summary_anchor, summary_placeholder_block};
+pub use sync::{
+    BulkDeleteResult, EventOutcome, MessageSyncer, ReconcileResult, SyncFeatures, SyncItem,
+    SyncOptions, SyncResult,
+};
+pub use template::{PageTemplate, compile_page_template, render_title, validate_page_title_format};
+pub use time_tracking::{TimeCommand, TimeEntry, format_duration, time_totals};
+pub use tweet::{Tweet, TweetFetcher, parse_tweet_id};
+pub use url_handler::{UrlHandler, UrlHandlers};
+pub use url_parser::{
+    CompiledUrlRules, UrlParseResult, apply_ogp_to_block, apply_ogp_to_bookmark,
+    apply_tweet_to_quote, build_rich_text_and_url_blocks, compile_url_rules,
+};
+pub use workspace::{TempWorkspace, WorkspaceFile};
+pub use youtube::YouTubeHandler;
+
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+
+/// 指定されたタイムゾーンでの現在の日付の開始時刻（00:00:00）を UTC で取得する。
+pub fn today_in_timezone(tz: &Tz) -> DateTime<Utc> {
+    Utc::now()
+        .with_timezone(tz)
+        .with_time(NaiveTime::MIN)
+        .unwrap()
+        .to_utc()
+}
+
+/// 指定されたタイムゾーンでの日付の開始時刻（00:00:00）を UTC で取得する。
+pub fn start_of_day_in_timezone(date: NaiveDate, tz: &Tz) -> Result<DateTime<Utc>> {
+    date.and_time(NaiveTime::MIN)
+        .and_local_timezone(*tz)
+        .earliest()
+        .map(|time| time.to_utc())
+        .with_context(|| format!("Start of day does not exist in {}: {}", tz, date))
+}
+
+/// 指定されたタイムゾーンでの日付を "YYYY-MM-DD" 形式の文字列として取得する。
+pub fn format_date_in_timezone(date: DateTime<Utc>, tz: &Tz) -> String {
+    date.with_timezone(tz).format("%Y-%m-%d").to_string()
+}
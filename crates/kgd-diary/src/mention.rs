@@ -0,0 +1,93 @@
+//! メッセージ中の Discord メンション（`<@id>` / `<@&id>` / `<#id>`）を扱う。
+
+use std::{borrow::Cow, collections::HashMap};
+
+use regex::{Captures, Regex};
+
+/// メッセージ中のメンション。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mention {
+    /// ユーザーメンション（`<@id>` / `<@!id>`）
+    User(u64),
+    /// ロールメンション（`<@&id>`）
+    Role(u64),
+    /// チャンネルメンション（`<#id>`）
+    Channel(u64),
+}
+
+impl Mention {
+    /// 解決した名前をメンションの表記（`@name` / `#name`）に整形する。
+    pub fn format(self, name: &str) -> String {
+        match self {
+            Mention::User(_) | Mention::Role(_) => format!("@{}", name),
+            Mention::Channel(_) => format!("#{}", name),
+        }
+    }
+}
+
+/// テキストに含まれるメンションを出現順に重複なく返す。
+pub fn parse_mentions(text: &str) -> Vec<Mention> {
+    let mut mentions = Vec::new();
+    for caps in mention_regex().captures_iter(text) {
+        let Some(mention) = mention_from_captures(&caps) else {
+            continue;
+        };
+        if !mentions.contains(&mention) {
+            mentions.push(mention);
+        }
+    }
+    mentions
+}
+
+/// 名前を解決できたメンションを `@name` / `#name` 形式に置換する。
+///
+/// 解決できなかったメンションは元の表記のまま残す。
+pub fn replace_mentions<'t>(text: &'t str, names: &HashMap<Mention, String>) -> Cow<'t, str> {
+    mention_regex().replace_all(text, |caps: &Captures| {
+        mention_from_captures(caps)
+            .and_then(|mention| names.get(&mention).map(|name| mention.format(name)))
+            .unwrap_or_else(|| caps[0].to_string())
+    })
+}
+
+fn mention_from_captures(caps: &Captures) -> Option<Mention> {
+    // Discord の ID は 0 にならない（serenity の ID 型も 0 を受け付けない）
+    let id = caps[2].parse::<u64>().ok().filter(|id| *id != 0)?;
+    match &caps[1] {
+        "@" | "@!" => Some(Mention::User(id)),
+        "@&" => Some(Mention::Role(id)),
+        "#" => Some(Mention::Channel(id)),
+        _ => None,
+    }
+}
+
+/// メンション（`<@id>` / `<@!id>` / `<@&id>` / `<#id>`）にマッチする正規表現。
+fn mention_regex() -> Regex {
+    Regex::new(r"<(@!?|@&|#)(\d+)>").unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mentions() {
+        assert_eq!(
+            parse_mentions("<@1> <@!1> <@&2> <#3> <:emoji:4> <@abc>"),
+            vec![Mention::User(1), Mention::Role(2), Mention::Channel(3)]
+        );
+    }
+
+    #[test]
+    fn test_replace_mentions() {
+        let names = HashMap::from([
+            (Mention::User(1), "alice".to_string()),
+            (Mention::Role(2), "admins".to_string()),
+            (Mention::Channel(3), "general".to_string()),
+        ]);
+        assert_eq!(
+            replace_mentions("hi <@1> <@!1>, <@&2> see <#3> <@9>", &names),
+            "hi @alice @alice, @admins see #general <@9>"
+        );
+    }
+}
@@ -0,0 +1,66 @@
+//! 外部の `mmdc`（mermaid-cli）コマンドを使った mermaid の図の描画を提供する。
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result, bail};
+use tokio::process::Command;
+
+/// `mmdc` コマンドの実行設定。
+#[derive(Debug, Clone)]
+pub struct Mermaid {
+    /// `mmdc` 実行ファイルのパス
+    path: PathBuf,
+    /// 1 回の実行のタイムアウト
+    timeout: Duration,
+}
+
+impl Mermaid {
+    /// 新しい Mermaid を作成する。
+    pub fn new(path: impl Into<PathBuf>, timeout: Duration) -> Self {
+        Self {
+            path: path.into(),
+            timeout,
+        }
+    }
+
+    /// `input` の mermaid の定義を描画し、PNG として `output` に書き出す。
+    pub async fn render_png(&self, input: &Path, output: &Path) -> Result<()> {
+        let child = Command::new(&self.path)
+            .args([
+                OsStr::new("--quiet"),
+                OsStr::new("-i"),
+                input.as_os_str(),
+                OsStr::new("-o"),
+                output.as_os_str(),
+                // Notion のダークモードでも読めるよう背景を白にする
+                OsStr::new("-b"),
+                OsStr::new("white"),
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to run mmdc: {}", self.path.display()))?;
+
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .with_context(|| format!("mmdc timed out after {:?}", self.timeout))?
+            .context("Failed to wait for mmdc")?;
+
+        if !output.status.success() {
+            bail!(
+                "mmdc exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(())
+    }
+}
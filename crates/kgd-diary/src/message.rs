@@ -0,0 +1,140 @@
+//! 同期元のチャットサービスに依存しないメッセージの表現。
+
+use std::{collections::HashMap, future::Future};
+
+use chrono::{DateTime, Utc};
+
+use crate::mention::Mention;
+
+/// 同期するメッセージ。
+///
+/// 同期元のメッセージから、同期に必要な情報だけを取り出したもの。
+/// 本文は Discord 形式のマークアップ（カスタム絵文字 `<:name:id>` やメンション `<@id>`）を含んでよい。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMessage {
+    /// メッセージ ID（投稿順に増加する）
+    pub id: u64,
+    /// メッセージが投稿されたスレッドの ID
+    pub thread_id: u64,
+    /// 投稿者の ID
+    pub author_id: u64,
+    /// 投稿日時
+    pub posted_at: DateTime<Utc>,
+    /// 本文
+    pub content: String,
+    /// 添付ファイル
+    pub attachments: Vec<SourceAttachment>,
+    /// 同期元で解決済みのメンションの表示名（含まれないメンションは [`MessageSource`] で解決する）
+    pub mention_names: HashMap<Mention, String>,
+}
+
+/// メッセージの添付ファイル。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceAttachment {
+    /// ファイル名
+    pub filename: String,
+    /// ダウンロード元の URL
+    pub url: String,
+    /// ファイルサイズ（バイト）
+    pub size: u64,
+    /// 代替テキスト
+    pub description: Option<String>,
+}
+
+/// 同期元で発生したメッセージのイベント。
+///
+/// 同期元のイベントをこの形式に変換して [`MessageSyncer::handle_event`](crate::MessageSyncer::handle_event) に渡す。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageEvent {
+    /// メッセージが投稿された
+    Created(SourceMessage),
+    /// メッセージが編集された（編集後のメッセージ）
+    Updated(SourceMessage),
+    /// メッセージが削除された
+    Deleted {
+        /// メッセージが投稿されたスレッドの ID
+        thread_id: u64,
+        /// 削除されたメッセージ ID
+        message_id: u64,
+    },
+    /// 複数のメッセージがまとめて削除された
+    BulkDeleted {
+        /// メッセージが投稿されたスレッドの ID
+        thread_id: u64,
+        /// 削除されたメッセージ ID のリスト
+        message_ids: Vec<u64>,
+    },
+}
+
+impl MessageEvent {
+    /// イベントが発生したスレッドの ID を返す。
+    pub fn thread_id(&self) -> u64 {
+        match self {
+            MessageEvent::Created(message) | MessageEvent::Updated(message) => message.thread_id,
+            MessageEvent::Deleted { thread_id, .. }
+            | MessageEvent::BulkDeleted { thread_id, .. } => *thread_id,
+        }
+    }
+}
+
+/// 同期元のチャットサービスに固有の処理。
+///
+/// 同期元ごとに実装し、[`MessageSyncer`](crate::MessageSyncer) に渡す。
+pub trait MessageSource: Sync {
+    /// メッセージ ID から投稿日時を求める。
+    ///
+    /// 時刻の見出しを挟むかどうかの判定で、前回同期したメッセージの投稿日時を知るために使う。
+    fn posted_at(&self, message_id: u64) -> Option<DateTime<Utc>>;
+
+    /// 本文中のメンションを表示名に解決する。
+    ///
+    /// 解決できなかったメンションは結果に含めない（元の表記のまま同期される）。
+    fn resolve_mentions(
+        &self,
+        message: &SourceMessage,
+        mentions: &[Mention],
+    ) -> impl Future<Output = HashMap<Mention, String>> + Send;
+
+    /// 添付ファイルのダウンロードに付ける `Authorization` ヘッダーの値を返す。
+    ///
+    /// 認証なしでダウンロードできる同期元は None を返す（デフォルト）。
+    fn attachment_authorization(&self, _attachment: &SourceAttachment) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_event_thread_id() {
+        let message = SourceMessage {
+            id: 2,
+            thread_id: 1,
+            author_id: 3,
+            posted_at: DateTime::UNIX_EPOCH,
+            content: "hello".to_string(),
+            attachments: Vec::new(),
+            mention_names: HashMap::new(),
+        };
+        assert_eq!(MessageEvent::Created(message.clone()).thread_id(), 1);
+        assert_eq!(MessageEvent::Updated(message).thread_id(), 1);
+        assert_eq!(
+            MessageEvent::Deleted {
+                thread_id: 4,
+                message_id: 5
+            }
+            .thread_id(),
+            4
+        );
+        assert_eq!(
+            MessageEvent::BulkDeleted {
+                thread_id: 6,
+                message_ids: vec![7, 8]
+            }
+            .thread_id(),
+            6
+        );
+    }
+}
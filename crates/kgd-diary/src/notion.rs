@@ -0,0 +1,1127 @@
+//! Notion API との連携機能を提供する。
+
+use std::{
+    collections::BTreeMap,
+    io::{Seek as _, SeekFrom},
+    sync::OnceLock,
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result, bail};
+use chrono::NaiveDate;
+use notion_client::{
+    NotionClientError,
+    endpoints::Client,
+    objects::{
+        page::{DateOrDateTime, DatePropertyValue, PageProperty, SelectPropertyValue},
+        parent::Parent,
+        rich_text::{RichText, Text},
+        user::User,
+    },
+};
+use reqwest::multipart;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use thiserror::Error;
+use tokio::io::AsyncReadExt as _;
+use tokio_util::io::ReaderStream;
+
+use crate::config::{NotionApiVersion, NotionPropertyConfig, NotionPropertyValue};
+
+use super::{
+    cache::TtlCache,
+    retry::{RetryError, RetryPolicy, is_transient_status, parse_retry_after},
+    workspace::WorkspaceFile,
+};
+
+/// single_part モードでアップロードできる最大サイズ（20 MiB）。
+const SINGLE_PART_MAX_SIZE: u64 = 20 * 1024 * 1024;
+
+/// multi_part モードで 1 パートあたりに送信するサイズ（Notion API は 5〜20 MiB を要求する）。
+const MULTI_PART_CHUNK_SIZE: u64 = 10 * 1024 * 1024;
+
+/// 1 回のリクエストで追加できるブロックの最大数（Notion API の制限）。
+const MAX_APPEND_CHILDREN: usize = 100;
+
+/// Notion API クライアントのラッパー。
+pub struct NotionClient {
+    /// notion-client のクライアント（初回の利用時に作成する）
+    client: OnceLock<Client>,
+    /// HTTP クライアント（ファイルアップロード用、初回の利用時に作成する）
+    http_client: OnceLock<reqwest::Client>,
+    /// Notion API トークン
+    token: String,
+    /// 日報を保存するデータベース ID
+    database_id: String,
+    /// タイトルプロパティ名
+    title_property: String,
+    /// ページ作成時に設定するプロパティ
+    properties: Vec<NotionPropertyConfig>,
+    /// タイトルから検索して見つかったページ（ID と URL）のキャッシュ
+    page_cache: TtlCache<String, (String, String)>,
+    /// rate limit などの一時的なエラーに対するリトライ方針
+    retry_policy: RetryPolicy,
+    /// 使用する Notion API のバージョン
+    api_version: NotionApiVersion,
+    /// データベースのデータソース ID（データソースの API を使う場合に初回の利用時に取得する）
+    data_source_id: OnceLock<String>,
+}
+
+/// アップロードするファイルの内容。
+pub enum UploadData {
+    /// メモリ上のバイト列
+    Bytes(Vec<u8>),
+    /// 作業ディレクトリ内の一時ファイル（値の破棄時に削除される）
+    File {
+        /// 一時ファイル
+        file: WorkspaceFile,
+        /// ファイルサイズ
+        len: u64,
+    },
+}
+
+/// Notion API の呼び出しで発生したエラー。
+///
+/// `anyhow::Error` に包まれて返るため、呼び出し側は `downcast_ref` で取り出して原因やリトライ可否を判定できる。
+#[derive(Debug, Error)]
+pub enum NotionError {
+    /// rate limit に達した（HTTP 429）
+    #[error("Notion API rate limited")]
+    RateLimited {
+        /// サーバーが指定した再試行までの待機時間
+        retry_after: Option<Duration>,
+    },
+    /// トークンが無効、または権限が無い（HTTP 401）
+    #[error("Notion API unauthorized: {0}")]
+    Unauthorized(String),
+    /// リクエストの内容が不正（HTTP 400）。レスポンスのエラーメッセージを持つ
+    #[error("Notion API validation error: {0}")]
+    ValidationError(String),
+    /// その他のエラー。`status` が None の場合は通信自体に失敗している
+    #[error("Notion API request failed{}: {message}", .status.map(|s| format!(" ({s})")).unwrap_or_default())]
+    Http {
+        /// HTTP ステータスコード
+        status: Option<u16>,
+        /// エラーの内容
+        message: String,
+    },
+}
+
+/// ファイルアップロードのレスポンス。
+#[derive(Debug, Deserialize)]
+struct FileUploadResponse {
+    id: String,
+    status: String,
+}
+
+/// ファイルアップロードのリクエストボディ。
+#[derive(Debug, Serialize)]
+struct CreateFileUploadRequest {
+    mode: String,
+    filename: String,
+    content_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    number_of_parts: Option<usize>,
+}
+
+impl NotionClient {
+    /// 新しい NotionClient を作成する。
+    ///
+    /// HTTP クライアントは Notion API を初めて呼び出すときに作成する（日報を使わない間は作らない）。
+    /// `api_version` でデータソースの API を選んだ場合は、データベースの最初のデータソースを使う。
+    pub fn new(
+        token: impl Into<String>,
+        database_id: impl Into<String>,
+        title_property: impl Into<String>,
+        properties: Vec<NotionPropertyConfig>,
+        cache_ttl: Duration,
+        retry_policy: RetryPolicy,
+        api_version: NotionApiVersion,
+    ) -> Result<Self> {
+        Ok(Self {
+            client: OnceLock::new(),
+            http_client: OnceLock::new(),
+            token: token.into(),
+            database_id: database_id.into(),
+            title_property: title_property.into(),
+            properties,
+            page_cache: TtlCache::new(cache_ttl),
+            retry_policy,
+            api_version,
+            data_source_id: OnceLock::new(),
+        })
+    }
+
+    /// notion-client のクライアントを返す（初回の呼び出し時に作成する）。
+    fn client(&self) -> Result<&Client> {
+        if let Some(client) = self.client.get() {
+            return Ok(client);
+        }
+        let client =
+            Client::new(self.token.clone(), None).context("Failed to create Notion client")?;
+        Ok(self.client.get_or_init(|| client))
+    }
+
+    /// HTTP クライアントを返す（初回の呼び出し時に作成する）。
+    fn http_client(&self) -> &reqwest::Client {
+        self.http_client.get_or_init(reqwest::Client::new)
+    }
+
+    /// 指定したタイトルの日報ページを検索し、存在すればページ ID と URL を返す。
+    ///
+    /// 見つかったページはキャッシュされ、有効期限内は Notion API を呼び出さない。
+    /// 手動や他のインスタンスで作成されたページを見落として重複して作成しないよう、
+    /// 見つからなかった結果はキャッシュしない。
+    pub async fn find_diary_page_by_title(&self, title: &str) -> Result<Option<(String, String)>> {
+        let key = title.to_string();
+        if let Some(page) = self.page_cache.get(&key) {
+            tracing::debug!(title = %title, "Notion page lookup served from cache");
+            return Ok(Some(page));
+        }
+
+        let page = self.query_diary_page_by_title(title).await?;
+        if let Some(page) = &page {
+            self.page_cache.insert(key, page.clone());
+        }
+        Ok(page)
+    }
+
+    /// 日報ページを作成し、ページ ID と URL を返す。
+    ///
+    /// 日付型のプロパティには `date` を設定する。
+    /// 作成結果でタイトル検索のキャッシュを更新する。
+    pub async fn create_diary_page(
+        &self,
+        title: &str,
+        date: NaiveDate,
+    ) -> Result<(String, String)> {
+        let key = title.to_string();
+        match self.create_diary_page_inner(title, date).await {
+            Ok(page) => {
+                self.page_cache.insert(key, page.clone());
+                Ok(page)
+            }
+            Err(e) => {
+                // 作成の成否が不明なため、次回の検索では必ず Notion に問い合わせる
+                self.page_cache.invalidate(&key);
+                Err(e)
+            }
+        }
+    }
+
+    /// ファイルをNotionにアップロードし、ファイルアップロードIDを返す。
+    ///
+    /// 20 MiB を超えるファイルは multi_part モードでチャンクに分割して送信する。
+    pub async fn upload_file(
+        &self,
+        filename: &str,
+        content_type: &str,
+        data: impl Into<UploadData>,
+    ) -> Result<String> {
+        let data = data.into();
+        let parts = split_into_parts(data.size());
+        let multi_part = parts.len() > 1;
+
+        // 1. Create file upload
+        let create_request = CreateFileUploadRequest {
+            mode: if multi_part {
+                "multi_part"
+            } else {
+                "single_part"
+            }
+            .to_string(),
+            filename: filename.to_string(),
+            content_type: content_type.to_string(),
+            number_of_parts: multi_part.then_some(parts.len()),
+        };
+
+        let file_upload: FileUploadResponse = self
+            .send_json("create file upload", || {
+                Ok(self
+                    .http_client()
+                    .post("https://api.notion.com/v1/file_uploads")
+                    .json(&create_request))
+            })
+            .await?;
+
+        let file_upload_id = file_upload.id;
+
+        // 2. Send file content
+        let mut upload_result = None;
+        for (index, &(offset, len)) in parts.iter().enumerate() {
+            let part_number = index + 1;
+            if multi_part {
+                tracing::debug!(
+                    filename,
+                    part_number,
+                    total_parts = parts.len(),
+                    "Sending file upload part"
+                );
+            }
+
+            // multipart のボディは再利用できないため、リトライのたびに組み立て直す
+            let result: FileUploadResponse = self
+                .send_json("send file upload", || {
+                    let part = data
+                        .to_part(offset, len)?
+                        .file_name(filename.to_string())
+                        .mime_str(content_type)
+                        .context("Invalid content type")?;
+                    let mut form = multipart::Form::new().part("file", part);
+                    if multi_part {
+                        form = form.text("part_number", part_number.to_string());
+                    }
+
+                    Ok(self
+                        .http_client()
+                        .post(format!(
+                            "https://api.notion.com/v1/file_uploads/{}/send",
+                            file_upload_id
+                        ))
+                        .multipart(form))
+                })
+                .await?;
+            upload_result = Some(result);
+        }
+
+        // 3. Complete multi-part upload
+        if multi_part {
+            let result: FileUploadResponse = self
+                .send_json("complete file upload", || {
+                    Ok(self.http_client().post(format!(
+                        "https://api.notion.com/v1/file_uploads/{}/complete",
+                        file_upload_id
+                    )))
+                })
+                .await?;
+            upload_result = Some(result);
+        }
+
+        let status = upload_result
+            .map(|result| result.status)
+            .unwrap_or_default();
+        if status != "uploaded" {
+            bail!("File upload not completed: status = {}", status);
+        }
+
+        Ok(file_upload_id)
+    }
+
+    /// 複数のブロックを一括でページに追加し、作成されたブロック ID のリストを返す。
+    pub async fn append_blocks(
+        &self,
+        page_id: &str,
+        children: Vec<serde_json::Value>,
+    ) -> Result<Vec<String>> {
+        self.append_blocks_inner(page_id, None, children).await
+    }
+
+    /// 複数のブロックをページ内の指定したブロックの直後に挿入し、作成されたブロック ID のリストを返す。
+    pub async fn insert_blocks_after(
+        &self,
+        page_id: &str,
+        after_block_id: &str,
+        children: Vec<serde_json::Value>,
+    ) -> Result<Vec<String>> {
+        self.append_blocks_inner(page_id, Some(after_block_id), children)
+            .await
+    }
+
+    /// テキストブロックを更新する。
+    pub async fn update_text_block(
+        &self,
+        block_id: &str,
+        rich_text: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        let body = serde_json::json!({
+            "paragraph": {
+                "rich_text": rich_text
+            }
+        });
+
+        self.send("update block", || {
+            Ok(self
+                .http_client()
+                .patch(format!("https://api.notion.com/v1/blocks/{}", block_id))
+                .json(&body))
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// callout ブロックのテキストを更新する。
+    pub async fn update_callout_block(
+        &self,
+        block_id: &str,
+        rich_text: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        let body = serde_json::json!({
+            "callout": {
+                "rich_text": rich_text
+            }
+        });
+
+        self.send("update block", || {
+            Ok(self
+                .http_client()
+                .patch(format!("https://api.notion.com/v1/blocks/{}", block_id))
+                .json(&body))
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// ページ直下のブロックをページ上の順にすべて取得する。
+    ///
+    /// Notion API は 1 リクエストで 100 件までしか返さないため、続きがある場合はカーソルを辿って取得する。
+    pub async fn list_blocks(&self, page_id: &str) -> Result<Vec<serde_json::Value>> {
+        let mut blocks = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let children: BlockChildrenResponse = self
+                .send_json("list block children", || {
+                    let mut query = vec![("page_size", "100")];
+                    if let Some(cursor) = &cursor {
+                        query.push(("start_cursor", cursor.as_str()));
+                    }
+                    Ok(self
+                        .http_client()
+                        .get(format!(
+                            "https://api.notion.com/v1/blocks/{}/children",
+                            page_id
+                        ))
+                        .query(&query))
+                })
+                .await?;
+            blocks.extend(children.results);
+            match children.next_cursor {
+                Some(next_cursor) if children.has_more => cursor = Some(next_cursor),
+                _ => break,
+            }
+        }
+
+        Ok(blocks)
+    }
+
+    /// ブロックを削除する。
+    pub async fn delete_block(&self, block_id: &str) -> Result<()> {
+        self.send("delete block", || {
+            Ok(self
+                .http_client()
+                .delete(format!("https://api.notion.com/v1/blocks/{}", block_id)))
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// 日報ページをアーカイブする。
+    ///
+    /// タイトル検索のキャッシュも無効化する。
+    pub async fn archive_diary_page(&self, title: &str, page_id: &str) -> Result<()> {
+        self.page_cache.invalidate(&title.to_string());
+
+        let body = serde_json::json!({ "archived": true });
+
+        self.send("archive page", || {
+            Ok(self
+                .http_client()
+                .patch(format!("https://api.notion.com/v1/pages/{}", page_id))
+                .json(&body))
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// ページを取得し、ページ ID と URL を返す。
+    ///
+    /// アーカイブ済みのページはエラーとして返す。
+    pub async fn get_page(&self, page_id: &str) -> Result<(String, String)> {
+        let client = self.client()?;
+        let page = self
+            .retry_policy
+            .run("retrieve page", || async move {
+                client
+                    .pages
+                    .retrieve_a_page(page_id, None)
+                    .await
+                    .map_err(classify_notion_client_error)
+            })
+            .await
+            .context("Failed to retrieve Notion page")?;
+
+        if page.archived {
+            bail!("Notion page is archived: {}", page_id);
+        }
+
+        Ok((page.id, page.url))
+    }
+
+    /// ブロックを追加する。`after_block_id` が None の場合はページの末尾に追加する。
+    ///
+    /// Notion API は 1 リクエストで追加できるブロックを 100 件までに制限しているため、
+    /// それを超える場合は分割し、前回追加した最後のブロックの直後に続けて追加する。
+    async fn append_blocks_inner(
+        &self,
+        page_id: &str,
+        after_block_id: Option<&str>,
+        children: Vec<serde_json::Value>,
+    ) -> Result<Vec<String>> {
+        let mut block_ids = Vec::with_capacity(children.len());
+        let mut after_block_id = after_block_id.map(str::to_string);
+        for chunk in children.chunks(MAX_APPEND_CHILDREN) {
+            let mut body = serde_json::json!({ "children": chunk });
+            if let Some(after_block_id) = &after_block_id {
+                body["after"] = serde_json::json!(after_block_id);
+            }
+
+            let result: AppendBlockChildrenResponse = self
+                .send_json("append blocks", || {
+                    Ok(self
+                        .http_client()
+                        .patch(format!(
+                            "https://api.notion.com/v1/blocks/{}/children",
+                            page_id
+                        ))
+                        .json(&body))
+                })
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to append blocks {}..{} of {}",
+                        block_ids.len(),
+                        block_ids.len() + chunk.len(),
+                        children.len()
+                    )
+                })?;
+
+            let ids: Vec<String> = result.results.into_iter().map(|b| b.id).collect();
+            // 先頭以外のチャンクは、ページの末尾ではなく前のチャンクの直後に追加する
+            if let Some(last) = ids.last() {
+                after_block_id = Some(last.clone());
+            }
+            block_ids.extend(ids);
+        }
+
+        Ok(block_ids)
+    }
+
+    /// タイトルで日報ページをデータベースから検索する（キャッシュを経由しない）。
+    async fn query_diary_page_by_title(&self, title: &str) -> Result<Option<(String, String)>> {
+        let body = serde_json::json!({
+            "filter": {
+                "property": self.title_property,
+                "title": {
+                    "equals": title
+                }
+            },
+            "page_size": 1
+        });
+
+        let url = match self.api_version {
+            NotionApiVersion::Legacy => format!(
+                "https://api.notion.com/v1/databases/{}/query",
+                self.database_id
+            ),
+            NotionApiVersion::DataSources => format!(
+                "https://api.notion.com/v1/data_sources/{}/query",
+                self.data_source_id().await?
+            ),
+        };
+        let result: DatabaseQueryResponse = self
+            .send_json("query database", || {
+                Ok(self.http_client().post(&url).json(&body))
+            })
+            .await?;
+
+        Ok(result
+            .results
+            .first()
+            .map(|page| (page.id.clone(), page.url.clone())))
+    }
+
+    /// 日報ページを作成する（キャッシュを経由しない）。
+    async fn create_diary_page_inner(
+        &self,
+        title: &str,
+        date: NaiveDate,
+    ) -> Result<(String, String)> {
+        let mut properties = BTreeMap::new();
+
+        // タイトルプロパティを設定
+        properties.insert(
+            self.title_property.clone(),
+            PageProperty::Title {
+                id: None,
+                title: vec![RichText::Text {
+                    text: Text {
+                        content: title.to_string(),
+                        link: None,
+                    },
+                    annotations: None,
+                    plain_text: None,
+                    href: None,
+                }],
+            },
+        );
+
+        // 設定されたプロパティを設定
+        for property in &self.properties {
+            properties.insert(
+                property.property.clone(),
+                page_property(&property.value, date)?,
+            );
+        }
+
+        // notion-client はデータソースを親にしたページの作成に対応していないため、直接呼び出す
+        if self.api_version == NotionApiVersion::DataSources {
+            let body = data_source_page_request(self.data_source_id().await?, &properties)?;
+            let page: PageInfo = self
+                .send_json("create page", || {
+                    Ok(self
+                        .http_client()
+                        .post("https://api.notion.com/v1/pages")
+                        .json(&body))
+                })
+                .await
+                .context("Failed to create Notion page")?;
+            return Ok((page.id, page.url));
+        }
+
+        let request = notion_client::endpoints::pages::create::request::CreateAPageRequest {
+            parent: Parent::DatabaseId {
+                database_id: self.database_id.clone(),
+            },
+            properties,
+            ..Default::default()
+        };
+
+        let client = self.client()?;
+        let page = self
+            .retry_policy
+            .run("create page", || {
+                let request = request.clone();
+                async move {
+                    client
+                        .pages
+                        .create_a_page(request)
+                        .await
+                        .map_err(classify_notion_client_error)
+                }
+            })
+            .await
+            .context("Failed to create Notion page")?;
+
+        Ok((page.id, page.url))
+    }
+
+    /// データベースのデータソース ID を返す。初回はデータベースを取得して最初のデータソースを使う。
+    async fn data_source_id(&self) -> Result<&str> {
+        if let Some(id) = self.data_source_id.get() {
+            return Ok(id);
+        }
+
+        let database: DatabaseResponse = self
+            .send_json("retrieve database", || {
+                Ok(self.http_client().get(format!(
+                    "https://api.notion.com/v1/databases/{}",
+                    self.database_id
+                )))
+            })
+            .await?;
+
+        if database.data_sources.len() > 1 {
+            tracing::warn!(
+                database_id = %self.database_id,
+                data_sources = database.data_sources.len(),
+                "Notion database has multiple data sources, using the first one"
+            );
+        }
+        let data_source =
+            database.data_sources.into_iter().next().with_context(|| {
+                format!("Notion database {} has no data source", self.database_id)
+            })?;
+        tracing::info!(
+            database_id = %self.database_id,
+            data_source_id = %data_source.id,
+            "Resolved Notion data source"
+        );
+
+        Ok(self.data_source_id.get_or_init(|| data_source.id))
+    }
+
+    /// Notion API にリクエストを送信し、成功したレスポンスを返す。
+    ///
+    /// 429（rate limit）や 5xx などの一時的なエラーは `Retry-After` ヘッダーに従って自動リトライする。
+    /// リクエストはリトライのたびに `build` で組み立て直す。
+    async fn send(
+        &self,
+        operation: &str,
+        build: impl Fn() -> Result<reqwest::RequestBuilder>,
+    ) -> Result<reqwest::Response> {
+        let build = &build;
+        self.retry_policy
+            .run(operation, || async move {
+                let request = build().map_err(RetryError::permanent)?;
+                let response = request
+                    .header("Authorization", format!("Bearer {}", self.token))
+                    .header("Notion-Version", self.api_version.as_str())
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        RetryError::from(NotionError::Http {
+                            status: None,
+                            message: e.to_string(),
+                        })
+                        .context(format!("Failed to {}", operation))
+                    })?;
+
+                let status = response.status();
+                if status.is_success() {
+                    return Ok(response);
+                }
+
+                let retry_after = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(parse_retry_after);
+                let body = response.text().await.unwrap_or_default();
+                Err(RetryError::from(NotionError::from_response(
+                    status.as_u16(),
+                    retry_after,
+                    &body,
+                ))
+                .context(format!("Failed to {}", operation)))
+            })
+            .await
+    }
+
+    /// Notion API にリクエストを送信し、レスポンスの JSON をデシリアライズして返す。
+    async fn send_json<T: DeserializeOwned>(
+        &self,
+        operation: &str,
+        build: impl Fn() -> Result<reqwest::RequestBuilder>,
+    ) -> Result<T> {
+        self.send(operation, build)
+            .await?
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse {} response", operation))
+    }
+}
+
+impl NotionError {
+    /// エラーレスポンスのステータスコードとボディからエラーを作成する。
+    pub fn from_response(status: u16, retry_after: Option<Duration>, body: &str) -> Self {
+        // エラーレスポンスは `{"object": "error", "code": ..., "message": ...}` の形式
+        let message = serde_json::from_str::<ErrorResponse>(body)
+            .map(|response| response.message)
+            .unwrap_or_else(|_| body.to_string());
+        Self::from_status(status, retry_after, message)
+    }
+
+    /// ステータスコードとエラーメッセージからエラーを作成する。
+    fn from_status(status: u16, retry_after: Option<Duration>, message: String) -> Self {
+        match status {
+            429 => Self::RateLimited { retry_after },
+            401 => Self::Unauthorized(message),
+            400 => Self::ValidationError(message),
+            _ => Self::Http {
+                status: Some(status),
+                message,
+            },
+        }
+    }
+
+    /// リトライで回復する可能性があるかどうかを返す。
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimited { .. } => true,
+            Self::Unauthorized(_) | Self::ValidationError(_) => false,
+            Self::Http { status, .. } => status.is_none_or(is_transient_status),
+        }
+    }
+
+    /// サーバーが指定した再試行までの待機時間を返す。
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl From<NotionError> for RetryError {
+    fn from(error: NotionError) -> Self {
+        if error.is_retryable() {
+            Self::Transient {
+                retry_after: error.retry_after(),
+                error: error.into(),
+            }
+        } else {
+            Self::Permanent(error.into())
+        }
+    }
+}
+
+impl UploadData {
+    /// ファイルサイズを返す。
+    pub fn size(&self) -> u64 {
+        match self {
+            Self::Bytes(bytes) => bytes.len() as u64,
+            Self::File { len, .. } => *len,
+        }
+    }
+
+    /// `offset` から `len` バイトの範囲で multipart のパートを作成する。
+    ///
+    /// 一時ファイルはメモリに読み込まずストリームで送信する。
+    fn to_part(&self, offset: u64, len: u64) -> Result<multipart::Part> {
+        match self {
+            Self::Bytes(bytes) => {
+                let range = offset as usize..(offset + len) as usize;
+                Ok(multipart::Part::bytes(bytes[range].to_vec()))
+            }
+            Self::File { file, .. } => {
+                let mut file =
+                    std::fs::File::open(file.path()).context("Failed to open temporary file")?;
+                file.seek(SeekFrom::Start(offset))
+                    .context("Failed to seek temporary file")?;
+                let reader = tokio::fs::File::from_std(file).take(len);
+                Ok(multipart::Part::stream_with_length(
+                    reqwest::Body::wrap_stream(ReaderStream::new(reader)),
+                    len,
+                ))
+            }
+        }
+    }
+}
+
+impl From<Vec<u8>> for UploadData {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+/// Notion ページの URL（または ID）からページ ID を取り出す。
+///
+/// URL の末尾にある 32 桁の 16 進数（ハイフン区切りも可）をページ ID とみなし、
+/// ハイフン区切りの形式で返す。
+pub fn parse_page_id(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next()?;
+    let segment = path.trim_end_matches('/').rsplit('/').next()?;
+    let chars: Vec<char> = segment.chars().filter(|c| *c != '-').collect();
+    let id: String = chars[chars.len().checked_sub(32)?..].iter().collect();
+    if !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    Some(format!(
+        "{}-{}-{}-{}-{}",
+        &id[0..8],
+        &id[8..12],
+        &id[12..16],
+        &id[16..20],
+        &id[20..32]
+    ))
+}
+
+/// データソースを親にしてページを作成するリクエストのボディを組み立てる。
+fn data_source_page_request(
+    data_source_id: &str,
+    properties: &BTreeMap<String, PageProperty>,
+) -> Result<serde_json::Value> {
+    Ok(serde_json::json!({
+        "parent": {
+            "type": "data_source_id",
+            "data_source_id": data_source_id,
+        },
+        "properties": serde_json::to_value(properties).context("Failed to serialize page properties")?,
+    }))
+}
+
+/// 設定されたプロパティの値を Notion のページプロパティに変換する。
+fn page_property(value: &NotionPropertyValue, date: NaiveDate) -> Result<PageProperty> {
+    let select_value = |name: &str| SelectPropertyValue {
+        id: None,
+        name: Some(name.to_string()),
+        color: None,
+    };
+
+    let property = match value {
+        NotionPropertyValue::Select { value } => PageProperty::Select {
+            id: None,
+            select: Some(select_value(value)),
+        },
+        NotionPropertyValue::MultiSelect { value } => PageProperty::MultiSelect {
+            id: None,
+            multi_select: value.iter().map(|name| select_value(name)).collect(),
+        },
+        NotionPropertyValue::Date => PageProperty::Date {
+            id: None,
+            date: Some(DatePropertyValue {
+                start: Some(DateOrDateTime::Date(date)),
+                end: None,
+                time_zone: None,
+            }),
+        },
+        NotionPropertyValue::Number { value } => PageProperty::Number {
+            id: None,
+            number: Some(
+                serde_json::Number::from_f64(*value)
+                    .with_context(|| format!("Invalid number property value: {}", value))?,
+            ),
+        },
+        NotionPropertyValue::Checkbox { value } => PageProperty::Checkbox {
+            id: None,
+            checkbox: *value,
+        },
+        NotionPropertyValue::People { value } => PageProperty::People {
+            id: None,
+            people: value
+                .iter()
+                .map(|id| User {
+                    object: "user".to_string(),
+                    id: id.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+        },
+    };
+
+    Ok(property)
+}
+
+/// アップロードするデータを送信単位の `(offset, len)` に分割する。
+///
+/// single_part で送信できるサイズなら 1 パートにまとめる。
+fn split_into_parts(size: u64) -> Vec<(u64, u64)> {
+    if size <= SINGLE_PART_MAX_SIZE {
+        return vec![(0, size)];
+    }
+
+    (0..size)
+        .step_by(MULTI_PART_CHUNK_SIZE as usize)
+        .map(|offset| (offset, MULTI_PART_CHUNK_SIZE.min(size - offset)))
+        .collect()
+}
+
+/// notion-client のエラーを、リトライで回復しうるかどうかで分類する。
+fn classify_notion_client_error(error: NotionClientError) -> RetryError {
+    match error {
+        NotionClientError::InvalidStatusCode { error: response } => {
+            let status = u16::try_from(response.status).unwrap_or(u16::MAX);
+            NotionError::from_status(status, None, response.message).into()
+        }
+        NotionClientError::FailedToRequest { .. } | NotionClientError::FailedToText { .. } => {
+            NotionError::Http {
+                status: None,
+                message: error.to_string(),
+            }
+            .into()
+        }
+        _ => RetryError::permanent(error),
+    }
+}
+
+/// Notion API のエラーレスポンス。
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    message: String,
+}
+
+/// ブロック追加レスポンスのブロック情報。
+#[derive(Debug, Deserialize)]
+struct BlockInfo {
+    id: String,
+}
+
+/// ブロック追加レスポンス。
+#[derive(Debug, Deserialize)]
+struct AppendBlockChildrenResponse {
+    results: Vec<BlockInfo>,
+}
+
+/// ブロックの子要素一覧のレスポンス（ブロックは JSON のまま扱う）。
+#[derive(Debug, Deserialize)]
+struct BlockChildrenResponse {
+    results: Vec<serde_json::Value>,
+    /// 続きのページがあるかどうか
+    #[serde(default)]
+    has_more: bool,
+    /// 続きのページを取得するためのカーソル
+    #[serde(default)]
+    next_cursor: Option<String>,
+}
+
+/// データベースクエリレスポンスのページ情報。
+#[derive(Debug, Deserialize)]
+struct PageInfo {
+    id: String,
+    url: String,
+}
+
+/// データベースクエリレスポンス。
+#[derive(Debug, Deserialize)]
+struct DatabaseQueryResponse {
+    results: Vec<PageInfo>,
+}
+
+/// データベースの取得レスポンス（データソースの一覧だけを読む）。
+#[derive(Debug, Deserialize)]
+struct DatabaseResponse {
+    #[serde(default)]
+    data_sources: Vec<DataSourceInfo>,
+}
+
+/// データベースに含まれるデータソース。
+#[derive(Debug, Deserialize)]
+struct DataSourceInfo {
+    id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_into_parts_single_part() {
+        assert_eq!(split_into_parts(0), vec![(0, 0)]);
+        assert_eq!(
+            split_into_parts(SINGLE_PART_MAX_SIZE),
+            vec![(0, SINGLE_PART_MAX_SIZE)]
+        );
+    }
+
+    #[test]
+    fn test_split_into_parts_multi_part() {
+        let size = SINGLE_PART_MAX_SIZE + 1;
+        assert_eq!(
+            split_into_parts(size),
+            vec![
+                (0, MULTI_PART_CHUNK_SIZE),
+                (MULTI_PART_CHUNK_SIZE, MULTI_PART_CHUNK_SIZE),
+                (2 * MULTI_PART_CHUNK_SIZE, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_notion_error_from_response() {
+        let body = r#"{"object":"error","status":400,"code":"validation_error","message":"Title is not a property that exists."}"#;
+        assert!(matches!(
+            NotionError::from_response(400, None, body),
+            NotionError::ValidationError(message) if message == "Title is not a property that exists."
+        ));
+        assert!(matches!(
+            NotionError::from_response(401, None, "unauthorized"),
+            NotionError::Unauthorized(message) if message == "unauthorized"
+        ));
+
+        let rate_limited = NotionError::from_response(429, Some(Duration::from_secs(3)), "");
+        assert!(rate_limited.is_retryable());
+        assert_eq!(rate_limited.retry_after(), Some(Duration::from_secs(3)));
+
+        assert!(NotionError::from_response(502, None, "").is_retryable());
+        assert!(!NotionError::from_response(404, None, "").is_retryable());
+        assert!(!NotionError::from_response(400, None, body).is_retryable());
+    }
+
+    #[test]
+    fn test_notion_error_downcast_through_retry_error() {
+        let error = RetryError::from(NotionError::from_response(401, None, "unauthorized"))
+            .context("Failed to query database".to_string());
+        assert!(!error.is_transient());
+
+        let error = error.into_inner();
+        assert_eq!(error.to_string(), "Failed to query database");
+        assert!(matches!(
+            error.downcast_ref::<NotionError>(),
+            Some(NotionError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn test_page_property_serialization() {
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+
+        let property = page_property(&NotionPropertyValue::Date, date).unwrap();
+        assert_eq!(
+            serde_json::to_value(property).unwrap(),
+            serde_json::json!({
+                "type": "date",
+                "date": { "start": "2025-01-01", "end": null, "time_zone": null }
+            })
+        );
+
+        let property = page_property(
+            &NotionPropertyValue::People {
+                value: vec!["user-id".to_string()],
+            },
+            date,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(property).unwrap(),
+            serde_json::json!({ "type": "people", "people": [{ "object": "user", "id": "user-id" }] })
+        );
+
+        assert!(page_property(&NotionPropertyValue::Number { value: f64::NAN }, date).is_err());
+    }
+
+    #[test]
+    fn test_data_source_page_request() {
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let properties = BTreeMap::from([(
+            "日付".to_string(),
+            page_property(&NotionPropertyValue::Date, date).unwrap(),
+        )]);
+        assert_eq!(
+            data_source_page_request("source-id", &properties).unwrap(),
+            serde_json::json!({
+                "parent": { "type": "data_source_id", "data_source_id": "source-id" },
+                "properties": {
+                    "日付": {
+                        "type": "date",
+                        "date": { "start": "2025-01-01", "end": null, "time_zone": null }
+                    }
+                }
+            })
+        );
+
+        let database: DatabaseResponse = serde_json::from_value(serde_json::json!({
+            "object": "database",
+            "id": "database-id",
+            "data_sources": [{ "id": "source-id", "name": "日報" }]
+        }))
+        .unwrap();
+        assert_eq!(database.data_sources[0].id, "source-id");
+    }
+
+    #[test]
+    fn test_parse_page_id() {
+        let expected = Some("0123abcd-4567-89ef-0123-456789abcdef".to_string());
+        assert_eq!(
+            parse_page_id(
+                "https://www.notion.so/workspace/2025-01-01-0123abcd456789ef0123456789abcdef?pvs=4"
+            ),
+            expected
+        );
+        assert_eq!(
+            parse_page_id("https://www.notion.so/0123abcd456789ef0123456789abcdef#heading"),
+            expected
+        );
+        assert_eq!(
+            parse_page_id("0123abcd-4567-89ef-0123-456789abcdef"),
+            expected
+        );
+        assert_eq!(parse_page_id("https://www.notion.so/workspace/Diary"), None);
+        assert_eq!(
+            parse_page_id("https://www.notion.so/xyz3abcd456789ef0123456789abcdef"),
+            None
+        );
+    }
+}
@@ -0,0 +1,533 @@
+//! OGP メタデータの取得機能を提供する。
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use encoding_rs::{Encoding, UTF_8};
+use futures::future::BoxFuture;
+use regex::bytes::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::{block::BlockKind, url_handler::UrlHandler, url_parser};
+
+/// meta タグの charset を探す HTML の先頭のバイト数。
+const CHARSET_SNIFF_BYTES: usize = 4096;
+
+/// 取得する HTML の最大バイト数（OGP は head にあるため、先頭だけを読めば足りる）。
+///
+/// og:image などの画像のダウンロードも、この大きさを上限にする。
+pub(crate) const MAX_BODY_BYTES: usize = 512 * 1024;
+
+/// たどるリダイレクトの最大回数。
+const MAX_REDIRECTS: usize = 5;
+
+/// OGP メタデータ。
+#[derive(Debug, Clone, Default)]
+pub struct OgpMetadata {
+    /// og:title - ページタイトル
+    pub title: Option<String>,
+    /// og:description - ページ説明
+    pub description: Option<String>,
+    /// og:image - サムネイル画像の URL（ページの URL で解決した絶対 URL）
+    pub image: Option<String>,
+    /// og:site_name - サイト名
+    pub site_name: Option<String>,
+    /// og:type - ページの種類（`website`・`article` など）
+    pub og_type: Option<String>,
+}
+
+/// OGP メタデータを取得するクライアント。
+pub struct OgpFetcher {
+    http_client: reqwest::Client,
+}
+
+impl OgpFetcher {
+    /// 新しい OgpFetcher を作成する。
+    pub fn new(timeout: Duration) -> Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .user_agent("kgd-bot/1.0")
+            .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
+            .build()
+            .context("Failed to create HTTP client for OGP fetcher")?;
+
+        Ok(Self { http_client })
+    }
+
+    /// URL から OGP メタデータを取得する。
+    ///
+    /// 取得に失敗した場合は None を返す（エラーはログに記録）。
+    pub async fn fetch(&self, url: &str) -> Option<OgpMetadata> {
+        match self.fetch_inner(url).await {
+            Ok(metadata) => Some(metadata),
+            Err(e) => {
+                tracing::debug!(url = %url, error = %e, "Failed to fetch OGP metadata");
+                None
+            }
+        }
+    }
+
+    /// 複数の URL から OGP メタデータを並列で取得する。
+    pub async fn fetch_many(&self, urls: &[String]) -> HashMap<String, OgpMetadata> {
+        let futures: Vec<_> = urls
+            .iter()
+            .map(|url| async {
+                let metadata = self.fetch(url).await;
+                (url.clone(), metadata)
+            })
+            .collect();
+
+        futures::future::join_all(futures)
+            .await
+            .into_iter()
+            .filter_map(|(url, ogp)| ogp.map(|o| (url, o)))
+            .collect()
+    }
+
+    async fn fetch_inner(&self, url: &str) -> Result<OgpMetadata> {
+        let mut response = self
+            .http_client
+            .get(url)
+            .send()
+            .await
+            .context("HTTP request failed")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("HTTP status: {}", response.status());
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        // 動画や画像などの URL は本文をダウンロードしない
+        if let Some(content_type) = &content_type
+            && !is_html_content_type(content_type)
+        {
+            anyhow::bail!("Not an HTML page: {}", content_type);
+        }
+
+        // 巨大なページは先頭だけを読んで打ち切る
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .context("Failed to read response body")?
+        {
+            let remaining = MAX_BODY_BYTES - body.len();
+            body.extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+            if body.len() >= MAX_BODY_BYTES {
+                break;
+            }
+        }
+        let html = decode_html(&body, content_type.as_deref());
+
+        let mut metadata = parse_ogp_metadata(&html);
+        metadata.image = metadata
+            .image
+            .and_then(|image| reqwest::Url::parse(url).ok()?.join(&image).ok())
+            .map(String::from);
+        Ok(metadata)
+    }
+}
+
+/// すべてのブックマークに OGP のタイトルと説明をキャプションとして付け、
+/// サムネイル画像のブロックに og:image を貼る。
+///
+/// どのサイトでも使えるため、サイト専用のハンドラーより低い優先度で登録する。
+impl UrlHandler for OgpFetcher {
+    fn name(&self) -> &'static str {
+        "ogp"
+    }
+
+    fn matches(&self, _url: &str, kind: BlockKind) -> bool {
+        matches!(kind, BlockKind::Bookmark | BlockKind::OgpImage)
+    }
+
+    fn build_block<'a>(
+        &'a self,
+        url: &'a str,
+        block: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<serde_json::Value>> {
+        Box::pin(async move {
+            let metadata = self.fetch_inner(url).await?;
+            let mut block = block.clone();
+            url_parser::apply_ogp_to_block(&mut block, &metadata)?;
+            Ok(block)
+        })
+    }
+}
+
+/// Content-Type が HTML（`text/html` または `application/xhtml+xml`）かどうかを返す。
+fn is_html_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime.eq_ignore_ascii_case("text/html") || mime.eq_ignore_ascii_case("application/xhtml+xml")
+}
+
+/// HTML のバイト列を文字コードを判定してデコードする。
+///
+/// BOM、Content-Type の charset、meta タグの charset の順に判定し、どれもなければ UTF-8 とみなす。
+fn decode_html(bytes: &[u8], content_type: Option<&str>) -> String {
+    let encoding = Encoding::for_bom(bytes)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| {
+            content_type
+                .and_then(charset_parameter)
+                .and_then(|label| Encoding::for_label(label.as_bytes()))
+        })
+        // UTF-16 の指定は ASCII 互換のバイト列から読めたものなので UTF-8 として扱う
+        .or_else(|| sniff_meta_charset(bytes).map(Encoding::output_encoding))
+        .unwrap_or(UTF_8);
+
+    let (html, _, _) = encoding.decode(bytes);
+    html.into_owned()
+}
+
+/// `text/html; charset=Shift_JIS` 形式の値から charset を取り出す。
+fn charset_parameter(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches(['"', '\'']))
+    })
+}
+
+/// HTML の先頭にある meta タグ（`<meta charset>` または `<meta http-equiv="Content-Type">`）の charset を返す。
+fn sniff_meta_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    let re = Regex::new(r#"(?i)<meta\s[^>]*charset\s*=\s*["']?\s*([\w:.-]+)"#).unwrap();
+    let head = &bytes[..bytes.len().min(CHARSET_SNIFF_BYTES)];
+    let label = re.captures(head)?.get(1)?.as_bytes();
+    Encoding::for_label(label)
+}
+
+/// HTML から OGP メタデータをパースする。
+///
+/// HTML パーサーで DOM を組み立て、meta タグから OGP 情報を抽出する。
+fn parse_ogp_metadata(html: &str) -> OgpMetadata {
+    let document = Html::parse_document(html);
+
+    OgpMetadata {
+        // フォールバック: <title> タグ
+        title: extract_meta_property(&document, "og:title")
+            .or_else(|| extract_title_tag(&document)),
+        // フォールバック: description meta タグ
+        description: extract_meta_property(&document, "og:description")
+            .or_else(|| extract_meta_name(&document, "description")),
+        image: extract_meta_property(&document, "og:image"),
+        site_name: extract_meta_property(&document, "og:site_name"),
+        og_type: extract_meta_property(&document, "og:type"),
+    }
+}
+
+/// property 属性で指定された meta タグの content を抽出する。
+///
+/// property の代わりに name 属性で OGP を指定しているページにも対応する。
+fn extract_meta_property(document: &Html, property: &str) -> Option<String> {
+    find_meta_content(document, |meta| {
+        ["property", "name"].iter().any(|attribute| {
+            meta.attr(attribute)
+                .is_some_and(|value| value.trim().eq_ignore_ascii_case(property))
+        })
+    })
+}
+
+/// name 属性で指定された meta タグの content を抽出する。
+fn extract_meta_name(document: &Html, name: &str) -> Option<String> {
+    find_meta_content(document, |meta| {
+        meta.attr("name")
+            .is_some_and(|value| value.trim().eq_ignore_ascii_case(name))
+    })
+}
+
+/// 条件に合う最初の meta タグのうち、content が空でないものの content を返す。
+fn find_meta_content(
+    document: &Html,
+    mut predicate: impl FnMut(&ElementRef) -> bool,
+) -> Option<String> {
+    let selector = Selector::parse("meta[content]").unwrap();
+    document
+        .select(&selector)
+        .filter(|meta| predicate(meta))
+        .filter_map(|meta| meta.attr("content"))
+        .map(str::trim)
+        .find(|content| !content.is_empty())
+        .map(str::to_string)
+}
+
+/// <title> タグの内容を抽出する。
+fn extract_title_tag(document: &Html) -> Option<String> {
+    let selector = Selector::parse("title").unwrap();
+    let title = document
+        .select(&selector)
+        .next()?
+        .text()
+        .collect::<String>();
+    let title = title.trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ogp_metadata_full() {
+        let html = r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+                <meta property="og:title" content="Test Title">
+                <meta property="og:description" content="Test Description">
+            </head>
+            </html>
+        "#;
+
+        let metadata = parse_ogp_metadata(html);
+        assert_eq!(metadata.title, Some("Test Title".to_string()));
+        assert_eq!(metadata.description, Some("Test Description".to_string()));
+    }
+
+    #[test]
+    fn test_parse_ogp_metadata_image() {
+        let html = r#"
+            <meta property="og:image:width" content="1200">
+            <meta property="og:image" content="/images/card.png">
+        "#;
+
+        let metadata = parse_ogp_metadata(html);
+        assert_eq!(metadata.image, Some("/images/card.png".to_string()));
+        assert_eq!(parse_ogp_metadata("<title>No Image</title>").image, None);
+    }
+
+    #[test]
+    fn test_parse_ogp_metadata_content_first() {
+        let html = r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+                <meta content="Title Content First" property="og:title">
+                <meta content="Description Content First" property="og:description">
+            </head>
+            </html>
+        "#;
+
+        let metadata = parse_ogp_metadata(html);
+        assert_eq!(metadata.title, Some("Title Content First".to_string()));
+        assert_eq!(
+            metadata.description,
+            Some("Description Content First".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_ogp_metadata_fallback_to_title_tag() {
+        let html = r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+                <title>Fallback Title</title>
+            </head>
+            </html>
+        "#;
+
+        let metadata = parse_ogp_metadata(html);
+        assert_eq!(metadata.title, Some("Fallback Title".to_string()));
+        assert_eq!(metadata.description, None);
+    }
+
+    #[test]
+    fn test_parse_ogp_metadata_fallback_to_meta_description() {
+        let html = r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+                <meta property="og:title" content="OGP Title">
+                <meta name="description" content="Meta Description">
+            </head>
+            </html>
+        "#;
+
+        let metadata = parse_ogp_metadata(html);
+        assert_eq!(metadata.title, Some("OGP Title".to_string()));
+        assert_eq!(metadata.description, Some("Meta Description".to_string()));
+    }
+
+    #[test]
+    fn test_parse_ogp_metadata_og_description_takes_priority() {
+        let html = r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+                <meta property="og:description" content="OGP Description">
+                <meta name="description" content="Meta Description">
+            </head>
+            </html>
+        "#;
+
+        let metadata = parse_ogp_metadata(html);
+        assert_eq!(metadata.description, Some("OGP Description".to_string()));
+    }
+
+    #[test]
+    fn test_parse_ogp_metadata_empty_values_ignored() {
+        let html = r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+                <meta property="og:title" content="">
+                <meta property="og:description" content="   ">
+                <title>Fallback Title</title>
+            </head>
+            </html>
+        "#;
+
+        let metadata = parse_ogp_metadata(html);
+        assert_eq!(metadata.title, Some("Fallback Title".to_string()));
+        assert_eq!(metadata.description, None);
+    }
+
+    #[test]
+    fn test_parse_ogp_metadata_whitespace_trimmed() {
+        let html = r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+                <meta property="og:title" content="  Trimmed Title  ">
+                <meta property="og:description" content="  Trimmed Description  ">
+            </head>
+            </html>
+        "#;
+
+        let metadata = parse_ogp_metadata(html);
+        assert_eq!(metadata.title, Some("Trimmed Title".to_string()));
+        assert_eq!(
+            metadata.description,
+            Some("Trimmed Description".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_ogp_metadata_no_metadata() {
+        let html = r#"
+            <!DOCTYPE html>
+            <html>
+            <head></head>
+            <body>Hello</body>
+            </html>
+        "#;
+
+        let metadata = parse_ogp_metadata(html);
+        assert_eq!(metadata.title, None);
+        assert_eq!(metadata.description, None);
+    }
+
+    #[test]
+    fn test_parse_ogp_metadata_html_entities_decoded() {
+        let html = r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+                <meta property="og:title" content="Title &amp; More">
+                <meta property="og:description" content="&lt;Test&gt; &quot;Description&quot;">
+            </head>
+            </html>
+        "#;
+
+        let metadata = parse_ogp_metadata(html);
+        assert_eq!(metadata.title, Some("Title & More".to_string()));
+        assert_eq!(
+            metadata.description,
+            Some("<Test> \"Description\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_ogp_metadata_site_name_and_type() {
+        let html = r#"
+            <meta property="og:site_name" content="Example Blog">
+            <meta property="og:type" content="article">
+        "#;
+
+        let metadata = parse_ogp_metadata(html);
+        assert_eq!(metadata.site_name, Some("Example Blog".to_string()));
+        assert_eq!(metadata.og_type, Some("article".to_string()));
+    }
+
+    #[test]
+    fn test_parse_ogp_metadata_irregular_markup() {
+        // 属性の間の改行・他の属性・大文字・引用符のない値・name 属性での指定
+        let html = r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+                <META
+                    data-rh="true"
+                    CONTENT="It's a &quot;title&quot;"
+                    Property=og:title
+                />
+                <meta name="og:description" content='Described by "name"'>
+                <meta property="og:image"
+                      content="https://example.com/card.png?a=1&amp;b=2">
+            </head>
+            </html>
+        "#;
+
+        let metadata = parse_ogp_metadata(html);
+        assert_eq!(metadata.title, Some("It's a \"title\"".to_string()));
+        assert_eq!(
+            metadata.description,
+            Some("Described by \"name\"".to_string())
+        );
+        assert_eq!(
+            metadata.image,
+            Some("https://example.com/card.png?a=1&b=2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_is_html_content_type() {
+        assert!(is_html_content_type("text/html"));
+        assert!(is_html_content_type("Text/HTML; charset=Shift_JIS"));
+        assert!(is_html_content_type("application/xhtml+xml"));
+        assert!(!is_html_content_type("video/mp4"));
+        assert!(!is_html_content_type("application/json; charset=utf-8"));
+    }
+
+    #[test]
+    fn test_decode_html_charset() {
+        let html = "<meta charset=\"Shift_JIS\"><title>日本語のタイトル</title>";
+        let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode(html);
+        assert_eq!(decode_html(&bytes, Some("text/html")), html);
+
+        let html = r#"<meta http-equiv="Content-Type" content="text/html; charset=EUC-JP">
+            <meta property="og:title" content="古いサイト">"#;
+        let (bytes, _, _) = encoding_rs::EUC_JP.encode(html);
+        let metadata = parse_ogp_metadata(&decode_html(&bytes, None));
+        assert_eq!(metadata.title, Some("古いサイト".to_string()));
+
+        // Content-Type の charset を meta タグより優先する
+        let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode("<meta charset=\"utf-8\">日本語");
+        assert_eq!(
+            decode_html(&bytes, Some("text/html; charset=\"Shift_JIS\"")),
+            "<meta charset=\"utf-8\">日本語"
+        );
+        // 指定がなければ UTF-8
+        assert_eq!(decode_html("日本語".as_bytes(), None), "日本語");
+    }
+
+    #[test]
+    fn test_extract_title_tag() {
+        let title = |html| extract_title_tag(&Html::parse_document(html));
+        assert_eq!(title("<title>Test</title>"), Some("Test".to_string()));
+        assert_eq!(
+            title("<title>  Trimmed  </title>"),
+            Some("Trimmed".to_string())
+        );
+        assert_eq!(title("<title>A &amp; B</title>"), Some("A & B".to_string()));
+        assert_eq!(title("<title></title>"), None);
+        assert_eq!(title("<p>No title</p>"), None);
+    }
+}
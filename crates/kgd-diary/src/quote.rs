@@ -0,0 +1,226 @@
+//! メッセージ本文中の `>` で始まる行（Discord の引用）を Notion の quote ブロックに変換する。
+//!
+//! 連続する引用行は 1 つの quote ブロックにまとめ、`>>> ` で始まる行からは本文の最後までを引用として扱う。
+//! 引用の中でさらに `>` で始まる行は、入れ子の quote ブロック（子ブロック）にする。
+//! コードブロック（```）の中の行は引用として扱わない。
+
+/// 入れ子にする引用の深さの上限。
+///
+/// Notion API は 1 回のリクエストで 2 階層までのブロックしか作成できないため、
+/// それより深い引用は `>` を残したままテキストとして扱う。
+const MAX_QUOTE_DEPTH: usize = 2;
+
+/// 本文を引用とそれ以外のテキストに分けた部分。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuotePart {
+    /// 引用以外のテキスト
+    Text(String),
+    /// 引用
+    Quote(Quote),
+}
+
+/// 本文から取り出した引用。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quote {
+    /// `>` を 1 段取り除いた引用の中身（入れ子の引用を含む）
+    pub parts: Vec<QuotePart>,
+}
+
+impl Quote {
+    /// quote ブロックを作成する。
+    ///
+    /// 先頭のテキストを引用の本文にし、続くテキストは paragraph、入れ子の引用は quote の子ブロックにする。
+    /// テキストは `rich_text` で rich_text の要素に変換する。
+    pub fn to_block(
+        &self,
+        rich_text: &impl Fn(&str) -> Vec<serde_json::Value>,
+    ) -> serde_json::Value {
+        let mut parts = self.parts.iter().peekable();
+        let text = match parts.peek() {
+            Some(QuotePart::Text(text)) => {
+                parts.next();
+                rich_text(text)
+            }
+            _ => Vec::new(),
+        };
+        let children: Vec<serde_json::Value> = parts
+            .map(|part| match part {
+                QuotePart::Text(text) => serde_json::json!({
+                    "object": "block",
+                    "type": "paragraph",
+                    "paragraph": {
+                        "rich_text": rich_text(text)
+                    }
+                }),
+                QuotePart::Quote(quote) => quote.to_block(rich_text),
+            })
+            .collect();
+
+        let mut block = serde_json::json!({
+            "object": "block",
+            "type": "quote",
+            "quote": {
+                "rich_text": text
+            }
+        });
+        if !children.is_empty() {
+            block["quote"]["children"] = serde_json::json!(children);
+        }
+        block
+    }
+}
+
+/// 本文を引用とそれ以外のテキストに分ける。
+///
+/// 引用の前後の空行は取り除き、空になったテキストは含めない。引用が無い場合は本文をそのまま 1 つのテキストとして返す。
+pub fn split_quotes(text: &str) -> Vec<QuotePart> {
+    if !text.contains('>') {
+        return vec![QuotePart::Text(text.to_string())];
+    }
+
+    let parts = split_quotes_at_depth(text, 1);
+    if !parts.iter().any(|part| matches!(part, QuotePart::Quote(_))) {
+        return vec![QuotePart::Text(text.to_string())];
+    }
+    parts
+}
+
+/// `depth` 段目の引用として本文を分ける。
+fn split_quotes_at_depth(text: &str, depth: usize) -> Vec<QuotePart> {
+    let mut parts = Vec::new();
+    let mut text_lines: Vec<&str> = Vec::new();
+    let mut quote_lines: Vec<&str> = Vec::new();
+    let mut in_code_block = false;
+
+    let mut lines = text.split('\n');
+    while let Some(line) = lines.next() {
+        if !in_code_block
+            && depth == 1
+            && let Some(rest) = line.strip_prefix(">>> ")
+        {
+            push_text(&mut parts, &mut text_lines);
+            quote_lines.push(rest);
+            quote_lines.extend(lines.by_ref());
+            break;
+        }
+
+        match strip_quote_marker(line).filter(|_| !in_code_block && depth <= MAX_QUOTE_DEPTH) {
+            Some(rest) => {
+                push_text(&mut parts, &mut text_lines);
+                quote_lines.push(rest);
+            }
+            None => {
+                push_quote(&mut parts, &mut quote_lines, depth);
+                if line.trim_start().starts_with("```") {
+                    in_code_block = !in_code_block;
+                }
+                text_lines.push(line);
+            }
+        }
+    }
+    push_text(&mut parts, &mut text_lines);
+    push_quote(&mut parts, &mut quote_lines, depth);
+    parts
+}
+
+/// 行が引用であれば、`>` を取り除いた中身を返す（`>text` のように空白が続かないものは引用として扱わない）。
+fn strip_quote_marker(line: &str) -> Option<&str> {
+    match line.strip_prefix('>')? {
+        "" => Some(""),
+        rest => rest.strip_prefix(' '),
+    }
+}
+
+/// 溜まった引用以外の行を、前後の空行を取り除いて追加する。
+fn push_text(parts: &mut Vec<QuotePart>, lines: &mut Vec<&str>) {
+    let text = lines.join("\n");
+    lines.clear();
+    let text = text.trim_matches('\n');
+    if !text.trim().is_empty() {
+        parts.push(QuotePart::Text(text.to_string()));
+    }
+}
+
+/// 溜まった引用の行を 1 つの引用として追加する。中身が空の引用は追加しない。
+fn push_quote(parts: &mut Vec<QuotePart>, lines: &mut Vec<&str>, depth: usize) {
+    if lines.is_empty() {
+        return;
+    }
+    let text = lines.join("\n");
+    lines.clear();
+    let quote_parts = split_quotes_at_depth(&text, depth + 1);
+    if !quote_parts.is_empty() {
+        parts.push(QuotePart::Quote(Quote { parts: quote_parts }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> QuotePart {
+        QuotePart::Text(text.to_string())
+    }
+
+    fn quote(parts: Vec<QuotePart>) -> QuotePart {
+        QuotePart::Quote(Quote { parts })
+    }
+
+    #[test]
+    fn test_split_quotes() {
+        assert_eq!(
+            split_quotes("today\n> quoted\n>\n> second\nafter"),
+            vec![
+                text("today"),
+                quote(vec![text("quoted\n\nsecond")]),
+                text("after"),
+            ]
+        );
+        assert_eq!(
+            split_quotes("intro\n>>> a\nb\n\n> c"),
+            vec![
+                text("intro"),
+                quote(vec![text("a\nb"), quote(vec![text("c")])]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_nested_quotes() {
+        assert_eq!(
+            split_quotes("> a\n> > b\n> c"),
+            vec![quote(vec![text("a"), quote(vec![text("b")]), text("c")])]
+        );
+        // 上限より深い引用は `>` を残す
+        assert_eq!(
+            split_quotes("> > > deep"),
+            vec![quote(vec![quote(vec![text("> deep")])])]
+        );
+    }
+
+    #[test]
+    fn test_split_quotes_without_quote() {
+        for text in [">not a quote", "a > b", "```\n> code\n```", "a\n>"] {
+            assert_eq!(split_quotes(text), vec![QuotePart::Text(text.to_string())]);
+        }
+    }
+
+    #[test]
+    fn test_quote_block() {
+        let rich_text = |text: &str| vec![serde_json::json!({ "text": { "content": text } })];
+        let Some(QuotePart::Quote(parsed)) = split_quotes("> a\n> > b\n> c").pop() else {
+            panic!("expected a quote");
+        };
+        let block = parsed.to_block(&rich_text);
+        assert_eq!(block["type"], "quote");
+        assert_eq!(block["quote"]["rich_text"][0]["text"]["content"], "a");
+        let children = block["quote"]["children"].as_array().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0]["quote"]["rich_text"][0]["text"]["content"], "b");
+        assert!(children[0]["quote"].get("children").is_none());
+        assert_eq!(
+            children[1]["paragraph"]["rich_text"][0]["text"]["content"],
+            "c"
+        );
+    }
+}
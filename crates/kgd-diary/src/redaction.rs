@@ -0,0 +1,92 @@
+//! Notion に同期する前にメッセージ本文から機密情報を伏せ字にする。
+
+use std::borrow::Cow;
+
+use anyhow::Result;
+use regex::Regex;
+
+use crate::config::RedactionRuleConfig;
+
+/// コンパイル済み伏せ字ルール一式。
+pub struct CompiledRedactionRules {
+    /// ルールごとの正規表現と置換文字列
+    rules: Vec<(Regex, String)>,
+}
+
+impl CompiledRedactionRules {
+    /// すべてのルールを順に適用し、伏せ字にしたテキストと置換した件数を返す。
+    ///
+    /// どのルールにもマッチしない場合は元のテキストを借用したまま返す。
+    pub fn redact<'t>(&self, text: &'t str) -> (Cow<'t, str>, usize) {
+        let mut redacted = Cow::Borrowed(text);
+        let mut count = 0;
+
+        for (re, replacement) in &self.rules {
+            let matches = re.find_iter(&redacted).count();
+            if matches == 0 {
+                continue;
+            }
+            count += matches;
+            redacted = Cow::Owned(re.replace_all(&redacted, replacement.as_str()).into_owned());
+        }
+
+        (redacted, count)
+    }
+}
+
+/// 設定からコンパイル済み伏せ字ルールを作成する。
+///
+/// 無効な正規表現はエラーとして返す。
+pub fn compile_redaction_rules(rules: &[RedactionRuleConfig]) -> Result<CompiledRedactionRules> {
+    let rules = rules
+        .iter()
+        .map(|rule| {
+            let re = Regex::new(&rule.pattern).map_err(|e| {
+                anyhow::anyhow!("Invalid redaction pattern '{}': {}", rule.pattern, e)
+            })?;
+            Ok((re, rule.replacement.clone()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(CompiledRedactionRules { rules })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, replacement: &str) -> RedactionRuleConfig {
+        RedactionRuleConfig {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_redact_without_rules_borrows_text() {
+        let rules = compile_redaction_rules(&[]).unwrap();
+        let (redacted, count) = rules.redact("password: hunter2");
+        assert!(matches!(redacted, Cow::Borrowed(_)));
+        assert_eq!(redacted, "password: hunter2");
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_redact_applies_all_rules_and_counts_matches() {
+        let rules = compile_redaction_rules(&[
+            rule(r"(?i)(password\s*[:=]\s*)\S+", "${1}[REDACTED]"),
+            rule(r"\b(?:\d[ -]?){12,15}\d\b", "[CARD]"),
+        ])
+        .unwrap();
+
+        let (redacted, count) =
+            rules.redact("password=hunter2 card 4111 1111 1111 1111 and 4242424242424242");
+        assert_eq!(redacted, "password=[REDACTED] card [CARD] and [CARD]");
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_compile_rejects_invalid_pattern() {
+        assert!(compile_redaction_rules(&[rule("(unclosed", "x")]).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+//! 期間内の日報ページをまとめた週報・月報ページを Notion に作成する。
+
+use anyhow::Result;
+use chrono::{Datelike, Days, Months, NaiveDate};
+use chrono_tz::Tz;
+
+use super::notion::NotionClient;
+use super::start_of_day_in_timezone;
+use super::store::{DiaryEntryStats, DiaryStorage};
+
+/// レポートの集計単位。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportKind {
+    /// 月曜日から日曜日までの週報
+    Weekly,
+    /// 1 日から月末までの月報
+    Monthly,
+}
+
+/// レポートの対象期間（両端を含む）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportPeriod {
+    /// 集計単位
+    pub kind: ReportKind,
+    /// 期間の初日
+    pub start: NaiveDate,
+    /// 期間の最終日
+    pub end: NaiveDate,
+}
+
+/// レポートページの作成結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReportOutcome {
+    /// 新しくページを作成した（ページ URL）
+    Created(String),
+    /// 同じ期間のページが既に存在した（ページ URL）
+    AlreadyExists(String),
+    /// 期間内に日報が無いため作成しなかった
+    NoEntries,
+}
+
+impl ReportPeriod {
+    /// 指定した日付を含む週（月曜日〜日曜日）の期間を返す。
+    pub fn week_of(date: NaiveDate) -> Self {
+        let start = date - Days::new(u64::from(date.weekday().num_days_from_monday()));
+        Self {
+            kind: ReportKind::Weekly,
+            start,
+            end: start + Days::new(6),
+        }
+    }
+
+    /// 指定した日付を含む月の期間を返す。
+    pub fn month_of(date: NaiveDate) -> Self {
+        let start = date.with_day(1).unwrap();
+        Self {
+            kind: ReportKind::Monthly,
+            start,
+            end: start + Months::new(1) - Days::new(1),
+        }
+    }
+
+    /// レポートページのタイトルを返す。
+    pub fn title(&self) -> String {
+        match self.kind {
+            ReportKind::Weekly => format!(
+                "週報 {}〜{}",
+                self.start.format("%Y-%m-%d"),
+                self.end.format("%Y-%m-%d")
+            ),
+            ReportKind::Monthly => format!("月報 {}", self.start.format("%Y-%m")),
+        }
+    }
+}
+
+/// 指定した日付に締めを迎えるレポートの期間を返す。
+///
+/// 週報は月曜日に前週分を、月報は 1 日に前月分を対象にする。
+pub fn due_report_periods(today: NaiveDate, weekly: bool, monthly: bool) -> Vec<ReportPeriod> {
+    let yesterday = today - Days::new(1);
+    let mut periods = Vec::new();
+    if weekly && today.weekday().num_days_from_monday() == 0 {
+        periods.push(ReportPeriod::week_of(yesterday));
+    }
+    if monthly && today.day() == 1 {
+        periods.push(ReportPeriod::month_of(yesterday));
+    }
+    periods
+}
+
+/// フォーラムチャンネルの期間内の日報ページへのリンクと統計をまとめたレポートページを作成する。
+///
+/// レポートページは `notion` のデータベースに作成する。
+/// 同じタイトルのページが既に存在する場合や、期間内に日報が無い場合は作成しない。
+pub async fn publish_report(
+    notion: &NotionClient,
+    store: &impl DiaryStorage,
+    forum_channel_id: u64,
+    period: &ReportPeriod,
+    timezone: &Tz,
+) -> Result<ReportOutcome> {
+    let title = period.title();
+    if let Some((_, url)) = notion.find_diary_page_by_title(&title).await? {
+        return Ok(ReportOutcome::AlreadyExists(url));
+    }
+
+    let entries = store
+        .get_entry_stats_in_date_range(
+            forum_channel_id,
+            start_of_day_in_timezone(period.start, timezone)?,
+            start_of_day_in_timezone(period.end, timezone)?,
+        )
+        .await?;
+    if entries.is_empty() {
+        return Ok(ReportOutcome::NoEntries);
+    }
+
+    let (page_id, url) = notion.create_diary_page(&title, period.start).await?;
+    notion
+        .append_blocks(&page_id, report_blocks(&entries, timezone))
+        .await?;
+
+    tracing::info!(
+        title = %title,
+        entries = entries.len(),
+        "Created diary report page"
+    );
+
+    Ok(ReportOutcome::Created(url))
+}
+
+/// レポートページに追加するブロックを作成する。
+fn report_blocks(entries: &[DiaryEntryStats], timezone: &Tz) -> Vec<serde_json::Value> {
+    let message_count: i64 = entries.iter().map(|entry| entry.message_count).sum();
+    let image_count: i64 = entries.iter().map(|entry| entry.image_count).sum();
+
+    let mut blocks = vec![
+        text_block(
+            "paragraph",
+            vec![plain_text(&format!(
+                "日報 {}件 / メッセージ {}件 / 画像 {}件",
+                entries.len(),
+                message_count,
+                image_count
+            ))],
+        ),
+        text_block("heading_2", vec![plain_text("日報一覧")]),
+    ];
+    blocks.extend(entries.iter().map(|entry| {
+        let date = entry.date.with_timezone(timezone).format("%Y-%m-%d");
+        text_block(
+            "bulleted_list_item",
+            vec![
+                serde_json::json!({
+                    "type": "text",
+                    "text": {
+                        "content": date.to_string(),
+                        "link": { "url": entry.page_url }
+                    }
+                }),
+                plain_text(&format!(
+                    " — メッセージ {}件 / 画像 {}件",
+                    entry.message_count, entry.image_count
+                )),
+            ],
+        )
+    }));
+    blocks
+}
+
+fn text_block(block_type: &str, rich_text: Vec<serde_json::Value>) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": block_type,
+        block_type: {
+            "rich_text": rich_text
+        }
+    })
+}
+
+fn plain_text(content: &str) -> serde_json::Value {
+    serde_json::json!({
+        "type": "text",
+        "text": {
+            "content": content
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone as _;
+
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_report_period_ranges_and_titles() {
+        // 2025-01-08 は水曜日
+        let week = ReportPeriod::week_of(date(2025, 1, 8));
+        assert_eq!(
+            (week.start, week.end),
+            (date(2025, 1, 6), date(2025, 1, 12))
+        );
+        assert_eq!(week.title(), "週報 2025-01-06〜2025-01-12");
+
+        let month = ReportPeriod::month_of(date(2024, 2, 15));
+        assert_eq!(
+            (month.start, month.end),
+            (date(2024, 2, 1), date(2024, 2, 29))
+        );
+        assert_eq!(month.title(), "月報 2024-02");
+    }
+
+    #[test]
+    fn test_due_report_periods() {
+        // 2024-12-30 は月曜日、2025-01-01 は水曜日、2025-09-01 は月曜日
+        assert_eq!(
+            due_report_periods(date(2024, 12, 30), true, true),
+            vec![ReportPeriod::week_of(date(2024, 12, 29))]
+        );
+        assert_eq!(
+            due_report_periods(date(2025, 1, 1), true, true),
+            vec![ReportPeriod::month_of(date(2024, 12, 31))]
+        );
+        assert_eq!(due_report_periods(date(2025, 9, 1), true, true).len(), 2);
+        assert!(due_report_periods(date(2025, 9, 1), false, false).is_empty());
+        assert!(due_report_periods(date(2025, 9, 2), true, true).is_empty());
+    }
+
+    #[test]
+    fn test_report_blocks_link_each_entry() {
+        let timezone = chrono_tz::Asia::Tokyo;
+        let entries = vec![
+            DiaryEntryStats {
+                page_url: "https://www.notion.so/a".to_string(),
+                date: timezone
+                    .with_ymd_and_hms(2025, 1, 6, 0, 0, 0)
+                    .unwrap()
+                    .to_utc(),
+                message_count: 3,
+                image_count: 1,
+            },
+            DiaryEntryStats {
+                page_url: "https://www.notion.so/b".to_string(),
+                date: timezone
+                    .with_ymd_and_hms(2025, 1, 7, 0, 0, 0)
+                    .unwrap()
+                    .to_utc(),
+                message_count: 2,
+                image_count: 0,
+            },
+        ];
+
+        let blocks = report_blocks(&entries, &timezone);
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(
+            blocks[0]["paragraph"]["rich_text"][0]["text"]["content"],
+            "日報 2件 / メッセージ 5件 / 画像 1件"
+        );
+        let link = &blocks[2]["bulleted_list_item"]["rich_text"][0]["text"];
+        assert_eq!(link["content"], "2025-01-06");
+        assert_eq!(link["link"]["url"], "https://www.notion.so/a");
+    }
+}
@@ -0,0 +1,258 @@
+//! 一時的な失敗に対する指数バックオフ付きリトライ。
+
+use std::{future::Future, time::Duration};
+
+use anyhow::Result;
+
+/// 指数バックオフによるリトライ方針。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 最大試行回数（初回を含む）
+    max_attempts: u32,
+    /// 初回リトライまでの待機時間
+    initial_backoff: Duration,
+    /// 待機時間の上限
+    max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// 新しい RetryPolicy を作成する。
+    pub fn new(max_attempts: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    /// 指定した試行回数（1 始まり）の失敗後に待機する時間を返す。
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        self.initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff)
+    }
+
+    /// 失敗後にリトライすべきであれば、待機時間を返す。
+    ///
+    /// サーバーから待機時間が指定されている場合はそちらを優先する。
+    pub fn next_delay(&self, attempt: u32, error: &RetryError) -> Option<Duration> {
+        if !error.is_transient() || attempt >= self.max_attempts {
+            return None;
+        }
+        Some(error.retry_after().unwrap_or_else(|| self.backoff(attempt)))
+    }
+
+    /// 一時的なエラーの間、指数バックオフで操作をリトライする。
+    pub async fn run<T, F, Fut>(&self, operation: &str, mut f: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = std::result::Result<T, RetryError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match f().await {
+                Ok(value) => return Ok(value),
+                Err(error) => {
+                    let Some(delay) = self.next_delay(attempt, &error) else {
+                        return Err(error.into_inner());
+                    };
+                    tracing::warn!(
+                        operation,
+                        attempt,
+                        delay = ?delay,
+                        error = %error.inner(),
+                        "Transient error, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+}
+
+/// リトライ可否の判定付きエラー。
+#[derive(Debug)]
+pub enum RetryError {
+    /// 再試行で回復する可能性のあるエラー（ネットワークエラー、429、5xx など）
+    Transient {
+        /// 元のエラー
+        error: anyhow::Error,
+        /// サーバーが指定した再試行までの待機時間
+        retry_after: Option<Duration>,
+    },
+    /// 再試行しても回復しないエラー
+    Permanent(anyhow::Error),
+}
+
+impl RetryError {
+    /// 一時的なエラーを作成する。
+    pub fn transient(error: impl Into<anyhow::Error>) -> Self {
+        Self::Transient {
+            error: error.into(),
+            retry_after: None,
+        }
+    }
+
+    /// 恒久的なエラーを作成する。
+    pub fn permanent(error: impl Into<anyhow::Error>) -> Self {
+        Self::Permanent(error.into())
+    }
+
+    /// HTTP ステータスコードから一時的か恒久的かを判定してエラーを作成する。
+    pub fn from_status(
+        status: u16,
+        retry_after: Option<Duration>,
+        error: impl Into<anyhow::Error>,
+    ) -> Self {
+        if is_transient_status(status) {
+            Self::Transient {
+                error: error.into(),
+                retry_after,
+            }
+        } else {
+            Self::Permanent(error.into())
+        }
+    }
+
+    /// 一時的なエラーかどうかを返す。
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Transient { .. })
+    }
+
+    /// サーバーが指定した再試行までの待機時間を返す。
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::Transient { retry_after, .. } => *retry_after,
+            Self::Permanent(_) => None,
+        }
+    }
+
+    /// 元のエラーを参照する。
+    pub fn inner(&self) -> &anyhow::Error {
+        match self {
+            Self::Transient { error, .. } | Self::Permanent(error) => error,
+        }
+    }
+
+    /// 元のエラーを取り出す。
+    pub fn into_inner(self) -> anyhow::Error {
+        match self {
+            Self::Transient { error, .. } | Self::Permanent(error) => error,
+        }
+    }
+
+    /// 元のエラーに説明を付け加える。リトライ可否と待機時間はそのまま引き継ぐ。
+    pub fn context(self, context: String) -> Self {
+        match self {
+            Self::Transient { error, retry_after } => Self::Transient {
+                error: error.context(context),
+                retry_after,
+            },
+            Self::Permanent(error) => Self::Permanent(error.context(context)),
+        }
+    }
+}
+
+/// 再試行で回復する可能性のある HTTP ステータスコードかどうかを返す。
+pub fn is_transient_status(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+/// `Retry-After` ヘッダーの値（秒数）を待機時間に変換する。
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let seconds: f64 = value.trim().parse().ok()?;
+    (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_exponentially_until_max() {
+        let policy = RetryPolicy::new(5, Duration::from_millis(100), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_next_delay_respects_max_attempts_and_error_kind() {
+        let policy = RetryPolicy::new(2, Duration::from_millis(100), Duration::from_secs(1));
+        let transient = RetryError::transient(anyhow::anyhow!("boom"));
+        let permanent = RetryError::permanent(anyhow::anyhow!("boom"));
+
+        let rate_limited = RetryError::from_status(
+            429,
+            Some(Duration::from_secs(3)),
+            anyhow::anyhow!("rate limited"),
+        );
+
+        assert_eq!(
+            policy.next_delay(1, &transient),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(policy.next_delay(2, &transient), None);
+        assert_eq!(policy.next_delay(1, &permanent), None);
+        assert_eq!(
+            policy.next_delay(1, &rate_limited),
+            Some(Duration::from_secs(3))
+        );
+    }
+
+    #[test]
+    fn test_is_transient_status() {
+        assert!(is_transient_status(429));
+        assert!(is_transient_status(500));
+        assert!(is_transient_status(503));
+        assert!(!is_transient_status(400));
+        assert!(!is_transient_status(404));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("2"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_retry_after("0.5"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_retry_after("soon"), None);
+        assert_eq!(parse_retry_after("-1"), None);
+    }
+
+    #[tokio::test]
+    async fn test_run_retries_transient_errors() {
+        let policy = RetryPolicy::new(3, Duration::ZERO, Duration::ZERO);
+        let mut calls = 0;
+        let result = policy
+            .run("test", || {
+                calls += 1;
+                let current = calls;
+                async move {
+                    if current < 3 {
+                        Err(RetryError::transient(anyhow::anyhow!("transient")))
+                    } else {
+                        Ok(current)
+                    }
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(result, 3);
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_on_permanent_error() {
+        let policy = RetryPolicy::new(3, Duration::ZERO, Duration::ZERO);
+        let mut calls = 0;
+        let result: Result<()> = policy
+            .run("test", || {
+                calls += 1;
+                async { Err(RetryError::permanent(anyhow::anyhow!("permanent"))) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,95 @@
+//! 同期先のページを操作するためのインターフェース。
+
+use std::future::Future;
+
+use anyhow::Result;
+
+use crate::notion::{NotionClient, UploadData};
+
+/// メッセージのブロックを書き込む同期先。
+///
+/// ブロックは Notion API のブロックオブジェクト形式の JSON で受け渡す。
+pub trait DiarySink: Sync {
+    /// 複数のブロックを一括でページの末尾に追加し、作成されたブロック ID のリストを返す。
+    fn append_blocks(
+        &self,
+        page_id: &str,
+        children: Vec<serde_json::Value>,
+    ) -> impl Future<Output = Result<Vec<String>>> + Send;
+
+    /// 複数のブロックをページ内の指定したブロックの直後に挿入し、作成されたブロック ID のリストを返す。
+    fn insert_blocks_after(
+        &self,
+        page_id: &str,
+        after_block_id: &str,
+        children: Vec<serde_json::Value>,
+    ) -> impl Future<Output = Result<Vec<String>>> + Send;
+
+    /// テキストブロックの内容を置き換える。
+    fn update_text_block(
+        &self,
+        block_id: &str,
+        rich_text: Vec<serde_json::Value>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// ブロックを削除する。
+    fn delete_block(&self, block_id: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// ページ直下のブロックをページ上の順にすべて取得する。
+    fn list_blocks(
+        &self,
+        page_id: &str,
+    ) -> impl Future<Output = Result<Vec<serde_json::Value>>> + Send;
+
+    /// ファイルをアップロードし、ブロックから参照するためのファイルアップロード ID を返す。
+    fn upload_file(
+        &self,
+        filename: &str,
+        content_type: &str,
+        data: impl Into<UploadData> + Send,
+    ) -> impl Future<Output = Result<String>> + Send;
+}
+
+impl DiarySink for NotionClient {
+    async fn append_blocks(
+        &self,
+        page_id: &str,
+        children: Vec<serde_json::Value>,
+    ) -> Result<Vec<String>> {
+        NotionClient::append_blocks(self, page_id, children).await
+    }
+
+    async fn insert_blocks_after(
+        &self,
+        page_id: &str,
+        after_block_id: &str,
+        children: Vec<serde_json::Value>,
+    ) -> Result<Vec<String>> {
+        NotionClient::insert_blocks_after(self, page_id, after_block_id, children).await
+    }
+
+    async fn update_text_block(
+        &self,
+        block_id: &str,
+        rich_text: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        NotionClient::update_text_block(self, block_id, rich_text).await
+    }
+
+    async fn delete_block(&self, block_id: &str) -> Result<()> {
+        NotionClient::delete_block(self, block_id).await
+    }
+
+    async fn list_blocks(&self, page_id: &str) -> Result<Vec<serde_json::Value>> {
+        NotionClient::list_blocks(self, page_id).await
+    }
+
+    async fn upload_file(
+        &self,
+        filename: &str,
+        content_type: &str,
+        data: impl Into<UploadData> + Send,
+    ) -> Result<String> {
+        NotionClient::upload_file(self, filename, content_type, data).await
+    }
+}
@@ -0,0 +1,863 @@
+//! メモリ上に保存するストア。
+//!
+//! テストや、データベースを用意せずに日報を試すときに使う。再起動すると内容は消える。
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use crate::{history::SyncHistoryRecord, time_tracking::TimeEntry};
+
+use super::{
+    BlockKind, DiaryEntry, DiaryEntryStats, DiaryStorage, DiarySyncStatus, EmojiCount,
+    MessageBlock, ThreadBlockStats,
+};
+
+/// メモリ上に保存するストア。
+///
+/// クローンしたストアは同じ内容を共有する。
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    state: Arc<Mutex<State>>,
+}
+
+/// ストアの内容。PostgreSQL のテーブルに対応する。
+#[derive(Default)]
+struct State {
+    /// スレッド ID ごとの日報エントリ
+    entries: HashMap<u64, DiaryEntry>,
+    /// スレッド ID ごとの日報ページのサマリーのブロック ID
+    summary_blocks: HashMap<u64, String>,
+    /// メッセージとブロックの対応（メッセージが属するスレッド ID 付き）
+    blocks: Vec<(u64, MessageBlock)>,
+    /// メッセージ ID ごとの本文の描画結果のハッシュ
+    content_hashes: HashMap<u64, String>,
+    /// スレッド ID ごとの同期状態
+    sync_states: HashMap<u64, SyncState>,
+    /// 日報スレッドのメッセージに付いたリアクション
+    reactions: Vec<ReactionRecord>,
+    /// `!start` / `!stop` で記録した作業
+    time_entries: Vec<TimeEntry>,
+    /// メッセージの同期履歴（記録した順）
+    sync_history: Vec<SyncHistoryRecord>,
+}
+
+/// スレッドの同期状態。
+struct SyncState {
+    last_synced_at: Option<DateTime<Utc>>,
+    failure_count: i32,
+    last_failed_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    warning_count: i32,
+    last_warning: Option<String>,
+    attachment_bytes: u64,
+    sync_enabled: bool,
+}
+
+impl Default for SyncState {
+    fn default() -> Self {
+        Self {
+            last_synced_at: None,
+            failure_count: 0,
+            last_failed_at: None,
+            last_error: None,
+            warning_count: 0,
+            last_warning: None,
+            attachment_bytes: 0,
+            // 同期状態が未記録のスレッドは有効として扱う
+            sync_enabled: true,
+        }
+    }
+}
+
+/// 記録したリアクション 1 件。
+struct ReactionRecord {
+    message_id: u64,
+    user_id: u64,
+    emoji: String,
+    reacted_at: DateTime<Utc>,
+}
+
+impl MemoryStore {
+    /// 空のストアを作成する。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ストアの内容をロックする。
+    fn state(&self) -> MutexGuard<'_, State> {
+        // 他のスレッドがパニックしても内容は壊れていないため、そのまま使う
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl State {
+    /// スレッドのブロックを返す。
+    fn thread_blocks(&self, thread_id: u64) -> impl Iterator<Item = &MessageBlock> {
+        self.blocks
+            .iter()
+            .filter(move |(id, _)| *id == thread_id)
+            .map(|(_, block)| block)
+    }
+}
+
+impl DiaryStorage for MemoryStore {
+    async fn insert(&self, entry: &DiaryEntry) -> Result<()> {
+        let mut state = self.state();
+        let state = &mut *state;
+        match state.entries.get_mut(&entry.thread_id) {
+            // 作成日時とクローズした日時は最初に記録したものを残す
+            Some(existing) => {
+                // ページを作り直した場合、サマリーのブロックは新しいページに無い
+                if existing.page_id != entry.page_id {
+                    state.summary_blocks.remove(&entry.thread_id);
+                }
+                existing.forum_channel_id = entry.forum_channel_id;
+                existing.page_id = entry.page_id.clone();
+                existing.page_url = entry.page_url.clone();
+                existing.date = entry.date;
+            }
+            None => {
+                state.entries.insert(
+                    entry.thread_id,
+                    DiaryEntry {
+                        closed_at: None,
+                        ..entry.clone()
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_by_thread(&self, thread_id: u64) -> Result<Option<DiaryEntry>> {
+        Ok(self.state().entries.get(&thread_id).cloned())
+    }
+
+    async fn mark_closed(&self, thread_id: u64, closed_at: DateTime<Utc>) -> Result<()> {
+        if let Some(entry) = self.state().entries.get_mut(&thread_id) {
+            entry.closed_at.get_or_insert(closed_at);
+        }
+        Ok(())
+    }
+
+    async fn get_by_date(
+        &self,
+        forum_channel_id: u64,
+        date: DateTime<Utc>,
+    ) -> Result<Option<DiaryEntry>> {
+        Ok(self
+            .state()
+            .entries
+            .values()
+            .find(|entry| entry.forum_channel_id == forum_channel_id && entry.date == date)
+            .cloned())
+    }
+
+    async fn get_summary_block(&self, thread_id: u64) -> Result<Option<String>> {
+        Ok(self.state().summary_blocks.get(&thread_id).cloned())
+    }
+
+    async fn set_summary_block(&self, thread_id: u64, block_id: &str) -> Result<()> {
+        let mut state = self.state();
+        if state.entries.contains_key(&thread_id) {
+            state.summary_blocks.insert(thread_id, block_id.to_string());
+        }
+        Ok(())
+    }
+
+    async fn insert_message_block(&self, thread_id: u64, block: &MessageBlock) -> Result<()> {
+        let mut state = self.state();
+        if !state
+            .blocks
+            .iter()
+            .any(|(_, existing)| existing.block_id == block.block_id)
+        {
+            state.blocks.push((thread_id, block.clone()));
+        }
+        Ok(())
+    }
+
+    async fn get_blocks_by_message(&self, message_id: u64) -> Result<Vec<MessageBlock>> {
+        self.get_blocks_by_messages(&[message_id]).await
+    }
+
+    async fn delete_blocks_by_message(&self, message_id: u64) -> Result<()> {
+        self.state()
+            .blocks
+            .retain(|(_, block)| block.message_id != message_id);
+        Ok(())
+    }
+
+    async fn get_blocks_by_messages(&self, message_ids: &[u64]) -> Result<Vec<MessageBlock>> {
+        let mut blocks: Vec<MessageBlock> = self
+            .state()
+            .blocks
+            .iter()
+            .filter(|(_, block)| message_ids.contains(&block.message_id))
+            .map(|(_, block)| block.clone())
+            .collect();
+        blocks.sort_by_key(|block| (block.message_id, block.block_order));
+        Ok(blocks)
+    }
+
+    async fn get_blocks_by_thread(&self, thread_id: u64) -> Result<Vec<MessageBlock>> {
+        let mut blocks: Vec<MessageBlock> = self
+            .state()
+            .blocks
+            .iter()
+            .filter(|(block_thread_id, _)| *block_thread_id == thread_id)
+            .map(|(_, block)| block.clone())
+            .collect();
+        blocks.sort_by_key(|block| (block.message_id, block.block_order));
+        Ok(blocks)
+    }
+
+    async fn delete_blocks_by_messages(&self, message_ids: &[u64]) -> Result<()> {
+        let mut state = self.state();
+        state
+            .blocks
+            .retain(|(_, block)| !message_ids.contains(&block.message_id));
+        state
+            .content_hashes
+            .retain(|message_id, _| !message_ids.contains(message_id));
+        Ok(())
+    }
+
+    async fn delete_block(&self, block_id: &str) -> Result<()> {
+        self.state()
+            .blocks
+            .retain(|(_, block)| block.block_id != block_id);
+        Ok(())
+    }
+
+    async fn update_block_order(&self, block_id: &str, block_order: i32) -> Result<()> {
+        if let Some((_, block)) = self
+            .state()
+            .blocks
+            .iter_mut()
+            .find(|(_, block)| block.block_id == block_id)
+        {
+            block.block_order = block_order;
+        }
+        Ok(())
+    }
+
+    async fn get_content_hash(&self, message_id: u64) -> Result<Option<String>> {
+        Ok(self.state().content_hashes.get(&message_id).cloned())
+    }
+
+    async fn set_content_hash(&self, message_id: u64, content_hash: &str) -> Result<()> {
+        self.state()
+            .content_hashes
+            .insert(message_id, content_hash.to_string());
+        Ok(())
+    }
+
+    async fn delete_content_hash(&self, message_id: u64) -> Result<()> {
+        self.state().content_hashes.remove(&message_id);
+        Ok(())
+    }
+
+    async fn get_last_synced_message_id(&self, thread_id: u64) -> Result<Option<u64>> {
+        Ok(self
+            .state()
+            .thread_blocks(thread_id)
+            .map(|block| block.message_id)
+            .max())
+    }
+
+    async fn has_blocks_by_message(&self, message_id: u64) -> Result<bool> {
+        Ok(self
+            .state()
+            .blocks
+            .iter()
+            .any(|(_, block)| block.message_id == message_id))
+    }
+
+    async fn get_entries_in_date_range(
+        &self,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<DiaryEntry>> {
+        let mut entries: Vec<DiaryEntry> = self
+            .state()
+            .entries
+            .values()
+            .filter(|entry| entry.date >= start_date && entry.date <= end_date)
+            .cloned()
+            .collect();
+        entries.sort_by_key(|entry| entry.date);
+        Ok(entries)
+    }
+
+    async fn get_entry_stats_in_date_range(
+        &self,
+        forum_channel_id: u64,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<DiaryEntryStats>> {
+        let state = self.state();
+        let mut stats: Vec<DiaryEntryStats> = state
+            .entries
+            .values()
+            .filter(|entry| {
+                entry.forum_channel_id == forum_channel_id
+                    && entry.date >= start_date
+                    && entry.date <= end_date
+            })
+            .map(|entry| {
+                let blocks = state.thread_blocks(entry.thread_id);
+                let (message_ids, image_count) = blocks.fold(
+                    (HashSet::new(), 0),
+                    |(mut message_ids, image_count), block| {
+                        message_ids.insert(block.message_id);
+                        let image = i64::from(block.block_type == BlockKind::Image);
+                        (message_ids, image_count + image)
+                    },
+                );
+                DiaryEntryStats {
+                    page_url: entry.page_url.clone(),
+                    date: entry.date,
+                    message_count: message_ids.len() as i64,
+                    image_count,
+                }
+            })
+            .collect();
+        stats.sort_by_key(|stats| stats.date);
+        Ok(stats)
+    }
+
+    async fn get_thread_block_stats(&self, thread_id: u64) -> Result<ThreadBlockStats> {
+        let state = self.state();
+        let mut message_ids = HashSet::new();
+        let mut image_count = 0;
+        let mut urls = HashSet::new();
+        for block in state.thread_blocks(thread_id) {
+            message_ids.insert(block.message_id);
+            match block.block_type {
+                BlockKind::Image => image_count += 1,
+                BlockKind::Bookmark | BlockKind::Embed => {
+                    urls.extend(block.source_url.as_deref());
+                }
+                _ => {}
+            }
+        }
+        Ok(ThreadBlockStats {
+            message_count: message_ids.len() as i64,
+            image_count,
+            link_count: urls.len() as i64,
+        })
+    }
+
+    async fn get_latest_entry(&self, forum_channel_id: u64) -> Result<Option<DiaryEntry>> {
+        Ok(self
+            .state()
+            .entries
+            .values()
+            .filter(|entry| entry.forum_channel_id == forum_channel_id)
+            .max_by_key(|entry| entry.date)
+            .cloned())
+    }
+
+    async fn assign_forum_channel(&self, forum_channel_id: u64) -> Result<u64> {
+        let mut assigned = 0;
+        for entry in self.state().entries.values_mut() {
+            if entry.forum_channel_id == 0 {
+                entry.forum_channel_id = forum_channel_id;
+                assigned += 1;
+            }
+        }
+        Ok(assigned)
+    }
+
+    async fn record_sync_success(
+        &self,
+        thread_id: u64,
+        synced_at: DateTime<Utc>,
+        warnings: &[String],
+    ) -> Result<()> {
+        let mut state = self.state();
+        let sync_state = state.sync_states.entry(thread_id).or_default();
+        sync_state.last_synced_at = Some(synced_at);
+        sync_state.warning_count = sync_state
+            .warning_count
+            .saturating_add(i32::try_from(warnings.len()).unwrap_or(i32::MAX));
+        if let Some(warning) = warnings.last() {
+            sync_state.last_warning = Some(warning.clone());
+        }
+        Ok(())
+    }
+
+    async fn record_sync_failure(
+        &self,
+        thread_id: u64,
+        failed_at: DateTime<Utc>,
+        error: &str,
+    ) -> Result<()> {
+        let mut state = self.state();
+        let sync_state = state.sync_states.entry(thread_id).or_default();
+        sync_state.failure_count = sync_state.failure_count.saturating_add(1);
+        sync_state.last_failed_at = Some(failed_at);
+        sync_state.last_error = Some(error.to_string());
+        Ok(())
+    }
+
+    async fn get_attachment_bytes(&self, thread_id: u64) -> Result<u64> {
+        Ok(self
+            .state()
+            .sync_states
+            .get(&thread_id)
+            .map_or(0, |sync_state| sync_state.attachment_bytes))
+    }
+
+    async fn add_attachment_bytes(&self, thread_id: u64, bytes: u64) -> Result<()> {
+        let mut state = self.state();
+        let sync_state = state.sync_states.entry(thread_id).or_default();
+        sync_state.attachment_bytes = sync_state.attachment_bytes.saturating_add(bytes);
+        Ok(())
+    }
+
+    async fn set_sync_enabled(&self, thread_id: u64, enabled: bool) -> Result<()> {
+        self.state()
+            .sync_states
+            .entry(thread_id)
+            .or_default()
+            .sync_enabled = enabled;
+        Ok(())
+    }
+
+    async fn is_sync_enabled(&self, thread_id: u64) -> Result<bool> {
+        Ok(self
+            .state()
+            .sync_states
+            .get(&thread_id)
+            .is_none_or(|sync_state| sync_state.sync_enabled))
+    }
+
+    async fn get_sync_status(&self, thread_id: u64) -> Result<DiarySyncStatus> {
+        let state = self.state();
+        let message_ids: HashSet<u64> = state
+            .thread_blocks(thread_id)
+            .map(|block| block.message_id)
+            .collect();
+        let block_count = state.thread_blocks(thread_id).count() as i64;
+        let default_state = SyncState::default();
+        let sync_state = state.sync_states.get(&thread_id).unwrap_or(&default_state);
+        Ok(DiarySyncStatus {
+            message_count: message_ids.len() as i64,
+            block_count,
+            last_synced_at: sync_state.last_synced_at,
+            failure_count: sync_state.failure_count,
+            last_failed_at: sync_state.last_failed_at,
+            last_error: sync_state.last_error.clone(),
+            warning_count: sync_state.warning_count,
+            last_warning: sync_state.last_warning.clone(),
+            sync_enabled: sync_state.sync_enabled,
+        })
+    }
+
+    async fn record_reaction(
+        &self,
+        message_id: u64,
+        user_id: u64,
+        emoji: &str,
+        _thread_id: u64,
+        reacted_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let mut state = self.state();
+        if !state.reactions.iter().any(|reaction| {
+            reaction.message_id == message_id
+                && reaction.user_id == user_id
+                && reaction.emoji == emoji
+        }) {
+            state.reactions.push(ReactionRecord {
+                message_id,
+                user_id,
+                emoji: emoji.to_string(),
+                reacted_at,
+            });
+        }
+        Ok(())
+    }
+
+    async fn delete_reaction(&self, message_id: u64, user_id: u64, emoji: &str) -> Result<()> {
+        self.state().reactions.retain(|reaction| {
+            !(reaction.message_id == message_id
+                && reaction.user_id == user_id
+                && reaction.emoji == emoji)
+        });
+        Ok(())
+    }
+
+    async fn delete_reactions_by_message(
+        &self,
+        message_id: u64,
+        emoji: Option<&str>,
+    ) -> Result<()> {
+        self.state().reactions.retain(|reaction| {
+            reaction.message_id != message_id || emoji.is_some_and(|emoji| reaction.emoji != emoji)
+        });
+        Ok(())
+    }
+
+    async fn get_emoji_counts(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<EmojiCount>> {
+        let state = self.state();
+        let mut users_by_emoji: HashMap<&str, (i64, HashSet<u64>)> = HashMap::new();
+        for reaction in state
+            .reactions
+            .iter()
+            .filter(|reaction| since.is_none_or(|since| reaction.reacted_at >= since))
+        {
+            let (count, users) = users_by_emoji.entry(&reaction.emoji).or_default();
+            *count += 1;
+            users.insert(reaction.user_id);
+        }
+
+        let mut counts: Vec<EmojiCount> = users_by_emoji
+            .into_iter()
+            .map(|(emoji, (count, users))| EmojiCount {
+                emoji: emoji.to_string(),
+                count,
+                user_count: users.len() as i64,
+            })
+            .collect();
+        counts.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(b.user_count.cmp(&a.user_count))
+                .then(a.emoji.cmp(&b.emoji))
+        });
+        counts.truncate(usize::try_from(limit).unwrap_or(0));
+        Ok(counts)
+    }
+
+    async fn start_time_entry(
+        &self,
+        thread_id: u64,
+        message_id: u64,
+        task: &str,
+        started_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let mut state = self.state();
+        if !state
+            .time_entries
+            .iter()
+            .any(|entry| entry.message_id == message_id)
+        {
+            state.time_entries.push(TimeEntry {
+                thread_id,
+                message_id,
+                task: task.to_string(),
+                started_at,
+                stopped_at: None,
+                block_id: None,
+            });
+        }
+        Ok(())
+    }
+
+    async fn stop_time_entry(
+        &self,
+        thread_id: u64,
+        stopped_at: DateTime<Utc>,
+    ) -> Result<Option<TimeEntry>> {
+        Ok(self
+            .state()
+            .time_entries
+            .iter_mut()
+            .filter(|entry| entry.thread_id == thread_id && entry.stopped_at.is_none())
+            .max_by_key(|entry| entry.started_at)
+            .map(|entry| {
+                entry.stopped_at = Some(stopped_at);
+                entry.clone()
+            }))
+    }
+
+    async fn set_time_entry_block(&self, message_id: u64, block_id: &str) -> Result<()> {
+        if let Some(entry) = self
+            .state()
+            .time_entries
+            .iter_mut()
+            .find(|entry| entry.message_id == message_id)
+        {
+            entry.block_id = Some(block_id.to_string());
+        }
+        Ok(())
+    }
+
+    async fn get_time_entries(&self, thread_id: u64) -> Result<Vec<TimeEntry>> {
+        let mut entries: Vec<TimeEntry> = self
+            .state()
+            .time_entries
+            .iter()
+            .filter(|entry| entry.thread_id == thread_id)
+            .cloned()
+            .collect();
+        entries.sort_by_key(|entry| entry.started_at);
+        Ok(entries)
+    }
+
+    async fn record_sync_history(&self, record: &SyncHistoryRecord) -> Result<()> {
+        self.state().sync_history.push(record.clone());
+        Ok(())
+    }
+
+    async fn get_sync_history(
+        &self,
+        thread_id: Option<u64>,
+        limit: i64,
+    ) -> Result<Vec<SyncHistoryRecord>> {
+        // 同じ日時の履歴は後から記録したものを先にする
+        let mut history: Vec<SyncHistoryRecord> = self
+            .state()
+            .sync_history
+            .iter()
+            .rev()
+            .filter(|record| thread_id.is_none_or(|thread_id| record.thread_id == thread_id))
+            .cloned()
+            .collect();
+        history.sort_by_key(|record| std::cmp::Reverse(record.recorded_at));
+        history.truncate(usize::try_from(limit).unwrap_or(0));
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{SyncAction, SyncHistoryStatus};
+
+    fn entry(thread_id: u64, forum_channel_id: u64, day: u32) -> DiaryEntry {
+        let date = DateTime::parse_from_rfc3339(&format!("2025-02-{:02}T00:00:00Z", day))
+            .unwrap()
+            .to_utc();
+        DiaryEntry {
+            thread_id,
+            forum_channel_id,
+            page_id: format!("page-{}", thread_id),
+            page_url: format!("https://www.notion.so/page-{}", thread_id),
+            date,
+            created_at: date,
+            closed_at: None,
+        }
+    }
+
+    fn block(message_id: u64, block_id: &str, kind: BlockKind, order: i32) -> MessageBlock {
+        MessageBlock {
+            message_id,
+            block_id: block_id.to_string(),
+            block_type: kind,
+            block_order: order,
+            source_url: None,
+            page_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entries() {
+        let store = MemoryStore::new();
+        store.insert(&entry(1, 10, 1)).await.unwrap();
+        store.insert(&entry(2, 10, 3)).await.unwrap();
+        store.insert(&entry(3, 0, 2)).await.unwrap();
+
+        let closed_at = entry(1, 10, 2).date;
+        store.mark_closed(1, closed_at).await.unwrap();
+        store.mark_closed(1, entry(1, 10, 5).date).await.unwrap();
+        // 作り直しても、クローズした日時は残る
+        store.insert(&entry(1, 10, 1)).await.unwrap();
+        assert_eq!(
+            store.get_by_thread(1).await.unwrap().unwrap().closed_at,
+            Some(closed_at)
+        );
+
+        assert_eq!(store.assign_forum_channel(10).await.unwrap(), 1);
+        assert_eq!(
+            store.get_latest_entry(10).await.unwrap().unwrap().thread_id,
+            2
+        );
+        let in_range: Vec<u64> = store
+            .get_entries_in_date_range(entry(0, 0, 1).date, entry(0, 0, 2).date)
+            .await
+            .unwrap()
+            .iter()
+            .map(|entry| entry.thread_id)
+            .collect();
+        assert_eq!(in_range, vec![1, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_summary_block() {
+        let store = MemoryStore::new();
+        store.set_summary_block(1, "summary").await.unwrap();
+        // エントリが無いスレッドには記録しない
+        assert_eq!(store.get_summary_block(1).await.unwrap(), None);
+
+        store.insert(&entry(1, 10, 1)).await.unwrap();
+        store.set_summary_block(1, "summary").await.unwrap();
+        store.insert(&entry(1, 10, 1)).await.unwrap();
+        assert_eq!(
+            store.get_summary_block(1).await.unwrap().as_deref(),
+            Some("summary")
+        );
+
+        // ページを作り直すと、古いページのサマリーは使わない
+        store
+            .insert(&DiaryEntry {
+                page_id: "recreated".to_string(),
+                ..entry(1, 10, 1)
+            })
+            .await
+            .unwrap();
+        assert_eq!(store.get_summary_block(1).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_message_blocks() {
+        let store = MemoryStore::new();
+        store.insert(&entry(1, 10, 1)).await.unwrap();
+        store
+            .insert_message_block(1, &block(100, "b", BlockKind::Image, 1))
+            .await
+            .unwrap();
+        store
+            .insert_message_block(1, &block(100, "a", BlockKind::Text, 0))
+            .await
+            .unwrap();
+        store
+            .insert_message_block(1, &block(200, "c", BlockKind::Text, 0))
+            .await
+            .unwrap();
+        store.set_content_hash(100, "hash").await.unwrap();
+
+        let block_ids: Vec<String> = store
+            .get_blocks_by_message(100)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|block| block.block_id)
+            .collect();
+        assert_eq!(block_ids, vec!["a", "b"]);
+        assert_eq!(store.get_blocks_by_thread(1).await.unwrap().len(), 3);
+        assert!(store.get_blocks_by_thread(2).await.unwrap().is_empty());
+        assert_eq!(
+            store.get_last_synced_message_id(1).await.unwrap(),
+            Some(200)
+        );
+
+        let status = store.get_sync_status(1).await.unwrap();
+        assert_eq!((status.message_count, status.block_count), (2, 3));
+        assert!(status.sync_enabled);
+
+        let stats = store
+            .get_entry_stats_in_date_range(10, entry(0, 0, 1).date, entry(0, 0, 1).date)
+            .await
+            .unwrap();
+        assert_eq!((stats[0].message_count, stats[0].image_count), (2, 1));
+
+        store.delete_blocks_by_messages(&[100]).await.unwrap();
+        assert!(!store.has_blocks_by_message(100).await.unwrap());
+        assert_eq!(store.get_content_hash(100).await.unwrap(), None);
+        assert!(store.has_blocks_by_message(200).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_emoji_counts() {
+        let store = MemoryStore::new();
+        let now = Utc::now();
+        store.record_reaction(1, 10, "👍", 5, now).await.unwrap();
+        store.record_reaction(1, 10, "👍", 5, now).await.unwrap();
+        store.record_reaction(2, 11, "👍", 5, now).await.unwrap();
+        store.record_reaction(2, 11, "🎉", 5, now).await.unwrap();
+
+        let counts = store.get_emoji_counts(None, 10).await.unwrap();
+        assert_eq!(
+            counts,
+            vec![
+                EmojiCount {
+                    emoji: "👍".to_string(),
+                    count: 2,
+                    user_count: 2,
+                },
+                EmojiCount {
+                    emoji: "🎉".to_string(),
+                    count: 1,
+                    user_count: 1,
+                },
+            ]
+        );
+
+        store
+            .delete_reactions_by_message(2, Some("🎉"))
+            .await
+            .unwrap();
+        assert_eq!(store.get_emoji_counts(None, 10).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_time_entries() {
+        let store = MemoryStore::new();
+        let start = entry(0, 0, 1).date;
+        assert_eq!(store.stop_time_entry(1, start).await.unwrap(), None);
+
+        store.start_time_entry(1, 10, "docs", start).await.unwrap();
+        store
+            .start_time_entry(1, 11, "review", start + chrono::TimeDelta::minutes(5))
+            .await
+            .unwrap();
+        let stopped = store
+            .stop_time_entry(1, start + chrono::TimeDelta::minutes(30))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stopped.task, "review");
+
+        store.set_time_entry_block(11, "block").await.unwrap();
+        let entries = store.get_time_entries(1).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].stopped_at, None);
+        assert_eq!(entries[1].block_id.as_deref(), Some("block"));
+    }
+
+    #[tokio::test]
+    async fn test_sync_history() {
+        let store = MemoryStore::new();
+        let now = Utc::now();
+        for (thread_id, message_id, action) in [
+            (1, 10, SyncAction::Create),
+            (2, 20, SyncAction::Create),
+            (1, 10, SyncAction::Delete),
+        ] {
+            store
+                .record_sync_history(&SyncHistoryRecord {
+                    thread_id,
+                    message_id,
+                    action,
+                    status: SyncHistoryStatus::Success,
+                    detail: None,
+                    recorded_at: now,
+                })
+                .await
+                .unwrap();
+        }
+
+        let history = store.get_sync_history(None, 2).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].action, SyncAction::Delete);
+        assert_eq!(history[1].thread_id, 2);
+
+        let history = store.get_sync_history(Some(1), 10).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history.iter().all(|record| record.thread_id == 1));
+    }
+}
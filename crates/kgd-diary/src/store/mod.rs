@@ -0,0 +1,657 @@
+//! スレッドと Notion ページの紐付け情報を永続化するストア。
+//!
+//! 保存先ごとの実装は [`DiaryStorage`] を実装する。通常は PostgreSQL に保存し（[`PostgresStore`]）、
+//! テストやデータベースを用意せずに試す場合はメモリ上に保存する（[`MemoryStore`]）。
+
+mod memory;
+mod postgres;
+
+use std::future::Future;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::{block::BlockKind, history::SyncHistoryRecord, time_tracking::TimeEntry};
+
+pub use memory::MemoryStore;
+pub use postgres::PostgresStore;
+
+/// インメモリのストアを使う場合に指定するデータベース URL のスキーム。
+const MEMORY_URL_SCHEME: &str = "memory:";
+
+/// メッセージとブロックの対応情報。
+#[derive(Debug, Clone, FromRow)]
+pub struct MessageBlock {
+    /// Discord メッセージ ID
+    #[sqlx(try_from = "i64")]
+    pub message_id: u64,
+    /// Notion ブロック ID
+    pub block_id: String,
+    /// ブロックの種類
+    #[sqlx(try_from = "String")]
+    pub block_type: BlockKind,
+    /// ブロックの順序
+    pub block_order: i32,
+    /// ブックマーク・埋め込みブロックの元になった URL
+    pub source_url: Option<String>,
+    /// ブロックを追加した Notion ページの ID（記録を始める前に追加したブロックは None）
+    pub page_id: Option<String>,
+}
+
+/// 日報エントリの情報。
+#[derive(Debug, Clone, FromRow)]
+pub struct DiaryEntry {
+    /// Discord スレッド ID
+    #[sqlx(try_from = "i64")]
+    pub thread_id: u64,
+    /// スレッドが属するフォーラムチャンネル ID
+    #[sqlx(try_from = "i64")]
+    pub forum_channel_id: u64,
+    /// Notion ページ ID
+    pub page_id: String,
+    /// Notion ページ URL
+    pub page_url: String,
+    /// 日付
+    pub date: DateTime<Utc>,
+    /// 作成日時
+    pub created_at: DateTime<Utc>,
+    /// スレッドをクローズした日時（クローズしていない場合は None）
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+impl DiaryEntry {
+    /// スレッドがクローズ済みかどうかを返す。
+    pub fn is_closed(&self) -> bool {
+        self.closed_at.is_some()
+    }
+}
+
+/// 期間レポートに載せる日報エントリごとの集計。
+#[derive(Debug, Clone, FromRow)]
+pub struct DiaryEntryStats {
+    /// Notion ページ URL
+    pub page_url: String,
+    /// 日付
+    pub date: DateTime<Utc>,
+    /// 同期済みのメッセージ数
+    pub message_count: i64,
+    /// 同期済みの画像ブロック数
+    pub image_count: i64,
+}
+
+/// スレッドで同期したブロックの集計。
+#[derive(Debug, Clone, FromRow)]
+pub struct ThreadBlockStats {
+    /// 同期済みのメッセージ数
+    pub message_count: i64,
+    /// 同期済みの画像ブロック数
+    pub image_count: i64,
+    /// 同期済みのブックマーク・埋め込みブロックの URL の数
+    pub link_count: i64,
+}
+
+/// スレッドごとの同期状態。
+#[derive(Debug, Clone, FromRow)]
+pub struct DiarySyncStatus {
+    /// 同期済みのメッセージ数
+    pub message_count: i64,
+    /// 同期済みのブロック数
+    pub block_count: i64,
+    /// 最後に同期が成功した日時
+    pub last_synced_at: Option<DateTime<Utc>>,
+    /// 同期に失敗した回数
+    pub failure_count: i32,
+    /// 最後に同期が失敗した日時
+    pub last_failed_at: Option<DateTime<Utc>>,
+    /// 最後に発生したエラーの内容
+    pub last_error: Option<String>,
+    /// 警告付きで同期した項目の件数
+    pub warning_count: i32,
+    /// 最後に発生した警告の内容
+    pub last_warning: Option<String>,
+    /// 同期が有効かどうか（一時停止中は false）
+    pub sync_enabled: bool,
+}
+
+/// 絵文字ごとのリアクションの集計。
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct EmojiCount {
+    /// 絵文字（カスタム絵文字は `<:name:id>` の形式）
+    pub emoji: String,
+    /// リアクションが付いた回数
+    pub count: i64,
+    /// リアクションを付けたユーザーの数
+    pub user_count: i64,
+}
+
+/// 日報のエントリ・メッセージとブロックの対応・同期状態などの保存先。
+pub trait DiaryStorage: Sync {
+    /// エントリを追加する。
+    fn insert(&self, entry: &DiaryEntry) -> impl Future<Output = Result<()>> + Send;
+
+    /// スレッド ID からエントリを取得する。
+    fn get_by_thread(
+        &self,
+        thread_id: u64,
+    ) -> impl Future<Output = Result<Option<DiaryEntry>>> + Send;
+
+    /// スレッドをクローズした日時を記録する（クローズ済みの場合は最初の日時のままにする）。
+    fn mark_closed(
+        &self,
+        thread_id: u64,
+        closed_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// フォーラムチャンネルと日付からエントリを取得する。
+    ///
+    /// 指定された日時が含まれる日（その日の00:00:00から翌日の00:00:00まで）のエントリを検索する。
+    fn get_by_date(
+        &self,
+        forum_channel_id: u64,
+        date: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Option<DiaryEntry>>> + Send;
+
+    /// 日報ページのサマリーの callout ブロック ID を取得する。
+    fn get_summary_block(
+        &self,
+        thread_id: u64,
+    ) -> impl Future<Output = Result<Option<String>>> + Send;
+
+    /// 日報ページのサマリーの callout ブロック ID を記録する。
+    fn set_summary_block(
+        &self,
+        thread_id: u64,
+        block_id: &str,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// メッセージとブロックの対応を、メッセージが属するスレッド ID と共に保存する。
+    fn insert_message_block(
+        &self,
+        thread_id: u64,
+        block: &MessageBlock,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// メッセージ ID から対応するブロック一覧を取得する。
+    fn get_blocks_by_message(
+        &self,
+        message_id: u64,
+    ) -> impl Future<Output = Result<Vec<MessageBlock>>> + Send;
+
+    /// メッセージ ID に対応するブロックをすべて削除する。
+    fn delete_blocks_by_message(&self, message_id: u64) -> impl Future<Output = Result<()>> + Send;
+
+    /// 複数のメッセージ ID に対応するブロック一覧をまとめて取得する。
+    fn get_blocks_by_messages(
+        &self,
+        message_ids: &[u64],
+    ) -> impl Future<Output = Result<Vec<MessageBlock>>> + Send;
+
+    /// スレッドで同期したすべてのメッセージのブロックを取得する。
+    fn get_blocks_by_thread(
+        &self,
+        thread_id: u64,
+    ) -> impl Future<Output = Result<Vec<MessageBlock>>> + Send;
+
+    /// 複数のメッセージ ID に対応するブロックと本文のハッシュをまとめて削除する。
+    fn delete_blocks_by_messages(
+        &self,
+        message_ids: &[u64],
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// ブロックを 1 件削除する。
+    fn delete_block(&self, block_id: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// ブロックの順序を更新する。
+    fn update_block_order(
+        &self,
+        block_id: &str,
+        block_order: i32,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// メッセージ本文の描画結果のハッシュを取得する。
+    fn get_content_hash(
+        &self,
+        message_id: u64,
+    ) -> impl Future<Output = Result<Option<String>>> + Send;
+
+    /// メッセージ本文の描画結果のハッシュを保存する。
+    fn set_content_hash(
+        &self,
+        message_id: u64,
+        content_hash: &str,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// メッセージ本文の描画結果のハッシュを削除する。
+    fn delete_content_hash(&self, message_id: u64) -> impl Future<Output = Result<()>> + Send;
+
+    /// スレッドで同期済みのメッセージのうち、最新のメッセージ ID を取得する。
+    fn get_last_synced_message_id(
+        &self,
+        thread_id: u64,
+    ) -> impl Future<Output = Result<Option<u64>>> + Send;
+
+    /// Message ID に紐づくブロックが存在するかどうかを返す。
+    fn has_blocks_by_message(&self, message_id: u64) -> impl Future<Output = Result<bool>> + Send;
+
+    /// 指定した日付範囲に含まれる日報エントリを古い順で取得する。
+    fn get_entries_in_date_range(
+        &self,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Vec<DiaryEntry>>> + Send;
+
+    /// フォーラムチャンネルの指定した日付範囲に含まれる日報エントリを、同期済みメッセージ数と画像数の集計付きで古い順に取得する。
+    fn get_entry_stats_in_date_range(
+        &self,
+        forum_channel_id: u64,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Vec<DiaryEntryStats>>> + Send;
+
+    /// スレッドで同期したメッセージ・画像・リンクの数を集計する。
+    fn get_thread_block_stats(
+        &self,
+        thread_id: u64,
+    ) -> impl Future<Output = Result<ThreadBlockStats>> + Send;
+
+    /// フォーラムチャンネルの最新の日報エントリを取得する。
+    fn get_latest_entry(
+        &self,
+        forum_channel_id: u64,
+    ) -> impl Future<Output = Result<Option<DiaryEntry>>> + Send;
+
+    /// フォーラムチャンネルが記録されていない（複数の日報に対応する前の）エントリに、フォーラムチャンネル ID を割り当てる。
+    ///
+    /// # Returns
+    /// 割り当てたエントリの件数
+    fn assign_forum_channel(
+        &self,
+        forum_channel_id: u64,
+    ) -> impl Future<Output = Result<u64>> + Send;
+
+    /// スレッドの同期成功を記録する。
+    ///
+    /// `warnings` が空でない場合は警告件数を加算し、最後の警告を記録する。
+    fn record_sync_success(
+        &self,
+        thread_id: u64,
+        synced_at: DateTime<Utc>,
+        warnings: &[String],
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// スレッドの同期失敗を記録し、失敗回数を加算する。
+    fn record_sync_failure(
+        &self,
+        thread_id: u64,
+        failed_at: DateTime<Utc>,
+        error: &str,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// スレッドで同期済みの添付ファイルの合計バイト数を取得する。
+    fn get_attachment_bytes(&self, thread_id: u64) -> impl Future<Output = Result<u64>> + Send;
+
+    /// スレッドで同期済みの添付ファイルの合計バイト数を加算する。
+    fn add_attachment_bytes(
+        &self,
+        thread_id: u64,
+        bytes: u64,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// スレッドの同期の有効/無効を設定する。
+    fn set_sync_enabled(
+        &self,
+        thread_id: u64,
+        enabled: bool,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// スレッドの同期が有効かどうかを取得する。
+    ///
+    /// 同期状態が未記録のスレッドは有効として扱う。
+    fn is_sync_enabled(&self, thread_id: u64) -> impl Future<Output = Result<bool>> + Send;
+
+    /// スレッドの同期状態を取得する。
+    ///
+    /// 同期状態が未記録のスレッドでも、件数 0 の状態を返す。
+    fn get_sync_status(
+        &self,
+        thread_id: u64,
+    ) -> impl Future<Output = Result<DiarySyncStatus>> + Send;
+
+    /// 日報スレッドのメッセージに付いたリアクションを記録する（記録済みの場合は何もしない）。
+    fn record_reaction(
+        &self,
+        message_id: u64,
+        user_id: u64,
+        emoji: &str,
+        thread_id: u64,
+        reacted_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// ユーザーが外したリアクションの記録を削除する。
+    fn delete_reaction(
+        &self,
+        message_id: u64,
+        user_id: u64,
+        emoji: &str,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// メッセージに付いたリアクションの記録を削除する。`emoji` を指定した場合はその絵文字だけを削除する。
+    fn delete_reactions_by_message(
+        &self,
+        message_id: u64,
+        emoji: Option<&str>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// 指定した日時以降（`None` の場合は全期間）のリアクションを絵文字ごとに集計し、多い順に取得する。
+    fn get_emoji_counts(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> impl Future<Output = Result<Vec<EmojiCount>>> + Send;
+
+    /// 作業の開始を記録する（記録済みの場合は何もしない）。
+    fn start_time_entry(
+        &self,
+        thread_id: u64,
+        message_id: u64,
+        task: &str,
+        started_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// スレッドで作業中の作業（最後に開始したもの）の終了を記録し、終了した作業を返す。
+    ///
+    /// 作業中の作業が無い場合は None を返す。
+    fn stop_time_entry(
+        &self,
+        thread_id: u64,
+        stopped_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Option<TimeEntry>>> + Send;
+
+    /// 作業を書き込んだ to_do ブロックの ID を記録する。
+    fn set_time_entry_block(
+        &self,
+        message_id: u64,
+        block_id: &str,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// スレッドで記録した作業を開始日時の古い順に取得する。
+    fn get_time_entries(
+        &self,
+        thread_id: u64,
+    ) -> impl Future<Output = Result<Vec<TimeEntry>>> + Send;
+
+    /// メッセージの同期履歴を 1 件記録する。
+    fn record_sync_history(
+        &self,
+        record: &SyncHistoryRecord,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// 同期履歴を新しい順に取得する。`thread_id` を指定した場合はそのスレッドだけを取得する。
+    fn get_sync_history(
+        &self,
+        thread_id: Option<u64>,
+        limit: i64,
+    ) -> impl Future<Output = Result<Vec<SyncHistoryRecord>>> + Send;
+}
+
+/// 設定で選んだ保存先のストア。
+///
+/// データベース URL に応じて [`PostgresStore`] か [`MemoryStore`] を使う。
+#[derive(Clone)]
+pub enum DiaryStore {
+    /// PostgreSQL に保存する
+    Postgres(PostgresStore),
+    /// メモリ上に保存する（再起動すると消える）
+    Memory(MemoryStore),
+}
+
+impl DiaryStore {
+    /// データベース URL に応じたストアを作成する。
+    ///
+    /// `memory:` の場合はメモリ上に保存し、それ以外は PostgreSQL に接続してマイグレーションを実行する。
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        if database_url.trim() == MEMORY_URL_SCHEME {
+            tracing::warn!("Using in-memory diary store, data will be lost on restart");
+            return Ok(Self::Memory(MemoryStore::new()));
+        }
+        Ok(Self::Postgres(PostgresStore::connect(database_url).await?))
+    }
+}
+
+/// 保存先のストアのメソッドを呼び出す。
+macro_rules! dispatch {
+    ($self:ident, $method:ident($($arg:expr),*)) => {
+        match $self {
+            DiaryStore::Postgres(store) => store.$method($($arg),*).await,
+            DiaryStore::Memory(store) => store.$method($($arg),*).await,
+        }
+    };
+}
+
+impl DiaryStorage for DiaryStore {
+    async fn insert(&self, entry: &DiaryEntry) -> Result<()> {
+        dispatch!(self, insert(entry))
+    }
+
+    async fn get_by_thread(&self, thread_id: u64) -> Result<Option<DiaryEntry>> {
+        dispatch!(self, get_by_thread(thread_id))
+    }
+
+    async fn mark_closed(&self, thread_id: u64, closed_at: DateTime<Utc>) -> Result<()> {
+        dispatch!(self, mark_closed(thread_id, closed_at))
+    }
+
+    async fn get_by_date(
+        &self,
+        forum_channel_id: u64,
+        date: DateTime<Utc>,
+    ) -> Result<Option<DiaryEntry>> {
+        dispatch!(self, get_by_date(forum_channel_id, date))
+    }
+
+    async fn get_summary_block(&self, thread_id: u64) -> Result<Option<String>> {
+        dispatch!(self, get_summary_block(thread_id))
+    }
+
+    async fn set_summary_block(&self, thread_id: u64, block_id: &str) -> Result<()> {
+        dispatch!(self, set_summary_block(thread_id, block_id))
+    }
+
+    async fn insert_message_block(&self, thread_id: u64, block: &MessageBlock) -> Result<()> {
+        dispatch!(self, insert_message_block(thread_id, block))
+    }
+
+    async fn get_blocks_by_message(&self, message_id: u64) -> Result<Vec<MessageBlock>> {
+        dispatch!(self, get_blocks_by_message(message_id))
+    }
+
+    async fn delete_blocks_by_message(&self, message_id: u64) -> Result<()> {
+        dispatch!(self, delete_blocks_by_message(message_id))
+    }
+
+    async fn get_blocks_by_messages(&self, message_ids: &[u64]) -> Result<Vec<MessageBlock>> {
+        dispatch!(self, get_blocks_by_messages(message_ids))
+    }
+
+    async fn get_blocks_by_thread(&self, thread_id: u64) -> Result<Vec<MessageBlock>> {
+        dispatch!(self, get_blocks_by_thread(thread_id))
+    }
+
+    async fn delete_blocks_by_messages(&self, message_ids: &[u64]) -> Result<()> {
+        dispatch!(self, delete_blocks_by_messages(message_ids))
+    }
+
+    async fn delete_block(&self, block_id: &str) -> Result<()> {
+        dispatch!(self, delete_block(block_id))
+    }
+
+    async fn update_block_order(&self, block_id: &str, block_order: i32) -> Result<()> {
+        dispatch!(self, update_block_order(block_id, block_order))
+    }
+
+    async fn get_content_hash(&self, message_id: u64) -> Result<Option<String>> {
+        dispatch!(self, get_content_hash(message_id))
+    }
+
+    async fn set_content_hash(&self, message_id: u64, content_hash: &str) -> Result<()> {
+        dispatch!(self, set_content_hash(message_id, content_hash))
+    }
+
+    async fn delete_content_hash(&self, message_id: u64) -> Result<()> {
+        dispatch!(self, delete_content_hash(message_id))
+    }
+
+    async fn get_last_synced_message_id(&self, thread_id: u64) -> Result<Option<u64>> {
+        dispatch!(self, get_last_synced_message_id(thread_id))
+    }
+
+    async fn has_blocks_by_message(&self, message_id: u64) -> Result<bool> {
+        dispatch!(self, has_blocks_by_message(message_id))
+    }
+
+    async fn get_entries_in_date_range(
+        &self,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<DiaryEntry>> {
+        dispatch!(self, get_entries_in_date_range(start_date, end_date))
+    }
+
+    async fn get_entry_stats_in_date_range(
+        &self,
+        forum_channel_id: u64,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<DiaryEntryStats>> {
+        dispatch!(
+            self,
+            get_entry_stats_in_date_range(forum_channel_id, start_date, end_date)
+        )
+    }
+
+    async fn get_thread_block_stats(&self, thread_id: u64) -> Result<ThreadBlockStats> {
+        dispatch!(self, get_thread_block_stats(thread_id))
+    }
+
+    async fn get_latest_entry(&self, forum_channel_id: u64) -> Result<Option<DiaryEntry>> {
+        dispatch!(self, get_latest_entry(forum_channel_id))
+    }
+
+    async fn assign_forum_channel(&self, forum_channel_id: u64) -> Result<u64> {
+        dispatch!(self, assign_forum_channel(forum_channel_id))
+    }
+
+    async fn record_sync_success(
+        &self,
+        thread_id: u64,
+        synced_at: DateTime<Utc>,
+        warnings: &[String],
+    ) -> Result<()> {
+        dispatch!(self, record_sync_success(thread_id, synced_at, warnings))
+    }
+
+    async fn record_sync_failure(
+        &self,
+        thread_id: u64,
+        failed_at: DateTime<Utc>,
+        error: &str,
+    ) -> Result<()> {
+        dispatch!(self, record_sync_failure(thread_id, failed_at, error))
+    }
+
+    async fn get_attachment_bytes(&self, thread_id: u64) -> Result<u64> {
+        dispatch!(self, get_attachment_bytes(thread_id))
+    }
+
+    async fn add_attachment_bytes(&self, thread_id: u64, bytes: u64) -> Result<()> {
+        dispatch!(self, add_attachment_bytes(thread_id, bytes))
+    }
+
+    async fn set_sync_enabled(&self, thread_id: u64, enabled: bool) -> Result<()> {
+        dispatch!(self, set_sync_enabled(thread_id, enabled))
+    }
+
+    async fn is_sync_enabled(&self, thread_id: u64) -> Result<bool> {
+        dispatch!(self, is_sync_enabled(thread_id))
+    }
+
+    async fn get_sync_status(&self, thread_id: u64) -> Result<DiarySyncStatus> {
+        dispatch!(self, get_sync_status(thread_id))
+    }
+
+    async fn record_reaction(
+        &self,
+        message_id: u64,
+        user_id: u64,
+        emoji: &str,
+        thread_id: u64,
+        reacted_at: DateTime<Utc>,
+    ) -> Result<()> {
+        dispatch!(
+            self,
+            record_reaction(message_id, user_id, emoji, thread_id, reacted_at)
+        )
+    }
+
+    async fn delete_reaction(&self, message_id: u64, user_id: u64, emoji: &str) -> Result<()> {
+        dispatch!(self, delete_reaction(message_id, user_id, emoji))
+    }
+
+    async fn delete_reactions_by_message(
+        &self,
+        message_id: u64,
+        emoji: Option<&str>,
+    ) -> Result<()> {
+        dispatch!(self, delete_reactions_by_message(message_id, emoji))
+    }
+
+    async fn get_emoji_counts(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<EmojiCount>> {
+        dispatch!(self, get_emoji_counts(since, limit))
+    }
+
+    async fn start_time_entry(
+        &self,
+        thread_id: u64,
+        message_id: u64,
+        task: &str,
+        started_at: DateTime<Utc>,
+    ) -> Result<()> {
+        dispatch!(
+            self,
+            start_time_entry(thread_id, message_id, task, started_at)
+        )
+    }
+
+    async fn stop_time_entry(
+        &self,
+        thread_id: u64,
+        stopped_at: DateTime<Utc>,
+    ) -> Result<Option<TimeEntry>> {
+        dispatch!(self, stop_time_entry(thread_id, stopped_at))
+    }
+
+    async fn set_time_entry_block(&self, message_id: u64, block_id: &str) -> Result<()> {
+        dispatch!(self, set_time_entry_block(message_id, block_id))
+    }
+
+    async fn get_time_entries(&self, thread_id: u64) -> Result<Vec<TimeEntry>> {
+        dispatch!(self, get_time_entries(thread_id))
+    }
+
+    async fn record_sync_history(&self, record: &SyncHistoryRecord) -> Result<()> {
+        dispatch!(self, record_sync_history(record))
+    }
+
+    async fn get_sync_history(
+        &self,
+        thread_id: Option<u64>,
+        limit: i64,
+    ) -> Result<Vec<SyncHistoryRecord>> {
+        dispatch!(self, get_sync_history(thread_id, limit))
+    }
+}
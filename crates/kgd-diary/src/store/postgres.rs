@@ -0,0 +1,822 @@
+//! PostgreSQL に保存するストア。
+
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, postgres::PgPoolOptions};
+
+use crate::{history::SyncHistoryRecord, time_tracking::TimeEntry};
+
+use super::{
+    DiaryEntry, DiaryEntryStats, DiaryStorage, DiarySyncStatus, EmojiCount, MessageBlock,
+    ThreadBlockStats,
+};
+
+/// PostgreSQL に保存するストア。
+#[derive(Clone)]
+pub struct PostgresStore {
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    /// データベースに接続し、マイグレーションを実行する。
+    ///
+    /// ボットの状態を同じデータベースに保存する場合はそのマイグレーションも同じ履歴に記録されるため、
+    /// このストアに無いマイグレーションは無視する。
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url)
+            .await
+            .context("Failed to connect to database")?;
+
+        // マイグレーションを実行
+        let mut migrator = sqlx::migrate!("./migrations");
+        migrator.set_ignore_missing(true);
+        migrator
+            .run(&pool)
+            .await
+            .context("Failed to run migrations")?;
+
+        Ok(Self { pool })
+    }
+}
+
+impl DiaryStorage for PostgresStore {
+    async fn insert(&self, entry: &DiaryEntry) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO diary_entries (thread_id, forum_channel_id, page_id, page_url, date, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (thread_id) DO UPDATE SET
+                forum_channel_id = EXCLUDED.forum_channel_id,
+                -- ページを作り直した場合、サマリーのブロックは新しいページに無い
+                summary_block_id = CASE
+                    WHEN diary_entries.page_id = EXCLUDED.page_id THEN diary_entries.summary_block_id
+                END,
+                page_id = EXCLUDED.page_id,
+                page_url = EXCLUDED.page_url,
+                date = EXCLUDED.date
+            "#,
+        )
+        .bind(entry.thread_id as i64)
+        .bind(entry.forum_channel_id as i64)
+        .bind(&entry.page_id)
+        .bind(&entry.page_url)
+        .bind(entry.date)
+        .bind(entry.created_at)
+        .execute(&self.pool)
+        .await
+        .context("Failed to insert diary entry")?;
+
+        Ok(())
+    }
+
+    async fn get_by_thread(&self, thread_id: u64) -> Result<Option<DiaryEntry>> {
+        sqlx::query_as(
+            r#"
+            SELECT thread_id, forum_channel_id, page_id, page_url, date, created_at, closed_at
+            FROM diary_entries
+            WHERE thread_id = $1
+            "#,
+        )
+        .bind(thread_id as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch diary entry by thread")
+    }
+
+    async fn mark_closed(&self, thread_id: u64, closed_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE diary_entries
+            SET closed_at = COALESCE(closed_at, $2)
+            WHERE thread_id = $1
+            "#,
+        )
+        .bind(thread_id as i64)
+        .bind(closed_at)
+        .execute(&self.pool)
+        .await
+        .context("Failed to mark diary entry as closed")?;
+        Ok(())
+    }
+
+    async fn get_by_date(
+        &self,
+        forum_channel_id: u64,
+        date: DateTime<Utc>,
+    ) -> Result<Option<DiaryEntry>> {
+        sqlx::query_as(
+            r#"
+            SELECT thread_id, forum_channel_id, page_id, page_url, date, created_at, closed_at
+            FROM diary_entries
+            WHERE forum_channel_id = $1 AND date = $2
+            "#,
+        )
+        .bind(forum_channel_id as i64)
+        .bind(date)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch diary entry by date")
+    }
+
+    async fn get_summary_block(&self, thread_id: u64) -> Result<Option<String>> {
+        let block_id: Option<Option<String>> = sqlx::query_scalar(
+            r#"
+            SELECT summary_block_id
+            FROM diary_entries
+            WHERE thread_id = $1
+            "#,
+        )
+        .bind(thread_id as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch summary block")?;
+        Ok(block_id.flatten())
+    }
+
+    async fn set_summary_block(&self, thread_id: u64, block_id: &str) -> Result<()> {
+        sqlx::query("UPDATE diary_entries SET summary_block_id = $2 WHERE thread_id = $1")
+            .bind(thread_id as i64)
+            .bind(block_id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to save summary block")?;
+        Ok(())
+    }
+
+    async fn insert_message_block(&self, thread_id: u64, block: &MessageBlock) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO diary_message_blocks (message_id, block_id, block_type, block_order, thread_id, source_url, page_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (block_id) DO NOTHING
+            "#,
+        )
+        .bind(block.message_id as i64)
+        .bind(&block.block_id)
+        .bind(block.block_type.as_str())
+        .bind(block.block_order)
+        .bind(thread_id as i64)
+        .bind(&block.source_url)
+        .bind(&block.page_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to insert message block")?;
+
+        Ok(())
+    }
+
+    async fn get_blocks_by_message(&self, message_id: u64) -> Result<Vec<MessageBlock>> {
+        sqlx::query_as(
+            r#"
+            SELECT message_id, block_id, block_type, block_order, source_url, page_id
+            FROM diary_message_blocks
+            WHERE message_id = $1
+            ORDER BY block_order
+            "#,
+        )
+        .bind(message_id as i64)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch message blocks")
+    }
+
+    async fn delete_blocks_by_message(&self, message_id: u64) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM diary_message_blocks
+            WHERE message_id = $1
+            "#,
+        )
+        .bind(message_id as i64)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete message blocks")?;
+
+        Ok(())
+    }
+
+    async fn get_blocks_by_messages(&self, message_ids: &[u64]) -> Result<Vec<MessageBlock>> {
+        let message_ids: Vec<i64> = message_ids.iter().map(|id| *id as i64).collect();
+        sqlx::query_as(
+            r#"
+            SELECT message_id, block_id, block_type, block_order, source_url, page_id
+            FROM diary_message_blocks
+            WHERE message_id = ANY($1)
+            ORDER BY message_id, block_order
+            "#,
+        )
+        .bind(&message_ids)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch message blocks")
+    }
+
+    async fn get_blocks_by_thread(&self, thread_id: u64) -> Result<Vec<MessageBlock>> {
+        sqlx::query_as(
+            r#"
+            SELECT message_id, block_id, block_type, block_order, source_url, page_id
+            FROM diary_message_blocks
+            WHERE thread_id = $1
+            ORDER BY message_id, block_order
+            "#,
+        )
+        .bind(thread_id as i64)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch thread blocks")
+    }
+
+    async fn delete_blocks_by_messages(&self, message_ids: &[u64]) -> Result<()> {
+        let message_ids: Vec<i64> = message_ids.iter().map(|id| *id as i64).collect();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM diary_message_blocks
+            WHERE message_id = ANY($1)
+            "#,
+        )
+        .bind(&message_ids)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete message blocks")?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM diary_message_contents
+            WHERE message_id = ANY($1)
+            "#,
+        )
+        .bind(&message_ids)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete message content hashes")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(())
+    }
+
+    async fn delete_block(&self, block_id: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM diary_message_blocks
+            WHERE block_id = $1
+            "#,
+        )
+        .bind(block_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete message block")?;
+
+        Ok(())
+    }
+
+    async fn update_block_order(&self, block_id: &str, block_order: i32) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE diary_message_blocks
+            SET block_order = $2
+            WHERE block_id = $1
+            "#,
+        )
+        .bind(block_id)
+        .bind(block_order)
+        .execute(&self.pool)
+        .await
+        .context("Failed to update block order")?;
+
+        Ok(())
+    }
+
+    async fn get_content_hash(&self, message_id: u64) -> Result<Option<String>> {
+        sqlx::query_scalar(
+            r#"
+            SELECT content_hash
+            FROM diary_message_contents
+            WHERE message_id = $1
+            "#,
+        )
+        .bind(message_id as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch message content hash")
+    }
+
+    async fn set_content_hash(&self, message_id: u64, content_hash: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO diary_message_contents (message_id, content_hash, updated_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (message_id) DO UPDATE SET
+                content_hash = EXCLUDED.content_hash,
+                updated_at = EXCLUDED.updated_at
+            "#,
+        )
+        .bind(message_id as i64)
+        .bind(content_hash)
+        .execute(&self.pool)
+        .await
+        .context("Failed to save message content hash")?;
+
+        Ok(())
+    }
+
+    async fn delete_content_hash(&self, message_id: u64) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM diary_message_contents
+            WHERE message_id = $1
+            "#,
+        )
+        .bind(message_id as i64)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete message content hash")?;
+
+        Ok(())
+    }
+
+    async fn get_last_synced_message_id(&self, thread_id: u64) -> Result<Option<u64>> {
+        let message_id: Option<i64> = sqlx::query_scalar(
+            r#"
+            SELECT MAX(message_id)
+            FROM diary_message_blocks
+            WHERE thread_id = $1
+            "#,
+        )
+        .bind(thread_id as i64)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to fetch last synced message id")?;
+
+        Ok(message_id.map(|id| id as u64))
+    }
+
+    async fn has_blocks_by_message(&self, message_id: u64) -> Result<bool> {
+        sqlx::query_scalar(
+            r#"
+            SELECT EXISTS(
+                SELECT 1
+                FROM diary_message_blocks
+                WHERE message_id = $1
+            )
+            "#,
+        )
+        .bind(message_id as i64)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to check message blocks")
+    }
+
+    async fn get_entries_in_date_range(
+        &self,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<DiaryEntry>> {
+        // 起動時同期で日単位の対象スレッドをまとめて引くため、両端を含む範囲で取得する。
+        sqlx::query_as(
+            r#"
+            SELECT thread_id, forum_channel_id, page_id, page_url, date, created_at, closed_at
+            FROM diary_entries
+            WHERE date >= $1 AND date <= $2
+            ORDER BY date ASC
+            "#,
+        )
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch diary entries in date range")
+    }
+
+    async fn get_entry_stats_in_date_range(
+        &self,
+        forum_channel_id: u64,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<DiaryEntryStats>> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                e.page_url,
+                e.date,
+                COUNT(DISTINCT b.message_id) AS message_count,
+                COUNT(b.block_id) FILTER (WHERE b.block_type = 'image') AS image_count
+            FROM diary_entries e
+            LEFT JOIN diary_message_blocks b ON b.thread_id = e.thread_id
+            WHERE e.forum_channel_id = $1 AND e.date >= $2 AND e.date <= $3
+            GROUP BY e.thread_id, e.page_url, e.date
+            ORDER BY e.date ASC
+            "#,
+        )
+        .bind(forum_channel_id as i64)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch diary entry stats in date range")
+    }
+
+    async fn get_thread_block_stats(&self, thread_id: u64) -> Result<ThreadBlockStats> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(DISTINCT message_id) AS message_count,
+                COUNT(*) FILTER (WHERE block_type = 'image') AS image_count,
+                COUNT(DISTINCT source_url) FILTER (WHERE block_type IN ('bookmark', 'embed')) AS link_count
+            FROM diary_message_blocks
+            WHERE thread_id = $1
+            "#,
+        )
+        .bind(thread_id as i64)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to fetch thread block stats")
+    }
+
+    async fn get_latest_entry(&self, forum_channel_id: u64) -> Result<Option<DiaryEntry>> {
+        sqlx::query_as(
+            r#"
+            SELECT thread_id, forum_channel_id, page_id, page_url, date, created_at, closed_at
+            FROM diary_entries
+            WHERE forum_channel_id = $1
+            ORDER BY date DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(forum_channel_id as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch latest diary entry")
+    }
+
+    async fn assign_forum_channel(&self, forum_channel_id: u64) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE diary_entries
+            SET forum_channel_id = $1
+            WHERE forum_channel_id = 0
+            "#,
+        )
+        .bind(forum_channel_id as i64)
+        .execute(&self.pool)
+        .await
+        .context("Failed to assign forum channel to diary entries")?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn record_sync_success(
+        &self,
+        thread_id: u64,
+        synced_at: DateTime<Utc>,
+        warnings: &[String],
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO diary_sync_states (thread_id, last_synced_at, warning_count, last_warning)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (thread_id) DO UPDATE SET
+                last_synced_at = EXCLUDED.last_synced_at,
+                warning_count = diary_sync_states.warning_count + EXCLUDED.warning_count,
+                last_warning = COALESCE(EXCLUDED.last_warning, diary_sync_states.last_warning)
+            "#,
+        )
+        .bind(thread_id as i64)
+        .bind(synced_at)
+        .bind(i32::try_from(warnings.len()).unwrap_or(i32::MAX))
+        .bind(warnings.last())
+        .execute(&self.pool)
+        .await
+        .context("Failed to record sync success")?;
+
+        Ok(())
+    }
+
+    async fn record_sync_failure(
+        &self,
+        thread_id: u64,
+        failed_at: DateTime<Utc>,
+        error: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO diary_sync_states (thread_id, failure_count, last_failed_at, last_error)
+            VALUES ($1, 1, $2, $3)
+            ON CONFLICT (thread_id) DO UPDATE SET
+                failure_count = diary_sync_states.failure_count + 1,
+                last_failed_at = EXCLUDED.last_failed_at,
+                last_error = EXCLUDED.last_error
+            "#,
+        )
+        .bind(thread_id as i64)
+        .bind(failed_at)
+        .bind(error)
+        .execute(&self.pool)
+        .await
+        .context("Failed to record sync failure")?;
+
+        Ok(())
+    }
+
+    async fn get_attachment_bytes(&self, thread_id: u64) -> Result<u64> {
+        let bytes: Option<i64> = sqlx::query_scalar(
+            r#"
+            SELECT attachment_bytes
+            FROM diary_sync_states
+            WHERE thread_id = $1
+            "#,
+        )
+        .bind(thread_id as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch attachment bytes")?;
+
+        Ok(bytes.unwrap_or(0).max(0) as u64)
+    }
+
+    async fn add_attachment_bytes(&self, thread_id: u64, bytes: u64) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO diary_sync_states (thread_id, attachment_bytes)
+            VALUES ($1, $2)
+            ON CONFLICT (thread_id) DO UPDATE SET
+                attachment_bytes = diary_sync_states.attachment_bytes + EXCLUDED.attachment_bytes
+            "#,
+        )
+        .bind(thread_id as i64)
+        .bind(i64::try_from(bytes).unwrap_or(i64::MAX))
+        .execute(&self.pool)
+        .await
+        .context("Failed to add attachment bytes")?;
+
+        Ok(())
+    }
+
+    async fn set_sync_enabled(&self, thread_id: u64, enabled: bool) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO diary_sync_states (thread_id, sync_enabled)
+            VALUES ($1, $2)
+            ON CONFLICT (thread_id) DO UPDATE SET
+                sync_enabled = EXCLUDED.sync_enabled
+            "#,
+        )
+        .bind(thread_id as i64)
+        .bind(enabled)
+        .execute(&self.pool)
+        .await
+        .context("Failed to update sync enabled flag")?;
+
+        Ok(())
+    }
+
+    async fn is_sync_enabled(&self, thread_id: u64) -> Result<bool> {
+        let enabled: Option<bool> = sqlx::query_scalar(
+            r#"
+            SELECT sync_enabled
+            FROM diary_sync_states
+            WHERE thread_id = $1
+            "#,
+        )
+        .bind(thread_id as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch sync enabled flag")?;
+
+        Ok(enabled.unwrap_or(true))
+    }
+
+    async fn get_sync_status(&self, thread_id: u64) -> Result<DiarySyncStatus> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                (
+                    SELECT COUNT(DISTINCT message_id)
+                    FROM diary_message_blocks
+                    WHERE thread_id = $1
+                ) AS message_count,
+                (
+                    SELECT COUNT(*)
+                    FROM diary_message_blocks
+                    WHERE thread_id = $1
+                ) AS block_count,
+                s.last_synced_at,
+                COALESCE(s.failure_count, 0) AS failure_count,
+                s.last_failed_at,
+                s.last_error,
+                COALESCE(s.warning_count, 0) AS warning_count,
+                s.last_warning,
+                COALESCE(s.sync_enabled, TRUE) AS sync_enabled
+            FROM (SELECT 1) AS base
+            LEFT JOIN diary_sync_states s ON s.thread_id = $1
+            "#,
+        )
+        .bind(thread_id as i64)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to fetch sync status")
+    }
+
+    async fn record_reaction(
+        &self,
+        message_id: u64,
+        user_id: u64,
+        emoji: &str,
+        thread_id: u64,
+        reacted_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO diary_reactions (message_id, user_id, emoji, thread_id, reacted_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (message_id, user_id, emoji) DO NOTHING
+            "#,
+        )
+        .bind(message_id as i64)
+        .bind(user_id as i64)
+        .bind(emoji)
+        .bind(thread_id as i64)
+        .bind(reacted_at)
+        .execute(&self.pool)
+        .await
+        .context("Failed to record reaction")?;
+        Ok(())
+    }
+
+    async fn delete_reaction(&self, message_id: u64, user_id: u64, emoji: &str) -> Result<()> {
+        sqlx::query(
+            "DELETE FROM diary_reactions WHERE message_id = $1 AND user_id = $2 AND emoji = $3",
+        )
+        .bind(message_id as i64)
+        .bind(user_id as i64)
+        .bind(emoji)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete reaction")?;
+        Ok(())
+    }
+
+    async fn delete_reactions_by_message(
+        &self,
+        message_id: u64,
+        emoji: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM diary_reactions
+            WHERE message_id = $1 AND ($2::TEXT IS NULL OR emoji = $2)
+            "#,
+        )
+        .bind(message_id as i64)
+        .bind(emoji)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete reactions by message")?;
+        Ok(())
+    }
+
+    async fn get_emoji_counts(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<EmojiCount>> {
+        sqlx::query_as(
+            r#"
+            SELECT emoji, COUNT(*) AS count, COUNT(DISTINCT user_id) AS user_count
+            FROM diary_reactions
+            WHERE $1::TIMESTAMPTZ IS NULL OR reacted_at >= $1
+            GROUP BY emoji
+            ORDER BY count DESC, user_count DESC, emoji ASC
+            LIMIT $2
+            "#,
+        )
+        .bind(since)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch emoji counts")
+    }
+
+    async fn start_time_entry(
+        &self,
+        thread_id: u64,
+        message_id: u64,
+        task: &str,
+        started_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO diary_time_entries (message_id, thread_id, task, started_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (message_id) DO NOTHING
+            "#,
+        )
+        .bind(message_id as i64)
+        .bind(thread_id as i64)
+        .bind(task)
+        .bind(started_at)
+        .execute(&self.pool)
+        .await
+        .context("Failed to start time entry")?;
+        Ok(())
+    }
+
+    async fn stop_time_entry(
+        &self,
+        thread_id: u64,
+        stopped_at: DateTime<Utc>,
+    ) -> Result<Option<TimeEntry>> {
+        sqlx::query_as(
+            r#"
+            UPDATE diary_time_entries
+            SET stopped_at = $2
+            WHERE message_id = (
+                SELECT message_id
+                FROM diary_time_entries
+                WHERE thread_id = $1 AND stopped_at IS NULL
+                ORDER BY started_at DESC
+                LIMIT 1
+            )
+            RETURNING thread_id, message_id, task, started_at, stopped_at, block_id
+            "#,
+        )
+        .bind(thread_id as i64)
+        .bind(stopped_at)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to stop time entry")
+    }
+
+    async fn set_time_entry_block(&self, message_id: u64, block_id: &str) -> Result<()> {
+        sqlx::query("UPDATE diary_time_entries SET block_id = $2 WHERE message_id = $1")
+            .bind(message_id as i64)
+            .bind(block_id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to save time entry block")?;
+        Ok(())
+    }
+
+    async fn get_time_entries(&self, thread_id: u64) -> Result<Vec<TimeEntry>> {
+        sqlx::query_as(
+            r#"
+            SELECT thread_id, message_id, task, started_at, stopped_at, block_id
+            FROM diary_time_entries
+            WHERE thread_id = $1
+            ORDER BY started_at ASC
+            "#,
+        )
+        .bind(thread_id as i64)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch time entries")
+    }
+
+    async fn record_sync_history(&self, record: &SyncHistoryRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO sync_history (thread_id, message_id, action, status, detail, recorded_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(record.thread_id as i64)
+        .bind(record.message_id as i64)
+        .bind(record.action.as_str())
+        .bind(record.status.as_str())
+        .bind(record.detail.as_deref())
+        .bind(record.recorded_at)
+        .execute(&self.pool)
+        .await
+        .context("Failed to record sync history")?;
+        Ok(())
+    }
+
+    async fn get_sync_history(
+        &self,
+        thread_id: Option<u64>,
+        limit: i64,
+    ) -> Result<Vec<SyncHistoryRecord>> {
+        sqlx::query_as(
+            r#"
+            SELECT thread_id, message_id, action, status, detail, recorded_at
+            FROM sync_history
+            WHERE $1::BIGINT IS NULL OR thread_id = $1
+            ORDER BY recorded_at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(thread_id.map(|thread_id| thread_id as i64))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch sync history")
+    }
+}
@@ -0,0 +1,200 @@
+//! 日報ページの冒頭に置くサマリーの callout ブロックを扱う。
+
+use std::time::Duration;
+
+use crate::{store::MessageBlock, sync::normalize_block_id, time_tracking::format_duration};
+
+/// サマリーの callout ブロックに付けるアイコン（既存のサマリーを見分ける目印にも使う）。
+const SUMMARY_ICON: &str = "📊";
+
+/// 日報ページのサマリー。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageSummary {
+    /// 同期済みのメッセージ数
+    pub message_count: i64,
+    /// 同期済みの画像ブロック数
+    pub image_count: i64,
+    /// 同期済みのブックマーク・埋め込みブロックの URL の数
+    pub link_count: i64,
+    /// スレッドに投稿したユーザーの表示名
+    pub participants: Vec<String>,
+    /// `!start` / `!stop` で記録した作業名ごとの作業時間（長い順）
+    pub time_totals: Vec<(String, Duration)>,
+}
+
+impl PageSummary {
+    /// サマリーの callout ブロックを作成する。
+    pub fn to_block(&self) -> serde_json::Value {
+        summary_block(self.rich_text())
+    }
+
+    /// サマリーの callout ブロックの rich_text を作成する。
+    pub fn rich_text(&self) -> Vec<serde_json::Value> {
+        let participants = if self.participants.is_empty() {
+            "なし".to_string()
+        } else {
+            self.participants.join(", ")
+        };
+        let mut text = format!(
+            "メッセージ {}件 / 画像 {}件 / リンク {}件\n参加者: {}",
+            self.message_count, self.image_count, self.link_count, participants
+        );
+        if !self.time_totals.is_empty() {
+            let total = self.time_totals.iter().map(|(_, duration)| *duration).sum();
+            let tasks: Vec<String> = self
+                .time_totals
+                .iter()
+                .map(|(task, duration)| format!("{} {}", task, format_duration(*duration)))
+                .collect();
+            text.push_str(&format!(
+                "\n作業時間: {}（{}）",
+                format_duration(total),
+                tasks.join(", ")
+            ));
+        }
+        vec![plain_text(&text)]
+    }
+}
+
+/// ページ作成時に冒頭へ置く、クローズ時にサマリーへ更新する callout ブロックを作成する。
+pub fn summary_placeholder_block() -> serde_json::Value {
+    summary_block(vec![plain_text("日報をクローズするとサマリーを表示します")])
+}
+
+/// ブロックがサマリーの callout ブロックかどうかを返す。
+pub fn is_summary_block(block: &serde_json::Value) -> bool {
+    block["type"] == "callout" && block["callout"]["icon"]["emoji"] == SUMMARY_ICON
+}
+
+/// ページにサマリーが無い場合に、サマリーを直後に挿入するブロックの ID を返す。
+///
+/// ページテンプレートなど同期の対象外のブロックの後、最初に同期したメッセージのブロックの前に挿入する。
+/// ページの先頭が同期したメッセージのブロックの場合（先頭には挿入できない）や、ページが空の場合は None を返す。
+pub fn summary_anchor<'a>(
+    page_blocks: &'a [serde_json::Value],
+    tracked_blocks: &[MessageBlock],
+) -> Option<&'a str> {
+    let first_tracked = page_blocks
+        .iter()
+        .position(|block| {
+            block["id"].as_str().is_some_and(|block_id| {
+                let block_id = normalize_block_id(block_id);
+                tracked_blocks
+                    .iter()
+                    .any(|tracked| normalize_block_id(&tracked.block_id) == block_id)
+            })
+        })
+        .unwrap_or(page_blocks.len());
+    page_blocks[..first_tracked]
+        .last()
+        .and_then(|block| block["id"].as_str())
+}
+
+fn summary_block(rich_text: Vec<serde_json::Value>) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "callout",
+        "callout": {
+            "rich_text": rich_text,
+            "icon": {
+                "type": "emoji",
+                "emoji": SUMMARY_ICON
+            },
+            "color": "gray_background"
+        }
+    })
+}
+
+fn plain_text(content: &str) -> serde_json::Value {
+    serde_json::json!({
+        "type": "text",
+        "text": {
+            "content": content
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_block() {
+        let summary = PageSummary {
+            message_count: 12,
+            image_count: 3,
+            link_count: 2,
+            participants: vec!["alice".to_string(), "bob".to_string()],
+            time_totals: Vec::new(),
+        };
+
+        let block = summary.to_block();
+        assert!(is_summary_block(&block));
+        assert_eq!(
+            block["callout"]["rich_text"][0]["text"]["content"],
+            "メッセージ 12件 / 画像 3件 / リンク 2件\n参加者: alice, bob"
+        );
+        assert_eq!(
+            PageSummary::default().rich_text()[0]["text"]["content"],
+            "メッセージ 0件 / 画像 0件 / リンク 0件\n参加者: なし"
+        );
+    }
+
+    #[test]
+    fn test_summary_time_totals() {
+        let summary = PageSummary {
+            time_totals: vec![
+                ("docs".to_string(), Duration::from_secs(75 * 60)),
+                ("review".to_string(), Duration::from_secs(30 * 60)),
+            ],
+            ..PageSummary::default()
+        };
+        assert_eq!(
+            summary.rich_text()[0]["text"]["content"],
+            "メッセージ 0件 / 画像 0件 / リンク 0件\n参加者: なし\n作業時間: 1h 45m（docs 1h 15m, review 30m）"
+        );
+    }
+
+    #[test]
+    fn test_summary_anchor() {
+        let page_blocks = vec![
+            serde_json::json!({ "id": "template-1" }),
+            serde_json::json!({ "id": "template-2" }),
+            serde_json::json!({ "id": "aaaa-bbbb" }),
+            serde_json::json!({ "id": "cccc" }),
+        ];
+        let tracked = |block_id: &str| MessageBlock {
+            message_id: 1,
+            block_id: block_id.to_string(),
+            block_type: crate::BlockKind::Text,
+            block_order: 0,
+            source_url: None,
+            page_id: None,
+        };
+
+        assert_eq!(
+            summary_anchor(&page_blocks, &[tracked("AAAABBBB"), tracked("cccc")]),
+            Some("template-2")
+        );
+        // 同期したメッセージが無ければページの末尾に追加する
+        assert_eq!(summary_anchor(&page_blocks, &[]), Some("cccc"));
+        assert_eq!(
+            summary_anchor(&page_blocks[2..], &[tracked("aaaabbbb")]),
+            None
+        );
+        assert_eq!(summary_anchor(&[], &[]), None);
+    }
+
+    #[test]
+    fn test_is_summary_block() {
+        assert!(is_summary_block(&summary_placeholder_block()));
+        assert!(!is_summary_block(&serde_json::json!({
+            "type": "callout",
+            "callout": { "icon": { "type": "emoji", "emoji": "💡" } }
+        })));
+        assert!(!is_summary_block(&serde_json::json!({
+            "type": "paragraph",
+            "paragraph": { "rich_text": [] }
+        })));
+    }
+}
@@ -0,0 +1,3332 @@
+//! メッセージを Notion に同期する機能を提供する。
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    io::{SeekFrom, Write as _},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use chrono::DateTime;
+use chrono_tz::Tz;
+use futures::StreamExt as _;
+use sha2::{Digest as _, Sha256};
+use tokio::io::{AsyncSeekExt as _, AsyncWriteExt as _};
+
+use crate::{
+    block::BlockKind,
+    code::{self, CodePart},
+    config::{ImageRuleConfig, OversizePolicy, RedactionRuleConfig, UrlRuleConfig},
+    convert::{self, CompiledImageRules, ImageRule, NormalizedImage},
+    emoji::{self, CustomEmoji},
+    equation::{self, MathPart},
+    ffmpeg::Ffmpeg,
+    github::GitHubHandler,
+    history::{SyncAction, SyncHistoryRecord, SyncHistoryStatus},
+    mention::{self, Mention},
+    mermaid::Mermaid,
+    message::{MessageEvent, MessageSource, SourceAttachment, SourceMessage},
+    notion::UploadData,
+    ogp::{self, OgpFetcher},
+    quote::{self, QuotePart},
+    redaction::{self, CompiledRedactionRules},
+    retry::{RetryError, RetryPolicy, parse_retry_after},
+    sink::DiarySink,
+    store::{DiaryStorage, MessageBlock},
+    summary::is_summary_block,
+    table::{self, ContentPart},
+    time_tracking::{TimeCommand, TimeEntry, is_time_section_heading, time_section_heading_block},
+    tweet::TweetFetcher,
+    url_handler::{UrlHandler, UrlHandlers},
+    url_parser,
+    workspace::{TempWorkspace, WorkspaceFile},
+    youtube::YouTubeHandler,
+};
+
+/// サイズの上限を超える画像を再圧縮するときの JPEG の品質（`image_jpeg_quality` 未設定時）。
+const OVERSIZE_JPEG_QUALITY: u8 = 75;
+
+/// 一括削除で同時に発行する Notion のブロック削除リクエスト数。
+///
+/// Notion の rate limit（平均 3 リクエスト/秒）を超えないよう抑える。超えた場合はリトライ方針に従って待つ。
+const BULK_DELETE_CONCURRENCY: usize = 3;
+
+/// 動画サムネイルの書き出し用に確保する一時ファイルの容量（4 MiB）。
+const VIDEO_THUMBNAIL_RESERVED_SIZE: u64 = 4 * 1024 * 1024;
+
+/// mermaid の図の書き出し用に確保する一時ファイルの容量（4 MiB）。
+const DIAGRAM_RESERVED_SIZE: u64 = 4 * 1024 * 1024;
+
+/// rich_text の 1 要素に入れられるテキストの長さの上限（Notion API の制限、UTF-16 のコード単位）。
+const RICH_TEXT_MAX_LENGTH: usize = 2000;
+
+/// 1 ブロックに入れられる rich_text の要素数の上限（Notion API の制限）。
+const RICH_TEXT_MAX_ELEMENTS: usize = 100;
+
+/// 同期結果の情報。
+pub struct SyncResult {
+    /// 同期が実行されたかどうか
+    pub synced: bool,
+    /// 項目ごとの同期結果（作成したブロックをページ上の順に並べ、同期できなかった項目は末尾に置く）
+    pub items: Vec<SyncItem>,
+}
+
+/// メッセージの一括削除の結果。
+#[derive(Debug, Clone, Default)]
+pub struct BulkDeleteResult {
+    /// ブロックを削除したメッセージ数
+    pub deleted_messages: usize,
+    /// 削除したブロック数
+    pub deleted_blocks: usize,
+    /// 削除に失敗したブロック ID
+    pub failed_blocks: Vec<String>,
+    /// ブロックを削除したメッセージ ID
+    pub message_ids: Vec<u64>,
+    /// ブロックの削除に失敗したメッセージ ID
+    pub failed_message_ids: Vec<u64>,
+}
+
+/// 日報ページのブロックとストアに記録したブロックを突き合わせた結果。
+#[derive(Debug, Clone, Default)]
+pub struct ReconcileResult {
+    /// 突き合わせたストアのブロック数
+    pub checked_blocks: usize,
+    /// Notion 側で削除されていたため記録を削除したブロック ID
+    pub removed_blocks: Vec<String>,
+    /// ストアに記録されていない（孤児の）ブロック ID
+    pub orphan_blocks: Vec<String>,
+}
+
+/// [`MessageEvent`] を処理した結果。
+pub enum EventOutcome {
+    /// 日報に紐付いていないスレッドのイベントのため処理しなかった
+    Ignored,
+    /// メッセージを同期した
+    Created(SyncResult),
+    /// メッセージのブロックを更新した（対応するブロックがなかった場合は false）
+    Updated(bool),
+    /// メッセージのブロックを削除した（対応するブロックがなかった場合は false）
+    Deleted(bool),
+    /// 複数のメッセージのブロックをまとめて削除した
+    BulkDeleted(BulkDeleteResult),
+}
+
+/// ブロックや添付ファイルなど、同期した項目 1 件の結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncItem {
+    /// ブロックの種類（ブロックを作成しなかった場合は、作成するはずだった種類）
+    pub kind: BlockKind,
+    /// 作成した Notion ブロック ID（ブロックを作成しなかった場合は None）
+    pub block_id: Option<String>,
+    /// 同期はできたが、変換などの付随処理に失敗した・代替手段で同期したことを表す警告
+    pub warnings: Vec<String>,
+    /// 同期できなかった理由
+    pub error: Option<String>,
+}
+
+impl SyncResult {
+    /// 作成したブロック数を返す。
+    pub fn block_count(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.block_id.is_some())
+            .count()
+    }
+
+    /// 同期できなかった項目がある、または警告付きで同期した項目があるかどうかを返す。
+    pub fn is_partial(&self) -> bool {
+        self.items
+            .iter()
+            .any(|item| !item.warnings.is_empty() || item.error.is_some())
+    }
+
+    /// 警告と同期できなかった理由を `種類: 内容` の形式で返す。
+    pub fn issues(&self) -> Vec<String> {
+        self.items
+            .iter()
+            .flat_map(|item| {
+                item.warnings
+                    .iter()
+                    .chain(item.error.as_ref())
+                    .map(|issue| format!("{}: {}", item.kind, issue))
+            })
+            .collect()
+    }
+
+    /// 同期しなかった場合の結果を返す。
+    fn not_synced() -> Self {
+        Self {
+            synced: false,
+            items: Vec::new(),
+        }
+    }
+}
+
+impl SyncItem {
+    /// ブロックを作成する項目を返す（ブロック ID は追加後に設定する）。
+    fn block(kind: BlockKind) -> Self {
+        Self {
+            kind,
+            block_id: None,
+            warnings: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// 同期できなかった項目を返す。
+    fn failed(kind: BlockKind, error: impl Into<String>) -> Self {
+        Self {
+            error: Some(error.into()),
+            ..Self::block(kind)
+        }
+    }
+}
+
+/// メッセージの同期の設定。
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    /// URL 変換ルール
+    pub url_rules: Vec<UrlRuleConfig>,
+    /// ルールにマッチしない URL のデフォルト変換
+    pub default_convert_to: Vec<String>,
+    /// ブロックを生成する前に URL からトラッキング用のクエリパラメータを取り除くかどうか
+    pub strip_tracking_params: bool,
+    /// 同期前に本文へ適用する伏せ字ルール
+    pub redaction_rules: Vec<RedactionRuleConfig>,
+    /// 添付画像の正規化ルール
+    pub image_rules: Vec<ImageRuleConfig>,
+    /// アップロード前に画像を縮小する長辺の最大ピクセル数
+    pub image_max_dimension: Option<u32>,
+    /// アップロード前に JPEG 画像を再圧縮する品質
+    pub image_jpeg_quality: Option<u8>,
+    /// OGP メタデータの取得のタイムアウト（None の場合は OGP 取得を行わない）
+    pub ogp_timeout: Option<Duration>,
+    /// GitHub API のトークン（未指定の場合は認証なしで GitHub の URL を展開する）
+    pub github_token: Option<String>,
+    /// X の投稿の取得のタイムアウト
+    pub tweet_timeout: Duration,
+    /// 1 メッセージあたりの添付ファイル数の上限
+    pub max_attachments_per_message: usize,
+    /// 1 メッセージあたりの添付ファイルの合計バイト数の上限
+    pub max_attachment_bytes_per_message: u64,
+    /// 1 日（日報スレッド）あたりの添付ファイルの合計バイト数の上限
+    pub max_attachment_bytes_per_day: u64,
+    /// 添付ファイル 1 件あたりのサイズの上限
+    pub max_attachment_size: u64,
+    /// サイズの上限を超える添付ファイルの扱い
+    pub on_oversize: OversizePolicy,
+    /// これを超えるサイズの添付ファイルはメモリに載せず一時ファイルにダウンロードする
+    pub attachment_memory_threshold: u64,
+    /// ffmpeg の実行ファイルのパス
+    pub ffmpeg_path: PathBuf,
+    /// ffmpeg の実行のタイムアウト
+    pub ffmpeg_timeout: Duration,
+    /// mermaid の図の描画に使う mmdc（mermaid-cli）の実行ファイルのパス
+    pub mermaid_path: PathBuf,
+    /// mmdc の実行のタイムアウト
+    pub mermaid_timeout: Duration,
+    /// 動画を変換するときの映像ビットレート（kbps）
+    pub video_transcode_bitrate_kbps: u32,
+    /// これを超えるサイズの動画は形式に関わらず変換する
+    pub video_transcode_min_size: u64,
+    /// 同期する投稿者（空の場合は全員）
+    pub sync_users: Vec<u64>,
+    /// 同期しない投稿者
+    pub ignore_users: Vec<u64>,
+    /// クローズ済みのスレッドに投稿されたメッセージも同期するかどうか
+    pub sync_after_close: bool,
+    /// 前回同期したメッセージからこれ以上空いた場合に時刻の見出しを挟む
+    pub section_heading_interval: Option<Duration>,
+    /// 見出しの時刻に使うタイムゾーン
+    pub timezone: Tz,
+    /// 添付ファイルのダウンロードに使うリトライ方針
+    pub retry_policy: RetryPolicy,
+    /// 変換処理の有効/無効
+    pub features: SyncFeatures,
+}
+
+/// 同期時の変換処理の有効/無効。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncFeatures {
+    /// HEIC/HEIF 画像を JPEG に変換して画像ブロックとして同期する
+    pub heic_conversion: bool,
+    /// スポイラー画像をトグルブロックに折りたたむ
+    pub spoiler_toggle: bool,
+    /// カスタム絵文字の画像を取得して画像ブロックとして同期する
+    pub custom_emoji_images: bool,
+    /// 動画から ffmpeg でサムネイルを作成し、動画ブロックの上に画像ブロックとして同期する
+    pub video_thumbnails: bool,
+    /// .mov や大きな動画を ffmpeg で H.264 の MP4 に変換してから同期する
+    pub video_transcode: bool,
+    /// `!start <作業>` / `!stop` のメッセージで作業時間を記録し、「Time」セクションに書き込む
+    pub time_tracking: bool,
+    /// 本文中の Markdown の表を table ブロックに変換する
+    pub markdown_tables: bool,
+    /// 本文中の `>` で始まる行を quote ブロックに変換する
+    pub markdown_quotes: bool,
+    /// 本文中の `$...$` / `$$...$$` を数式に変換する
+    pub math_equations: bool,
+    /// 本文中のコードブロック（```）を code ブロックに変換する
+    pub code_blocks: bool,
+    /// mermaid のコードブロックを mmdc で画像に描画し、code ブロックの下に画像ブロックとして同期する
+    pub mermaid_diagrams: bool,
+}
+
+/// メッセージを Notion に同期するためのシンクロナイザー。
+///
+/// 同期元に固有の処理は `M`、ブロックの書き込み先は `S`、メッセージとブロックの対応の保存先は `D` に任せる。
+pub struct MessageSyncer<'a, M, S, D> {
+    /// 同期元のチャットサービス（メンションの名前解決用）
+    source: M,
+    /// ブロックの書き込み先
+    sink: &'a S,
+    /// 日報ストア
+    store: &'a D,
+    /// HTTP クライアント（画像ダウンロード用）
+    http_client: reqwest::Client,
+    /// URL 変換ルール（コンパイル済み）
+    url_rules: url_parser::CompiledUrlRules,
+    /// 同期前に本文へ適用する伏せ字ルール（コンパイル済み）
+    redaction_rules: CompiledRedactionRules,
+    /// 添付画像の正規化ルール（コンパイル済み）
+    image_rules: CompiledImageRules,
+    /// アップロード前に画像を縮小する長辺の最大ピクセル数
+    image_max_dimension: Option<u32>,
+    /// アップロード前に JPEG 画像を再圧縮する品質
+    image_jpeg_quality: Option<u8>,
+    /// URL から生成したブロックをリッチ化するハンドラー
+    url_handlers: UrlHandlers,
+    /// 添付ファイルの同期上限
+    attachment_limits: AttachmentLimits,
+    /// これを超えるサイズの添付ファイルはメモリに載せず一時ファイルにダウンロードする
+    attachment_memory_threshold: u64,
+    /// ダウンロードや変換に使う一時ファイルの作業ディレクトリ
+    workspace: TempWorkspace,
+    /// 動画処理に使う ffmpeg
+    ffmpeg: Ffmpeg,
+    /// mermaid の図の描画に使う mmdc
+    mermaid: Mermaid,
+    /// 動画の変換条件
+    video_transcode: VideoTranscode,
+    /// 同期するメッセージの投稿者の条件
+    user_filter: UserFilter,
+    /// クローズ済みのスレッドに投稿されたメッセージも同期するかどうか
+    sync_after_close: bool,
+    /// 前回同期したメッセージからこれ以上空いた場合に時刻の見出しを挟む
+    section_heading_interval: Option<Duration>,
+    /// 見出しの時刻に使うタイムゾーン
+    timezone: Tz,
+    /// 添付ファイルのダウンロードに使うリトライ方針
+    retry_policy: RetryPolicy,
+    /// 変換処理の有効/無効
+    features: SyncFeatures,
+}
+
+impl<'a, M, S, D> MessageSyncer<'a, M, S, D> {
+    /// 新しい MessageSyncer を作成する。
+    ///
+    /// # Arguments
+    /// * `source` - 同期元のチャットサービス
+    /// * `sink` - ブロックの書き込み先
+    /// * `store` - 日報ストア
+    /// * `options` - 同期の設定
+    /// * `workspace` - 一時ファイルの作業ディレクトリ
+    pub fn new(
+        source: M,
+        sink: &'a S,
+        store: &'a D,
+        options: &SyncOptions,
+        workspace: &TempWorkspace,
+    ) -> Result<Self> {
+        let url_rules =
+            url_parser::compile_url_rules(&options.url_rules, &options.default_convert_to)?
+                .strip_tracking_params(options.strip_tracking_params);
+        let redaction_rules = redaction::compile_redaction_rules(&options.redaction_rules)?;
+        let image_rules = convert::compile_image_rules(&options.image_rules)?;
+
+        // サイト専用のハンドラーを優先し、どのサイトでも使える OGP は最後に試す
+        let mut url_handlers = UrlHandlers::default();
+        if let Some(timeout) = options.ogp_timeout {
+            url_handlers.push(GitHubHandler::new(
+                timeout,
+                options.github_token.as_deref(),
+            )?);
+            url_handlers.push(YouTubeHandler::new(timeout)?);
+            url_handlers.push(OgpFetcher::new(timeout)?);
+        }
+        url_handlers.push(TweetFetcher::new(options.tweet_timeout)?);
+
+        Ok(Self {
+            source,
+            sink,
+            store,
+            http_client: reqwest::Client::new(),
+            url_rules,
+            redaction_rules,
+            image_rules,
+            image_max_dimension: options.image_max_dimension,
+            image_jpeg_quality: options.image_jpeg_quality,
+            url_handlers,
+            attachment_limits: AttachmentLimits::from_options(options),
+            attachment_memory_threshold: options.attachment_memory_threshold,
+            workspace: workspace.clone(),
+            ffmpeg: Ffmpeg::new(&options.ffmpeg_path, options.ffmpeg_timeout),
+            mermaid: Mermaid::new(&options.mermaid_path, options.mermaid_timeout),
+            video_transcode: VideoTranscode::from_options(options),
+            user_filter: UserFilter::from_options(options),
+            sync_after_close: options.sync_after_close,
+            section_heading_interval: options.section_heading_interval,
+            timezone: options.timezone,
+            retry_policy: options.retry_policy,
+            features: options.features,
+        })
+    }
+
+    /// URL から生成したブロックをリッチ化するハンドラーを追加する。
+    ///
+    /// 組み込みのハンドラー（GitHub・YouTube・OGP・X）より先に試す。
+    /// 複数追加した場合は、後から追加したハンドラーほど優先される。
+    pub fn with_url_handler(mut self, handler: impl UrlHandler + 'static) -> Self {
+        self.url_handlers.push_front(handler);
+        self
+    }
+}
+
+impl<M, S, D> MessageSyncer<'_, M, S, D>
+where
+    M: MessageSource,
+    S: DiarySink,
+    D: DiaryStorage,
+{
+    /// 同期元で発生したメッセージのイベントを処理する。
+    ///
+    /// イベントが発生したスレッドに紐付く日報がある場合のみ、投稿は日報ページに同期し、
+    /// 編集・削除は同期済みのブロックに反映する。
+    /// 編集・削除を反映した結果は同期履歴に記録する（投稿は [`Self::sync_message`] が記録する）。
+    pub async fn handle_event(&self, event: &MessageEvent) -> Result<EventOutcome> {
+        let thread_id = event.thread_id();
+        let Some(entry) = self.store.get_by_thread(thread_id).await? else {
+            return Ok(EventOutcome::Ignored);
+        };
+
+        match event {
+            MessageEvent::Created(message) => self
+                .sync_message(&entry.page_id, message)
+                .await
+                .map(EventOutcome::Created),
+            MessageEvent::Updated(message) => {
+                let result = self.update_message(message).await;
+                self.record_event_history(thread_id, message.id, SyncAction::Update, &result)
+                    .await;
+                result.map(EventOutcome::Updated)
+            }
+            MessageEvent::Deleted { message_id, .. } => {
+                let result = self.delete_message(*message_id).await;
+                self.record_event_history(thread_id, *message_id, SyncAction::Delete, &result)
+                    .await;
+                result.map(EventOutcome::Deleted)
+            }
+            MessageEvent::BulkDeleted { message_ids, .. } => {
+                let result = self.delete_messages(message_ids).await;
+                match &result {
+                    Ok(deleted) => {
+                        for &message_id in &deleted.message_ids {
+                            let (status, detail) =
+                                if deleted.failed_message_ids.contains(&message_id) {
+                                    (
+                                        SyncHistoryStatus::Partial,
+                                        Some("Failed to delete some blocks".to_string()),
+                                    )
+                                } else {
+                                    (SyncHistoryStatus::Success, None)
+                                };
+                            self.record_history(
+                                thread_id,
+                                message_id,
+                                SyncAction::Delete,
+                                status,
+                                detail,
+                            )
+                            .await;
+                        }
+                    }
+                    Err(e) => {
+                        for &message_id in message_ids {
+                            self.record_history(
+                                thread_id,
+                                message_id,
+                                SyncAction::Delete,
+                                SyncHistoryStatus::Failure,
+                                Some(format!("{e:#}")),
+                            )
+                            .await;
+                        }
+                    }
+                }
+                result.map(EventOutcome::BulkDeleted)
+            }
+        }
+    }
+
+    /// 編集・削除を反映した結果を同期履歴に記録する。
+    ///
+    /// 対応するブロックが無く何もしなかった場合は記録しない。
+    async fn record_event_history(
+        &self,
+        thread_id: u64,
+        message_id: u64,
+        action: SyncAction,
+        result: &Result<bool>,
+    ) {
+        let (status, detail) = match result {
+            Ok(false) => return,
+            Ok(true) => (SyncHistoryStatus::Success, None),
+            Err(e) => (SyncHistoryStatus::Failure, Some(format!("{e:#}"))),
+        };
+        self.record_history(thread_id, message_id, action, status, detail)
+            .await;
+    }
+
+    /// 同期履歴を 1 件記録する。
+    ///
+    /// 記録に失敗しても同期の結果には影響させず、警告のログだけを出す。
+    async fn record_history(
+        &self,
+        thread_id: u64,
+        message_id: u64,
+        action: SyncAction,
+        status: SyncHistoryStatus,
+        detail: Option<String>,
+    ) {
+        let record = SyncHistoryRecord {
+            thread_id,
+            message_id,
+            action,
+            status,
+            detail,
+            recorded_at: chrono::Utc::now(),
+        };
+        if let Err(e) = self.store.record_sync_history(&record).await {
+            tracing::warn!(message_id, error = %e, "Failed to record sync history");
+        }
+    }
+
+    /// メッセージを Notion ページに同期する。
+    ///
+    /// テキストと添付ファイルのブロックを1回の API 呼び出しでまとめて追加することで、
+    /// ブロック間に不要な空行が入るのを防ぐ。
+    /// 同期の成否はスレッドの同期状態と同期履歴に記録する。
+    /// スレッドの同期が一時停止中の場合や、投稿者が同期の対象外の場合は同期しない。
+    /// `sync_after_close` が無効な場合は、クローズ済みのスレッドへの投稿も同期しない。
+    ///
+    /// # Returns
+    /// 同期結果（同期されたかどうかと作成されたブロック情報）
+    pub async fn sync_message(&self, page_id: &str, message: &SourceMessage) -> Result<SyncResult> {
+        let thread_id = message.thread_id;
+        if !self.store.is_sync_enabled(thread_id).await? {
+            tracing::debug!(
+                thread_id,
+                "Sync is paused for this thread, skipping message"
+            );
+            return Ok(SyncResult::not_synced());
+        }
+
+        if !self.sync_after_close
+            && self
+                .store
+                .get_by_thread(thread_id)
+                .await?
+                .is_some_and(|entry| entry.is_closed())
+        {
+            tracing::debug!(thread_id, "Thread is closed, skipping message");
+            return Ok(SyncResult::not_synced());
+        }
+
+        if !self.user_filter.allows(message.author_id) {
+            tracing::debug!(
+                thread_id,
+                user_id = message.author_id,
+                "Author is excluded from sync, skipping message"
+            );
+            return Ok(SyncResult::not_synced());
+        }
+
+        match self.sync_message_inner(page_id, message).await {
+            Ok(result) => {
+                if result.synced {
+                    let issues = result.issues();
+                    for item in result
+                        .items
+                        .iter()
+                        .filter(|item| !item.warnings.is_empty() || item.error.is_some())
+                    {
+                        tracing::warn!(
+                            message_id = message.id,
+                            kind = %item.kind,
+                            block_id = ?item.block_id,
+                            warnings = ?item.warnings,
+                            error = ?item.error,
+                            "Message synced with issues"
+                        );
+                    }
+                    self.store
+                        .record_sync_success(thread_id, chrono::Utc::now(), &issues)
+                        .await?;
+                    let (status, detail) = if issues.is_empty() {
+                        (SyncHistoryStatus::Success, None)
+                    } else {
+                        (SyncHistoryStatus::Partial, Some(issues.join("; ")))
+                    };
+                    self.record_history(thread_id, message.id, SyncAction::Create, status, detail)
+                        .await;
+                }
+                Ok(result)
+            }
+            Err(e) => {
+                let error = format!("{e:#}");
+                if let Err(record_error) = self
+                    .store
+                    .record_sync_failure(thread_id, chrono::Utc::now(), &error)
+                    .await
+                {
+                    tracing::warn!(error = %record_error, "Failed to record sync failure");
+                }
+                self.record_history(
+                    thread_id,
+                    message.id,
+                    SyncAction::Create,
+                    SyncHistoryStatus::Failure,
+                    Some(error),
+                )
+                .await;
+                Err(e)
+            }
+        }
+    }
+
+    /// メッセージのブロックを構築して Notion ページに追加する。
+    async fn sync_message_inner(
+        &self,
+        page_id: &str,
+        message: &SourceMessage,
+    ) -> Result<SyncResult> {
+        let content = self.redact_content(message);
+        let has_content = !content.is_empty();
+        let has_attachments = !message.attachments.is_empty();
+
+        if !has_content && !has_attachments {
+            return Ok(SyncResult::not_synced());
+        }
+
+        if self.features.time_tracking
+            && !has_attachments
+            // 作業名は「Time」セクションに書き込むため、伏せ字にした本文から解釈する
+            && let Some(command) = TimeCommand::parse(&content)
+        {
+            return self.sync_time_command(page_id, message, command).await;
+        }
+
+        // ブロック JSON と、各ブロックに対応する同期結果を収集する
+        // 順序: 添付ファイル（画像埋め込み → ファイルリンク） → テキスト
+        let mut children: Vec<serde_json::Value> = Vec::new();
+        let mut blocks: Vec<SyncItem> = Vec::new();
+        // ブロックを作成しなかった（同期できなかった）項目
+        let mut unsynced: Vec<SyncItem> = Vec::new();
+
+        // 添付ファイル: 上限内のものだけをアップロードしてブロック JSON を収集
+        // サイズの上限を超えるものは on_oversize の設定に従って扱う
+        let thread_id = message.thread_id;
+        let limits = &self.attachment_limits;
+        let (selected, attachment_bytes) = if has_attachments {
+            let used_today = self.store.get_attachment_bytes(thread_id).await?;
+            let candidates: Vec<usize> = (0..message.attachments.len())
+                .filter(|&i| {
+                    limits.on_oversize == OversizePolicy::Compress
+                        || !limits.is_oversized(&message.attachments[i])
+                })
+                .collect();
+            let (indices, bytes) = limits.select(
+                candidates.iter().map(|&i| message.attachments[i].size),
+                used_today,
+            );
+            let selected: Vec<usize> = indices.into_iter().map(|i| candidates[i]).collect();
+            (selected, bytes)
+        } else {
+            (Vec::new(), 0)
+        };
+
+        let mut skipped_attachments = 0;
+        for (index, attachment) in message.attachments.iter().enumerate() {
+            if selected.contains(&index) {
+                self.prepare_attachment_blocks(attachment, &mut children, &mut blocks)
+                    .await?;
+            } else if limits.is_oversized(attachment) && limits.on_oversize == OversizePolicy::Link
+            {
+                tracing::info!(
+                    filename = %attachment.filename,
+                    size = attachment.size,
+                    "Attachment exceeds max_attachment_size, linking to Discord CDN"
+                );
+                children.push(external_file_block_json(
+                    &attachment.url,
+                    &attachment.filename,
+                ));
+                let mut item = SyncItem::block(BlockKind::File);
+                item.warnings.push("linked to Discord CDN".to_string());
+                blocks.push(item);
+            } else {
+                skipped_attachments += 1;
+                unsynced.push(SyncItem::failed(
+                    classify_file(&attachment.filename).block_kind(),
+                    format!("{} exceeded attachment limits", attachment.filename),
+                ));
+            }
+        }
+
+        if skipped_attachments > 0 {
+            tracing::warn!(
+                message_id = message.id,
+                skipped_attachments,
+                "Attachment limits exceeded, skipping attachments"
+            );
+            children.push(notice_block_json(&format!(
+                "⚠️ 上限を超えたため、{}件の添付ファイルは同期されませんでした",
+                skipped_attachments
+            )));
+            blocks.push(SyncItem::block(BlockKind::Notice));
+        }
+
+        // テキストブロック（URL をリンク化 + ルールに基づく追加ブロック生成）
+        // 出現順に paragraph / bookmark / embed / quote ブロックが並ぶ
+        // カスタム絵文字タグは :name: 形式に、メンションは @name / #name 形式に置換する
+        let mut rendered_hash = None;
+        if has_content {
+            let text = self.render_text(message, &content).await;
+            let mut url_blocks = self.build_content_blocks(&text);
+            rendered_hash = Some(content_hash(&url_blocks));
+
+            // ブックマークの OGP や X の投稿など、URL ハンドラーでリッチ化する
+            self.build_url_blocks(
+                url_blocks
+                    .iter_mut()
+                    .map(|(block_json, block_type)| (block_json, *block_type)),
+            )
+            .await;
+
+            for (mut block_json, block_type) in url_blocks {
+                if block_type == BlockKind::OgpImage
+                    && !self.upload_ogp_image(message.id, &mut block_json).await
+                {
+                    continue;
+                }
+                let diagram = self.render_diagram(message.id, &block_json).await;
+                children.push(block_json);
+                blocks.push(SyncItem::block(block_type));
+                if let Some(diagram) = diagram {
+                    children.push(diagram);
+                    blocks.push(SyncItem::block(BlockKind::Diagram));
+                }
+            }
+
+            // カスタム絵文字の画像ブロック（取得に失敗した絵文字は名前の置換のみとする）
+            if self.features.custom_emoji_images {
+                for custom_emoji in emoji::parse_custom_emojis(&content) {
+                    match self.upload_custom_emoji(&custom_emoji).await {
+                        Ok(file_upload_id) => {
+                            children.push(image_block_json(&file_upload_id));
+                            blocks.push(SyncItem::block(BlockKind::Image));
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                emoji = %custom_emoji.name,
+                                emoji_id = custom_emoji.id,
+                                error = %e,
+                                "Failed to sync custom emoji image"
+                            );
+                            unsynced.push(SyncItem::failed(
+                                BlockKind::Image,
+                                format!("custom emoji :{}: upload failed", custom_emoji.name),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        if children.is_empty() {
+            return Ok(SyncResult::not_synced());
+        }
+
+        // 前回同期したメッセージから時間が空いていれば、メッセージの前に時刻の見出しを挟む
+        if let Some(heading) = self.section_heading(message).await? {
+            children.insert(0, heading_block_json(&heading));
+            blocks.insert(0, SyncItem::block(BlockKind::Heading));
+        }
+
+        // メッセージ編集時の差分検出用に、ブロックの元になった URL を控えておく
+        let source_urls: Vec<Option<String>> = children
+            .iter()
+            .zip(&blocks)
+            .map(|(block_json, item)| block_source_url(block_json, item.kind))
+            .collect();
+
+        // 全ブロックを一括で追加
+        let block_ids = self.sink.append_blocks(page_id, children).await?;
+
+        // DB にブロック情報を保存
+        for (i, ((block_id, item), source_url)) in block_ids
+            .into_iter()
+            .zip(blocks.iter_mut())
+            .zip(source_urls)
+            .enumerate()
+        {
+            let message_block = MessageBlock {
+                message_id: message.id,
+                block_id: block_id.clone(),
+                block_type: item.kind,
+                block_order: i as i32,
+                source_url,
+                page_id: Some(page_id.to_string()),
+            };
+            self.store
+                .insert_message_block(thread_id, &message_block)
+                .await?;
+            item.block_id = Some(block_id);
+        }
+
+        if let Some(rendered_hash) = &rendered_hash {
+            self.store
+                .set_content_hash(message.id, rendered_hash)
+                .await?;
+        }
+
+        if attachment_bytes > 0 {
+            self.store
+                .add_attachment_bytes(thread_id, attachment_bytes)
+                .await?;
+        }
+
+        blocks.extend(unsynced);
+        Ok(SyncResult {
+            synced: true,
+            items: blocks,
+        })
+    }
+
+    /// メッセージが更新されたときに Notion ブロックを更新する。
+    ///
+    /// 本文から生成したブロック（テキスト・ブックマーク・埋め込み）を生成し直して既存のブロックと比較し、
+    /// テキストブロックは内容を更新し、不要になったブロックは削除し、新たに必要になったブロックは挿入する。
+    /// 添付ファイルのブロックは更新しない。
+    /// スレッドの同期が一時停止中の場合は更新しない。
+    pub async fn update_message(&self, message: &SourceMessage) -> Result<bool> {
+        let thread_id = message.thread_id;
+        if !self.store.is_sync_enabled(thread_id).await? {
+            return Ok(false);
+        }
+
+        let blocks = self.store.get_blocks_by_message(message.id).await?;
+
+        // 作業時間のコマンドのメッセージは本文を同期していないため、編集しても反映しない
+        if blocks.is_empty() || blocks.iter().any(|b| b.block_type == BlockKind::TimeEntry) {
+            return Ok(false);
+        }
+
+        let Some(entry) = self.store.get_by_thread(thread_id).await? else {
+            return Ok(false);
+        };
+        // 非公開メモなど日報ページ以外に追加したブロックは、そのページに挿入する
+        let page_id = blocks
+            .iter()
+            .find_map(|b| b.page_id.as_deref())
+            .unwrap_or(&entry.page_id);
+
+        let content = self.redact_content(message);
+        let text = self.render_text(message, &content).await;
+        let mut new_blocks = self.build_content_blocks(&text);
+
+        // 埋め込みの展開やピン留めでも編集イベントが届くため、描画結果が変わらなければ更新しない
+        let rendered_hash = content_hash(&new_blocks);
+        if self
+            .store
+            .get_content_hash(message.id)
+            .await?
+            .is_some_and(|hash| hash == rendered_hash)
+        {
+            tracing::debug!(
+                message_id = message.id,
+                "Rendered content is unchanged, skipping update"
+            );
+            return Ok(false);
+        }
+
+        // 本文から生成したブロックの前後にある添付ファイルやカスタム絵文字のブロックはそのまま残す
+        let first_derived = blocks
+            .iter()
+            .position(|b| b.block_type.is_derived_from_text())
+            .unwrap_or(blocks.len());
+        let (before, rest) = blocks.split_at(first_derived);
+        let (old_derived, after): (Vec<&MessageBlock>, Vec<&MessageBlock>) = rest
+            .iter()
+            .partition(|b| b.block_type.is_derived_from_text());
+
+        let new_urls: Vec<Option<String>> = new_blocks
+            .iter()
+            .map(|(block_json, block_type)| block_source_url(block_json, *block_type))
+            .collect();
+        let matches = match_derived_blocks(
+            &old_derived
+                .iter()
+                .map(|b| (b.block_type, b.source_url.as_deref()))
+                .collect::<Vec<_>>(),
+            &new_blocks
+                .iter()
+                .zip(&new_urls)
+                .map(|((_, block_type), url)| (*block_type, url.as_deref()))
+                .collect::<Vec<_>>(),
+        );
+
+        // 新たに作成するブロックのみ URL ハンドラーでリッチ化する
+        self.build_url_blocks(
+            new_blocks
+                .iter_mut()
+                .zip(&matches)
+                .filter(|(_, matched)| matched.is_none())
+                .map(|((block_json, block_type), _)| (block_json, *block_type)),
+        )
+        .await;
+
+        // 更新後のブロックの並び（ブロック情報と、新たに作成したかどうか）
+        let mut placed: Vec<(MessageBlock, bool)> =
+            before.iter().map(|b| ((*b).clone(), false)).collect();
+        let mut anchor = before.last().map(|b| b.block_id.clone());
+        let mut pending = Vec::new();
+
+        for (((mut block_json, block_type), source_url), matched) in
+            new_blocks.into_iter().zip(new_urls).zip(&matches)
+        {
+            let Some(index) = *matched else {
+                if block_type == BlockKind::OgpImage
+                    && !self.upload_ogp_image(message.id, &mut block_json).await
+                {
+                    continue;
+                }
+                let diagram = self.render_diagram(message.id, &block_json).await;
+                pending.push((block_json, block_type, source_url));
+                if let Some(diagram) = diagram {
+                    pending.push((diagram, BlockKind::Diagram, None));
+                }
+                continue;
+            };
+
+            self.insert_pending_blocks(page_id, message.id, &mut anchor, &mut pending, &mut placed)
+                .await?;
+
+            let block = old_derived[index];
+            if block_type.is_updatable()
+                && let Some(rich_text) = block_json["paragraph"]["rich_text"].as_array()
+            {
+                self.sink
+                    .update_text_block(&block.block_id, rich_text.clone())
+                    .await?;
+            }
+            anchor = Some(block.block_id.clone());
+            placed.push((block.clone(), false));
+        }
+        self.insert_pending_blocks(page_id, message.id, &mut anchor, &mut pending, &mut placed)
+            .await?;
+
+        // 生成し直したブロックに対応しなくなったブロックを削除
+        for (index, block) in old_derived.iter().enumerate() {
+            if matches.contains(&Some(index)) || !block.block_type.is_deletable_standalone() {
+                continue;
+            }
+            self.sink.delete_block(&block.block_id).await?;
+            self.store.delete_block(&block.block_id).await?;
+        }
+
+        // 新しい並びに合わせて順序を振り直し、作成したブロックを保存する
+        placed.extend(after.iter().map(|b| ((*b).clone(), false)));
+        for (order, (mut block, is_new)) in placed.into_iter().enumerate() {
+            let order = order as i32;
+            if is_new {
+                block.block_order = order;
+                self.store.insert_message_block(thread_id, &block).await?;
+            } else if block.block_order != order {
+                self.store
+                    .update_block_order(&block.block_id, order)
+                    .await?;
+            }
+        }
+
+        self.store
+            .set_content_hash(message.id, &rendered_hash)
+            .await?;
+
+        Ok(true)
+    }
+
+    /// メッセージが削除されたときに対応する Notion ブロックを削除する。
+    ///
+    /// 削除した内容が Notion に残らないよう、同期の一時停止中でも削除する。
+    pub async fn delete_message(&self, message_id: u64) -> Result<bool> {
+        let blocks = self.store.get_blocks_by_message(message_id).await?;
+
+        if blocks.is_empty() {
+            return Ok(false);
+        }
+
+        // すべてのブロックを削除
+        for block in &blocks {
+            self.sink.delete_block(&block.block_id).await?;
+        }
+
+        // DB からブロック情報を削除
+        self.store.delete_blocks_by_message(message_id).await?;
+        self.store.delete_content_hash(message_id).await?;
+
+        Ok(true)
+    }
+
+    /// メッセージがまとめて削除されたときに、対応する Notion ブロックをまとめて削除する。
+    ///
+    /// ブロックの対応は 1 回のクエリで取得し、削除リクエストは同時実行数を抑えて並列に発行する。
+    /// 同期元のメッセージは既に無いため、削除に失敗したブロックも対応情報は削除する。
+    pub async fn delete_messages(&self, message_ids: &[u64]) -> Result<BulkDeleteResult> {
+        let blocks = self.store.get_blocks_by_messages(message_ids).await?;
+
+        if blocks.is_empty() {
+            return Ok(BulkDeleteResult::default());
+        }
+
+        // Send な Future にするため、ブロック ID は所有した値として渡す
+        let block_ids: Vec<String> = blocks.iter().map(|block| block.block_id.clone()).collect();
+        let results: Vec<(String, Result<()>)> = futures::stream::iter(block_ids)
+            .map(|block_id| async move {
+                let result = self.sink.delete_block(&block_id).await;
+                (block_id, result)
+            })
+            .buffer_unordered(BULK_DELETE_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut result = BulkDeleteResult::default();
+        for (block_id, deleted) in results {
+            match deleted {
+                Ok(()) => result.deleted_blocks += 1,
+                Err(e) => {
+                    tracing::warn!(block_id = %block_id, error = %e, "Failed to delete block");
+                    if let Some(block) = blocks.iter().find(|block| block.block_id == block_id)
+                        && !result.failed_message_ids.contains(&block.message_id)
+                    {
+                        result.failed_message_ids.push(block.message_id);
+                    }
+                    result.failed_blocks.push(block_id);
+                }
+            }
+        }
+
+        let mut deleted_message_ids: Vec<u64> = blocks.iter().map(|b| b.message_id).collect();
+        deleted_message_ids.dedup();
+        result.deleted_messages = deleted_message_ids.len();
+
+        self.store
+            .delete_blocks_by_messages(&deleted_message_ids)
+            .await?;
+        result.message_ids = deleted_message_ids;
+
+        Ok(result)
+    }
+
+    /// 描画した本文から、本文由来のブロックを出現順に生成する。
+    ///
+    /// コードブロックは code ブロック、Markdown の表は table ブロック、`>` で始まる行は quote ブロックにし、
+    /// それ以外のテキストは URL ルールに従ってブロック化する。
+    /// mermaid の図の画像ブロックは描画結果が本文だけで決まらないため、ブロックの作成時に code ブロックから生成する。
+    fn build_content_blocks(&self, text: &str) -> Vec<(serde_json::Value, BlockKind)> {
+        let parts = if self.features.code_blocks {
+            code::split_code_blocks(text)
+        } else {
+            vec![CodePart::Text(text.to_string())]
+        };
+        // コードは書式やリンクを付けず、長さの上限に収まるよう分ける
+        let rich_text = |code: &str| {
+            split_text(code, RICH_TEXT_MAX_LENGTH)
+                .into_iter()
+                .take(RICH_TEXT_MAX_ELEMENTS)
+                .map(
+                    |content| serde_json::json!({ "type": "text", "text": { "content": content } }),
+                )
+                .collect()
+        };
+        parts
+            .into_iter()
+            .flat_map(|part| match part {
+                CodePart::Text(text) => self.build_markdown_blocks(&text),
+                CodePart::Code(code) => vec![(code.to_block(&rich_text), BlockKind::Code)],
+            })
+            .collect()
+    }
+
+    /// コードブロック以外のテキストから、表・引用と URL ルールに従ったブロックを出現順に生成する。
+    fn build_markdown_blocks(&self, text: &str) -> Vec<(serde_json::Value, BlockKind)> {
+        let parts = if self.features.markdown_tables {
+            table::split_tables(text)
+        } else {
+            vec![ContentPart::Text(text.to_string())]
+        };
+        parts
+            .into_iter()
+            .flat_map(|part| match part {
+                ContentPart::Text(text) => self.build_text_blocks(&text),
+                ContentPart::Table(table) => vec![(table.to_block(), BlockKind::Table)],
+            })
+            .collect()
+    }
+
+    /// 表以外のテキストから、引用ブロックと URL ルールに従ったブロックを出現順に生成する。
+    fn build_text_blocks(&self, text: &str) -> Vec<(serde_json::Value, BlockKind)> {
+        let parts = if self.features.markdown_quotes {
+            quote::split_quotes(text)
+        } else {
+            vec![QuotePart::Text(text.to_string())]
+        };
+        let rich_text = |text: &str| {
+            // 引用の中の URL はインラインリンクにする
+            let mut rich_text = url_parser::build_inline_rich_text(text, &self.url_rules);
+            if self.features.math_equations {
+                rich_text = equation::split_inline_equations(&rich_text);
+            }
+            // Discord のメッセージの長さでは要素数の上限に達することはまず無いため、超えた分は切り詰める
+            rich_text
+                .iter()
+                .flat_map(split_rich_text_element)
+                .take(RICH_TEXT_MAX_ELEMENTS)
+                .collect()
+        };
+        parts
+            .into_iter()
+            .flat_map(|part| match part {
+                QuotePart::Text(text) => self.build_paragraph_blocks(&text),
+                QuotePart::Quote(quote) => {
+                    vec![(quote.to_block(&rich_text), BlockKind::Blockquote)]
+                }
+            })
+            .collect()
+    }
+
+    /// 引用以外のテキストから、数式ブロックと URL ルールに従ったブロックを出現順に生成する。
+    ///
+    /// `$$...$$` は数式ブロックにし、段落の中の `$...$` はインライン数式にする。
+    fn build_paragraph_blocks(&self, text: &str) -> Vec<(serde_json::Value, BlockKind)> {
+        if !self.features.math_equations {
+            return split_long_text_blocks(
+                url_parser::build_rich_text_and_url_blocks(text, &self.url_rules).blocks,
+            );
+        }
+
+        equation::split_block_equations(text)
+            .into_iter()
+            .flat_map(|part| match part {
+                MathPart::Text(text) => {
+                    let mut blocks =
+                        url_parser::build_rich_text_and_url_blocks(&text, &self.url_rules).blocks;
+                    for (block_json, block_type) in &mut blocks {
+                        if *block_type != BlockKind::Text {
+                            continue;
+                        }
+                        if let Some(rich_text) = block_json["paragraph"]["rich_text"].as_array() {
+                            let rich_text = equation::split_inline_equations(rich_text);
+                            block_json["paragraph"]["rich_text"] = serde_json::json!(rich_text);
+                        }
+                    }
+                    split_long_text_blocks(blocks)
+                }
+                MathPart::Equation(expression) => vec![(
+                    equation::equation_block_json(&expression),
+                    BlockKind::Equation,
+                )],
+            })
+            .collect()
+    }
+
+    /// 日報ページのブロックとストアに記録したブロックを突き合わせる。
+    ///
+    /// Notion 側で手動削除されたブロックの記録は削除する。メッセージのブロックがすべて削除された場合は
+    /// 本文のハッシュも削除し、未同期のメッセージとして扱う。
+    /// ストアに記録されていない孤児のブロックは警告のログを出すだけで、削除はしない。
+    /// 非公開メモのページなど、別のページに追加したブロックの記録は突き合わせない。
+    pub async fn reconcile_page(&self, thread_id: u64, page_id: &str) -> Result<ReconcileResult> {
+        let page_blocks = self.sink.list_blocks(page_id).await?;
+        let normalized_page_id = normalize_block_id(page_id);
+        let records: Vec<MessageBlock> = self
+            .store
+            .get_blocks_by_thread(thread_id)
+            .await?
+            .into_iter()
+            .filter(|record| {
+                record
+                    .page_id
+                    .as_deref()
+                    .is_none_or(|id| normalize_block_id(id) == normalized_page_id)
+            })
+            .collect();
+
+        let page_block_ids: HashSet<String> = page_blocks
+            .iter()
+            .filter_map(|block| block["id"].as_str())
+            .map(normalize_block_id)
+            .collect();
+        let mut result = ReconcileResult {
+            checked_blocks: records.len(),
+            ..Default::default()
+        };
+
+        let mut remaining_messages: HashSet<u64> = HashSet::new();
+        let mut emptied_messages: Vec<u64> = Vec::new();
+        for record in &records {
+            if page_block_ids.contains(&normalize_block_id(&record.block_id)) {
+                remaining_messages.insert(record.message_id);
+                continue;
+            }
+            tracing::info!(
+                thread_id,
+                message_id = record.message_id,
+                block_id = %record.block_id,
+                "Block was deleted from Notion, removing its record"
+            );
+            self.store.delete_block(&record.block_id).await?;
+            result.removed_blocks.push(record.block_id.clone());
+            if !emptied_messages.contains(&record.message_id) {
+                emptied_messages.push(record.message_id);
+            }
+        }
+        for message_id in emptied_messages {
+            if !remaining_messages.contains(&message_id) {
+                self.store.delete_content_hash(message_id).await?;
+            }
+        }
+
+        let tracked_block_ids: HashSet<String> = records
+            .iter()
+            .map(|record| normalize_block_id(&record.block_id))
+            .collect();
+        result.orphan_blocks = find_orphan_blocks(&page_blocks, &tracked_block_ids);
+        for block_id in &result.orphan_blocks {
+            tracing::warn!(
+                thread_id,
+                page_id,
+                block_id = %block_id,
+                "Found a block in the diary page that is not tracked in the store"
+            );
+        }
+
+        Ok(result)
+    }
+
+    /// 添付ファイルをアップロードし、対応するブロック JSON と同期結果を収集する。
+    ///
+    /// 付随処理の警告は、この添付ファイルで最初に追加したブロックの同期結果に記録する。
+    /// HEIC の場合は JPG 変換版（画像ブロック）と元ファイル（ファイルブロック）の 2 つを追加する。
+    /// 動画（mp4 / mov / webm）はファイルブロックではなく動画ブロックとして追加する。
+    async fn prepare_attachment_blocks(
+        &self,
+        attachment: &SourceAttachment,
+        children: &mut Vec<serde_json::Value>,
+        blocks: &mut Vec<SyncItem>,
+    ) -> Result<()> {
+        let mut file_type = match classify_file(&attachment.filename) {
+            // 変換が無効な場合、HEIC は通常のファイルとして扱う
+            FileType::Heic if !self.features.heic_conversion => FileType::Other,
+            file_type => file_type,
+        };
+        let (mut data, mut content_type) = self.download_attachment(attachment).await?;
+        let mut filename = Cow::Borrowed(attachment.filename.as_str());
+        // 失敗しても同期は続けられる付随処理の警告
+        let mut warnings = Vec::new();
+
+        // 正規化ルールに一致する画像は変換してからアップロードする
+        if let Some(rule) = self.image_rules.find(&attachment.filename, data.size()) {
+            match self.normalize_image(rule, &data) {
+                Ok(image) => {
+                    tracing::info!(
+                        filename = %attachment.filename,
+                        from = rule.source_name(),
+                        to = rule.target_name(),
+                        original_size = data.size(),
+                        normalized_size = image.data.len(),
+                        "Normalized image attachment"
+                    );
+                    filename = Cow::Owned(replace_extension(&attachment.filename, image.extension));
+                    content_type = image.content_type.to_string();
+                    data = UploadData::Bytes(image.data);
+                    file_type = FileType::Image;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        filename = %attachment.filename,
+                        error = %e,
+                        "Failed to normalize image, uploading original"
+                    );
+                    warnings.push("image normalization failed".to_string());
+                }
+            }
+        }
+
+        // 設定に応じて画像を縮小・再圧縮する
+        if file_type == FileType::Image
+            && (self.image_max_dimension.is_some() || self.image_jpeg_quality.is_some())
+        {
+            match self.shrink_image(&data) {
+                Ok(Some(image)) => {
+                    tracing::info!(
+                        filename = %attachment.filename,
+                        original_size = data.size(),
+                        shrunk_size = image.data.len(),
+                        "Resized and recompressed image attachment"
+                    );
+                    filename = Cow::Owned(replace_extension(&filename, image.extension));
+                    content_type = image.content_type.to_string();
+                    data = UploadData::Bytes(image.data);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(
+                        filename = %attachment.filename,
+                        error = %e,
+                        "Failed to shrink image, uploading as is"
+                    );
+                    warnings.push("image resize failed".to_string());
+                }
+            }
+        }
+
+        // サイズの上限を超えるものは圧縮し、圧縮できなければ外部ファイルブロックにする
+        let compressed = self.attachment_limits.is_oversized(attachment);
+        if compressed {
+            match self
+                .compress_attachment(file_type, &filename, &mut data)
+                .await
+            {
+                Ok((compressed_file_type, compressed_data)) => {
+                    tracing::info!(
+                        filename = %attachment.filename,
+                        original_size = data.size(),
+                        compressed_size = compressed_data.size(),
+                        "Compressed oversized attachment"
+                    );
+                    let extension = match compressed_file_type {
+                        FileType::Video => "mp4",
+                        _ => "jpg",
+                    };
+                    filename = Cow::Owned(replace_extension(&filename, extension));
+                    content_type = guess_content_type(&filename)
+                        .unwrap_or_else(|| "application/octet-stream".to_string());
+                    data = compressed_data;
+                    file_type = compressed_file_type;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        filename = %attachment.filename,
+                        error = %e,
+                        "Failed to compress oversized attachment, linking to Discord CDN"
+                    );
+                    children.push(external_file_block_json(
+                        &attachment.url,
+                        &attachment.filename,
+                    ));
+                    warnings.push("compression failed, linked to Discord CDN".to_string());
+                    let mut item = SyncItem::block(BlockKind::File);
+                    item.warnings = warnings;
+                    blocks.push(item);
+                    return Ok(());
+                }
+            }
+        }
+
+        let mut attachment_children = Vec::new();
+        let mut attachment_blocks = Vec::new();
+
+        match file_type {
+            FileType::Image => {
+                let file_upload_id = self
+                    .sink
+                    .upload_file(&filename, &content_type, data)
+                    .await
+                    .context("Failed to upload image to Notion")?;
+                attachment_children.push(image_block_json(&file_upload_id));
+                attachment_blocks.push(SyncItem::block(BlockKind::Image));
+            }
+            FileType::Heic => {
+                // HEIC を JPEG に変換してアップロード (Unix のみ)
+                #[cfg(unix)]
+                match self.convert_heic_to_jpeg(&data) {
+                    Ok(jpeg_data) => {
+                        let jpeg_filename = replace_extension(&filename, "jpg");
+                        let jpeg_upload_id = self
+                            .sink
+                            .upload_file(&jpeg_filename, "image/jpeg", jpeg_data)
+                            .await
+                            .context("Failed to upload converted JPEG to Notion")?;
+                        attachment_children.push(image_block_json(&jpeg_upload_id));
+                        attachment_blocks.push(SyncItem::block(BlockKind::Image));
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Failed to convert HEIC to JPEG, skipping conversion");
+                        warnings.push("HEIC to JPEG conversion failed".to_string());
+                    }
+                }
+
+                #[cfg(not(unix))]
+                tracing::warn!(
+                    filename = %attachment.filename,
+                    "HEIC to JPEG conversion is not supported on this platform"
+                );
+
+                // 元の HEIC ファイルもアップロード
+                let file_upload_id = self
+                    .sink
+                    .upload_file(&filename, &content_type, data)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "Failed to upload file to Notion: filename={}, content_type={}",
+                            filename, content_type
+                        )
+                    })?;
+                attachment_children.push(file_block_json(&file_upload_id, &filename));
+                attachment_blocks.push(SyncItem::block(BlockKind::File));
+            }
+            FileType::Video => {
+                // 圧縮のために変換済みの動画は再変換しない
+                if !compressed
+                    && self.features.video_transcode
+                    && self
+                        .video_transcode
+                        .should_transcode(&filename, data.size())
+                {
+                    match self.transcode_video(&filename, &mut data).await {
+                        Ok(transcoded) => {
+                            tracing::info!(
+                                filename = %filename,
+                                original_size = data.size(),
+                                transcoded_size = transcoded.size(),
+                                "Transcoded video attachment to H.264 MP4"
+                            );
+                            filename = Cow::Owned(replace_extension(&filename, "mp4"));
+                            content_type = "video/mp4".to_string();
+                            data = transcoded;
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                filename = %filename,
+                                error = %e,
+                                "Failed to transcode video, uploading original"
+                            );
+                            warnings.push("video transcode failed".to_string());
+                        }
+                    }
+                }
+
+                if self.features.video_thumbnails {
+                    match self.upload_video_thumbnail(&filename, &mut data).await {
+                        Ok(thumbnail_upload_id) => {
+                            attachment_children.push(image_block_json(&thumbnail_upload_id));
+                            attachment_blocks.push(SyncItem::block(BlockKind::Image));
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                filename = %filename,
+                                error = %e,
+                                "Failed to create video thumbnail, syncing video without it"
+                            );
+                            warnings.push("video thumbnail failed".to_string());
+                        }
+                    }
+                }
+
+                let file_upload_id = self
+                    .sink
+                    .upload_file(&filename, &content_type, data)
+                    .await
+                    .context("Failed to upload video to Notion")?;
+                attachment_children.push(video_block_json(&file_upload_id));
+                attachment_blocks.push(SyncItem::block(BlockKind::Video));
+            }
+            FileType::Audio => {
+                // ボイスメッセージの waveform などのメタデータは同期しない
+                let file_upload_id = self
+                    .sink
+                    .upload_file(&filename, &content_type, data)
+                    .await
+                    .context("Failed to upload audio to Notion")?;
+                attachment_children.push(audio_block_json(&file_upload_id));
+                attachment_blocks.push(SyncItem::block(BlockKind::Audio));
+            }
+            FileType::Other => {
+                tracing::debug!(
+                    filename = %filename,
+                    content_type = %content_type,
+                    size = data.size(),
+                    "Uploading file to Notion"
+                );
+
+                let file_upload_id = self
+                    .sink
+                    .upload_file(&filename, &content_type, data)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "Failed to upload file to Notion: filename={}, content_type={}",
+                            filename, content_type
+                        )
+                    })?;
+                attachment_children.push(file_block_json(&file_upload_id, &filename));
+                attachment_blocks.push(SyncItem::block(BlockKind::File));
+            }
+        }
+
+        if matches!(file_type, FileType::Image | FileType::Heic)
+            && is_spoiler_attachment(&attachment.filename)
+            && self.features.spoiler_toggle
+        {
+            let summary = spoiler_summary(attachment.description.as_deref());
+            children.push(toggle_block_json(&summary, attachment_children));
+            attachment_blocks = vec![SyncItem::block(BlockKind::Toggle)];
+        } else {
+            children.extend(attachment_children);
+        }
+
+        if let Some(first) = attachment_blocks.first_mut() {
+            first.warnings = warnings;
+        }
+        blocks.extend(attachment_blocks);
+
+        Ok(())
+    }
+
+    /// メッセージの前に挟む時刻の見出しを返す。
+    ///
+    /// スレッドで前回同期したメッセージから `section_heading_interval` 以上空いた場合のみ返す。
+    async fn section_heading(&self, message: &SourceMessage) -> Result<Option<String>> {
+        let Some(interval) = self.section_heading_interval else {
+            return Ok(None);
+        };
+        let Some(last_message_id) = self
+            .store
+            .get_last_synced_message_id(message.thread_id)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let Some(last_posted_at) = self.source.posted_at(last_message_id) else {
+            return Ok(None);
+        };
+        Ok(section_heading_text(
+            last_posted_at.timestamp(),
+            message.posted_at.timestamp(),
+            interval,
+            &self.timezone,
+        ))
+    }
+
+    /// 溜めておいたブロックを `anchor` の直後に挿入し、`anchor` を最後に挿入したブロックに進める。
+    ///
+    /// `anchor` が None の場合（メッセージの先頭に挿入する場合）は直前のブロックが分からないため、
+    /// ページの末尾に追加する。
+    async fn insert_pending_blocks(
+        &self,
+        page_id: &str,
+        message_id: u64,
+        anchor: &mut Option<String>,
+        pending: &mut Vec<(serde_json::Value, BlockKind, Option<String>)>,
+        placed: &mut Vec<(MessageBlock, bool)>,
+    ) -> Result<()> {
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let (children, metas): (Vec<_>, Vec<_>) = pending
+            .drain(..)
+            .map(|(block_json, block_type, source_url)| (block_json, (block_type, source_url)))
+            .unzip();
+        let block_ids = match anchor.as_deref() {
+            Some(after) => {
+                self.sink
+                    .insert_blocks_after(page_id, after, children)
+                    .await?
+            }
+            None => {
+                tracing::debug!(
+                    message_id,
+                    "No block precedes the edited blocks, appending to the end of the page"
+                );
+                self.sink.append_blocks(page_id, children).await?
+            }
+        };
+
+        for (block_id, (block_type, source_url)) in block_ids.into_iter().zip(metas) {
+            *anchor = Some(block_id.clone());
+            placed.push((
+                MessageBlock {
+                    message_id,
+                    block_id,
+                    block_type,
+                    block_order: 0,
+                    source_url,
+                    page_id: Some(page_id.to_string()),
+                },
+                true,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 伏せ字ルールを適用したメッセージ本文を返す。
+    ///
+    /// 監査用に置換した件数だけをログに残し、元の内容は記録しない。
+    fn redact_content<'m>(&self, message: &'m SourceMessage) -> Cow<'m, str> {
+        let (content, redactions) = self.redaction_rules.redact(&message.content);
+        if redactions > 0 {
+            tracing::info!(
+                message_id = message.id,
+                redactions,
+                "Redacted message content before syncing"
+            );
+        }
+        content
+    }
+
+    /// 本文中のカスタム絵文字とメンションを、Notion 上で読めるテキストに置換する。
+    async fn render_text(&self, message: &SourceMessage, content: &str) -> String {
+        let text = emoji::replace_custom_emojis(content);
+        let names = self.resolve_mentions(message, &text).await;
+        mention::replace_mentions(&text, &names).into_owned()
+    }
+
+    /// テキスト中のメンションを名前に解決する。
+    ///
+    /// 同期元で解決済みの名前を優先し、残りを同期元に問い合わせる。
+    /// 解決に失敗したメンションは結果に含めない（元の表記のまま同期される）。
+    async fn resolve_mentions(
+        &self,
+        message: &SourceMessage,
+        text: &str,
+    ) -> HashMap<Mention, String> {
+        let mut names = HashMap::new();
+        let mut unresolved = Vec::new();
+        for m in mention::parse_mentions(text) {
+            match message.mention_names.get(&m) {
+                Some(name) => {
+                    names.insert(m, name.clone());
+                }
+                None => unresolved.push(m),
+            }
+        }
+
+        if !unresolved.is_empty() {
+            names.extend(self.source.resolve_mentions(message, &unresolved).await);
+        }
+
+        names
+    }
+
+    /// HEIC を JPEG に変換する。
+    ///
+    /// 一時ファイルにダウンロードした場合は、変換結果も作業ディレクトリに書き出す。
+    #[cfg(unix)]
+    fn convert_heic_to_jpeg(&self, data: &UploadData) -> Result<UploadData> {
+        match data {
+            UploadData::Bytes(bytes) => Ok(UploadData::Bytes(heif::convert_heic_to_jpeg(bytes)?)),
+            UploadData::File { file, len } => {
+                // JPEG は元の HEIC より大きくなりやすいため、余裕を持って容量を確保する
+                let (_, output) = self.workspace.create_file(".jpg", len.saturating_mul(2))?;
+                heif::heif_to_jpeg(file.path(), output.path())?;
+                let len = std::fs::metadata(output.path())
+                    .context("Failed to read converted JPEG metadata")?
+                    .len();
+                Ok(UploadData::File { file: output, len })
+            }
+        }
+    }
+
+    /// 動画のサムネイルを ffmpeg で作成してアップロードし、ファイルアップロード ID を返す。
+    async fn upload_video_thumbnail(
+        &self,
+        filename: &str,
+        data: &mut UploadData,
+    ) -> Result<String> {
+        let input = self.write_to_workspace(filename, data)?;
+        let (_, output) = self
+            .workspace
+            .create_file(".jpg", VIDEO_THUMBNAIL_RESERVED_SIZE)?;
+        self.ffmpeg.extract_thumbnail(input, output.path()).await?;
+
+        let thumbnail = tokio::fs::read(output.path())
+            .await
+            .context("Failed to read video thumbnail")?;
+        if thumbnail.is_empty() {
+            anyhow::bail!("ffmpeg produced an empty thumbnail");
+        }
+
+        self.sink
+            .upload_file(&replace_extension(filename, "jpg"), "image/jpeg", thumbnail)
+            .await
+            .context("Failed to upload video thumbnail to Notion")
+    }
+
+    /// サイズの上限を超える添付ファイルを圧縮し、圧縮後の種類とデータを返す。
+    ///
+    /// 画像は JPEG に再圧縮し、動画は H.264 の MP4 に変換する。
+    /// それ以外の種類や、圧縮後も上限を超える場合はエラーを返す。
+    async fn compress_attachment(
+        &self,
+        file_type: FileType,
+        filename: &str,
+        data: &mut UploadData,
+    ) -> Result<(FileType, UploadData)> {
+        let compressed = match file_type {
+            FileType::Image => {
+                let bytes = read_upload_data(data)?;
+                let image = convert::compress_to_jpeg(
+                    &bytes,
+                    self.image_jpeg_quality.unwrap_or(OVERSIZE_JPEG_QUALITY),
+                )?;
+                (FileType::Image, UploadData::Bytes(image.data))
+            }
+            FileType::Video => (FileType::Video, self.transcode_video(filename, data).await?),
+            _ => anyhow::bail!("Compression is not supported for this file type"),
+        };
+
+        let max_size = self.attachment_limits.max_size;
+        if compressed.1.size() > max_size {
+            anyhow::bail!(
+                "Compressed attachment is still too large: {} bytes (limit: {} bytes)",
+                compressed.1.size(),
+                max_size
+            );
+        }
+
+        Ok(compressed)
+    }
+
+    /// 動画を ffmpeg で H.264 の MP4 に変換する。
+    async fn transcode_video(&self, filename: &str, data: &mut UploadData) -> Result<UploadData> {
+        // 変換後のサイズは事前に分からないため、元の動画と同じ容量を確保する
+        let (_, output) = self.workspace.create_file(".mp4", data.size())?;
+        let input = self.write_to_workspace(filename, data)?;
+        self.ffmpeg
+            .transcode_to_h264(input, output.path(), self.video_transcode.bitrate_kbps)
+            .await?;
+
+        let len = tokio::fs::metadata(output.path())
+            .await
+            .context("Failed to read transcoded video metadata")?
+            .len();
+        Ok(UploadData::File { file: output, len })
+    }
+
+    /// ffmpeg の入力にするため、メモリ上のデータを一時ファイルに書き出し、そのパスを返す。
+    ///
+    /// ffmpeg は入力をシークするため、標準入力ではなくファイルで渡す。
+    fn write_to_workspace<'d>(&self, filename: &str, data: &'d mut UploadData) -> Result<&'d Path> {
+        if let UploadData::Bytes(bytes) = data {
+            let suffix = Path::new(filename)
+                .extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy()))
+                .unwrap_or_default();
+            let len = bytes.len() as u64;
+            let (mut handle, file) = self.workspace.create_file(&suffix, len)?;
+            handle
+                .write_all(bytes)
+                .context("Failed to write attachment to temporary file")?;
+            *data = UploadData::File { file, len };
+        }
+
+        match data {
+            UploadData::File { file, .. } => Ok(file.path()),
+            UploadData::Bytes(_) => unreachable!(),
+        }
+    }
+
+    /// 正規化ルールに従って画像を変換する。
+    ///
+    /// 一時ファイルにダウンロードした場合は、変換のためにメモリへ読み込む。
+    fn normalize_image(&self, rule: &ImageRule, data: &UploadData) -> Result<NormalizedImage> {
+        rule.apply(&read_upload_data(data)?)
+    }
+
+    /// 設定された最大サイズと品質で画像を縮小・再圧縮する。
+    ///
+    /// 縮小も再圧縮も不要な場合は `None` を返す。
+    fn shrink_image(&self, data: &UploadData) -> Result<Option<NormalizedImage>> {
+        convert::shrink_image(
+            &read_upload_data(data)?,
+            self.image_max_dimension,
+            self.image_jpeg_quality,
+        )
+    }
+
+    /// `!start` / `!stop` のメッセージで作業時間を記録する。
+    ///
+    /// どちらのコマンドも作業中の作業があれば終了し、その作業を「Time」セクションに書き込む。
+    /// `!start` はその後に新しい作業を開始する。コマンドのメッセージの本文は同期しない。
+    async fn sync_time_command(
+        &self,
+        page_id: &str,
+        message: &SourceMessage,
+        command: TimeCommand,
+    ) -> Result<SyncResult> {
+        let thread_id = message.thread_id;
+        let mut items = Vec::new();
+        if let Some(entry) = self
+            .store
+            .stop_time_entry(thread_id, message.posted_at)
+            .await?
+        {
+            items.push(self.write_time_entry(page_id, message.id, &entry).await?);
+        }
+
+        match command {
+            TimeCommand::Start(task) => {
+                self.store
+                    .start_time_entry(thread_id, message.id, &task, message.posted_at)
+                    .await?;
+            }
+            TimeCommand::Stop if items.is_empty() => {
+                tracing::debug!(thread_id, "No running task to stop");
+                return Ok(SyncResult::not_synced());
+            }
+            TimeCommand::Stop => {}
+        }
+
+        Ok(SyncResult {
+            synced: true,
+            items,
+        })
+    }
+
+    /// 終了した作業の to_do ブロックを「Time」セクションの末尾に書き込む。
+    ///
+    /// スレッドで最初に書き込む作業や、前の作業のブロックが見つからない場合は、
+    /// ページの末尾に見出しを付けて新しいセクションを作る。
+    /// ブロックはコマンドのメッセージに紐付け、メッセージを削除したときに一緒に削除する。
+    async fn write_time_entry(
+        &self,
+        page_id: &str,
+        message_id: u64,
+        entry: &TimeEntry,
+    ) -> Result<SyncItem> {
+        let block = entry
+            .to_block(&self.timezone)
+            .context("Time entry has not been stopped")?;
+        let anchor = self
+            .store
+            .get_time_entries(entry.thread_id)
+            .await?
+            .into_iter()
+            .filter_map(|entry| entry.block_id)
+            .next_back();
+
+        let inserted = match anchor {
+            Some(anchor) => match self
+                .sink
+                .insert_blocks_after(page_id, &anchor, vec![block.clone()])
+                .await
+            {
+                Ok(block_ids) => Some(block_ids),
+                Err(e) => {
+                    tracing::warn!(
+                        error = %e,
+                        block_id = %anchor,
+                        "Failed to insert time entry after the previous one, starting a new section"
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+        let block_ids = match inserted {
+            Some(block_ids) => block_ids,
+            None => {
+                self.sink
+                    .append_blocks(page_id, vec![time_section_heading_block(), block])
+                    .await?
+            }
+        };
+        let block_id = block_ids
+            .last()
+            .cloned()
+            .context("No block was created for the time entry")?;
+
+        self.store
+            .set_time_entry_block(entry.message_id, &block_id)
+            .await?;
+        let message_block = MessageBlock {
+            message_id,
+            block_id: block_id.clone(),
+            block_type: BlockKind::TimeEntry,
+            block_order: 0,
+            source_url: None,
+            page_id: Some(page_id.to_string()),
+        };
+        self.store
+            .insert_message_block(entry.thread_id, &message_block)
+            .await?;
+
+        Ok(SyncItem {
+            block_id: Some(block_id),
+            ..SyncItem::block(BlockKind::TimeEntry)
+        })
+    }
+
+    /// URL から生成したブロックを、URL ハンドラーで並列にリッチ化する。
+    ///
+    /// 扱えるハンドラーがないブロックや、ハンドラーが失敗したブロックはそのまま残す。
+    async fn build_url_blocks<'b>(
+        &self,
+        blocks: impl IntoIterator<Item = (&'b mut serde_json::Value, BlockKind)>,
+    ) {
+        let futures: Vec<_> = blocks
+            .into_iter()
+            .filter_map(|(block_json, block_type)| {
+                let url = block_source_url(block_json, block_type)?;
+                Some(async move {
+                    if let Some(built) = self
+                        .url_handlers
+                        .build_block(&url, block_type, block_json)
+                        .await
+                    {
+                        *block_json = built;
+                    }
+                })
+            })
+            .collect();
+
+        futures::future::join_all(futures).await;
+    }
+
+    /// mermaid の code ブロックであれば図を mmdc で PNG に描画してアップロードし、画像ブロックを返す。
+    ///
+    /// 描画やアップロードに失敗した場合は、code ブロックだけを残すため None を返す。
+    async fn render_diagram(
+        &self,
+        message_id: u64,
+        block_json: &serde_json::Value,
+    ) -> Option<serde_json::Value> {
+        if !self.features.mermaid_diagrams || block_json["code"]["language"] != "mermaid" {
+            return None;
+        }
+        let source: String = block_json["code"]["rich_text"]
+            .as_array()?
+            .iter()
+            .filter_map(|element| element["text"]["content"].as_str())
+            .collect();
+
+        let result = async {
+            let (mut handle, input) = self.workspace.create_file(".mmd", source.len() as u64)?;
+            handle
+                .write_all(source.as_bytes())
+                .context("Failed to write mermaid diagram to temporary file")?;
+            let (_, output) = self.workspace.create_file(".png", DIAGRAM_RESERVED_SIZE)?;
+            self.mermaid.render_png(input.path(), output.path()).await?;
+
+            let image = tokio::fs::read(output.path())
+                .await
+                .context("Failed to read rendered diagram")?;
+            if image.is_empty() {
+                anyhow::bail!("mmdc produced an empty image");
+            }
+            self.sink
+                .upload_file("diagram.png", "image/png", image)
+                .await
+                .context("Failed to upload diagram to Notion")
+        }
+        .await;
+
+        match result {
+            Ok(file_upload_id) => Some(image_block_json(&file_upload_id)),
+            Err(e) => {
+                tracing::warn!(message_id, error = %e, "Failed to render mermaid diagram");
+                None
+            }
+        }
+    }
+
+    /// URL ハンドラーが設定したサムネイル画像を Notion にアップロードし、ブロックの画像を差し替える。
+    ///
+    /// 画像の URL がない（URL ハンドラーで取得できなかった）場合は、ブロックを作らないため false を返す。
+    /// 画像が大きすぎる場合やアップロードに失敗した場合は外部画像のまま貼る。
+    async fn upload_ogp_image(&self, message_id: u64, block_json: &mut serde_json::Value) -> bool {
+        let Some(image_url) = block_json["image"]["external"]["url"]
+            .as_str()
+            .map(str::to_string)
+        else {
+            tracing::debug!(message_id, "No thumbnail image found, skipping image block");
+            return false;
+        };
+
+        let result = async {
+            let (data, content_type) = self
+                .download(&image_url, "download thumbnail", ogp::MAX_BODY_BYTES)
+                .await?;
+            let content_type = content_type
+                .filter(|content_type| content_type.starts_with("image/"))
+                .context("Thumbnail is not an image")?;
+            let filename = image_url
+                .rsplit('/')
+                .next()
+                .and_then(|name| name.split(['?', '#']).next())
+                .filter(|name| !name.is_empty())
+                .unwrap_or("thumbnail");
+            self.sink
+                .upload_file(filename, &content_type, data)
+                .await
+                .context("Failed to upload thumbnail to Notion")
+        }
+        .await;
+
+        match result {
+            Ok(file_upload_id) => {
+                block_json["image"] = serde_json::json!({
+                    "type": "file_upload",
+                    "file_upload": {
+                        "id": file_upload_id
+                    },
+                    "caption": block_json["image"]["caption"].take()
+                });
+            }
+            Err(e) => {
+                tracing::warn!(
+                    message_id,
+                    image_url = %image_url,
+                    error = %e,
+                    "Failed to upload thumbnail, using external image"
+                );
+            }
+        }
+        true
+    }
+
+    /// URL からファイルをダウンロードし、内容と Content-Type を返す。
+    ///
+    /// 一時的な失敗はリトライする。`max_bytes` を超えるファイルは読み込みを打ち切ってエラーにする。
+    async fn download(
+        &self,
+        url: &str,
+        operation: &str,
+        max_bytes: usize,
+    ) -> Result<(Vec<u8>, Option<String>)> {
+        self.retry_policy
+            .run(operation, || async move {
+                let mut response = self
+                    .http_client
+                    .get(url)
+                    .send()
+                    .await
+                    .map_err(RetryError::transient)?;
+
+                let status = response.status();
+                if !status.is_success() {
+                    return Err(RetryError::from_status(
+                        status.as_u16(),
+                        None,
+                        anyhow::anyhow!("Failed to {}: status = {}", operation, status),
+                    ));
+                }
+
+                let content_type = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .map(|value| value.split(';').next().unwrap_or(value).trim().to_string());
+                if let Some(length) = response.content_length()
+                    && length > max_bytes as u64
+                {
+                    return Err(RetryError::permanent(anyhow::anyhow!(
+                        "Failed to {}: {} bytes exceeds the limit of {} bytes",
+                        operation,
+                        length,
+                        max_bytes
+                    )));
+                }
+
+                // Content-Length がない場合もあるため、受信しながら上限を確認する
+                let mut data = Vec::new();
+                while let Some(chunk) = response.chunk().await.map_err(RetryError::transient)? {
+                    if data.len() + chunk.len() > max_bytes {
+                        return Err(RetryError::permanent(anyhow::anyhow!(
+                            "Failed to {}: response exceeds the limit of {} bytes",
+                            operation,
+                            max_bytes
+                        )));
+                    }
+                    data.extend_from_slice(&chunk);
+                }
+                Ok((data, content_type))
+            })
+            .await
+    }
+
+    /// カスタム絵文字の画像を Discord CDN から取得して Notion にアップロードし、ファイルアップロード ID を返す。
+    async fn upload_custom_emoji(&self, custom_emoji: &CustomEmoji) -> Result<String> {
+        let (data, _) = self
+            .download(
+                &custom_emoji.cdn_url(),
+                "download custom emoji",
+                ogp::MAX_BODY_BYTES,
+            )
+            .await?;
+
+        self.sink
+            .upload_file(&custom_emoji.filename(), custom_emoji.content_type(), data)
+            .await
+            .context("Failed to upload custom emoji to Notion")
+    }
+
+    /// 同期元から添付ファイルをダウンロードする。
+    ///
+    /// 一時的な失敗はリトライし、途中まで受信済みの場合は Range リクエストで続きから再開する。
+    /// しきい値を超えるファイルはメモリに載せず、一時ファイルにストリームで書き込む。
+    async fn download_attachment(
+        &self,
+        attachment: &SourceAttachment,
+    ) -> Result<(UploadData, String)> {
+        let mut data = DownloadBuffer::new(
+            &self.workspace,
+            attachment.size,
+            self.attachment_memory_threshold,
+        )?;
+        let mut header_content_type = None;
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            let error = match self
+                .download_attachment_once(attachment, &mut data, &mut header_content_type)
+                .await
+            {
+                Ok(()) => break,
+                Err(error) => error,
+            };
+
+            let Some(delay) = self.retry_policy.next_delay(attempt, &error) else {
+                return Err(error.into_inner().context("Failed to download attachment"));
+            };
+            tracing::warn!(
+                filename = %attachment.filename,
+                attempt,
+                received = data.len(),
+                delay = ?delay,
+                error = %error.inner(),
+                "Attachment download interrupted, retrying"
+            );
+            tokio::time::sleep(delay).await;
+        }
+
+        let header_content_type =
+            header_content_type.unwrap_or_else(|| "application/octet-stream".to_string());
+
+        // 同期元が返す Content-Type が汎用的な場合、ファイル名の拡張子から推定する
+        let content_type = if header_content_type == "application/octet-stream"
+            || header_content_type.is_empty()
+        {
+            guess_content_type(&attachment.filename).unwrap_or(header_content_type)
+        } else {
+            header_content_type
+        };
+
+        Ok((data.finish().await?, content_type))
+    }
+
+    /// 添付ファイルのダウンロードを 1 回試行し、受信したデータを `data` に追記する。
+    ///
+    /// `data` が空でない場合は受信済みの位置から Range リクエストで再開する。
+    async fn download_attachment_once(
+        &self,
+        attachment: &SourceAttachment,
+        data: &mut DownloadBuffer,
+        content_type: &mut Option<String>,
+    ) -> std::result::Result<(), RetryError> {
+        let mut request = self.http_client.get(&attachment.url);
+        if let Some(authorization) = self.source.attachment_authorization(attachment) {
+            request = request.header(reqwest::header::AUTHORIZATION, authorization);
+        }
+        if data.len() > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", data.len()));
+        }
+
+        let mut response = request.send().await.map_err(RetryError::transient)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_retry_after);
+            return Err(RetryError::from_status(
+                status.as_u16(),
+                retry_after,
+                anyhow::anyhow!("Failed to download file: status = {}", status),
+            ));
+        }
+
+        // Range が無視された場合は最初から受信し直す
+        if status != reqwest::StatusCode::PARTIAL_CONTENT {
+            data.clear().await.map_err(RetryError::permanent)?;
+        }
+
+        if content_type.is_none() {
+            *content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+        }
+
+        while let Some(chunk) = response.chunk().await.map_err(RetryError::transient)? {
+            data.write(&chunk).await.map_err(RetryError::permanent)?;
+        }
+
+        if data.len() < attachment.size {
+            return Err(RetryError::transient(anyhow::anyhow!(
+                "Incomplete download: received {} of {} bytes",
+                data.len(),
+                attachment.size
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// 添付ファイルのダウンロード先。
+///
+/// しきい値以下のファイルはメモリに、それを超えるファイルは一時ファイルに書き込む。
+enum DownloadBuffer {
+    /// メモリ上のバッファ
+    Memory(Vec<u8>),
+    /// 作業ディレクトリ内の一時ファイル
+    File {
+        /// 書き込み用のファイルハンドル
+        handle: tokio::fs::File,
+        /// 一時ファイル（破棄時に削除される）
+        file: WorkspaceFile,
+        /// 書き込み済みのバイト数
+        len: u64,
+    },
+}
+
+impl DownloadBuffer {
+    /// ファイルサイズとしきい値からダウンロード先を作成する。
+    fn new(workspace: &TempWorkspace, size: u64, memory_threshold: u64) -> Result<Self> {
+        if size <= memory_threshold {
+            return Ok(Self::Memory(Vec::new()));
+        }
+        let (handle, file) = workspace
+            .create_file(".download", size)
+            .context("Failed to create temporary file for download")?;
+        Ok(Self::File {
+            handle: tokio::fs::File::from_std(handle),
+            file,
+            len: 0,
+        })
+    }
+
+    /// 受信済みのバイト数を返す。
+    fn len(&self) -> u64 {
+        match self {
+            Self::Memory(data) => data.len() as u64,
+            Self::File { len, .. } => *len,
+        }
+    }
+
+    /// 受信済みのデータを破棄する。
+    async fn clear(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Memory(data) => data.clear(),
+            Self::File { handle, len, .. } => {
+                handle.set_len(0).await?;
+                handle.seek(SeekFrom::Start(0)).await?;
+                *len = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// 受信したデータを追記する。
+    async fn write(&mut self, chunk: &[u8]) -> std::io::Result<()> {
+        match self {
+            Self::Memory(data) => data.extend_from_slice(chunk),
+            Self::File { handle, len, .. } => {
+                handle.write_all(chunk).await?;
+                *len += chunk.len() as u64;
+            }
+        }
+        Ok(())
+    }
+
+    /// 書き込みを完了し、アップロード用のデータに変換する。
+    async fn finish(self) -> Result<UploadData> {
+        match self {
+            Self::Memory(data) => Ok(UploadData::Bytes(data)),
+            Self::File {
+                mut handle,
+                file,
+                len,
+            } => {
+                handle
+                    .flush()
+                    .await
+                    .context("Failed to flush temporary file")?;
+                Ok(UploadData::File { file, len })
+            }
+        }
+    }
+}
+
+/// 添付ファイルの同期上限。
+#[derive(Debug, Clone, Copy)]
+struct AttachmentLimits {
+    /// 1 メッセージあたりの添付ファイル数の上限
+    max_count: usize,
+    /// 1 メッセージあたりの合計バイト数の上限
+    max_bytes_per_message: u64,
+    /// 1 日（日報スレッド）あたりの合計バイト数の上限
+    max_bytes_per_day: u64,
+    /// 添付ファイル 1 件あたりのサイズの上限
+    max_size: u64,
+    /// サイズの上限を超える添付ファイルの扱い
+    on_oversize: OversizePolicy,
+}
+
+impl AttachmentLimits {
+    /// 同期の設定から添付ファイルの同期上限を作成する。
+    fn from_options(options: &SyncOptions) -> Self {
+        Self {
+            max_count: options.max_attachments_per_message,
+            max_bytes_per_message: options.max_attachment_bytes_per_message,
+            max_bytes_per_day: options.max_attachment_bytes_per_day,
+            max_size: options.max_attachment_size,
+            on_oversize: options.on_oversize,
+        }
+    }
+
+    /// 添付ファイルが 1 件あたりのサイズの上限を超えているかどうかを返す。
+    fn is_oversized(&self, attachment: &SourceAttachment) -> bool {
+        attachment.size > self.max_size
+    }
+
+    /// 上限内に収まる添付ファイルを先頭から選び、そのインデックスと合計バイト数を返す。
+    ///
+    /// 上限を超える添付ファイルは飛ばし、後続の小さいファイルは引き続き選択対象とする。
+    fn select(&self, sizes: impl IntoIterator<Item = u64>, used_today: u64) -> (Vec<usize>, u64) {
+        let mut selected = Vec::new();
+        let mut total = 0u64;
+
+        for (index, size) in sizes.into_iter().enumerate() {
+            if selected.len() >= self.max_count {
+                break;
+            }
+            let message_total = total.saturating_add(size);
+            if message_total > self.max_bytes_per_message
+                || used_today.saturating_add(message_total) > self.max_bytes_per_day
+            {
+                continue;
+            }
+            total = message_total;
+            selected.push(index);
+        }
+
+        (selected, total)
+    }
+}
+
+/// 同期するメッセージの投稿者の条件。
+#[derive(Debug, Clone)]
+struct UserFilter {
+    /// 同期する投稿者（空の場合は全員）
+    sync_users: Vec<u64>,
+    /// 同期しない投稿者
+    ignore_users: Vec<u64>,
+}
+
+impl UserFilter {
+    /// 同期の設定から投稿者の条件を作成する。
+    fn from_options(options: &SyncOptions) -> Self {
+        Self {
+            sync_users: options.sync_users.clone(),
+            ignore_users: options.ignore_users.clone(),
+        }
+    }
+
+    /// 投稿者のメッセージを同期するかどうかを返す。
+    ///
+    /// 両方のリストに含まれる場合は同期しない。
+    fn allows(&self, user_id: u64) -> bool {
+        if self.ignore_users.contains(&user_id) {
+            return false;
+        }
+        self.sync_users.is_empty() || self.sync_users.contains(&user_id)
+    }
+}
+
+/// 動画を H.264 の MP4 に変換する条件。
+#[derive(Debug, Clone, Copy)]
+struct VideoTranscode {
+    /// 変換後の映像ビットレート（kbps）
+    bitrate_kbps: u32,
+    /// これを超えるサイズの動画は形式に関わらず変換する
+    min_size: u64,
+}
+
+impl VideoTranscode {
+    /// 同期の設定から動画の変換条件を作成する。
+    fn from_options(options: &SyncOptions) -> Self {
+        Self {
+            bitrate_kbps: options.video_transcode_bitrate_kbps,
+            min_size: options.video_transcode_min_size,
+        }
+    }
+
+    /// 動画を変換すべきかどうかを返す。
+    ///
+    /// iPhone の画面収録などの .mov はブラウザで再生できないことが多いため常に変換する。
+    fn should_transcode(&self, filename: &str, size: u64) -> bool {
+        filename.to_lowercase().ends_with(".mov") || size > self.min_size
+    }
+}
+
+/// 比較のため、ブロック ID からハイフンを取り除いて小文字にする。
+pub(crate) fn normalize_block_id(block_id: &str) -> String {
+    block_id.replace('-', "").to_ascii_lowercase()
+}
+
+/// ページ直下のブロックのうち、ストアに記録されていない孤児のブロック ID を返す。
+///
+/// 最初に記録済みのブロックより前（ページテンプレートやサマリー）は同期の対象外のため除き、
+/// サマリーと「Time」セクションの見出しも bot が作成するブロックとして除く。
+fn find_orphan_blocks(
+    page_blocks: &[serde_json::Value],
+    tracked_block_ids: &HashSet<String>,
+) -> Vec<String> {
+    page_blocks
+        .iter()
+        .filter_map(|block| Some((block, block["id"].as_str()?)))
+        .skip_while(|(_, block_id)| !tracked_block_ids.contains(&normalize_block_id(block_id)))
+        .filter(|(block, block_id)| {
+            !tracked_block_ids.contains(&normalize_block_id(block_id))
+                && !is_summary_block(block)
+                && !is_time_section_heading(block)
+        })
+        .map(|(_, block_id)| block_id.to_string())
+        .collect()
+}
+
+/// ブックマーク・埋め込み・引用ブロックの元になった URL を返す。
+fn block_source_url(block_json: &serde_json::Value, block_type: BlockKind) -> Option<String> {
+    let key = match block_type {
+        BlockKind::Bookmark => "bookmark",
+        BlockKind::Embed => "embed",
+        BlockKind::Quote => return url_parser::quote_source_url(block_json).map(str::to_string),
+        BlockKind::OgpImage => {
+            return url_parser::ogp_image_source_url(block_json).map(str::to_string);
+        }
+        _ => return None,
+    };
+    block_json[key]["url"].as_str().map(str::to_string)
+}
+
+/// Notion の rich_text の上限を超えないよう、本文から生成したテキストブロックを分割する。
+///
+/// 長いテキストは同じ書式のまま複数の rich_text 要素に分け、
+/// 要素数が上限を超える場合は複数の paragraph ブロックに分ける。
+fn split_long_text_blocks(
+    blocks: Vec<(serde_json::Value, BlockKind)>,
+) -> Vec<(serde_json::Value, BlockKind)> {
+    let mut result = Vec::with_capacity(blocks.len());
+    for (block_json, block_type) in blocks {
+        let rich_text = match block_json["paragraph"]["rich_text"].as_array() {
+            Some(rich_text) if block_type == BlockKind::Text => rich_text,
+            _ => {
+                result.push((block_json, block_type));
+                continue;
+            }
+        };
+
+        let elements: Vec<serde_json::Value> =
+            rich_text.iter().flat_map(split_rich_text_element).collect();
+        for chunk in elements.chunks(RICH_TEXT_MAX_ELEMENTS) {
+            let mut block = block_json.clone();
+            block["paragraph"]["rich_text"] = serde_json::Value::Array(chunk.to_vec());
+            result.push((block, block_type));
+        }
+    }
+    result
+}
+
+/// rich_text の要素のテキストが上限を超える場合、書式やリンクを保ったまま複数の要素に分ける。
+fn split_rich_text_element(element: &serde_json::Value) -> Vec<serde_json::Value> {
+    let Some(content) = element["text"]["content"].as_str() else {
+        return vec![element.clone()];
+    };
+
+    split_text(content, RICH_TEXT_MAX_LENGTH)
+        .into_iter()
+        .map(|part| {
+            let mut element = element.clone();
+            element["text"]["content"] = serde_json::json!(part);
+            element
+        })
+        .collect()
+}
+
+/// テキストを UTF-16 のコード単位で `max_len` 以下の断片に分ける。
+///
+/// コードブロックなどの行が途中で切れないよう、できるだけ改行の直後で区切る。
+fn split_text(text: &str, max_len: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while rest.encode_utf16().count() > max_len {
+        // 上限に収まる最後の位置（バイト単位）
+        let mut limit = 0;
+        let mut len = 0;
+        for (index, c) in rest.char_indices() {
+            len += c.len_utf16();
+            if len > max_len {
+                break;
+            }
+            limit = index + c.len_utf8();
+        }
+        if limit == 0 {
+            break;
+        }
+
+        let split = rest[..limit].rfind('\n').map_or(limit, |index| index + 1);
+        let (head, tail) = rest.split_at(split);
+        parts.push(head);
+        rest = tail;
+    }
+    parts.push(rest);
+    parts
+}
+
+/// 本文から生成したブロックの SHA-256 ハッシュを 16 進文字列で返す。
+///
+/// OGP などの取得結果を含まない、本文だけで決まる描画結果を比較するために使う。
+fn content_hash(blocks: &[(serde_json::Value, BlockKind)]) -> String {
+    let serialized = serde_json::to_vec(blocks).unwrap_or_default();
+    format!("{:x}", Sha256::digest(serialized))
+}
+
+/// 生成し直した本文由来のブロックを既存のブロックに対応付ける。
+///
+/// 種類と URL が一致するブロックを、既存のブロックの並び順を保ったまま先頭から貪欲に対応付ける。
+/// テキストブロックは内容に関わらず対応付ける（内容は更新する）。
+/// 表・引用・数式・コードは内容を比較できないため対応付けず、古いブロックを削除して作り直す。
+/// mermaid の図はブロックの作成時に生成するため、古いブロックは常に削除する。
+/// 戻り値は新しいブロックごとの、対応する既存ブロックのインデックス（新たに作成する場合は None）。
+fn match_derived_blocks(
+    old: &[(BlockKind, Option<&str>)],
+    new: &[(BlockKind, Option<&str>)],
+) -> Vec<Option<usize>> {
+    let mut next = 0;
+    new.iter()
+        .map(|key| {
+            if matches!(
+                key.0,
+                BlockKind::Table
+                    | BlockKind::Blockquote
+                    | BlockKind::Equation
+                    | BlockKind::Code
+                    | BlockKind::Diagram
+            ) {
+                return None;
+            }
+            let index = old[next..].iter().position(|old| old == key)? + next;
+            next = index + 1;
+            Some(index)
+        })
+        .collect()
+}
+
+/// アップロード済み画像の画像ブロック JSON を生成する。
+fn image_block_json(file_upload_id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "image",
+        "image": {
+            "type": "file_upload",
+            "file_upload": {
+                "id": file_upload_id
+            }
+        }
+    })
+}
+
+/// アップロード済み動画の動画ブロック JSON を生成する。
+fn video_block_json(file_upload_id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "video",
+        "video": {
+            "type": "file_upload",
+            "file_upload": {
+                "id": file_upload_id
+            }
+        }
+    })
+}
+
+/// アップロード済み音声の音声ブロック JSON を生成する。
+fn audio_block_json(file_upload_id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "audio",
+        "audio": {
+            "type": "file_upload",
+            "file_upload": {
+                "id": file_upload_id
+            }
+        }
+    })
+}
+
+/// アップロード済みファイルのファイルブロック JSON を生成する。
+fn file_block_json(file_upload_id: &str, filename: &str) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "file",
+        "file": {
+            "type": "file_upload",
+            "file_upload": {
+                "id": file_upload_id
+            },
+            "name": filename
+        }
+    })
+}
+
+/// 外部 URL を参照するファイルブロック JSON を生成する。
+fn external_file_block_json(url: &str, filename: &str) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "file",
+        "file": {
+            "type": "external",
+            "external": {
+                "url": url
+            },
+            "name": filename
+        }
+    })
+}
+
+/// トグルブロック JSON を生成する。
+fn toggle_block_json(summary: &str, children: Vec<serde_json::Value>) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "toggle",
+        "toggle": {
+            "rich_text": [{
+                "type": "text",
+                "text": {
+                    "content": summary
+                }
+            }],
+            "children": children
+        }
+    })
+}
+
+/// 同期時の注意書きを表す段落ブロック JSON を生成する。
+fn notice_block_json(text: &str) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "paragraph",
+        "paragraph": {
+            "rich_text": [{
+                "type": "text",
+                "text": {
+                    "content": text
+                },
+                "annotations": {
+                    "color": "gray"
+                }
+            }]
+        }
+    })
+}
+
+/// 時刻の見出しブロック JSON を生成する。
+fn heading_block_json(text: &str) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "heading_2",
+        "heading_2": {
+            "rich_text": [{
+                "type": "text",
+                "text": {
+                    "content": text
+                }
+            }]
+        }
+    })
+}
+
+/// 前回のメッセージから `interval` 以上空いていれば、メッセージの時（`14:00` 形式）を見出しとして返す。
+///
+/// 時刻は UNIX 秒で受け取る。
+fn section_heading_text(
+    last_posted_at: i64,
+    posted_at: i64,
+    interval: Duration,
+    timezone: &Tz,
+) -> Option<String> {
+    let elapsed = posted_at.checked_sub(last_posted_at)?;
+    if elapsed < i64::try_from(interval.as_secs()).unwrap_or(i64::MAX) {
+        return None;
+    }
+    let posted_at = DateTime::from_timestamp(posted_at, 0)?.with_timezone(timezone);
+    Some(posted_at.format("%H:00").to_string())
+}
+
+/// ファイル名の拡張子から Content-Type を推定する。
+fn guess_content_type(filename: &str) -> Option<String> {
+    mime_guess::from_path(filename)
+        .first()
+        .map(|mime| mime.to_string())
+}
+
+fn is_spoiler_attachment(filename: &str) -> bool {
+    filename.starts_with("SPOILER_")
+}
+
+fn spoiler_summary(description: Option<&str>) -> String {
+    let mut summary = "Spoiler image".to_string();
+    if let Some(description) = description
+        .map(str::trim)
+        .filter(|description| !description.is_empty())
+    {
+        summary.push_str("\nALT: ");
+        summary.push_str(description);
+    }
+    summary
+}
+
+/// ファイルの種類。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileType {
+    /// 画像ファイル（.png, .jpg, .jpeg, .gif, .webp）
+    Image,
+    /// HEIC/HEIF ファイル（変換が必要）
+    Heic,
+    /// 動画ファイル（.mp4, .mov, .webm）
+    Video,
+    /// 音声ファイル（.ogg, .oga, .mp3, .wav, .m4a）
+    Audio,
+    /// その他のファイル
+    Other,
+}
+
+impl FileType {
+    /// このファイル種類の添付ファイルから作成する主なブロックの種類を返す。
+    fn block_kind(self) -> BlockKind {
+        match self {
+            FileType::Image | FileType::Heic => BlockKind::Image,
+            FileType::Video => BlockKind::Video,
+            FileType::Audio => BlockKind::Audio,
+            FileType::Other => BlockKind::File,
+        }
+    }
+}
+
+/// ファイル名からファイル種類を判定する。
+fn classify_file(filename: &str) -> FileType {
+    let lower = filename.to_lowercase();
+
+    let image_extensions = [".png", ".jpg", ".jpeg", ".gif", ".webp"];
+    if image_extensions.iter().any(|ext| lower.ends_with(ext)) {
+        return FileType::Image;
+    }
+
+    let heic_extensions = [".heic", ".heif"];
+    if heic_extensions.iter().any(|ext| lower.ends_with(ext)) {
+        return FileType::Heic;
+    }
+
+    let video_extensions = [".mp4", ".mov", ".webm"];
+    if video_extensions.iter().any(|ext| lower.ends_with(ext)) {
+        return FileType::Video;
+    }
+
+    // Discord のボイスメッセージは .ogg で添付される
+    let audio_extensions = [".ogg", ".oga", ".mp3", ".wav", ".m4a"];
+    if audio_extensions.iter().any(|ext| lower.ends_with(ext)) {
+        return FileType::Audio;
+    }
+
+    FileType::Other
+}
+
+/// アップロードするデータをメモリ上のバイト列として取得する。
+///
+/// 一時ファイルにダウンロードした場合はファイルから読み込む。
+fn read_upload_data(data: &UploadData) -> Result<Cow<'_, [u8]>> {
+    match data {
+        UploadData::Bytes(bytes) => Ok(Cow::Borrowed(bytes)),
+        UploadData::File { file, .. } => Ok(Cow::Owned(
+            std::fs::read(file.path()).context("Failed to read downloaded attachment")?,
+        )),
+    }
+}
+
+/// ファイル名の拡張子を置き換える。
+fn replace_extension(filename: &str, new_ext: &str) -> String {
+    if let Some(pos) = filename.rfind('.') {
+        format!("{}.{}", &filename[..pos], new_ext)
+    } else {
+        format!("{}.{}", filename, new_ext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    /// メンションを解決しない同期元。
+    struct NoopSource;
+
+    impl MessageSource for NoopSource {
+        fn posted_at(&self, _message_id: u64) -> Option<chrono::DateTime<chrono::Utc>> {
+            None
+        }
+
+        async fn resolve_mentions(
+            &self,
+            _message: &SourceMessage,
+            _mentions: &[Mention],
+        ) -> HashMap<Mention, String> {
+            HashMap::new()
+        }
+    }
+
+    /// 書き込みを想定しないテストで使う同期先（呼び出されるとテストを失敗させる）。
+    struct UnusedSink;
+
+    impl DiarySink for UnusedSink {
+        async fn append_blocks(
+            &self,
+            _page_id: &str,
+            _children: Vec<serde_json::Value>,
+        ) -> Result<Vec<String>> {
+            unreachable!("append_blocks")
+        }
+
+        async fn insert_blocks_after(
+            &self,
+            _page_id: &str,
+            _after_block_id: &str,
+            _children: Vec<serde_json::Value>,
+        ) -> Result<Vec<String>> {
+            unreachable!("insert_blocks_after")
+        }
+
+        async fn update_text_block(
+            &self,
+            _block_id: &str,
+            _rich_text: Vec<serde_json::Value>,
+        ) -> Result<()> {
+            unreachable!("update_text_block")
+        }
+
+        async fn delete_block(&self, _block_id: &str) -> Result<()> {
+            unreachable!("delete_block")
+        }
+
+        async fn list_blocks(&self, _page_id: &str) -> Result<Vec<serde_json::Value>> {
+            unreachable!("list_blocks")
+        }
+
+        async fn upload_file(
+            &self,
+            _filename: &str,
+            _content_type: &str,
+            _data: impl Into<UploadData> + Send,
+        ) -> Result<String> {
+            unreachable!("upload_file")
+        }
+    }
+
+    /// 外部のサービスやコマンドを使わない同期の設定を返す。
+    fn sync_options(redaction_rules: Vec<RedactionRuleConfig>) -> SyncOptions {
+        SyncOptions {
+            url_rules: Vec::new(),
+            default_convert_to: vec!["link".to_string()],
+            strip_tracking_params: false,
+            redaction_rules,
+            image_rules: Vec::new(),
+            image_max_dimension: None,
+            image_jpeg_quality: None,
+            ogp_timeout: None,
+            github_token: None,
+            tweet_timeout: Duration::from_secs(1),
+            max_attachments_per_message: 10,
+            max_attachment_bytes_per_message: 1024 * 1024,
+            max_attachment_bytes_per_day: 1024 * 1024,
+            max_attachment_size: 1024 * 1024,
+            on_oversize: OversizePolicy::Skip,
+            attachment_memory_threshold: 1024 * 1024,
+            ffmpeg_path: PathBuf::from("ffmpeg"),
+            ffmpeg_timeout: Duration::from_secs(1),
+            mermaid_path: PathBuf::from("mmdc"),
+            mermaid_timeout: Duration::from_secs(1),
+            video_transcode_bitrate_kbps: 4000,
+            video_transcode_min_size: 1024 * 1024,
+            sync_users: Vec::new(),
+            ignore_users: Vec::new(),
+            sync_after_close: true,
+            section_heading_interval: None,
+            timezone: chrono_tz::Asia::Tokyo,
+            retry_policy: RetryPolicy::new(1, Duration::ZERO, Duration::ZERO),
+            features: SyncFeatures {
+                time_tracking: true,
+                ..SyncFeatures::default()
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_time_command_task_is_redacted() {
+        let options = sync_options(vec![RedactionRuleConfig {
+            pattern: r"(token=)\S+".to_string(),
+            replacement: "${1}[REDACTED]".to_string(),
+        }]);
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = TempWorkspace::open(dir.path(), 1024 * 1024).unwrap();
+        let store = MemoryStore::new();
+        let syncer =
+            MessageSyncer::new(NoopSource, &UnusedSink, &store, &options, &workspace).unwrap();
+
+        let message = SourceMessage {
+            id: 2,
+            thread_id: 1,
+            author_id: 3,
+            posted_at: chrono::Utc::now(),
+            content: "!start rotate token=hunter2".to_string(),
+            attachments: Vec::new(),
+            mention_names: HashMap::new(),
+        };
+        syncer.sync_message_inner("page", &message).await.unwrap();
+
+        let entries = store.get_time_entries(1).await.unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.task.as_str())
+                .collect::<Vec<_>>(),
+            vec!["rotate token=[REDACTED]"]
+        );
+    }
+
+    #[test]
+    fn test_split_text() {
+        assert_eq!(split_text("short", 10), vec!["short"]);
+        // 改行があれば改行の直後で区切る
+        assert_eq!(
+            split_text("```\nfn a() {}\nfn b() {}\n```", 16),
+            vec!["```\nfn a() {}\n", "fn b() {}\n```"]
+        );
+        // 改行が無ければ上限ちょうどで区切る
+        assert_eq!(split_text("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        // サロゲートペアの絵文字は 2 単位として数え、途中で切らない
+        assert_eq!(split_text("a😀b", 2), vec!["a", "😀", "b"]);
+    }
+
+    #[test]
+    fn test_split_long_text_blocks() {
+        let long = "a".repeat(RICH_TEXT_MAX_LENGTH * 2 + 1);
+        let paragraph = serde_json::json!({
+            "object": "block",
+            "type": "paragraph",
+            "paragraph": {
+                "rich_text": [{ "type": "text", "text": { "content": long } }]
+            }
+        });
+        let bookmark =
+            serde_json::json!({ "type": "bookmark", "bookmark": { "url": "https://example.com" } });
+
+        let blocks = split_long_text_blocks(vec![
+            (paragraph.clone(), BlockKind::Text),
+            (bookmark.clone(), BlockKind::Bookmark),
+        ]);
+        assert_eq!(blocks.len(), 2);
+        let rich_text = blocks[0].0["paragraph"]["rich_text"].as_array().unwrap();
+        let lengths: Vec<usize> = rich_text
+            .iter()
+            .map(|e| e["text"]["content"].as_str().unwrap().len())
+            .collect();
+        assert_eq!(lengths, vec![RICH_TEXT_MAX_LENGTH, RICH_TEXT_MAX_LENGTH, 1]);
+        assert_eq!(rich_text[0]["type"], "text");
+        assert_eq!(blocks[1], (bookmark, BlockKind::Bookmark));
+
+        // 要素数が上限を超える場合はブロックを分ける
+        let elements: Vec<serde_json::Value> = (0..RICH_TEXT_MAX_ELEMENTS + 1)
+            .map(|i| serde_json::json!({ "type": "text", "text": { "content": i.to_string() } }))
+            .collect();
+        let mut paragraph = paragraph;
+        paragraph["paragraph"]["rich_text"] = serde_json::Value::Array(elements);
+        let blocks = split_long_text_blocks(vec![(paragraph, BlockKind::Text)]);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(
+            blocks[0].0["paragraph"]["rich_text"]
+                .as_array()
+                .unwrap()
+                .len(),
+            RICH_TEXT_MAX_ELEMENTS
+        );
+        assert_eq!(
+            blocks[1].0["paragraph"]["rich_text"][0]["text"]["content"],
+            "100"
+        );
+        assert!(blocks.iter().all(|(_, kind)| *kind == BlockKind::Text));
+    }
+
+    #[test]
+    fn test_classify_file_image() {
+        assert_eq!(classify_file("photo.png"), FileType::Image);
+        assert_eq!(classify_file("photo.PNG"), FileType::Image);
+        assert_eq!(classify_file("image.jpg"), FileType::Image);
+        assert_eq!(classify_file("image.JPG"), FileType::Image);
+        assert_eq!(classify_file("picture.jpeg"), FileType::Image);
+        assert_eq!(classify_file("animation.gif"), FileType::Image);
+        assert_eq!(classify_file("modern.webp"), FileType::Image);
+    }
+
+    #[test]
+    fn test_classify_file_heic() {
+        assert_eq!(classify_file("photo.heic"), FileType::Heic);
+        assert_eq!(classify_file("photo.HEIC"), FileType::Heic);
+        assert_eq!(classify_file("image.heif"), FileType::Heic);
+        assert_eq!(classify_file("image.HEIF"), FileType::Heic);
+    }
+
+    #[test]
+    fn test_classify_file_video() {
+        assert_eq!(classify_file("clip.mp4"), FileType::Video);
+        assert_eq!(classify_file("IMG_0001.MOV"), FileType::Video);
+        assert_eq!(classify_file("screen.webm"), FileType::Video);
+        assert_eq!(classify_file("moviemp4"), FileType::Other);
+    }
+
+    #[test]
+    fn test_classify_file_audio() {
+        assert_eq!(classify_file("voice-message.ogg"), FileType::Audio);
+        assert_eq!(classify_file("song.MP3"), FileType::Audio);
+        assert_eq!(classify_file("recording.wav"), FileType::Audio);
+        assert_eq!(classify_file("memo.m4a"), FileType::Audio);
+        assert_eq!(classify_file("songmp3"), FileType::Other);
+    }
+
+    #[test]
+    fn test_classify_file_other() {
+        assert_eq!(classify_file("document.pdf"), FileType::Other);
+        assert_eq!(classify_file("archive.zip"), FileType::Other);
+        assert_eq!(classify_file("script.js"), FileType::Other);
+        assert_eq!(classify_file("noextension"), FileType::Other);
+    }
+
+    #[test]
+    fn test_classify_file_rejects_similar_names() {
+        // ドットなしの拡張子文字列で終わるファイル名は画像として判定されない
+        assert_eq!(classify_file("somepng"), FileType::Other);
+        assert_eq!(classify_file("filejpg"), FileType::Other);
+        assert_eq!(classify_file("imageheic"), FileType::Other);
+    }
+
+    #[test]
+    fn test_guess_content_type() {
+        assert_eq!(
+            guess_content_type("photo.heic"),
+            Some("image/heic".to_string())
+        );
+        assert_eq!(
+            guess_content_type("photo.HEIC"),
+            Some("image/heic".to_string())
+        );
+        assert_eq!(
+            guess_content_type("image.heif"),
+            Some("image/heif".to_string())
+        );
+        assert_eq!(
+            guess_content_type("photo.png"),
+            Some("image/png".to_string())
+        );
+        assert_eq!(
+            guess_content_type("photo.jpg"),
+            Some("image/jpeg".to_string())
+        );
+        assert_eq!(
+            guess_content_type("doc.pdf"),
+            Some("application/pdf".to_string())
+        );
+        assert_eq!(
+            guess_content_type("archive.zip"),
+            Some("application/zip".to_string())
+        );
+        assert_eq!(
+            guess_content_type("data.gpx"),
+            Some("application/gpx+xml".to_string())
+        );
+        assert_eq!(guess_content_type("noextension"), None);
+    }
+
+    #[test]
+    fn test_is_spoiler_attachment() {
+        assert!(is_spoiler_attachment("SPOILER_photo.png"));
+        assert!(!is_spoiler_attachment("photo.png"));
+        assert!(!is_spoiler_attachment("spoiler_photo.png"));
+    }
+
+    #[test]
+    fn test_spoiler_summary_without_alt() {
+        assert_eq!(spoiler_summary(None), "Spoiler image");
+        assert_eq!(spoiler_summary(Some("   ")), "Spoiler image");
+    }
+
+    #[test]
+    fn test_spoiler_summary_with_alt() {
+        assert_eq!(
+            spoiler_summary(Some("sensitive content")),
+            "Spoiler image\nALT: sensitive content"
+        );
+    }
+
+    #[test]
+    fn test_toggle_block_json_includes_children() {
+        let toggle = toggle_block_json("Spoiler image", vec![image_block_json("upload-id")]);
+
+        assert_eq!(toggle["type"], "toggle");
+        assert_eq!(
+            toggle["toggle"]["rich_text"][0]["text"]["content"],
+            "Spoiler image"
+        );
+        let children = toggle["toggle"]["children"]
+            .as_array()
+            .expect("toggle children should be an array");
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0]["type"], "image");
+    }
+
+    #[test]
+    fn test_attachment_limits_select() {
+        let limits = AttachmentLimits {
+            max_count: 3,
+            max_bytes_per_message: 100,
+            max_bytes_per_day: 1000,
+            max_size: 100,
+            on_oversize: OversizePolicy::Skip,
+        };
+
+        // 合計サイズを超えるファイルは飛ばし、後続の小さいファイルは選択する
+        assert_eq!(limits.select([40, 70, 50, 10], 0), (vec![0, 2, 3], 100));
+        // 件数の上限
+        assert_eq!(limits.select([1, 1, 1, 1, 1], 0), (vec![0, 1, 2], 3));
+        // 1 日あたりの上限
+        assert_eq!(limits.select([30, 20], 960), (vec![0], 30));
+        assert_eq!(limits.select([30], 1000), (vec![], 0));
+    }
+
+    #[test]
+    fn test_sync_result_is_partial() {
+        let result = |items| SyncResult {
+            synced: true,
+            items,
+        };
+        let synced = |kind| SyncItem {
+            block_id: Some("block".to_string()),
+            ..SyncItem::block(kind)
+        };
+
+        assert!(!result(vec![]).is_partial());
+        assert!(!result(vec![synced(BlockKind::Image), synced(BlockKind::Text)]).is_partial());
+
+        let mut with_warning = synced(BlockKind::Video);
+        with_warning
+            .warnings
+            .push("video thumbnail failed".to_string());
+        let partial = result(vec![with_warning, synced(BlockKind::Text)]);
+        assert!(partial.is_partial());
+        assert_eq!(partial.block_count(), 2);
+        assert_eq!(partial.issues(), vec!["video: video thumbnail failed"]);
+
+        let skipped = result(vec![
+            synced(BlockKind::Image),
+            SyncItem::failed(BlockKind::Video, "movie.mp4 exceeded attachment limits"),
+        ]);
+        assert!(skipped.is_partial());
+        assert_eq!(skipped.block_count(), 1);
+        assert_eq!(
+            skipped.issues(),
+            vec!["video: movie.mp4 exceeded attachment limits"]
+        );
+    }
+
+    #[test]
+    fn test_video_transcode_should_transcode() {
+        let transcode = VideoTranscode {
+            bitrate_kbps: 4000,
+            min_size: 100,
+        };
+
+        assert!(transcode.should_transcode("ScreenRecording.MOV", 10));
+        assert!(transcode.should_transcode("clip.mp4", 101));
+        assert!(!transcode.should_transcode("clip.mp4", 100));
+        assert!(!transcode.should_transcode("clip.webm", 10));
+    }
+
+    #[test]
+    fn test_match_derived_blocks() {
+        let text = (BlockKind::Text, None);
+        let bookmark = |url| (BlockKind::Bookmark, Some(url));
+
+        // URL を追加した場合はテキストを更新し、ブックマークを作成する
+        assert_eq!(
+            match_derived_blocks(&[text], &[text, bookmark("https://a")]),
+            vec![Some(0), None]
+        );
+        // URL を差し替えた場合は古いブックマークに対応付けない
+        assert_eq!(
+            match_derived_blocks(
+                &[text, bookmark("https://a"), text],
+                &[text, bookmark("https://b"), text]
+            ),
+            vec![Some(0), None, Some(2)]
+        );
+        // 順序が入れ替わった場合は既存の並びを保てるものだけ対応付ける
+        assert_eq!(
+            match_derived_blocks(
+                &[bookmark("https://a"), bookmark("https://b")],
+                &[bookmark("https://b"), bookmark("https://a")]
+            ),
+            vec![Some(1), None]
+        );
+        // URL を保存する前に同期したブロックは作り直す
+        assert_eq!(
+            match_derived_blocks(
+                &[(BlockKind::Embed, None)],
+                &[(BlockKind::Embed, Some("https://a"))]
+            ),
+            vec![None]
+        );
+        // 表・引用・数式・コードは内容を比較できないため、常に作り直す
+        let table = (BlockKind::Table, None);
+        assert_eq!(
+            match_derived_blocks(&[text, table], &[text, table]),
+            vec![Some(0), None]
+        );
+        let quote = (BlockKind::Blockquote, None);
+        assert_eq!(
+            match_derived_blocks(&[quote, text], &[quote, text]),
+            vec![None, Some(1)]
+        );
+        let equation = (BlockKind::Equation, None);
+        assert_eq!(match_derived_blocks(&[equation], &[equation]), vec![None]);
+        let code = (BlockKind::Code, None);
+        assert_eq!(
+            match_derived_blocks(&[code, (BlockKind::Diagram, None), text], &[code, text]),
+            vec![None, Some(2)]
+        );
+    }
+
+    #[test]
+    fn test_find_orphan_blocks() {
+        let with_id = |mut block: serde_json::Value, id: &str| {
+            block["id"] = serde_json::json!(id);
+            block
+        };
+        let page_blocks = vec![
+            with_id(heading_block_json("template"), "template"),
+            with_id(crate::summary::summary_placeholder_block(), "summary"),
+            with_id(notice_block_json("synced"), "aaaa-bbbb"),
+            with_id(notice_block_json("manual"), "manual"),
+            with_id(time_section_heading_block(), "time"),
+            with_id(notice_block_json("synced"), "cccc"),
+        ];
+        let tracked: HashSet<String> = ["aaaabbbb", "cccc"]
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        assert_eq!(find_orphan_blocks(&page_blocks, &tracked), vec!["manual"]);
+        assert!(find_orphan_blocks(&page_blocks, &HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let rules = url_parser::compile_url_rules(&[], &["link".to_string()]).unwrap();
+        let render = |text| url_parser::build_rich_text_and_url_blocks(text, &rules).blocks;
+
+        assert_eq!(
+            content_hash(&render("hello https://example.com")),
+            content_hash(&render("hello https://example.com"))
+        );
+        assert_ne!(
+            content_hash(&render("hello https://example.com")),
+            content_hash(&render("hello https://example.org"))
+        );
+    }
+
+    #[test]
+    fn test_section_heading_text() {
+        let tz = chrono_tz::Asia::Tokyo;
+        let hour = Duration::from_secs(60 * 60);
+        // 2025-01-01 05:30:00 UTC = 14:30 JST
+        let posted_at = 1_735_709_400;
+
+        assert_eq!(
+            section_heading_text(posted_at - 3600, posted_at, hour, &tz),
+            Some("14:00".to_string())
+        );
+        assert_eq!(
+            section_heading_text(posted_at - 3599, posted_at, hour, &tz),
+            None
+        );
+        // 前回より古いメッセージを後から同期した場合は挟まない
+        assert_eq!(
+            section_heading_text(posted_at + 3600, posted_at, hour, &tz),
+            None
+        );
+    }
+
+    #[test]
+    fn test_user_filter_allows() {
+        let everyone = UserFilter {
+            sync_users: vec![],
+            ignore_users: vec![2],
+        };
+        assert!(everyone.allows(1));
+        assert!(!everyone.allows(2));
+
+        let only = UserFilter {
+            sync_users: vec![1, 2],
+            ignore_users: vec![2],
+        };
+        assert!(only.allows(1));
+        assert!(!only.allows(2));
+        assert!(!only.allows(3));
+    }
+
+    #[test]
+    fn test_replace_extension() {
+        assert_eq!(replace_extension("photo.heic", "jpg"), "photo.jpg");
+        assert_eq!(replace_extension("image.HEIC", "jpg"), "image.jpg");
+        assert_eq!(replace_extension("my.photo.heic", "jpg"), "my.photo.jpg");
+        assert_eq!(replace_extension("noextension", "jpg"), "noextension.jpg");
+    }
+}
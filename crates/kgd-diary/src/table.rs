@@ -0,0 +1,253 @@
+//! メッセージ本文中の Markdown の表を Notion の table ブロックに変換する。
+//!
+//! ヘッダー行・区切り行（`| --- | :-: |` など）・本文の行からなる GitHub 形式の表だけを扱う。
+//! 区切り行が無いものや、列数がヘッダーと合わないものは表として扱わず、テキストのまま同期する。
+
+/// 1 つの table ブロックに入れられる行数の上限（Notion API の子ブロック数の制限）。
+const MAX_TABLE_ROWS: usize = 100;
+
+/// 本文を表とそれ以外のテキストに分けた部分。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentPart {
+    /// 表以外のテキスト
+    Text(String),
+    /// 表（先頭の行がヘッダー）
+    Table(Table),
+}
+
+/// 本文から取り出した表。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    /// ヘッダーを含むすべての行（各行のセル数は列数に揃える）
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// 列数を返す。
+    pub fn width(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    /// 先頭の行を列ヘッダーにした table ブロックを作成する。
+    pub fn to_block(&self) -> serde_json::Value {
+        let rows: Vec<serde_json::Value> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let cells: Vec<serde_json::Value> = row
+                    .iter()
+                    .map(|cell| {
+                        if cell.is_empty() {
+                            serde_json::json!([])
+                        } else {
+                            serde_json::json!([{
+                                "type": "text",
+                                "text": {
+                                    "content": cell
+                                }
+                            }])
+                        }
+                    })
+                    .collect();
+                serde_json::json!({
+                    "object": "block",
+                    "type": "table_row",
+                    "table_row": {
+                        "cells": cells
+                    }
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "object": "block",
+            "type": "table",
+            "table": {
+                "table_width": self.width(),
+                "has_column_header": true,
+                "has_row_header": false,
+                "children": rows
+            }
+        })
+    }
+}
+
+/// 本文を表とそれ以外のテキストに分ける。
+///
+/// 表の前後の空行は取り除き、空になったテキストは含めない。表が無い場合は本文をそのまま 1 つのテキストとして返す。
+pub fn split_tables(text: &str) -> Vec<ContentPart> {
+    if !text.contains('|') {
+        return vec![ContentPart::Text(text.to_string())];
+    }
+
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut parts = Vec::new();
+    let mut text_start = 0;
+    let mut index = 0;
+
+    while index < lines.len() {
+        let Some((table, row_count)) = parse_table(&lines[index..]) else {
+            index += 1;
+            continue;
+        };
+        push_text(&mut parts, &lines[text_start..index]);
+        parts.push(ContentPart::Table(table));
+        index += row_count;
+        text_start = index;
+    }
+    if text_start == 0 {
+        return vec![ContentPart::Text(text.to_string())];
+    }
+    push_text(&mut parts, &lines[text_start..]);
+    parts
+}
+
+/// 表の前後のテキストを、空行を取り除いて追加する。
+fn push_text(parts: &mut Vec<ContentPart>, lines: &[&str]) {
+    let text = lines.join("\n");
+    let text = text.trim_matches('\n');
+    if !text.trim().is_empty() {
+        parts.push(ContentPart::Text(text.to_string()));
+    }
+}
+
+/// 先頭の行から始まる表を解釈し、表と使った行数を返す。
+fn parse_table(lines: &[&str]) -> Option<(Table, usize)> {
+    let [header, separator, ..] = lines else {
+        return None;
+    };
+    if !header.contains('|') {
+        return None;
+    }
+    let header = split_row(header);
+    let width = header.len();
+    if width == 0 || !is_separator_row(separator, width) {
+        return None;
+    }
+
+    let mut rows = vec![header];
+    rows.extend(
+        lines[2..]
+            .iter()
+            .take_while(|line| line.contains('|') && !line.trim().is_empty())
+            .map(|line| {
+                let mut row = split_row(line);
+                row.resize(width, String::new());
+                row
+            }),
+    );
+    if rows.len() > MAX_TABLE_ROWS {
+        return None;
+    }
+
+    let row_count = rows.len() + 1;
+    Some((Table { rows }, row_count))
+}
+
+/// 行をセルに分ける。先頭と末尾の `|` は区切りとして扱わず、`\|` はセル内の `|` として扱う。
+fn split_row(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = match line.strip_suffix('|') {
+        Some(stripped) if !stripped.ends_with('\\') => stripped,
+        _ => line,
+    };
+
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+/// ヘッダーと同じ列数の区切り行（`---`・`:--`・`--:`・`:-:` のセル）かどうかを返す。
+fn is_separator_row(line: &str, width: usize) -> bool {
+    if !line.contains('-') {
+        return false;
+    }
+    let cells = split_row(line);
+    cells.len() == width
+        && cells.iter().all(|cell| {
+            let dashes = cell.strip_prefix(':').unwrap_or(cell);
+            let dashes = dashes.strip_suffix(':').unwrap_or(dashes);
+            !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(|cell| cell.to_string()).collect()
+    }
+
+    #[test]
+    fn test_split_tables() {
+        let text = "today\n\n| task | time |\n| --- | ---: |\n| docs | 1h |\n| review |\n\ndone";
+        assert_eq!(
+            split_tables(text),
+            vec![
+                ContentPart::Text("today".to_string()),
+                ContentPart::Table(Table {
+                    rows: vec![
+                        row(&["task", "time"]),
+                        row(&["docs", "1h"]),
+                        row(&["review", ""]),
+                    ],
+                }),
+                ContentPart::Text("done".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_tables_without_table() {
+        for text in [
+            "a | b",
+            "a | b\nc | d",
+            "| a | b |\n| --- |\n| c | d |",
+            "| a | b |\n| x | y |",
+        ] {
+            assert_eq!(
+                split_tables(text),
+                vec![ContentPart::Text(text.to_string())]
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_row() {
+        assert_eq!(split_row("| a | b |"), row(&["a", "b"]));
+        assert_eq!(split_row("a|b"), row(&["a", "b"]));
+        assert_eq!(split_row(r"| a \| b | c |"), row(&["a | b", "c"]));
+        assert_eq!(split_row("| a | |"), row(&["a", ""]));
+    }
+
+    #[test]
+    fn test_table_block() {
+        let table = Table {
+            rows: vec![row(&["task", "time"]), row(&["docs", ""])],
+        };
+        let block = table.to_block();
+        assert_eq!(block["table"]["table_width"], 2);
+        assert_eq!(block["table"]["has_column_header"], true);
+        let rows = block["table"]["children"].as_array().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[1]["table_row"]["cells"][0][0]["text"]["content"],
+            "docs"
+        );
+        assert_eq!(rows[1]["table_row"]["cells"][1], serde_json::json!([]));
+    }
+}
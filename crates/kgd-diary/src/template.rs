@@ -0,0 +1,228 @@
+//! 日報ページ・スレッドのタイトルや、ページの作成時に挿入する雛形ブロックを扱う。
+//!
+//! タイトルと雛形ブロックの文字列では次のプレースホルダーを日報の日付に置換する。
+//! `{{date}}`（YYYY-MM-DD）、`{{year}}`、`{{month}}`、`{{day}}`、
+//! `{{weekday}}` / `{{weekday_ja}}`（月〜日）、`{{weekday_en}}`（Mon〜Sun）、
+//! `{{iso_year}}`、`{{iso_week}}`（ISO 8601 の週番号、2 桁）、
+//! `{{era}}`（令和6年、元年は「令和元年」）、`{{era_name}}`（令和）、`{{era_year}}`（6）
+
+use anyhow::{Context as _, Result, bail};
+use chrono::{Datelike, NaiveDate};
+use serde_json::Value;
+
+/// `{{weekday}}` に使う曜日名（月曜日始まり）。
+const WEEKDAY_NAMES: [&str; 7] = ["月", "火", "水", "木", "金", "土", "日"];
+
+/// 和暦の元号と開始日（新しい順）。
+const ERAS: [(&str, i32, u32, u32); 5] = [
+    ("令和", 2019, 5, 1),
+    ("平成", 1989, 1, 8),
+    ("昭和", 1926, 12, 25),
+    ("大正", 1912, 7, 30),
+    ("明治", 1868, 1, 25),
+];
+
+/// 日報ページの雛形ブロック。
+#[derive(Debug, Clone)]
+pub struct PageTemplate {
+    /// Notion ブロック JSON の一覧
+    blocks: Vec<Value>,
+}
+
+impl PageTemplate {
+    /// 日報の日付を埋め込んだブロックの一覧を返す。
+    pub fn render(&self, date: NaiveDate) -> Vec<Value> {
+        self.blocks
+            .iter()
+            .cloned()
+            .map(|mut block| {
+                replace_placeholders(&mut block, date);
+                block
+            })
+            .collect()
+    }
+}
+
+/// タイトルのフォーマットに日報の日付を埋め込む。
+pub fn render_title(format: &str, date: NaiveDate) -> String {
+    expand_placeholders(format, date)
+}
+
+/// Notion ページのタイトルのフォーマットを検証する。
+///
+/// タイトルで日報ページを検索するため、日付を一意に表すプレースホルダーを含まない場合はエラーとして返す。
+pub fn validate_page_title_format(format: &str) -> Result<()> {
+    let has_year = format.contains("{{year}}") || format.contains("{{era}}");
+    let has_date = format.contains("{{date}}")
+        || (has_year && format.contains("{{month}}") && format.contains("{{day}}"));
+    if !has_date {
+        bail!(
+            "Page title format must contain {{{{date}}}} or all of {{{{year}}}} (or {{{{era}}}}), {{{{month}}}} and {{{{day}}}}: {}",
+            format
+        );
+    }
+    Ok(())
+}
+
+/// 設定の雛形ブロック（Notion ブロック JSON の配列）を読み込む。
+///
+/// 未設定の場合は `None` を返す。配列でない場合や `type` を持たないブロックはエラーとして返す。
+pub fn compile_page_template(template: Option<&str>) -> Result<Option<PageTemplate>> {
+    let Some(template) = template else {
+        return Ok(None);
+    };
+
+    let blocks: Vec<Value> =
+        serde_json::from_str(template).context("Page template must be a JSON array of blocks")?;
+    for (index, block) in blocks.iter().enumerate() {
+        if !block["type"].is_string() {
+            bail!("Page template block #{} has no \"type\"", index + 1);
+        }
+    }
+
+    Ok(Some(PageTemplate { blocks }))
+}
+
+/// 文字列に含まれるプレースホルダーを日付に置換する。
+fn expand_placeholders(text: &str, date: NaiveDate) -> String {
+    if !text.contains("{{") {
+        return text.to_string();
+    }
+
+    let weekday_ja = WEEKDAY_NAMES[date.weekday().num_days_from_monday() as usize];
+    let (era_name, era_year) = japanese_era(date);
+    let era = match era_year {
+        1 => format!("{}元年", era_name),
+        year => format!("{}{}年", era_name, year),
+    };
+
+    text.replace("{{date}}", &date.format("%Y-%m-%d").to_string())
+        .replace("{{year}}", &date.format("%Y").to_string())
+        .replace("{{month}}", &date.format("%m").to_string())
+        .replace("{{day}}", &date.format("%d").to_string())
+        .replace("{{weekday}}", weekday_ja)
+        .replace("{{weekday_ja}}", weekday_ja)
+        .replace("{{weekday_en}}", &date.format("%a").to_string())
+        .replace("{{iso_year}}", &date.iso_week().year().to_string())
+        .replace("{{iso_week}}", &format!("{:02}", date.iso_week().week()))
+        .replace("{{era}}", &era)
+        .replace("{{era_name}}", era_name)
+        .replace("{{era_year}}", &era_year.to_string())
+}
+
+/// 日付の和暦の元号と年を返す。
+///
+/// 明治より前の日付は西暦の年をそのまま返す。
+fn japanese_era(date: NaiveDate) -> (&'static str, i32) {
+    ERAS.iter()
+        .find(|(_, year, month, day)| {
+            NaiveDate::from_ymd_opt(*year, *month, *day).is_some_and(|start| date >= start)
+        })
+        .map(|(name, year, _, _)| (*name, date.year() - year + 1))
+        .unwrap_or(("", date.year()))
+}
+
+/// JSON に含まれる文字列のプレースホルダーを再帰的に置換する。
+fn replace_placeholders(value: &mut Value, date: NaiveDate) {
+    match value {
+        Value::String(text) => {
+            *text = expand_placeholders(text, date);
+        }
+        Value::Array(values) => {
+            for value in values {
+                replace_placeholders(value, date);
+            }
+        }
+        Value::Object(map) => {
+            for value in map.values_mut() {
+                replace_placeholders(value, date);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_replaces_placeholders() {
+        let template = compile_page_template(Some(
+            r#"[
+                {"type": "heading_2", "heading_2": {"rich_text": [{"type": "text", "text": {"content": "{{date}} の予定"}}]}},
+                {"type": "to_do", "to_do": {"rich_text": [{"type": "text", "text": {"content": "振り返り（{{date}}）"}}], "checked": false}}
+            ]"#,
+        ))
+        .unwrap()
+        .unwrap();
+
+        let blocks = template.render(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+        assert_eq!(
+            blocks[0]["heading_2"]["rich_text"][0]["text"]["content"],
+            "2025-01-01 の予定"
+        );
+        assert_eq!(
+            blocks[1]["to_do"]["rich_text"][0]["text"]["content"],
+            "振り返り（2025-01-01）"
+        );
+        assert_eq!(blocks[1]["to_do"]["checked"], false);
+    }
+
+    #[test]
+    fn test_render_title() {
+        let date = NaiveDate::from_ymd_opt(2025, 1, 4).unwrap();
+        assert_eq!(render_title("{{date}}", date), "2025-01-04");
+        assert_eq!(
+            render_title("{{month}}/{{day}}（{{weekday}}） 日報", date),
+            "01/04（土） 日報"
+        );
+    }
+
+    #[test]
+    fn test_render_title_locale_placeholders() {
+        let date = NaiveDate::from_ymd_opt(2024, 10, 12).unwrap();
+        assert_eq!(
+            render_title("{{date}} ({{weekday_ja}})", date),
+            "2024-10-12 (土)"
+        );
+        assert_eq!(render_title("{{weekday_en}}", date), "Sat");
+        assert_eq!(render_title("{{iso_year}}-W{{iso_week}}", date), "2024-W41");
+        assert_eq!(
+            render_title("{{era}}{{month}}月{{day}}日", date),
+            "令和6年10月12日"
+        );
+
+        // 年をまたぐ ISO 週と、元号の切り替わり
+        let date = NaiveDate::from_ymd_opt(2024, 12, 30).unwrap();
+        assert_eq!(render_title("{{iso_year}}-W{{iso_week}}", date), "2025-W01");
+        let date = NaiveDate::from_ymd_opt(2019, 4, 30).unwrap();
+        assert_eq!(render_title("{{era}}", date), "平成31年");
+        let date = NaiveDate::from_ymd_opt(2019, 5, 1).unwrap();
+        assert_eq!(
+            render_title("{{era}} {{era_name}} {{era_year}}", date),
+            "令和元年 令和 1"
+        );
+    }
+
+    #[test]
+    fn test_validate_page_title_format() {
+        assert!(validate_page_title_format("{{date}} {{weekday}}").is_ok());
+        assert!(validate_page_title_format("{{year}}年{{month}}月{{day}}日").is_ok());
+        assert!(validate_page_title_format("{{era}}{{month}}月{{day}}日").is_ok());
+        assert!(validate_page_title_format("{{month}}/{{day}}").is_err());
+        assert!(validate_page_title_format("日報").is_err());
+    }
+
+    #[test]
+    fn test_compile_page_template_validates_blocks() {
+        assert!(compile_page_template(None).unwrap().is_none());
+        assert!(compile_page_template(Some(r#"{"type": "divider"}"#)).is_err());
+        assert!(compile_page_template(Some(r#"[{"divider": {}}]"#)).is_err());
+        assert!(
+            compile_page_template(Some(r#"[{"type": "divider", "divider": {}}]"#))
+                .unwrap()
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! 日報スレッドの `!start <作業>` / `!stop` による作業時間の記録。
+//!
+//! `!start` から `!stop` までを 1 件の作業として記録し、日報ページの「Time」セクションに
+//! チェック済みの to_do ブロックとして書き込む。記録した作業時間はクローズ時のサマリーで集計する。
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use sqlx::FromRow;
+
+/// 作業の開始を表すメッセージの書き出し。
+const START_COMMAND: &str = "!start";
+
+/// 作業の終了を表すメッセージの書き出し。
+const STOP_COMMAND: &str = "!stop";
+
+/// 「Time」セクションの見出し。
+const TIME_SECTION_HEADING: &str = "⏱ Time";
+
+/// 作業時間の記録のコマンド。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeCommand {
+    /// 作業を開始する（作業名）
+    Start(String),
+    /// 作業を終了する
+    Stop,
+}
+
+impl TimeCommand {
+    /// メッセージの本文がコマンドであれば解釈する。
+    ///
+    /// `!start` には作業名が必要。`!stop` の後ろの文字列は無視する。
+    pub fn parse(content: &str) -> Option<Self> {
+        let content = content.trim();
+        if let Some(task) = strip_command(content, START_COMMAND) {
+            let task = task.trim();
+            return (!task.is_empty()).then(|| TimeCommand::Start(task.to_string()));
+        }
+        strip_command(content, STOP_COMMAND).map(|_| TimeCommand::Stop)
+    }
+}
+
+/// コマンドで始まる場合に、コマンドの後ろの文字列を返す（`!starting` などは一致しない）。
+fn strip_command<'a>(content: &'a str, command: &str) -> Option<&'a str> {
+    let rest = content.strip_prefix(command)?;
+    (rest.is_empty() || rest.starts_with(char::is_whitespace)).then_some(rest)
+}
+
+/// 記録した作業 1 件。
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct TimeEntry {
+    /// 作業を記録した日報スレッドの ID
+    #[sqlx(try_from = "i64")]
+    pub thread_id: u64,
+    /// `!start` のメッセージ ID
+    #[sqlx(try_from = "i64")]
+    pub message_id: u64,
+    /// 作業名
+    pub task: String,
+    /// 開始日時
+    pub started_at: DateTime<Utc>,
+    /// 終了日時（作業中の場合は None）
+    pub stopped_at: Option<DateTime<Utc>>,
+    /// 日報ページに書き込んだ to_do ブロックの ID（作業中の場合は None）
+    pub block_id: Option<String>,
+}
+
+impl TimeEntry {
+    /// 作業時間を返す（作業中の場合は None）。
+    pub fn duration(&self) -> Option<Duration> {
+        let elapsed = self.stopped_at? - self.started_at;
+        Some(elapsed.to_std().unwrap_or_default())
+    }
+
+    /// 作業を表す to_do ブロックを作成する（作業中の場合は None）。
+    ///
+    /// `作業名 — 1h 23m (09:00–10:23)` の形式で、時刻は `timezone` で表示する。
+    pub fn to_block(&self, timezone: &Tz) -> Option<serde_json::Value> {
+        let stopped_at = self.stopped_at?;
+        let text = format!(
+            "{} — {} ({}–{})",
+            self.task,
+            format_duration(self.duration()?),
+            self.started_at.with_timezone(timezone).format("%H:%M"),
+            stopped_at.with_timezone(timezone).format("%H:%M"),
+        );
+        Some(serde_json::json!({
+            "object": "block",
+            "type": "to_do",
+            "to_do": {
+                "rich_text": [{
+                    "type": "text",
+                    "text": {
+                        "content": text
+                    }
+                }],
+                "checked": true
+            }
+        }))
+    }
+}
+
+/// 「Time」セクションの見出しブロックを作成する。
+pub fn time_section_heading_block() -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "heading_3",
+        "heading_3": {
+            "rich_text": [{
+                "type": "text",
+                "text": {
+                    "content": TIME_SECTION_HEADING
+                }
+            }]
+        }
+    })
+}
+
+/// 「Time」セクションの見出しブロックかどうかを返す。
+pub fn is_time_section_heading(block: &serde_json::Value) -> bool {
+    block["type"] == "heading_3"
+        && block["heading_3"]["rich_text"][0]["text"]["content"] == TIME_SECTION_HEADING
+}
+
+/// 終了した作業の作業時間を作業名ごとに合計し、長い順に返す。
+///
+/// 作業中の作業は含めない。
+pub fn time_totals(entries: &[TimeEntry]) -> Vec<(String, Duration)> {
+    let mut totals: Vec<(String, Duration)> = Vec::new();
+    for entry in entries {
+        let Some(duration) = entry.duration() else {
+            continue;
+        };
+        match totals.iter_mut().find(|(task, _)| *task == entry.task) {
+            Some((_, total)) => *total += duration,
+            None => totals.push((entry.task.clone(), duration)),
+        }
+    }
+    totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    totals
+}
+
+/// 作業時間を `1h 23m` の形式にする（1 分未満は切り捨てる）。
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h {}m", hours, minutes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(task: &str, start: &str, stop: Option<&str>) -> TimeEntry {
+        let at = |time: &str| {
+            DateTime::parse_from_rfc3339(&format!("2025-02-01T{}:00Z", time))
+                .unwrap()
+                .to_utc()
+        };
+        TimeEntry {
+            thread_id: 1,
+            message_id: 2,
+            task: task.to_string(),
+            started_at: at(start),
+            stopped_at: stop.map(at),
+            block_id: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_time_command() {
+        assert_eq!(
+            TimeCommand::parse("!start  write docs "),
+            Some(TimeCommand::Start("write docs".to_string()))
+        );
+        assert_eq!(TimeCommand::parse("!stop"), Some(TimeCommand::Stop));
+        assert_eq!(
+            TimeCommand::parse("!stop done for now"),
+            Some(TimeCommand::Stop)
+        );
+        assert_eq!(TimeCommand::parse("!start"), None);
+        assert_eq!(TimeCommand::parse("!starting"), None);
+        assert_eq!(TimeCommand::parse("!stopped"), None);
+        assert_eq!(TimeCommand::parse("let's !start"), None);
+    }
+
+    #[test]
+    fn test_time_entry_block() {
+        let block = entry("review", "00:00", Some("01:23"))
+            .to_block(&chrono_tz::Asia::Tokyo)
+            .unwrap();
+        assert_eq!(block["to_do"]["checked"], true);
+        assert_eq!(
+            block["to_do"]["rich_text"][0]["text"]["content"],
+            "review — 1h 23m (09:00–10:23)"
+        );
+        assert!(
+            entry("review", "00:00", None)
+                .to_block(&chrono_tz::Asia::Tokyo)
+                .is_none()
+        );
+        assert!(is_time_section_heading(&time_section_heading_block()));
+        assert!(!is_time_section_heading(&block));
+    }
+
+    #[test]
+    fn test_time_totals() {
+        let entries = vec![
+            entry("docs", "00:00", Some("00:30")),
+            entry("review", "01:00", Some("02:00")),
+            entry("docs", "03:00", Some("03:45")),
+            entry("docs", "04:00", None),
+        ];
+        assert_eq!(
+            time_totals(&entries),
+            vec![
+                ("docs".to_string(), Duration::from_secs(75 * 60)),
+                ("review".to_string(), Duration::from_secs(60 * 60)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(59)), "0m");
+        assert_eq!(format_duration(Duration::from_secs(45 * 60)), "45m");
+        assert_eq!(format_duration(Duration::from_secs(2 * 60 * 60)), "2h");
+        assert_eq!(format_duration(Duration::from_secs(83 * 60)), "1h 23m");
+    }
+}
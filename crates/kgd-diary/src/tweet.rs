@@ -0,0 +1,371 @@
+//! X（旧 Twitter）の投稿を syndication API から取得する機能を提供する。
+//!
+//! X のページは OGP を返さないため、埋め込みウィジェット用の syndication API で本文・投稿者・画像を取得する。
+
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{Context as _, Result};
+use futures::future::BoxFuture;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{block::BlockKind, url_handler::UrlHandler, url_parser};
+
+/// syndication API のエンドポイント。
+const SYNDICATION_URL: &str = "https://cdn.syndication.twimg.com/tweet-result";
+
+/// X の投稿。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tweet {
+    /// 投稿者の表示名
+    pub author_name: String,
+    /// 投稿者のユーザー名（`@` を除く）
+    pub screen_name: String,
+    /// 本文（短縮 URL は展開済み）
+    pub text: String,
+    /// 添付画像の URL（動画の場合はサムネイル）
+    pub image_urls: Vec<String>,
+}
+
+/// X の投稿を取得するクライアント。
+pub struct TweetFetcher {
+    http_client: reqwest::Client,
+}
+
+impl TweetFetcher {
+    /// 新しい TweetFetcher を作成する。
+    pub fn new(timeout: Duration) -> Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .user_agent("kgd-bot/1.0")
+            .build()
+            .context("Failed to create HTTP client for tweet fetcher")?;
+
+        Ok(Self { http_client })
+    }
+
+    /// 投稿の URL から投稿を取得する。
+    ///
+    /// 取得に失敗した場合は None を返す（エラーはログに記録）。
+    pub async fn fetch(&self, url: &str) -> Option<Tweet> {
+        let Some(id) = parse_tweet_id(url) else {
+            tracing::debug!(url = %url, "URL is not a tweet URL");
+            return None;
+        };
+
+        match self.fetch_inner(id).await {
+            Ok(tweet) => Some(tweet),
+            Err(e) => {
+                tracing::debug!(url = %url, error = %e, "Failed to fetch tweet");
+                None
+            }
+        }
+    }
+
+    /// 複数の URL から投稿を並列で取得する。
+    pub async fn fetch_many(&self, urls: &[String]) -> HashMap<String, Tweet> {
+        let futures: Vec<_> = urls
+            .iter()
+            .map(|url| async {
+                let tweet = self.fetch(url).await;
+                (url.clone(), tweet)
+            })
+            .collect();
+
+        futures::future::join_all(futures)
+            .await
+            .into_iter()
+            .filter_map(|(url, tweet)| tweet.map(|t| (url, t)))
+            .collect()
+    }
+
+    async fn fetch_inner(&self, id: u64) -> Result<Tweet> {
+        let response = self
+            .http_client
+            .get(SYNDICATION_URL)
+            .query(&[
+                ("id", id.to_string()),
+                ("lang", "ja".to_string()),
+                ("token", syndication_token(id)),
+            ])
+            .send()
+            .await
+            .context("HTTP request failed")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("HTTP status: {}", response.status());
+        }
+
+        let tweet: SyndicationTweet = response.json().await.context(
+            "Failed to parse syndication response (the tweet may be deleted or protected)",
+        )?;
+
+        Ok(tweet.into_tweet())
+    }
+}
+
+/// X の投稿の引用ブロックを、投稿者・本文・画像を引用するブロックに置き換える。
+impl UrlHandler for TweetFetcher {
+    fn name(&self) -> &'static str {
+        "tweet"
+    }
+
+    fn matches(&self, url: &str, kind: BlockKind) -> bool {
+        kind == BlockKind::Quote && parse_tweet_id(url).is_some()
+    }
+
+    fn build_block<'a>(
+        &'a self,
+        url: &'a str,
+        block: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<serde_json::Value>> {
+        Box::pin(async move {
+            let id = parse_tweet_id(url).context("URL is not a tweet URL")?;
+            let tweet = self.fetch_inner(id).await?;
+            let mut block = block.clone();
+            url_parser::apply_tweet_to_quote(&mut block, &tweet);
+            Ok(block)
+        })
+    }
+}
+
+/// X の投稿の URL から投稿 ID を取り出す。
+pub fn parse_tweet_id(url: &str) -> Option<u64> {
+    let re =
+        Regex::new(r"^https?://(?:www\.|mobile\.)?(?:x|twitter)\.com/[^/?#]+/status(?:es)?/(\d+)")
+            .unwrap();
+    re.captures(url)
+        .and_then(|caps| caps.get(1))
+        .and_then(|id| id.as_str().parse().ok())
+}
+
+/// syndication API のレスポンス。
+#[derive(Debug, Deserialize)]
+struct SyndicationTweet {
+    /// 本文（末尾に添付メディアの短縮 URL を含む）
+    text: String,
+    /// 本文のうち表示する範囲（コードポイント単位）
+    #[serde(default)]
+    display_text_range: Option<[usize; 2]>,
+    /// 投稿者
+    user: SyndicationUser,
+    /// 本文中の URL などのエンティティ
+    #[serde(default)]
+    entities: SyndicationEntities,
+    /// 添付メディア
+    #[serde(default, rename = "mediaDetails")]
+    media_details: Vec<SyndicationMedia>,
+}
+
+/// 投稿者の情報。
+#[derive(Debug, Deserialize)]
+struct SyndicationUser {
+    name: String,
+    screen_name: String,
+}
+
+/// 本文中のエンティティ。
+#[derive(Debug, Default, Deserialize)]
+struct SyndicationEntities {
+    #[serde(default)]
+    urls: Vec<SyndicationUrl>,
+}
+
+/// 本文中の短縮 URL と展開後の URL。
+#[derive(Debug, Deserialize)]
+struct SyndicationUrl {
+    url: String,
+    expanded_url: String,
+}
+
+/// 添付メディア。
+#[derive(Debug, Deserialize)]
+struct SyndicationMedia {
+    /// 画像の URL（動画の場合はサムネイル）
+    media_url_https: String,
+}
+
+impl SyndicationTweet {
+    /// 表示範囲の本文を取り出し、短縮 URL を展開した投稿に変換する。
+    fn into_tweet(self) -> Tweet {
+        let mut text = match self.display_text_range {
+            Some([start, end]) => self
+                .text
+                .chars()
+                .skip(start)
+                .take(end.saturating_sub(start))
+                .collect(),
+            None => self.text,
+        };
+        for url in &self.entities.urls {
+            text = text.replace(&url.url, &url.expanded_url);
+        }
+
+        Tweet {
+            author_name: self.user.name,
+            screen_name: self.user.screen_name,
+            text: text.trim().to_string(),
+            image_urls: self
+                .media_details
+                .into_iter()
+                .map(|media| media.media_url_https)
+                .collect(),
+        }
+    }
+}
+
+/// syndication API が要求するトークンを投稿 ID から求める。
+///
+/// 埋め込みウィジェットと同じく `(id / 1e15 * π).toString(36)` から `0` と `.` を取り除いたもの。
+fn syndication_token(id: u64) -> String {
+    let value = (id as f64 / 1e15) * std::f64::consts::PI;
+    to_radix_string(value, 36)
+        .chars()
+        .filter(|c| !matches!(c, '0' | '.'))
+        .collect()
+}
+
+/// 正の浮動小数点数を JavaScript の `Number.prototype.toString(radix)` と同じ表記に変換する。
+///
+/// 小数部は元の値と区別できる最短の桁数まで出力する。
+fn to_radix_string(value: f64, radix: u32) -> String {
+    const DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let radix_f = f64::from(radix);
+
+    let mut integer = value.floor();
+    let mut fraction = value - integer;
+    let mut delta = (0.5 * (value.next_up() - value)).max(0.0_f64.next_up());
+    let mut fraction_digits: Vec<u32> = Vec::new();
+
+    if fraction >= delta {
+        loop {
+            fraction *= radix_f;
+            delta *= radix_f;
+            let digit = fraction as u32;
+            fraction_digits.push(digit);
+            fraction -= f64::from(digit);
+
+            // 残りが半分を超え、切り上げても元の値と区別できる場合は繰り上げて終える
+            if (fraction > 0.5 || (fraction == 0.5 && digit & 1 == 1)) && fraction + delta > 1.0 {
+                loop {
+                    match fraction_digits.pop() {
+                        Some(digit) if digit + 1 < radix => {
+                            fraction_digits.push(digit + 1);
+                            break;
+                        }
+                        Some(_) => {}
+                        None => {
+                            integer += 1.0;
+                            break;
+                        }
+                    }
+                }
+                break;
+            }
+
+            if fraction < delta {
+                break;
+            }
+        }
+    }
+
+    let mut integer = integer as u64;
+    let mut integer_digits = Vec::new();
+    loop {
+        integer_digits.push((integer % u64::from(radix)) as usize);
+        integer /= u64::from(radix);
+        if integer == 0 {
+            break;
+        }
+    }
+
+    let mut result: String = integer_digits
+        .into_iter()
+        .rev()
+        .map(|digit| char::from(DIGITS[digit]))
+        .collect();
+    if !fraction_digits.is_empty() {
+        result.push('.');
+        result.extend(
+            fraction_digits
+                .into_iter()
+                .map(|digit| char::from(DIGITS[digit as usize])),
+        );
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tweet_id() {
+        assert_eq!(parse_tweet_id("https://x.com/jack/status/20"), Some(20));
+        assert_eq!(
+            parse_tweet_id("https://twitter.com/rustlang/status/1585341984679469056?s=20"),
+            Some(1585341984679469056)
+        );
+        assert_eq!(
+            parse_tweet_id("https://mobile.twitter.com/user/statuses/123/photo/1"),
+            Some(123)
+        );
+        assert_eq!(parse_tweet_id("https://x.com/jack"), None);
+        assert_eq!(parse_tweet_id("https://example.com/jack/status/20"), None);
+    }
+
+    #[test]
+    fn test_syndication_token() {
+        // ブラウザの `(id / 1e15 * Math.PI).toString(36).replace(/(0+|\.)/g, '')` と同じ結果になる
+        assert_eq!(syndication_token(20), "6dq1a2xwd93");
+        assert_eq!(syndication_token(1585341984679469056), "3uchycv2wqc");
+        assert_eq!(syndication_token(1234567890123456789), "2zqic77uqyk");
+        assert_eq!(syndication_token(1800000000000000000), "4d2v7cbm2xj");
+    }
+
+    #[test]
+    fn test_to_radix_string() {
+        assert_eq!(to_radix_string(0.5, 2), "0.1");
+        assert_eq!(to_radix_string(255.0, 16), "ff");
+        assert_eq!(to_radix_string(35.5, 36), "z.i");
+    }
+
+    #[test]
+    fn test_syndication_tweet_into_tweet() {
+        let json = serde_json::json!({
+            "__typename": "Tweet",
+            "text": "見て https://t.co/abc かわいい https://t.co/media",
+            "display_text_range": [0, 24],
+            "user": { "name": "ねこ", "screen_name": "neko" },
+            "entities": {
+                "urls": [{ "url": "https://t.co/abc", "expanded_url": "https://example.com/cat" }]
+            },
+            "mediaDetails": [
+                { "type": "photo", "media_url_https": "https://pbs.twimg.com/media/a.jpg" },
+                { "type": "video", "media_url_https": "https://pbs.twimg.com/media/b.jpg" }
+            ]
+        });
+        let tweet = serde_json::from_value::<SyndicationTweet>(json)
+            .unwrap()
+            .into_tweet();
+
+        assert_eq!(
+            tweet,
+            Tweet {
+                author_name: "ねこ".to_string(),
+                screen_name: "neko".to_string(),
+                text: "見て https://example.com/cat かわいい".to_string(),
+                image_urls: vec![
+                    "https://pbs.twimg.com/media/a.jpg".to_string(),
+                    "https://pbs.twimg.com/media/b.jpg".to_string(),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_syndication_tombstone_is_rejected() {
+        let json = serde_json::json!({ "__typename": "TweetTombstone", "tombstone": {} });
+        assert!(serde_json::from_value::<SyndicationTweet>(json).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! URL から生成したブロックをリッチ化するハンドラーを提供する。
+//!
+//! URL ルールで生成したブックマーク・引用などのブロックは、まず URL だけを持つブロックとして作り、
+//! 同期時に [`UrlHandler`] が外部から取得した情報（OGP・GitHub API・X の投稿など）で置き換える。
+//! 新しいサイトに対応する場合は [`UrlHandler`] を実装して
+//! [`MessageSyncer::with_url_handler`](crate::MessageSyncer::with_url_handler) で登録する。
+//!
+//! ブロックの種類と URL は変えずに内容だけを置き換えるため、メッセージの編集時の
+//! 既存ブロックとの対応付けや、本文の変更の検出には影響しない。
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+
+use crate::block::BlockKind;
+
+/// URL から生成したブロックをリッチ化するハンドラー。
+pub trait UrlHandler: Send + Sync {
+    /// ハンドラーの名前（ログ用）。
+    fn name(&self) -> &'static str;
+
+    /// このハンドラーで扱うブロックかどうかを返す。
+    ///
+    /// # Arguments
+    /// * `url` - ブロックの元になった URL
+    /// * `kind` - URL ルールで生成したブロックの種類
+    fn matches(&self, url: &str, kind: BlockKind) -> bool;
+
+    /// URL から取得した情報で、URL ルールで生成したブロックを置き換えるブロックを作る。
+    ///
+    /// 作るブロックは `block` と同じ種類にする。
+    fn build_block<'a>(
+        &'a self,
+        url: &'a str,
+        block: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<serde_json::Value>>;
+}
+
+/// 優先順にハンドラーを試す URL ハンドラーのレジストリ。
+#[derive(Default)]
+pub struct UrlHandlers {
+    handlers: Vec<Box<dyn UrlHandler>>,
+}
+
+impl UrlHandlers {
+    /// ハンドラーを既存のハンドラーより低い優先度で追加する。
+    pub fn push(&mut self, handler: impl UrlHandler + 'static) {
+        self.handlers.push(Box::new(handler));
+    }
+
+    /// ハンドラーを既存のハンドラーより高い優先度で追加する。
+    pub fn push_front(&mut self, handler: impl UrlHandler + 'static) {
+        self.handlers.insert(0, Box::new(handler));
+    }
+
+    /// ブロックを扱えるハンドラーを優先順に試し、最初に成功したハンドラーのブロックを返す。
+    ///
+    /// 扱えるハンドラーがない場合や、すべて失敗した場合は None を返す（エラーはログに記録）。
+    pub async fn build_block(
+        &self,
+        url: &str,
+        kind: BlockKind,
+        block: &serde_json::Value,
+    ) -> Option<serde_json::Value> {
+        for handler in self.handlers.iter().filter(|h| h.matches(url, kind)) {
+            match handler.build_block(url, block).await {
+                Ok(built) => return Some(built),
+                Err(e) => {
+                    tracing::debug!(
+                        handler = handler.name(),
+                        url = %url,
+                        error = %e,
+                        "URL handler failed to build block"
+                    );
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedHandler {
+        prefix: &'static str,
+        caption: &'static str,
+    }
+
+    impl UrlHandler for FixedHandler {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+
+        fn matches(&self, url: &str, kind: BlockKind) -> bool {
+            kind == BlockKind::Bookmark && url.starts_with(self.prefix)
+        }
+
+        fn build_block<'a>(
+            &'a self,
+            _url: &'a str,
+            block: &'a serde_json::Value,
+        ) -> BoxFuture<'a, Result<serde_json::Value>> {
+            Box::pin(async move {
+                if self.caption.is_empty() {
+                    anyhow::bail!("not found");
+                }
+                let mut block = block.clone();
+                block["bookmark"]["caption"] = serde_json::json!(self.caption);
+                Ok(block)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_url_handlers_first_success_wins() {
+        let mut handlers = UrlHandlers::default();
+        handlers.push(FixedHandler {
+            prefix: "https://example.com/",
+            caption: "any",
+        });
+        handlers.push_front(FixedHandler {
+            prefix: "https://example.com/a",
+            caption: "a",
+        });
+        handlers.push_front(FixedHandler {
+            prefix: "https://example.com/broken",
+            caption: "",
+        });
+
+        let block = serde_json::json!({ "bookmark": { "caption": [] } });
+        let caption = |built: Option<serde_json::Value>| {
+            built.and_then(|b| b["bookmark"]["caption"].as_str().map(str::to_string))
+        };
+        assert_eq!(
+            caption(
+                handlers
+                    .build_block("https://example.com/a/1", BlockKind::Bookmark, &block)
+                    .await
+            )
+            .as_deref(),
+            Some("a")
+        );
+        // 失敗したハンドラーの次に扱えるハンドラーを試す
+        assert_eq!(
+            caption(
+                handlers
+                    .build_block("https://example.com/broken", BlockKind::Bookmark, &block)
+                    .await
+            )
+            .as_deref(),
+            Some("any")
+        );
+        assert!(
+            handlers
+                .build_block("https://example.com/a/1", BlockKind::Embed, &block)
+                .await
+                .is_none()
+        );
+        assert!(
+            handlers
+                .build_block("https://other.example.com/", BlockKind::Bookmark, &block)
+                .await
+                .is_none()
+        );
+    }
+}
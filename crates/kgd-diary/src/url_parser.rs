@@ -1,11 +1,13 @@
 //! メッセージテキスト内の URL を解析し、Notion ブロック構築用のセグメントに分割する。
 
-use anyhow::{Result, bail};
+use anyhow::{Context as _, Result, bail};
 use regex::Regex;
 
 use crate::config::{PatternConfig, UrlRuleConfig};
 
+use super::block::BlockKind;
 use super::ogp::OgpMetadata;
+use super::tweet::Tweet;
 
 /// URL から生成する変換の種類。
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -16,6 +18,10 @@ pub enum UrlBlockType {
     Bookmark,
     /// Notion 埋め込みブロック
     Embed,
+    /// X の投稿の本文・投稿者・画像を展開した Notion 引用ブロック
+    Tweet,
+    /// リンク先のサムネイル（og:image）をアップロードした Notion 画像ブロック
+    Image,
 }
 
 /// URL マッチング方法。
@@ -45,6 +51,20 @@ struct UrlRule {
     matcher: UrlMatcher,
     /// 生成するブロックタイプのリスト
     block_types: Vec<UrlBlockType>,
+    /// ブロックを生成する前に URL を書き換える置換テンプレート（正規表現パターンのみ）
+    rewrite: Option<String>,
+}
+
+impl UrlRule {
+    /// 置換テンプレートがあれば、パターンにマッチした部分をすべて置換した URL を返す。
+    fn rewrite_url(&self, url: &str) -> String {
+        match (&self.matcher, &self.rewrite) {
+            (UrlMatcher::Regex(re), Some(rewrite)) => {
+                re.replace_all(url, rewrite.as_str()).into_owned()
+            }
+            _ => url.to_string(),
+        }
+    }
 }
 
 /// コンパイル済み URL 変換ルール一式。
@@ -53,21 +73,33 @@ pub struct CompiledUrlRules {
     rules: Vec<UrlRule>,
     /// どのルールにもマッチしなかった URL に適用するデフォルトの変換
     default_types: Vec<UrlBlockType>,
+    /// URL から既知のトラッキングパラメータを取り除くか
+    strip_tracking_params: bool,
+}
+
+impl CompiledUrlRules {
+    /// URL から既知のトラッキングパラメータを取り除くかを設定する。
+    pub fn strip_tracking_params(mut self, enabled: bool) -> Self {
+        self.strip_tracking_params = enabled;
+        self
+    }
 }
 
 /// URL 解析結果のブロック。出現順に並ぶ。
 pub struct UrlParseResult {
-    /// 出現順の Notion ブロック JSON と block_type 文字列のペア
-    pub blocks: Vec<(serde_json::Value, String)>,
+    /// 出現順の Notion ブロック JSON とブロックの種類のペア
+    pub blocks: Vec<(serde_json::Value, BlockKind)>,
     /// Bookmark として処理された URL のリスト（OGP 取得対象）
     pub bookmark_urls: Vec<String>,
+    /// Tweet として処理された URL のリスト（投稿の取得対象）
+    pub tweet_urls: Vec<String>,
 }
 
 /// 設定からコンパイル済み URL ルールを作成する。
 ///
 /// 各ルールの `expect_matches` / `expect_no_matches` によるバリデーションも行い、
 /// 期待通りでない場合はエラーを返す。
-/// 無効なパターンや不明なブロックタイプはエラーとして返す。
+/// 無効なパターンや不明なブロックタイプ、正規表現以外のパターンへの `rewrite` はエラーとして返す。
 pub fn compile_url_rules(
     rules: &[UrlRuleConfig],
     default_convert_to: &[String],
@@ -109,6 +141,13 @@ pub fn compile_url_rules(
             }
         }
 
+        if rule.rewrite.is_some() && !matches!(matcher, UrlMatcher::Regex(_)) {
+            bail!(
+                "rewrite requires a regex pattern, but pattern is {:?}",
+                rule.pattern
+            );
+        }
+
         // expect_no_matches のバリデーション
         for url in &rule.expect_no_matches {
             if matcher.is_match(url) {
@@ -120,10 +159,26 @@ pub fn compile_url_rules(
             }
         }
 
-        compiled_rules.push(UrlRule {
+        let compiled_rule = UrlRule {
             matcher,
             block_types,
-        });
+            rewrite: rule.rewrite.clone(),
+        };
+
+        // 書き換え後も URL として扱えるか、expect_matches の URL で確認する
+        for url in &rule.expect_matches {
+            let rewritten = compiled_rule.rewrite_url(url);
+            if !rewritten.starts_with("https://") && !rewritten.starts_with("http://") {
+                bail!(
+                    "URL pattern {:?} rewrites '{}' to '{}', which is not an http(s) URL",
+                    rule.pattern,
+                    url,
+                    rewritten
+                );
+            }
+        }
+
+        compiled_rules.push(compiled_rule);
     }
 
     let default_types = default_convert_to
@@ -134,6 +189,7 @@ pub fn compile_url_rules(
     Ok(CompiledUrlRules {
         rules: compiled_rules,
         default_types,
+        strip_tracking_params: false,
     })
 }
 
@@ -143,9 +199,10 @@ pub fn compile_url_rules(
 /// bookmark/embed が出現する位置で paragraph を分割して順序を保持する。
 pub fn build_rich_text_and_url_blocks(text: &str, compiled: &CompiledUrlRules) -> UrlParseResult {
     let segments = parse_segments(text);
-    let mut blocks: Vec<(serde_json::Value, String)> = Vec::new();
+    let mut blocks: Vec<(serde_json::Value, BlockKind)> = Vec::new();
     let mut pending_rich_text: Vec<serde_json::Value> = Vec::new();
     let mut bookmark_urls: Vec<String> = Vec::new();
+    let mut tweet_urls: Vec<String> = Vec::new();
 
     for segment in segments {
         match segment {
@@ -161,6 +218,10 @@ pub fn build_rich_text_and_url_blocks(text: &str, compiled: &CompiledUrlRules) -
             }
             TextSegment::Url(url) => {
                 let block_types = classify_url(&url, compiled);
+                let mut url = rewrite_url(&url, compiled);
+                if compiled.strip_tracking_params {
+                    url = strip_tracking_params(&url);
+                }
 
                 // インラインリンクは pending_rich_text に追加
                 let has_link = block_types.contains(&UrlBlockType::Link);
@@ -168,10 +229,8 @@ pub fn build_rich_text_and_url_blocks(text: &str, compiled: &CompiledUrlRules) -
                     pending_rich_text.push(inline_link_json(&url));
                 }
 
-                // bookmark/embed の前に溜まった rich_text を paragraph として flush
-                let has_standalone = block_types
-                    .iter()
-                    .any(|t| matches!(t, UrlBlockType::Bookmark | UrlBlockType::Embed));
+                // bookmark/embed/tweet/image の前に溜まった rich_text を paragraph として flush
+                let has_standalone = block_types.iter().any(|t| *t != UrlBlockType::Link);
                 if has_standalone {
                     flush_paragraph(&mut pending_rich_text, &mut blocks);
                 }
@@ -181,10 +240,17 @@ pub fn build_rich_text_and_url_blocks(text: &str, compiled: &CompiledUrlRules) -
                         UrlBlockType::Link => {} // 上で処理済み
                         UrlBlockType::Bookmark => {
                             bookmark_urls.push(url.clone());
-                            blocks.push((bookmark_block_json(&url), "bookmark".to_string()));
+                            blocks.push((bookmark_block_json(&url), BlockKind::Bookmark));
                         }
                         UrlBlockType::Embed => {
-                            blocks.push((embed_block_json(&url), "embed".to_string()));
+                            blocks.push((embed_block_json(&url), BlockKind::Embed));
+                        }
+                        UrlBlockType::Tweet => {
+                            tweet_urls.push(url.clone());
+                            blocks.push((tweet_block_json(&url), BlockKind::Quote));
+                        }
+                        UrlBlockType::Image => {
+                            blocks.push((ogp_image_block_json(&url), BlockKind::OgpImage));
                         }
                     }
                 }
@@ -203,13 +269,34 @@ pub fn build_rich_text_and_url_blocks(text: &str, compiled: &CompiledUrlRules) -
     UrlParseResult {
         blocks,
         bookmark_urls,
+        tweet_urls,
     }
 }
 
+/// テキストの URL をインラインリンクにした rich_text 要素を生成する。
+///
+/// 引用の中など、ブロックを分けられない位置のテキストに使う。URL の書き換えは適用するが、
+/// ブックマークや埋め込みなどのブロックは生成しない。
+pub fn build_inline_rich_text(text: &str, compiled: &CompiledUrlRules) -> Vec<serde_json::Value> {
+    parse_segments(text)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            TextSegment::Plain(s) => (!s.is_empty()).then(|| plain_text_json(&s)),
+            TextSegment::Url(url) => {
+                let mut url = rewrite_url(&url, compiled);
+                if compiled.strip_tracking_params {
+                    url = strip_tracking_params(&url);
+                }
+                Some(inline_link_json(&url))
+            }
+        })
+        .collect()
+}
+
 /// 溜まった rich_text 要素を paragraph ブロックとして blocks に追加し、クリアする。
 fn flush_paragraph(
     pending_rich_text: &mut Vec<serde_json::Value>,
-    blocks: &mut Vec<(serde_json::Value, String)>,
+    blocks: &mut Vec<(serde_json::Value, BlockKind)>,
 ) {
     if pending_rich_text.is_empty() {
         return;
@@ -223,7 +310,7 @@ fn flush_paragraph(
                 "rich_text": rich_text
             }
         }),
-        "text".to_string(),
+        BlockKind::Text,
     ));
 }
 
@@ -263,12 +350,69 @@ fn parse_segments(text: &str) -> Vec<TextSegment> {
 /// 最初にマッチしたルールのみ適用。どのルールにもマッチしなかった場合は
 /// デフォルトの変換タイプを返す。
 fn classify_url(url: &str, compiled: &CompiledUrlRules) -> Vec<UrlBlockType> {
-    for rule in &compiled.rules {
-        if rule.matcher.is_match(url) {
-            return rule.block_types.clone();
-        }
+    match find_rule(url, compiled) {
+        Some(rule) => rule.block_types.clone(),
+        None => compiled.default_types.clone(),
+    }
+}
+
+/// URL にマッチするルールの置換テンプレートで URL を書き換える。
+///
+/// 最初にマッチしたルールのみ適用。置換テンプレートが無い場合はそのまま返す。
+fn rewrite_url(url: &str, compiled: &CompiledUrlRules) -> String {
+    match find_rule(url, compiled) {
+        Some(rule) => rule.rewrite_url(url),
+        None => url.to_string(),
+    }
+}
+
+/// URL のクエリから既知のトラッキングパラメータを取り除く。
+///
+/// 残りのパラメータの順序とフラグメントはそのまま保ち、パラメータが無くなった場合は `?` も取り除く。
+fn strip_tracking_params(url: &str) -> String {
+    let (without_fragment, fragment) = match url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (url, None),
+    };
+    let Some((base, query)) = without_fragment.split_once('?') else {
+        return url.to_string();
+    };
+
+    let params = query
+        .split('&')
+        .filter(|param| {
+            let name = param.split_once('=').map_or(*param, |(name, _)| name);
+            !param.is_empty() && !is_tracking_param(name)
+        })
+        .collect::<Vec<_>>();
+
+    let mut stripped = base.to_string();
+    if !params.is_empty() {
+        stripped.push('?');
+        stripped.push_str(&params.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        stripped.push('#');
+        stripped.push_str(fragment);
     }
-    compiled.default_types.clone()
+    stripped
+}
+
+/// クエリパラメータ名が既知のトラッキングパラメータかを判定する。
+fn is_tracking_param(name: &str) -> bool {
+    const TRACKING_PARAMS: [&str; 14] = [
+        "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "twclid", "igshid",
+        "mc_cid", "mc_eid", "_ga", "_gl", "ref_src",
+    ];
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name)
+}
+
+/// URL に最初にマッチするルールを返す。
+fn find_rule<'a>(url: &str, compiled: &'a CompiledUrlRules) -> Option<&'a UrlRule> {
+    compiled
+        .rules
+        .iter()
+        .find(|rule| rule.matcher.is_match(url))
 }
 
 /// ブロックタイプ文字列をパースする。
@@ -277,6 +421,8 @@ fn parse_block_type(s: &str) -> Option<UrlBlockType> {
         "link" => Some(UrlBlockType::Link),
         "bookmark" => Some(UrlBlockType::Bookmark),
         "embed" => Some(UrlBlockType::Embed),
+        "tweet" => Some(UrlBlockType::Tweet),
+        "image" => Some(UrlBlockType::Image),
         _ => {
             tracing::warn!(block_type = %s, "Unknown block type in convert_to, skipping");
             None
@@ -330,6 +476,114 @@ fn embed_block_json(url: &str) -> serde_json::Value {
     })
 }
 
+/// X の投稿の引用ブロック JSON を生成する。
+///
+/// 投稿を取得できるまでは URL へのリンクだけを引用する。
+fn tweet_block_json(url: &str) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "quote",
+        "quote": {
+            "rich_text": [inline_link_json(url)]
+        }
+    })
+}
+
+/// リンク先のサムネイル画像ブロック JSON を生成する。
+///
+/// 画像を取得できるまでは画像の URL を持たず、キャプションにリンク先の URL だけを置く。
+fn ogp_image_block_json(url: &str) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "image",
+        "image": {
+            "caption": [inline_link_json(url)]
+        }
+    })
+}
+
+/// サムネイル画像ブロックのリンク先の URL（キャプションの先頭のリンク）を返す。
+pub fn ogp_image_source_url(block_json: &serde_json::Value) -> Option<&str> {
+    block_json["image"]["caption"]
+        .as_array()?
+        .iter()
+        .find_map(|text| text["text"]["link"]["url"].as_str())
+}
+
+/// サムネイル画像ブロックに画像の URL を外部画像として設定する。
+pub fn apply_image_url_to_ogp_image(block_json: &mut serde_json::Value, image_url: &str) {
+    block_json["image"]["type"] = serde_json::json!("external");
+    block_json["image"]["external"] = serde_json::json!({ "url": image_url });
+}
+
+/// 取得した X の投稿を引用ブロックに適用する。
+///
+/// 1 行目に投稿者（投稿へのリンク）、続けて本文を引用し、画像は引用ブロックの子ブロックとして並べる。
+pub fn apply_tweet_to_quote(block_json: &mut serde_json::Value, tweet: &Tweet) {
+    let Some(url) = quote_source_url(block_json).map(str::to_string) else {
+        return;
+    };
+
+    let header = format!("{} (@{})", tweet.author_name, tweet.screen_name);
+    let mut rich_text = vec![serde_json::json!({
+        "type": "text",
+        "text": {
+            "content": header,
+            "link": {
+                "url": url
+            }
+        },
+        "annotations": {
+            "bold": true
+        }
+    })];
+    if !tweet.text.is_empty() {
+        rich_text.push(plain_text_json(&format!("\n{}", tweet.text)));
+    }
+    block_json["quote"]["rich_text"] = serde_json::json!(rich_text);
+
+    if !tweet.image_urls.is_empty() {
+        let children: Vec<serde_json::Value> = tweet
+            .image_urls
+            .iter()
+            .map(|image_url| {
+                serde_json::json!({
+                    "object": "block",
+                    "type": "image",
+                    "image": {
+                        "type": "external",
+                        "external": {
+                            "url": image_url
+                        }
+                    }
+                })
+            })
+            .collect();
+        block_json["quote"]["children"] = serde_json::json!(children);
+    }
+}
+
+/// 引用ブロックの元になった投稿の URL（先頭のリンク）を返す。
+pub fn quote_source_url(block_json: &serde_json::Value) -> Option<&str> {
+    block_json["quote"]["rich_text"]
+        .as_array()?
+        .iter()
+        .find_map(|text| text["text"]["link"]["url"].as_str())
+}
+
+/// OGP メタデータを、ブックマークブロックまたはサムネイル画像ブロックに適用する。
+///
+/// サムネイル画像ブロックに適用する場合、og:image がなければエラーを返す。
+pub fn apply_ogp_to_block(block_json: &mut serde_json::Value, ogp: &OgpMetadata) -> Result<()> {
+    if block_json["type"] == "image" {
+        let image_url = ogp.image.as_deref().context("Page has no og:image")?;
+        apply_image_url_to_ogp_image(block_json, image_url);
+    } else {
+        apply_ogp_to_bookmark(block_json, ogp);
+    }
+    Ok(())
+}
+
 /// OGP メタデータをブックマークブロックに適用する。
 ///
 /// タイトルと説明をキャプションとして設定する。
@@ -444,6 +698,7 @@ mod tests {
         CompiledUrlRules {
             rules,
             default_types: vec![],
+            strip_tracking_params: false,
         }
     }
 
@@ -455,6 +710,7 @@ mod tests {
         CompiledUrlRules {
             rules,
             default_types,
+            strip_tracking_params: false,
         }
     }
 
@@ -479,6 +735,7 @@ mod tests {
             vec![UrlRule {
                 matcher: UrlMatcher::Regex(Regex::new(r"https://github\.com/.*").unwrap()),
                 block_types: vec![UrlBlockType::Bookmark],
+                rewrite: None,
             }],
             vec![UrlBlockType::Link],
         );
@@ -494,6 +751,7 @@ mod tests {
             vec![UrlRule {
                 matcher: UrlMatcher::Regex(Regex::new(r"https://github\.com/.*").unwrap()),
                 block_types: vec![UrlBlockType::Bookmark],
+                rewrite: None,
             }],
             vec![UrlBlockType::Link],
         );
@@ -509,10 +767,12 @@ mod tests {
             UrlRule {
                 matcher: UrlMatcher::Regex(Regex::new(r"https://github\.com/.*").unwrap()),
                 block_types: vec![UrlBlockType::Embed],
+                rewrite: None,
             },
             UrlRule {
                 matcher: UrlMatcher::Regex(Regex::new(r"https://.*").unwrap()),
                 block_types: vec![UrlBlockType::Bookmark],
+                rewrite: None,
             },
         ]);
         assert_eq!(
@@ -526,6 +786,7 @@ mod tests {
         let compiled = compiled_with_rules(vec![UrlRule {
             matcher: UrlMatcher::Glob("https://github.com/**".to_string()),
             block_types: vec![UrlBlockType::Bookmark],
+            rewrite: None,
         }]);
         assert_eq!(
             classify_url("https://github.com/ekuinox/kgd", &compiled),
@@ -538,6 +799,7 @@ mod tests {
         let compiled = compiled_with_rules(vec![UrlRule {
             matcher: UrlMatcher::Prefix("https://github.com/".to_string()),
             block_types: vec![UrlBlockType::Bookmark],
+            rewrite: None,
         }]);
         assert_eq!(
             classify_url("https://github.com/ekuinox/kgd", &compiled),
@@ -550,7 +812,7 @@ mod tests {
         let compiled = compiled_with_default(vec![], vec![UrlBlockType::Link]);
         let result = build_rich_text_and_url_blocks("plain text", &compiled);
         assert_eq!(result.blocks.len(), 1);
-        assert_eq!(result.blocks[0].1, "text");
+        assert_eq!(result.blocks[0].1, BlockKind::Text);
         let rich_text = result.blocks[0].0["paragraph"]["rich_text"]
             .as_array()
             .unwrap();
@@ -565,7 +827,7 @@ mod tests {
         let result = build_rich_text_and_url_blocks("see https://example.com here", &compiled);
         // すべてインラインなので paragraph 1 つ
         assert_eq!(result.blocks.len(), 1);
-        assert_eq!(result.blocks[0].1, "text");
+        assert_eq!(result.blocks[0].1, BlockKind::Text);
         let rich_text = result.blocks[0].0["paragraph"]["rich_text"]
             .as_array()
             .unwrap();
@@ -574,12 +836,23 @@ mod tests {
         assert_eq!(rich_text[1]["text"]["link"]["url"], "https://example.com");
     }
 
+    #[test]
+    fn test_build_inline_rich_text() {
+        let compiled = compiled_with_default(vec![], vec![UrlBlockType::Bookmark]);
+        let rich_text = build_inline_rich_text("see https://example.com here", &compiled);
+        // ブックマークの設定でもブロックは作らず、インラインリンクにする
+        assert_eq!(rich_text.len(), 3);
+        assert_eq!(rich_text[0]["text"]["content"], "see ");
+        assert_eq!(rich_text[1]["text"]["link"]["url"], "https://example.com");
+        assert_eq!(rich_text[2]["text"]["content"], " here");
+    }
+
     #[test]
     fn test_build_url_no_default_renders_plain_text() {
         let compiled = compiled_with_rules(vec![]);
         let result = build_rich_text_and_url_blocks("see https://example.com here", &compiled);
         assert_eq!(result.blocks.len(), 1);
-        assert_eq!(result.blocks[0].1, "text");
+        assert_eq!(result.blocks[0].1, BlockKind::Text);
         let rich_text = result.blocks[0].0["paragraph"]["rich_text"]
             .as_array()
             .unwrap();
@@ -595,6 +868,7 @@ mod tests {
             vec![UrlRule {
                 matcher: UrlMatcher::Regex(Regex::new(r"https://github\.com/.*").unwrap()),
                 block_types: vec![UrlBlockType::Bookmark],
+                rewrite: None,
             }],
             vec![UrlBlockType::Link],
         );
@@ -602,13 +876,13 @@ mod tests {
             build_rich_text_and_url_blocks("check https://github.com/ekuinox/kgd", &compiled);
         // "check " → paragraph, URL → bookmark の順
         assert_eq!(result.blocks.len(), 2);
-        assert_eq!(result.blocks[0].1, "text");
+        assert_eq!(result.blocks[0].1, BlockKind::Text);
         let rich_text = result.blocks[0].0["paragraph"]["rich_text"]
             .as_array()
             .unwrap();
         assert_eq!(rich_text.len(), 1);
         assert_eq!(rich_text[0]["text"]["content"], "check ");
-        assert_eq!(result.blocks[1].1, "bookmark");
+        assert_eq!(result.blocks[1].1, BlockKind::Bookmark);
         assert_eq!(
             result.blocks[1].0["bookmark"]["url"],
             "https://github.com/ekuinox/kgd"
@@ -621,6 +895,7 @@ mod tests {
             vec![UrlRule {
                 matcher: UrlMatcher::Regex(Regex::new(r"https://github\.com/.*").unwrap()),
                 block_types: vec![UrlBlockType::Link, UrlBlockType::Bookmark],
+                rewrite: None,
             }],
             vec![UrlBlockType::Link],
         );
@@ -628,7 +903,7 @@ mod tests {
             build_rich_text_and_url_blocks("check https://github.com/ekuinox/kgd", &compiled);
         // "check " + inline link → paragraph, bookmark の順
         assert_eq!(result.blocks.len(), 2);
-        assert_eq!(result.blocks[0].1, "text");
+        assert_eq!(result.blocks[0].1, BlockKind::Text);
         let rich_text = result.blocks[0].0["paragraph"]["rich_text"]
             .as_array()
             .unwrap();
@@ -637,7 +912,7 @@ mod tests {
             rich_text[1]["text"]["link"]["url"],
             "https://github.com/ekuinox/kgd"
         );
-        assert_eq!(result.blocks[1].1, "bookmark");
+        assert_eq!(result.blocks[1].1, BlockKind::Bookmark);
     }
 
     #[test]
@@ -645,11 +920,12 @@ mod tests {
         let compiled = compiled_with_rules(vec![UrlRule {
             matcher: UrlMatcher::Regex(Regex::new(r"https://youtube\.com/watch.*").unwrap()),
             block_types: vec![UrlBlockType::Embed],
+            rewrite: None,
         }]);
         let result = build_rich_text_and_url_blocks("https://youtube.com/watch?v=abc", &compiled);
         // embed のみ、paragraph なし
         assert_eq!(result.blocks.len(), 1);
-        assert_eq!(result.blocks[0].1, "embed");
+        assert_eq!(result.blocks[0].1, BlockKind::Embed);
         assert_eq!(
             result.blocks[0].0["embed"]["url"],
             "https://youtube.com/watch?v=abc"
@@ -665,11 +941,12 @@ mod tests {
                 UrlBlockType::Bookmark,
                 UrlBlockType::Embed,
             ],
+            rewrite: None,
         }]);
         let result = build_rich_text_and_url_blocks("https://youtube.com/watch?v=abc", &compiled);
         // inline link → paragraph が flush され、bookmark, embed が続く
         assert_eq!(result.blocks.len(), 3);
-        assert_eq!(result.blocks[0].1, "text");
+        assert_eq!(result.blocks[0].1, BlockKind::Text);
         let rich_text = result.blocks[0].0["paragraph"]["rich_text"]
             .as_array()
             .unwrap();
@@ -677,8 +954,8 @@ mod tests {
             rich_text[0]["text"]["link"]["url"],
             "https://youtube.com/watch?v=abc"
         );
-        assert_eq!(result.blocks[1].1, "bookmark");
-        assert_eq!(result.blocks[2].1, "embed");
+        assert_eq!(result.blocks[1].1, BlockKind::Bookmark);
+        assert_eq!(result.blocks[2].1, BlockKind::Embed);
     }
 
     #[test]
@@ -687,6 +964,7 @@ mod tests {
             vec![UrlRule {
                 matcher: UrlMatcher::Regex(Regex::new(r"https://github\.com/.*").unwrap()),
                 block_types: vec![UrlBlockType::Bookmark],
+                rewrite: None,
             }],
             vec![UrlBlockType::Link],
         );
@@ -696,7 +974,7 @@ mod tests {
         );
         // "see " + inline link(example.com) + " and " → paragraph, bookmark(github.com)
         assert_eq!(result.blocks.len(), 2);
-        assert_eq!(result.blocks[0].1, "text");
+        assert_eq!(result.blocks[0].1, BlockKind::Text);
         let rich_text = result.blocks[0].0["paragraph"]["rich_text"]
             .as_array()
             .unwrap();
@@ -704,7 +982,7 @@ mod tests {
         assert_eq!(rich_text[0]["text"]["content"], "see ");
         assert_eq!(rich_text[1]["text"]["link"]["url"], "https://example.com");
         assert_eq!(rich_text[2]["text"]["content"], " and ");
-        assert_eq!(result.blocks[1].1, "bookmark");
+        assert_eq!(result.blocks[1].1, BlockKind::Bookmark);
     }
 
     #[test]
@@ -713,6 +991,7 @@ mod tests {
             vec![UrlRule {
                 matcher: UrlMatcher::Regex(Regex::new(r"https://github\.com/.*").unwrap()),
                 block_types: vec![UrlBlockType::Bookmark],
+                rewrite: None,
             }],
             vec![UrlBlockType::Link],
         );
@@ -720,23 +999,128 @@ mod tests {
             build_rich_text_and_url_blocks("before https://github.com/foo after", &compiled);
         // "before " → paragraph, bookmark, " after" → paragraph
         assert_eq!(result.blocks.len(), 3);
-        assert_eq!(result.blocks[0].1, "text");
+        assert_eq!(result.blocks[0].1, BlockKind::Text);
         let rt0 = result.blocks[0].0["paragraph"]["rich_text"]
             .as_array()
             .unwrap();
         assert_eq!(rt0[0]["text"]["content"], "before ");
-        assert_eq!(result.blocks[1].1, "bookmark");
+        assert_eq!(result.blocks[1].1, BlockKind::Bookmark);
         assert_eq!(
             result.blocks[1].0["bookmark"]["url"],
             "https://github.com/foo"
         );
-        assert_eq!(result.blocks[2].1, "text");
+        assert_eq!(result.blocks[2].1, BlockKind::Text);
         let rt2 = result.blocks[2].0["paragraph"]["rich_text"]
             .as_array()
             .unwrap();
         assert_eq!(rt2[0]["text"]["content"], " after");
     }
 
+    #[test]
+    fn test_build_rewrites_url() {
+        let compiled = compile_url_rules(
+            &[
+                UrlRuleConfig {
+                    pattern: PatternConfig::Regex(r"^https://(?:x|twitter)\.com/".to_string()),
+                    convert_to: vec!["link".to_string(), "embed".to_string()],
+                    expect_matches: vec![],
+                    expect_no_matches: vec![],
+                    rewrite: Some("https://fixupx.com/".to_string()),
+                },
+                UrlRuleConfig {
+                    pattern: PatternConfig::Regex(
+                        r"^(https://example\.com/[^?#]*)[?#].*$".to_string(),
+                    ),
+                    convert_to: vec!["bookmark".to_string()],
+                    expect_matches: vec![],
+                    expect_no_matches: vec![],
+                    rewrite: Some("$1".to_string()),
+                },
+            ],
+            &["link".to_string()],
+        )
+        .unwrap();
+
+        let result = build_rich_text_and_url_blocks(
+            "https://x.com/user/status/1 https://example.com/a?utm_source=feed",
+            &compiled,
+        );
+        let rich_text = result.blocks[0].0["paragraph"]["rich_text"]
+            .as_array()
+            .unwrap();
+        assert_eq!(
+            rich_text[0]["text"]["link"]["url"],
+            "https://fixupx.com/user/status/1"
+        );
+        assert_eq!(
+            result.blocks[1].0["embed"]["url"],
+            "https://fixupx.com/user/status/1"
+        );
+        assert_eq!(
+            result.blocks[3].0["bookmark"]["url"],
+            "https://example.com/a"
+        );
+        assert_eq!(result.bookmark_urls, vec!["https://example.com/a"]);
+    }
+
+    #[test]
+    fn test_strip_tracking_params() {
+        assert_eq!(
+            strip_tracking_params("https://example.com/a?utm_source=feed&utm_medium=rss"),
+            "https://example.com/a"
+        );
+        assert_eq!(
+            strip_tracking_params("https://example.com/a?id=1&fbclid=abc&page=2#top"),
+            "https://example.com/a?id=1&page=2#top"
+        );
+        assert_eq!(
+            strip_tracking_params("https://example.com/a?utm=keep"),
+            "https://example.com/a?utm=keep"
+        );
+        assert_eq!(
+            strip_tracking_params("https://example.com/a#utm_source=x"),
+            "https://example.com/a#utm_source=x"
+        );
+    }
+
+    #[test]
+    fn test_build_strips_tracking_params() {
+        let compiled =
+            compiled_with_default(vec![], vec![UrlBlockType::Link]).strip_tracking_params(true);
+        let result =
+            build_rich_text_and_url_blocks("https://example.com/a?utm_source=x&id=1", &compiled);
+        let rich_text = result.blocks[0].0["paragraph"]["rich_text"]
+            .as_array()
+            .unwrap();
+        assert_eq!(
+            rich_text[0]["text"]["link"]["url"],
+            "https://example.com/a?id=1"
+        );
+    }
+
+    #[test]
+    fn test_compile_url_rules_rewrite_validation() {
+        // 正規表現以外のパターンには置換テンプレートを指定できない
+        let rules = vec![UrlRuleConfig {
+            pattern: PatternConfig::Prefix("https://x.com/".to_string()),
+            convert_to: vec!["link".to_string()],
+            expect_matches: vec![],
+            expect_no_matches: vec![],
+            rewrite: Some("https://fixupx.com/".to_string()),
+        }];
+        assert!(compile_url_rules(&rules, &[]).is_err());
+
+        // 書き換え後が URL でなくなる場合はエラー
+        let rules = vec![UrlRuleConfig {
+            pattern: PatternConfig::Regex(r"^https://x\.com/".to_string()),
+            convert_to: vec!["link".to_string()],
+            expect_matches: vec!["https://x.com/user".to_string()],
+            expect_no_matches: vec![],
+            rewrite: Some(String::new()),
+        }];
+        assert!(compile_url_rules(&rules, &[]).is_err());
+    }
+
     #[test]
     fn test_compile_url_rules_regex_valid() {
         let rules = vec![UrlRuleConfig {
@@ -744,6 +1128,7 @@ mod tests {
             convert_to: vec!["bookmark".to_string()],
             expect_matches: vec![],
             expect_no_matches: vec![],
+            rewrite: None,
         }];
         let compiled = compile_url_rules(&rules, &["link".to_string()]).unwrap();
         assert_eq!(compiled.rules.len(), 1);
@@ -758,6 +1143,7 @@ mod tests {
             convert_to: vec!["bookmark".to_string()],
             expect_matches: vec![],
             expect_no_matches: vec![],
+            rewrite: None,
         }];
         assert!(compile_url_rules(&rules, &[]).is_err());
     }
@@ -769,6 +1155,7 @@ mod tests {
             convert_to: vec!["bookmark".to_string()],
             expect_matches: vec![],
             expect_no_matches: vec![],
+            rewrite: None,
         }];
         let compiled = compile_url_rules(&rules, &[]).unwrap();
         assert_eq!(compiled.rules.len(), 1);
@@ -782,6 +1169,7 @@ mod tests {
             convert_to: vec!["bookmark".to_string()],
             expect_matches: vec![],
             expect_no_matches: vec![],
+            rewrite: None,
         }];
         let compiled = compile_url_rules(&rules, &[]).unwrap();
         assert_eq!(compiled.rules.len(), 1);
@@ -795,6 +1183,7 @@ mod tests {
             convert_to: vec!["unknown_type".to_string()],
             expect_matches: vec![],
             expect_no_matches: vec![],
+            rewrite: None,
         }];
         // 有効なブロックタイプがないのでエラー
         assert!(compile_url_rules(&rules, &[]).is_err());
@@ -807,6 +1196,7 @@ mod tests {
             convert_to: vec!["bookmark".to_string(), "invalid".to_string()],
             expect_matches: vec![],
             expect_no_matches: vec![],
+            rewrite: None,
         }];
         let compiled = compile_url_rules(&rules, &[]).unwrap();
         assert_eq!(compiled.rules.len(), 1);
@@ -826,6 +1216,7 @@ mod tests {
             convert_to: vec!["link".to_string(), "bookmark".to_string()],
             expect_matches: vec![],
             expect_no_matches: vec![],
+            rewrite: None,
         }];
         let compiled = compile_url_rules(&rules, &["link".to_string()]).unwrap();
         assert_eq!(compiled.rules.len(), 1);
@@ -842,6 +1233,7 @@ mod tests {
             convert_to: vec!["embed".to_string(), "bookmark".to_string()],
             expect_matches: vec!["https://www.youtube.com/watch?v=DFaYoGSCKbs".to_string()],
             expect_no_matches: vec!["https://www.youtube.com/".to_string()],
+            rewrite: None,
         }];
         assert!(compile_url_rules(&rules, &[]).is_ok());
     }
@@ -853,6 +1245,7 @@ mod tests {
             convert_to: vec!["bookmark".to_string()],
             expect_matches: vec!["https://gitlab.com/user/repo".to_string()],
             expect_no_matches: vec![],
+            rewrite: None,
         }];
         assert!(compile_url_rules(&rules, &[]).is_err());
     }
@@ -864,6 +1257,7 @@ mod tests {
             convert_to: vec!["bookmark".to_string()],
             expect_matches: vec![],
             expect_no_matches: vec!["https://github.com/ekuinox/kgd".to_string()],
+            rewrite: None,
         }];
         assert!(compile_url_rules(&rules, &[]).is_err());
     }
@@ -903,6 +1297,7 @@ mod tests {
             vec![UrlRule {
                 matcher: UrlMatcher::Regex(Regex::new(r"https://github\.com/.*").unwrap()),
                 block_types: vec![UrlBlockType::Bookmark],
+                rewrite: None,
             }],
             vec![UrlBlockType::Link],
         );
@@ -930,6 +1325,106 @@ mod tests {
         assert!(result.bookmark_urls.is_empty());
     }
 
+    #[test]
+    fn test_build_tweet_rule() {
+        let compiled = compiled_with_rules(vec![UrlRule {
+            matcher: UrlMatcher::Regex(Regex::new(r"https://x\.com/.*").unwrap()),
+            block_types: vec![UrlBlockType::Tweet],
+            rewrite: None,
+        }]);
+        let result = build_rich_text_and_url_blocks("見て https://x.com/neko/status/1", &compiled);
+        // テキストの paragraph に続いて引用ブロック
+        assert_eq!(result.blocks.len(), 2);
+        assert_eq!(result.blocks[1].1, BlockKind::Quote);
+        assert_eq!(
+            quote_source_url(&result.blocks[1].0),
+            Some("https://x.com/neko/status/1")
+        );
+        assert_eq!(result.tweet_urls, vec!["https://x.com/neko/status/1"]);
+        assert!(result.bookmark_urls.is_empty());
+    }
+
+    #[test]
+    fn test_build_bookmark_and_image_rule() {
+        let compiled = compiled_with_rules(vec![UrlRule {
+            matcher: UrlMatcher::Prefix("https://example.com/".to_string()),
+            block_types: vec![UrlBlockType::Bookmark, UrlBlockType::Image],
+            rewrite: None,
+        }]);
+        let result = build_rich_text_and_url_blocks("https://example.com/a", &compiled);
+        assert_eq!(result.blocks.len(), 2);
+        assert_eq!(result.blocks[0].1, BlockKind::Bookmark);
+        assert_eq!(result.blocks[1].1, BlockKind::OgpImage);
+        assert_eq!(
+            ogp_image_source_url(&result.blocks[1].0),
+            Some("https://example.com/a")
+        );
+    }
+
+    #[test]
+    fn test_apply_ogp_to_image_block() {
+        let ogp = OgpMetadata {
+            title: Some("Title".to_string()),
+            description: None,
+            image: Some("https://example.com/card.png".to_string()),
+            site_name: None,
+            og_type: None,
+        };
+        let mut block = ogp_image_block_json("https://example.com/a");
+        apply_ogp_to_block(&mut block, &ogp).unwrap();
+        assert_eq!(block["image"]["type"], "external");
+        assert_eq!(
+            block["image"]["external"]["url"],
+            "https://example.com/card.png"
+        );
+        // キャプションのリンク先は変わらない
+        assert_eq!(ogp_image_source_url(&block), Some("https://example.com/a"));
+
+        let mut block = ogp_image_block_json("https://example.com/a");
+        let no_image = OgpMetadata {
+            image: None,
+            ..ogp.clone()
+        };
+        assert!(apply_ogp_to_block(&mut block, &no_image).is_err());
+
+        let mut block = bookmark_block_json("https://example.com/a");
+        apply_ogp_to_block(&mut block, &no_image).unwrap();
+        assert_eq!(block["bookmark"]["caption"][0]["text"]["content"], "Title");
+    }
+
+    #[test]
+    fn test_apply_tweet_to_quote() {
+        let mut block = tweet_block_json("https://x.com/neko/status/1");
+        let tweet = Tweet {
+            author_name: "ねこ".to_string(),
+            screen_name: "neko".to_string(),
+            text: "にゃーん".to_string(),
+            image_urls: vec!["https://pbs.twimg.com/media/a.jpg".to_string()],
+        };
+
+        apply_tweet_to_quote(&mut block, &tweet);
+
+        let rich_text = block["quote"]["rich_text"].as_array().unwrap();
+        assert_eq!(rich_text.len(), 2);
+        assert_eq!(rich_text[0]["text"]["content"], "ねこ (@neko)");
+        assert_eq!(
+            rich_text[0]["text"]["link"]["url"],
+            "https://x.com/neko/status/1"
+        );
+        assert_eq!(rich_text[1]["text"]["content"], "\nにゃーん");
+        let children = block["quote"]["children"].as_array().unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(
+            children[0]["image"]["external"]["url"],
+            "https://pbs.twimg.com/media/a.jpg"
+        );
+        // 元の URL は取得後も引用ブロックから取り出せる
+        assert_eq!(
+            quote_source_url(&block),
+            Some("https://x.com/neko/status/1")
+        );
+    }
+
     #[test]
     fn test_apply_ogp_to_bookmark_with_title_and_description() {
         let mut block = serde_json::json!({
@@ -944,6 +1439,9 @@ mod tests {
         let ogp = OgpMetadata {
             title: Some("Example Title".to_string()),
             description: Some("Example Description".to_string()),
+            image: None,
+            site_name: None,
+            og_type: None,
         };
 
         apply_ogp_to_bookmark(&mut block, &ogp);
@@ -970,6 +1468,9 @@ mod tests {
         let ogp = OgpMetadata {
             title: Some("Title Only".to_string()),
             description: None,
+            image: None,
+            site_name: None,
+            og_type: None,
         };
 
         apply_ogp_to_bookmark(&mut block, &ogp);
@@ -993,6 +1494,9 @@ mod tests {
         let ogp = OgpMetadata {
             title: None,
             description: Some("Description Only".to_string()),
+            image: None,
+            site_name: None,
+            og_type: None,
         };
 
         apply_ogp_to_bookmark(&mut block, &ogp);
@@ -1016,6 +1520,9 @@ mod tests {
         let ogp = OgpMetadata {
             title: None,
             description: None,
+            image: None,
+            site_name: None,
+            og_type: None,
         };
 
         apply_ogp_to_bookmark(&mut block, &ogp);
@@ -1040,6 +1547,9 @@ mod tests {
         let ogp = OgpMetadata {
             title: Some("Title".to_string()),
             description: Some(long_description),
+            image: None,
+            site_name: None,
+            og_type: None,
         };
 
         apply_ogp_to_bookmark(&mut block, &ogp);
@@ -0,0 +1,177 @@
+//! ダウンロードや変換処理で使う一時ファイルの作業ディレクトリを管理する。
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use anyhow::{Context as _, Result, bail};
+use tempfile::TempPath;
+
+/// 作業ディレクトリに作成する一時ファイル名の接頭辞。
+const FILE_PREFIX: &str = "kgd-";
+
+/// 容量上限付きの一時ファイル作業ディレクトリ。
+///
+/// クローンしたインスタンス同士で使用量を共有する。
+#[derive(Clone)]
+pub struct TempWorkspace {
+    /// 一時ファイルを作成するディレクトリ
+    dir: PathBuf,
+    /// 一時ファイルの合計サイズの上限（バイト）
+    quota: u64,
+    /// 確保済みのサイズ（バイト）
+    used: Arc<AtomicU64>,
+}
+
+/// 作業ディレクトリ内の一時ファイル。
+///
+/// 破棄時にファイルを削除し、確保した容量を解放する。
+pub struct WorkspaceFile {
+    /// 一時ファイルのパス
+    path: TempPath,
+    /// 確保した容量
+    _reservation: Reservation,
+}
+
+impl TempWorkspace {
+    /// 作業ディレクトリを開く。
+    ///
+    /// ディレクトリが無ければ作成し、前回の実行で残った一時ファイルを削除する。
+    pub fn open(dir: impl Into<PathBuf>, quota: u64) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create temp directory: {}", dir.display()))?;
+
+        let removed = cleanup_leftover_files(&dir)?;
+        if removed > 0 {
+            tracing::info!(dir = %dir.display(), removed, "Removed leftover temporary files");
+        }
+
+        Ok(Self {
+            dir,
+            quota,
+            used: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// 指定したサイズの容量を確保して一時ファイルを作成する。
+    ///
+    /// 容量の上限を超える場合はエラーを返す。
+    /// `suffix` はファイル名の末尾（拡張子など）に付与される。
+    pub fn create_file(&self, suffix: &str, size: u64) -> Result<(fs::File, WorkspaceFile)> {
+        let reservation = self.reserve(size)?;
+        let (file, path) = tempfile::Builder::new()
+            .prefix(FILE_PREFIX)
+            .suffix(suffix)
+            .tempfile_in(&self.dir)
+            .with_context(|| format!("Failed to create temporary file in {}", self.dir.display()))?
+            .into_parts();
+
+        Ok((
+            file,
+            WorkspaceFile {
+                path,
+                _reservation: reservation,
+            },
+        ))
+    }
+
+    fn reserve(&self, size: u64) -> Result<Reservation> {
+        let reserved = self
+            .used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                used.checked_add(size).filter(|total| *total <= self.quota)
+            });
+        if reserved.is_err() {
+            bail!(
+                "Temporary workspace quota exceeded: requested {} bytes, {} of {} bytes in use",
+                size,
+                self.used.load(Ordering::SeqCst),
+                self.quota
+            );
+        }
+
+        Ok(Reservation {
+            used: self.used.clone(),
+            size,
+        })
+    }
+}
+
+impl WorkspaceFile {
+    /// 一時ファイルのパスを返す。
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// 確保した容量。破棄時に解放する。
+struct Reservation {
+    /// 作業ディレクトリ全体の使用量
+    used: Arc<AtomicU64>,
+    /// 確保したサイズ
+    size: u64,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.used.fetch_sub(self.size, Ordering::SeqCst);
+    }
+}
+
+/// 作業ディレクトリに残った一時ファイルを削除し、削除した件数を返す。
+///
+/// 他のファイルを誤って消さないよう、接頭辞が一致するファイルだけを対象にする。
+fn cleanup_leftover_files(dir: &Path) -> Result<usize> {
+    let mut removed = 0;
+    for entry in fs::read_dir(dir)
+        .with_context(|| format!("Failed to read temp directory: {}", dir.display()))?
+    {
+        let entry = entry?;
+        let is_leftover = entry.file_name().to_string_lossy().starts_with(FILE_PREFIX)
+            && entry.file_type()?.is_file();
+        if is_leftover {
+            fs::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_is_released_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = TempWorkspace::open(dir.path(), 100).unwrap();
+
+        let (_, first) = workspace.create_file(".bin", 60).unwrap();
+        assert!(workspace.create_file(".bin", 50).is_err());
+
+        let path = first.path().to_path_buf();
+        assert!(path.exists());
+        drop(first);
+        assert!(!path.exists());
+
+        assert!(workspace.create_file(".bin", 50).is_ok());
+    }
+
+    #[test]
+    fn test_open_removes_only_leftover_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("kgd-leftover.bin"), b"data").unwrap();
+        fs::write(dir.path().join("keep.txt"), b"data").unwrap();
+
+        TempWorkspace::open(dir.path(), 100).unwrap();
+
+        assert!(!dir.path().join("kgd-leftover.bin").exists());
+        assert!(dir.path().join("keep.txt").exists());
+    }
+}
@@ -0,0 +1,150 @@
+//! YouTube の動画のブックマークとサムネイル画像を oEmbed API で展開する URL ハンドラー。
+//!
+//! YouTube のページは同意画面などを返して OGP を取得できないことがあるため、oEmbed API を使う。
+
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use futures::future::BoxFuture;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{block::BlockKind, ogp::OgpMetadata, url_handler::UrlHandler, url_parser};
+
+/// YouTube の oEmbed API のエンドポイント。
+const OEMBED_URL: &str = "https://www.youtube.com/oembed";
+
+/// YouTube の動画のブックマークに動画のタイトルとチャンネル名をキャプションとして付け、
+/// サムネイル画像のブロックに動画のサムネイルを貼る [`UrlHandler`]。
+pub struct YouTubeHandler {
+    http_client: reqwest::Client,
+}
+
+impl YouTubeHandler {
+    /// 新しい YouTubeHandler を作成する。
+    pub fn new(timeout: Duration) -> Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .user_agent("kgd-bot/1.0")
+            .build()
+            .context("Failed to create HTTP client for YouTube handler")?;
+
+        Ok(Self { http_client })
+    }
+
+    async fn fetch_inner(&self, url: &str) -> Result<OgpMetadata> {
+        let response = self
+            .http_client
+            .get(OEMBED_URL)
+            .query(&[("url", url), ("format", "json")])
+            .send()
+            .await
+            .context("HTTP request failed")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("HTTP status: {}", response.status());
+        }
+
+        let oembed: YouTubeOEmbed = response
+            .json()
+            .await
+            .context("Failed to parse oEmbed response")?;
+
+        Ok(oembed.into_metadata())
+    }
+}
+
+impl UrlHandler for YouTubeHandler {
+    fn name(&self) -> &'static str {
+        "youtube"
+    }
+
+    fn matches(&self, url: &str, kind: BlockKind) -> bool {
+        matches!(kind, BlockKind::Bookmark | BlockKind::OgpImage) && is_youtube_video_url(url)
+    }
+
+    fn build_block<'a>(
+        &'a self,
+        url: &'a str,
+        block: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<serde_json::Value>> {
+        Box::pin(async move {
+            let metadata = self.fetch_inner(url).await?;
+            let mut block = block.clone();
+            url_parser::apply_ogp_to_block(&mut block, &metadata)?;
+            Ok(block)
+        })
+    }
+}
+
+/// YouTube の動画（通常の動画・ショート・ライブ）の URL かどうかを返す。
+fn is_youtube_video_url(url: &str) -> bool {
+    let re = Regex::new(
+        r"^https?://(?:(?:www\.|m\.|music\.)?youtube\.com/(?:watch\?(?:.*&)?v=|shorts/|live/)|youtu\.be/)[\w-]+",
+    )
+    .unwrap();
+    re.is_match(url)
+}
+
+/// oEmbed API のレスポンス。
+#[derive(Debug, Deserialize)]
+struct YouTubeOEmbed {
+    /// 動画のタイトル
+    title: String,
+    /// チャンネル名
+    author_name: Option<String>,
+    /// サムネイル画像の URL
+    thumbnail_url: Option<String>,
+}
+
+impl YouTubeOEmbed {
+    /// 動画のタイトルをタイトル、チャンネル名を説明、サムネイルを画像とするメタデータに変換する。
+    fn into_metadata(self) -> OgpMetadata {
+        OgpMetadata {
+            title: Some(self.title),
+            description: self.author_name.filter(|name| !name.trim().is_empty()),
+            image: self.thumbnail_url,
+            site_name: Some("YouTube".to_string()),
+            og_type: Some("video".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_youtube_video_url() {
+        assert!(is_youtube_video_url(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ"
+        ));
+        assert!(is_youtube_video_url(
+            "https://www.youtube.com/watch?list=PL1&v=dQw4w9WgXcQ"
+        ));
+        assert!(is_youtube_video_url("https://youtu.be/dQw4w9WgXcQ?t=10"));
+        assert!(is_youtube_video_url(
+            "https://m.youtube.com/shorts/abcDEF123"
+        ));
+        assert!(!is_youtube_video_url("https://www.youtube.com/@channel"));
+        assert!(!is_youtube_video_url("https://example.com/watch?v=abc"));
+    }
+
+    #[test]
+    fn test_oembed_into_metadata() {
+        let oembed: YouTubeOEmbed = serde_json::from_value(serde_json::json!({
+            "title": "動画のタイトル",
+            "author_name": "チャンネル",
+            "thumbnail_url": "https://i.ytimg.com/vi/abc/hqdefault.jpg",
+            "type": "video"
+        }))
+        .unwrap();
+        let metadata = oembed.into_metadata();
+        assert_eq!(metadata.title.as_deref(), Some("動画のタイトル"));
+        assert_eq!(metadata.description.as_deref(), Some("チャンネル"));
+        assert_eq!(
+            metadata.image.as_deref(),
+            Some("https://i.ytimg.com/vi/abc/hqdefault.jpg")
+        );
+    }
+}
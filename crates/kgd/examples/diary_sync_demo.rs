@@ -0,0 +1,269 @@
+//! 日報の同期エンジンを Discord なしで動かすデモ。
+//!
+//! Notion のデータベースにデモ用の日報ページを作成し、1 日分のメッセージの投稿・編集・削除を
+//! 実際の [`MessageSyncer`] に流して、その結果を表示する。
+//! 同期エンジンの使い方の見本を兼ね、変更後に実際の Notion で動作を確かめるために使う。
+//!
+//! ```sh
+//! NOTION_TOKEN=secret_xxx cargo run -p kgd --example diary_sync_demo -- \
+//!     --notion-database-id <database id>
+//! ```
+//!
+//! メッセージとブロックの対応はメモリ上に記録する。`--database-url` に PostgreSQL の URL を渡すと、
+//! 本番と同じくデータベースに記録する。
+
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use clap::Parser;
+use kgd_diary::{
+    DiaryEntry, DiaryStorage as _, DiaryStore, EventOutcome, Mention, MessageEvent, MessageSource,
+    MessageSyncer, NotionClient, RetryPolicy, SourceMessage, SyncFeatures, SyncOptions,
+    TempWorkspace,
+    config::{NotionApiVersion, OversizePolicy},
+    start_of_day_in_timezone,
+};
+
+/// Discord の snowflake の基準時刻（2015-01-01T00:00:00Z のミリ秒）。
+const DISCORD_EPOCH_MILLIS: i64 = 1_420_070_400_000;
+
+/// デモの投稿者のユーザー ID。
+const DEMO_USER_ID: u64 = 1;
+
+#[derive(Parser)]
+struct Args {
+    /// 日報ページを作成する Notion データベースの ID
+    #[arg(long)]
+    notion_database_id: String,
+
+    /// データベースのタイトルのプロパティ名
+    #[arg(long, default_value = "Name")]
+    notion_title_property: String,
+
+    /// メッセージとブロックの対応を記録するデータベースの URL（`memory:` の場合はメモリ上に記録する）
+    #[arg(long, default_value = "memory:")]
+    database_url: String,
+
+    /// 日付と時刻の見出しに使うタイムゾーン
+    #[arg(long, default_value = "Asia/Tokyo")]
+    timezone: Tz,
+}
+
+/// デモ用の同期元。
+///
+/// メッセージ ID は Discord と同じ snowflake にして、ID から投稿日時を求められるようにする。
+struct DemoSource;
+
+impl MessageSource for DemoSource {
+    fn posted_at(&self, message_id: u64) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp_millis((message_id >> 22) as i64 + DISCORD_EPOCH_MILLIS)
+    }
+
+    async fn resolve_mentions(
+        &self,
+        _message: &SourceMessage,
+        mentions: &[Mention],
+    ) -> HashMap<Mention, String> {
+        mentions
+            .iter()
+            .filter(|mention| **mention == Mention::User(DEMO_USER_ID))
+            .map(|mention| (*mention, "demo-user".to_string()))
+            .collect()
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive(tracing::Level::INFO.into()),
+        )
+        .init();
+
+    let args = Args::parse();
+    let notion_token =
+        std::env::var("NOTION_TOKEN").context("NOTION_TOKEN must be set to run the demo")?;
+
+    let retry_policy = RetryPolicy::new(5, Duration::from_millis(500), Duration::from_secs(30));
+    let notion_client = NotionClient::new(
+        notion_token,
+        &args.notion_database_id,
+        &args.notion_title_property,
+        Vec::new(),
+        Duration::from_secs(60),
+        retry_policy,
+        NotionApiVersion::default(),
+    )?;
+    let store = DiaryStore::connect(&args.database_url).await?;
+    let workspace = TempWorkspace::open(std::env::temp_dir().join("kgd-demo"), 256 * 1024 * 1024)?;
+
+    // 実行のたびに別の日報になるよう、スレッド ID とタイトルに実行時刻を使う
+    let now = Utc::now();
+    let local_date = now.with_timezone(&args.timezone).date_naive();
+    let title = format!(
+        "kgd demo {}",
+        now.with_timezone(&args.timezone)
+            .format("%Y-%m-%d %H:%M:%S")
+    );
+    let (page_id, page_url) = notion_client.create_diary_page(&title, local_date).await?;
+    let thread_id = now.timestamp_millis() as u64;
+    store
+        .insert(&DiaryEntry {
+            thread_id,
+            forum_channel_id: 0,
+            page_id,
+            page_url: page_url.clone(),
+            date: start_of_day_in_timezone(local_date, &args.timezone)?,
+            created_at: now,
+            closed_at: None,
+        })
+        .await?;
+    println!("Created {} ({})", title, page_url);
+
+    let syncer = MessageSyncer::new(
+        DemoSource,
+        &notion_client,
+        &store,
+        &sync_options(args.timezone, retry_policy),
+        &workspace,
+    )?;
+
+    let start_of_day = start_of_day_in_timezone(local_date, &args.timezone)?;
+    for event in demo_events(thread_id, start_of_day) {
+        let label = describe_event(&event);
+        match syncer.handle_event(&event).await {
+            Ok(outcome) => println!("{}: {}", label, describe_outcome(&outcome)),
+            Err(e) => println!("{}: failed: {:#}", label, e),
+        }
+    }
+
+    store.mark_closed(thread_id, Utc::now()).await?;
+    println!("Done, open {} to see the result", page_url);
+
+    Ok(())
+}
+
+/// デモの同期の設定を返す。
+///
+/// 外部コマンドが必要な変換（ffmpeg など）は使わない。
+fn sync_options(timezone: Tz, retry_policy: RetryPolicy) -> SyncOptions {
+    SyncOptions {
+        url_rules: Vec::new(),
+        default_convert_to: vec!["bookmark".to_string()],
+        strip_tracking_params: true,
+        redaction_rules: Vec::new(),
+        image_rules: Vec::new(),
+        image_max_dimension: None,
+        image_jpeg_quality: None,
+        ogp_timeout: Some(Duration::from_secs(10)),
+        github_token: None,
+        tweet_timeout: Duration::from_secs(10),
+        max_attachments_per_message: 10,
+        max_attachment_bytes_per_message: 100 * 1024 * 1024,
+        max_attachment_bytes_per_day: 1024 * 1024 * 1024,
+        max_attachment_size: 100 * 1024 * 1024,
+        on_oversize: OversizePolicy::Skip,
+        attachment_memory_threshold: 16 * 1024 * 1024,
+        ffmpeg_path: PathBuf::from("ffmpeg"),
+        ffmpeg_timeout: Duration::from_secs(60),
+        mermaid_path: PathBuf::from("mmdc"),
+        mermaid_timeout: Duration::from_secs(60),
+        video_transcode_bitrate_kbps: 4000,
+        video_transcode_min_size: 50 * 1024 * 1024,
+        sync_users: Vec::new(),
+        ignore_users: Vec::new(),
+        sync_after_close: true,
+        section_heading_interval: Some(Duration::from_secs(2 * 60 * 60)),
+        timezone,
+        retry_policy,
+        features: SyncFeatures::default(),
+    }
+}
+
+/// 1 日分のメッセージのイベントを返す。
+///
+/// 朝から夜までの投稿に加えて、投稿の編集と削除を含める。
+fn demo_events(thread_id: u64, start_of_day: DateTime<Utc>) -> Vec<MessageEvent> {
+    let message = |time: &str, content: &str| {
+        let posted_at =
+            start_of_day + (NaiveTime::parse_from_str(time, "%H:%M").unwrap() - NaiveTime::MIN);
+        SourceMessage {
+            id: snowflake(posted_at),
+            thread_id,
+            author_id: DEMO_USER_ID,
+            posted_at,
+            content: content.to_string(),
+            attachments: Vec::new(),
+            mention_names: HashMap::new(),
+        }
+    };
+
+    let morning = message("08:30", "おはよう。今日は **日報の同期エンジン** を試す");
+    let typo = message("09:00", "朝ごはんはパンｎ");
+    let link = message("12:15", "昼休みに読んだ https://www.rust-lang.org/");
+    let todo = message(
+        "15:40",
+        "やること\n- サンプルを書く\n- 動作を確認する\n<@1> にレビューしてもらう",
+    );
+    let aside = message("18:05", "この投稿は削除される");
+    let night = message("22:30", "おやすみ");
+
+    let mut fixed = typo.clone();
+    fixed.content = "朝ごはんはパン".to_string();
+
+    vec![
+        MessageEvent::Created(morning),
+        MessageEvent::Created(typo),
+        MessageEvent::Updated(fixed),
+        MessageEvent::Created(link),
+        MessageEvent::Created(todo),
+        MessageEvent::Created(aside.clone()),
+        MessageEvent::Deleted {
+            thread_id,
+            message_id: aside.id,
+        },
+        MessageEvent::Created(night),
+    ]
+}
+
+/// 投稿日時から Discord と同じ形式のメッセージ ID を作る。
+fn snowflake(posted_at: DateTime<Utc>) -> u64 {
+    ((posted_at.timestamp_millis() - DISCORD_EPOCH_MILLIS) as u64) << 22
+}
+
+/// イベントを 1 行で表す。
+fn describe_event(event: &MessageEvent) -> String {
+    match event {
+        MessageEvent::Created(message) => format!("created {:?}", message.content),
+        MessageEvent::Updated(message) => format!("updated {:?}", message.content),
+        MessageEvent::Deleted { message_id, .. } => format!("deleted {}", message_id),
+        MessageEvent::BulkDeleted { message_ids, .. } => {
+            format!("deleted {} messages", message_ids.len())
+        }
+    }
+}
+
+/// イベントの処理結果を 1 行で表す。
+fn describe_outcome(outcome: &EventOutcome) -> String {
+    match outcome {
+        EventOutcome::Ignored => "ignored".to_string(),
+        EventOutcome::Created(result) if !result.synced => "skipped".to_string(),
+        EventOutcome::Created(result) => {
+            let issues = result.issues();
+            if issues.is_empty() {
+                format!("{} block(s)", result.block_count())
+            } else {
+                format!("{} block(s), {}", result.block_count(), issues.join(", "))
+            }
+        }
+        EventOutcome::Updated(updated) => format!("updated: {}", updated),
+        EventOutcome::Deleted(deleted) => format!("deleted: {}", deleted),
+        EventOutcome::BulkDeleted(result) => format!(
+            "{} message(s), {} block(s)",
+            result.deleted_messages, result.deleted_blocks
+        ),
+    }
+}
@@ -0,0 +1,9 @@
+-- 定期実行ジョブの実行記録を管理するテーブル
+CREATE TABLE scheduled_job_runs (
+    -- ジョブ名
+    name TEXT PRIMARY KEY,
+    -- 最後に実行した日時
+    last_run_at TIMESTAMPTZ NOT NULL,
+    -- 最後の実行で発生したエラーの内容（成功時は NULL）
+    last_error TEXT
+);
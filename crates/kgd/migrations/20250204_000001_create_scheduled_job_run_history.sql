@@ -0,0 +1,19 @@
+-- ジョブが連続して失敗した回数（成功すると 0 に戻る）
+ALTER TABLE scheduled_job_runs ADD COLUMN consecutive_failures INT NOT NULL DEFAULT 0;
+
+-- 定期実行ジョブの実行履歴を管理するテーブル
+CREATE TABLE scheduled_job_run_history (
+    id BIGSERIAL PRIMARY KEY,
+    -- ジョブ名
+    name TEXT NOT NULL,
+    -- 実行を開始した日時
+    started_at TIMESTAMPTZ NOT NULL,
+    -- 実行を終了した日時
+    finished_at TIMESTAMPTZ NOT NULL,
+    -- 発生したエラーの内容（成功時は NULL）
+    error TEXT
+);
+
+-- ジョブごとに新しい順で履歴を取得するためのインデックス
+CREATE INDEX idx_scheduled_job_run_history_name_started_at
+    ON scheduled_job_run_history(name, started_at DESC);
@@ -0,0 +1,6 @@
+-- ステータス通知の一時停止を管理するテーブル（1 行だけを使う）
+CREATE TABLE status_snooze (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    -- 通知を再開する日時
+    snoozed_until TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,15 @@
+-- サーバーに Wake-on-LAN のパケットを送った記録を管理するテーブル
+CREATE TABLE server_wakes (
+    id BIGSERIAL PRIMARY KEY,
+    -- サーバー名
+    server_name TEXT NOT NULL,
+    -- パケットを送ったユーザーの ID（定期実行ジョブから送った場合は NULL）
+    user_id BIGINT,
+    -- パケットを送った日時
+    sent_at TIMESTAMPTZ NOT NULL,
+    -- 送った後に最初にオンラインになった日時（起動を確認できていない場合は NULL）
+    online_at TIMESTAMPTZ
+);
+
+-- サーバーごとに新しい順で引くためのインデックス
+CREATE INDEX idx_server_wakes_server_name_sent_at ON server_wakes(server_name, sent_at);
@@ -0,0 +1,13 @@
+-- ステータスチェックでサーバーがオンラインだった期間を管理するテーブル
+CREATE TABLE server_uptime (
+    id BIGSERIAL PRIMARY KEY,
+    -- サーバー名
+    server_name TEXT NOT NULL,
+    -- オンラインを最初に確認した日時
+    started_at TIMESTAMPTZ NOT NULL,
+    -- オンラインを最後に確認した日時
+    last_seen_at TIMESTAMPTZ NOT NULL
+);
+
+-- サーバーごとに最後の期間を引くためのインデックス
+CREATE INDEX idx_server_uptime_server_name_last_seen_at ON server_uptime(server_name, last_seen_at);
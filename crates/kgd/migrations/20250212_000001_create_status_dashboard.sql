@@ -0,0 +1,6 @@
+-- ステータスを編集で更新し続けるメッセージを管理するテーブル（1 行だけを使う）
+CREATE TABLE status_dashboard (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    -- ステータスのメッセージの ID
+    message_id BIGINT NOT NULL
+);
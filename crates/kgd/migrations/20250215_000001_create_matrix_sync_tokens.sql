@@ -0,0 +1,7 @@
+-- Matrix の /sync を再開する位置を管理するテーブル
+CREATE TABLE matrix_sync_tokens (
+    -- Bot の Matrix ユーザー ID
+    user_id TEXT PRIMARY KEY,
+    -- 前回の /sync で得た next_batch
+    next_batch TEXT NOT NULL
+);
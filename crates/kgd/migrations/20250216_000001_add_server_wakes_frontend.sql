@@ -0,0 +1,3 @@
+-- WOL を送ったフロントエンドを記録する（Discord 以外からも送れるようにしたため）
+-- NULL の行は、user_id があれば Discord、なければ定期実行ジョブから送ったものとして扱う
+ALTER TABLE server_wakes ADD COLUMN frontend TEXT;
@@ -0,0 +1,199 @@
+//! チャットサービスに依存しない Bot のコマンドを提供する。
+//!
+//! Discord・Telegram などのフロントエンドは、受け取ったコマンドを [`Command`] に変換し、
+//! [`is_authorized`] で実行できるユーザーかを確かめてから [`execute`] で実行する。
+//! 確認の手順を挟めないため、電源の操作（シャットダウン・再起動・サスペンド）はここでは扱わない。
+
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use tracing::{info, warn};
+
+use crate::{
+    config::{Config, ServerConfig},
+    status::{self, ServerStatus},
+    store::{BotStorage as _, BotStore, WakeRequester},
+    wol::send_wol_packet,
+};
+
+/// Bot のコマンド。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// サーバーに Wake-on-LAN のパケットを送る
+    Wol {
+        /// サーバー名
+        server: String,
+    },
+    /// サーバーのステータスを確認する
+    Status,
+}
+
+impl Command {
+    /// `/wol サーバー名` 形式のテキストのコマンドを解析する。
+    ///
+    /// コマンド名の後ろの `@bot名` は無視する。
+    /// コマンドでないテキストや未知のコマンドは None を、引数が足りない場合はエラーを返す。
+    pub fn parse_text(text: &str) -> Result<Option<Self>> {
+        let Some(text) = text.trim().strip_prefix('/') else {
+            return Ok(None);
+        };
+        let (name, argument) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let name = name.split_once('@').map_or(name, |(name, _)| name);
+        let argument = argument.trim();
+
+        match name {
+            "wol" => {
+                anyhow::ensure!(!argument.is_empty(), "Usage: /wol <server>");
+                Ok(Some(Command::Wol {
+                    server: argument.to_string(),
+                }))
+            }
+            "status" => Ok(Some(Command::Status)),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// コマンドを実行できるユーザーかどうかを返す。
+///
+/// 管理者が設定されていない場合は、すべてのユーザーに許可する
+/// （誰でも Bot に届く Telegram では、管理者の設定を必須にしている）。
+pub fn is_authorized<T: PartialEq>(admins: &[T], user_id: &T) -> bool {
+    admins.is_empty() || admins.contains(user_id)
+}
+
+/// コマンドを実行し、結果のメッセージを返す。
+///
+/// `/wol` は起動を待たずに返し、起動したかどうかは別のタスクで確認して記録する。
+pub async fn execute(
+    config: &Config,
+    store: &BotStore,
+    command: &Command,
+    requested_by: WakeRequester,
+) -> Result<String> {
+    match command {
+        Command::Wol { server } => {
+            let (server, wake_id) = wake_server(config, store, server, requested_by).await?;
+            if let Some(wake_id) = wake_id {
+                spawn_boot_watch(config, store, server, wake_id);
+            }
+            Ok(format!(
+                "Sent WOL packet to {} ({})",
+                server.name, server.mac_address
+            ))
+        }
+        Command::Status => {
+            let statuses = status::check_servers(&config.servers, status::PING_TIMEOUT).await;
+            Ok(format_statuses(&statuses))
+        }
+    }
+}
+
+/// 名前で指定したサーバーに Wake-on-LAN のパケットを送り、送ったことを記録する。
+///
+/// 記録に失敗しても WOL の結果には影響させず、警告のログを出して記録の ID を None にする。
+pub async fn wake_server<'a>(
+    config: &'a Config,
+    store: &BotStore,
+    name: &str,
+    requested_by: WakeRequester,
+) -> Result<(&'a ServerConfig, Option<i64>)> {
+    let server = config
+        .find_server(name)
+        .with_context(|| format!("Server '{}' not found", name))?;
+    send_wol_packet(server.mac_address, None).context("Failed to send WOL packet")?;
+    info!(server = %server.name, mac = %server.mac_address, "WOL packet sent");
+
+    let wake_id = match store
+        .record_wake(&server.name, requested_by, chrono::Utc::now())
+        .await
+    {
+        Ok(wake_id) => Some(wake_id),
+        Err(e) => {
+            warn!(error = %e, server = %server.name, "Failed to record server wake");
+            None
+        }
+    };
+    Ok((server, wake_id))
+}
+
+/// WOL を送ったサーバーが起動するまで待ち、起動した場合はその日時を記録して経過時間を返す。
+///
+/// `timeout` までに起動しなかった場合は None を返す。
+pub async fn wait_for_boot(
+    config: &Config,
+    store: &BotStore,
+    server: &ServerConfig,
+    wake_id: Option<i64>,
+    timeout: Duration,
+) -> Option<Duration> {
+    let elapsed =
+        status::wait_until_online(server, config.status.wol_poll_interval, timeout).await?;
+    if let Some(wake_id) = wake_id
+        && let Err(e) = store.record_wake_online(wake_id, chrono::Utc::now()).await
+    {
+        warn!(error = %e, wake_id, "Failed to record server online time");
+    }
+    Some(elapsed)
+}
+
+/// WOL を送ったサーバーが起動するのを別のタスクで待ち、起動した日時を記録する。
+///
+/// 結果を報告しない呼び出し元（Telegram・定期実行ジョブ）が、起動を待つ間に他の処理を止めないようにする。
+pub fn spawn_boot_watch(config: &Config, store: &BotStore, server: &ServerConfig, wake_id: i64) {
+    let config = config.clone();
+    let store = store.clone();
+    let server = server.clone();
+    tokio::spawn(async move {
+        let timeout = config.status.wol_boot_timeout;
+        wait_for_boot(&config, &store, &server, Some(wake_id), timeout).await;
+    });
+}
+
+/// サーバーのステータスの一覧をテキストにする。
+pub fn format_statuses(statuses: &[ServerStatus]) -> String {
+    let mut lines = vec!["Server Status".to_string()];
+    for status in statuses {
+        let status_text = if status.online { "Online" } else { "Offline" };
+        lines.push(format!("{}: {}", status.name, status_text));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_text_command() {
+        assert_eq!(
+            Command::parse_text("/wol Main Server").unwrap(),
+            Some(Command::Wol {
+                server: "Main Server".to_string()
+            })
+        );
+        assert_eq!(
+            Command::parse_text("/status@kgd_bot").unwrap(),
+            Some(Command::Status)
+        );
+        assert_eq!(Command::parse_text("おはよう").unwrap(), None);
+        assert_eq!(Command::parse_text("/help").unwrap(), None);
+        // 電源の操作は確認の手順がある Discord からだけ受け付ける
+        assert_eq!(
+            Command::parse_text("/shutdown Storage Server").unwrap(),
+            None
+        );
+        assert_eq!(
+            Command::parse_text("/reboot@kgd_bot Main Server").unwrap(),
+            None
+        );
+        assert!(Command::parse_text("/wol@kgd_bot ").is_err());
+    }
+
+    #[test]
+    fn test_is_authorized() {
+        assert!(is_authorized(&[], &1u64));
+        assert!(is_authorized(&[1u64, 2], &2));
+        assert!(!is_authorized(&[1u64], &2));
+    }
+}
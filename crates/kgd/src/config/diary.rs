@@ -0,0 +1,594 @@
+//! 日報機能の設定。
+
+use std::{path::PathBuf, time::Duration};
+
+use chrono_tz::Tz;
+use kgd_diary::{RetryPolicy, SyncFeatures, SyncOptions};
+use serde::{Deserialize, Serialize};
+use serde_with::{DisplayFromStr, serde_as};
+
+pub use kgd_diary::config::{
+    ImageRuleConfig, NotionApiVersion, NotionPropertyConfig, OversizePolicy, RedactionRuleConfig,
+    UrlRuleConfig,
+};
+
+use super::{Feature, FeaturesConfig, default_timezone};
+
+/// 日報機能の設定。
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DiaryConfig {
+    /// PostgreSQL データベース URL（`memory:` の場合はデータベースを使わずメモリ上に保存する）
+    pub database_url: String,
+    /// Notion API トークン
+    pub notion_token: String,
+    /// 日報を保存する Notion データベース ID
+    pub notion_database_id: String,
+    /// Notion データベースのタイトルプロパティ名
+    #[serde(default = "default_title_property")]
+    pub notion_title_property: String,
+    /// ページ作成時に設定するプロパティ
+    #[serde(default)]
+    pub notion_properties: Vec<NotionPropertyConfig>,
+    /// 使用する Notion API のバージョン（デフォルト: "2022-06-28"、追加の日報にも使う）
+    #[serde(default)]
+    pub notion_api_version: NotionApiVersion,
+    /// 日報スレッドを作成する Discord フォーラムチャンネル ID
+    pub forum_channel_id: u64,
+    /// 追加で運用する日報（フォーラムチャンネルと Notion データベースの組）の一覧
+    #[serde(default)]
+    pub additional_diaries: Vec<DiaryTargetConfig>,
+    /// メッセージを同期する契機（デフォルト: all）
+    #[serde(default)]
+    pub sync_mode: SyncMode,
+    /// クローズ済みの日報スレッドへの投稿も同期するか（デフォルト: true）
+    #[serde(default = "default_sync_after_close")]
+    pub sync_after_close: bool,
+    /// opt-in モード（`sync_mode = "reaction"`）で同期の契機にするリアクション絵文字
+    #[serde(default = "default_sync_trigger_reaction")]
+    pub sync_trigger_reaction: String,
+    /// 同期成功時にメッセージに付けるリアクション絵文字
+    #[serde(default = "default_sync_reaction")]
+    pub sync_reaction: String,
+    /// 一部の添付ファイルを同期しなかった、または警告付きで同期したメッセージに付けるリアクション絵文字
+    #[serde(default = "default_partial_sync_reaction")]
+    pub partial_sync_reaction: String,
+    /// 同期に失敗したメッセージに付けるリアクション絵文字
+    #[serde(default = "default_failed_sync_reaction")]
+    pub failed_sync_reaction: String,
+    /// 同期に失敗したときにメッセージの投稿者へ通知する方法（デフォルト: reply）
+    #[serde(default)]
+    pub sync_failure_notification: SyncFailureNotification,
+    /// 日報フォーラムでリアクションを付与する権限が無い場合の同期結果の伝え方（デフォルト: silent）
+    #[serde(default)]
+    pub reaction_fallback: ReactionFallback,
+    /// `reaction_fallback` が `thread_message` の場合に、スレッドへ報告する同期件数の間隔（デフォルト: 10）
+    #[serde(default = "default_reaction_fallback_interval")]
+    pub reaction_fallback_interval: u32,
+    /// 同期するメッセージの投稿者のユーザー ID 一覧（空の場合は全員のメッセージを同期する）
+    #[serde(default)]
+    pub sync_users: Vec<u64>,
+    /// 同期しないメッセージの投稿者のユーザー ID 一覧（`sync_users` より優先する）
+    #[serde(default)]
+    pub ignore_users: Vec<u64>,
+    /// 日報の日付計算に使用するタイムゾーン（デフォルト: Asia/Tokyo）
+    #[serde(default = "default_timezone")]
+    #[serde_as(as = "DisplayFromStr")]
+    pub timezone: Tz,
+    /// URL 変換ルール
+    /// パターンにマッチした URL を指定したブロックタイプに変換する
+    #[serde(default)]
+    pub url_rules: Vec<UrlRuleConfig>,
+    /// どのルールにもマッチしなかった URL に適用するデフォルトの変換（デフォルト: ["link"]）
+    #[serde(default = "default_convert_to")]
+    pub default_convert_to: Vec<String>,
+    /// 同期する前に URL から既知のトラッキングパラメータ（utm_* など）を取り除くか（デフォルト: false）
+    #[serde(default)]
+    pub strip_tracking_params: bool,
+    /// Notion に同期する前にメッセージ本文へ適用する伏せ字ルール
+    #[serde(default)]
+    pub redaction_rules: Vec<RedactionRuleConfig>,
+    /// 添付画像を Notion で扱いやすい形式に変換する正規化ルール（記述順に評価し最初に一致したものを適用）
+    #[serde(default)]
+    pub image_rules: Vec<ImageRuleConfig>,
+    /// アップロード前に画像を縮小する長辺の最大ピクセル数（未設定の場合は縮小しない）
+    #[serde(default)]
+    pub image_max_dimension: Option<u32>,
+    /// アップロード前に JPEG 画像を再圧縮する品質（1〜100、未設定の場合は再圧縮しない）
+    #[serde(default)]
+    pub image_jpeg_quality: Option<u8>,
+    /// 前回同期したメッセージからこの時間以上空いた場合に `## 14:00` のような見出しを挟む（未設定の場合は挟まない）
+    #[serde(default, with = "humantime_serde")]
+    pub section_heading_interval: Option<Duration>,
+    /// Notion ページのタイトルのフォーマット（デフォルト: `{{date}}`）
+    #[serde(default = "default_title_format")]
+    pub page_title_format: String,
+    /// 日報スレッド名のフォーマット（デフォルト: `{{date}}`）
+    #[serde(default = "default_title_format")]
+    pub thread_title_format: String,
+    /// 日報ページの作成時に挿入する雛形ブロック（Notion ブロック JSON の配列、`{{date}}` は日付に置換する）
+    #[serde(default)]
+    pub page_template_blocks: Option<String>,
+    /// スレッドの最初のメッセージで Notion リンクの展開を抑え、bot が作成した小さな埋め込みを付けるか（デフォルト: false）
+    #[serde(default)]
+    pub compact_notion_link: bool,
+    /// 新しい日報スレッドに自動で追加するユーザー ID 一覧
+    #[serde(default)]
+    pub thread_members: Vec<u64>,
+    /// 日報スレッドを作成したユーザーをスレッドに追加するか（デフォルト: false）
+    #[serde(default)]
+    pub add_thread_creator: bool,
+    /// スレッドに追加したユーザーをメンションして新しい日報を知らせるか（デフォルト: false）
+    #[serde(default)]
+    pub thread_rollcall: bool,
+    /// 指定したチャンネルのキーワード付きメッセージを今日の日報に転記する設定（未設定の場合は無効）
+    #[serde(default)]
+    pub keyword_trigger: Option<KeywordTriggerConfig>,
+    /// 書き出しを付けたメッセージを投稿者ごとの個人用のページに同期する設定（未設定の場合は無効）
+    #[serde(default)]
+    pub private_notes: Option<PrivateNotesConfig>,
+    /// 自動クローズ機能を有効にするか（デフォルト: false）
+    #[serde(default)]
+    pub auto_close_enabled: bool,
+    /// 自動クローズの確認メッセージを送信する時刻（時）（デフォルト: 8）
+    #[serde(default = "default_auto_close_hour")]
+    pub auto_close_hour: u32,
+    /// 毎週月曜日に前週分の週報ページを作成するか（デフォルト: false）
+    #[serde(default)]
+    pub weekly_report_enabled: bool,
+    /// 毎月 1 日に前月分の月報ページを作成するか（デフォルト: false）
+    #[serde(default)]
+    pub monthly_report_enabled: bool,
+    /// 週報・月報ページを作成する時刻（時）（デフォルト: 9）
+    #[serde(default = "default_report_hour")]
+    pub report_hour: u32,
+    /// 起動時に Notion のブロックとの対応を突き合わせる日報の日数（0 の場合は起動時に実行しない）（デフォルト: 3）
+    #[serde(default = "default_reconcile_days")]
+    pub reconcile_days: u32,
+    /// OGP メタデータ取得を有効にするか（デフォルト: true）
+    #[serde(default = "default_ogp_enabled")]
+    pub ogp_enabled: bool,
+    /// OGP メタデータ取得のタイムアウト（デフォルト: 10秒）
+    #[serde(default = "default_ogp_timeout", with = "humantime_serde")]
+    pub ogp_timeout: Duration,
+    /// X の投稿の取得のタイムアウト（デフォルト: 10秒）
+    #[serde(default = "default_tweet_timeout", with = "humantime_serde")]
+    pub tweet_timeout: Duration,
+    /// GitHub の URL の展開に使う GitHub API トークン（デフォルト: なし）
+    #[serde(default)]
+    pub github_token: Option<String>,
+    /// Notion API の読み取り結果をキャッシュする期間（デフォルト: 1分、0 で無効）
+    #[serde(default = "default_notion_cache_ttl", with = "humantime_serde")]
+    pub notion_cache_ttl: Duration,
+    /// 1 メッセージあたりに同期する添付ファイル数の上限（デフォルト: 10）
+    #[serde(default = "default_max_attachments_per_message")]
+    pub max_attachments_per_message: usize,
+    /// 1 メッセージあたりに同期する添付ファイルの合計バイト数の上限（デフォルト: 100 MiB）
+    #[serde(default = "default_max_attachment_bytes_per_message")]
+    pub max_attachment_bytes_per_message: u64,
+    /// 1 日（日報スレッド）あたりに同期する添付ファイルの合計バイト数の上限（デフォルト: 1 GiB）
+    #[serde(default = "default_max_attachment_bytes_per_day")]
+    pub max_attachment_bytes_per_day: u64,
+    /// Notion にアップロードする添付ファイル 1 件あたりのサイズの上限（デフォルト: 100 MiB）
+    #[serde(default = "default_max_attachment_size")]
+    pub max_attachment_size: u64,
+    /// `max_attachment_size` を超える添付ファイルの扱い（デフォルト: skip）
+    #[serde(default)]
+    pub on_oversize: OversizePolicy,
+    /// これを超えるサイズの添付ファイルはメモリに載せず一時ファイルにダウンロードする（デフォルト: 16 MiB）
+    #[serde(default = "default_attachment_memory_threshold")]
+    pub attachment_memory_threshold: u64,
+    /// ダウンロードや変換に使う一時ファイルのディレクトリ（デフォルト: システムの一時ディレクトリ配下の kgd）
+    #[serde(default)]
+    pub temp_dir: Option<PathBuf>,
+    /// 一時ファイルの合計サイズの上限（デフォルト: 1 GiB）
+    #[serde(default = "default_temp_dir_quota")]
+    pub temp_dir_quota: u64,
+    /// 動画処理に使う ffmpeg 実行ファイルのパス（デフォルト: "ffmpeg"）
+    #[serde(default = "default_ffmpeg_path")]
+    pub ffmpeg_path: PathBuf,
+    /// ffmpeg 1 回の実行のタイムアウト（デフォルト: 5分）
+    #[serde(default = "default_ffmpeg_timeout", with = "humantime_serde")]
+    pub ffmpeg_timeout: Duration,
+    /// mermaid の図の描画に使う mmdc（mermaid-cli）実行ファイルのパス（デフォルト: "mmdc"）
+    #[serde(default = "default_mermaid_path")]
+    pub mermaid_path: PathBuf,
+    /// mmdc 1 回の実行のタイムアウト（デフォルト: 1分）
+    #[serde(default = "default_mermaid_timeout", with = "humantime_serde")]
+    pub mermaid_timeout: Duration,
+    /// 動画を H.264 に変換する際の映像ビットレート（kbps、デフォルト: 4000）
+    #[serde(default = "default_video_transcode_bitrate_kbps")]
+    pub video_transcode_bitrate_kbps: u32,
+    /// これを超えるサイズの動画は .mov 以外も H.264 に変換する（デフォルト: 50 MiB）
+    #[serde(default = "default_video_transcode_min_size")]
+    pub video_transcode_min_size: u64,
+    /// 一時的な失敗に対する最大試行回数（初回を含む、デフォルト: 5）
+    #[serde(default = "default_retry_max_attempts")]
+    pub retry_max_attempts: u32,
+    /// 初回リトライまでの待機時間（デフォルト: 500ミリ秒）
+    #[serde(default = "default_retry_initial_backoff", with = "humantime_serde")]
+    pub retry_initial_backoff: Duration,
+    /// リトライ時の待機時間の上限（デフォルト: 30秒）
+    #[serde(default = "default_retry_max_backoff", with = "humantime_serde")]
+    pub retry_max_backoff: Duration,
+}
+
+impl DiaryConfig {
+    /// メッセージの同期の設定を返す。
+    ///
+    /// 変換処理の有効/無効は機能フラグから決める。
+    pub fn sync_options(&self, features: &FeaturesConfig) -> SyncOptions {
+        SyncOptions {
+            url_rules: self.url_rules.clone(),
+            default_convert_to: self.default_convert_to.clone(),
+            strip_tracking_params: self.strip_tracking_params,
+            redaction_rules: self.redaction_rules.clone(),
+            image_rules: self.image_rules.clone(),
+            image_max_dimension: self.image_max_dimension,
+            image_jpeg_quality: self.image_jpeg_quality,
+            ogp_timeout: (self.ogp_enabled && features.is_enabled(Feature::OgpCaptions))
+                .then_some(self.ogp_timeout),
+            tweet_timeout: self.tweet_timeout,
+            github_token: self.github_token.clone(),
+            max_attachments_per_message: self.max_attachments_per_message,
+            max_attachment_bytes_per_message: self.max_attachment_bytes_per_message,
+            max_attachment_bytes_per_day: self.max_attachment_bytes_per_day,
+            max_attachment_size: self.max_attachment_size,
+            on_oversize: self.on_oversize,
+            attachment_memory_threshold: self.attachment_memory_threshold,
+            ffmpeg_path: self.ffmpeg_path.clone(),
+            ffmpeg_timeout: self.ffmpeg_timeout,
+            mermaid_path: self.mermaid_path.clone(),
+            mermaid_timeout: self.mermaid_timeout,
+            video_transcode_bitrate_kbps: self.video_transcode_bitrate_kbps,
+            video_transcode_min_size: self.video_transcode_min_size,
+            sync_users: self.sync_users.clone(),
+            ignore_users: self.ignore_users.clone(),
+            sync_after_close: self.sync_after_close,
+            section_heading_interval: self.section_heading_interval,
+            timezone: self.timezone,
+            retry_policy: self.retry_policy(),
+            features: SyncFeatures {
+                heic_conversion: features.is_enabled(Feature::HeicConversion),
+                spoiler_toggle: features.is_enabled(Feature::SpoilerToggle),
+                custom_emoji_images: features.is_enabled(Feature::CustomEmojiImages),
+                video_thumbnails: features.is_enabled(Feature::VideoThumbnails),
+                video_transcode: features.is_enabled(Feature::VideoTranscode),
+                time_tracking: features.is_enabled(Feature::TimeTracking),
+                markdown_tables: features.is_enabled(Feature::MarkdownTables),
+                markdown_quotes: features.is_enabled(Feature::MarkdownQuotes),
+                math_equations: features.is_enabled(Feature::MathEquations),
+                code_blocks: features.is_enabled(Feature::CodeBlocks),
+                mermaid_diagrams: features.is_enabled(Feature::MermaidDiagrams),
+            },
+        }
+    }
+
+    /// Notion API 呼び出しや添付ファイルのダウンロードに使うリトライ方針を返す。
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::new(
+            self.retry_max_attempts,
+            self.retry_initial_backoff,
+            self.retry_max_backoff,
+        )
+    }
+}
+
+/// 日報スレッドのメッセージを同期する契機。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncMode {
+    /// すべてのメッセージを投稿時に同期する
+    #[default]
+    All,
+    /// `sync_trigger_reaction` のリアクションが付いたメッセージだけを同期する
+    Reaction,
+}
+
+/// 同期に失敗したときの投稿者への通知方法。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncFailureNotification {
+    /// 通知しない
+    Off,
+    /// 失敗したメッセージに返信する
+    #[default]
+    Reply,
+    /// 投稿者に DM を送る
+    Dm,
+}
+
+/// リアクションを付与する権限が無い場合の同期結果の伝え方。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReactionFallback {
+    /// 何もしない
+    #[default]
+    Silent,
+    /// 一定件数の同期ごとにスレッドへメッセージを送る
+    ThreadMessage,
+}
+
+/// 追加で運用する日報の設定（フォーラムチャンネルと Notion データベースの組）。
+///
+/// 同期などの設定と Notion API トークンは `[diary]` の設定を共有する。
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DiaryTargetConfig {
+    /// 日報スレッドを作成する Discord フォーラムチャンネル ID
+    pub forum_channel_id: u64,
+    /// 日報を保存する Notion データベース ID
+    pub notion_database_id: String,
+    /// Notion データベースのタイトルプロパティ名
+    #[serde(default = "default_title_property")]
+    pub notion_title_property: String,
+    /// ページ作成時に設定するプロパティ
+    #[serde(default)]
+    pub notion_properties: Vec<NotionPropertyConfig>,
+}
+
+/// キーワードによる日報への転記の設定。
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct KeywordTriggerConfig {
+    /// キーワード付きメッセージを監視するチャンネル ID
+    pub channel_id: u64,
+    /// 転記の対象にするメッセージの書き出し（デフォルト: "日報:"）
+    #[serde(default = "default_keyword_trigger_prefix")]
+    pub prefix: String,
+    /// 書き出しの代わりに使う正規表現パターン（マッチした部分を取り除いて転記する）
+    #[serde(default)]
+    pub pattern: Option<String>,
+}
+
+/// 個人用のメモの設定。
+///
+/// `prefix` で始まるメッセージは共有の日報ページではなく、投稿者ごとに設定した同期先に同期する。
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PrivateNotesConfig {
+    /// 個人用のメモにするメッセージの書き出し（デフォルト: "!private"）
+    #[serde(default = "default_private_notes_prefix")]
+    pub prefix: String,
+    /// 投稿者ごとの同期先
+    #[serde(default)]
+    pub destinations: Vec<PrivateNoteDestinationConfig>,
+}
+
+/// 個人用のメモの同期先。
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PrivateNoteDestinationConfig {
+    /// 投稿者の Discord ユーザー ID
+    pub user_id: u64,
+    /// 同期先の Notion ページまたはデータベース
+    #[serde(flatten)]
+    pub target: PrivateNoteTarget,
+}
+
+/// 個人用のメモを同期する Notion のページまたはデータベース。
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum PrivateNoteTarget {
+    /// すべてのメモを 1 つのページに追加する
+    Page {
+        /// 同期先の Notion ページの URL（または ID）
+        page_url: String,
+    },
+    /// 日付ごとのページ（日報と同じタイトル）に追加する。ページが無ければ作成する
+    Database {
+        /// 同期先の Notion データベース ID
+        notion_database_id: String,
+        /// Notion データベースのタイトルプロパティ名
+        #[serde(default = "default_title_property")]
+        notion_title_property: String,
+    },
+}
+
+fn default_private_notes_prefix() -> String {
+    "!private".to_string()
+}
+
+fn default_title_property() -> String {
+    "Name".to_string()
+}
+
+fn default_keyword_trigger_prefix() -> String {
+    "日報:".to_string()
+}
+
+fn default_title_format() -> String {
+    "{{date}}".to_string()
+}
+
+fn default_sync_reaction() -> String {
+    "✅".to_string()
+}
+
+fn default_partial_sync_reaction() -> String {
+    "🟡".to_string()
+}
+
+fn default_failed_sync_reaction() -> String {
+    "❌".to_string()
+}
+
+fn default_sync_trigger_reaction() -> String {
+    "📝".to_string()
+}
+
+fn default_reaction_fallback_interval() -> u32 {
+    10
+}
+
+fn default_convert_to() -> Vec<String> {
+    vec!["link".to_string()]
+}
+
+fn default_auto_close_hour() -> u32 {
+    8
+}
+
+fn default_report_hour() -> u32 {
+    9
+}
+
+fn default_reconcile_days() -> u32 {
+    3
+}
+
+fn default_sync_after_close() -> bool {
+    true
+}
+
+fn default_ogp_enabled() -> bool {
+    true
+}
+
+fn default_ogp_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_tweet_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_notion_cache_ttl() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_max_attachments_per_message() -> usize {
+    10
+}
+
+fn default_max_attachment_bytes_per_message() -> u64 {
+    100 * 1024 * 1024
+}
+
+fn default_max_attachment_bytes_per_day() -> u64 {
+    1024 * 1024 * 1024
+}
+
+fn default_max_attachment_size() -> u64 {
+    100 * 1024 * 1024
+}
+
+fn default_attachment_memory_threshold() -> u64 {
+    16 * 1024 * 1024
+}
+
+fn default_temp_dir_quota() -> u64 {
+    1024 * 1024 * 1024
+}
+
+fn default_ffmpeg_path() -> PathBuf {
+    PathBuf::from("ffmpeg")
+}
+
+fn default_ffmpeg_timeout() -> Duration {
+    Duration::from_secs(5 * 60)
+}
+
+fn default_mermaid_path() -> PathBuf {
+    PathBuf::from("mmdc")
+}
+
+fn default_mermaid_timeout() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_video_transcode_bitrate_kbps() -> u32 {
+    4000
+}
+
+fn default_video_transcode_min_size() -> u64 {
+    50 * 1024 * 1024
+}
+
+fn default_retry_max_attempts() -> u32 {
+    5
+}
+
+fn default_retry_initial_backoff() -> Duration {
+    Duration::from_millis(500)
+}
+
+fn default_retry_max_backoff() -> Duration {
+    Duration::from_secs(30)
+}
+
+#[cfg(test)]
+mod tests {
+    use kgd_diary::config::NotionPropertyValue;
+
+    use super::*;
+
+    #[test]
+    fn test_private_note_destinations() {
+        let parsed: PrivateNotesConfig = toml::from_str(
+            r#"
+            [[destinations]]
+            user_id = 1
+            page_url = "https://www.notion.so/notes-0123456789abcdef0123456789abcdef"
+
+            [[destinations]]
+            user_id = 2
+            notion_database_id = "db"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(parsed.prefix, "!private");
+        assert_eq!(
+            parsed.destinations[0].target,
+            PrivateNoteTarget::Page {
+                page_url: "https://www.notion.so/notes-0123456789abcdef0123456789abcdef"
+                    .to_string()
+            }
+        );
+        assert_eq!(
+            parsed.destinations[1].target,
+            PrivateNoteTarget::Database {
+                notion_database_id: "db".to_string(),
+                notion_title_property: "Name".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_notion_property_types() {
+        #[derive(Deserialize)]
+        struct Properties {
+            properties: Vec<NotionPropertyConfig>,
+        }
+
+        let parsed: Properties = toml::from_str(
+            r#"
+            [[properties]]
+            property = "Type"
+            type = "multi_select"
+            value = ["日報"]
+
+            [[properties]]
+            property = "Date"
+            type = "date"
+
+            [[properties]]
+            property = "Owner"
+            type = "people"
+            value = ["user-id"]
+            "#,
+        )
+        .unwrap();
+
+        let values: Vec<_> = parsed.properties.into_iter().map(|p| p.value).collect();
+        assert_eq!(
+            values,
+            vec![
+                NotionPropertyValue::MultiSelect {
+                    value: vec!["日報".to_string()]
+                },
+                NotionPropertyValue::Date,
+                NotionPropertyValue::People {
+                    value: vec!["user-id".to_string()]
+                },
+            ]
+        );
+    }
+}
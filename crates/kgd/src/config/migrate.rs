@@ -0,0 +1,263 @@
+//! 古い形式の設定ファイルを現在の形式に移行する。
+//!
+//! 設定ファイルを TOML のまま読み込んで移行を順に適用し、現在の [`Config`] として読み込めることを
+//! 確かめてから書き戻す。書き戻した設定ファイルからはコメントが消えるため、
+//! 書き戻す前に元のファイルをバックアップとして残す。
+//!
+//! 移行できるのは、廃止した `[[diary.notion_tags]]` から `[[diary.notion_properties]]` への書き換えだけ。
+//! それ以外の設定の変更は項目の追加だけで、古い設定ファイルのまま読み込める。
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+use toml::{Table, Value};
+
+use super::Config;
+
+/// 設定ファイルの移行の一覧（古いものから順に適用する）。
+///
+/// 各移行は該当する設定があれば書き換え、変更した内容の説明を返す。
+/// 既存の設定を読み込めなくする変更を入れた場合は、ここに移行を追加する。
+const MIGRATIONS: &[fn(&mut Table) -> Vec<String>] = &[migrate_notion_tags];
+
+/// 設定ファイルの移行の結果。
+#[derive(Debug)]
+pub struct MigrationReport {
+    /// 変更した内容の説明
+    pub changes: Vec<String>,
+    /// 元のファイルのバックアップ（書き戻していない場合は None）
+    pub backup: Option<PathBuf>,
+}
+
+/// 設定ファイルを現在の形式に移行する。
+///
+/// 変更がある場合は元のファイルを `{ファイル名}.{日時}.bak` にコピーしてから書き戻す。
+/// `dry_run` が true の場合は変更内容だけを返し、ファイルは変更しない。
+pub fn migrate_config_file(path: impl AsRef<Path>, dry_run: bool) -> Result<MigrationReport> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path).context("Failed to read configuration file")?;
+    let (migrated, changes) = migrate_config(&content)?;
+
+    if changes.is_empty() || dry_run {
+        return Ok(MigrationReport {
+            changes,
+            backup: None,
+        });
+    }
+
+    let backup = backup_path(path);
+    fs::copy(path, &backup).context("Failed to back up configuration file")?;
+    fs::write(path, migrated).context("Failed to write configuration file")?;
+
+    Ok(MigrationReport {
+        changes,
+        backup: Some(backup),
+    })
+}
+
+/// 設定ファイルの内容に移行を適用し、移行後の内容と変更した内容の説明を返す。
+///
+/// 移行後の内容が現在の設定として読み込めない場合はエラーを返す。
+pub fn migrate_config(content: &str) -> Result<(String, Vec<String>)> {
+    let mut table: Table = toml::from_str(content).context("Failed to parse configuration file")?;
+    let changes: Vec<String> = MIGRATIONS
+        .iter()
+        .flat_map(|migration| migration(&mut table))
+        .collect();
+    if changes.is_empty() {
+        return Ok((content.to_string(), changes));
+    }
+
+    let migrated =
+        toml::to_string_pretty(&table).context("Failed to serialize migrated configuration")?;
+    toml::from_str::<Config>(&migrated)
+        .context("Migrated configuration is still invalid, fix it by hand")?;
+    Ok((migrated, changes))
+}
+
+/// 元のファイルのバックアップの保存先を返す（既存のバックアップを上書きしないよう日時を付ける）。
+fn backup_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "config.toml".to_string());
+    path.with_file_name(format!(
+        "{}.{}.bak",
+        file_name,
+        chrono::Local::now().format("%Y%m%d%H%M%S")
+    ))
+}
+
+/// `[[diary.notion_tags]]` を `[[diary.notion_properties]]` に書き換える。
+///
+/// タグは `multi_select` の有無で select と multi_select のプロパティになる。
+/// 既に `notion_properties` がある場合はその前に追加し、同じプロパティは既存の設定を優先する。
+fn migrate_notion_tags(config: &mut Table) -> Vec<String> {
+    let Some(Value::Table(diary)) = config.get_mut("diary") else {
+        return Vec::new();
+    };
+    let Some(Value::Array(tags)) = diary.remove("notion_tags") else {
+        return Vec::new();
+    };
+
+    let mut changes = Vec::new();
+    let mut properties = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let Value::Table(mut tag) = tag else {
+            continue;
+        };
+        let multi_select = tag
+            .remove("multi_select")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+        let Some(value) = tag.remove("value") else {
+            continue;
+        };
+        let property = tag
+            .get("property")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        let (kind, value) = if multi_select {
+            ("multi_select", Value::Array(vec![value]))
+        } else {
+            ("select", value)
+        };
+        tag.insert("type".to_string(), Value::String(kind.to_string()));
+        tag.insert("value".to_string(), value);
+        changes.push(format!(
+            "diary.notion_tags: moved the tag for \"{}\" to diary.notion_properties (type = \"{}\")",
+            property, kind
+        ));
+        properties.push(Value::Table(tag));
+    }
+
+    if let Some(Value::Array(existing)) = diary.remove("notion_properties") {
+        properties.extend(existing);
+    }
+    diary.insert("notion_properties".to_string(), Value::Array(properties));
+
+    if changes.is_empty() {
+        changes.push("diary.notion_tags: removed the empty list".to_string());
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_config_file_keeps_backup() {
+        let dir = std::env::temp_dir().join(format!("kgd-migrate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        let old = format!(
+            "{}\n# Tag for new pages\n[[diary.notion_tags]]\nproperty = \"Type\"\nvalue = \"日報\"\n",
+            include_str!("../../../../config.example.toml")
+        );
+        fs::write(&path, &old).unwrap();
+
+        // dry run ではファイルを変更しない
+        let report = migrate_config_file(&path, true).unwrap();
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(report.backup, None);
+        assert_eq!(fs::read_to_string(&path).unwrap(), old);
+
+        let report = migrate_config_file(&path, false).unwrap();
+        let backup = report.backup.unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), old);
+        assert!(
+            !fs::read_to_string(&path)
+                .unwrap()
+                .contains("diary.notion_tags")
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_notion_tags() {
+        let mut config: Table = toml::from_str(
+            r#"
+            [diary]
+            notion_token = "secret"
+
+            [[diary.notion_tags]]
+            property = "Type"
+            value = "日報"
+            multi_select = true
+
+            [[diary.notion_tags]]
+            property = "Status"
+            value = "Open"
+
+            [[diary.notion_properties]]
+            property = "Date"
+            type = "date"
+            "#,
+        )
+        .unwrap();
+
+        let changes = migrate_notion_tags(&mut config);
+        assert_eq!(changes.len(), 2);
+        assert!(changes[0].contains("\"Type\""));
+
+        let expected: Table = toml::from_str(
+            r#"
+            [diary]
+            notion_token = "secret"
+
+            [[diary.notion_properties]]
+            property = "Type"
+            type = "multi_select"
+            value = ["日報"]
+
+            [[diary.notion_properties]]
+            property = "Status"
+            type = "select"
+            value = "Open"
+
+            [[diary.notion_properties]]
+            property = "Date"
+            type = "date"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config, expected);
+
+        // 移行済みの設定は変更しない
+        assert!(migrate_notion_tags(&mut config).is_empty());
+    }
+
+    #[test]
+    fn test_migrate_config() {
+        let content = include_str!("../../../../config.example.toml");
+        let (migrated, changes) = migrate_config(content).unwrap();
+        assert!(changes.is_empty());
+        assert_eq!(migrated, content);
+
+        let old = format!(
+            "{}\n[[diary.notion_tags]]\nproperty = \"Type\"\nvalue = \"日報\"\n",
+            content
+        );
+        let (migrated, changes) = migrate_config(&old).unwrap();
+        assert_eq!(changes.len(), 1);
+        #[cfg_attr(not(feature = "diary"), allow(unused_variables))]
+        let config: Config = toml::from_str(&migrated).unwrap();
+        #[cfg(feature = "diary")]
+        assert_eq!(
+            config.diary.notion_properties,
+            vec![kgd_diary::config::NotionPropertyConfig {
+                property: "Type".to_string(),
+                value: kgd_diary::config::NotionPropertyValue::Select {
+                    value: "日報".to_string()
+                },
+            }]
+        );
+    }
+}
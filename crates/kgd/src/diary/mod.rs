@@ -2,31 +2,23 @@
 //!
 //! フォーラムスレッドと Notion ページを紐付け、
 //! メッセージの同期とライフサイクル管理を行う。
+//! 同期の処理は `kgd-diary` クレートが担い、ここでは Discord との接続部分を扱う。
 
-mod notion;
-mod ogp;
-mod store;
-mod sync;
-mod url_parser;
+mod page;
+mod private;
+mod source;
+mod trigger;
 
-pub use notion::NotionClient;
-pub use store::{DiaryEntry, DiaryStore, MessageBlock};
-pub use sync::MessageSyncer;
-pub use url_parser::compile_url_rules;
-
-use chrono::{DateTime, NaiveTime, Utc};
-use chrono_tz::Tz;
-
-/// 指定されたタイムゾーンでの現在の日付の開始時刻（00:00:00）を UTC で取得する。
-pub fn today_in_timezone(tz: &Tz) -> DateTime<Utc> {
-    Utc::now()
-        .with_timezone(tz)
-        .with_time(NaiveTime::MIN)
-        .unwrap()
-        .to_utc()
-}
-
-/// 指定されたタイムゾーンでの日付を "YYYY-MM-DD" 形式の文字列として取得する。
-pub fn format_date_in_timezone(date: DateTime<Utc>, tz: &Tz) -> String {
-    date.with_timezone(tz).format("%Y-%m-%d").to_string()
-}
+pub use kgd_diary::{
+    DiaryEntry, DiaryStorage, DiaryStore, EmojiCount, EventOutcome, MessageEvent, NotionClient,
+    PageSummary, PageTemplate, ReconcileResult, ReportOutcome, ReportPeriod, RetryError,
+    SyncHistoryRecord, SyncHistoryStatus, SyncResult, TempWorkspace, compile_image_rules,
+    compile_page_template, compile_redaction_rules, compile_url_rules, due_report_periods,
+    format_date_in_timezone, is_summary_block, parse_page_id, publish_report, render_title,
+    start_of_day_in_timezone, summary_anchor, summary_placeholder_block, time_totals,
+    today_in_timezone, validate_page_title_format,
+};
+pub use page::create_templated_page;
+pub use private::{PrivateNotes, compile_private_notes};
+pub use source::{DiscordSource, MessageSyncer, source_message};
+pub use trigger::{KeywordTrigger, compile_keyword_trigger};
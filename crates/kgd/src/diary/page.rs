@@ -0,0 +1,36 @@
+//! 日報ページの作成。
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use kgd_diary::{NotionClient, PageTemplate, summary_placeholder_block};
+use tracing::warn;
+
+/// 日報ページを作成し、冒頭にサマリーの枠とテンプレートのブロックを挿入する。
+///
+/// ブロックの挿入に失敗してもページは作成済みのため、エラーはログに出力するだけにする。
+///
+/// # Returns
+/// 作成したページの ID と URL
+pub async fn create_templated_page(
+    notion_client: &NotionClient,
+    title: &str,
+    date: NaiveDate,
+    with_summary: bool,
+    template: Option<&PageTemplate>,
+) -> Result<(String, String)> {
+    let (page_id, page_url) = notion_client.create_diary_page(title, date).await?;
+
+    // サマリーはクローズ時に更新するため、ページの冒頭に置いておく
+    let mut blocks = Vec::new();
+    if with_summary {
+        blocks.push(summary_placeholder_block());
+    }
+    if let Some(template) = template {
+        blocks.extend(template.render(date));
+    }
+    if let Err(e) = notion_client.append_blocks(&page_id, blocks).await {
+        warn!(error = %e, page_id = %page_id, "Failed to insert initial page blocks");
+    }
+
+    Ok((page_id, page_url))
+}
@@ -0,0 +1,150 @@
+//! 書き出しを付けたメッセージを、共有の日報ではなく投稿者ごとの個人用のページに同期する。
+
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{Context as _, Result, bail};
+use chrono::NaiveDate;
+
+use crate::config::{DiaryConfig, PrivateNoteTarget};
+
+use super::{NotionClient, parse_page_id};
+
+/// 設定から作成した個人用のメモの同期先。
+#[derive(Clone)]
+pub struct PrivateNotes {
+    /// 個人用のメモにするメッセージの書き出し
+    prefix: String,
+    /// 投稿者の Discord ユーザー ID ごとの同期先
+    destinations: HashMap<u64, PrivateDestination>,
+}
+
+/// 投稿者ごとの同期先。
+#[derive(Clone)]
+enum PrivateDestination {
+    /// すべてのメモを追加するページの ID
+    Page(String),
+    /// 日付ごとのページを作成するデータベース
+    Database(Arc<NotionClient>),
+}
+
+impl PrivateNotes {
+    /// 個人用のメモであれば、書き出しを取り除いた本文を返す。
+    ///
+    /// 書き出しの直後が空白か本文の終わりの場合のみ個人用のメモとみなす（`!privately` などは対象外）。
+    pub fn strip_prefix<'a>(&self, content: &'a str) -> Option<&'a str> {
+        let rest = content.trim_start().strip_prefix(self.prefix.as_str())?;
+        if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        Some(rest.trim_start())
+    }
+
+    /// 投稿者の個人用のメモを同期するページ ID を返す。
+    ///
+    /// データベースが同期先の場合は `title` のページを検索し、無ければ `date` の日付で作成する。
+    /// 同期先が設定されていない投稿者はエラーとする（共有の日報に同期しないため）。
+    pub async fn page_id(&self, user_id: u64, title: &str, date: NaiveDate) -> Result<String> {
+        let Some(destination) = self.destinations.get(&user_id) else {
+            bail!(
+                "No private note destination is configured for user {}",
+                user_id
+            );
+        };
+
+        match destination {
+            PrivateDestination::Page(page_id) => Ok(page_id.clone()),
+            PrivateDestination::Database(client) => {
+                if let Some((page_id, _)) = client
+                    .find_diary_page_by_title(title)
+                    .await
+                    .context("Failed to find private note page")?
+                {
+                    return Ok(page_id);
+                }
+                let (page_id, _) = client
+                    .create_diary_page(title, date)
+                    .await
+                    .context("Failed to create private note page")?;
+                Ok(page_id)
+            }
+        }
+    }
+}
+
+/// 設定から個人用のメモの同期先を作成する。
+///
+/// 未設定の場合は `None` を返す。ページの URL が不正な場合や、同じユーザーが重複している場合はエラーとして返す。
+pub fn compile_private_notes(config: &DiaryConfig) -> Result<Option<PrivateNotes>> {
+    let Some(private_notes) = &config.private_notes else {
+        return Ok(None);
+    };
+    if private_notes.prefix.trim().is_empty() {
+        bail!("Private note prefix must not be empty");
+    }
+
+    let mut destinations = HashMap::new();
+    for destination in &private_notes.destinations {
+        let compiled = match &destination.target {
+            PrivateNoteTarget::Page { page_url } => PrivateDestination::Page(
+                parse_page_id(page_url)
+                    .with_context(|| format!("Invalid private note page URL '{}'", page_url))?,
+            ),
+            PrivateNoteTarget::Database {
+                notion_database_id,
+                notion_title_property,
+            } => PrivateDestination::Database(Arc::new(NotionClient::new(
+                &config.notion_token,
+                notion_database_id,
+                notion_title_property,
+                vec![],
+                config.notion_cache_ttl,
+                config.retry_policy(),
+                config.notion_api_version,
+            )?)),
+        };
+        if destinations.insert(destination.user_id, compiled).is_some() {
+            bail!(
+                "User {} has more than one private note destination",
+                destination.user_id
+            );
+        }
+    }
+
+    Ok(Some(PrivateNotes {
+        prefix: private_notes.prefix.clone(),
+        destinations,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn private_notes() -> PrivateNotes {
+        PrivateNotes {
+            prefix: "!private".to_string(),
+            destinations: HashMap::from([(1, PrivateDestination::Page("page".to_string()))]),
+        }
+    }
+
+    #[test]
+    fn test_strip_prefix() {
+        let notes = private_notes();
+        assert_eq!(
+            notes.strip_prefix("!private 体調がいまいち"),
+            Some("体調がいまいち")
+        );
+        assert_eq!(notes.strip_prefix("  !private\nメモ"), Some("メモ"));
+        assert_eq!(notes.strip_prefix("!private"), Some(""));
+        assert_eq!(notes.strip_prefix("!privately shared"), None);
+        assert_eq!(notes.strip_prefix("not !private"), None);
+    }
+
+    #[tokio::test]
+    async fn test_page_id() {
+        let notes = private_notes();
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        assert_eq!(notes.page_id(1, "2025-01-01", date).await.unwrap(), "page");
+        assert!(notes.page_id(2, "2025-01-01", date).await.is_err());
+    }
+}
@@ -0,0 +1,157 @@
+//! Discord のメッセージを日報の同期元として扱うためのアダプター。
+
+use std::collections::HashMap;
+
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Utc};
+use kgd_diary::{
+    DiaryStore, Mention, MessageSource, NotionClient, SourceAttachment, SourceMessage,
+};
+use serenity::{
+    http::Http,
+    model::{
+        channel::Message,
+        guild::Role,
+        id::{ChannelId, MessageId, UserId},
+    },
+};
+
+/// Discord のメッセージを Notion に同期するシンクロナイザー。
+pub type MessageSyncer<'a> =
+    kgd_diary::MessageSyncer<'a, DiscordSource<'a>, NotionClient, DiaryStore>;
+
+/// Discord のメッセージを同期エンジンに渡す形式に変換する。
+///
+/// メッセージに含まれるユーザーのメンションは、その場で表示名を解決しておく。
+pub fn source_message(message: &Message) -> SourceMessage {
+    SourceMessage {
+        id: message.id.get(),
+        thread_id: message.channel_id.get(),
+        author_id: message.author.id.get(),
+        posted_at: DateTime::from_timestamp(message.timestamp.unix_timestamp(), 0)
+            .unwrap_or_default(),
+        content: message.content.clone(),
+        attachments: message
+            .attachments
+            .iter()
+            .map(|attachment| SourceAttachment {
+                filename: attachment.filename.clone(),
+                url: attachment.url.clone(),
+                size: u64::from(attachment.size),
+                description: attachment.description.clone(),
+            })
+            .collect(),
+        mention_names: message
+            .mentions
+            .iter()
+            .map(|user| {
+                (
+                    Mention::User(user.id.get()),
+                    user.display_name().to_string(),
+                )
+            })
+            .collect(),
+    }
+}
+
+/// Discord を同期元とする [`MessageSource`] の実装。
+#[derive(Clone, Copy)]
+pub struct DiscordSource<'a> {
+    /// Discord HTTP クライアント（メンションの名前解決用）
+    http: &'a Http,
+}
+
+impl<'a> DiscordSource<'a> {
+    /// 新しい DiscordSource を作成する。
+    pub fn new(http: &'a Http) -> Self {
+        Self { http }
+    }
+
+    /// メッセージが投稿されたサーバーのロール一覧を取得する。
+    async fn fetch_guild_roles(&self, thread_id: u64) -> Result<Vec<Role>> {
+        let guild_id = self
+            .http
+            .get_channel(ChannelId::new(thread_id))
+            .await?
+            .guild()
+            .map(|channel| channel.guild_id)
+            .context("Message is not in a guild channel")?;
+        Ok(self.http.get_guild_roles(guild_id).await?)
+    }
+}
+
+impl MessageSource for DiscordSource<'_> {
+    fn posted_at(&self, message_id: u64) -> Option<DateTime<Utc>> {
+        let created_at = MessageId::new(message_id).created_at();
+        DateTime::from_timestamp(created_at.unix_timestamp(), 0)
+    }
+
+    async fn resolve_mentions(
+        &self,
+        message: &SourceMessage,
+        mentions: &[Mention],
+    ) -> HashMap<Mention, String> {
+        let mut names = HashMap::new();
+        let guild_roles = if mentions.iter().any(|m| matches!(m, Mention::Role(_))) {
+            self.fetch_guild_roles(message.thread_id).await
+        } else {
+            Ok(Vec::new())
+        };
+
+        for &m in mentions {
+            let name = match m {
+                Mention::User(id) => self
+                    .http
+                    .get_user(UserId::new(id))
+                    .await
+                    .map(|user| user.display_name().to_string())
+                    .map_err(anyhow::Error::from),
+                Mention::Role(id) => match &guild_roles {
+                    Ok(roles) => roles
+                        .iter()
+                        .find(|role| role.id.get() == id)
+                        .map(|role| role.name.clone())
+                        .context("Role not found in guild"),
+                    Err(e) => Err(anyhow::anyhow!("Failed to fetch guild roles: {:#}", e)),
+                },
+                Mention::Channel(id) => self
+                    .http
+                    .get_channel(ChannelId::new(id))
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|channel| {
+                        channel
+                            .guild()
+                            .map(|channel| channel.name)
+                            .context("Channel is not a guild channel")
+                    }),
+            };
+
+            match name {
+                Ok(name) => {
+                    names.insert(m, name);
+                }
+                Err(e) => {
+                    tracing::debug!(mention = ?m, error = %e, "Failed to resolve mention");
+                }
+            }
+        }
+
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_posted_at() {
+        let http = Http::new("");
+        let source = DiscordSource::new(&http);
+        assert_eq!(
+            source.posted_at(175928847299117063),
+            DateTime::from_timestamp(1462015105, 0)
+        );
+    }
+}
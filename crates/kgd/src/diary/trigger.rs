@@ -0,0 +1,115 @@
+//! 指定したチャンネルのキーワード付きメッセージを日報に転記する対象か判定する。
+
+use anyhow::{Context as _, Result};
+use regex::Regex;
+
+use crate::config::KeywordTriggerConfig;
+
+/// コンパイル済みのキーワードトリガー。
+#[derive(Debug, Clone)]
+pub struct KeywordTrigger {
+    /// 監視するチャンネル ID
+    channel_id: u64,
+    /// 転記の対象にするメッセージの判定方法
+    matcher: KeywordMatcher,
+}
+
+/// キーワードの判定方法。
+#[derive(Debug, Clone)]
+enum KeywordMatcher {
+    /// 書き出しが一致するか
+    Prefix(String),
+    /// 正規表現にマッチするか
+    Regex(Regex),
+}
+
+impl KeywordTrigger {
+    /// メッセージが転記の対象であれば、キーワードを取り除いた本文を返す。
+    ///
+    /// 監視するチャンネル以外のメッセージや、キーワードを取り除くと空になるメッセージは対象にしない。
+    pub fn extract<'a>(&self, channel_id: u64, content: &'a str) -> Option<&'a str> {
+        if channel_id != self.channel_id {
+            return None;
+        }
+
+        let body = match &self.matcher {
+            KeywordMatcher::Prefix(prefix) => content.trim_start().strip_prefix(prefix.as_str())?,
+            KeywordMatcher::Regex(re) => {
+                let matched = re.find(content)?;
+                // マッチした部分までを取り除いた残りを本文とする
+                &content[matched.end()..]
+            }
+        };
+
+        let body = body.trim();
+        (!body.is_empty()).then_some(body)
+    }
+}
+
+/// 設定からキーワードトリガーを作成する。
+///
+/// 未設定の場合は `None` を返す。無効な正規表現はエラーとして返す。
+pub fn compile_keyword_trigger(
+    config: Option<&KeywordTriggerConfig>,
+) -> Result<Option<KeywordTrigger>> {
+    let Some(config) = config else {
+        return Ok(None);
+    };
+
+    let matcher = match &config.pattern {
+        Some(pattern) => KeywordMatcher::Regex(
+            Regex::new(pattern)
+                .with_context(|| format!("Invalid keyword trigger pattern '{}'", pattern))?,
+        ),
+        None => KeywordMatcher::Prefix(config.prefix.clone()),
+    };
+
+    Ok(Some(KeywordTrigger {
+        channel_id: config.channel_id,
+        matcher,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(pattern: Option<&str>) -> KeywordTriggerConfig {
+        KeywordTriggerConfig {
+            channel_id: 1,
+            prefix: "日報:".to_string(),
+            pattern: pattern.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_extract_with_prefix() {
+        let trigger = compile_keyword_trigger(Some(&config(None)))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(trigger.extract(1, "日報: 朝会に出た"), Some("朝会に出た"));
+        assert_eq!(trigger.extract(1, "  日報:散歩"), Some("散歩"));
+        assert_eq!(trigger.extract(2, "日報: 朝会に出た"), None);
+        assert_eq!(trigger.extract(1, "今日の日報: 朝会に出た"), None);
+        assert_eq!(trigger.extract(1, "日報:   "), None);
+    }
+
+    #[test]
+    fn test_extract_with_regex() {
+        let trigger = compile_keyword_trigger(Some(&config(Some(r"^(?:日報|diary)[:：]"))))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(trigger.extract(1, "diary: lunch"), Some("lunch"));
+        assert_eq!(trigger.extract(1, "日報：ランチ"), Some("ランチ"));
+        assert_eq!(trigger.extract(1, "日報: "), None);
+        assert_eq!(trigger.extract(1, "memo: lunch"), None);
+    }
+
+    #[test]
+    fn test_compile_keyword_trigger() {
+        assert!(compile_keyword_trigger(None).unwrap().is_none());
+        assert!(compile_keyword_trigger(Some(&config(Some("[invalid")))).is_err());
+    }
+}
@@ -1,1342 +0,0 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
-
-use anyhow::{Context as _, Result};
-use chrono::{NaiveDate, Timelike};
-use serenity::{
-    all::{
-        ActionRowComponent, ButtonKind, ChannelId, ChannelType, CommandInteraction,
-        ComponentInteraction, CreateActionRow, CreateButton, CreateCommand, CreateCommandOption,
-        CreateEmbed, CreateForumPost, CreateInteractionResponse, CreateInteractionResponseMessage,
-        CreateMessage, EditInteractionResponse, EditThread, GatewayIntents, GetMessages, Http,
-        Message, MessageUpdateEvent, ReactionType,
-    },
-    async_trait,
-    builder::CreateEmbedFooter,
-    client::Context as SerenityContext,
-    model::application::CommandOptionType,
-    model::id::MessageId,
-    prelude::*,
-};
-use tokio::sync::{Mutex, mpsc};
-use tracing::{error, info, warn};
-
-use crate::{
-    config::Config,
-    diary::{
-        DiaryEntry, DiaryStore, MessageSyncer, NotionClient, compile_url_rules,
-        format_date_in_timezone, today_in_timezone,
-    },
-    status::ServerStatus,
-    version,
-    wol::send_wol_packet,
-};
-
-const DIARY_CLOSE_AND_NEW_BUTTON_ID: &str = "diary_close_and_new";
-const DIARY_THREAD_SYNC_BATCH_SIZE: u8 = 100;
-
-#[derive(Debug, Clone, Copy, Default)]
-struct DiaryThreadSyncReport {
-    checked_messages: usize,
-    synced_messages: usize,
-    already_synced_messages: usize,
-    skipped_messages: usize,
-}
-
-/// 毎時同期の実行済み時間帯を表す。
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct DiaryHourlySyncSlot {
-    /// タイムゾーン基準の日付。
-    date: NaiveDate,
-    /// タイムゾーン基準の時。
-    hour: u32,
-}
-
-impl DiaryHourlySyncSlot {
-    /// 現在時刻から毎時同期の判定に使う時間帯を作る。
-    fn current(timezone: &chrono_tz::Tz) -> Self {
-        let now = chrono::Utc::now().with_timezone(timezone);
-        Self {
-            date: now.date_naive(),
-            hour: now.hour(),
-        }
-    }
-}
-
-/// Discord イベントを処理するハンドラー。
-#[derive(Clone)]
-pub struct Handler {
-    /// アプリケーション設定
-    config: Config,
-    /// 日報ストア
-    diary_store: DiaryStore,
-    /// Notion クライアント
-    notion_client: Arc<NotionClient>,
-    /// 自動クローズ通知を送信済みの日付（タイムゾーン基準）
-    last_auto_close_notification_date: Arc<Mutex<Option<NaiveDate>>>,
-    /// 毎時同期を最後に試行した時間帯。
-    last_hourly_sync_slot: Arc<Mutex<Option<DiaryHourlySyncSlot>>>,
-}
-
-#[async_trait]
-impl EventHandler for Handler {
-    async fn ready(&self, ctx: SerenityContext, ready: serenity::model::gateway::Ready) {
-        info!(user = %ready.user.name, "Bot connected");
-
-        let mut commands = vec![
-            CreateCommand::new("wol")
-                .description("Wake up a server using Wake-on-LAN")
-                .add_option(
-                    CreateCommandOption::new(
-                        CommandOptionType::String,
-                        "server",
-                        "Server name to wake up",
-                    )
-                    .required(true),
-                ),
-            CreateCommand::new("servers").description("List all configured servers"),
-            CreateCommand::new("version").description("Show bot version information"),
-        ];
-
-        // 日報コマンドを追加
-        commands.push(
-            CreateCommand::new("diary")
-                .description("日報機能")
-                .add_option(CreateCommandOption::new(
-                    CommandOptionType::SubCommand,
-                    "new",
-                    "新しい日報を作成する",
-                ))
-                .add_option(CreateCommandOption::new(
-                    CommandOptionType::SubCommand,
-                    "close",
-                    "日報スレッドをクローズする",
-                ))
-                .add_option(CreateCommandOption::new(
-                    CommandOptionType::SubCommand,
-                    "sync",
-                    "Sync unsynced messages in this diary thread",
-                )),
-        );
-
-        match serenity::all::Command::set_global_commands(&ctx.http, commands).await {
-            Ok(commands) => {
-                let commands = commands
-                    .iter()
-                    .map(|command| {
-                        (
-                            command.name.as_str(),
-                            (command.version.get(), command.version.created_at().to_utc()),
-                        )
-                    })
-                    .collect::<HashMap<_, _>>();
-                info!(?commands, "Slash commands registered");
-            }
-            Err(e) => {
-                error!(error = %e, "Failed to register commands");
-            }
-        }
-    }
-
-    async fn interaction_create(
-        &self,
-        ctx: SerenityContext,
-        interaction: serenity::model::application::Interaction,
-    ) {
-        match interaction {
-            serenity::model::application::Interaction::Command(command) => {
-                if let Err(e) = self.handle_command(&ctx, &command).await {
-                    error!(error = ?e, command = %command.data.name, "Command error");
-
-                    let response = CreateInteractionResponseMessage::new()
-                        .content(format!("Error: {}", e))
-                        .ephemeral(true);
-
-                    if let Err(e) = command
-                        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-                        .await
-                    {
-                        error!(error = %e, "Failed to send error response");
-                    }
-                }
-            }
-            serenity::model::application::Interaction::Component(component) => {
-                if let Err(e) = self.handle_component(&ctx, &component).await {
-                    error!(error = ?e, custom_id = %component.data.custom_id, "Component interaction error");
-
-                    let response = CreateInteractionResponseMessage::new()
-                        .content(format!("Error: {}", e))
-                        .ephemeral(true);
-
-                    if let Err(e) = component
-                        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-                        .await
-                    {
-                        error!(error = %e, "Failed to send error response");
-                    }
-                }
-            }
-            _ => {}
-        }
-    }
-
-    async fn message(&self, ctx: SerenityContext, message: Message) {
-        // Bot 自身のメッセージは無視
-        if message.author.bot {
-            return;
-        }
-
-        // スレッドでない場合は無視
-        let Ok(channel) = message.channel(&ctx).await else {
-            return;
-        };
-        let Some(guild_channel) = channel.guild() else {
-            return;
-        };
-        if guild_channel.kind != ChannelType::PublicThread {
-            return;
-        }
-
-        // 該当スレッドの日報エントリを取得
-        let Ok(Some(entry)) = self
-            .diary_store
-            .get_by_thread(message.channel_id.get())
-            .await
-        else {
-            return;
-        };
-        let page_id = entry.page_id.clone();
-
-        // Notion に同期
-        let syncer = match MessageSyncer::new(
-            self.notion_client.as_ref(),
-            &self.diary_store,
-            &self.config.diary,
-        ) {
-            Ok(s) => s,
-            Err(e) => {
-                error!(error = %e, "Failed to create message syncer");
-                return;
-            }
-        };
-        match self
-            .sync_message_with_reaction(&ctx.http, &syncer, &page_id, &message)
-            .await
-        {
-            Ok((true, block_count)) => {
-                info!(
-                    thread_id = message.channel_id.get(),
-                    message_id = message.id.get(),
-                    blocks = block_count,
-                    "Message synced to Notion"
-                );
-                // 成功したらリアクションを付ける
-            }
-            Ok(_) => {
-                // スキップ (空メッセージなど)
-            }
-            Err(e) => {
-                error!(error = %e, "Failed to sync message to Notion");
-            }
-        }
-    }
-
-    async fn message_update(
-        &self,
-        ctx: SerenityContext,
-        _old_if_available: Option<Message>,
-        _new: Option<Message>,
-        event: MessageUpdateEvent,
-    ) {
-        // Bot 自身のメッセージは無視
-        if event.author.as_ref().is_some_and(|a| a.bot) {
-            return;
-        }
-
-        // スレッドでない場合は無視
-        let Ok(channel) = event.channel_id.to_channel(&ctx).await else {
-            return;
-        };
-        let Some(guild_channel) = channel.guild() else {
-            return;
-        };
-        if guild_channel.kind != ChannelType::PublicThread {
-            return;
-        }
-
-        // 該当スレッドの日報エントリを取得
-        let Ok(Some(_entry)) = self.diary_store.get_by_thread(event.channel_id.get()).await else {
-            return;
-        };
-
-        // コンテンツがない場合は無視
-        let Some(content) = event.content else {
-            return;
-        };
-
-        // メッセージを取得して更新
-        let Ok(message) = event.channel_id.message(&ctx.http, event.id).await else {
-            return;
-        };
-
-        // メッセージの内容で上書きして更新
-        let mut message = message;
-        message.content = content;
-
-        let syncer = match MessageSyncer::new(
-            self.notion_client.as_ref(),
-            &self.diary_store,
-            &self.config.diary,
-        ) {
-            Ok(s) => s,
-            Err(e) => {
-                error!(error = %e, "Failed to create message syncer");
-                return;
-            }
-        };
-        match syncer.update_message(&message).await {
-            Ok(true) => {
-                info!(
-                    thread_id = event.channel_id.get(),
-                    message_id = event.id.get(),
-                    "Message updated in Notion"
-                );
-            }
-            Ok(false) => {
-                // 対応するブロックがなかった（新規メッセージの可能性）
-            }
-            Err(e) => {
-                error!(error = %e, "Failed to update message in Notion");
-            }
-        }
-    }
-
-    async fn message_delete(
-        &self,
-        ctx: SerenityContext,
-        channel_id: ChannelId,
-        deleted_message_id: MessageId,
-        _guild_id: Option<serenity::model::id::GuildId>,
-    ) {
-        // スレッドでない場合は無視
-        let Ok(channel) = channel_id.to_channel(&ctx).await else {
-            return;
-        };
-        let Some(guild_channel) = channel.guild() else {
-            return;
-        };
-        if guild_channel.kind != ChannelType::PublicThread {
-            return;
-        }
-
-        // 該当スレッドの日報エントリを取得
-        let Ok(Some(_entry)) = self.diary_store.get_by_thread(channel_id.get()).await else {
-            return;
-        };
-
-        // Notion から対応するブロックを削除
-        let syncer = match MessageSyncer::new(
-            self.notion_client.as_ref(),
-            &self.diary_store,
-            &self.config.diary,
-        ) {
-            Ok(s) => s,
-            Err(e) => {
-                error!(error = %e, "Failed to create message syncer");
-                return;
-            }
-        };
-        match syncer.delete_message(deleted_message_id.get()).await {
-            Ok(true) => {
-                info!(
-                    thread_id = channel_id.get(),
-                    message_id = deleted_message_id.get(),
-                    "Message deleted from Notion"
-                );
-            }
-            Ok(false) => {
-                // 対応するブロックがなかった
-            }
-            Err(e) => {
-                error!(error = %e, "Failed to delete message from Notion");
-            }
-        }
-    }
-}
-
-impl Handler {
-    async fn handle_command(
-        &self,
-        ctx: &SerenityContext,
-        command: &CommandInteraction,
-    ) -> Result<()> {
-        let user_id = command.user.id.get();
-        if !self.config.discord.admins.is_empty() && !self.config.discord.admins.contains(&user_id)
-        {
-            warn!(user_id, "Unauthorized access attempt");
-            let response = CreateInteractionResponseMessage::new()
-                .content("You are not authorized to use this bot.")
-                .ephemeral(true);
-            command
-                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-                .await?;
-            return Ok(());
-        }
-
-        match command.data.name.as_str() {
-            "wol" => self.handle_wol(ctx, command).await,
-            "servers" => self.handle_servers(ctx, command).await,
-            "version" => self.handle_version(ctx, command).await,
-            "diary" => self.handle_diary(ctx, command).await,
-            _ => Ok(()),
-        }
-    }
-
-    async fn handle_wol(&self, ctx: &SerenityContext, command: &CommandInteraction) -> Result<()> {
-        let server_name = command
-            .data
-            .options
-            .first()
-            .and_then(|opt| opt.value.as_str())
-            .context("Server name not provided")?;
-
-        let server = self
-            .config
-            .find_server(server_name)
-            .context(format!("Server '{}' not found", server_name))?;
-
-        send_wol_packet(server.mac_address, None).context("Failed to send WOL packet")?;
-        info!(server = %server.name, mac = %server.mac_address, "WOL packet sent");
-
-        let response = CreateInteractionResponseMessage::new()
-            .content(format!(
-                "Sent WOL packet to {} ({})",
-                server.name, server.mac_address
-            ))
-            .ephemeral(false);
-
-        command
-            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-            .await?;
-
-        Ok(())
-    }
-
-    async fn handle_servers(
-        &self,
-        ctx: &SerenityContext,
-        command: &CommandInteraction,
-    ) -> Result<()> {
-        let mut embed = CreateEmbed::new()
-            .title("Configured Servers")
-            .color(0x00ff00);
-
-        for server in &self.config.servers {
-            let field_value = format!(
-                "**IP:** {}\n**MAC:** {}\n**Description:** {}",
-                server.ip_address, server.mac_address, server.description
-            );
-            embed = embed.field(&server.name, field_value, false);
-        }
-
-        embed = embed.footer(CreateEmbedFooter::new(format!(
-            "Total: {} server(s)",
-            self.config.servers.len()
-        )));
-
-        let response = CreateInteractionResponseMessage::new()
-            .embed(embed)
-            .ephemeral(false);
-
-        command
-            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-            .await?;
-
-        Ok(())
-    }
-
-    async fn handle_version(
-        &self,
-        ctx: &SerenityContext,
-        command: &CommandInteraction,
-    ) -> Result<()> {
-        let embed = CreateEmbed::new()
-            .title("kgd")
-            .color(0x5865f2)
-            .field("Version", version::VERSION, true)
-            .field("Git SHA", version::GIT_SHA, true)
-            .field("Target", version::TARGET_TRIPLE, true)
-            .field("Built", version::BUILD_DATE, false);
-
-        let response = CreateInteractionResponseMessage::new()
-            .embed(embed)
-            .ephemeral(false);
-
-        command
-            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-            .await?;
-
-        Ok(())
-    }
-
-    async fn handle_diary(
-        &self,
-        ctx: &SerenityContext,
-        command: &CommandInteraction,
-    ) -> Result<()> {
-        let subcommand = command
-            .data
-            .options
-            .first()
-            .context("Subcommand not provided")?;
-
-        match subcommand.name.as_str() {
-            "new" => self.handle_diary_new(ctx, command).await,
-            "close" => self.handle_diary_close(ctx, command).await,
-            "sync" => self.handle_diary_sync(ctx, command).await,
-            _ => Ok(()),
-        }
-    }
-
-    async fn handle_diary_new(
-        &self,
-        ctx: &SerenityContext,
-        command: &CommandInteraction,
-    ) -> Result<()> {
-        let diary_config = &self.config.diary;
-
-        // 今日の日付を設定されたタイムゾーンで取得
-        let date = today_in_timezone(&diary_config.timezone);
-
-        // 既に今日の日報が存在するかチェック
-        if let Some(entry) = self.diary_store.get_by_date(date).await? {
-            let thread_id = ChannelId::new(entry.thread_id);
-
-            let reopened = match thread_id.join_thread(&ctx.http).await {
-                Ok(()) => {
-                    let edit = EditThread::new().archived(false).locked(false);
-                    match thread_id.edit_thread(&ctx.http, edit).await {
-                        Ok(_) => true,
-                        Err(error) => {
-                            warn!(
-                                error = %error,
-                                thread_id = entry.thread_id,
-                                "Failed to reopen existing diary thread; falling back to link only"
-                            );
-                            false
-                        }
-                    }
-                }
-                Err(error) => {
-                    warn!(
-                        error = %error,
-                        thread_id = entry.thread_id,
-                        "Failed to join existing diary thread; falling back to link only"
-                    );
-                    false
-                }
-            };
-
-            if let Err(error) = self.ensure_close_and_new_button(&ctx.http, thread_id).await {
-                warn!(
-                    error = %error,
-                    thread_id = entry.thread_id,
-                    "Failed to ensure close button on existing diary thread"
-                );
-            }
-
-            info!(
-                date = %date,
-                thread_id = entry.thread_id,
-                reopened,
-                "Diary thread already exists for today"
-            );
-
-            let response = CreateInteractionResponseMessage::new()
-                .content(if reopened {
-                    format!("今日の日報を再開しました: <#{}>", entry.thread_id)
-                } else {
-                    format!(
-                        "今日の日報は既にありますが、再開はできません: <#{}>",
-                        entry.thread_id
-                    )
-                })
-                .ephemeral(false);
-            command
-                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-                .await?;
-            return Ok(());
-        }
-
-        // 日付を文字列に変換 (YYYY-MM-DD 形式、設定されたタイムゾーンで表示)
-        let date_str = format_date_in_timezone(date, &diary_config.timezone);
-
-        // 既存の Notion ページを検索、なければ新規作成
-        let (page_id, page_url, reused) = if let Some((page_id, page_url)) = self
-            .notion_client
-            .find_diary_page_by_title(&date_str)
-            .await
-            .context("Notion ページの検索に失敗しました")?
-        {
-            info!(date = %date, page_id = %page_id, "Found existing Notion page");
-            (page_id, page_url, true)
-        } else {
-            let (page_id, page_url) = self
-                .notion_client
-                .create_diary_page(&date_str)
-                .await
-                .context("Notion ページの作成に失敗しました")?;
-            (page_id, page_url, false)
-        };
-
-        // Discord フォーラムにスレッドを作成
-        let forum_channel = ChannelId::new(diary_config.forum_channel_id);
-        let initial_message = create_diary_thread_initial_message(&page_url);
-        let forum_post = CreateForumPost::new(date_str, initial_message);
-
-        let thread = forum_channel
-            .create_forum_post(&ctx.http, forum_post)
-            .await
-            .context("フォーラムスレッドの作成に失敗しました")?;
-
-        // 紐付け情報を保存
-        self.ensure_close_and_new_button(&ctx.http, thread.id)
-            .await?;
-
-        let entry = DiaryEntry {
-            thread_id: thread.id.get(),
-            page_id,
-            page_url: page_url.clone(),
-            date,
-            created_at: chrono::Utc::now(),
-        };
-
-        self.diary_store.insert(&entry).await?;
-
-        info!(date = %date, thread_id = thread.id.get(), reused, "Diary created");
-
-        // 成功レスポンス
-        let message = if reused {
-            format!(
-                "既存の Notion ページを使用して日報を作成しました\nスレッド: <#{}>\nNotion: {}",
-                thread.id, page_url
-            )
-        } else {
-            format!(
-                "日報を作成しました\nスレッド: <#{}>\nNotion: {}",
-                thread.id, page_url
-            )
-        };
-        let response = CreateInteractionResponseMessage::new()
-            .content(message)
-            .ephemeral(false);
-
-        command
-            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-            .await?;
-
-        Ok(())
-    }
-
-    async fn handle_diary_close(
-        &self,
-        ctx: &SerenityContext,
-        command: &CommandInteraction,
-    ) -> Result<()> {
-        // スレッド内からの呼び出しか確認
-        let channel = command.channel_id.to_channel(&ctx.http).await?;
-        let Some(guild_channel) = channel.guild() else {
-            let response = CreateInteractionResponseMessage::new()
-                .content("このコマンドはサーバー内でのみ使用できます")
-                .ephemeral(true);
-            command
-                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-                .await?;
-            return Ok(());
-        };
-
-        if guild_channel.kind != ChannelType::PublicThread {
-            let response = CreateInteractionResponseMessage::new()
-                .content("このコマンドは日報スレッド内から実行してください")
-                .ephemeral(true);
-            command
-                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-                .await?;
-            return Ok(());
-        }
-
-        // 該当スレッドが日報スレッドか確認
-        if self
-            .diary_store
-            .get_by_thread(command.channel_id.get())
-            .await?
-            .is_none()
-        {
-            let response = CreateInteractionResponseMessage::new()
-                .content("このスレッドは日報スレッドではありません")
-                .ephemeral(true);
-            command
-                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-                .await?;
-            return Ok(());
-        }
-
-        // 先にレスポンスを返す（アーカイブ後はレスポンスを返せないため）
-        let response = CreateInteractionResponseMessage::new()
-            .content("日報スレッドをクローズしています...")
-            .ephemeral(false);
-
-        command
-            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-            .await?;
-
-        // スレッドをアーカイブ & ロック (クローズ)
-        // locked=true にすることで、ユーザーが書き込んでも自動的に再開されない
-        let edit = EditThread::new().archived(true).locked(true);
-        command
-            .channel_id
-            .edit_thread(&ctx.http, edit)
-            .await
-            .context("スレッドのクローズに失敗しました")?;
-
-        info!(thread_id = command.channel_id.get(), "Diary thread closed");
-
-        Ok(())
-    }
-
-    /// コンポーネント操作を処理する。
-    /// 現在の日報スレッドを対象に、未同期メッセージの再同期を手動実行する。
-    async fn handle_diary_sync(
-        &self,
-        ctx: &SerenityContext,
-        command: &CommandInteraction,
-    ) -> Result<()> {
-        let channel = command.channel_id.to_channel(&ctx.http).await?;
-        let Some(guild_channel) = channel.guild() else {
-            let response = CreateInteractionResponseMessage::new()
-                .content("このコマンドはサーバー内の日報スレッドでのみ使用できます")
-                .ephemeral(true);
-            command
-                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-                .await?;
-            return Ok(());
-        };
-
-        if guild_channel.kind != ChannelType::PublicThread {
-            let response = CreateInteractionResponseMessage::new()
-                .content("このコマンドは日報スレッド内で実行してください")
-                .ephemeral(true);
-            command
-                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-                .await?;
-            return Ok(());
-        }
-
-        if self
-            .diary_store
-            .get_by_thread(command.channel_id.get())
-            .await?
-            .is_none()
-        {
-            let response = CreateInteractionResponseMessage::new()
-                .content("このスレッドは日報スレッドとして登録されていません")
-                .ephemeral(true);
-            command
-                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-                .await?;
-            return Ok(());
-        }
-
-        command.defer_ephemeral(&ctx.http).await?;
-
-        let report = self
-            .sync_missing_messages_in_thread(&ctx.http, command.channel_id)
-            .await?;
-
-        let result_message = format!(
-            "日報スレッドの未同期メッセージを確認しました。\n確認件数: {}件\n新規同期: {}件\n既に同期済み: {}件\nスキップ: {}件",
-            report.checked_messages,
-            report.synced_messages,
-            report.already_synced_messages,
-            report.skipped_messages
-        );
-
-        command
-            .edit_response(
-                &ctx.http,
-                EditInteractionResponse::new().content(result_message),
-            )
-            .await?;
-
-        Ok(())
-    }
-
-    async fn handle_component(
-        &self,
-        ctx: &SerenityContext,
-        component: &ComponentInteraction,
-    ) -> Result<()> {
-        if component.data.custom_id == "diary_close_and_new" {
-            self.handle_diary_close_and_new(ctx, component).await
-        } else {
-            Ok(())
-        }
-    }
-
-    /// 日報スレッドをクローズして新しいスレッドを作成する。
-    async fn handle_diary_close_and_new(
-        &self,
-        ctx: &SerenityContext,
-        component: &ComponentInteraction,
-    ) -> Result<()> {
-        let channel_id = component.channel_id;
-        let Some(_entry) = self.diary_store.get_by_thread(channel_id.get()).await? else {
-            anyhow::bail!("このスレッドは日報スレッドではありません");
-        };
-
-        let timezone = &self.config.diary.timezone;
-        let today = today_in_timezone(timezone);
-        if let Some(today_entry) = self.diary_store.get_by_date(today).await? {
-            let response = if today_entry.thread_id == channel_id.get() {
-                CreateInteractionResponseMessage::new()
-                    .content("このスレッドが今日の最新の日報です")
-                    .ephemeral(false)
-            } else {
-                CreateInteractionResponseMessage::new()
-                    .content(format!(
-                        "今日の最新の日報はこちらです: <#{}>",
-                        today_entry.thread_id
-                    ))
-                    .ephemeral(false)
-            };
-
-            component
-                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-                .await?;
-            return Ok(());
-        }
-
-        let response = CreateInteractionResponseMessage::new()
-            .content("日報スレッドをクローズして新しいスレッドを作成しています...")
-            .ephemeral(false);
-
-        component
-            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-            .await?;
-
-        let timezone = &self.config.diary.timezone;
-        let today = today_in_timezone(timezone);
-        let date_str = format_date_in_timezone(today, timezone);
-
-        let notion_client = self.notion_client.as_ref();
-        let (page_id, page_url) = match notion_client.find_diary_page_by_title(&date_str).await? {
-            Some((page_id, page_url)) => {
-                info!(page_id = %page_id, "Found existing Notion page");
-                (page_id, page_url)
-            }
-            None => {
-                info!(title = %date_str, "Creating new Notion page");
-                notion_client.create_diary_page(&date_str).await?
-            }
-        };
-
-        let forum_channel_id = ChannelId::new(self.config.diary.forum_channel_id);
-        let initial_message = create_diary_thread_initial_message(&page_url);
-        let forum_post = CreateForumPost::new(date_str, initial_message);
-        let thread = forum_channel_id
-            .create_forum_post(&ctx.http, forum_post)
-            .await
-            .context("Failed to create forum post")?;
-
-        self.ensure_close_and_new_button(&ctx.http, thread.id)
-            .await?;
-
-        info!(
-            thread_id = thread.id.get(),
-            page_id = %page_id,
-            "Created new diary thread"
-        );
-
-        let new_entry = DiaryEntry {
-            thread_id: thread.id.get(),
-            page_id: page_id.clone(),
-            page_url: page_url.clone(),
-            date: today,
-            created_at: chrono::Utc::now(),
-        };
-        self.diary_store.insert(&new_entry).await?;
-
-        let mention_message = CreateMessage::new().content(format!(
-            "新しい日報スレッドを作成しました: <#{}>",
-            thread.id.get()
-        ));
-        channel_id
-            .send_message(&ctx.http, mention_message)
-            .await
-            .context("Failed to send mention message")?;
-
-        let edit = EditThread::new().archived(true).locked(true);
-        channel_id
-            .edit_thread(&ctx.http, edit)
-            .await
-            .context("Failed to close thread after sending mention message")?;
-
-        info!(
-            old_thread_id = channel_id.get(),
-            new_thread_id = thread.id.get(),
-            "Diary thread closed by button"
-        );
-
-        Ok(())
-    }
-
-    /// 自動クローズのチェックを行い、必要ならボタン付きメッセージを送信する。
-    pub async fn check_auto_close(&self, http: &Http) -> Result<()> {
-        if !self.config.diary.auto_close_enabled {
-            return Ok(());
-        }
-
-        let timezone = &self.config.diary.timezone;
-        let now = chrono::Utc::now().with_timezone(timezone);
-        let today = today_in_timezone(timezone);
-        let today_local = now.date_naive();
-
-        // 指定された時刻以降かチェック
-        if now.hour() < self.config.diary.auto_close_hour {
-            return Ok(());
-        }
-
-        {
-            let last_auto_close_notification_date =
-                self.last_auto_close_notification_date.lock().await;
-            if last_auto_close_notification_date.is_some_and(|date| date == today_local) {
-                return Ok(());
-            }
-        }
-
-        // 最新のエントリのみを取得
-        let Some(entry) = self.diary_store.get_latest_entry().await? else {
-            return Ok(());
-        };
-
-        // 日付が今日より前かチェック
-        if entry.date >= today {
-            return Ok(());
-        }
-
-        // スレッドがまだアクティブかチェック
-        let thread_id = ChannelId::new(entry.thread_id);
-        let Ok(channel) = thread_id.to_channel(http).await else {
-            return Ok(());
-        };
-        let Some(thread) = channel.guild() else {
-            return Ok(());
-        };
-
-        // アーカイブされている、またはロックされている場合はスキップ
-        if thread
-            .thread_metadata
-            .is_some_and(|m| m.archived || m.locked)
-        {
-            return Ok(());
-        }
-
-        // ボタン付きメッセージを送信
-        self.send_auto_close_button(http, thread_id).await?;
-        *self.last_auto_close_notification_date.lock().await = Some(today_local);
-
-        info!(thread_id = entry.thread_id, "Sent auto-close button");
-
-        Ok(())
-    }
-
-    /// 毎時の境目で直近 3 日分の日報スレッドを再同期する。
-    ///
-    /// 起動直後は現在の時間帯だけ記録し、次の時間帯に切り替わるまでは同期しない。
-    pub async fn check_hourly_sync(&self, http: &Http) -> Result<()> {
-        let current_slot = DiaryHourlySyncSlot::current(&self.config.diary.timezone);
-
-        {
-            let mut last_hourly_sync_slot = self.last_hourly_sync_slot.lock().await;
-            match *last_hourly_sync_slot {
-                Some(slot) if slot == current_slot => return Ok(()),
-                None => {
-                    // 起動直後は現在の時間帯だけ記録し、次の時間帯から同期を始める。
-                    *last_hourly_sync_slot = Some(current_slot);
-                    return Ok(());
-                }
-                Some(_) => {}
-            }
-        }
-
-        self.sync_recent_diary_threads(http).await?;
-        *self.last_hourly_sync_slot.lock().await = Some(current_slot);
-
-        info!(
-            date = %current_slot.date,
-            hour = current_slot.hour,
-            "Hourly diary sync finished"
-        );
-
-        Ok(())
-    }
-
-    /// 自動クローズのボタン付きメッセージを送信する。
-    async fn send_auto_close_button(&self, http: &Http, thread_id: ChannelId) -> Result<()> {
-        let message = CreateMessage::new()
-            .content("日付が変わりました。このスレッドをクローズして新しい日報を作成しますか？")
-            .components(vec![create_close_and_new_action_row()]);
-
-        thread_id
-            .send_message(http, message)
-            .await
-            .context("Failed to send auto-close button message")?;
-
-        Ok(())
-    }
-
-    /// 直近 3 日分の日報スレッドを順番に再同期する。
-    async fn sync_recent_diary_threads(&self, http: &Http) -> Result<()> {
-        let today = today_in_timezone(&self.config.diary.timezone);
-        // 当日を含めた 3 日分だけを定期同期の対象にする。
-        let start_date = today - chrono::Duration::days(2);
-        let entries = self
-            .diary_store
-            .get_entries_in_date_range(start_date, today)
-            .await?;
-
-        let mut checked_messages = 0usize;
-        let mut synced_messages = 0usize;
-        let mut already_synced_messages = 0usize;
-        let mut skipped_messages = 0usize;
-
-        for entry in entries {
-            let thread_id = ChannelId::new(entry.thread_id);
-            match self.sync_missing_messages_in_thread(http, thread_id).await {
-                Ok(report) => {
-                    checked_messages += report.checked_messages;
-                    synced_messages += report.synced_messages;
-                    already_synced_messages += report.already_synced_messages;
-                    skipped_messages += report.skipped_messages;
-                }
-                Err(error) => {
-                    error!(
-                        error = %error,
-                        thread_id = entry.thread_id,
-                        "Failed to sync recent diary thread"
-                    );
-                }
-            }
-        }
-
-        info!(
-            start_date = %start_date,
-            end_date = %today,
-            checked_messages,
-            synced_messages,
-            already_synced_messages,
-            skipped_messages,
-            "Recent diary sync finished"
-        );
-
-        Ok(())
-    }
-
-    /// 指定した日報スレッドを全走査し、未同期メッセージだけを Notion に再同期する。
-    ///
-    /// 同期対象の期間絞り込みはここで持たず、呼び出し側で決める。
-    async fn sync_missing_messages_in_thread(
-        &self,
-        http: &Http,
-        thread_id: ChannelId,
-    ) -> Result<DiaryThreadSyncReport> {
-        let Some(entry) = self.diary_store.get_by_thread(thread_id.get()).await? else {
-            anyhow::bail!("Diary entry not found for thread {}", thread_id.get());
-        };
-
-        let channel = thread_id
-            .to_channel(http)
-            .await
-            .with_context(|| format!("Failed to fetch thread {}", thread_id.get()))?;
-        let Some(thread) = channel.guild() else {
-            anyhow::bail!("Channel {} is not a guild thread", thread_id.get());
-        };
-        if thread.kind != ChannelType::PublicThread {
-            anyhow::bail!("Channel {} is not a public thread", thread_id.get());
-        }
-
-        let syncer = MessageSyncer::new(
-            self.notion_client.as_ref(),
-            &self.diary_store,
-            &self.config.diary,
-        )?;
-        let mut before = None;
-        let mut pending_messages = Vec::new();
-        let mut report = DiaryThreadSyncReport::default();
-
-        loop {
-            let mut request = GetMessages::new().limit(DIARY_THREAD_SYNC_BATCH_SIZE);
-            if let Some(before_message_id) = before {
-                request = request.before(before_message_id);
-            }
-
-            let messages = thread_id.messages(http, request).await.with_context(|| {
-                format!("Failed to fetch messages for thread {}", thread_id.get())
-            })?;
-            if messages.is_empty() {
-                break;
-            }
-
-            before = messages.last().map(|message| message.id);
-
-            for message in messages {
-                if message.author.bot {
-                    continue;
-                }
-
-                pending_messages.push(message);
-            }
-        }
-
-        pending_messages.reverse();
-        report.checked_messages = pending_messages.len();
-
-        for message in pending_messages {
-            if self
-                .diary_store
-                .has_blocks_by_message(message.id.get())
-                .await?
-            {
-                report.already_synced_messages += 1;
-                continue;
-            }
-
-            let (synced, _) = self
-                .sync_message_with_reaction(http, &syncer, &entry.page_id, &message)
-                .await
-                .with_context(|| {
-                    format!(
-                        "Failed to sync missing message {} in thread {}",
-                        message.id.get(),
-                        thread_id.get()
-                    )
-                })?;
-
-            if synced {
-                report.synced_messages += 1;
-            } else {
-                report.skipped_messages += 1;
-            }
-        }
-
-        info!(
-            thread_id = thread_id.get(),
-            checked_messages = report.checked_messages,
-            synced_messages = report.synced_messages,
-            already_synced_messages = report.already_synced_messages,
-            skipped_messages = report.skipped_messages,
-            "Checked diary thread for missing message sync"
-        );
-
-        Ok(report)
-    }
-
-    /// 1 件の日報メッセージを Notion に同期し、成功時は同期済みリアクションを付与する。
-    async fn sync_message_with_reaction(
-        &self,
-        http: &Http,
-        syncer: &MessageSyncer<'_>,
-        page_id: &str,
-        message: &Message,
-    ) -> Result<(bool, usize)> {
-        let result = syncer.sync_message(page_id, message).await?;
-
-        if !result.synced {
-            return Ok((false, result.block_count));
-        }
-
-        self.add_sync_reaction(http, message).await;
-
-        Ok((true, result.block_count))
-    }
-
-    /// 同期済みのメッセージにリアクションを付与する。
-    async fn add_sync_reaction(&self, http: &Http, message: &Message) {
-        let reaction = ReactionType::Unicode(self.config.diary.sync_reaction.clone());
-        if let Err(error) = message.react(http, reaction).await {
-            error!(error = %error, "Failed to add sync reaction");
-        }
-    }
-
-    async fn ensure_close_and_new_button(&self, http: &Http, thread_id: ChannelId) -> Result<()> {
-        let messages = thread_id
-            .messages(http, GetMessages::new().limit(10))
-            .await
-            .context("Failed to fetch thread messages to inspect close button")?;
-
-        if messages.iter().any(message_has_close_and_new_button) {
-            return Ok(());
-        }
-
-        self.send_auto_close_button(http, thread_id).await?;
-
-        info!(
-            thread_id = thread_id.get(),
-            "Sent fallback close-and-new button message"
-        );
-
-        Ok(())
-    }
-}
-
-/// クローズ&新規作成ボタンの ActionRow を作成する。
-fn create_close_and_new_action_row() -> CreateActionRow {
-    let button = CreateButton::new(DIARY_CLOSE_AND_NEW_BUTTON_ID)
-        .label("クローズして新しい日報を作成")
-        .style(serenity::all::ButtonStyle::Primary);
-    CreateActionRow::Buttons(vec![button])
-}
-
-/// 日報スレッドの最初のメッセージを構築する。
-fn create_diary_thread_initial_message(page_url: &str) -> CreateMessage {
-    CreateMessage::new()
-        .content(format!("Notion: {}", page_url))
-        .components(vec![create_close_and_new_action_row()])
-}
-
-fn message_has_close_and_new_button(message: &Message) -> bool {
-    message.components.iter().any(|row| {
-        row.components.iter().any(|component| {
-            matches!(
-                component,
-                ActionRowComponent::Button(button)
-                    if matches!(
-                        &button.data,
-                        ButtonKind::NonLink { custom_id, .. }
-                            if custom_id == DIARY_CLOSE_AND_NEW_BUTTON_ID
-                    )
-            )
-        })
-    })
-}
-
-/// サーバーステータスをDiscordチャンネルに通知するための構造体。
-pub struct StatusNotifier {
-    /// Discord API クライアント
-    http: Arc<Http>,
-    /// 通知先チャンネルID
-    channel_id: ChannelId,
-    /// ステータスチェック間隔（フッター表示用）
-    interval: Duration,
-}
-
-impl StatusNotifier {
-    /// サーバーステータスをDiscordチャンネルに埋め込みメッセージとして送信する。
-    pub async fn send(&self, statuses: &[ServerStatus]) {
-        let mut embed = CreateEmbed::new().title("Server Status").color(0x00ff00);
-
-        for status in statuses {
-            let status_text = if status.online { "Online" } else { "Offline" };
-            embed = embed.field(&status.name, status_text, true);
-        }
-
-        embed = embed.footer(CreateEmbedFooter::new(format!(
-            "Updated every {}",
-            humantime::format_duration(self.interval)
-        )));
-
-        let message = CreateMessage::new().embed(embed);
-        if let Err(e) = self.channel_id.send_message(&self.http, message).await {
-            error!(error = %e, "Failed to send status message");
-        }
-    }
-}
-
-/// Discord Bot を起動し、イベントループを開始する。
-pub async fn run(config: Config, status_rx: mpsc::Receiver<Vec<ServerStatus>>) -> Result<()> {
-    let mut intents = GatewayIntents::GUILDS;
-
-    // メッセージイベントを購読
-    intents |= GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT;
-
-    let diary_config = &config.diary;
-
-    // 起動時に URL ルールのバリデーションを行う
-    compile_url_rules(&diary_config.url_rules, &diary_config.default_convert_to)
-        .context("Invalid URL rules in configuration")?;
-
-    let diary_store = DiaryStore::connect(&diary_config.database_url)
-        .await
-        .context("Failed to connect to database")?;
-    let notion_client = Arc::new(
-        NotionClient::new(
-            &diary_config.notion_token,
-            &diary_config.notion_database_id,
-            &diary_config.notion_title_property,
-            diary_config.notion_tags.clone(),
-        )
-        .context("Failed to create Notion client")?,
-    );
-
-    let handler = Handler {
-        config: config.clone(),
-        diary_store,
-        notion_client,
-        last_auto_close_notification_date: Arc::new(Mutex::new(None)),
-        last_hourly_sync_slot: Arc::new(Mutex::new(None)),
-    };
-
-    let mut client = Client::builder(&config.discord.token, intents)
-        .event_handler(handler.clone())
-        .await
-        .context("Failed to create Discord client")?;
-
-    let http = client.http.clone();
-    let channel_id = ChannelId::new(config.discord.status_channel_id);
-    let interval = config.status.interval;
-
-    let notifier = StatusNotifier {
-        http,
-        channel_id,
-        interval,
-    };
-
-    tokio::spawn(run_status_receiver(notifier, status_rx));
-
-    // 日報向けの定期タスクを起動
-    let diary_handler = handler.clone();
-    let diary_http = client.http.clone();
-    let diary_interval = Duration::from_secs(60);
-    tokio::spawn(async move {
-        run_diary_periodic_tasks(diary_handler, diary_http, diary_interval).await;
-    });
-    info!(interval = ?diary_interval, "Diary periodic tasks started");
-
-    info!("Starting bot");
-    client.start().await.context("Discord client error")?;
-
-    Ok(())
-}
-
-/// ステータスモニターからの通知を受信し、Discordに転送するループを実行する。
-async fn run_status_receiver(notifier: StatusNotifier, mut rx: mpsc::Receiver<Vec<ServerStatus>>) {
-    while let Some(statuses) = rx.recv().await {
-        notifier.send(&statuses).await;
-    }
-}
-
-/// 日報向けの定期メンテナンスタスクを実行する。
-async fn run_diary_periodic_tasks(handler: Handler, http: Arc<Http>, interval: Duration) {
-    let mut interval_timer = tokio::time::interval(interval);
-
-    loop {
-        interval_timer.tick().await;
-
-        if let Err(error) = handler.check_auto_close(&http).await {
-            error!(error = %error, "Auto-close check failed");
-        }
-
-        if let Err(error) = handler.check_hourly_sync(&http).await {
-            error!(error = %error, "Hourly diary sync check failed");
-        }
-    }
-}
@@ -0,0 +1,3650 @@
+//! Discord のフォーラムと Notion を連携する日報機能のコマンドとイベント処理。
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use chrono::{NaiveDate, Timelike};
+use serenity::{
+    all::{
+        ActionRowComponent, ApplicationFlags, ButtonKind, ChannelId, ChannelType,
+        CommandDataOptionValue, CommandInteraction, ComponentInteraction, CreateActionRow,
+        CreateButton, CreateCommand, CreateCommandOption, CreateEmbed, CreateForumPost,
+        CreateInteractionResponse, CreateInteractionResponseFollowup,
+        CreateInteractionResponseMessage, CreateMessage, EditInteractionResponse, EditMessage,
+        EditThread, GatewayIntents, GetMessages, GuildChannel, Http, InstallationContext,
+        InteractionContext, Mentionable as _, Message, MessageType, MessageUpdateEvent,
+        Permissions, Reaction, ReactionType, Ready, UserId,
+    },
+    builder::CreateEmbedAuthor,
+    client::Context as SerenityContext,
+    http::HttpError,
+    model::application::CommandOptionType,
+    model::id::MessageId,
+};
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::{
+    config::{Config, Feature, JobAction, ReactionFallback, SyncFailureNotification, SyncMode},
+    diary::{
+        DiaryEntry, DiaryStorage as _, DiaryStore, DiscordSource, EventOutcome, KeywordTrigger,
+        MessageEvent, MessageSyncer, NotionClient, PageSummary, PageTemplate, PrivateNotes,
+        ReconcileResult, ReportOutcome, ReportPeriod, RetryError, SyncHistoryRecord,
+        SyncHistoryStatus, SyncResult, TempWorkspace, compile_image_rules, compile_keyword_trigger,
+        compile_page_template, compile_private_notes, compile_redaction_rules, compile_url_rules,
+        create_templated_page, due_report_periods, format_date_in_timezone, is_summary_block,
+        parse_page_id, publish_report, render_title, source_message, start_of_day_in_timezone,
+        summary_anchor, summary_placeholder_block, time_totals, today_in_timezone,
+        validate_page_title_format,
+    },
+    email::EmailNotifier,
+    matrix::MatrixDiarySync,
+    store::BotStore,
+};
+
+use super::{
+    Handler, format_discord_timestamp, stats::stats_command, subcommand_string_option,
+    truncate_chars,
+};
+
+const DIARY_CLOSE_AND_NEW_BUTTON_ID: &str = "diary_close_and_new";
+/// 再同期ボタンの custom_id の接頭辞（後ろに `{thread_id}:{message_id}` が続く）
+const DIARY_RETRY_SYNC_BUTTON_PREFIX: &str = "diary_retry_sync:";
+const DIARY_THREAD_SYNC_BATCH_SIZE: u8 = 100;
+/// 同期に失敗したときに投稿者へ送る通知文
+const SYNC_FAILURE_NOTICE: &str =
+    "⚠️ このメッセージを日報ページに同期できませんでした。時間をおいて「再同期」を押してください。";
+/// スレッドの最初のメッセージの埋め込みに表示する Notion のアイコン
+const NOTION_ICON_URL: &str = "https://www.notion.so/images/logo-ios.png";
+const DIARY_BACKFILL_DEFAULT_DAYS: i64 = 7;
+const DIARY_BACKFILL_MAX_DAYS: i64 = 31;
+/// 日報スレッドに転記するキーワード付きメッセージの最大文字数（投稿者の表記を含めて 2000 文字に収める）
+const KEYWORD_REPOST_MAX_CHARS: usize = 1800;
+/// `/diary history` で表示する同期履歴の件数。
+const SYNC_HISTORY_DISPLAY_LIMIT: i64 = 15;
+/// `/diary history` で表示するエラーや警告の内容の最大文字数。
+const SYNC_HISTORY_DETAIL_MAX_CHARS: usize = 80;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct DiaryThreadSyncReport {
+    checked_messages: usize,
+    synced_messages: usize,
+    already_synced_messages: usize,
+    skipped_messages: usize,
+}
+
+/// 複数の日報ページのブロックとストアの対応を突き合わせた結果の集計。
+#[derive(Debug, Clone, Copy, Default)]
+struct DiaryReconcileReport {
+    checked_threads: usize,
+    checked_blocks: usize,
+    removed_blocks: usize,
+    orphan_blocks: usize,
+    failed_threads: usize,
+}
+
+/// 日報の運用単位（フォーラムチャンネルと Notion データベースの組）。
+#[derive(Clone)]
+pub struct DiaryTarget {
+    /// 日報スレッドを作成するフォーラムチャンネル
+    forum_channel_id: ChannelId,
+    /// 日報ページを作成する Notion データベースのクライアント
+    notion_client: Arc<NotionClient>,
+}
+
+/// 日報スレッドに紐付ける Notion ページ。
+struct DiaryPage<'a> {
+    /// ページのタイトル
+    title: &'a str,
+    /// ページ ID
+    id: &'a str,
+    /// ページ URL
+    url: &'a str,
+    /// このリクエストで作成したページか（スレッド作成に失敗したときにアーカイブする）
+    created: bool,
+}
+
+/// 毎時同期の実行済み時間帯を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiaryHourlySyncSlot {
+    /// タイムゾーン基準の日付。
+    date: NaiveDate,
+    /// タイムゾーン基準の時。
+    hour: u32,
+}
+
+impl DiaryHourlySyncSlot {
+    /// 現在時刻から毎時同期の判定に使う時間帯を作る。
+    fn current(timezone: &chrono_tz::Tz) -> Self {
+        let now = chrono::Utc::now().with_timezone(timezone);
+        Self {
+            date: now.date_naive(),
+            hour: now.hour(),
+        }
+    }
+}
+
+/// `/diary` コマンドの定義を返す（サーバー内でのみ使える）。
+pub fn diary_command() -> CreateCommand {
+    CreateCommand::new("diary")
+        .description("日報機能")
+        .integration_types(vec![InstallationContext::Guild])
+        .contexts(vec![InteractionContext::Guild])
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "new",
+            "新しい日報を作成する",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "close",
+            "日報スレッドをクローズする",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "sync",
+            "Sync unsynced messages in this diary thread",
+        ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "backfill",
+                "過去の日報スレッドの未同期メッセージを一括同期する",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "days",
+                    "遡る日数（デフォルト: 7日）",
+                )
+                .min_int_value(1)
+                .max_int_value(DIARY_BACKFILL_MAX_DAYS as u64)
+                .required(false),
+            ),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "status",
+            "日報スレッドの同期状態を表示する",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "history",
+            "直近の同期履歴を表示する（日報スレッド以外では全スレッド分）",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "reconcile",
+            "Notion のブロックと同期の記録を突き合わせる（日報スレッド以外では直近の日報すべて）",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "pause",
+            "この日報スレッドの Notion 同期を一時停止する",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "resume",
+            "この日報スレッドの Notion 同期を再開する",
+        ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "link",
+                "この日報スレッドを既存の Notion ページに紐付ける",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "url",
+                    "紐付ける Notion ページの URL",
+                )
+                .required(true),
+            ),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "selftest",
+            "日報機能の設定と権限を確認する",
+        ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "weekly",
+                "週報ページを Notion に作成する",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "date",
+                    "対象の週に含まれる日付（YYYY-MM-DD、デフォルト: 先週）",
+                )
+                .required(false),
+            ),
+        )
+}
+
+/// 日報機能のコマンド（`/diary`・`/stats`）の定義を返す。
+pub fn diary_commands() -> Vec<CreateCommand> {
+    vec![diary_command(), stats_command()]
+}
+
+/// 日報機能に必要なゲートウェイのインテントを返す。
+pub fn gateway_intents(config: &Config) -> GatewayIntents {
+    // メッセージイベントと、opt-in モードの同期や `/stats emoji` の集計に使うリアクションを購読
+    let mut intents = GatewayIntents::GUILD_MESSAGES | GatewayIntents::GUILD_MESSAGE_REACTIONS;
+
+    // MESSAGE_CONTENT は特権インテントのため、開発者ポータルで有効にできない環境では要求しない
+    if config.discord.message_content_intent {
+        intents |= GatewayIntents::MESSAGE_CONTENT;
+    }
+
+    intents
+}
+
+/// MESSAGE_CONTENT インテントが無いときのように、本文・添付ファイル・埋め込みのすべてが空のメッセージかどうかを返す。
+///
+/// 投票やシステムメッセージなど、本文が無くても正常なメッセージは除く。
+fn lacks_message_content(message: &Message) -> bool {
+    matches!(
+        message.kind,
+        MessageType::Regular | MessageType::InlineReply
+    ) && message.content.is_empty()
+        && message.attachments.is_empty()
+        && message.embeds.is_empty()
+        && message.sticker_items.is_empty()
+        && message.message_snapshots.is_empty()
+        && message.poll.is_none()
+}
+
+/// 日報機能の状態（日報ストアや Notion のクライアントなど）。
+#[derive(Clone)]
+pub struct DiaryState {
+    /// 日報ストア
+    pub(super) store: DiaryStore,
+    /// ページ単位の操作（ブロックの追加など）に使う Notion クライアント（最初の日報と共有する）
+    notion_client: Arc<NotionClient>,
+    /// 運用する日報の一覧（最初の要素が `[diary]` で設定した日報）
+    diary_targets: Vec<DiaryTarget>,
+    /// 自動クローズ通知を送信済みの日付（タイムゾーン基準）
+    last_auto_close_notification_date: Arc<Mutex<Option<NaiveDate>>>,
+    /// 毎時同期を最後に試行した時間帯。
+    last_hourly_sync_slot: Arc<Mutex<Option<DiaryHourlySyncSlot>>>,
+    /// 週報・月報の作成を確認済みの日付（タイムゾーン基準）
+    last_report_check_date: Arc<Mutex<Option<NaiveDate>>>,
+    /// 日報フォーラムでリアクションを付与できるか（権限を確認できるまでは付与できるとみなす）
+    reactions_allowed: Arc<AtomicBool>,
+    /// MESSAGE_CONTENT インテントが使えずメッセージ本文を受け取れていないことを検出したか
+    message_content_missing: Arc<AtomicBool>,
+    /// 削除や権限の喪失で日報の自動作成を停止しているフォーラム（フォーラム ID ごとの理由）
+    unavailable_forums: Arc<Mutex<HashMap<u64, ForumUnavailableReason>>>,
+    /// リアクションの代わりにスレッドへ報告するまでの同期件数（スレッド ID ごと）
+    reactionless_sync_counts: Arc<Mutex<HashMap<u64, u32>>>,
+    /// 日報の作成処理を直列化するロック（同日のページやスレッドの重複作成を防ぐ）
+    diary_creation_lock: Arc<Mutex<()>>,
+    /// ダウンロードや変換に使う一時ファイルの作業ディレクトリ
+    temp_workspace: TempWorkspace,
+    /// 日報ページの作成時に挿入する雛形ブロック
+    page_template: Option<PageTemplate>,
+    /// キーワード付きメッセージを日報に転記するトリガー
+    keyword_trigger: Option<KeywordTrigger>,
+    /// 書き出しを付けたメッセージを個人用のページに同期する設定
+    private_notes: Option<PrivateNotes>,
+    /// 同期の連続失敗をメールで通知する通知者
+    email_notifier: Option<Arc<EmailNotifier>>,
+}
+
+impl DiaryState {
+    /// 日報機能の設定を検証し、日報ストアや Notion のクライアントを用意する。
+    ///
+    /// Matrix で日報に同期するルームが設定されている場合は、日報ストアなどを共有する Matrix の同期も起動する。
+    pub async fn new(
+        config: &Config,
+        bot_store: &BotStore,
+        email_notifier: Option<Arc<EmailNotifier>>,
+    ) -> Result<Self> {
+        let diary_config = &config.diary;
+
+        // 起動時に URL ルールのバリデーションを行う
+        compile_url_rules(&diary_config.url_rules, &diary_config.default_convert_to)
+            .context("Invalid URL rules in configuration")?;
+        compile_redaction_rules(&diary_config.redaction_rules)
+            .context("Invalid redaction rules in configuration")?;
+        compile_image_rules(&diary_config.image_rules)
+            .context("Invalid image rules in configuration")?;
+        let page_template = compile_page_template(diary_config.page_template_blocks.as_deref())
+            .context("Invalid page template in configuration")?;
+        validate_page_title_format(&diary_config.page_title_format)
+            .context("Invalid page title format in configuration")?;
+        let keyword_trigger = compile_keyword_trigger(diary_config.keyword_trigger.as_ref())
+            .context("Invalid keyword trigger in configuration")?;
+        let private_notes = compile_private_notes(diary_config)
+            .context("Invalid private notes in configuration")?;
+        if let Some(quality) = diary_config.image_jpeg_quality
+            && !(1..=100).contains(&quality)
+        {
+            anyhow::bail!(
+                "image_jpeg_quality must be between 1 and 100, got {}",
+                quality
+            );
+        }
+
+        let temp_workspace = TempWorkspace::open(
+            diary_config
+                .temp_dir
+                .clone()
+                .unwrap_or_else(|| std::env::temp_dir().join("kgd")),
+            diary_config.temp_dir_quota,
+        )
+        .context("Failed to open temporary workspace")?;
+
+        let diary_store = DiaryStore::connect(&diary_config.database_url)
+            .await
+            .context("Failed to connect to database")?;
+
+        let notion_client = Arc::new(
+            NotionClient::new(
+                &diary_config.notion_token,
+                &diary_config.notion_database_id,
+                &diary_config.notion_title_property,
+                diary_config.notion_properties.clone(),
+                diary_config.notion_cache_ttl,
+                diary_config.retry_policy(),
+                diary_config.notion_api_version,
+            )
+            .context("Failed to create Notion client")?,
+        );
+        let mut diary_targets = vec![DiaryTarget {
+            forum_channel_id: ChannelId::new(diary_config.forum_channel_id),
+            notion_client: notion_client.clone(),
+        }];
+        for additional in &diary_config.additional_diaries {
+            let forum_channel_id = ChannelId::new(additional.forum_channel_id);
+            if diary_targets
+                .iter()
+                .any(|target| target.forum_channel_id == forum_channel_id)
+            {
+                anyhow::bail!(
+                    "Forum channel {} is configured for more than one diary",
+                    forum_channel_id
+                );
+            }
+            let notion_client = NotionClient::new(
+                &diary_config.notion_token,
+                &additional.notion_database_id,
+                &additional.notion_title_property,
+                additional.notion_properties.clone(),
+                diary_config.notion_cache_ttl,
+                diary_config.retry_policy(),
+                diary_config.notion_api_version,
+            )
+            .with_context(|| {
+                format!(
+                    "Failed to create Notion client for diary in forum channel {}",
+                    forum_channel_id
+                )
+            })?;
+            diary_targets.push(DiaryTarget {
+                forum_channel_id,
+                notion_client: Arc::new(notion_client),
+            });
+        }
+
+        // 複数の日報に対応する前のエントリは `[diary]` の日報のものとして扱う
+        let assigned = diary_store
+            .assign_forum_channel(diary_config.forum_channel_id)
+            .await?;
+        if assigned > 0 {
+            info!(
+                entries = assigned,
+                forum_channel_id = diary_config.forum_channel_id,
+                "Assigned forum channel to existing diary entries"
+            );
+        }
+
+        let diary_creation_lock = Arc::new(Mutex::new(()));
+        if config
+            .matrix
+            .as_ref()
+            .is_some_and(|matrix| !matrix.diary_room_ids.is_empty())
+        {
+            let matrix = MatrixDiarySync::new(
+                config.clone(),
+                bot_store.clone(),
+                diary_store.clone(),
+                notion_client.clone(),
+                temp_workspace.clone(),
+                page_template.clone(),
+                diary_creation_lock.clone(),
+            )
+            .context("Failed to create Matrix client")?;
+            tokio::spawn(matrix.run());
+            info!("Matrix diary sync started");
+        }
+
+        Ok(Self {
+            store: diary_store,
+            notion_client,
+            diary_targets,
+            last_auto_close_notification_date: Arc::new(Mutex::new(None)),
+            last_hourly_sync_slot: Arc::new(Mutex::new(None)),
+            last_report_check_date: Arc::new(Mutex::new(None)),
+            reactions_allowed: Arc::new(AtomicBool::new(true)),
+            message_content_missing: Arc::new(AtomicBool::new(false)),
+            unavailable_forums: Arc::new(Mutex::new(HashMap::new())),
+            reactionless_sync_counts: Arc::new(Mutex::new(HashMap::new())),
+            diary_creation_lock,
+            temp_workspace,
+            page_template,
+            keyword_trigger,
+            private_notes,
+            email_notifier,
+        })
+    }
+}
+
+impl Handler {
+    /// メッセージの投稿を日報に同期する（キーワード付きのメッセージは今日の日報に転記する）。
+    pub async fn handle_message(&self, ctx: SerenityContext, message: Message) {
+        // Bot 自身のメッセージは無視
+        if message.author.bot {
+            return;
+        }
+
+        if self.config.discord.message_content_intent && lacks_message_content(&message) {
+            self.report_missing_message_content(
+                "Received a message without any content, attachments or embeds",
+            );
+        }
+
+        if let Some(trigger) = &self.diary.keyword_trigger
+            && let Some(body) = trigger.extract(message.channel_id.get(), &message.content)
+        {
+            if let Err(e) = self.repost_to_today_diary(&ctx, &message, body).await {
+                error!(
+                    error = %e,
+                    message_id = message.id.get(),
+                    "Failed to repost keyword message to diary"
+                );
+                self.add_sync_reaction(
+                    &ctx.http,
+                    &message,
+                    &self.config.diary.failed_sync_reaction,
+                )
+                .await;
+            }
+            return;
+        }
+
+        // opt-in モードではリアクションが付いたときに同期する
+        if self.config.diary.sync_mode == SyncMode::Reaction {
+            return;
+        }
+
+        self.sync_thread_message(&ctx, &message).await;
+    }
+
+    /// opt-in モードで同期のリアクションが付いたメッセージを日報に同期する。
+    pub async fn handle_reaction_add(&self, ctx: SerenityContext, reaction: Reaction) {
+        if self.config.diary.sync_mode != SyncMode::Reaction
+            || reaction.emoji != self.sync_trigger_reaction()
+        {
+            return;
+        }
+
+        let Ok(message) = reaction.message(&ctx.http).await else {
+            return;
+        };
+        if message.author.bot {
+            return;
+        }
+
+        // 同期済みのメッセージに再度リアクションが付いた場合は何もしない
+        match self
+            .diary
+            .store
+            .has_blocks_by_message(message.id.get())
+            .await
+        {
+            Ok(false) => {}
+            Ok(true) => return,
+            Err(e) => {
+                error!(error = %e, "Failed to check message blocks");
+                return;
+            }
+        }
+
+        self.sync_thread_message(&ctx, &message).await;
+    }
+
+    /// 編集されたメッセージを Notion に反映する。
+    pub async fn handle_message_update(&self, ctx: SerenityContext, event: MessageUpdateEvent) {
+        // Bot 自身のメッセージは無視
+        if event.author.as_ref().is_some_and(|a| a.bot) {
+            return;
+        }
+
+        // コンテンツがない場合は無視
+        let Some(content) = event.content else {
+            return;
+        };
+
+        // 日報スレッドでない場合はメッセージを取得しない
+        let Ok(Some(_entry)) = self.diary.store.get_by_thread(event.channel_id.get()).await else {
+            return;
+        };
+
+        // メッセージを取得して更新
+        let Ok(message) = event.channel_id.message(&ctx.http, event.id).await else {
+            return;
+        };
+
+        // メッセージの内容で上書きして更新
+        let mut message = message;
+        message.content = content;
+
+        // 個人用のメモの編集は同期しない（新たなブロックが共有の日報ページに追加されないようにする）
+        if self
+            .diary
+            .private_notes
+            .as_ref()
+            .is_some_and(|notes| notes.strip_prefix(&message.content).is_some())
+        {
+            return;
+        }
+
+        match self
+            .handle_message_event(&ctx, MessageEvent::Updated(source_message(&message)))
+            .await
+        {
+            Some(Ok(EventOutcome::Updated(true))) => {
+                info!(
+                    thread_id = event.channel_id.get(),
+                    message_id = event.id.get(),
+                    "Message updated in Notion"
+                );
+            }
+            Some(Ok(_)) | None => {
+                // 対応するブロックがなかった（新規メッセージの可能性）
+            }
+            Some(Err(e)) => {
+                error!(error = %e, "Failed to update message in Notion");
+            }
+        }
+    }
+
+    /// 削除されたメッセージに対応するブロックを Notion から削除する。
+    pub async fn handle_message_delete(
+        &self,
+        ctx: SerenityContext,
+        channel_id: ChannelId,
+        deleted_message_id: MessageId,
+    ) {
+        // Notion から対応するブロックを削除
+        let event = MessageEvent::Deleted {
+            thread_id: channel_id.get(),
+            message_id: deleted_message_id.get(),
+        };
+        match self.handle_message_event(&ctx, event).await {
+            Some(Ok(EventOutcome::Deleted(true))) => {
+                info!(
+                    thread_id = channel_id.get(),
+                    message_id = deleted_message_id.get(),
+                    "Message deleted from Notion"
+                );
+            }
+            Some(Ok(_)) | None => {
+                // 対応するブロックがなかった
+            }
+            Some(Err(e)) => {
+                error!(error = %e, "Failed to delete message from Notion");
+            }
+        }
+    }
+
+    /// まとめて削除されたメッセージに対応するブロックを Notion から削除する。
+    pub async fn handle_message_delete_bulk(
+        &self,
+        ctx: SerenityContext,
+        channel_id: ChannelId,
+        multiple_deleted_messages_ids: Vec<MessageId>,
+    ) {
+        // Notion から対応するブロックをまとめて削除
+        let message_ids: Vec<u64> = multiple_deleted_messages_ids
+            .iter()
+            .map(|id| id.get())
+            .collect();
+        let requested_messages = message_ids.len();
+        let event = MessageEvent::BulkDeleted {
+            thread_id: channel_id.get(),
+            message_ids,
+        };
+        match self.handle_message_event(&ctx, event).await {
+            Some(Ok(EventOutcome::BulkDeleted(result))) if result.deleted_messages > 0 => {
+                info!(
+                    thread_id = channel_id.get(),
+                    requested_messages,
+                    deleted_messages = result.deleted_messages,
+                    deleted_blocks = result.deleted_blocks,
+                    failed_blocks = ?result.failed_blocks,
+                    "Bulk-deleted messages purged from Notion"
+                );
+            }
+            Some(Ok(_)) | None => {
+                // 対応するブロックがなかった
+            }
+            Some(Err(e)) => {
+                error!(error = %e, "Failed to bulk delete messages from Notion");
+            }
+        }
+    }
+
+    /// 日報機能のコマンド（`/diary`・`/stats`）を実行する。
+    pub async fn handle_diary_command(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        match command.data.name.as_str() {
+            "diary" => self.handle_diary(ctx, command).await,
+            "stats" => self.handle_stats(ctx, command).await,
+            _ => Ok(()),
+        }
+    }
+
+    pub async fn handle_diary(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let subcommand = command
+            .data
+            .options
+            .first()
+            .context("Subcommand not provided")?;
+
+        match subcommand.name.as_str() {
+            "new" => self.handle_diary_new(ctx, command).await,
+            "close" => self.handle_diary_close(ctx, command).await,
+            "sync" => self.handle_diary_sync(ctx, command).await,
+            "backfill" => self.handle_diary_backfill(ctx, command).await,
+            "status" => self.handle_diary_status(ctx, command).await,
+            "history" => self.handle_diary_history(ctx, command).await,
+            "reconcile" => self.handle_diary_reconcile(ctx, command).await,
+            "pause" => self.handle_diary_pause(ctx, command).await,
+            "resume" => self.handle_diary_resume(ctx, command).await,
+            "weekly" => self.handle_diary_weekly(ctx, command).await,
+            "link" => self.handle_diary_link(ctx, command).await,
+            "selftest" => self.handle_diary_selftest(ctx, command).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn handle_diary_new(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        // 他の作成処理を待つ間に応答の期限（3 秒）を過ぎないよう、ロックを取る前に応答を保留する
+        command.defer(&ctx.http).await?;
+
+        let content = match self
+            .open_today_diary(&ctx.http, command.channel_id, command.user.id)
+            .await
+        {
+            Ok(content) => content,
+            Err(e) => {
+                error!(error = ?e, "Failed to create diary");
+                format!("Error: {}", e)
+            }
+        };
+        command
+            .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 今日の日報を作成し（既にある場合は再開し）、`/diary new` の応答の文面を返す。
+    async fn open_today_diary(
+        &self,
+        http: &Http,
+        channel_id: ChannelId,
+        creator: UserId,
+    ) -> Result<String> {
+        let diary_config = &self.config.diary;
+        let target = self.resolve_diary_target(http, channel_id).await;
+
+        // 並行して実行された作成処理と重複しないよう、作成処理全体を直列化する
+        let _creation_guard = self.diary.diary_creation_lock.lock().await;
+
+        // 今日の日付を設定されたタイムゾーンで取得
+        let date = today_in_timezone(&diary_config.timezone);
+
+        // 既に今日の日報が存在するかチェック
+        if let Some(entry) = self
+            .diary
+            .store
+            .get_by_date(target.forum_channel_id.get(), date)
+            .await?
+        {
+            let thread_id = ChannelId::new(entry.thread_id);
+
+            let reopened = match thread_id.join_thread(http).await {
+                Ok(()) => {
+                    let edit = EditThread::new().archived(false).locked(false);
+                    match thread_id.edit_thread(http, edit).await {
+                        Ok(_) => true,
+                        Err(error) => {
+                            warn!(
+                                error = %error,
+                                thread_id = entry.thread_id,
+                                "Failed to reopen existing diary thread; falling back to link only"
+                            );
+                            false
+                        }
+                    }
+                }
+                Err(error) => {
+                    warn!(
+                        error = %error,
+                        thread_id = entry.thread_id,
+                        "Failed to join existing diary thread; falling back to link only"
+                    );
+                    false
+                }
+            };
+
+            if let Err(error) = self.ensure_close_and_new_button(http, thread_id).await {
+                warn!(
+                    error = %error,
+                    thread_id = entry.thread_id,
+                    "Failed to ensure close button on existing diary thread"
+                );
+            }
+
+            info!(
+                date = %date,
+                thread_id = entry.thread_id,
+                reopened,
+                "Diary thread already exists for today"
+            );
+
+            return Ok(if reopened {
+                format!("今日の日報を再開しました: <#{}>", entry.thread_id)
+            } else {
+                format!(
+                    "今日の日報は既にありますが、再開はできません: <#{}>",
+                    entry.thread_id
+                )
+            });
+        }
+
+        let (entry, reused) = self.create_diary(http, target, date, Some(creator)).await?;
+
+        // 成功レスポンス
+        Ok(if reused {
+            format!(
+                "既存の Notion ページを使用して日報を作成しました\nスレッド: <#{}>\nNotion: {}",
+                entry.thread_id, entry.page_url
+            )
+        } else {
+            format!(
+                "日報を作成しました\nスレッド: <#{}>\nNotion: {}",
+                entry.thread_id, entry.page_url
+            )
+        })
+    }
+
+    async fn handle_diary_close(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        // スレッド内からの呼び出しか確認
+        let channel = command.channel_id.to_channel(&ctx.http).await?;
+        let Some(guild_channel) = channel.guild() else {
+            let response = CreateInteractionResponseMessage::new()
+                .content("このコマンドはサーバー内でのみ使用できます")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        };
+
+        if guild_channel.kind != ChannelType::PublicThread {
+            let response = CreateInteractionResponseMessage::new()
+                .content("このコマンドは日報スレッド内から実行してください")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        }
+
+        // 該当スレッドが日報スレッドか確認
+        let Some(entry) = self
+            .diary
+            .store
+            .get_by_thread(command.channel_id.get())
+            .await?
+        else {
+            let response = CreateInteractionResponseMessage::new()
+                .content("このスレッドは日報スレッドではありません")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        };
+
+        // 先にレスポンスを返す（アーカイブ後はレスポンスを返せないため）
+        let response = CreateInteractionResponseMessage::new()
+            .content("日報スレッドをクローズしています...")
+            .ephemeral(false);
+
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        self.update_page_summary(&ctx.http, &entry).await;
+
+        // スレッドをアーカイブ & ロック (クローズ)
+        // locked=true にすることで、ユーザーが書き込んでも自動的に再開されない
+        let edit = EditThread::new().archived(true).locked(true);
+        command
+            .channel_id
+            .edit_thread(&ctx.http, edit)
+            .await
+            .context("スレッドのクローズに失敗しました")?;
+        self.diary
+            .store
+            .mark_closed(command.channel_id.get(), chrono::Utc::now())
+            .await?;
+
+        info!(thread_id = command.channel_id.get(), "Diary thread closed");
+
+        Ok(())
+    }
+
+    /// コンポーネント操作を処理する。
+    /// 現在の日報スレッドを対象に、未同期メッセージの再同期を手動実行する。
+    async fn handle_diary_sync(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let channel = command.channel_id.to_channel(&ctx.http).await?;
+        let Some(guild_channel) = channel.guild() else {
+            let response = CreateInteractionResponseMessage::new()
+                .content("このコマンドはサーバー内の日報スレッドでのみ使用できます")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        };
+
+        if guild_channel.kind != ChannelType::PublicThread {
+            let response = CreateInteractionResponseMessage::new()
+                .content("このコマンドは日報スレッド内で実行してください")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        }
+
+        if self
+            .diary
+            .store
+            .get_by_thread(command.channel_id.get())
+            .await?
+            .is_none()
+        {
+            let response = CreateInteractionResponseMessage::new()
+                .content("このスレッドは日報スレッドとして登録されていません")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        }
+
+        if !self
+            .diary
+            .store
+            .is_sync_enabled(command.channel_id.get())
+            .await?
+        {
+            let response = CreateInteractionResponseMessage::new()
+                .content("このスレッドは同期を一時停止中です。`/diary resume` で再開してください")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        }
+
+        command.defer_ephemeral(&ctx.http).await?;
+
+        let report = self
+            .sync_missing_messages_in_thread(&ctx.http, command.channel_id)
+            .await?;
+
+        let result_message = format!(
+            "日報スレッドの未同期メッセージを確認しました。\n確認件数: {}件\n新規同期: {}件\n既に同期済み: {}件\nスキップ: {}件",
+            report.checked_messages,
+            report.synced_messages,
+            report.already_synced_messages,
+            report.skipped_messages
+        );
+
+        command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new().content(result_message),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// 指定した日数分の日報スレッドを走査し、未同期メッセージを一括で Notion に同期する。
+    ///
+    /// Bot の停止中に投稿されたメッセージを取り戻すためのもので、スレッドごとの進捗を followup で報告する。
+    async fn handle_diary_backfill(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let days = subcommand_integer_option(command, "days")
+            .unwrap_or(DIARY_BACKFILL_DEFAULT_DAYS)
+            .clamp(1, DIARY_BACKFILL_MAX_DAYS);
+
+        command.defer_ephemeral(&ctx.http).await?;
+
+        let timezone = &self.config.diary.timezone;
+        let today = today_in_timezone(timezone);
+        let start_date = today - chrono::Duration::days(days - 1);
+        let entries = self
+            .discord_entries_in_date_range(start_date, today)
+            .await?;
+
+        if entries.is_empty() {
+            command
+                .edit_response(
+                    &ctx.http,
+                    EditInteractionResponse::new()
+                        .content(format!("直近{}日間の日報スレッドはありません", days)),
+                )
+                .await?;
+            return Ok(());
+        }
+
+        let total = entries.len();
+        command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new().content(format!(
+                    "直近{}日間の日報スレッド {}件のバックフィルを開始します",
+                    days, total
+                )),
+            )
+            .await?;
+
+        let mut total_report = DiaryThreadSyncReport::default();
+        let mut failed_threads = 0usize;
+
+        for (index, entry) in entries.iter().enumerate() {
+            let thread_id = ChannelId::new(entry.thread_id);
+            let date = format_date_in_timezone(entry.date, timezone);
+            let progress = match self
+                .sync_missing_messages_in_thread(&ctx.http, thread_id)
+                .await
+            {
+                Ok(report) => {
+                    total_report.checked_messages += report.checked_messages;
+                    total_report.synced_messages += report.synced_messages;
+                    total_report.already_synced_messages += report.already_synced_messages;
+                    total_report.skipped_messages += report.skipped_messages;
+                    format!(
+                        "[{}/{}] {} (<#{}>): 新規同期 {}件 / 既に同期済み {}件 / スキップ {}件",
+                        index + 1,
+                        total,
+                        date,
+                        entry.thread_id,
+                        report.synced_messages,
+                        report.already_synced_messages,
+                        report.skipped_messages
+                    )
+                }
+                Err(error) => {
+                    error!(
+                        error = %error,
+                        thread_id = entry.thread_id,
+                        "Failed to backfill diary thread"
+                    );
+                    failed_threads += 1;
+                    format!(
+                        "[{}/{}] {} (<#{}>): 同期に失敗しました",
+                        index + 1,
+                        total,
+                        date,
+                        entry.thread_id
+                    )
+                }
+            };
+
+            if let Err(error) = command
+                .create_followup(
+                    &ctx.http,
+                    CreateInteractionResponseFollowup::new()
+                        .content(progress)
+                        .ephemeral(true),
+                )
+                .await
+            {
+                warn!(error = %error, "Failed to send backfill progress");
+            }
+        }
+
+        info!(
+            days,
+            threads = total,
+            failed_threads,
+            checked_messages = total_report.checked_messages,
+            synced_messages = total_report.synced_messages,
+            "Diary backfill finished"
+        );
+
+        command
+            .create_followup(
+                &ctx.http,
+                CreateInteractionResponseFollowup::new()
+                    .content(format!(
+                        "バックフィルが完了しました。\n対象スレッド: {}件（失敗: {}件）\n確認件数: {}件\n新規同期: {}件\n既に同期済み: {}件\nスキップ: {}件",
+                        total,
+                        failed_threads,
+                        total_report.checked_messages,
+                        total_report.synced_messages,
+                        total_report.already_synced_messages,
+                        total_report.skipped_messages
+                    ))
+                    .ephemeral(true),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// 現在の日報スレッドの同期状態を表示する。
+    async fn handle_diary_status(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let thread_id = command.channel_id.get();
+        if self.diary.store.get_by_thread(thread_id).await?.is_none() {
+            let response = CreateInteractionResponseMessage::new()
+                .content("このスレッドは日報スレッドとして登録されていません")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        }
+
+        let status = self.diary.store.get_sync_status(thread_id).await?;
+
+        let mut embed = CreateEmbed::new()
+            .title("日報の同期状態")
+            .color(if status.failure_count > 0 {
+                0xffa500
+            } else {
+                0x00ff00
+            })
+            .field(
+                "同期済みメッセージ",
+                format!("{}件", status.message_count),
+                true,
+            )
+            .field("ブロック数", format!("{}件", status.block_count), true)
+            .field("失敗件数", format!("{}件", status.failure_count), true)
+            .field("警告件数", format!("{}件", status.warning_count), true)
+            .field(
+                "同期",
+                if status.sync_enabled {
+                    "有効"
+                } else {
+                    "一時停止中"
+                },
+                true,
+            )
+            .field(
+                "最終同期",
+                format_discord_timestamp(status.last_synced_at),
+                false,
+            );
+
+        if let Some(last_failed_at) = status.last_failed_at {
+            let error = status.last_error.as_deref().unwrap_or("不明なエラー");
+            embed = embed.field(
+                "最終失敗",
+                format!(
+                    "{}\n```\n{}\n```",
+                    format_discord_timestamp(Some(last_failed_at)),
+                    truncate_chars(error, 900)
+                ),
+                false,
+            );
+        }
+
+        if let Some(last_warning) = status.last_warning.as_deref() {
+            embed = embed.field(
+                "最後の警告",
+                format!("```\n{}\n```", truncate_chars(last_warning, 900)),
+                false,
+            );
+        }
+
+        let response = CreateInteractionResponseMessage::new()
+            .embed(embed)
+            .ephemeral(true);
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 直近の同期履歴を表示する。
+    ///
+    /// 日報スレッドで実行した場合はそのスレッドの履歴だけを、それ以外では全スレッドの履歴を表示する。
+    async fn handle_diary_history(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let thread_id = command.channel_id.get();
+        let in_thread = self.diary.store.get_by_thread(thread_id).await?.is_some();
+        let history = self
+            .diary
+            .store
+            .get_sync_history(in_thread.then_some(thread_id), SYNC_HISTORY_DISPLAY_LIMIT)
+            .await?;
+
+        let guild_id = command.guild_id.map_or(0, |guild_id| guild_id.get());
+        let description = if history.is_empty() {
+            "同期履歴はありません".to_string()
+        } else {
+            history
+                .iter()
+                .map(|record| format_sync_history_line(record, guild_id))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let embed = CreateEmbed::new()
+            .title(if in_thread {
+                "このスレッドの同期履歴"
+            } else {
+                "日報の同期履歴"
+            })
+            .description(description)
+            .color(
+                if history
+                    .iter()
+                    .any(|record| record.status != SyncHistoryStatus::Success)
+                {
+                    0xffa500
+                } else {
+                    0x00ff00
+                },
+            );
+
+        let response = CreateInteractionResponseMessage::new()
+            .embed(embed)
+            .ephemeral(true);
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 日報ページのブロックと同期の記録を突き合わせ、結果を表示する。
+    ///
+    /// 日報スレッドで実行した場合はそのスレッドを、それ以外では直近 `reconcile_days` 日間の日報を対象にする。
+    async fn handle_diary_reconcile(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        command.defer_ephemeral(&ctx.http).await?;
+
+        let content = match self
+            .diary
+            .store
+            .get_by_thread(command.channel_id.get())
+            .await?
+        {
+            Some(entry) => {
+                let result = self.reconcile_diary_thread(&ctx.http, &entry).await?;
+                let mut content = format!(
+                    "🔍 このスレッドのブロック {}件を確認しました\n\
+                     Notion で削除されていたブロックの記録: {}件\n\
+                     記録の無いブロック: {}件",
+                    result.checked_blocks,
+                    result.removed_blocks.len(),
+                    result.orphan_blocks.len()
+                );
+                if !result.removed_blocks.is_empty() {
+                    content.push_str(
+                        "\nブロックがすべて削除されたメッセージは `/diary sync` で同期し直せます",
+                    );
+                }
+                content
+            }
+            None => {
+                let days = self.config.diary.reconcile_days.max(1);
+                let entries = self.recent_diary_entries(days).await?;
+                let report = self.reconcile_diary_entries(&ctx.http, &entries).await;
+                format!(
+                    "🔍 直近{}日間の日報スレッド {}件（ブロック {}件）を確認しました\n\
+                     Notion で削除されていたブロックの記録: {}件\n\
+                     記録の無いブロック: {}件\n\
+                     失敗したスレッド: {}件",
+                    days,
+                    report.checked_threads,
+                    report.checked_blocks,
+                    report.removed_blocks,
+                    report.orphan_blocks,
+                    report.failed_threads
+                )
+            }
+        };
+
+        command
+            .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 現在の日報スレッドの Notion 同期を一時停止する。
+    async fn handle_diary_pause(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let thread_id = command.channel_id.get();
+        if self.diary.store.get_by_thread(thread_id).await?.is_none() {
+            let response = CreateInteractionResponseMessage::new()
+                .content("このスレッドは日報スレッドとして登録されていません")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        }
+
+        self.diary.store.set_sync_enabled(thread_id, false).await?;
+        info!(thread_id, "Diary thread sync paused");
+
+        let response = CreateInteractionResponseMessage::new()
+            .content(
+                "⏸️ このスレッドの Notion 同期を一時停止しました。`/diary resume` で再開できます",
+            )
+            .ephemeral(false);
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 現在の日報スレッドの Notion 同期を再開し、一時停止中に投稿されたメッセージを同期する。
+    async fn handle_diary_resume(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let thread_id = command.channel_id.get();
+        if self.diary.store.get_by_thread(thread_id).await?.is_none() {
+            let response = CreateInteractionResponseMessage::new()
+                .content("このスレッドは日報スレッドとして登録されていません")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        }
+
+        self.diary.store.set_sync_enabled(thread_id, true).await?;
+        info!(thread_id, "Diary thread sync resumed");
+
+        command.defer(&ctx.http).await?;
+
+        let content = match self
+            .sync_missing_messages_in_thread(&ctx.http, command.channel_id)
+            .await
+        {
+            Ok(report) => format!(
+                "▶️ このスレッドの Notion 同期を再開しました。一時停止中のメッセージ {}件を同期しました",
+                report.synced_messages
+            ),
+            Err(e) => {
+                error!(error = %e, thread_id, "Failed to sync messages after resuming");
+                "▶️ このスレッドの Notion 同期を再開しました。一時停止中のメッセージは `/diary sync` で同期してください".to_string()
+            }
+        };
+
+        command
+            .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 日報フォーラム（追加の日報を含む）のスレッドを既存の Notion ページに紐付ける。
+    ///
+    /// 日報スレッドとして登録済みの場合は紐付け先のページを差し替える。
+    /// 未登録のスレッドはスレッド名（"YYYY-MM-DD"）、または作成日時から日付を決めて登録する。
+    async fn handle_diary_link(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let diary_config = &self.config.diary;
+        let channel = command.channel_id.to_channel(&ctx.http).await?;
+        let thread = channel.guild().and_then(|channel| {
+            let target = channel
+                .parent_id
+                .and_then(|parent_id| self.find_diary_target(parent_id))?;
+            (channel.kind == ChannelType::PublicThread).then_some((channel, target))
+        });
+        let Some((thread, target)) = thread else {
+            let response = CreateInteractionResponseMessage::new()
+                .content("このコマンドは日報フォーラムのスレッド内で実行してください")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        };
+
+        let url = subcommand_string_option(command, "url").unwrap_or_default();
+        let Some(page_id) = parse_page_id(url) else {
+            let response = CreateInteractionResponseMessage::new()
+                .content("Notion ページの URL からページ ID を読み取れませんでした")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        };
+
+        command.defer(&ctx.http).await?;
+
+        let (page_id, page_url) = self
+            .diary
+            .notion_client
+            .get_page(&page_id)
+            .await
+            .context("Notion ページを取得できませんでした")?;
+
+        let timezone = &diary_config.timezone;
+        let existing = self.diary.store.get_by_thread(thread.id.get()).await?;
+        let (date, created_at) = match &existing {
+            Some(entry) => (entry.date, entry.created_at),
+            None => {
+                let date =
+                    NaiveDate::parse_from_str(&thread.name, "%Y-%m-%d").unwrap_or_else(|_| {
+                        thread
+                            .id
+                            .created_at()
+                            .to_utc()
+                            .with_timezone(timezone)
+                            .date_naive()
+                    });
+                (
+                    start_of_day_in_timezone(date, timezone)?,
+                    chrono::Utc::now(),
+                )
+            }
+        };
+
+        self.diary
+            .store
+            .insert(&DiaryEntry {
+                thread_id: thread.id.get(),
+                forum_channel_id: target.forum_channel_id.get(),
+                page_id: page_id.clone(),
+                page_url: page_url.clone(),
+                date,
+                created_at,
+                closed_at: None,
+            })
+            .await?;
+
+        info!(
+            thread_id = thread.id.get(),
+            page_id = %page_id,
+            previous_page_id = existing.as_ref().map(|entry| entry.page_id.as_str()),
+            "Diary thread linked to Notion page"
+        );
+
+        command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new().content(format!(
+                    "🔗 この日報スレッドを Notion ページに紐付けました: {}\n同期済みのメッセージは元のページに残ります",
+                    page_url
+                )),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// 日報機能の自己診断を実行し、見つかった問題を返信する。
+    async fn handle_diary_selftest(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        command.defer_ephemeral(&ctx.http).await?;
+
+        let issues = self.run_self_test(&ctx.http).await;
+        let embed = CreateEmbed::new().title("日報機能の自己診断");
+        let embed = if issues.is_empty() {
+            embed
+                .color(0x00ff00)
+                .description("問題は見つかりませんでした")
+        } else {
+            let description = issues
+                .iter()
+                .map(|issue| format!("⚠️ {}", issue))
+                .collect::<Vec<_>>()
+                .join("\n");
+            embed.color(0xffa500).description(description)
+        };
+        command
+            .edit_response(&ctx.http, EditInteractionResponse::new().embed(embed))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 週報ページを作成し、ページへのリンクを返信する。
+    async fn handle_diary_weekly(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let timezone = &self.config.diary.timezone;
+        let date = match subcommand_string_option(command, "date") {
+            Some(date) => match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                Ok(date) => date,
+                Err(_) => {
+                    let response = CreateInteractionResponseMessage::new()
+                        .content("日付は YYYY-MM-DD 形式で指定してください")
+                        .ephemeral(true);
+                    command
+                        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                        .await?;
+                    return Ok(());
+                }
+            },
+            None => chrono::Utc::now().with_timezone(timezone).date_naive() - chrono::Days::new(7),
+        };
+        let period = ReportPeriod::week_of(date);
+        let target = self
+            .resolve_diary_target(&ctx.http, command.channel_id)
+            .await;
+
+        command.defer(&ctx.http).await?;
+
+        let content = match publish_report(
+            &target.notion_client,
+            &self.diary.store,
+            target.forum_channel_id.get(),
+            &period,
+            timezone,
+        )
+        .await?
+        {
+            ReportOutcome::Created(url) => format!("📅 {} を作成しました: {}", period.title(), url),
+            ReportOutcome::AlreadyExists(url) => {
+                format!("📅 {} は作成済みです: {}", period.title(), url)
+            }
+            ReportOutcome::NoEntries => format!(
+                "{}〜{} の日報はありません",
+                period.start.format("%Y-%m-%d"),
+                period.end.format("%Y-%m-%d")
+            ),
+        };
+        command
+            .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn handle_diary_component(
+        &self,
+        ctx: &SerenityContext,
+        component: &ComponentInteraction,
+    ) -> Result<()> {
+        if component.data.custom_id == "diary_close_and_new" {
+            self.handle_diary_close_and_new(ctx, component).await
+        } else if let Some(target) = component
+            .data
+            .custom_id
+            .strip_prefix(DIARY_RETRY_SYNC_BUTTON_PREFIX)
+        {
+            self.handle_diary_retry_sync(ctx, component, target).await
+        } else {
+            Ok(())
+        }
+    }
+
+    /// 同期に失敗したメッセージを再同期する。
+    ///
+    /// 成功した場合は通知メッセージから再同期ボタンを取り除く。
+    async fn handle_diary_retry_sync(
+        &self,
+        ctx: &SerenityContext,
+        component: &ComponentInteraction,
+        target: &str,
+    ) -> Result<()> {
+        let (thread_id, message_id) =
+            parse_retry_sync_target(target).context("Invalid retry sync button")?;
+        let Some(entry) = self.diary.store.get_by_thread(thread_id.get()).await? else {
+            anyhow::bail!("このスレッドは日報スレッドではありません");
+        };
+        let message = thread_id
+            .message(&ctx.http, message_id)
+            .await
+            .context("Failed to fetch message to retry")?;
+        let syncer = MessageSyncer::new(
+            DiscordSource::new(&ctx.http),
+            self.diary.notion_client.as_ref(),
+            &self.diary.store,
+            &self.config.diary.sync_options(&self.config.features),
+            &self.diary.temp_workspace,
+        )?;
+
+        // 同期には時間がかかることがあるため、先に応答しておく
+        component.defer_ephemeral(&ctx.http).await?;
+
+        let (content, resolved) = if self
+            .diary
+            .store
+            .has_blocks_by_message(message_id.get())
+            .await?
+        {
+            ("このメッセージはすでに同期されています", true)
+        } else {
+            match self
+                .sync_message_with_reaction(&ctx.http, &syncer, &entry.page_id, &message)
+                .await
+            {
+                Ok(_) => {
+                    info!(
+                        thread_id = thread_id.get(),
+                        message_id = message_id.get(),
+                        "Message re-synced to Notion"
+                    );
+                    ("✅ 日報ページに同期しました", true)
+                }
+                Err(e) => {
+                    error!(error = %e, "Failed to re-sync message to Notion");
+                    (
+                        "同期できませんでした。しばらく時間をおいてからもう一度お試しください",
+                        false,
+                    )
+                }
+            }
+        };
+
+        component
+            .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+            .await?;
+
+        if resolved {
+            let mut notice = component.message.clone();
+            if let Err(e) = notice
+                .edit(&ctx.http, EditMessage::new().components(vec![]))
+                .await
+            {
+                warn!(error = %e, "Failed to remove retry sync button");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 日報スレッドをクローズして新しいスレッドを作成する。
+    async fn handle_diary_close_and_new(
+        &self,
+        ctx: &SerenityContext,
+        component: &ComponentInteraction,
+    ) -> Result<()> {
+        let channel_id = component.channel_id;
+        let Some(entry) = self.diary.store.get_by_thread(channel_id.get()).await? else {
+            anyhow::bail!("このスレッドは日報スレッドではありません");
+        };
+        let target = self.diary_target_for_entry(&entry);
+
+        // 並行して実行された作成処理と重複しないよう、作成処理全体を直列化する
+        let _creation_guard = self.diary.diary_creation_lock.lock().await;
+
+        let timezone = &self.config.diary.timezone;
+        let today = today_in_timezone(timezone);
+        if let Some(today_entry) = self
+            .diary
+            .store
+            .get_by_date(entry.forum_channel_id, today)
+            .await?
+        {
+            let response = if today_entry.thread_id == channel_id.get() {
+                CreateInteractionResponseMessage::new()
+                    .content("このスレッドが今日の最新の日報です")
+                    .ephemeral(false)
+            } else {
+                CreateInteractionResponseMessage::new()
+                    .content(format!(
+                        "今日の最新の日報はこちらです: <#{}>",
+                        today_entry.thread_id
+                    ))
+                    .ephemeral(false)
+            };
+
+            component
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        }
+
+        let response = CreateInteractionResponseMessage::new()
+            .content("日報スレッドをクローズして新しいスレッドを作成しています...")
+            .ephemeral(false);
+
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        let (new_entry, _) = self
+            .create_diary(&ctx.http, target, today, Some(component.user.id))
+            .await?;
+
+        let mention_message = CreateMessage::new().content(format!(
+            "新しい日報スレッドを作成しました: <#{}>",
+            new_entry.thread_id
+        ));
+        channel_id
+            .send_message(&ctx.http, mention_message)
+            .await
+            .context("Failed to send mention message")?;
+
+        self.update_page_summary(&ctx.http, &entry).await;
+
+        let edit = EditThread::new().archived(true).locked(true);
+        channel_id
+            .edit_thread(&ctx.http, edit)
+            .await
+            .context("Failed to close thread after sending mention message")?;
+        self.diary
+            .store
+            .mark_closed(channel_id.get(), chrono::Utc::now())
+            .await?;
+
+        info!(
+            old_thread_id = channel_id.get(),
+            new_thread_id = new_entry.thread_id,
+            "Diary thread closed by button"
+        );
+
+        Ok(())
+    }
+
+    /// 日報機能の設定と権限を確認し、見つかった問題を利用者向けの文言で返す。
+    ///
+    /// 確認結果はリアクションを付与できるかどうかの判定にも反映する。
+    pub async fn run_self_test(&self, http: &Http) -> Vec<String> {
+        let mut issues = Vec::new();
+
+        match self.refresh_reaction_permission(http).await {
+            Ok(true) => {}
+            Ok(false) => issues.push(
+                "日報フォーラムでリアクションを付与する権限（リアクションの追加・メッセージ履歴を読む）がありません。同期結果は reaction_fallback の設定に従って伝えます".to_string(),
+            ),
+            Err(e) => issues.push(format!("日報フォーラムの権限を確認できませんでした: {:#}", e)),
+        }
+
+        if !self.config.discord.message_content_intent {
+            issues.push(
+                "MESSAGE_CONTENT インテントを要求していないため、bot へのメンション以外のメッセージ本文は日報に同期されません（discord.message_content_intent）".to_string(),
+            );
+        } else if self.diary.message_content_missing.load(Ordering::Relaxed) {
+            issues.push(
+                "メッセージ本文が受け取れていません。開発者ポータルの Bot 設定で Message Content Intent を有効にしてください".to_string(),
+            );
+        }
+
+        for (forum_channel_id, reason) in self.diary.unavailable_forums.lock().await.iter() {
+            issues.push(
+                ForumUnavailableError {
+                    forum_channel_id: ChannelId::new(*forum_channel_id),
+                    reason: *reason,
+                }
+                .to_string(),
+            );
+        }
+
+        issues
+    }
+
+    /// 接続時に MESSAGE_CONTENT インテントが使えるかを確認する。
+    ///
+    /// MESSAGE_CONTENT は特権インテントで、開発者ポータルで有効にしていないとメッセージ本文が空のまま届き、
+    /// 何も同期されない。Ready に含まれるアプリケーションのフラグでポータルの設定を確認する。
+    /// 有効なフラグは bot の規模で異なり、100 サーバー未満では `GATEWAY_MESSAGE_CONTENT_LIMITED`、
+    /// 認証済みの bot では `GATEWAY_MESSAGE_CONTENT` が立つ。
+    pub fn check_message_content_intent(&self, ready: &Ready) {
+        if !self.config.discord.message_content_intent {
+            return;
+        }
+
+        let enabled = ready.application.flags.intersects(
+            ApplicationFlags::GATEWAY_MESSAGE_CONTENT
+                | ApplicationFlags::GATEWAY_MESSAGE_CONTENT_LIMITED,
+        );
+        if !enabled {
+            self.report_missing_message_content(
+                "Message Content Intent is not enabled in the developer portal",
+            );
+        }
+    }
+
+    /// メッセージ本文を受け取れていないことを一度だけ警告し、自己診断で報告できるようにする。
+    fn report_missing_message_content(&self, reason: &str) {
+        if self
+            .diary
+            .message_content_missing
+            .swap(true, Ordering::Relaxed)
+        {
+            return;
+        }
+        error!(
+            reason,
+            "MESSAGE_CONTENT intent appears to be unavailable, so message text arrives empty and nothing is synced. \
+             Enable \"Message Content Intent\" on the Bot page of the Discord developer portal, \
+             or set discord.message_content_intent = false"
+        );
+    }
+
+    /// 自己診断を実行し、結果をログに出力する。
+    pub async fn log_self_test(&self, http: &Http) {
+        let issues = self.run_self_test(http).await;
+        if issues.is_empty() {
+            info!("Self-test passed");
+        }
+        for issue in issues {
+            warn!(issue = %issue, "Self-test found a problem");
+        }
+    }
+
+    /// 日報フォーラムでの bot の権限を取得し、リアクションを付与できるかどうかを更新する。
+    ///
+    /// 複数の日報を運用する場合は、すべてのフォーラムで付与できる場合のみ付与できるとみなす。
+    async fn refresh_reaction_permission(&self, http: &Http) -> Result<bool> {
+        let mut allowed = true;
+        for target in &self.diary.diary_targets {
+            allowed &= self
+                .has_reaction_permission(http, target.forum_channel_id)
+                .await?;
+        }
+        self.diary
+            .reactions_allowed
+            .store(allowed, Ordering::Relaxed);
+
+        Ok(allowed)
+    }
+
+    /// フォーラムでリアクションを付与する権限があるかどうかを返す。
+    async fn has_reaction_permission(
+        &self,
+        http: &Http,
+        forum_channel_id: ChannelId,
+    ) -> Result<bool> {
+        let forum = forum_channel_id
+            .to_channel(http)
+            .await
+            .context("Failed to fetch diary forum channel")?
+            .guild()
+            .context("Diary forum channel is not a guild channel")?;
+        let guild = forum
+            .guild_id
+            .to_partial_guild(http)
+            .await
+            .context("Failed to fetch guild")?;
+        let bot_id = http
+            .get_current_user()
+            .await
+            .context("Failed to fetch current user")?
+            .id;
+        let member = forum
+            .guild_id
+            .member(http, bot_id)
+            .await
+            .context("Failed to fetch bot member")?;
+
+        // スレッドには権限の上書きが無いため、親のフォーラムの権限で判定する
+        Ok(guild
+            .user_permissions_in(&forum, &member)
+            .contains(Permissions::ADD_REACTIONS | Permissions::READ_MESSAGE_HISTORY))
+    }
+
+    /// 日報機能を使う定期実行ジョブの処理を実行する。
+    pub async fn run_diary_job(&self, http: &Http, action: &JobAction) -> Result<()> {
+        match action {
+            JobAction::DiaryNew => {
+                self.create_today_diaries(http).await?;
+            }
+            JobAction::AutoClose => {
+                self.send_auto_close_buttons(http).await?;
+            }
+            JobAction::WeeklyReport => {
+                let today = chrono::Utc::now()
+                    .with_timezone(&self.config.diary.timezone)
+                    .date_naive();
+                self.publish_reports(&ReportPeriod::week_of(today - chrono::Days::new(7)))
+                    .await?;
+            }
+            JobAction::MonthlyReport => {
+                let today = chrono::Utc::now()
+                    .with_timezone(&self.config.diary.timezone)
+                    .date_naive();
+                self.publish_reports(&ReportPeriod::month_of(
+                    ReportPeriod::month_of(today).start - chrono::Days::new(1),
+                ))
+                .await?;
+            }
+            JobAction::DiaryReconcile => {
+                self.reconcile_recent_diaries(http, self.config.diary.reconcile_days.max(1))
+                    .await?;
+            }
+            JobAction::EmojiStats { channel_id, period } => {
+                let embed = self.emoji_stats_embed(*period).await?;
+                ChannelId::new(*channel_id)
+                    .send_message(http, CreateMessage::new().embed(embed))
+                    .await
+                    .context("Failed to send emoji stats")?;
+            }
+            JobAction::Wol { .. } | JobAction::Message { .. } => {
+                anyhow::bail!("{} does not use the diary", action.name())
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 自動クローズのチェックを行い、必要ならボタン付きメッセージを送信する。
+    pub async fn check_auto_close(&self, http: &Http) -> Result<()> {
+        if !self.config.diary.auto_close_enabled {
+            return Ok(());
+        }
+
+        let now = chrono::Utc::now().with_timezone(&self.config.diary.timezone);
+        let today_local = now.date_naive();
+
+        // 指定された時刻以降かチェック
+        if now.hour() < self.config.diary.auto_close_hour {
+            return Ok(());
+        }
+
+        {
+            let last_auto_close_notification_date =
+                self.diary.last_auto_close_notification_date.lock().await;
+            if last_auto_close_notification_date.is_some_and(|date| date == today_local) {
+                return Ok(());
+            }
+        }
+
+        self.send_auto_close_buttons(http).await?;
+        *self.diary.last_auto_close_notification_date.lock().await = Some(today_local);
+
+        Ok(())
+    }
+
+    /// 前日以前の日報スレッドがアクティブなまま残っていれば、クローズボタンを送信する。
+    pub async fn send_auto_close_buttons(&self, http: &Http) -> Result<()> {
+        let today = today_in_timezone(&self.config.diary.timezone);
+
+        for target in &self.diary.diary_targets {
+            // 日報ごとに最新のエントリのみを取得
+            let Some(entry) = self
+                .diary
+                .store
+                .get_latest_entry(target.forum_channel_id.get())
+                .await?
+            else {
+                continue;
+            };
+
+            // 日付が今日より前かチェック
+            if entry.date >= today {
+                continue;
+            }
+
+            // スレッドがまだアクティブかチェック
+            let thread_id = ChannelId::new(entry.thread_id);
+            let Ok(channel) = thread_id.to_channel(http).await else {
+                continue;
+            };
+            let Some(thread) = channel.guild() else {
+                continue;
+            };
+
+            // アーカイブされている、またはロックされている場合はスキップ
+            if thread
+                .thread_metadata
+                .is_some_and(|m| m.archived || m.locked)
+            {
+                continue;
+            }
+
+            // ボタン付きメッセージを送信
+            self.send_auto_close_button(http, thread_id).await?;
+
+            info!(thread_id = entry.thread_id, "Sent auto-close button");
+        }
+
+        Ok(())
+    }
+
+    /// 週報・月報の締めの日であれば、前の期間のレポートページを作成する。
+    ///
+    /// 週報は月曜日に前週分を、月報は 1 日に前月分を、それぞれ設定された時刻以降に作成する。
+    pub async fn check_periodic_reports(&self) -> Result<()> {
+        let diary_config = &self.config.diary;
+        if !diary_config.weekly_report_enabled && !diary_config.monthly_report_enabled {
+            return Ok(());
+        }
+
+        let timezone = &diary_config.timezone;
+        let now = chrono::Utc::now().with_timezone(timezone);
+        let today_local = now.date_naive();
+
+        if now.hour() < diary_config.report_hour {
+            return Ok(());
+        }
+
+        {
+            let last_report_check_date = self.diary.last_report_check_date.lock().await;
+            if last_report_check_date.is_some_and(|date| date == today_local) {
+                return Ok(());
+            }
+        }
+
+        let periods = due_report_periods(
+            today_local,
+            diary_config.weekly_report_enabled,
+            diary_config.monthly_report_enabled,
+        );
+        for period in periods {
+            self.publish_reports(&period).await?;
+        }
+        *self.diary.last_report_check_date.lock().await = Some(today_local);
+
+        Ok(())
+    }
+
+    /// すべての日報について、期間のレポートページを作成する。
+    ///
+    /// 同じタイトルのページがあれば作成しないため、再実行しても重複しない。
+    pub async fn publish_reports(&self, period: &ReportPeriod) -> Result<()> {
+        for target in &self.diary.diary_targets {
+            match publish_report(
+                &target.notion_client,
+                &self.diary.store,
+                target.forum_channel_id.get(),
+                period,
+                &self.config.diary.timezone,
+            )
+            .await?
+            {
+                ReportOutcome::Created(url) => {
+                    info!(
+                        title = %period.title(),
+                        forum_channel_id = target.forum_channel_id.get(),
+                        url = %url,
+                        "Periodic report created"
+                    );
+                }
+                ReportOutcome::AlreadyExists(_) => {}
+                ReportOutcome::NoEntries => {
+                    info!(
+                        title = %period.title(),
+                        forum_channel_id = target.forum_channel_id.get(),
+                        "No diary entries for periodic report"
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 毎時の境目で直近 3 日分の日報スレッドを再同期する。
+    ///
+    /// 起動直後は現在の時間帯だけ記録し、次の時間帯に切り替わるまでは同期しない。
+    pub async fn check_hourly_sync(&self, http: &Http) -> Result<()> {
+        let current_slot = DiaryHourlySyncSlot::current(&self.config.diary.timezone);
+
+        {
+            let mut last_hourly_sync_slot = self.diary.last_hourly_sync_slot.lock().await;
+            match *last_hourly_sync_slot {
+                Some(slot) if slot == current_slot => return Ok(()),
+                None => {
+                    // 起動直後は現在の時間帯だけ記録し、次の時間帯から同期を始める。
+                    *last_hourly_sync_slot = Some(current_slot);
+                    return Ok(());
+                }
+                Some(_) => {}
+            }
+        }
+
+        self.sync_recent_diary_threads(http).await?;
+        *self.diary.last_hourly_sync_slot.lock().await = Some(current_slot);
+
+        info!(
+            date = %current_slot.date,
+            hour = current_slot.hour,
+            "Hourly diary sync finished"
+        );
+
+        Ok(())
+    }
+
+    /// 今日の日報がまだ無い日報について、日報を作成する。
+    pub async fn create_today_diaries(&self, http: &Http) -> Result<()> {
+        let _creation_guard = self.diary.diary_creation_lock.lock().await;
+        let date = today_in_timezone(&self.config.diary.timezone);
+        for target in &self.diary.diary_targets {
+            // 使えなくなったフォーラムは、/diary new での作成に成功するか再起動するまで作成しない
+            if self
+                .diary
+                .unavailable_forums
+                .lock()
+                .await
+                .contains_key(&target.forum_channel_id.get())
+            {
+                warn!(
+                    forum_channel_id = target.forum_channel_id.get(),
+                    "Skipping diary creation because the diary forum is unavailable"
+                );
+                continue;
+            }
+            if self
+                .diary
+                .store
+                .get_by_date(target.forum_channel_id.get(), date)
+                .await?
+                .is_some()
+            {
+                continue;
+            }
+            self.create_diary(http, target, date, None).await?;
+        }
+
+        Ok(())
+    }
+
+    /// 自動クローズのボタン付きメッセージを送信する。
+    async fn send_auto_close_button(&self, http: &Http, thread_id: ChannelId) -> Result<()> {
+        let message = CreateMessage::new()
+            .content("日付が変わりました。このスレッドをクローズして新しい日報を作成しますか？")
+            .components(vec![create_close_and_new_action_row()]);
+
+        thread_id
+            .send_message(http, message)
+            .await
+            .context("Failed to send auto-close button message")?;
+
+        Ok(())
+    }
+
+    /// 直近 3 日分の日報スレッドを順番に再同期する。
+    async fn sync_recent_diary_threads(&self, http: &Http) -> Result<()> {
+        let today = today_in_timezone(&self.config.diary.timezone);
+        // 当日を含めた 3 日分だけを定期同期の対象にする。
+        let start_date = today - chrono::Duration::days(2);
+        let entries = self
+            .discord_entries_in_date_range(start_date, today)
+            .await?;
+
+        let mut checked_messages = 0usize;
+        let mut synced_messages = 0usize;
+        let mut already_synced_messages = 0usize;
+        let mut skipped_messages = 0usize;
+
+        for entry in entries {
+            let thread_id = ChannelId::new(entry.thread_id);
+            match self.sync_missing_messages_in_thread(http, thread_id).await {
+                Ok(report) => {
+                    checked_messages += report.checked_messages;
+                    synced_messages += report.synced_messages;
+                    already_synced_messages += report.already_synced_messages;
+                    skipped_messages += report.skipped_messages;
+                }
+                Err(error) => {
+                    error!(
+                        error = %error,
+                        thread_id = entry.thread_id,
+                        "Failed to sync recent diary thread"
+                    );
+                }
+            }
+        }
+
+        info!(
+            start_date = %start_date,
+            end_date = %today,
+            checked_messages,
+            synced_messages,
+            already_synced_messages,
+            skipped_messages,
+            "Recent diary sync finished"
+        );
+
+        Ok(())
+    }
+
+    /// 指定した日報スレッドを全走査し、未同期メッセージだけを Notion に再同期する。
+    ///
+    /// 同期対象の期間絞り込みはここで持たず、呼び出し側で決める。
+    async fn sync_missing_messages_in_thread(
+        &self,
+        http: &Http,
+        thread_id: ChannelId,
+    ) -> Result<DiaryThreadSyncReport> {
+        let Some(entry) = self.diary.store.get_by_thread(thread_id.get()).await? else {
+            anyhow::bail!("Diary entry not found for thread {}", thread_id.get());
+        };
+        if !self.diary.store.is_sync_enabled(thread_id.get()).await? {
+            info!(
+                thread_id = thread_id.get(),
+                "Diary thread sync is paused, skipping missing message sync"
+            );
+            return Ok(DiaryThreadSyncReport::default());
+        }
+
+        let channel = thread_id
+            .to_channel(http)
+            .await
+            .with_context(|| format!("Failed to fetch thread {}", thread_id.get()))?;
+        let Some(thread) = channel.guild() else {
+            anyhow::bail!("Channel {} is not a guild thread", thread_id.get());
+        };
+        if thread.kind != ChannelType::PublicThread {
+            anyhow::bail!("Channel {} is not a public thread", thread_id.get());
+        }
+
+        let syncer = MessageSyncer::new(
+            DiscordSource::new(http),
+            self.diary.notion_client.as_ref(),
+            &self.diary.store,
+            &self.config.diary.sync_options(&self.config.features),
+            &self.diary.temp_workspace,
+        )?;
+        let mut before = None;
+        let mut pending_messages = Vec::new();
+        let mut report = DiaryThreadSyncReport::default();
+
+        loop {
+            let mut request = GetMessages::new().limit(DIARY_THREAD_SYNC_BATCH_SIZE);
+            if let Some(before_message_id) = before {
+                request = request.before(before_message_id);
+            }
+
+            let messages = thread_id.messages(http, request).await.with_context(|| {
+                format!("Failed to fetch messages for thread {}", thread_id.get())
+            })?;
+            if messages.is_empty() {
+                break;
+            }
+
+            before = messages.last().map(|message| message.id);
+
+            for message in messages {
+                if message.author.bot {
+                    continue;
+                }
+
+                pending_messages.push(message);
+            }
+        }
+
+        pending_messages.reverse();
+        report.checked_messages = pending_messages.len();
+
+        for message in pending_messages {
+            if self
+                .diary
+                .store
+                .has_blocks_by_message(message.id.get())
+                .await?
+            {
+                report.already_synced_messages += 1;
+                continue;
+            }
+
+            if !self.is_sync_requested(&message) {
+                report.skipped_messages += 1;
+                continue;
+            }
+
+            let (synced, _) = self
+                .sync_message_with_reaction(http, &syncer, &entry.page_id, &message)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to sync missing message {} in thread {}",
+                        message.id.get(),
+                        thread_id.get()
+                    )
+                })?;
+
+            if synced {
+                report.synced_messages += 1;
+            } else {
+                report.skipped_messages += 1;
+            }
+        }
+
+        info!(
+            thread_id = thread_id.get(),
+            checked_messages = report.checked_messages,
+            synced_messages = report.synced_messages,
+            already_synced_messages = report.already_synced_messages,
+            skipped_messages = report.skipped_messages,
+            "Checked diary thread for missing message sync"
+        );
+
+        Ok(report)
+    }
+
+    /// 直近 `days` 日間の日報ページのブロックとストアの対応を突き合わせる。
+    ///
+    /// 起動時と定期実行ジョブから呼ばれる。失敗したスレッドがあってもほかのスレッドは続け、最後にエラーを返す。
+    pub async fn reconcile_recent_diaries(&self, http: &Http, days: u32) -> Result<()> {
+        let entries = self.recent_diary_entries(days).await?;
+        let report = self.reconcile_diary_entries(http, &entries).await;
+        info!(
+            days,
+            checked_threads = report.checked_threads,
+            checked_blocks = report.checked_blocks,
+            removed_blocks = report.removed_blocks,
+            orphan_blocks = report.orphan_blocks,
+            failed_threads = report.failed_threads,
+            "Reconciled recent diary pages"
+        );
+        if report.failed_threads > 0 {
+            anyhow::bail!(
+                "Failed to reconcile {} of {} diary threads",
+                report.failed_threads,
+                entries.len()
+            );
+        }
+        Ok(())
+    }
+
+    /// 直近 `days` 日間（今日を含む）の日報エントリを取得する。
+    async fn recent_diary_entries(&self, days: u32) -> Result<Vec<DiaryEntry>> {
+        let today = today_in_timezone(&self.config.diary.timezone);
+        let start_date = today - chrono::Duration::days(i64::from(days.max(1)) - 1);
+        self.diary
+            .store
+            .get_entries_in_date_range(start_date, today)
+            .await
+    }
+
+    /// 日報エントリごとにページのブロックとストアの対応を突き合わせ、結果を集計する。
+    async fn reconcile_diary_entries(
+        &self,
+        http: &Http,
+        entries: &[DiaryEntry],
+    ) -> DiaryReconcileReport {
+        let mut report = DiaryReconcileReport::default();
+        for entry in entries {
+            match self.reconcile_diary_thread(http, entry).await {
+                Ok(result) => {
+                    report.checked_threads += 1;
+                    report.checked_blocks += result.checked_blocks;
+                    report.removed_blocks += result.removed_blocks.len();
+                    report.orphan_blocks += result.orphan_blocks.len();
+                }
+                Err(e) => {
+                    error!(error = ?e, thread_id = entry.thread_id, "Failed to reconcile diary thread");
+                    report.failed_threads += 1;
+                }
+            }
+        }
+        report
+    }
+
+    /// 日報スレッドの Notion ページのブロックとストアの対応を突き合わせる。
+    async fn reconcile_diary_thread(
+        &self,
+        http: &Http,
+        entry: &DiaryEntry,
+    ) -> Result<ReconcileResult> {
+        let syncer = MessageSyncer::new(
+            DiscordSource::new(http),
+            self.diary_target_for_entry(entry).notion_client.as_ref(),
+            &self.diary.store,
+            &self.config.diary.sync_options(&self.config.features),
+            &self.diary.temp_workspace,
+        )?;
+        let result = syncer
+            .reconcile_page(entry.thread_id, &entry.page_id)
+            .await
+            .with_context(|| format!("Failed to reconcile diary thread {}", entry.thread_id))?;
+        if !result.removed_blocks.is_empty() || !result.orphan_blocks.is_empty() {
+            info!(
+                thread_id = entry.thread_id,
+                checked_blocks = result.checked_blocks,
+                removed_blocks = result.removed_blocks.len(),
+                orphan_blocks = result.orphan_blocks.len(),
+                "Reconciled diary page"
+            );
+        }
+        Ok(result)
+    }
+
+    /// 1 件の日報メッセージを Notion に同期し、結果に応じたリアクションを付与する。
+    ///
+    /// すべて同期できた場合は同期済み、一部の添付ファイルを同期しなかった・警告付きで同期した場合は
+    /// 部分同期、同期に失敗した場合は同期失敗のリアクションを付与する。
+    async fn sync_message_with_reaction(
+        &self,
+        http: &Http,
+        syncer: &MessageSyncer<'_>,
+        page_id: &str,
+        message: &Message,
+    ) -> Result<(bool, usize)> {
+        let result = self.sync_source_message(syncer, page_id, message).await;
+        self.react_to_sync_result(http, message, result).await
+    }
+
+    /// メッセージを日報ページに同期する。
+    ///
+    /// 個人用のメモは書き出しを取り除き、日報ページの代わりに投稿者の個人用のページに同期する。
+    async fn sync_source_message(
+        &self,
+        syncer: &MessageSyncer<'_>,
+        page_id: &str,
+        message: &Message,
+    ) -> Result<SyncResult> {
+        let mut source = source_message(message);
+        let Some((private_notes, body)) = self.diary.private_notes.as_ref().and_then(|notes| {
+            let body = notes.strip_prefix(&source.content)?.to_string();
+            Some((notes, body))
+        }) else {
+            return syncer.sync_message(page_id, &source).await;
+        };
+        source.content = body;
+
+        let diary_config = &self.config.diary;
+        let local_date = source
+            .posted_at
+            .with_timezone(&diary_config.timezone)
+            .date_naive();
+        let title = render_title(&diary_config.page_title_format, local_date);
+        let page_id = private_notes
+            .page_id(source.author_id, &title, local_date)
+            .await?;
+        syncer.sync_message(&page_id, &source).await
+    }
+
+    /// 同期結果に応じたリアクションをメッセージに付与する。
+    ///
+    /// # Returns
+    /// 同期されたかどうかと作成されたブロック数（同期に失敗した場合はそのエラー）
+    async fn react_to_sync_result(
+        &self,
+        http: &Http,
+        message: &Message,
+        result: Result<SyncResult>,
+    ) -> Result<(bool, usize)> {
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                self.add_sync_reaction(http, message, &self.config.diary.failed_sync_reaction)
+                    .await;
+                return Err(e);
+            }
+        };
+
+        if !result.synced {
+            return Ok((false, result.block_count()));
+        }
+
+        if !self.diary.reactions_allowed.load(Ordering::Relaxed) {
+            self.report_reactionless_sync(http, message.channel_id)
+                .await;
+            return Ok((true, result.block_count()));
+        }
+
+        let reaction = if result.is_partial() {
+            &self.config.diary.partial_sync_reaction
+        } else {
+            &self.config.diary.sync_reaction
+        };
+        self.add_sync_reaction(http, message, reaction).await;
+        self.remove_failed_sync_reaction(http, message).await;
+
+        Ok((true, result.block_count()))
+    }
+
+    /// 以前の同期失敗で付与したリアクションを取り除く。
+    async fn remove_failed_sync_reaction(&self, http: &Http, message: &Message) {
+        let reaction = ReactionType::Unicode(self.config.diary.failed_sync_reaction.clone());
+        let reacted = message
+            .reactions
+            .iter()
+            .any(|r| r.me && r.reaction_type == reaction);
+        if !reacted {
+            return;
+        }
+
+        if let Err(e) = message.delete_reaction(http, None, reaction).await {
+            warn!(error = %e, "Failed to remove failed sync reaction");
+        }
+    }
+
+    /// 日報スレッドに投稿されたメッセージを Notion に同期する。
+    ///
+    /// 日報スレッド以外のメッセージは無視する。失敗した場合は投稿者に通知する。
+    async fn sync_thread_message(&self, ctx: &SerenityContext, message: &Message) {
+        let is_private_note = self
+            .diary
+            .private_notes
+            .as_ref()
+            .is_some_and(|notes| notes.strip_prefix(&message.content).is_some());
+        let result = if is_private_note {
+            match self.sync_private_note(ctx, message).await {
+                Some(result) => result,
+                None => return,
+            }
+        } else {
+            match self
+                .handle_message_event(ctx, MessageEvent::Created(source_message(message)))
+                .await
+            {
+                Some(Ok(EventOutcome::Created(result))) => Ok(result),
+                Some(Ok(_)) | None => return,
+                Some(Err(e)) => Err(e),
+            }
+        };
+
+        match self.react_to_sync_result(&ctx.http, message, result).await {
+            Ok((true, block_count)) => {
+                info!(
+                    thread_id = message.channel_id.get(),
+                    message_id = message.id.get(),
+                    blocks = block_count,
+                    "Message synced to Notion"
+                );
+                if let Some(email) = &self.diary.email_notifier {
+                    email.record_sync_success();
+                }
+            }
+            Ok(_) => {
+                // スキップ (空メッセージなど)
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to sync message to Notion");
+                self.notify_sync_failure(&ctx.http, message).await;
+                if let Some(email) = &self.diary.email_notifier {
+                    email.record_sync_failure(&e).await;
+                }
+            }
+        }
+    }
+
+    /// 日報スレッドに投稿された個人用のメモを同期する。
+    ///
+    /// 同期先が日報ページではないため、同期エンジンのイベント処理を通さずに同期する。
+    /// 日報スレッドでない場合は None を返す。
+    async fn sync_private_note(
+        &self,
+        ctx: &SerenityContext,
+        message: &Message,
+    ) -> Option<Result<SyncResult>> {
+        let entry = match self
+            .diary
+            .store
+            .get_by_thread(message.channel_id.get())
+            .await
+        {
+            Ok(entry) => entry?,
+            Err(e) => return Some(Err(e)),
+        };
+        let syncer = match MessageSyncer::new(
+            DiscordSource::new(&ctx.http),
+            self.diary.notion_client.as_ref(),
+            &self.diary.store,
+            &self.config.diary.sync_options(&self.config.features),
+            &self.diary.temp_workspace,
+        ) {
+            Ok(syncer) => syncer,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(
+            self.sync_source_message(&syncer, &entry.page_id, message)
+                .await,
+        )
+    }
+
+    /// Discord のメッセージのイベントを同期エンジンに渡して処理する。
+    ///
+    /// スレッド以外のチャンネルのイベントや、シンクロナイザーを作成できなかった場合は None を返す。
+    async fn handle_message_event(
+        &self,
+        ctx: &SerenityContext,
+        event: MessageEvent,
+    ) -> Option<Result<EventOutcome>> {
+        // スレッドでない場合は無視
+        let channel = ChannelId::new(event.thread_id())
+            .to_channel(ctx)
+            .await
+            .ok()?;
+        let guild_channel = channel.guild()?;
+        if guild_channel.kind != ChannelType::PublicThread {
+            return None;
+        }
+
+        let syncer = match MessageSyncer::new(
+            DiscordSource::new(&ctx.http),
+            self.diary.notion_client.as_ref(),
+            &self.diary.store,
+            &self.config.diary.sync_options(&self.config.features),
+            &self.diary.temp_workspace,
+        ) {
+            Ok(s) => s,
+            Err(e) => {
+                error!(error = %e, "Failed to create message syncer");
+                return None;
+            }
+        };
+        Some(syncer.handle_event(&event).await)
+    }
+
+    /// opt-in モードで同期の契機にするリアクションを返す。
+    fn sync_trigger_reaction(&self) -> ReactionType {
+        ReactionType::Unicode(self.config.diary.sync_trigger_reaction.clone())
+    }
+
+    /// メッセージが同期の対象として選ばれているかどうかを返す。
+    ///
+    /// opt-in モードでは同期の契機にするリアクションが付いたメッセージだけを対象にする。
+    fn is_sync_requested(&self, message: &Message) -> bool {
+        match self.config.diary.sync_mode {
+            SyncMode::All => true,
+            SyncMode::Reaction => {
+                let trigger = self.sync_trigger_reaction();
+                message
+                    .reactions
+                    .iter()
+                    .any(|reaction| reaction.reaction_type == trigger)
+            }
+        }
+    }
+
+    /// 同期に失敗したメッセージの投稿者に、再同期ボタン付きで通知する。
+    ///
+    /// 通知方法は日報設定の `sync_failure_notification` に従う。
+    async fn notify_sync_failure(&self, http: &Http, message: &Message) {
+        let action_row = create_retry_sync_action_row(message.channel_id, message.id);
+        let result = match self.config.diary.sync_failure_notification {
+            SyncFailureNotification::Off => return,
+            SyncFailureNotification::Reply => {
+                let builder = CreateMessage::new()
+                    .content(SYNC_FAILURE_NOTICE)
+                    .reference_message(message)
+                    .components(vec![action_row]);
+                message.channel_id.send_message(http, builder).await
+            }
+            SyncFailureNotification::Dm => {
+                let builder = CreateMessage::new()
+                    .content(format!("{}\n{}", SYNC_FAILURE_NOTICE, message.link()))
+                    .components(vec![action_row]);
+                message.author.direct_message(http, builder).await
+            }
+        };
+
+        if let Err(e) = result {
+            warn!(
+                error = %e,
+                message_id = message.id.get(),
+                "Failed to notify author of sync failure"
+            );
+        }
+    }
+
+    /// リアクションを付与できない場合に、設定に従ってスレッドへ同期件数を報告する。
+    async fn report_reactionless_sync(&self, http: &Http, thread_id: ChannelId) {
+        if self.config.diary.reaction_fallback == ReactionFallback::Silent {
+            return;
+        }
+
+        let interval = self.config.diary.reaction_fallback_interval.max(1);
+        {
+            let mut counts = self.diary.reactionless_sync_counts.lock().await;
+            let count = counts.entry(thread_id.get()).or_default();
+            *count += 1;
+            if *count < interval {
+                return;
+            }
+            *count = 0;
+        }
+
+        let builder = CreateMessage::new().content(format!(
+            "✅ {}件のメッセージを Notion に同期しました（リアクションを付与する権限が無いため、まとめてお知らせしています）",
+            interval
+        ));
+        if let Err(e) = thread_id.send_message(http, builder).await {
+            warn!(
+                error = %e,
+                thread_id = thread_id.get(),
+                "Failed to report synced messages"
+            );
+        }
+    }
+
+    /// 同期済みのメッセージにリアクションを付与する。
+    ///
+    /// 一時的な失敗は日報設定のリトライ方針に従って再試行する。
+    /// 権限不足で失敗した場合は、以降のリアクションの付与を止める。
+    async fn add_sync_reaction(&self, http: &Http, message: &Message, reaction: &str) {
+        if !self.diary.reactions_allowed.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let reaction = &ReactionType::Unicode(reaction.to_string());
+        let result = self
+            .config
+            .diary
+            .retry_policy()
+            .run("add sync reaction", || async move {
+                message
+                    .react(http, reaction.clone())
+                    .await
+                    .map(|_| ())
+                    .map_err(classify_serenity_error)
+            })
+            .await;
+        if let Err(error) = result {
+            if is_missing_permissions(&error) {
+                // 以降の同期で同じエラーを繰り返さないよう、自己診断で再確認するまで付与を止める
+                self.diary.reactions_allowed.store(false, Ordering::Relaxed);
+                warn!("Missing permission to add reactions in diary threads; falling back");
+                return;
+            }
+            error!(error = %error, "Failed to add sync reaction");
+        }
+    }
+
+    /// `[diary]` で設定した日報を返す。
+    fn primary_diary_target(&self) -> &DiaryTarget {
+        &self.diary.diary_targets[0]
+    }
+
+    /// フォーラムチャンネル ID に対応する日報を返す。
+    fn find_diary_target(&self, forum_channel_id: ChannelId) -> Option<&DiaryTarget> {
+        self.diary
+            .diary_targets
+            .iter()
+            .find(|target| target.forum_channel_id == forum_channel_id)
+    }
+
+    /// 指定した日付範囲に含まれる、Discord のフォーラムスレッドの日報エントリを古い順で取得する。
+    ///
+    /// Matrix のルームの日報エントリは含めない。
+    async fn discord_entries_in_date_range(
+        &self,
+        start_date: chrono::DateTime<chrono::Utc>,
+        end_date: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<DiaryEntry>> {
+        let entries = self
+            .diary
+            .store
+            .get_entries_in_date_range(start_date, end_date)
+            .await?;
+        Ok(entries
+            .into_iter()
+            .filter(|entry| {
+                self.diary
+                    .diary_targets
+                    .iter()
+                    .any(|target| target.forum_channel_id.get() == entry.forum_channel_id)
+            })
+            .collect())
+    }
+
+    /// 日報エントリが属する日報を返す（設定から削除された場合は `[diary]` の日報）。
+    fn diary_target_for_entry(&self, entry: &DiaryEntry) -> &DiaryTarget {
+        self.find_diary_target(ChannelId::new(entry.forum_channel_id))
+            .unwrap_or_else(|| self.primary_diary_target())
+    }
+
+    /// コマンドを実行したチャンネルから対象の日報を決める。
+    ///
+    /// 日報フォーラムまたはそのスレッドで実行された場合はその日報を、それ以外は `[diary]` の日報を返す。
+    async fn resolve_diary_target(&self, http: &Http, channel_id: ChannelId) -> &DiaryTarget {
+        if let Some(target) = self.find_diary_target(channel_id) {
+            return target;
+        }
+
+        let parent_id = match channel_id.to_channel(http).await {
+            Ok(channel) => channel.guild().and_then(|channel| channel.parent_id),
+            Err(e) => {
+                warn!(
+                    error = %e,
+                    channel_id = channel_id.get(),
+                    "Failed to fetch channel to resolve diary"
+                );
+                None
+            }
+        };
+        parent_id
+            .and_then(|parent_id| self.find_diary_target(parent_id))
+            .unwrap_or_else(|| self.primary_diary_target())
+    }
+
+    /// 指定した日報に、指定した日付の日報（Notion ページとフォーラムスレッド）を作成して保存する。
+    ///
+    /// 同じタイトルの Notion ページが既にあればそのページを使う。
+    /// 呼び出し側で `diary_creation_lock` を取得しておくこと。
+    ///
+    /// # Returns
+    /// 保存した日報エントリと、既存の Notion ページを使ったかどうか
+    async fn create_diary(
+        &self,
+        http: &Http,
+        target: &DiaryTarget,
+        date: chrono::DateTime<chrono::Utc>,
+        creator: Option<UserId>,
+    ) -> Result<(DiaryEntry, bool)> {
+        let diary_config = &self.config.diary;
+
+        // 設定されたタイムゾーンでの日付からページとスレッドのタイトルを作る
+        let local_date = date.with_timezone(&diary_config.timezone).date_naive();
+        let page_title = render_title(&diary_config.page_title_format, local_date);
+        let thread_title = render_title(&diary_config.thread_title_format, local_date);
+
+        // フォーラムが削除されたり見えなくなったりしていれば、Notion ページを作る前に止める
+        if let Err(error) = target.forum_channel_id.to_channel(http).await
+            && let Some(reason) = forum_unavailable_reason(&error)
+        {
+            let error = self
+                .pause_unavailable_forum(http, target.forum_channel_id, reason)
+                .await;
+            return Err(error.into());
+        }
+
+        // 既存の Notion ページを検索、なければ新規作成
+        let (page_id, page_url, reused) = if let Some((page_id, page_url)) = target
+            .notion_client
+            .find_diary_page_by_title(&page_title)
+            .await
+            .context("Notion ページの検索に失敗しました")?
+        {
+            info!(date = %date, page_id = %page_id, "Found existing Notion page");
+            (page_id, page_url, true)
+        } else {
+            info!(title = %page_title, "Creating new Notion page");
+            let (page_id, page_url) = self
+                .create_diary_page_from_template(target, &page_title, local_date)
+                .await
+                .context("Notion ページの作成に失敗しました")?;
+            (page_id, page_url, false)
+        };
+
+        // Discord フォーラムにスレッドを作成（失敗時は作成したページをロールバック）
+        let thread = self
+            .create_diary_forum_post(
+                http,
+                target,
+                &thread_title,
+                DiaryPage {
+                    title: &page_title,
+                    id: &page_id,
+                    url: &page_url,
+                    created: !reused,
+                },
+            )
+            .await?;
+
+        self.ensure_close_and_new_button(http, thread.id).await?;
+        self.add_diary_thread_members(http, thread.id, creator)
+            .await;
+
+        // 紐付け情報を保存
+        let entry = DiaryEntry {
+            thread_id: thread.id.get(),
+            forum_channel_id: target.forum_channel_id.get(),
+            page_id,
+            page_url,
+            date,
+            created_at: chrono::Utc::now(),
+            closed_at: None,
+        };
+        self.diary.store.insert(&entry).await?;
+
+        info!(date = %date, thread_id = entry.thread_id, reused, "Diary created");
+
+        if self
+            .diary
+            .unavailable_forums
+            .lock()
+            .await
+            .remove(&entry.forum_channel_id)
+            .is_some()
+        {
+            info!(
+                forum_channel_id = entry.forum_channel_id,
+                "Diary forum is available again; resuming automatic diary creation"
+            );
+        }
+
+        Ok((entry, reused))
+    }
+
+    /// 日報フォーラムを使えなくなったことを記録し、日報の自動作成を止める。
+    ///
+    /// 管理者への通知は、フォーラムごとに最初に検出したときだけ送る。
+    async fn pause_unavailable_forum(
+        &self,
+        http: &Http,
+        forum_channel_id: ChannelId,
+        reason: ForumUnavailableReason,
+    ) -> ForumUnavailableError {
+        let error = ForumUnavailableError {
+            forum_channel_id,
+            reason,
+        };
+        if self
+            .diary
+            .unavailable_forums
+            .lock()
+            .await
+            .insert(forum_channel_id.get(), reason)
+            .is_some()
+        {
+            return error;
+        }
+
+        error!(
+            forum_channel_id = forum_channel_id.get(),
+            reason = ?reason,
+            "Diary forum is unavailable; pausing automatic diary creation"
+        );
+
+        let embed = CreateEmbed::new()
+            .title("Diary Forum Unavailable")
+            .color(0xff0000)
+            .description(error.to_string());
+        for admin in &self.config.discord.admins {
+            if let Err(e) = UserId::new(*admin)
+                .direct_message(http, CreateMessage::new().embed(embed.clone()))
+                .await
+            {
+                warn!(
+                    error = %e,
+                    user_id = admin,
+                    "Failed to send diary forum unavailable alert"
+                );
+            }
+        }
+
+        error
+    }
+
+    /// キーワード付きメッセージを今日の日報スレッドに転記し、Notion に同期する。
+    ///
+    /// 今日の日報がなければ作成する。同期には元のメッセージの投稿者と添付ファイルを使い、
+    /// 同期の結果を示すリアクションは転記したメッセージに付ける。
+    /// 転記に失敗した場合はエラーを返し、同期に失敗した場合はリアクションで示す。
+    async fn repost_to_today_diary(
+        &self,
+        ctx: &SerenityContext,
+        message: &Message,
+        body: &str,
+    ) -> Result<()> {
+        let entry = {
+            // 並行して実行された作成処理と重複しないよう、作成処理全体を直列化する
+            let _creation_guard = self.diary.diary_creation_lock.lock().await;
+
+            // 転記先は `[diary]` で設定した日報とする
+            let target = self.primary_diary_target();
+            let today = today_in_timezone(&self.config.diary.timezone);
+            match self
+                .diary
+                .store
+                .get_by_date(target.forum_channel_id.get(), today)
+                .await?
+            {
+                Some(entry) => entry,
+                None => {
+                    self.create_diary(&ctx.http, target, today, Some(message.author.id))
+                        .await?
+                        .0
+                }
+            }
+        };
+
+        let thread_id = ChannelId::new(entry.thread_id);
+        let repost = CreateMessage::new().content(format!(
+            "{}\n-# <@{}> より {}",
+            truncate_chars(body, KEYWORD_REPOST_MAX_CHARS),
+            message.author.id,
+            message.link()
+        ));
+        let reposted = thread_id
+            .send_message(&ctx.http, repost)
+            .await
+            .context("Failed to repost keyword message to diary thread")?;
+
+        // 転記したメッセージとして、元のメッセージの投稿者・本文・添付ファイルを同期する
+        let mut synced_message = message.clone();
+        synced_message.id = reposted.id;
+        synced_message.channel_id = reposted.channel_id;
+        synced_message.timestamp = reposted.timestamp;
+        synced_message.content = body.to_string();
+        synced_message.reactions.clear();
+
+        let syncer = MessageSyncer::new(
+            DiscordSource::new(&ctx.http),
+            self.diary.notion_client.as_ref(),
+            &self.diary.store,
+            &self.config.diary.sync_options(&self.config.features),
+            &self.diary.temp_workspace,
+        )?;
+        if let Err(e) = self
+            .sync_message_with_reaction(&ctx.http, &syncer, &entry.page_id, &synced_message)
+            .await
+        {
+            // 転記したメッセージは bot の投稿のため、再同期ボタンでの通知はしない
+            error!(error = %e, "Failed to sync reposted keyword message to Notion");
+        }
+
+        info!(
+            message_id = message.id.get(),
+            thread_id = entry.thread_id,
+            "Keyword message reposted to diary"
+        );
+
+        Ok(())
+    }
+
+    /// 日報ページを作成し、設定された雛形ブロックを挿入する。
+    ///
+    /// 雛形の挿入に失敗してもページの作成は成功として扱う。
+    async fn create_diary_page_from_template(
+        &self,
+        target: &DiaryTarget,
+        title: &str,
+        date: NaiveDate,
+    ) -> Result<(String, String)> {
+        create_templated_page(
+            &target.notion_client,
+            title,
+            date,
+            self.config.features.is_enabled(Feature::PageSummary),
+            self.diary.page_template.as_ref(),
+        )
+        .await
+    }
+
+    /// 日報ページの冒頭のサマリーを、スレッドの同期結果と参加者で更新する。
+    ///
+    /// サマリーのブロックは記録したブロック ID で探し、記録が無ければページ内のサマリーを探して記録する。
+    /// サマリーが無いページ（機能を有効にする前に作成したページなど）では、同期したメッセージの前に挿入する。
+    /// 更新に失敗してもクローズ処理は続けるため、エラーはログに出力するだけにする。
+    async fn update_page_summary(&self, http: &Http, entry: &DiaryEntry) {
+        if !self.config.features.is_enabled(Feature::PageSummary) {
+            return;
+        }
+
+        if let Err(e) = self.update_page_summary_inner(http, entry).await {
+            warn!(
+                error = %e,
+                thread_id = entry.thread_id,
+                page_id = %entry.page_id,
+                "Failed to update diary page summary"
+            );
+        }
+    }
+
+    async fn update_page_summary_inner(&self, http: &Http, entry: &DiaryEntry) -> Result<()> {
+        let stats = self
+            .diary
+            .store
+            .get_thread_block_stats(entry.thread_id)
+            .await?;
+        let summary = PageSummary {
+            message_count: stats.message_count,
+            image_count: stats.image_count,
+            link_count: stats.link_count,
+            participants: self
+                .collect_thread_participants(http, ChannelId::new(entry.thread_id))
+                .await?,
+            time_totals: time_totals(&self.diary.store.get_time_entries(entry.thread_id).await?),
+        };
+
+        let block_id = match self.diary.store.get_summary_block(entry.thread_id).await? {
+            Some(block_id) => block_id,
+            None => {
+                let page_blocks = self.diary.notion_client.list_blocks(&entry.page_id).await?;
+                match page_blocks
+                    .iter()
+                    .filter(|block| is_summary_block(block))
+                    .find_map(|block| block["id"].as_str())
+                {
+                    Some(block_id) => block_id.to_string(),
+                    None => self.insert_page_summary(entry, &page_blocks).await?,
+                }
+            }
+        };
+        self.diary
+            .notion_client
+            .update_callout_block(&block_id, summary.rich_text())
+            .await?;
+        self.diary
+            .store
+            .set_summary_block(entry.thread_id, &block_id)
+            .await?;
+
+        info!(
+            thread_id = entry.thread_id,
+            messages = summary.message_count,
+            participants = summary.participants.len(),
+            "Diary page summary updated"
+        );
+
+        Ok(())
+    }
+
+    /// サマリーが無い日報ページにサマリーの枠を挿入し、そのブロック ID を返す。
+    ///
+    /// ページテンプレートの後、同期したメッセージの前に挿入する。
+    /// 直前に置けるブロックが無い場合はページの末尾に追加する。
+    async fn insert_page_summary(
+        &self,
+        entry: &DiaryEntry,
+        page_blocks: &[serde_json::Value],
+    ) -> Result<String> {
+        let tracked_blocks = self
+            .diary
+            .store
+            .get_blocks_by_thread(entry.thread_id)
+            .await?;
+        let blocks = vec![summary_placeholder_block()];
+        let block_ids = match summary_anchor(page_blocks, &tracked_blocks) {
+            Some(anchor) => {
+                self.diary
+                    .notion_client
+                    .insert_blocks_after(&entry.page_id, anchor, blocks)
+                    .await?
+            }
+            None => {
+                info!(
+                    page_id = %entry.page_id,
+                    "No block precedes the synced messages, appending the summary to the end"
+                );
+                self.diary
+                    .notion_client
+                    .append_blocks(&entry.page_id, blocks)
+                    .await?
+            }
+        };
+        block_ids
+            .into_iter()
+            .next()
+            .context("No block was created for the summary")
+    }
+
+    /// スレッドに投稿したユーザー（bot を除く）の表示名を、名前順で重複なく返す。
+    async fn collect_thread_participants(
+        &self,
+        http: &Http,
+        thread_id: ChannelId,
+    ) -> Result<Vec<String>> {
+        let mut participants = HashMap::new();
+        let mut before = None;
+
+        loop {
+            let mut request = GetMessages::new().limit(DIARY_THREAD_SYNC_BATCH_SIZE);
+            if let Some(before_message_id) = before {
+                request = request.before(before_message_id);
+            }
+
+            let messages = thread_id.messages(http, request).await.with_context(|| {
+                format!("Failed to fetch messages for thread {}", thread_id.get())
+            })?;
+            let Some(last) = messages.last() else {
+                break;
+            };
+            before = Some(last.id);
+
+            for message in messages.into_iter().filter(|message| !message.author.bot) {
+                participants
+                    .entry(message.author.id)
+                    .or_insert_with(|| message.author.display_name().to_string());
+            }
+        }
+
+        let mut participants: Vec<String> = participants.into_values().collect();
+        participants.sort();
+        Ok(participants)
+    }
+
+    /// 日報フォーラムにスレッドを作成する。
+    ///
+    /// スレッド作成に失敗した場合、このリクエストで作成した Notion ページ（`page.created` が true）は
+    /// 孤立しないようアーカイブし、どちらの処理が成功したかをエラーメッセージで報告する。
+    async fn create_diary_forum_post(
+        &self,
+        http: &Http,
+        target: &DiaryTarget,
+        thread_title: &str,
+        page: DiaryPage<'_>,
+    ) -> Result<GuildChannel> {
+        let DiaryPage {
+            title: page_title,
+            id: page_id,
+            url: page_url,
+            created: page_created,
+        } = page;
+        let forum_channel = target.forum_channel_id;
+        let initial_message = create_diary_thread_initial_message(
+            page_title,
+            page_url,
+            self.config.diary.compact_notion_link,
+        );
+        let forum_post = CreateForumPost::new(thread_title, initial_message);
+
+        let error = match forum_channel.create_forum_post(http, forum_post).await {
+            Ok(thread) => return Ok(thread),
+            Err(error) => match forum_unavailable_reason(&error) {
+                Some(reason) => self
+                    .pause_unavailable_forum(http, forum_channel, reason)
+                    .await
+                    .into(),
+                None => anyhow::Error::from(error),
+            },
+        };
+
+        if !page_created {
+            return Err(error.context(format!(
+                "フォーラムスレッドの作成に失敗しました（既存の Notion ページは変更していません: {}）",
+                page_url
+            )));
+        }
+
+        match target
+            .notion_client
+            .archive_diary_page(page_title, page_id)
+            .await
+        {
+            Ok(()) => {
+                warn!(
+                    page_id = %page_id,
+                    "Archived Notion page after failing to create forum thread"
+                );
+                Err(error.context(
+                    "フォーラムスレッドの作成に失敗したため、作成した Notion ページをアーカイブしました",
+                ))
+            }
+            Err(archive_error) => {
+                error!(
+                    error = %archive_error,
+                    page_id = %page_id,
+                    "Failed to archive orphaned Notion page"
+                );
+                Err(error.context(format!(
+                    "フォーラムスレッドの作成に失敗しました。Notion ページは作成済みですがアーカイブにも失敗したため、手動で確認してください: {}",
+                    page_url
+                )))
+            }
+        }
+    }
+
+    /// 設定されたユーザーを新しい日報スレッドに追加し、必要ならメンションで知らせる。
+    ///
+    /// 定期実行ジョブで作成した場合など、作成者がいない場合は `creator` に `None` を渡す。
+    /// 追加や通知に失敗してもスレッドの作成は成功として扱う。
+    async fn add_diary_thread_members(
+        &self,
+        http: &Http,
+        thread_id: ChannelId,
+        creator: Option<UserId>,
+    ) {
+        let diary_config = &self.config.diary;
+        let mut members: Vec<UserId> = diary_config
+            .thread_members
+            .iter()
+            .map(|id| UserId::new(*id))
+            .collect();
+        if diary_config.add_thread_creator
+            && let Some(creator) = creator
+            && !members.contains(&creator)
+        {
+            members.push(creator);
+        }
+
+        let mut added = Vec::new();
+        for user_id in members {
+            match thread_id.add_thread_member(http, user_id).await {
+                Ok(()) => added.push(user_id),
+                Err(e) => warn!(
+                    error = %e,
+                    thread_id = thread_id.get(),
+                    user_id = user_id.get(),
+                    "Failed to add diary thread member"
+                ),
+            }
+        }
+
+        if !diary_config.thread_rollcall || added.is_empty() {
+            return;
+        }
+
+        let mentions = added
+            .iter()
+            .map(|user_id| user_id.mention().to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let message =
+            CreateMessage::new().content(format!("📣 今日の日報スレッドです {}", mentions));
+        if let Err(e) = thread_id.send_message(http, message).await {
+            warn!(
+                error = %e,
+                thread_id = thread_id.get(),
+                "Failed to send diary thread rollcall"
+            );
+        }
+    }
+
+    async fn ensure_close_and_new_button(&self, http: &Http, thread_id: ChannelId) -> Result<()> {
+        let messages = thread_id
+            .messages(http, GetMessages::new().limit(10))
+            .await
+            .context("Failed to fetch thread messages to inspect close button")?;
+
+        if messages.iter().any(message_has_close_and_new_button) {
+            return Ok(());
+        }
+
+        self.send_auto_close_button(http, thread_id).await?;
+
+        info!(
+            thread_id = thread_id.get(),
+            "Sent fallback close-and-new button message"
+        );
+
+        Ok(())
+    }
+}
+
+/// クローズ&新規作成ボタンの ActionRow を作成する。
+fn create_close_and_new_action_row() -> CreateActionRow {
+    let button = CreateButton::new(DIARY_CLOSE_AND_NEW_BUTTON_ID)
+        .label("クローズして新しい日報を作成")
+        .style(serenity::all::ButtonStyle::Primary);
+    CreateActionRow::Buttons(vec![button])
+}
+
+/// 同期に失敗したメッセージの再同期ボタンの ActionRow を作成する。
+fn create_retry_sync_action_row(thread_id: ChannelId, message_id: MessageId) -> CreateActionRow {
+    let button = CreateButton::new(format!(
+        "{}{}:{}",
+        DIARY_RETRY_SYNC_BUTTON_PREFIX,
+        thread_id.get(),
+        message_id.get()
+    ))
+    .label("再同期")
+    .style(serenity::all::ButtonStyle::Secondary);
+    CreateActionRow::Buttons(vec![button])
+}
+
+/// 再同期ボタンの custom_id の接頭辞より後ろから、スレッド ID とメッセージ ID を取り出す。
+fn parse_retry_sync_target(target: &str) -> Option<(ChannelId, MessageId)> {
+    let (thread_id, message_id) = target.split_once(':')?;
+    let thread_id = thread_id.parse::<u64>().ok().filter(|id| *id != 0)?;
+    let message_id = message_id.parse::<u64>().ok().filter(|id| *id != 0)?;
+    Some((ChannelId::new(thread_id), MessageId::new(message_id)))
+}
+
+/// 日報スレッドの最初のメッセージを構築する。
+///
+/// `compact` が true の場合は Notion リンクを `<>` で囲んで展開を抑え、代わりに小さな埋め込みを付ける。
+fn create_diary_thread_initial_message(
+    title: &str,
+    page_url: &str,
+    compact: bool,
+) -> CreateMessage {
+    let message = CreateMessage::new().components(vec![create_close_and_new_action_row()]);
+    if !compact {
+        return message.content(format!("Notion: {}", page_url));
+    }
+
+    let embed = CreateEmbed::new()
+        .author(CreateEmbedAuthor::new("Notion").icon_url(NOTION_ICON_URL))
+        .title(title)
+        .url(page_url)
+        .color(0xffffff);
+    message
+        .content(format!("Notion: <{}>", page_url))
+        .embed(embed)
+}
+
+/// 同期履歴 1 件を `/diary history` の 1 行に整形する。
+///
+/// 結果の絵文字・日時・操作・メッセージへのリンクの後ろに、エラーや警告の内容を切り詰めて付ける。
+fn format_sync_history_line(record: &SyncHistoryRecord, guild_id: u64) -> String {
+    let emoji = match record.status {
+        SyncHistoryStatus::Success => "✅",
+        SyncHistoryStatus::Partial => "⚠️",
+        SyncHistoryStatus::Failure => "❌",
+    };
+    let mut line = format!(
+        "{} <t:{}:f> {} https://discord.com/channels/{}/{}/{}",
+        emoji,
+        record.recorded_at.timestamp(),
+        record.action,
+        guild_id,
+        record.thread_id,
+        record.message_id
+    );
+    if let Some(detail) = record.detail.as_deref() {
+        line.push_str(&format!(
+            "\n└ `{}`",
+            truncate_chars(&detail.replace('`', "'"), SYNC_HISTORY_DETAIL_MAX_CHARS)
+        ));
+    }
+    line
+}
+
+/// Discord API のエラーを、リトライで回復しうるかどうかで分類する。
+fn classify_serenity_error(error: serenity::Error) -> RetryError {
+    let status = match &error {
+        serenity::Error::Http(http_error) => http_error.status_code().map(|s| s.as_u16()),
+        _ => None,
+    };
+    let is_request_error = matches!(&error, serenity::Error::Http(HttpError::Request(_)));
+
+    match status {
+        Some(status) => RetryError::from_status(status, None, error),
+        None if is_request_error => RetryError::transient(error),
+        None => RetryError::permanent(error),
+    }
+}
+
+/// 日報フォーラムを使えなくなった理由。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForumUnavailableReason {
+    /// チャンネルが削除された
+    Deleted,
+    /// bot がチャンネルを見られないか、投稿する権限がない
+    NoAccess,
+}
+
+impl ForumUnavailableReason {
+    fn description(self) -> &'static str {
+        match self {
+            Self::Deleted => "が見つかりません（削除された可能性があります）",
+            Self::NoAccess => "に bot がアクセスできません",
+        }
+    }
+}
+
+/// 日報フォーラムが削除されたか、bot がアクセスできなくなったことを表すエラー。
+///
+/// 利用者と管理者に見せる文言に、復旧の手順を含める。
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "日報フォーラム {} {}。フォーラムが存在し、bot に「チャンネルを見る」「メッセージを送信」「投稿を作成」の権限があるか確認するか、設定の forum_channel_id を正しいフォーラムに変更して kgd を再起動してください。復旧するまで日報の自動作成は停止します（/diary new で作成できると再開します）",
+    .forum_channel_id.mention(),
+    .reason.description()
+)]
+struct ForumUnavailableError {
+    forum_channel_id: ChannelId,
+    reason: ForumUnavailableReason,
+}
+
+/// Discord API のエラーが、日報フォーラムの削除や権限の喪失によるものかどうかを判定する。
+fn forum_unavailable_reason(error: &serenity::Error) -> Option<ForumUnavailableReason> {
+    match error {
+        serenity::Error::Http(HttpError::UnsuccessfulRequest(response)) => {
+            forum_unavailable_reason_from_response(
+                response.status_code.as_u16(),
+                response.error.code,
+            )
+        }
+        _ => None,
+    }
+}
+
+/// HTTP ステータスと Discord のエラーコードから、フォーラムを使えなくなった理由を判定する。
+///
+/// エラーコードは 10003 が Unknown Channel、50001 が Missing Access、50013 が Missing Permissions。
+fn forum_unavailable_reason_from_response(
+    status: u16,
+    code: isize,
+) -> Option<ForumUnavailableReason> {
+    match (status, code) {
+        (404, _) | (_, 10003) => Some(ForumUnavailableReason::Deleted),
+        (403, _) | (_, 50001 | 50013) => Some(ForumUnavailableReason::NoAccess),
+        _ => None,
+    }
+}
+
+/// Discord API のエラーが権限不足（403）によるものかどうかを返す。
+fn is_missing_permissions(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<serenity::Error>() {
+        Some(serenity::Error::Http(http_error)) => {
+            http_error.status_code().map(|status| status.as_u16()) == Some(403)
+        }
+        _ => false,
+    }
+}
+
+/// サブコマンドに指定された整数オプションの値を取得する。
+fn subcommand_integer_option(command: &CommandInteraction, name: &str) -> Option<i64> {
+    let subcommand = command.data.options.first()?;
+    let CommandDataOptionValue::SubCommand(options) = &subcommand.value else {
+        return None;
+    };
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_i64())
+}
+
+fn message_has_close_and_new_button(message: &Message) -> bool {
+    message.components.iter().any(|row| {
+        row.components.iter().any(|component| {
+            matches!(
+                component,
+                ActionRowComponent::Button(button)
+                    if matches!(
+                        &button.data,
+                        ButtonKind::NonLink { custom_id, .. }
+                            if custom_id == DIARY_CLOSE_AND_NEW_BUTTON_ID
+                    )
+            )
+        })
+    })
+}
+
+/// 日報向けの定期メンテナンスタスクを起動する。
+pub fn spawn_periodic_tasks(handler: Handler, http: Arc<Http>, interval: Duration) {
+    tokio::spawn(run_diary_periodic_tasks(handler, http, interval));
+    info!(interval = ?interval, "Diary periodic tasks started");
+}
+
+/// 日報向けの定期メンテナンスタスクを実行する。
+async fn run_diary_periodic_tasks(handler: Handler, http: Arc<Http>, interval: Duration) {
+    // 起動時に、停止中に Notion で手動削除されたブロックの記録を掃除する
+    let reconcile_days = handler.config.diary.reconcile_days;
+    if reconcile_days > 0
+        && let Err(error) = handler
+            .reconcile_recent_diaries(&http, reconcile_days)
+            .await
+    {
+        error!(error = %error, "Startup diary reconcile failed");
+    }
+
+    let mut interval_timer = tokio::time::interval(interval);
+
+    loop {
+        interval_timer.tick().await;
+
+        if let Err(error) = handler.check_auto_close(&http).await {
+            error!(error = %error, "Auto-close check failed");
+        }
+
+        if let Err(error) = handler.check_hourly_sync(&http).await {
+            error!(error = %error, "Hourly diary sync check failed");
+        }
+
+        if let Err(error) = handler.check_periodic_reports().await {
+            error!(error = %error, "Periodic report check failed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lacks_message_content() {
+        let mut message = Message::default();
+        assert!(lacks_message_content(&message));
+
+        message.content = "hello".to_string();
+        assert!(!lacks_message_content(&message));
+
+        message.content.clear();
+        message.kind = MessageType::ThreadStarterMessage;
+        assert!(!lacks_message_content(&message));
+    }
+
+    #[test]
+    fn test_gateway_intents_message_content() {
+        let mut config: Config =
+            toml::from_str(include_str!("../../../../config.example.toml")).unwrap();
+        assert!(gateway_intents(&config).contains(GatewayIntents::MESSAGE_CONTENT));
+
+        config.discord.message_content_intent = false;
+        let intents = gateway_intents(&config);
+        assert!(intents.contains(GatewayIntents::GUILD_MESSAGES));
+        assert!(!intents.contains(GatewayIntents::MESSAGE_CONTENT));
+    }
+
+    #[test]
+    fn test_create_diary_thread_initial_message() {
+        let url = "https://www.notion.so/page";
+
+        let message = serde_json::to_value(create_diary_thread_initial_message(
+            "2025-01-01",
+            url,
+            false,
+        ))
+        .unwrap();
+        assert_eq!(message["content"], "Notion: https://www.notion.so/page");
+        assert!(message["embeds"].as_array().is_none_or(|e| e.is_empty()));
+
+        let message =
+            serde_json::to_value(create_diary_thread_initial_message("2025-01-01", url, true))
+                .unwrap();
+        assert_eq!(message["content"], "Notion: <https://www.notion.so/page>");
+        assert_eq!(message["embeds"][0]["title"], "2025-01-01");
+        assert_eq!(message["embeds"][0]["url"], url);
+    }
+
+    #[test]
+    fn test_parse_retry_sync_target() {
+        assert_eq!(
+            parse_retry_sync_target("123:456"),
+            Some((ChannelId::new(123), MessageId::new(456)))
+        );
+        assert_eq!(parse_retry_sync_target("123"), None);
+        assert_eq!(parse_retry_sync_target("0:456"), None);
+        assert_eq!(parse_retry_sync_target("abc:456"), None);
+    }
+
+    #[test]
+    fn test_format_sync_history_line() {
+        let mut record = SyncHistoryRecord {
+            thread_id: 2,
+            message_id: 3,
+            action: kgd_diary::SyncAction::Create,
+            status: SyncHistoryStatus::Success,
+            detail: None,
+            recorded_at: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        };
+        assert_eq!(
+            format_sync_history_line(&record, 1),
+            "✅ <t:1700000000:f> create https://discord.com/channels/1/2/3"
+        );
+
+        record.status = SyncHistoryStatus::Failure;
+        record.detail = Some(format!("`boom` {}", "x".repeat(100)));
+        let line = format_sync_history_line(&record, 1);
+        assert!(line.starts_with("❌ "));
+        assert!(line.contains("\n└ `'boom' x"));
+        assert!(line.ends_with("...`"));
+    }
+
+    #[test]
+    fn test_forum_unavailable_reason_from_response() {
+        assert_eq!(
+            forum_unavailable_reason_from_response(404, 10003),
+            Some(ForumUnavailableReason::Deleted)
+        );
+        assert_eq!(
+            forum_unavailable_reason_from_response(403, 50001),
+            Some(ForumUnavailableReason::NoAccess)
+        );
+        assert_eq!(
+            forum_unavailable_reason_from_response(400, 50013),
+            Some(ForumUnavailableReason::NoAccess)
+        );
+        assert_eq!(forum_unavailable_reason_from_response(429, 0), None);
+        assert_eq!(forum_unavailable_reason_from_response(500, 0), None);
+
+        let message = ForumUnavailableError {
+            forum_channel_id: ChannelId::new(42),
+            reason: ForumUnavailableReason::Deleted,
+        }
+        .to_string();
+        assert!(message.starts_with("日報フォーラム <#42> が見つかりません"));
+        assert!(message.contains("forum_channel_id"));
+    }
+}
@@ -0,0 +1,112 @@
+//! 日報機能を含めずにビルドした場合の日報機能の代わり。
+//!
+//! 日報のコマンドやイベントの処理を何もしない実装に置き換え、
+//! ハンドラーからは日報機能の有無を意識せずに呼び出せるようにする。
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use serenity::all::{
+    ChannelId, CommandInteraction, ComponentInteraction, CreateCommand, GatewayIntents, Http,
+    Message, MessageId, MessageUpdateEvent, Reaction, Ready,
+};
+use serenity::client::Context as SerenityContext;
+use tracing::warn;
+
+use crate::{
+    config::{Config, JobAction},
+    email::EmailNotifier,
+    store::BotStore,
+};
+
+use super::Handler;
+
+/// 日報機能の状態（日報機能を含めずにビルドした場合は何も持たない）。
+#[derive(Clone)]
+pub struct DiaryState;
+
+impl DiaryState {
+    /// 日報機能を使う設定があれば、使えないことを警告する。
+    pub async fn new(
+        config: &Config,
+        _bot_store: &BotStore,
+        _email_notifier: Option<Arc<EmailNotifier>>,
+    ) -> Result<Self> {
+        if config
+            .matrix
+            .as_ref()
+            .is_some_and(|matrix| !matrix.diary_room_ids.is_empty())
+        {
+            warn!("Matrix diary rooms require the diary feature, ignoring matrix.diary_room_ids");
+        }
+        Ok(Self)
+    }
+}
+
+/// 日報機能のコマンドの定義を返す（日報機能を含めずにビルドした場合は無い）。
+pub fn diary_commands() -> Vec<CreateCommand> {
+    Vec::new()
+}
+
+/// 日報機能に必要なゲートウェイのインテントを返す（日報機能を含めずにビルドした場合は無い）。
+pub fn gateway_intents(_config: &Config) -> GatewayIntents {
+    GatewayIntents::empty()
+}
+
+/// 日報向けの定期メンテナンスタスクを起動する（日報機能を含めずにビルドした場合は何もしない）。
+pub fn spawn_periodic_tasks(_handler: Handler, _http: Arc<Http>, _interval: Duration) {}
+
+impl Handler {
+    pub fn check_message_content_intent(&self, _ready: &Ready) {}
+
+    pub async fn log_self_test(&self, _http: &Http) {}
+
+    pub async fn handle_message(&self, _ctx: SerenityContext, _message: Message) {}
+
+    pub async fn record_reaction(&self, _reaction: &Reaction) {}
+
+    pub async fn handle_reaction_add(&self, _ctx: SerenityContext, _reaction: Reaction) {}
+
+    pub async fn handle_reaction_remove(&self, _reaction: &Reaction) {}
+
+    pub async fn handle_reactions_cleared(&self, _message_id: MessageId, _emoji: Option<&str>) {}
+
+    pub async fn handle_message_update(&self, _ctx: SerenityContext, _event: MessageUpdateEvent) {}
+
+    pub async fn handle_message_delete(
+        &self,
+        _ctx: SerenityContext,
+        _channel_id: ChannelId,
+        _deleted_message_id: MessageId,
+    ) {
+    }
+
+    pub async fn handle_message_delete_bulk(
+        &self,
+        _ctx: SerenityContext,
+        _channel_id: ChannelId,
+        _multiple_deleted_messages_ids: Vec<MessageId>,
+    ) {
+    }
+
+    pub async fn handle_diary_command(
+        &self,
+        _ctx: &SerenityContext,
+        _command: &CommandInteraction,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    pub async fn handle_diary_component(
+        &self,
+        _ctx: &SerenityContext,
+        _component: &ComponentInteraction,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// 日報機能を使う定期実行ジョブは、起動時の設定の検証で弾いているため実行されない。
+    pub async fn run_diary_job(&self, _http: &Http, action: &JobAction) -> Result<()> {
+        anyhow::bail!("{} requires the diary feature", action.name())
+    }
+}
@@ -0,0 +1,336 @@
+//! 定期実行ジョブのコマンドと実行。
+//!
+//! ジョブの実行結果はボットの状態のストアに記録する。日報を使う処理は日報機能に任せる。
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use anyhow::{Context as _, Result};
+use serenity::all::{
+    ChannelId, CommandInteraction, CreateCommand, CreateCommandOption, CreateEmbed,
+    CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
+    Http, UserId,
+};
+use serenity::{client::Context as SerenityContext, model::application::CommandOptionType};
+use tracing::{error, info, warn};
+
+use crate::{
+    command::{spawn_boot_watch, wake_server},
+    config::{Config, JobAction, JobFailureAlert},
+    scheduler::{ScheduledJob, Scheduler},
+    store::{BotStorage as _, WakeRequester},
+};
+
+use super::{Handler, format_discord_timestamp, subcommand_string_option, truncate_chars};
+
+/// `/jobs history` で表示する実行履歴の件数。
+const JOB_HISTORY_LIMIT: i64 = 10;
+
+/// 設定から定期実行ジョブのスケジューラーを作成する。
+///
+/// 存在しないサーバーを起動するジョブや、日報機能を含めずにビルドした場合の日報を使うジョブはエラーにする。
+pub fn create_scheduler(config: &Config) -> Result<Scheduler> {
+    let scheduler = Scheduler::new(&config.scheduler, chrono::Utc::now())
+        .context("Invalid scheduler jobs in configuration")?;
+    for job in scheduler.jobs() {
+        if let JobAction::Wol { server } = &job.action
+            && config.find_server(server).is_none()
+        {
+            anyhow::bail!(
+                "Scheduled job '{}' refers to unknown server '{}'",
+                job.name,
+                server
+            );
+        }
+        if job.action.requires_diary() && !cfg!(feature = "diary") {
+            anyhow::bail!(
+                "Scheduled job '{}' ({}) requires the diary feature",
+                job.name,
+                job.action.name()
+            );
+        }
+    }
+    Ok(scheduler)
+}
+
+/// `/jobs` コマンドの定義を返す。
+pub fn jobs_command() -> CreateCommand {
+    CreateCommand::new("jobs")
+        .description("Scheduled jobs")
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "List scheduled jobs and their next runs",
+        ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "history",
+                "Show recent runs of a scheduled job",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::String, "name", "Job name")
+                    .required(true),
+            ),
+        )
+}
+
+impl Handler {
+    pub async fn handle_jobs(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let subcommand = command
+            .data
+            .options
+            .first()
+            .map(|opt| opt.name.as_str())
+            .unwrap_or("");
+
+        match subcommand {
+            "list" => self.handle_jobs_list(ctx, command).await,
+            "history" => self.handle_jobs_history(ctx, command).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn handle_jobs_list(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let last_runs = self
+            .bot_store
+            .get_job_runs()
+            .await?
+            .into_iter()
+            .map(|run| (run.name.clone(), run))
+            .collect::<HashMap<_, _>>();
+
+        let mut embed = CreateEmbed::new().title("Scheduled Jobs").color(0x5865f2);
+        for job in self.scheduler.jobs() {
+            let last_run = last_runs.get(&job.name);
+            let next_run = self
+                .scheduler
+                .next_run(job, last_run.map(|run| run.last_run_at));
+
+            let mut field_value = format!(
+                "**Schedule:** `{}`\n**Action:** {}\n**Next run:** {}\n**Last run:** {}",
+                job.schedule_text,
+                job.action.name(),
+                format_discord_timestamp(next_run),
+                format_discord_timestamp(last_run.map(|run| run.last_run_at))
+            );
+            if let Some(run) = last_run
+                && let Some(error) = &run.last_error
+            {
+                field_value.push_str(&format!(
+                    "\n**Last error:** {} ({} failure(s) in a row)",
+                    truncate_chars(error, 200),
+                    run.consecutive_failures
+                ));
+            }
+            embed = embed.field(&job.name, field_value, false);
+        }
+
+        embed = embed.footer(CreateEmbedFooter::new(format!(
+            "Total: {} job(s)",
+            self.scheduler.jobs().len()
+        )));
+
+        let response = CreateInteractionResponseMessage::new()
+            .embed(embed)
+            .ephemeral(false);
+
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn handle_jobs_history(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let name = subcommand_string_option(command, "name").context("Job name not provided")?;
+
+        let content = if !self.scheduler.jobs().iter().any(|job| job.name == name) {
+            format!("Scheduled job '{}' not found", name)
+        } else {
+            let records = self
+                .bot_store
+                .get_job_run_history(name, JOB_HISTORY_LIMIT)
+                .await?;
+            if records.is_empty() {
+                format!("Scheduled job '{}' has not run yet", name)
+            } else {
+                let lines = records
+                    .iter()
+                    .map(|record| {
+                        let duration = (record.finished_at - record.started_at)
+                            .to_std()
+                            .unwrap_or_default();
+                        let outcome = match &record.error {
+                            Some(error) => format!("❌ {}", truncate_chars(error, 100)),
+                            None => "✅".to_string(),
+                        };
+                        format!(
+                            "{} ({}) {}",
+                            format_discord_timestamp(Some(record.started_at)),
+                            humantime::format_duration(Duration::from_secs(duration.as_secs())),
+                            outcome
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                format!("**{}**\n{}", name, lines.join("\n"))
+            }
+        };
+
+        let response = CreateInteractionResponseMessage::new()
+            .content(content)
+            .ephemeral(false);
+
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 実行日時を迎えた定期実行ジョブを実行し、結果を記録する。
+    ///
+    /// 停止中に実行日時を過ぎたジョブは、起動後に 1 回だけ実行する。
+    pub async fn check_scheduled_jobs(&self, http: &Http) -> Result<()> {
+        if self.scheduler.jobs().is_empty() {
+            return Ok(());
+        }
+
+        let last_runs = self
+            .bot_store
+            .get_job_runs()
+            .await?
+            .into_iter()
+            .map(|run| (run.name, run.last_run_at))
+            .collect::<HashMap<_, _>>();
+
+        let now = chrono::Utc::now();
+        for job in self.scheduler.due_jobs(&last_runs, now) {
+            let result = self.run_job(http, job).await;
+            let finished_at = chrono::Utc::now();
+            let error = match result {
+                Ok(()) => {
+                    info!(job = %job.name, action = job.action.name(), "Scheduled job finished");
+                    None
+                }
+                Err(e) => {
+                    error!(error = %e, job = %job.name, "Scheduled job failed");
+                    Some(format!("{:#}", e))
+                }
+            };
+            let consecutive_failures = self
+                .bot_store
+                .record_job_run(&job.name, now, finished_at, error.as_deref())
+                .await?;
+
+            // 連続失敗が閾値に達したときだけ通知し、失敗が続いても繰り返し通知しない
+            let threshold = self.config.scheduler.failure_alert_threshold.max(1);
+            if let Some(error) = &error
+                && u32::try_from(consecutive_failures).is_ok_and(|count| count == threshold)
+            {
+                self.alert_job_failure(http, job, threshold, error).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 定期実行ジョブが連続して失敗したことを、設定された通知先に知らせる。
+    ///
+    /// 通知に失敗してもログに残すだけにする。
+    async fn alert_job_failure(&self, http: &Http, job: &ScheduledJob, failures: u32, error: &str) {
+        let embed = CreateEmbed::new()
+            .title("Scheduled Job Failed")
+            .color(0xff0000)
+            .description(format!(
+                "Job `{}` ({}) failed {} time(s) in a row.",
+                job.name,
+                job.action.name(),
+                failures
+            ))
+            .field("Last error", truncate_chars(error, 1000), false);
+
+        match self.config.scheduler.failure_alert {
+            JobFailureAlert::Off => {}
+            JobFailureAlert::StatusChannel => {
+                let channel_id = ChannelId::new(self.config.discord.status_channel_id);
+                if let Err(e) = channel_id
+                    .send_message(http, CreateMessage::new().embed(embed))
+                    .await
+                {
+                    warn!(error = %e, job = %job.name, "Failed to send scheduled job failure alert");
+                }
+            }
+            JobFailureAlert::Dm => {
+                for admin in &self.config.discord.admins {
+                    if let Err(e) = UserId::new(*admin)
+                        .direct_message(http, CreateMessage::new().embed(embed.clone()))
+                        .await
+                    {
+                        warn!(
+                            error = %e,
+                            job = %job.name,
+                            user_id = admin,
+                            "Failed to send scheduled job failure alert"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// 定期実行ジョブの処理を実行する。
+    async fn run_job(&self, http: &Http, job: &ScheduledJob) -> Result<()> {
+        match &job.action {
+            JobAction::Wol { server } => {
+                let (server, wake_id) = wake_server(
+                    &self.config,
+                    &self.bot_store,
+                    server,
+                    WakeRequester::Schedule,
+                )
+                .await?;
+                // 起動を待つ間に他のジョブを止めないよう、別のタスクで待って記録する
+                if let Some(wake_id) = wake_id {
+                    spawn_boot_watch(&self.config, &self.bot_store, server, wake_id);
+                }
+            }
+            JobAction::Message {
+                channel_id,
+                content,
+            } => {
+                ChannelId::new(*channel_id)
+                    .say(http, content)
+                    .await
+                    .context("Failed to send scheduled message")?;
+            }
+            action => self.run_diary_job(http, action).await?,
+        }
+
+        Ok(())
+    }
+}
+
+/// 実行日時を迎えた定期実行ジョブを定期的に確認して実行する。
+pub async fn run_scheduled_jobs(handler: Handler, http: Arc<Http>, interval: Duration) {
+    let mut interval_timer = tokio::time::interval(interval);
+
+    loop {
+        interval_timer.tick().await;
+
+        if let Err(error) = handler.check_scheduled_jobs(&http).await {
+            error!(error = %error, "Scheduled job check failed");
+        }
+    }
+}
@@ -0,0 +1,2098 @@
+#[cfg(feature = "diary")]
+mod diary;
+#[cfg(not(feature = "diary"))]
+#[path = "diary_disabled.rs"]
+mod diary;
+mod jobs;
+mod power;
+#[cfg(feature = "diary")]
+mod stats;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex as StdMutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context as _, Result};
+use serenity::{
+    all::{
+        ButtonStyle, ChannelId, CommandDataOptionValue, CommandInteraction, ComponentInteraction,
+        CreateActionRow, CreateAttachment, CreateAutocompleteResponse, CreateButton, CreateCommand,
+        CreateCommandOption, CreateEmbed, CreateInteractionResponse,
+        CreateInteractionResponseFollowup, CreateInteractionResponseMessage, CreateMessage,
+        EditInteractionResponse, EditMessage, EditWebhookMessage, ExecuteWebhook, GatewayIntents,
+        Http, InstallationContext, InteractionContext, Message, MessageId, MessageUpdateEvent,
+        Reaction, Timestamp, UserId, WebhookId,
+    },
+    async_trait,
+    builder::{Builder as _, CreateEmbedFooter},
+    client::Context as SerenityContext,
+    model::application::CommandOptionType,
+    prelude::*,
+    utils::parse_webhook,
+};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::{
+    command::{is_authorized, wait_for_boot, wake_server},
+    config::{
+        Config, FeaturesConfig, ServerConfig, StatusNotifyMode, StatusWebhookConfig,
+        UpdateNotification,
+    },
+    email::EmailNotifier,
+    inventory::{self, ExportFormat, InventoryHistory},
+    scheduler::Scheduler,
+    shutdown::{PowerAction, PowerOutcome, power_server},
+    status::{self, HealthStatus, ServerStatus, StatusSnooze, format_ports},
+    store::{BotStorage as _, BotStore, WakeRecord, WakeRequester},
+    update::UpdateChecker,
+    version,
+};
+
+use self::diary::DiaryState;
+
+/// Discord が一度に受け付ける補完候補の上限。
+const AUTOCOMPLETE_MAX_CHOICES: usize = 25;
+
+/// 電源の操作の確認ボタンの custom_id の接尾辞（前に操作の名前、後ろにサーバー名が続く）
+const POWER_CONFIRM_BUTTON_SUFFIX: &str = "_confirm:";
+
+/// 電源の操作の取り消しボタンの custom_id の接尾辞（前に操作の名前が続く）
+const POWER_CANCEL_BUTTON_SUFFIX: &str = "_cancel";
+
+/// 電源の操作の結果に表示する標準エラー出力の文字数の上限（embed のフィールドの上限 1024 文字に収める）
+const POWER_STDERR_MAX_CHARS: usize = 900;
+
+/// `/servers show` のメモとリンクの文字数の上限（embed のフィールドの上限 1024 文字に収める）
+const SERVER_DETAIL_MAX_CHARS: usize = 1000;
+
+/// ステータスの再確認ボタンの custom_id
+const STATUS_REFRESH_BUTTON_ID: &str = "status_refresh";
+
+/// ステータスを再確認できる間隔（連打で ping を送り続けないようにする）
+const STATUS_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// `/servers` で起動時間の平均を求める WOL の記録の件数。
+const WAKE_HISTORY_LIMIT: i64 = 20;
+
+/// 定期実行ジョブや日報向けの定期タスクを確認する間隔。
+const PERIODIC_TASK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// `/wol` の後に起動を待つ時間の上限。
+///
+/// インタラクションのトークンは 15 分で失効し、それ以降は followup を送れないため、それより短くする。
+const WOL_BOOT_TIMEOUT_MAX: Duration = Duration::from_secs(14 * 60);
+
+/// 登録済みのスラッシュコマンドと定義の差分（コマンド名の一覧）。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct CommandDiff {
+    /// 新しく追加するコマンド
+    added: Vec<String>,
+    /// 削除されるコマンド
+    removed: Vec<String>,
+    /// 定義が変わったコマンド
+    changed: Vec<String>,
+}
+
+impl CommandDiff {
+    /// 差分が無いかどうかを返す。
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Discord イベントを処理するハンドラー。
+#[derive(Clone)]
+pub struct Handler {
+    /// アプリケーション設定
+    config: Config,
+    /// ボットの状態（ジョブの実行記録・サーバーの起動記録など）のストア
+    bot_store: BotStore,
+    /// 定期実行ジョブのスケジューラー
+    scheduler: Scheduler,
+    /// 日報機能の状態（日報機能を含めずにビルドした場合は何も持たない）
+    #[cfg_attr(not(feature = "diary"), allow(dead_code))]
+    diary: DiaryState,
+    /// ステータス通知の一時停止の状態
+    status_snooze: StatusSnooze,
+    /// `/status check` でステータスを最後に確認した時刻
+    last_status_check: Arc<StdMutex<Option<Instant>>>,
+    /// 新しいバージョンの確認（設定されていない場合は None）
+    update_checker: Option<Arc<UpdateChecker>>,
+}
+
+#[async_trait]
+impl EventHandler for Handler {
+    async fn ready(&self, ctx: SerenityContext, ready: serenity::model::gateway::Ready) {
+        info!(user = %ready.user.name, "Bot connected");
+
+        self.check_message_content_intent(&ready);
+
+        // 日報以外のコマンドはサーバー外（bot との DM など）でも使えるようにする
+        let (integration_types, contexts) = if self.config.discord.user_install {
+            (
+                vec![InstallationContext::Guild, InstallationContext::User],
+                vec![
+                    InteractionContext::Guild,
+                    InteractionContext::BotDm,
+                    InteractionContext::PrivateChannel,
+                ],
+            )
+        } else {
+            (
+                vec![InstallationContext::Guild],
+                vec![InteractionContext::Guild, InteractionContext::BotDm],
+            )
+        };
+
+        let mut commands = vec![
+            CreateCommand::new("wol")
+                .description("Wake up a server using Wake-on-LAN")
+                .add_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "server",
+                        "Server name to wake up",
+                    )
+                    .required(true)
+                    .set_autocomplete(true),
+                ),
+            create_power_command(PowerAction::Shutdown),
+            create_power_command(PowerAction::Reboot),
+            create_power_command(PowerAction::Suspend),
+            CreateCommand::new("servers")
+                .description("Configured servers")
+                .add_option(CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "list",
+                    "List all configured servers",
+                ))
+                .add_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "show",
+                        "Show a server's details, notes and links",
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "server",
+                            "Server name to show",
+                        )
+                        .required(true)
+                        .set_autocomplete(true),
+                    ),
+                )
+                .add_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "export",
+                        "Export the server inventory as a file",
+                    )
+                    .add_sub_option(ExportFormat::ALL.into_iter().fold(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "format",
+                            "File format (default: csv)",
+                        ),
+                        |option, format| option.add_string_choice(format.name(), format.name()),
+                    )),
+                ),
+            CreateCommand::new("version").description("Show bot version information"),
+            CreateCommand::new("status")
+                .description("Server status notifications")
+                .add_option(CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "check",
+                    "Check the server status now",
+                ))
+                .add_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "snooze",
+                        "Silence status notifications for a while",
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "duration",
+                            "How long to silence (e.g. 30m, 2h)",
+                        )
+                        .required(true),
+                    ),
+                ),
+            jobs::jobs_command(),
+        ]
+        .into_iter()
+        .map(|command| {
+            command
+                .integration_types(integration_types.clone())
+                .contexts(contexts.clone())
+        })
+        .collect::<Vec<_>>();
+
+        // `/power` と日報のコマンドを追加（日報はサーバー内でのみ使える）
+        commands.push(power::power_command());
+        commands.extend(diary::diary_commands());
+
+        // 定義が変わっていなければ登録し直さない（コマンド更新の日次上限を消費しないため）
+        let needs_registration = match diff_global_commands(&ctx.http, &commands).await {
+            Ok(diff) if diff.is_empty() => {
+                info!(
+                    commands = commands.len(),
+                    "Slash commands are up to date, skipping registration"
+                );
+                false
+            }
+            Ok(diff) => {
+                info!(
+                    added = ?diff.added,
+                    removed = ?diff.removed,
+                    changed = ?diff.changed,
+                    "Slash command definitions changed"
+                );
+                true
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to compare registered commands, registering all");
+                true
+            }
+        };
+
+        if needs_registration {
+            match serenity::all::Command::set_global_commands(&ctx.http, commands).await {
+                Ok(commands) => {
+                    let commands = commands
+                        .iter()
+                        .map(|command| {
+                            (
+                                command.name.as_str(),
+                                (command.version.get(), command.version.created_at().to_utc()),
+                            )
+                        })
+                        .collect::<HashMap<_, _>>();
+                    info!(?commands, "Slash commands registered");
+                }
+                Err(e) => {
+                    error!(error = %e, "Failed to register commands");
+                }
+            }
+        }
+
+        self.log_self_test(&ctx.http).await;
+    }
+
+    async fn interaction_create(
+        &self,
+        ctx: SerenityContext,
+        interaction: serenity::model::application::Interaction,
+    ) {
+        match interaction {
+            serenity::model::application::Interaction::Command(command) => {
+                if let Err(e) = self.handle_command(&ctx, &command).await {
+                    error!(error = ?e, command = %command.data.name, "Command error");
+
+                    let response = CreateInteractionResponseMessage::new()
+                        .content(format!("Error: {}", e))
+                        .ephemeral(true);
+
+                    if let Err(e) = command
+                        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                        .await
+                    {
+                        error!(error = %e, "Failed to send error response");
+                    }
+                }
+            }
+            serenity::model::application::Interaction::Autocomplete(autocomplete) => {
+                if let Err(e) = self.handle_autocomplete(&ctx, &autocomplete).await {
+                    error!(error = ?e, command = %autocomplete.data.name, "Autocomplete error");
+                }
+            }
+            serenity::model::application::Interaction::Component(component) => {
+                if let Err(e) = self.handle_component(&ctx, &component).await {
+                    error!(error = ?e, custom_id = %component.data.custom_id, "Component interaction error");
+
+                    let response = CreateInteractionResponseMessage::new()
+                        .content(format!("Error: {}", e))
+                        .ephemeral(true);
+
+                    if let Err(e) = component
+                        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                        .await
+                    {
+                        error!(error = %e, "Failed to send error response");
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    async fn message(&self, ctx: SerenityContext, message: Message) {
+        self.handle_message(ctx, message).await;
+    }
+
+    async fn reaction_add(&self, ctx: SerenityContext, reaction: Reaction) {
+        self.record_reaction(&reaction).await;
+        self.handle_reaction_add(ctx, reaction).await;
+    }
+
+    async fn reaction_remove(&self, _ctx: SerenityContext, reaction: Reaction) {
+        self.handle_reaction_remove(&reaction).await;
+    }
+
+    async fn reaction_remove_all(
+        &self,
+        _ctx: SerenityContext,
+        _channel_id: ChannelId,
+        removed_from_message_id: MessageId,
+    ) {
+        self.handle_reactions_cleared(removed_from_message_id, None)
+            .await;
+    }
+
+    async fn reaction_remove_emoji(&self, _ctx: SerenityContext, removed_reactions: Reaction) {
+        self.handle_reactions_cleared(
+            removed_reactions.message_id,
+            Some(&removed_reactions.emoji.to_string()),
+        )
+        .await;
+    }
+
+    async fn message_update(
+        &self,
+        ctx: SerenityContext,
+        _old_if_available: Option<Message>,
+        _new: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        self.handle_message_update(ctx, event).await;
+    }
+
+    async fn message_delete(
+        &self,
+        ctx: SerenityContext,
+        channel_id: ChannelId,
+        deleted_message_id: MessageId,
+        _guild_id: Option<serenity::model::id::GuildId>,
+    ) {
+        self.handle_message_delete(ctx, channel_id, deleted_message_id)
+            .await;
+    }
+
+    async fn message_delete_bulk(
+        &self,
+        ctx: SerenityContext,
+        channel_id: ChannelId,
+        multiple_deleted_messages_ids: Vec<MessageId>,
+        _guild_id: Option<serenity::model::id::GuildId>,
+    ) {
+        self.handle_message_delete_bulk(ctx, channel_id, multiple_deleted_messages_ids)
+            .await;
+    }
+}
+
+impl Handler {
+    async fn handle_command(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let user_id = command.user.id.get();
+        if !is_authorized(&self.config.discord.admins, &user_id) {
+            warn!(user_id, "Unauthorized access attempt");
+            let response = CreateInteractionResponseMessage::new()
+                .content("You are not authorized to use this bot.")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        }
+
+        // 日報コマンドはサーバーのチャンネルを前提にするため、DM などからの実行は断る
+        if command.data.name == "diary" && command.guild_id.is_none() {
+            let response = CreateInteractionResponseMessage::new()
+                .content("このコマンドはサーバー内でのみ使用できます")
+                .ephemeral(true);
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        }
+
+        match command.data.name.as_str() {
+            "wol" => self.handle_wol(ctx, command).await,
+            "servers" => self.handle_servers(ctx, command).await,
+            "version" => self.handle_version(ctx, command).await,
+            "jobs" => self.handle_jobs(ctx, command).await,
+            "status" => self.handle_status(ctx, command).await,
+            "power" => self.handle_power_usage(ctx, command).await,
+            name => match PowerAction::from_name(name) {
+                Some(action) => self.handle_power(ctx, command, action).await,
+                None => self.handle_diary_command(ctx, command).await,
+            },
+        }
+    }
+
+    /// オプションの入力中に補完候補を返す。
+    ///
+    /// 許可されていないユーザーにはサーバー名を見せないよう、候補を返さない。
+    async fn handle_autocomplete(
+        &self,
+        ctx: &SerenityContext,
+        autocomplete: &CommandInteraction,
+    ) -> Result<()> {
+        let authorized = is_authorized(&self.config.discord.admins, &autocomplete.user.id.get());
+        let choices = match autocomplete.data.autocomplete() {
+            Some(option) if authorized => match autocomplete.data.name.as_str() {
+                "wol" | "servers" => server_name_suggestions(&self.config.servers, option.value),
+                name if PowerAction::from_name(name).is_some() => server_name_suggestions(
+                    self.config
+                        .servers
+                        .iter()
+                        .filter(|server| server.shutdown.is_some()),
+                    option.value,
+                ),
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+
+        let response = choices
+            .into_iter()
+            .fold(CreateAutocompleteResponse::new(), |response, name| {
+                response.add_string_choice(name, name)
+            });
+        autocomplete
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(response))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn handle_wol(&self, ctx: &SerenityContext, command: &CommandInteraction) -> Result<()> {
+        let server_name = command
+            .data
+            .options
+            .first()
+            .and_then(|opt| opt.value.as_str())
+            .context("Server name not provided")?;
+
+        // 起動を待つ間に応答の期限（3 秒）を過ぎないよう、先に応答を保留する
+        command.defer(&ctx.http).await?;
+
+        let requested_by = WakeRequester::Discord(command.user.id.get());
+        let (server, wake_id) =
+            match wake_server(&self.config, &self.bot_store, server_name, requested_by).await {
+                Ok(wake) => wake,
+                Err(e) => {
+                    command
+                        .edit_response(
+                            &ctx.http,
+                            EditInteractionResponse::new().content(format!("Error: {}", e)),
+                        )
+                        .await?;
+                    return Ok(());
+                }
+            };
+        command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new().content(format!(
+                    "Sent WOL packet to {} ({})",
+                    server.name, server.mac_address
+                )),
+            )
+            .await?;
+
+        let timeout = self
+            .config
+            .status
+            .wol_boot_timeout
+            .min(WOL_BOOT_TIMEOUT_MAX);
+        let elapsed = wait_for_boot(&self.config, &self.bot_store, server, wake_id, timeout).await;
+
+        command
+            .create_followup(
+                &ctx.http,
+                CreateInteractionResponseFollowup::new().content(format_wol_result(
+                    &server.name,
+                    elapsed,
+                    timeout,
+                )),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// 電源の操作の確認のボタンを表示する。
+    ///
+    /// 誤操作で止めてしまわないよう、確認のボタンが押されてから操作する。
+    async fn handle_power(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+        action: PowerAction,
+    ) -> Result<()> {
+        let server_name = command
+            .data
+            .options
+            .first()
+            .and_then(|opt| opt.value.as_str())
+            .context("Server name not provided")?;
+        let server = self
+            .config
+            .find_server(server_name)
+            .with_context(|| format!("Server '{}' not found", server_name))?;
+        anyhow::ensure!(
+            server.shutdown.is_some(),
+            "Shutdown is not configured for '{}'",
+            server.name
+        );
+
+        let response = CreateInteractionResponseMessage::new()
+            .content(format!("{} **{}**?", action.label(), server.name))
+            .components(vec![create_power_action_row(action, &server.name)])
+            .ephemeral(true);
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn handle_component(
+        &self,
+        ctx: &SerenityContext,
+        component: &ComponentInteraction,
+    ) -> Result<()> {
+        if component.data.custom_id == STATUS_REFRESH_BUTTON_ID {
+            return self.handle_status_refresh(ctx, component).await;
+        }
+        match parse_power_button(&component.data.custom_id) {
+            Some(PowerButton::Confirm(action, server_name)) => {
+                return self
+                    .handle_power_confirm(ctx, component, action, server_name)
+                    .await;
+            }
+            Some(PowerButton::Cancel(action)) => {
+                let response = CreateInteractionResponseMessage::new()
+                    .content(format!("{} cancelled", action.label()))
+                    .components(vec![]);
+                component
+                    .create_response(
+                        &ctx.http,
+                        CreateInteractionResponse::UpdateMessage(response),
+                    )
+                    .await?;
+                return Ok(());
+            }
+            None => {}
+        }
+
+        self.handle_diary_component(ctx, component).await
+    }
+
+    /// 確認のボタンが押されたサーバーの電源を操作し、結果の embed で確認のメッセージを置き換える。
+    async fn handle_power_confirm(
+        &self,
+        ctx: &SerenityContext,
+        component: &ComponentInteraction,
+        action: PowerAction,
+        server_name: &str,
+    ) -> Result<()> {
+        let user_id = component.user.id.get();
+        if !is_authorized(&self.config.discord.admins, &user_id) {
+            warn!(user_id, "Unauthorized access attempt");
+            let response = CreateInteractionResponseMessage::new()
+                .content("You are not authorized to use this bot.")
+                .ephemeral(true);
+            component
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        }
+
+        // SSH の接続に時間がかかることがあるため、先にボタンを取り除いて応答しておく
+        let response = CreateInteractionResponseMessage::new()
+            .content(format!(
+                "Sending {} command to **{}**...",
+                action.name(),
+                server_name
+            ))
+            .components(vec![]);
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(response),
+            )
+            .await?;
+
+        let result = power_server(&self.config, server_name, action)
+            .await
+            .map(|(_, outcome)| outcome);
+        if let Err(e) = &result {
+            error!(
+                error = ?e,
+                server = %server_name,
+                action = action.name(),
+                "Failed to run power command"
+            );
+        }
+        component
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new()
+                    .content("")
+                    .embed(create_power_result_embed(action, server_name, &result)),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn handle_servers(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let subcommand = command
+            .data
+            .options
+            .first()
+            .map(|opt| opt.name.as_str())
+            .unwrap_or("");
+
+        match subcommand {
+            "list" => self.handle_servers_list(ctx, command).await,
+            "show" => self.handle_servers_show(ctx, command).await,
+            "export" => self.handle_servers_export(ctx, command).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn handle_servers_list(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let mut embed = CreateEmbed::new()
+            .title("Configured Servers")
+            .color(0x00ff00);
+
+        for server in &self.config.servers {
+            let mut field_value = format!(
+                "**IP:** {}\n**MAC:** {}\n**Description:** {}",
+                server.ip_address, server.mac_address, server.description
+            );
+            if let Some(summary) = self.wake_summary(&server.name).await {
+                field_value = format!("{}\n{}", field_value, summary);
+            }
+            embed = embed.field(&server.name, field_value, false);
+        }
+
+        embed = embed.footer(CreateEmbedFooter::new(format!(
+            "Total: {} server(s)",
+            self.config.servers.len()
+        )));
+
+        let response = CreateInteractionResponseMessage::new()
+            .embed(embed)
+            .ephemeral(false);
+
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 1 台のサーバーの詳細を、メモと手順書などのリンクを付けて表示する。
+    async fn handle_servers_show(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let server_name =
+            subcommand_string_option(command, "server").context("Server name not provided")?;
+
+        let response = match self.config.find_server(server_name) {
+            Some(server) => {
+                let wake_summary = self.wake_summary(&server.name).await;
+                CreateInteractionResponseMessage::new()
+                    .embed(create_server_detail_embed(server, wake_summary))
+                    .ephemeral(false)
+            }
+            None => CreateInteractionResponseMessage::new()
+                .content(format!("Error: Server '{}' not found", server_name))
+                .ephemeral(true),
+        };
+
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        Ok(())
+    }
+
+    /// サーバーのインベントリをファイルにして添付する。
+    async fn handle_servers_export(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let format = match subcommand_string_option(command, "format") {
+            Some(name) => ExportFormat::from_name(name)
+                .with_context(|| format!("Unknown format: {}", name))?,
+            None => ExportFormat::Csv,
+        };
+
+        // ping の待ち時間で応答の期限（3 秒）を過ぎないよう、先に応答を保留する
+        command.defer(&ctx.http).await?;
+
+        let response = match self.export_inventory(format).await {
+            Ok(content) => EditInteractionResponse::new().new_attachment(CreateAttachment::bytes(
+                content,
+                format!("servers.{}", format.name()),
+            )),
+            Err(e) => {
+                error!(error = ?e, "Failed to export server inventory");
+                EditInteractionResponse::new().content(format!("Error: {:#}", e))
+            }
+        };
+        command.edit_response(&ctx.http, response).await?;
+
+        Ok(())
+    }
+
+    /// サーバーのインベントリを指定した形式の文字列にする。
+    async fn export_inventory(&self, format: ExportFormat) -> Result<String> {
+        let history = InventoryHistory::load(&self.bot_store, &self.config.servers).await?;
+
+        let rows = inventory::collect(&self.config, &history).await;
+        inventory::render(&rows, format)
+    }
+
+    /// サーバーの Wake-on-LAN の履歴の要約を返す（履歴が無い場合は None）。
+    async fn wake_summary(&self, server_name: &str) -> Option<String> {
+        match self
+            .bot_store
+            .get_wake_history(server_name, WAKE_HISTORY_LIMIT)
+            .await
+        {
+            Ok(history) => return format_wake_summary(&history),
+            Err(e) => warn!(error = %e, server = %server_name, "Failed to fetch wake history"),
+        }
+        None
+    }
+
+    async fn handle_version(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let mut embed = CreateEmbed::new()
+            .title("kgd")
+            .color(0x5865f2)
+            .field("Version", version::VERSION, true)
+            .field("Git SHA", version::GIT_SHA, true)
+            .field("Target", version::TARGET_TRIPLE, true)
+            .field("Built", version::BUILD_DATE, false)
+            .field(
+                "Features",
+                format_enabled_features(&self.config.features),
+                false,
+            );
+        if let Some(release) = self
+            .update_checker
+            .as_ref()
+            .and_then(|checker| checker.available_update())
+        {
+            embed = embed.color(0xffa500).field(
+                "Update available",
+                format!("[{}]({})", release.version, release.url),
+                false,
+            );
+        }
+
+        let response = CreateInteractionResponseMessage::new()
+            .embed(embed)
+            .ephemeral(false);
+
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn handle_status(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let subcommand = command
+            .data
+            .options
+            .first()
+            .map(|opt| opt.name.as_str())
+            .unwrap_or("");
+
+        match subcommand {
+            "check" => self.handle_status_check(ctx, command).await,
+            "snooze" => self.handle_status_snooze(ctx, command).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// サーバーのステータスを確認し、再確認のボタンを付けて本人にだけ表示する。
+    async fn handle_status_check(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        // ping の待ち時間で応答の期限（3 秒）を過ぎないよう、先に応答を保留する
+        command.defer_ephemeral(&ctx.http).await?;
+        self.record_status_check(Instant::now());
+
+        let statuses = status::check_servers(&self.config.servers, status::PING_TIMEOUT).await;
+        command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new()
+                    .embed(create_status_dashboard_embed(&statuses))
+                    .components(vec![create_status_refresh_action_row()]),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// 再確認のボタンが押されたら、ステータスを確認し直して embed を置き換える。
+    ///
+    /// 前回の確認から [`STATUS_REFRESH_INTERVAL`] が経っていない場合は確認せず、待つよう伝える。
+    async fn handle_status_refresh(
+        &self,
+        ctx: &SerenityContext,
+        component: &ComponentInteraction,
+    ) -> Result<()> {
+        let user_id = component.user.id.get();
+        let denial = if !is_authorized(&self.config.discord.admins, &user_id) {
+            warn!(user_id, "Unauthorized access attempt");
+            Some("You are not authorized to use this bot.".to_string())
+        } else {
+            self.record_status_check(Instant::now()).map(|wait| {
+                format!(
+                    "Please wait {} seconds before refreshing again.",
+                    wait.as_secs().max(1)
+                )
+            })
+        };
+        if let Some(content) = denial {
+            let response = CreateInteractionResponseMessage::new()
+                .content(content)
+                .ephemeral(true);
+            component
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Ok(());
+        }
+
+        component.defer(&ctx.http).await?;
+        let statuses = status::check_servers(&self.config.servers, status::PING_TIMEOUT).await;
+        component
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new()
+                    .embed(create_status_dashboard_embed(&statuses))
+                    .components(vec![create_status_refresh_action_row()]),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// ステータスの確認を記録する。
+    ///
+    /// 前回の確認から [`STATUS_REFRESH_INTERVAL`] が経っていない場合は記録せず、残りの待ち時間を返す。
+    fn record_status_check(&self, now: Instant) -> Option<Duration> {
+        let mut last = self.last_status_check.lock().unwrap();
+        let wait = status_refresh_wait(*last, now);
+        if wait.is_none() {
+            *last = Some(now);
+        }
+        wait
+    }
+
+    /// ステータス通知を指定した時間だけ一時停止する（重大な通知は止めない）。
+    async fn handle_status_snooze(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let duration =
+            subcommand_string_option(command, "duration").context("Duration not provided")?;
+
+        let content = match humantime::parse_duration(duration)
+            .ok()
+            .and_then(|duration| chrono::Duration::from_std(duration).ok())
+            .filter(|duration| *duration > chrono::Duration::zero())
+        {
+            Some(duration) => {
+                let until = chrono::Utc::now() + duration;
+                self.bot_store.set_status_snooze(until).await?;
+                self.status_snooze.snooze_until(until);
+                info!(until = %until, "Status notifications snoozed");
+                format!(
+                    "Status notifications snoozed until {}",
+                    format_discord_timestamp(Some(until))
+                )
+            }
+            None => format!("Invalid duration '{}' (e.g. 30m, 2h)", duration),
+        };
+
+        let response = CreateInteractionResponseMessage::new()
+            .content(content)
+            .ephemeral(false);
+
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 再起動の前に一時停止したステータス通知を、期限まで止めたままにする。
+    async fn restore_status_snooze(&self) -> Result<()> {
+        if let Some(until) = self.bot_store.get_status_snooze().await? {
+            self.status_snooze.snooze_until(until);
+            info!(until = %until, "Status notifications are snoozed");
+        }
+        Ok(())
+    }
+
+    /// データベースに保存したステータス通知の一時停止を消す。
+    async fn clear_saved_status_snooze(&self) -> Result<()> {
+        self.bot_store.clear_status_snooze().await
+    }
+
+    /// データベースに保存したステータスのダッシュボードのメッセージを取得する。
+    async fn saved_status_dashboard_message(&self) -> Option<MessageId> {
+        match self.bot_store.get_status_dashboard_message().await {
+            Ok(message_id) => message_id.map(MessageId::new),
+            Err(e) => {
+                warn!(error = %e, "Failed to load status dashboard message");
+                None
+            }
+        }
+    }
+
+    /// ステータスのダッシュボードのメッセージをデータベースに保存する。
+    async fn save_status_dashboard_message(&self, message_id: MessageId) {
+        if let Err(e) = self
+            .bot_store
+            .set_status_dashboard_message(message_id.get())
+            .await
+        {
+            warn!(error = %e, "Failed to save status dashboard message");
+        }
+    }
+
+    /// 新しいバージョンを確認し、見つけた場合は設定された通知先に知らせる。
+    ///
+    /// 同じバージョンは 1 回だけ通知する。通知に失敗してもログに残すだけにする。
+    pub async fn check_for_update(&self, http: &Http) -> Result<()> {
+        let (Some(checker), Some(update_config)) =
+            (&self.update_checker, &self.config.update_check)
+        else {
+            return Ok(());
+        };
+        let Some(release) = checker.check().await? else {
+            return Ok(());
+        };
+        info!(version = %release.version, url = %release.url, "New kgd version available");
+
+        let embed = CreateEmbed::new()
+            .title("Update available")
+            .color(0xffa500)
+            .description(format!(
+                "kgd {} is available (running {}).\n[Release notes]({})",
+                release.version,
+                version::VERSION,
+                release.url
+            ));
+
+        match update_config.notify {
+            UpdateNotification::StatusChannel => {
+                let channel_id = ChannelId::new(self.config.discord.status_channel_id);
+                if let Err(e) = channel_id
+                    .send_message(http, CreateMessage::new().embed(embed))
+                    .await
+                {
+                    warn!(error = %e, "Failed to send update notification");
+                }
+            }
+            UpdateNotification::Dm => {
+                for admin in &self.config.discord.admins {
+                    if let Err(e) = UserId::new(*admin)
+                        .direct_message(http, CreateMessage::new().embed(embed.clone()))
+                        .await
+                    {
+                        warn!(error = %e, user_id = admin, "Failed to send update notification");
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 登録済みのグローバルコマンドを取得し、定義との差分を返す。
+async fn diff_global_commands(http: &Http, commands: &[CreateCommand]) -> Result<CommandDiff> {
+    let registered = serenity::all::Command::get_global_commands(http)
+        .await
+        .context("Failed to fetch registered commands")?;
+    let desired = commands
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    let registered = registered
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    Ok(diff_command_definitions(&desired, &registered))
+}
+
+/// コマンド定義（JSON）同士を比較し、名前ごとの差分を返す。
+///
+/// ID やバージョンなど Discord 側が付与する項目は比較しない。
+fn diff_command_definitions(
+    desired: &[serde_json::Value],
+    registered: &[serde_json::Value],
+) -> CommandDiff {
+    let by_name = |commands: &[serde_json::Value]| {
+        commands
+            .iter()
+            .map(|command| {
+                let name = command["name"].as_str().unwrap_or_default().to_string();
+                (name, normalize_command_definition(command, true))
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+    let desired = by_name(desired);
+    let registered = by_name(registered);
+
+    let mut diff = CommandDiff::default();
+    for (name, definition) in &desired {
+        match registered.get(name) {
+            None => diff.added.push(name.clone()),
+            Some(current) if current != definition => diff.changed.push(name.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.removed = registered
+        .keys()
+        .filter(|name| !desired.contains_key(*name))
+        .cloned()
+        .collect();
+    diff
+}
+
+/// 比較に使う項目だけを残し、省略時と同じ値（null・false・空配列）を取り除く。
+fn normalize_command_definition(value: &serde_json::Value, top_level: bool) -> serde_json::Value {
+    const COMMAND_KEYS: &[&str] = &[
+        "name",
+        "description",
+        "options",
+        "integration_types",
+        "contexts",
+    ];
+    const OPTION_KEYS: &[&str] = &[
+        "type",
+        "name",
+        "description",
+        "required",
+        "choices",
+        "options",
+        "min_value",
+        "max_value",
+        "min_length",
+        "max_length",
+        "channel_types",
+        "autocomplete",
+    ];
+
+    let keys = if top_level { COMMAND_KEYS } else { OPTION_KEYS };
+    let mut normalized = serde_json::Map::new();
+    for key in keys {
+        let field = match &value[*key] {
+            serde_json::Value::Null | serde_json::Value::Bool(false) => continue,
+            serde_json::Value::Array(items) if items.is_empty() => continue,
+            serde_json::Value::Array(items) if *key == "options" => serde_json::Value::Array(
+                items
+                    .iter()
+                    .map(|item| normalize_command_definition(item, false))
+                    .collect(),
+            ),
+            field => field.clone(),
+        };
+        normalized.insert(key.to_string(), field);
+    }
+    serde_json::Value::Object(normalized)
+}
+
+/// SSH で電源を操作するコマンドを作成する。
+fn create_power_command(action: PowerAction) -> CreateCommand {
+    let (description, option_description) = match action {
+        PowerAction::Shutdown => ("Shut down a server over SSH", "Server name to shut down"),
+        PowerAction::Reboot => ("Reboot a server over SSH", "Server name to reboot"),
+        PowerAction::Suspend => ("Suspend a server over SSH", "Server name to suspend"),
+    };
+    CreateCommand::new(action.name())
+        .description(description)
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "server", option_description)
+                .required(true)
+                .set_autocomplete(true),
+        )
+}
+
+/// 電源の操作の確認のボタン。
+#[derive(Debug, PartialEq, Eq)]
+enum PowerButton<'a> {
+    /// 操作するサーバー名を持つ確認のボタン
+    Confirm(PowerAction, &'a str),
+    /// 取り消しのボタン
+    Cancel(PowerAction),
+}
+
+/// ボタンの custom_id が電源の操作の確認のボタンであれば解析する。
+fn parse_power_button(custom_id: &str) -> Option<PowerButton<'_>> {
+    PowerAction::ALL.into_iter().find_map(|action| {
+        let rest = custom_id.strip_prefix(action.name())?;
+        if let Some(server_name) = rest.strip_prefix(POWER_CONFIRM_BUTTON_SUFFIX) {
+            Some(PowerButton::Confirm(action, server_name))
+        } else {
+            (rest == POWER_CANCEL_BUTTON_SUFFIX).then_some(PowerButton::Cancel(action))
+        }
+    })
+}
+
+/// 電源の操作の確認のボタンを作成する。
+fn create_power_action_row(action: PowerAction, server_name: &str) -> CreateActionRow {
+    let confirm = CreateButton::new(format!(
+        "{}{}{}",
+        action.name(),
+        POWER_CONFIRM_BUTTON_SUFFIX,
+        server_name
+    ))
+    .label(action.label())
+    .style(ButtonStyle::Danger);
+    let cancel = CreateButton::new(format!("{}{}", action.name(), POWER_CANCEL_BUTTON_SUFFIX))
+        .label("Cancel")
+        .style(ButtonStyle::Secondary);
+    CreateActionRow::Buttons(vec![confirm, cancel])
+}
+
+/// 電源の操作の結果（終了コードと標準エラー出力）の embed を作成する。
+fn create_power_result_embed(
+    action: PowerAction,
+    server_name: &str,
+    result: &Result<PowerOutcome>,
+) -> CreateEmbed {
+    let embed = CreateEmbed::new().title(format!("{}: {}", action.label(), server_name));
+    let outcome = match result {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            return embed.color(0xff0000).description(format!("Error: {:#}", e));
+        }
+    };
+
+    let description = if outcome.disconnected {
+        format!(
+            "Sent {} command (connection closed by the server)",
+            action.name()
+        )
+    } else if outcome.is_success() {
+        format!("Sent {} command", action.name())
+    } else {
+        format!("{} command failed", action.name())
+    };
+    let exit_code = outcome
+        .exit_code
+        .map_or_else(|| "なし".to_string(), |code| code.to_string());
+    let mut embed = embed
+        .color(if outcome.is_success() {
+            0x00ff00
+        } else {
+            0xff0000
+        })
+        .description(description)
+        .field("Exit code", exit_code, true);
+    if !outcome.stderr.is_empty() {
+        embed = embed.field(
+            "stderr",
+            format!(
+                "```\n{}\n```",
+                truncate_chars(&outcome.stderr, POWER_STDERR_MAX_CHARS)
+            ),
+            false,
+        );
+    }
+    embed
+}
+
+/// サーバーのステータスの embed を作成する。
+///
+/// オンラインのサーバーには ping の応答時間、ポートの開閉とヘルスチェックの結果を付ける。
+fn create_status_embed(statuses: &[ServerStatus]) -> CreateEmbed {
+    statuses.iter().fold(
+        CreateEmbed::new().title("Server Status").color(0x00ff00),
+        |embed, status| {
+            if !status.online {
+                return embed.field(&status.name, "Offline", true);
+            }
+            let status_text = ["Online".to_string()]
+                .into_iter()
+                .chain(status.ping.format_latency())
+                .chain(format_ports(&status.ports))
+                .chain(status.health.as_ref().map(HealthStatus::format))
+                .collect::<Vec<_>>()
+                .join("\n");
+            embed.field(&status.name, status_text, true)
+        },
+    )
+}
+
+/// `/servers show` のサーバーの詳細の embed を作成する。
+fn create_server_detail_embed(server: &ServerConfig, wake_summary: Option<String>) -> CreateEmbed {
+    let mut embed = CreateEmbed::new()
+        .title(&server.name)
+        .color(0x00ff00)
+        .field("IP", &server.ip_address, true)
+        .field("MAC", server.mac_address.to_string(), true);
+    if !server.description.is_empty() {
+        embed = embed.description(&server.description);
+    }
+    if let Some(summary) = wake_summary {
+        embed = embed.field("Wake history", summary, false);
+    }
+    if let Some(notes) = server
+        .notes
+        .as_deref()
+        .map(str::trim)
+        .filter(|notes| !notes.is_empty())
+    {
+        embed = embed.field(
+            "Notes",
+            truncate_chars(notes, SERVER_DETAIL_MAX_CHARS),
+            false,
+        );
+    }
+    if !server.links.is_empty() {
+        let links = server
+            .links
+            .iter()
+            .map(|link| format!("[{}]({})", link.label, link.url))
+            .collect::<Vec<_>>()
+            .join("\n");
+        embed = embed.field(
+            "Links",
+            truncate_chars(&links, SERVER_DETAIL_MAX_CHARS),
+            false,
+        );
+    }
+    embed
+}
+
+/// 状態が変わったサーバーを知らせる embed を作成する（オフラインになったサーバーがあれば赤にする）。
+fn create_status_change_embed(changes: &[&ServerStatus]) -> CreateEmbed {
+    let description = changes
+        .iter()
+        .map(|status| format_status_change(status))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let color = if changes.iter().any(|status| !status.online) {
+        0xff0000
+    } else {
+        0x00ff00
+    };
+    CreateEmbed::new().description(description).color(color)
+}
+
+/// サーバーの状態の変化を 1 行の文にする。
+fn format_status_change(status: &ServerStatus) -> String {
+    if status.online {
+        format!("✅ {} came online", status.name)
+    } else {
+        format!("⚠️ {} went offline", status.name)
+    }
+}
+
+/// `/status check` のステータスの embed を作成する（確認した時刻を付ける）。
+fn create_status_dashboard_embed(statuses: &[ServerStatus]) -> CreateEmbed {
+    create_status_embed(statuses)
+        .footer(CreateEmbedFooter::new("Checked at"))
+        .timestamp(Timestamp::now())
+}
+
+/// ステータスの再確認のボタンを作成する。
+fn create_status_refresh_action_row() -> CreateActionRow {
+    CreateActionRow::Buttons(vec![
+        CreateButton::new(STATUS_REFRESH_BUTTON_ID)
+            .label("Refresh")
+            .emoji('🔄')
+            .style(ButtonStyle::Secondary),
+    ])
+}
+
+/// 前回の確認から再確認できるまでの残りの待ち時間を返す（すぐに確認できる場合は None）。
+fn status_refresh_wait(last: Option<Instant>, now: Instant) -> Option<Duration> {
+    let elapsed = now.saturating_duration_since(last?);
+    (elapsed < STATUS_REFRESH_INTERVAL).then(|| STATUS_REFRESH_INTERVAL - elapsed)
+}
+
+/// `/servers` に表示する、最後に WOL を送った日時・送ったユーザー・起動時間と、起動時間の平均を作成する。
+///
+/// 記録が無い場合は None を返す。
+fn format_wake_summary(history: &[WakeRecord]) -> Option<String> {
+    let last = history.first()?;
+    let woken_by = match last.requested_by {
+        WakeRequester::Schedule => "schedule".to_string(),
+        WakeRequester::Discord(user_id) => format!("<@{}>", user_id),
+        WakeRequester::Telegram(user_id) => format!("Telegram user {}", user_id),
+    };
+    let boot = match last.boot_time() {
+        Some(boot_time) => format!("booted in {}", format_boot_time(boot_time)),
+        None => "boot not confirmed".to_string(),
+    };
+    let mut summary = format!(
+        "**Last woken:** <t:{}:R> by {}, {}",
+        last.sent_at.timestamp(),
+        woken_by,
+        boot
+    );
+
+    let boot_times: Vec<Duration> = history.iter().filter_map(WakeRecord::boot_time).collect();
+    if !boot_times.is_empty() {
+        let average = boot_times.iter().sum::<Duration>() / boot_times.len() as u32;
+        summary.push_str(&format!(
+            "\n**Average boot:** {} (last {} boots)",
+            format_boot_time(average),
+            boot_times.len()
+        ));
+    }
+    Some(summary)
+}
+
+/// 起動時間を秒単位に丸めて表示する。
+fn format_boot_time(boot_time: Duration) -> String {
+    humantime::format_duration(Duration::from_secs(boot_time.as_secs())).to_string()
+}
+
+/// `/wol` の後に起動を待った結果のメッセージを作成する。
+fn format_wol_result(server_name: &str, elapsed: Option<Duration>, timeout: Duration) -> String {
+    match elapsed {
+        Some(elapsed) => format!(
+            "🟢 {} が起動しました ({} 秒)",
+            server_name,
+            elapsed.as_secs()
+        ),
+        None => format!(
+            "🔴 {} は {} 秒以内に起動しませんでした",
+            server_name,
+            timeout.as_secs()
+        ),
+    }
+}
+
+/// 入力中の文字列を含むサーバー名を、前方一致するものを先にして返す（大文字・小文字は区別しない）。
+///
+/// Discord の補完候補の上限（25 件）までに絞る。
+fn server_name_suggestions<'a>(
+    servers: impl IntoIterator<Item = &'a ServerConfig>,
+    input: &str,
+) -> Vec<&'a str> {
+    let input = input.trim().to_lowercase();
+    let mut matches: Vec<(bool, &str)> = servers
+        .into_iter()
+        .filter_map(|server| {
+            let name = server.name.to_lowercase();
+            name.contains(&input)
+                .then(|| (!name.starts_with(&input), server.name.as_str()))
+        })
+        .collect();
+    matches.sort_by_key(|(not_prefix, _)| *not_prefix);
+    matches
+        .into_iter()
+        .take(AUTOCOMPLETE_MAX_CHOICES)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// 有効な機能フラグの一覧を表示用の文字列にする。
+fn format_enabled_features(features: &FeaturesConfig) -> String {
+    let names = features
+        .enabled_features()
+        .into_iter()
+        .map(|feature| format!("`{}`", feature.name()))
+        .collect::<Vec<_>>();
+    if names.is_empty() {
+        "なし".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+/// サブコマンドに指定された文字列オプションの値を取得する。
+fn subcommand_string_option<'a>(command: &'a CommandInteraction, name: &str) -> Option<&'a str> {
+    let subcommand = command.data.options.first()?;
+    let CommandDataOptionValue::SubCommand(options) = &subcommand.value else {
+        return None;
+    };
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_str())
+}
+
+/// 日時を Discord のタイムスタンプ記法に変換する。未設定の場合は「なし」を返す。
+fn format_discord_timestamp(time: Option<chrono::DateTime<chrono::Utc>>) -> String {
+    match time {
+        Some(time) => format!("<t:{}:f> (<t:{}:R>)", time.timestamp(), time.timestamp()),
+        None => "なし".to_string(),
+    }
+}
+
+/// 文字列を指定した文字数までに切り詰める。
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        format!("{}...", text.chars().take(max_chars).collect::<String>())
+    }
+}
+
+/// サーバーステータスをDiscordチャンネルに通知するための構造体。
+pub struct StatusNotifier {
+    /// Discord API クライアント（Webhook で投稿する場合はトークンを持たない）
+    http: Arc<Http>,
+    /// 投稿先
+    target: StatusTarget,
+    /// ステータスチェック間隔（フッター表示用）
+    interval: Duration,
+    /// ステータスを投稿するタイミング
+    mode: StatusNotifyMode,
+    /// 前回投稿したときの各サーバーのオンライン状態（変化だけを投稿する場合に使う）
+    previous: HashMap<String, bool>,
+    /// 編集で更新し続けるメッセージ（ダッシュボードとして投稿する場合に使う）
+    dashboard_message: Option<MessageId>,
+}
+
+impl StatusNotifier {
+    /// サーバーステータスをDiscordチャンネルに埋め込みメッセージとして送信する。
+    ///
+    /// 変化だけを投稿する設定では、前回からオンライン/オフラインが変わったサーバーがあるときだけ送信する。
+    /// ダッシュボードとして投稿する設定では、前回のメッセージを編集し、
+    /// 新しくメッセージを投稿した場合はその ID を返す（再起動後も同じメッセージを編集するため）。
+    pub async fn send(&mut self, statuses: &[ServerStatus]) -> Option<MessageId> {
+        let any_offline = statuses.iter().any(|status| !status.online);
+        match self.mode {
+            StatusNotifyMode::Every => {
+                self.post(self.status_embed(statuses), any_offline).await;
+                None
+            }
+            StatusNotifyMode::Changes => {
+                let changes = status::status_changes(&self.previous, statuses);
+                if !changes.is_empty() {
+                    let any_offline = changes.iter().any(|status| !status.online);
+                    self.post(create_status_change_embed(&changes), any_offline)
+                        .await;
+                }
+                self.previous = statuses
+                    .iter()
+                    .map(|status| (status.name.clone(), status.online))
+                    .collect();
+                None
+            }
+            StatusNotifyMode::Dashboard => {
+                let embed = self.status_embed(statuses).timestamp(Timestamp::now());
+                if let Some(message_id) = self.dashboard_message {
+                    match self.edit(message_id, embed.clone()).await {
+                        Ok(()) => return None,
+                        // 削除されたなどで編集できない場合は投稿し直す
+                        Err(e) => {
+                            warn!(error = %e, "Failed to edit status dashboard, posting a new one")
+                        }
+                    }
+                }
+                match self.create(embed, any_offline).await {
+                    Ok(message_id) => {
+                        self.dashboard_message = Some(message_id);
+                        Some(message_id)
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to send status message");
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// 全サーバーのステータスの embed を作成する（チェックの間隔をフッターに付ける）。
+    fn status_embed(&self, statuses: &[ServerStatus]) -> CreateEmbed {
+        create_status_embed(statuses).footer(CreateEmbedFooter::new(format!(
+            "Updated every {}",
+            humantime::format_duration(self.interval)
+        )))
+    }
+
+    /// 一時停止していた通知を再開したことを送信する。
+    pub async fn send_resumed(&self) {
+        let embed = CreateEmbed::new()
+            .title("Status notifications resumed")
+            .color(0x5865f2);
+        self.post(embed, false).await;
+    }
+
+    /// 埋め込みメッセージを投稿先に送信する。
+    async fn post(&self, embed: CreateEmbed, any_offline: bool) {
+        if let Err(e) = self.create(embed, any_offline).await {
+            error!(error = %e, "Failed to send status message");
+        }
+    }
+
+    /// 埋め込みメッセージを投稿先に送信し、投稿したメッセージの ID を返す。
+    async fn create(&self, embed: CreateEmbed, any_offline: bool) -> serenity::Result<MessageId> {
+        match &self.target {
+            StatusTarget::Channel(channel_id) => {
+                let message = CreateMessage::new().embed(embed);
+                let message = channel_id.send_message(&self.http, message).await?;
+                Ok(message.id)
+            }
+            StatusTarget::Webhook {
+                webhook_id,
+                token,
+                config,
+            } => {
+                let (username, avatar_url) = config.appearance(any_offline);
+                let mut message = ExecuteWebhook::new().embed(embed);
+                if let Some(username) = username {
+                    message = message.username(username);
+                }
+                if let Some(avatar_url) = avatar_url {
+                    message = message.avatar_url(avatar_url);
+                }
+                // 投稿したメッセージの ID を受け取るため、投稿の完了を待つ
+                let message = message
+                    .execute(&self.http, (*webhook_id, token, true))
+                    .await?
+                    .ok_or(serenity::Error::Other("Webhook did not return the message"))?;
+                Ok(message.id)
+            }
+        }
+    }
+
+    /// 投稿済みのメッセージの埋め込みを差し替える。
+    ///
+    /// Webhook の表示名やアイコンは編集では変えられないため、投稿したときのままになる。
+    async fn edit(&self, message_id: MessageId, embed: CreateEmbed) -> serenity::Result<()> {
+        match &self.target {
+            StatusTarget::Channel(channel_id) => {
+                let message = EditMessage::new().embed(embed);
+                channel_id
+                    .edit_message(&self.http, message_id, message)
+                    .await?;
+            }
+            StatusTarget::Webhook {
+                webhook_id, token, ..
+            } => {
+                EditWebhookMessage::new()
+                    .embed(embed)
+                    .execute(&self.http, (*webhook_id, token, message_id))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// サーバーステータスの投稿先。
+enum StatusTarget {
+    /// Bot のユーザーとしてチャンネルに投稿する
+    Channel(ChannelId),
+    /// Webhook で投稿する（Bot のトークンやゲートウェイの接続を使わない）
+    Webhook {
+        webhook_id: WebhookId,
+        token: String,
+        config: StatusWebhookConfig,
+    },
+}
+
+impl StatusTarget {
+    /// 設定から投稿先を決める。Webhook の URL が不正な場合はエラーを返す。
+    fn from_config(config: &Config) -> Result<Self> {
+        let Some(webhook) = &config.status.webhook else {
+            return Ok(StatusTarget::Channel(ChannelId::new(
+                config.discord.status_channel_id,
+            )));
+        };
+        let url = reqwest::Url::parse(&webhook.url).context("Invalid status webhook URL")?;
+        let (webhook_id, token) =
+            parse_webhook(&url).context("Status webhook URL is not a Discord webhook URL")?;
+        Ok(StatusTarget::Webhook {
+            webhook_id,
+            token: token.to_string(),
+            config: webhook.clone(),
+        })
+    }
+}
+
+/// Discord Bot を起動し、イベントループを開始する。
+///
+/// 日報機能が有効な場合は、日報ストアや Notion のクライアントを用意してから接続する。
+pub async fn run(
+    config: Config,
+    bot_store: BotStore,
+    status_rx: mpsc::Receiver<Vec<ServerStatus>>,
+    email_notifier: Option<Arc<EmailNotifier>>,
+    status_snooze: StatusSnooze,
+) -> Result<()> {
+    for name in config.features.unknown_flags() {
+        warn!(flag = %name, "Unknown feature flag in configuration, ignoring");
+    }
+    let enabled_features = config
+        .features
+        .enabled_features()
+        .into_iter()
+        .map(|feature| feature.name())
+        .collect::<Vec<_>>();
+    info!(?enabled_features, "Feature flags loaded");
+
+    let status_target = StatusTarget::from_config(&config)?;
+    let update_checker = config
+        .update_check
+        .as_ref()
+        .map(|update_config| UpdateChecker::new(&update_config.repository).map(Arc::new))
+        .transpose()?;
+    let scheduler = jobs::create_scheduler(&config)?;
+    let diary = DiaryState::new(&config, &bot_store, email_notifier).await?;
+
+    let handler = Handler {
+        config: config.clone(),
+        bot_store,
+        scheduler,
+        diary,
+        status_snooze,
+        last_status_check: Arc::default(),
+        update_checker,
+    };
+    handler.restore_status_snooze().await?;
+    let intents = GatewayIntents::GUILDS | diary::gateway_intents(&config);
+
+    let mut client = Client::builder(&config.discord.token, intents)
+        .event_handler(handler.clone())
+        .await
+        .context("Failed to create Discord client")?;
+
+    // Webhook での投稿には Bot のトークンを使わない
+    let http = match &status_target {
+        StatusTarget::Channel(_) => client.http.clone(),
+        StatusTarget::Webhook { .. } => Arc::new(Http::new("")),
+    };
+    let interval = config.status.interval;
+
+    let notifier = StatusNotifier {
+        http,
+        target: status_target,
+        interval,
+        mode: config.status.notify_mode,
+        previous: HashMap::new(),
+        dashboard_message: handler.saved_status_dashboard_message().await,
+    };
+
+    tokio::spawn(run_status_receiver(notifier, status_rx, handler.clone()));
+
+    // 定期実行ジョブと日報向けの定期タスクを起動
+    tokio::spawn(jobs::run_scheduled_jobs(
+        handler.clone(),
+        client.http.clone(),
+        PERIODIC_TASK_INTERVAL,
+    ));
+    diary::spawn_periodic_tasks(handler.clone(), client.http.clone(), PERIODIC_TASK_INTERVAL);
+
+    if let Some(update_config) = &config.update_check {
+        let update_handler = handler.clone();
+        let update_http = client.http.clone();
+        let update_interval = update_config.interval;
+        tokio::spawn(async move {
+            run_update_checks(update_handler, update_http, update_interval).await;
+        });
+        info!(interval = ?update_interval, "Update checks started");
+    }
+
+    info!("Starting bot");
+    client.start().await.context("Discord client error")?;
+
+    Ok(())
+}
+
+/// ステータスモニターからの通知を受信し、Discordに転送するループを実行する。
+///
+/// 通知の一時停止中は転送せず、期限が過ぎたら再開したことを通知してから転送を再開する。
+/// 変化だけを投稿する設定では、一時停止の間に変わったサーバーを再開後にまとめて通知する。
+async fn run_status_receiver(
+    mut notifier: StatusNotifier,
+    mut rx: mpsc::Receiver<Vec<ServerStatus>>,
+    handler: Handler,
+) {
+    while let Some(statuses) = rx.recv().await {
+        // 消費電力の見積もりに使うため、通知の一時停止中もオンラインの期間は記録する
+        handler.record_uptime(&statuses).await;
+
+        let now = chrono::Utc::now();
+        if handler.status_snooze.is_snoozed(now) {
+            continue;
+        }
+        if handler.status_snooze.take_expired(now) {
+            if let Err(e) = handler.clear_saved_status_snooze().await {
+                error!(error = %e, "Failed to clear status snooze");
+            }
+            info!("Status notifications resumed");
+            notifier.send_resumed().await;
+        }
+        if let Some(message_id) = notifier.send(&statuses).await {
+            handler.save_status_dashboard_message(message_id).await;
+        }
+    }
+}
+
+/// 新しいバージョンを定期的に確認する（起動直後にも確認する）。
+async fn run_update_checks(handler: Handler, http: Arc<Http>, interval: Duration) {
+    let mut interval_timer = tokio::time::interval(interval);
+
+    loop {
+        interval_timer.tick().await;
+
+        if let Err(error) = handler.check_for_update(&http).await {
+            warn!(error = %error, "Update check failed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone as _;
+
+    use super::*;
+    use crate::{config::ServerLink, ping::PingResult, status::PortStatus};
+
+    #[test]
+    fn test_format_discord_timestamp() {
+        let time = chrono::Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            format_discord_timestamp(Some(time)),
+            "<t:1735689600:f> (<t:1735689600:R>)"
+        );
+        assert_eq!(format_discord_timestamp(None), "なし");
+    }
+
+    #[test]
+    fn test_format_enabled_features() {
+        assert_eq!(
+            format_enabled_features(&FeaturesConfig::default()),
+            "`ogp_captions`, `heic_conversion`, `spoiler_toggle`, `markdown_tables`, `markdown_quotes`, `math_equations`, `code_blocks`"
+        );
+    }
+
+    #[test]
+    fn test_format_wol_result() {
+        assert_eq!(
+            format_wol_result(
+                "Main Server",
+                Some(Duration::from_millis(42_500)),
+                Duration::from_secs(300)
+            ),
+            "🟢 Main Server が起動しました (42 秒)"
+        );
+        assert_eq!(
+            format_wol_result("Main Server", None, Duration::from_secs(300)),
+            "🔴 Main Server は 300 秒以内に起動しませんでした"
+        );
+    }
+
+    #[test]
+    fn test_format_wake_summary() {
+        let sent_at = chrono::Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let wake = |id, requested_by, boot_secs: Option<i64>| WakeRecord {
+            id,
+            server_name: "Main Server".to_string(),
+            requested_by,
+            sent_at,
+            online_at: boot_secs.map(|secs| sent_at + chrono::Duration::seconds(secs)),
+        };
+
+        assert_eq!(format_wake_summary(&[]), None);
+        assert_eq!(
+            format_wake_summary(&[
+                wake(3, WakeRequester::Discord(42), Some(58)),
+                wake(2, WakeRequester::Schedule, Some(62))
+            ])
+            .unwrap(),
+            "**Last woken:** <t:1735689600:R> by <@42>, booted in 58s\n**Average boot:** 1m (last 2 boots)"
+        );
+        assert_eq!(
+            format_wake_summary(&[wake(1, WakeRequester::Schedule, None)]).unwrap(),
+            "**Last woken:** <t:1735689600:R> by schedule, boot not confirmed"
+        );
+        assert_eq!(
+            format_wake_summary(&[wake(4, WakeRequester::Telegram(7), None)]).unwrap(),
+            "**Last woken:** <t:1735689600:R> by Telegram user 7, boot not confirmed"
+        );
+    }
+
+    #[test]
+    fn test_create_status_embed() {
+        let statuses = vec![
+            ServerStatus {
+                name: "Main Server".to_string(),
+                online: true,
+                ping: PingResult {
+                    sent: 3,
+                    received: 3,
+                    rtt_min: Some(Duration::from_micros(800)),
+                    rtt_avg: Some(Duration::from_micros(1_200)),
+                    rtt_max: Some(Duration::from_micros(2_000)),
+                },
+                ports: vec![
+                    PortStatus {
+                        port: 22,
+                        open: true,
+                    },
+                    PortStatus {
+                        port: 80,
+                        open: false,
+                    },
+                ],
+                health: Some(HealthStatus {
+                    status_code: Some(503),
+                    response_time: Some(Duration::from_millis(42)),
+                }),
+            },
+            ServerStatus {
+                name: "Storage Server".to_string(),
+                online: false,
+                ping: PingResult::default(),
+                ports: vec![],
+                health: None,
+            },
+        ];
+        let embed = serde_json::to_value(create_status_embed(&statuses)).unwrap();
+        assert_eq!(
+            embed["fields"][0]["value"],
+            "Online\n1.2 ms (0.8–2.0)\nPorts: 22 ✅ · 80 ❌\nHealth: 503 ❌ (42 ms)"
+        );
+        assert_eq!(embed["fields"][1]["value"], "Offline");
+    }
+
+    #[test]
+    fn test_format_status_change() {
+        let status = |online| ServerStatus {
+            name: "Main Server".to_string(),
+            online,
+            ping: PingResult::default(),
+            ports: vec![],
+            health: None,
+        };
+        assert_eq!(
+            format_status_change(&status(false)),
+            "⚠️ Main Server went offline"
+        );
+        assert_eq!(
+            format_status_change(&status(true)),
+            "✅ Main Server came online"
+        );
+    }
+
+    #[test]
+    fn test_status_refresh_wait() {
+        let now = Instant::now();
+        assert_eq!(status_refresh_wait(None, now), None);
+        assert_eq!(
+            status_refresh_wait(Some(now), now + Duration::from_secs(3)),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(
+            status_refresh_wait(Some(now), now + STATUS_REFRESH_INTERVAL),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_power_button() {
+        assert_eq!(
+            parse_power_button("reboot_confirm:Main Server"),
+            Some(PowerButton::Confirm(PowerAction::Reboot, "Main Server"))
+        );
+        assert_eq!(
+            parse_power_button("suspend_cancel"),
+            Some(PowerButton::Cancel(PowerAction::Suspend))
+        );
+        assert_eq!(parse_power_button("shutdown_cancelled"), None);
+        assert_eq!(parse_power_button("diary_keyword:1"), None);
+    }
+
+    #[test]
+    fn test_create_power_result_embed() {
+        let outcome = PowerOutcome {
+            exit_code: Some(1),
+            stderr: "sudo: a password is required".to_string(),
+            disconnected: false,
+        };
+        let embed = serde_json::to_value(create_power_result_embed(
+            PowerAction::Reboot,
+            "Main Server",
+            &Ok(outcome),
+        ))
+        .unwrap();
+        assert_eq!(embed["title"], "Reboot: Main Server");
+        assert_eq!(embed["color"], 0xff0000);
+        assert_eq!(embed["fields"][0]["value"], "1");
+        assert_eq!(
+            embed["fields"][1]["value"],
+            "```\nsudo: a password is required\n```"
+        );
+
+        let embed = serde_json::to_value(create_power_result_embed(
+            PowerAction::Shutdown,
+            "Main Server",
+            &Err(anyhow::anyhow!("ssh timed out")),
+        ))
+        .unwrap();
+        assert_eq!(embed["description"], "Error: ssh timed out");
+        assert!(embed.get("fields").is_none());
+    }
+
+    #[test]
+    fn test_create_server_detail_embed() {
+        let server = ServerConfig {
+            name: "Main Server".to_string(),
+            notes: Some("Check the UPS first.\nDisks mount slowly.\n".to_string()),
+            links: vec![
+                ServerLink {
+                    label: "Runbook".to_string(),
+                    url: "https://wiki.example.com/main".to_string(),
+                },
+                ServerLink {
+                    label: "BMC".to_string(),
+                    url: "https://192.168.1.200".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+        let embed = serde_json::to_value(create_server_detail_embed(&server, None)).unwrap();
+        assert_eq!(embed["title"], "Main Server");
+        assert_eq!(embed["description"], "Example server");
+        assert_eq!(embed["fields"][0]["value"], "192.168.1.100");
+        assert_eq!(embed["fields"][2]["name"], "Notes");
+        assert_eq!(
+            embed["fields"][2]["value"],
+            "Check the UPS first.\nDisks mount slowly."
+        );
+        assert_eq!(
+            embed["fields"][3]["value"],
+            "[Runbook](https://wiki.example.com/main)\n[BMC](https://192.168.1.200)"
+        );
+
+        let server = ServerConfig {
+            notes: Some("  ".to_string()),
+            ..Default::default()
+        };
+        let embed = serde_json::to_value(create_server_detail_embed(&server, None)).unwrap();
+        assert_eq!(embed["fields"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_server_name_suggestions() {
+        let servers: Vec<ServerConfig> = ["nas", "Game-PC", "backup-nas"]
+            .into_iter()
+            .map(|name| ServerConfig {
+                name: name.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        assert_eq!(
+            server_name_suggestions(&servers, ""),
+            vec!["nas", "Game-PC", "backup-nas"]
+        );
+        assert_eq!(
+            server_name_suggestions(&servers, "NAS"),
+            vec!["nas", "backup-nas"]
+        );
+        assert_eq!(server_name_suggestions(&servers, "ga"), vec!["Game-PC"]);
+        assert!(server_name_suggestions(&servers, "web").is_empty());
+
+        let many: Vec<ServerConfig> = (0..30)
+            .map(|i| ServerConfig {
+                name: format!("server-{}", i),
+                ..Default::default()
+            })
+            .collect();
+        assert_eq!(
+            server_name_suggestions(&many, "server").len(),
+            AUTOCOMPLETE_MAX_CHOICES
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "diary")]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("abc", 3), "abc");
+        assert_eq!(truncate_chars("あいうえお", 2), "あい...");
+    }
+
+    #[test]
+    fn test_diff_command_definitions() {
+        let desired = vec![
+            serde_json::json!({
+                "name": "diary",
+                "description": "日報",
+                "options": [{ "type": 1, "name": "new", "description": "作成", "required": false }]
+            }),
+            serde_json::json!({ "name": "ping", "description": "Ping" }),
+        ];
+        let registered = vec![
+            serde_json::json!({
+                "id": "1",
+                "version": "2",
+                "name": "diary",
+                "description": "日報",
+                "options": [{ "type": 1, "name": "new", "description": "作成", "options": [] }]
+            }),
+            serde_json::json!({ "id": "3", "name": "status", "description": "Status" }),
+        ];
+        assert_eq!(
+            diff_command_definitions(&desired, &registered),
+            CommandDiff {
+                added: vec!["ping".to_string()],
+                removed: vec!["status".to_string()],
+                changed: vec![],
+            }
+        );
+
+        let mut renamed = desired.clone();
+        renamed[0]["options"][0]["description"] = serde_json::json!("新規作成");
+        assert_eq!(
+            diff_command_definitions(&renamed, &desired).changed,
+            vec!["diary".to_string()]
+        );
+        assert!(diff_command_definitions(&desired, &desired).is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+//! サーバーの消費電力の見積もりのコマンド。
+//!
+//! ステータスモニターでオンラインを確認した期間をボットの状態のストアに記録し、
+//! 設定したサーバーごとの消費電力から電力量と電気代を見積もる。
+
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use serenity::all::{
+    CommandInteraction, CreateCommand, CreateCommandOption, CreateEmbed, CreateEmbedFooter,
+    CreateInteractionResponse, CreateInteractionResponseMessage,
+};
+use serenity::{client::Context as SerenityContext, model::application::CommandOptionType};
+use tracing::warn;
+
+use crate::{
+    config::{PowerConfig, StatsPeriod},
+    status::ServerStatus,
+    store::BotStorage as _,
+};
+
+use super::{Handler, subcommand_string_option};
+
+/// 同じオンラインの期間として扱う確認の間隔（ステータスチェックの間隔に対する倍数）。
+///
+/// 1 回分のチェックが遅れても期間が途切れないよう、間隔の 2 倍まで許す。
+const UPTIME_GAP_FACTOR: u32 = 2;
+
+/// `/power` コマンドの定義を返す。
+pub fn power_command() -> CreateCommand {
+    let period = StatsPeriod::ALL.into_iter().fold(
+        CreateCommandOption::new(
+            CommandOptionType::String,
+            "period",
+            "Period to estimate (default: last 7 days)",
+        ),
+        |option, period| option.add_string_choice(period.label(), period.name()),
+    );
+
+    CreateCommand::new("power")
+        .description("Server power usage")
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "report",
+                "Estimate the energy used and its cost per server",
+            )
+            .add_sub_option(period),
+        )
+}
+
+impl Handler {
+    pub async fn handle_power_usage(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let subcommand = command
+            .data
+            .options
+            .first()
+            .map(|opt| opt.name.as_str())
+            .unwrap_or("");
+
+        match subcommand {
+            "report" => self.handle_power_report(ctx, command).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn handle_power_report(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let period = match subcommand_string_option(command, "period") {
+            Some(name) => {
+                StatsPeriod::from_name(name).with_context(|| format!("Unknown period: {}", name))?
+            }
+            None => StatsPeriod::default(),
+        };
+
+        let response = CreateInteractionResponseMessage::new()
+            .embed(self.power_report_embed(period).await?)
+            .ephemeral(false);
+
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 期間内のサーバーごとの電力量と電気代の見積もりを埋め込みにする。
+    pub async fn power_report_embed(&self, period: StatsPeriod) -> Result<CreateEmbed> {
+        let since = period
+            .duration()
+            .map(|duration| chrono::Utc::now() - duration);
+        let spans = self.bot_store.get_uptime_spans(since).await?;
+        let power = &self.config.power;
+
+        let mut embed = CreateEmbed::new()
+            .title("Power Report")
+            .color(0xf1c40f)
+            .footer(CreateEmbedFooter::new(format!(
+                "{} · estimated from status checks",
+                period.label()
+            )));
+
+        let mut total_kwh = 0.0;
+        let mut estimated = 0;
+        for server in &self.config.servers {
+            let Some(watts) = server.watts else {
+                continue;
+            };
+            let uptime = spans
+                .iter()
+                .filter(|span| span.server_name == server.name)
+                .map(|span| span.duration_since(since))
+                .sum();
+            let kwh = estimate_kwh(uptime, watts);
+            total_kwh += kwh;
+            estimated += 1;
+            embed = embed.field(
+                &server.name,
+                format_power_usage(Some(uptime), kwh, power),
+                true,
+            );
+        }
+
+        if estimated == 0 {
+            return Ok(embed.description(
+                "Set `watts` for servers in the configuration to estimate power usage",
+            ));
+        }
+        Ok(embed.field("Total", format_power_usage(None, total_kwh, power), false))
+    }
+
+    /// ステータスチェックでオンラインだったサーバーを記録する。
+    pub async fn record_uptime(&self, statuses: &[ServerStatus]) {
+        let now = chrono::Utc::now();
+        let max_gap = self.config.status.interval * UPTIME_GAP_FACTOR;
+        for status in statuses.iter().filter(|status| status.online) {
+            if let Err(e) = self
+                .bot_store
+                .record_server_online(&status.name, now, max_gap)
+                .await
+            {
+                warn!(error = %e, server = %status.name, "Failed to record server uptime");
+            }
+        }
+    }
+}
+
+/// 起動していた時間と消費電力から電力量（kWh）を見積もる。
+fn estimate_kwh(uptime: Duration, watts: f64) -> f64 {
+    watts * uptime.as_secs_f64() / 3600.0 / 1000.0
+}
+
+/// 電力量の見積もりを表示用の文字列にする（電気代の単価を設定していれば電気代も付ける）。
+fn format_power_usage(uptime: Option<Duration>, kwh: f64, config: &PowerConfig) -> String {
+    let mut lines = Vec::new();
+    if let Some(uptime) = uptime {
+        let minutes = Duration::from_secs(uptime.as_secs() / 60 * 60);
+        lines.push(format!("Uptime: {}", humantime::format_duration(minutes)));
+    }
+    lines.push(format!("Energy: {:.2} kWh", kwh));
+    if let Some(price) = config.price_per_kwh {
+        lines.push(format!("Cost: {:.2} {}", kwh * price, config.currency));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_power_usage() {
+        let uptime = Duration::from_secs(12 * 3600 + 30 * 60 + 15);
+        let kwh = estimate_kwh(uptime, 80.0);
+        assert!((kwh - 1.000_333).abs() < 1e-6);
+
+        let mut config = PowerConfig::default();
+        assert_eq!(
+            format_power_usage(Some(uptime), kwh, &config),
+            "Uptime: 12h 30m\nEnergy: 1.00 kWh"
+        );
+
+        config.price_per_kwh = Some(31.0);
+        assert_eq!(
+            format_power_usage(None, 2.5, &config),
+            "Energy: 2.50 kWh\nCost: 77.50 JPY"
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! 日報フォーラムの統計コマンド。
+//!
+//! 日報スレッドに付いたリアクションを日報のデータベースに記録し、よく使われた絵文字を集計する。
+
+use anyhow::{Context as _, Result};
+use serenity::all::{
+    CommandInteraction, CreateCommand, CreateCommandOption, CreateEmbed, CreateEmbedFooter,
+    CreateInteractionResponse, CreateInteractionResponseMessage, MessageId, Reaction,
+};
+use serenity::{client::Context as SerenityContext, model::application::CommandOptionType};
+use tracing::error;
+
+use crate::{
+    config::StatsPeriod,
+    diary::{DiaryStorage as _, EmojiCount},
+};
+
+use super::{Handler, subcommand_string_option};
+
+/// `/stats emoji` で表示する絵文字の数。
+const EMOJI_STATS_LIMIT: i64 = 10;
+
+/// `/stats` コマンドの定義を返す。
+pub fn stats_command() -> CreateCommand {
+    let period = StatsPeriod::ALL.into_iter().fold(
+        CreateCommandOption::new(
+            CommandOptionType::String,
+            "period",
+            "Period to summarize (default: last 7 days)",
+        ),
+        |option, period| option.add_string_choice(period.label(), period.name()),
+    );
+
+    CreateCommand::new("stats")
+        .description("Diary forum statistics")
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "emoji",
+                "Show the most-used reactions in diary threads",
+            )
+            .add_sub_option(period),
+        )
+}
+
+impl Handler {
+    pub async fn handle_stats(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let subcommand = command
+            .data
+            .options
+            .first()
+            .map(|opt| opt.name.as_str())
+            .unwrap_or("");
+
+        match subcommand {
+            "emoji" => self.handle_stats_emoji(ctx, command).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn handle_stats_emoji(
+        &self,
+        ctx: &SerenityContext,
+        command: &CommandInteraction,
+    ) -> Result<()> {
+        let period = match subcommand_string_option(command, "period") {
+            Some(name) => {
+                StatsPeriod::from_name(name).with_context(|| format!("Unknown period: {}", name))?
+            }
+            None => StatsPeriod::default(),
+        };
+
+        let response = CreateInteractionResponseMessage::new()
+            .embed(self.emoji_stats_embed(period).await?)
+            .ephemeral(false);
+
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 期間内に日報スレッドでよく使われたリアクションの集計を埋め込みにする。
+    pub async fn emoji_stats_embed(&self, period: StatsPeriod) -> Result<CreateEmbed> {
+        let since = period
+            .duration()
+            .map(|duration| chrono::Utc::now() - duration);
+        let counts = self
+            .diary
+            .store
+            .get_emoji_counts(since, EMOJI_STATS_LIMIT)
+            .await?;
+
+        Ok(CreateEmbed::new()
+            .title("Emoji Stats")
+            .description(format_emoji_counts(&counts))
+            .color(0x5865f2)
+            .footer(CreateEmbedFooter::new(period.label())))
+    }
+
+    /// 日報スレッドのメッセージに付いたリアクションを記録する。
+    ///
+    /// bot が付けたリアクション（同期結果の表示など）は集計に含めない。
+    pub async fn record_reaction(&self, reaction: &Reaction) {
+        let Some(user_id) = reaction.user_id else {
+            return;
+        };
+        if reaction
+            .member
+            .as_ref()
+            .is_some_and(|member| member.user.bot)
+        {
+            return;
+        }
+
+        let result = async {
+            if self
+                .diary
+                .store
+                .get_by_thread(reaction.channel_id.get())
+                .await?
+                .is_none()
+            {
+                return Ok(());
+            }
+            self.diary
+                .store
+                .record_reaction(
+                    reaction.message_id.get(),
+                    user_id.get(),
+                    &reaction.emoji.to_string(),
+                    reaction.channel_id.get(),
+                    chrono::Utc::now(),
+                )
+                .await
+        }
+        .await;
+        if let Err(e) = result {
+            error!(error = %e, message_id = reaction.message_id.get(), "Failed to record reaction");
+        }
+    }
+
+    /// 外されたリアクションの記録を削除する。
+    pub async fn handle_reaction_remove(&self, reaction: &Reaction) {
+        let Some(user_id) = reaction.user_id else {
+            return;
+        };
+        if let Err(e) = self
+            .diary
+            .store
+            .delete_reaction(
+                reaction.message_id.get(),
+                user_id.get(),
+                &reaction.emoji.to_string(),
+            )
+            .await
+        {
+            error!(error = %e, message_id = reaction.message_id.get(), "Failed to delete reaction");
+        }
+    }
+
+    /// メッセージからまとめて外されたリアクションの記録を削除する。
+    ///
+    /// `emoji` を指定した場合はその絵文字のリアクションだけを削除する。
+    pub async fn handle_reactions_cleared(&self, message_id: MessageId, emoji: Option<&str>) {
+        if let Err(e) = self
+            .diary
+            .store
+            .delete_reactions_by_message(message_id.get(), emoji)
+            .await
+        {
+            error!(error = %e, message_id = message_id.get(), "Failed to delete reactions");
+        }
+    }
+}
+
+/// 絵文字ごとの集計を順位付きの一覧にする。
+fn format_emoji_counts(counts: &[EmojiCount]) -> String {
+    if counts.is_empty() {
+        return "No reactions in this period".to_string();
+    }
+
+    counts
+        .iter()
+        .enumerate()
+        .map(|(index, count)| {
+            format!(
+                "{}. {} × {} ({} user(s))",
+                index + 1,
+                count.emoji,
+                count.count,
+                count.user_count
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_emoji_counts() {
+        assert_eq!(format_emoji_counts(&[]), "No reactions in this period");
+
+        let counts = vec![
+            EmojiCount {
+                emoji: "👍".to_string(),
+                count: 12,
+                user_count: 5,
+            },
+            EmojiCount {
+                emoji: "<:kgd:123>".to_string(),
+                count: 3,
+                user_count: 1,
+            },
+        ];
+        assert_eq!(
+            format_emoji_counts(&counts),
+            "1. 👍 × 12 (5 user(s))\n2. <:kgd:123> × 3 (1 user(s))"
+        );
+    }
+}
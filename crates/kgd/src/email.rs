@@ -0,0 +1,387 @@
+//! 重大なイベントをメール（SMTP）で通知する機能を提供する。
+//!
+//! Discord などのチャットサービスに届かない場合の最後の手段として、
+//! サーバーの長時間の停止・Bot の起動と停止・日報の同期の連続失敗だけを通知する。
+
+#[cfg(feature = "diary")]
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context as _, Result};
+use chrono_tz::Tz;
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport as _, Message, Tokio1Executor,
+    message::{Mailbox, header::ContentType},
+    transport::smtp::authentication::Credentials,
+};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::{
+    config::{EmailConfig, SmtpSecurity},
+    status::ServerStatus,
+};
+
+/// メールで通知する重大なイベント。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CriticalEvent {
+    /// Bot が起動した
+    BotStarted,
+    /// Bot が停止した
+    BotStopped {
+        /// 異常終了の場合のエラー
+        error: Option<String>,
+    },
+    /// サーバーがしきい値以上オフラインになっている
+    ServerDown {
+        /// サーバー名
+        server: String,
+        /// オフラインになってからの時間
+        downtime: Duration,
+    },
+    /// 通知したオフラインのサーバーがオンラインに戻った
+    ServerRecovered {
+        /// サーバー名
+        server: String,
+        /// オフラインだった時間
+        downtime: Duration,
+    },
+    /// 日報の同期が続けて失敗した
+    #[cfg(feature = "diary")]
+    SyncFailures {
+        /// 連続して失敗した回数
+        failures: u32,
+        /// 最後のエラー
+        error: String,
+    },
+}
+
+impl CriticalEvent {
+    /// 件名に使う見出しを返す。
+    fn title(&self) -> String {
+        match self {
+            CriticalEvent::BotStarted => "Bot が起動しました".to_string(),
+            CriticalEvent::BotStopped { error: None } => "Bot が停止しました".to_string(),
+            CriticalEvent::BotStopped { error: Some(_) } => "Bot が異常終了しました".to_string(),
+            CriticalEvent::ServerDown { server, .. } => format!("{} がオフラインです", server),
+            CriticalEvent::ServerRecovered { server, .. } => {
+                format!("{} がオンラインに戻りました", server)
+            }
+            #[cfg(feature = "diary")]
+            CriticalEvent::SyncFailures { .. } => "日報の同期が失敗し続けています".to_string(),
+        }
+    }
+
+    /// 本文を返す。
+    fn body(&self) -> String {
+        match self {
+            CriticalEvent::BotStarted => "kgd が起動しました。".to_string(),
+            CriticalEvent::BotStopped { error: None } => "kgd が停止しました。".to_string(),
+            CriticalEvent::BotStopped { error: Some(error) } => {
+                format!("kgd がエラーで停止しました。\n\n{}", error)
+            }
+            CriticalEvent::ServerDown { server, downtime } => format!(
+                "{} が {} 以上オフラインになっています。",
+                server,
+                format_minutes(*downtime)
+            ),
+            CriticalEvent::ServerRecovered { server, downtime } => format!(
+                "{} がオンラインに戻りました（オフラインだった時間: {}）。",
+                server,
+                format_minutes(*downtime)
+            ),
+            #[cfg(feature = "diary")]
+            CriticalEvent::SyncFailures { failures, error } => format!(
+                "日報の Notion への同期が {} 回続けて失敗しました。\n\n最後のエラー:\n{}",
+                failures, error
+            ),
+        }
+    }
+}
+
+/// 重大なイベントをメールで通知する構造体。
+pub struct EmailNotifier {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+    /// 送信元
+    from: Mailbox,
+    /// 送信先
+    to: Vec<Mailbox>,
+    /// 通知の条件とテンプレート
+    config: EmailConfig,
+    /// 本文の日時に使うタイムゾーン
+    timezone: Tz,
+    /// 日報の同期が続けて失敗している回数
+    #[cfg(feature = "diary")]
+    sync_failures: AtomicU32,
+}
+
+impl EmailNotifier {
+    /// 新しい EmailNotifier を作成する。
+    ///
+    /// # Arguments
+    /// * `config` - メールの設定
+    /// * `timezone` - 本文の日時に使うタイムゾーン
+    pub fn new(config: &EmailConfig, timezone: Tz) -> Result<Self> {
+        let builder = match config.smtp_security {
+            SmtpSecurity::Starttls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)
+                    .context("Failed to create SMTP transport")?
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host)
+                .context("Failed to create SMTP transport")?,
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host)
+            }
+        };
+        let port = config.smtp_port.unwrap_or(match config.smtp_security {
+            SmtpSecurity::Starttls => 587,
+            SmtpSecurity::Tls => 465,
+            SmtpSecurity::None => 25,
+        });
+        let mut builder = builder.port(port);
+        if let Some(username) = &config.username {
+            builder = builder.credentials(Credentials::new(
+                username.clone(),
+                config.password.clone().unwrap_or_default(),
+            ));
+        }
+
+        let from = config
+            .from
+            .parse()
+            .with_context(|| format!("Invalid email address: {}", config.from))?;
+        let to = config
+            .to
+            .iter()
+            .map(|to| {
+                to.parse()
+                    .with_context(|| format!("Invalid email address: {}", to))
+            })
+            .collect::<Result<Vec<Mailbox>>>()?;
+        anyhow::ensure!(!to.is_empty(), "No email recipients configured");
+
+        Ok(Self {
+            mailer: builder.build(),
+            from,
+            to,
+            config: config.clone(),
+            timezone,
+            #[cfg(feature = "diary")]
+            sync_failures: AtomicU32::new(0),
+        })
+    }
+
+    /// イベントをメールで通知する。
+    ///
+    /// 送信に失敗してもログに残すだけにする。
+    pub async fn send(&self, event: &CriticalEvent) {
+        match self.send_inner(event).await {
+            Ok(()) => info!(event = ?event, "Email notification sent"),
+            Err(e) => error!(error = %e, event = ?event, "Failed to send email notification"),
+        }
+    }
+
+    async fn send_inner(&self, event: &CriticalEvent) -> Result<()> {
+        let title = event.title();
+        let body = event.body();
+        let time = chrono::Utc::now()
+            .with_timezone(&self.timezone)
+            .format("%Y-%m-%d %H:%M:%S %Z")
+            .to_string();
+
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(render_template(
+                &self.config.subject_template,
+                &title,
+                &body,
+                &time,
+            ))
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        let message = builder
+            .body(render_template(
+                &self.config.body_template,
+                &title,
+                &body,
+                &time,
+            ))
+            .context("Failed to build email")?;
+
+        self.mailer
+            .send(message)
+            .await
+            .context("Failed to send email via SMTP")?;
+        Ok(())
+    }
+
+    /// ステータスモニターの結果を受け取り、長時間オフラインのサーバーを通知し続ける。
+    pub async fn run(self: Arc<Self>, mut status_rx: mpsc::Receiver<Vec<ServerStatus>>) {
+        let mut tracker = DowntimeTracker::default();
+        while let Some(statuses) = status_rx.recv().await {
+            for event in tracker.update(&statuses, Instant::now(), self.config.down_threshold) {
+                self.send(&event).await;
+            }
+        }
+    }
+
+    /// 日報の同期に成功したことを記録する（連続失敗の回数を戻す）。
+    #[cfg(feature = "diary")]
+    pub fn record_sync_success(&self) {
+        self.sync_failures.store(0, Ordering::Relaxed);
+    }
+
+    /// 日報の同期に失敗したことを記録し、連続失敗がしきい値に達したときだけ通知する。
+    #[cfg(feature = "diary")]
+    pub async fn record_sync_failure(&self, error: &anyhow::Error) {
+        let failures = self.sync_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures == self.config.sync_failure_threshold.max(1) {
+            self.send(&CriticalEvent::SyncFailures {
+                failures,
+                error: format!("{:#}", error),
+            })
+            .await;
+        }
+    }
+}
+
+/// サーバーがオフラインになった時刻を追跡し、しきい値を超えたときと戻ったときのイベントを作る。
+#[derive(Default)]
+struct DowntimeTracker {
+    /// オフラインのサーバーと、オフラインを最初に確認した時刻
+    offline_since: HashMap<String, Instant>,
+    /// オフラインを通知済みのサーバー
+    alerted: HashSet<String>,
+}
+
+impl DowntimeTracker {
+    /// ステータスモニターの結果を反映し、通知するイベントを返す。
+    fn update(
+        &mut self,
+        statuses: &[ServerStatus],
+        now: Instant,
+        threshold: Duration,
+    ) -> Vec<CriticalEvent> {
+        let mut events = Vec::new();
+        for status in statuses {
+            if status.online {
+                let Some(since) = self.offline_since.remove(&status.name) else {
+                    continue;
+                };
+                if self.alerted.remove(&status.name) {
+                    events.push(CriticalEvent::ServerRecovered {
+                        server: status.name.clone(),
+                        downtime: now.duration_since(since),
+                    });
+                }
+            } else {
+                let since = *self.offline_since.entry(status.name.clone()).or_insert(now);
+                let downtime = now.duration_since(since);
+                if downtime >= threshold && self.alerted.insert(status.name.clone()) {
+                    events.push(CriticalEvent::ServerDown {
+                        server: status.name.clone(),
+                        downtime,
+                    });
+                }
+            }
+        }
+        events
+    }
+}
+
+/// テンプレートの `{title}`・`{body}`・`{time}` を置き換える。
+fn render_template(template: &str, title: &str, body: &str, time: &str) -> String {
+    template
+        .replace("{title}", title)
+        .replace("{body}", body)
+        .replace("{time}", time)
+}
+
+/// 時間を分単位で表す（1 分未満は切り捨てる）。
+fn format_minutes(duration: Duration) -> String {
+    humantime::format_duration(Duration::from_secs(duration.as_secs() / 60 * 60)).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_downtime_tracker() {
+        let threshold = Duration::from_secs(30 * 60);
+        let start = Instant::now();
+        let mut tracker = DowntimeTracker::default();
+
+        assert!(
+            tracker
+                .update(&[ServerStatus::for_test("Main", false)], start, threshold)
+                .is_empty()
+        );
+        let at = start + Duration::from_secs(31 * 60);
+        assert_eq!(
+            tracker.update(&[ServerStatus::for_test("Main", false)], at, threshold),
+            vec![CriticalEvent::ServerDown {
+                server: "Main".to_string(),
+                downtime: Duration::from_secs(31 * 60),
+            }]
+        );
+        // 通知済みのサーバーは繰り返し通知しない
+        let at = start + Duration::from_secs(60 * 60);
+        assert!(
+            tracker
+                .update(&[ServerStatus::for_test("Main", false)], at, threshold)
+                .is_empty()
+        );
+        assert_eq!(
+            tracker.update(&[ServerStatus::for_test("Main", true)], at, threshold),
+            vec![CriticalEvent::ServerRecovered {
+                server: "Main".to_string(),
+                downtime: Duration::from_secs(60 * 60),
+            }]
+        );
+
+        // しきい値未満で戻った場合は通知しない
+        tracker.update(&[ServerStatus::for_test("Main", false)], at, threshold);
+        assert!(
+            tracker
+                .update(
+                    &[ServerStatus::for_test("Main", true)],
+                    at + Duration::from_secs(60),
+                    threshold
+                )
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_render_template() {
+        let event = CriticalEvent::ServerDown {
+            server: "Main".to_string(),
+            downtime: Duration::from_secs(31 * 60 + 20),
+        };
+        assert_eq!(
+            render_template(
+                "[kgd] {title}",
+                &event.title(),
+                &event.body(),
+                "2026-01-02 03:04:05 JST"
+            ),
+            "[kgd] Main がオフラインです"
+        );
+        assert_eq!(
+            render_template(
+                "{body}\n\n{time}",
+                &event.title(),
+                &event.body(),
+                "2026-01-02 03:04:05 JST"
+            ),
+            "Main が 31m 以上オフラインになっています。\n\n2026-01-02 03:04:05 JST"
+        );
+    }
+}
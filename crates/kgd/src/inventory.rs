@@ -0,0 +1,335 @@
+//! 設定したサーバーの一覧（インベントリ）を CSV・JSON で書き出す機能を提供する。
+//!
+//! サーバーの設定に、現在のステータス・稼働率・最後に Wake-on-LAN で起動した日時・
+//! MAC アドレスのベンダーを加えて書き出す。ベンダーはシステムの OUI のデータベースから引く。
+
+use std::{collections::HashMap, fs};
+
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Utc};
+use macaddr::MacAddr6;
+use serde::Serialize;
+
+use crate::{
+    config::{Config, ServerConfig},
+    status::{self, ServerStatus},
+    store::{BotStorage as _, BotStore},
+};
+
+/// OUI のデータベースを探すパス（Debian の ieee-data と nmap のファイル）。
+const OUI_PATHS: &[&str] = &[
+    "/usr/share/ieee-data/oui.txt",
+    "/var/lib/ieee-data/oui.txt",
+    "/usr/share/misc/oui.txt",
+    "/usr/share/nmap/nmap-mac-prefixes",
+];
+
+/// 稼働率を集計する期間（記録を始めてからこれより短い場合は記録を始めてからの期間）。
+const UPTIME_WINDOW: chrono::Duration = chrono::Duration::days(30);
+
+/// インベントリを書き出す形式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// CSV（1 行目は列名）
+    Csv,
+    /// JSON の配列
+    Json,
+}
+
+impl ExportFormat {
+    /// すべての形式。
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::Csv, ExportFormat::Json];
+
+    /// コマンドで指定する名前を返す（ファイルの拡張子にも使う）。
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    /// 名前から形式を返す。
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.name() == name)
+    }
+}
+
+/// インベントリの 1 台分の行。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InventoryRow {
+    /// サーバー名
+    pub name: String,
+    /// IP アドレス
+    pub ip_address: String,
+    /// MAC アドレス
+    pub mac_address: String,
+    /// MAC アドレスのベンダー（OUI のデータベースに無い場合やローカルで割り当てたアドレスは None）
+    pub vendor: Option<String>,
+    /// サーバーの説明文
+    pub description: String,
+    /// 書き出したときにオンラインだったか
+    pub online: bool,
+    /// 稼働率（%）（稼働の記録が無い場合は None）
+    pub uptime_percent: Option<f64>,
+    /// 最後に Wake-on-LAN のパケットを送った日時（記録が無い場合は None）
+    pub last_wake: Option<DateTime<Utc>>,
+}
+
+/// ボットの状態のストアに記録したサーバーごとの稼働率と最後に起動した日時。
+#[derive(Debug, Clone, Default)]
+pub struct InventoryHistory {
+    /// サーバー名ごとの稼働率（%）
+    uptime_percent: HashMap<String, f64>,
+    /// サーバー名ごとの最後に Wake-on-LAN のパケットを送った日時
+    last_wake: HashMap<String, DateTime<Utc>>,
+}
+
+impl InventoryHistory {
+    /// ストアからサーバーごとの稼働率と最後に起動した日時を読み込む。
+    pub async fn load(store: &BotStore, servers: &[ServerConfig]) -> Result<Self> {
+        let now = Utc::now();
+        let spans = store.get_uptime_spans(Some(now - UPTIME_WINDOW)).await?;
+
+        let mut history = Self::default();
+        // 記録を始めてから集計期間に満たない場合は、記録を始めてからの割合にする
+        if let Some(window_start) = spans
+            .iter()
+            .map(|span| span.started_at)
+            .min()
+            .map(|started_at| started_at.max(now - UPTIME_WINDOW))
+        {
+            let window = (now - window_start).to_std().unwrap_or_default();
+            if !window.is_zero() {
+                for server in servers {
+                    let uptime: std::time::Duration = spans
+                        .iter()
+                        .filter(|span| span.server_name == server.name)
+                        .map(|span| span.duration_since(Some(window_start)))
+                        .sum();
+                    let percent = (uptime.as_secs_f64() / window.as_secs_f64() * 100.0).min(100.0);
+                    history.uptime_percent.insert(server.name.clone(), percent);
+                }
+            }
+        }
+
+        for server in servers {
+            if let Some(wake) = store.get_wake_history(&server.name, 1).await?.first() {
+                history.last_wake.insert(server.name.clone(), wake.sent_at);
+            }
+        }
+        Ok(history)
+    }
+}
+
+/// MAC アドレスの先頭 3 バイト（OUI）からベンダー名を引くデータベース。
+#[derive(Debug, Clone, Default)]
+pub struct OuiDatabase {
+    vendors: HashMap<[u8; 3], String>,
+}
+
+impl OuiDatabase {
+    /// システムにある OUI のデータベースを読み込む（見つからない場合は空にする）。
+    pub fn load() -> Self {
+        OUI_PATHS
+            .iter()
+            .find_map(|path| fs::read_to_string(path).ok())
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    /// IEEE の oui.txt か nmap の nmap-mac-prefixes の形式のデータベースを読み込む。
+    fn parse(content: &str) -> Self {
+        let mut vendors = HashMap::new();
+        for line in content.lines() {
+            let line = line.trim();
+            let Some((prefix, rest)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            let Some(oui) = parse_oui(prefix) else {
+                continue;
+            };
+            let vendor = rest
+                .trim()
+                .trim_start_matches("(hex)")
+                .trim_start_matches("(base 16)")
+                .trim();
+            if !vendor.is_empty() {
+                vendors.entry(oui).or_insert_with(|| vendor.to_string());
+            }
+        }
+        Self { vendors }
+    }
+
+    /// MAC アドレスのベンダー名を返す。
+    ///
+    /// ローカルで割り当てたアドレス（仮想マシンやランダム化したアドレス）はベンダーを持たない。
+    pub fn vendor(&self, mac_address: MacAddr6) -> Option<String> {
+        let bytes = mac_address.as_bytes();
+        if bytes[0] & 0x02 != 0 {
+            return None;
+        }
+        self.vendors.get(&[bytes[0], bytes[1], bytes[2]]).cloned()
+    }
+}
+
+/// `00-11-22`・`00:11:22`・`001122` の形式の OUI を読み取る。
+fn parse_oui(prefix: &str) -> Option<[u8; 3]> {
+    let hex: String = prefix.chars().filter(|c| *c != '-' && *c != ':').collect();
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let byte = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+    Some([byte(0)?, byte(2)?, byte(4)?])
+}
+
+/// サーバーのステータスを確認し、インベントリの行を作成する。
+pub async fn collect(config: &Config, history: &InventoryHistory) -> Vec<InventoryRow> {
+    let statuses = status::check_servers(&config.servers, status::PING_TIMEOUT).await;
+    build_rows(&config.servers, &statuses, history, &OuiDatabase::load())
+}
+
+/// サーバーの設定とステータス・記録からインベントリの行を作成する。
+fn build_rows(
+    servers: &[ServerConfig],
+    statuses: &[ServerStatus],
+    history: &InventoryHistory,
+    oui: &OuiDatabase,
+) -> Vec<InventoryRow> {
+    servers
+        .iter()
+        .map(|server| InventoryRow {
+            name: server.name.clone(),
+            ip_address: server.ip_address.clone(),
+            mac_address: server.mac_address.to_string(),
+            vendor: oui.vendor(server.mac_address),
+            description: server.description.clone(),
+            online: statuses
+                .iter()
+                .any(|status| status.name == server.name && status.online),
+            uptime_percent: history.uptime_percent.get(&server.name).copied(),
+            last_wake: history.last_wake.get(&server.name).copied(),
+        })
+        .collect()
+}
+
+/// インベントリを指定した形式の文字列にする。
+pub fn render(rows: &[InventoryRow], format: ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Csv => Ok(render_csv(rows)),
+        ExportFormat::Json => {
+            serde_json::to_string_pretty(rows).context("Failed to serialize inventory")
+        }
+    }
+}
+
+/// インベントリを CSV にする。
+fn render_csv(rows: &[InventoryRow]) -> String {
+    let mut csv = String::from(
+        "name,ip_address,mac_address,vendor,description,status,uptime_percent,last_wake\n",
+    );
+    for row in rows {
+        let fields = [
+            row.name.clone(),
+            row.ip_address.clone(),
+            row.mac_address.clone(),
+            row.vendor.clone().unwrap_or_default(),
+            row.description.clone(),
+            if row.online { "online" } else { "offline" }.to_string(),
+            row.uptime_percent
+                .map(|percent| format!("{:.1}", percent))
+                .unwrap_or_default(),
+            row.last_wake
+                .map(|time| time.to_rfc3339())
+                .unwrap_or_default(),
+        ];
+        let line = fields
+            .iter()
+            .map(|field| escape_csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push_str(&line);
+        csv.push('\n');
+    }
+    csv
+}
+
+/// カンマ・ダブルクォート・改行を含むフィールドをダブルクォートで囲む。
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone as _;
+
+    use super::*;
+
+    #[test]
+    fn test_oui_database() {
+        let oui = OuiDatabase::parse(
+            "OUI/MA-L                                                    Organization\n\
+             00-11-22   (hex)\t\tCIMSYS Inc\n\
+             001122     (base 16)\t\tCIMSYS Inc\n\
+             # nmap-mac-prefixes\n\
+             A8BBCC Example Networks\n",
+        );
+        assert_eq!(
+            oui.vendor(MacAddr6::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55)),
+            Some("CIMSYS Inc".to_string())
+        );
+        assert_eq!(
+            oui.vendor(MacAddr6::new(0xA8, 0xBB, 0xCC, 0x00, 0x00, 0x01)),
+            Some("Example Networks".to_string())
+        );
+        assert_eq!(
+            oui.vendor(MacAddr6::new(0x08, 0x00, 0x27, 0x00, 0x00, 0x01)),
+            None
+        );
+        // 2 ビット目が立っているアドレスはローカルで割り当てたもの
+        assert_eq!(
+            oui.vendor(MacAddr6::new(0xAA, 0xBB, 0xCC, 0x00, 0x00, 0x01)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let servers = vec![
+            ServerConfig {
+                name: "Main Server".to_string(),
+                description: "Rack 1, \"top\"".to_string(),
+                ..Default::default()
+            },
+            ServerConfig {
+                name: "Storage Server".to_string(),
+                description: String::new(),
+                ..Default::default()
+            },
+        ];
+        let statuses = vec![ServerStatus::for_test("Main Server", true)];
+        let history = InventoryHistory {
+            uptime_percent: HashMap::from([("Main Server".to_string(), 99.54)]),
+            last_wake: HashMap::from([(
+                "Main Server".to_string(),
+                Utc.with_ymd_and_hms(2025, 2, 10, 8, 30, 0).unwrap(),
+            )]),
+        };
+        let rows = build_rows(&servers, &statuses, &history, &OuiDatabase::default());
+
+        assert_eq!(
+            render(&rows, ExportFormat::Csv).unwrap(),
+            "name,ip_address,mac_address,vendor,description,status,uptime_percent,last_wake\n\
+             Main Server,192.168.1.100,00:11:22:33:44:55,,\"Rack 1, \"\"top\"\"\",online,99.5,2025-02-10T08:30:00+00:00\n\
+             Storage Server,192.168.1.100,00:11:22:33:44:55,,,offline,,\n"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&rows, ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json[0]["online"], true);
+        assert_eq!(json[1]["uptime_percent"], serde_json::Value::Null);
+    }
+}
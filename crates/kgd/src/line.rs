@@ -0,0 +1,166 @@
+//! サーバーの起動・停止を LINE に通知する機能を提供する。
+//!
+//! ステータスモニターの結果を受け取り、前回からオンライン/オフラインが変わったサーバーだけを通知する。
+
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{Context as _, Result};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::{
+    config::LineConfig,
+    status::{ServerStatus, status_changes},
+};
+
+/// Messaging API のプッシュメッセージのエンドポイント。
+const MESSAGING_API_PUSH_URL: &str = "https://api.line.me/v2/bot/message/push";
+
+/// LINE Notify の通知のエンドポイント。
+const NOTIFY_API_URL: &str = "https://notify-api.line.me/api/notify";
+
+/// LINE API のリクエストのタイムアウト。
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// サーバーの起動・停止を LINE に通知する構造体。
+pub struct LineNotifier {
+    http_client: reqwest::Client,
+    /// 通知に使う API と送信先
+    config: LineConfig,
+}
+
+impl LineNotifier {
+    /// 新しい LineNotifier を作成する。
+    pub fn new(config: LineConfig) -> Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent("kgd-bot/1.0")
+            .build()
+            .context("Failed to create HTTP client for LINE")?;
+
+        Ok(Self {
+            http_client,
+            config,
+        })
+    }
+
+    /// ステータスモニターの結果を受け取り、状態が変わったサーバーを通知し続ける。
+    ///
+    /// 起動直後の結果では、オフラインのサーバーだけを通知する。
+    pub async fn run(self, mut status_rx: mpsc::Receiver<Vec<ServerStatus>>) {
+        info!("Starting LINE status notifier");
+
+        let mut previous = HashMap::new();
+        while let Some(statuses) = status_rx.recv().await {
+            let changes = status_changes(&previous, &statuses);
+            if !changes.is_empty() {
+                let text = alert_message(&changes);
+                if let Err(e) = self.send(&text).await {
+                    error!(error = %e, "Failed to send LINE status alert");
+                }
+            }
+            previous = statuses
+                .into_iter()
+                .map(|status| (status.name, status.online))
+                .collect();
+        }
+    }
+
+    /// テキストメッセージを送信する。
+    async fn send(&self, text: &str) -> Result<()> {
+        match &self.config {
+            LineConfig::MessagingApi {
+                channel_access_token,
+                to,
+            } => {
+                for to in to {
+                    let body = serde_json::json!({
+                        "to": to,
+                        "messages": [{ "type": "text", "text": text }],
+                    });
+                    let request = self
+                        .http_client
+                        .post(MESSAGING_API_PUSH_URL)
+                        .bearer_auth(channel_access_token)
+                        .json(&body);
+                    send_request(request)
+                        .await
+                        .with_context(|| format!("Failed to push LINE message to {}", to))?;
+                }
+            }
+            LineConfig::Notify { notify_token } => {
+                // LINE Notify は本文の前に改行を入れないと通知名の直後に続けて表示される
+                let message = format!("\n{}", text);
+                let request = self
+                    .http_client
+                    .post(NOTIFY_API_URL)
+                    .bearer_auth(notify_token)
+                    .form(&[("message", message)]);
+                send_request(request)
+                    .await
+                    .context("Failed to send LINE Notify message")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// リクエストを送信し、成功のステータスでなければエラーを返す。
+async fn send_request(request: reqwest::RequestBuilder) -> Result<()> {
+    let response = request.send().await.context("HTTP request failed")?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("LINE API error: {} {}", status, body);
+    }
+    Ok(())
+}
+
+/// 状態が変わったサーバーの通知文を作る。
+fn alert_message(changes: &[&ServerStatus]) -> String {
+    changes
+        .iter()
+        .map(|status| {
+            if status.online {
+                format!("🟢 {} がオンラインになりました", status.name)
+            } else {
+                format!("🔴 {} がオフラインになりました", status.name)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_changes() {
+        let first = vec![
+            ServerStatus::for_test("Main", true),
+            ServerStatus::for_test("Storage", false),
+        ];
+        let changes = status_changes(&HashMap::new(), &first);
+        assert_eq!(
+            alert_message(&changes),
+            "🔴 Storage がオフラインになりました"
+        );
+
+        let previous = first
+            .iter()
+            .map(|status| (status.name.clone(), status.online))
+            .collect();
+        let next = vec![
+            ServerStatus::for_test("Main", false),
+            ServerStatus::for_test("Storage", true),
+        ];
+        let changes = status_changes(&previous, &next);
+        assert_eq!(
+            alert_message(&changes),
+            "🔴 Main がオフラインになりました\n🟢 Storage がオンラインになりました"
+        );
+
+        assert!(status_changes(&previous, &first).is_empty());
+    }
+}
@@ -1,20 +1,35 @@
+mod command;
 mod config;
+#[cfg(feature = "diary")]
 mod diary;
 mod discord;
+mod email;
+mod inventory;
+mod line;
+mod matrix;
 mod ping;
+mod scheduler;
+mod shutdown;
 mod status;
+mod store;
+mod telegram;
+mod update;
 mod version;
 mod wol;
 
-use std::{path::PathBuf, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::{Context as _, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use tokio::sync::mpsc;
 use tracing::info;
 
 use crate::{
-    config::{open_config, write_default_config},
+    config::{migrate_config_file, open_config, write_default_config},
     version::short_version,
 };
 
@@ -26,11 +41,51 @@ struct Args {
 
     #[arg(long)]
     init: bool,
+
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+enum CliCommand {
+    /// Manage the configured servers
+    Servers {
+        #[command(subcommand)]
+        command: ServersCommand,
+    },
+    /// Manage the configuration file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ServersCommand {
+    /// Print the server inventory with status, uptime, last wake and MAC vendor
+    Export {
+        #[arg(long, value_enum, default_value = "csv")]
+        format: inventory::ExportFormat,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Upgrade an old-format configuration file to the current format, keeping a backup
+    ///
+    /// Only `[[diary.notion_tags]]` needs migrating; other settings are read as they are.
+    Migrate {
+        /// Only report the changes without writing the file
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // 書き出したインベントリを標準出力に出すため、ログは標準エラー出力に出す
     tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
         .with_env_filter(
             tracing_subscriber::EnvFilter::from_default_env()
                 .add_directive(tracing::Level::INFO.into()),
@@ -47,16 +102,181 @@ async fn main() -> Result<()> {
 
     tracing::info!(version = short_version(), "kgd version");
 
+    // 古い形式の設定は読み込めないことがあるため、読み込む前に移行する
+    if let Some(CliCommand::Config {
+        command: ConfigCommand::Migrate { dry_run },
+    }) = args.command
+    {
+        return migrate_config(&args.config, dry_run);
+    }
+
     let config = open_config(&args.config).context("Failed to load configuration")?;
     info!(servers = config.servers.len(), "Configuration loaded");
 
+    if let Some(CliCommand::Servers {
+        command: ServersCommand::Export { format },
+    }) = args.command
+    {
+        return export_inventory(&config, format).await;
+    }
+
+    let bot_store = store::BotStore::connect(config.state_database_url())
+        .await
+        .context("Failed to connect to state database")?;
+
+    // 一時停止中も Discord には結果を送る（一時停止と再開の通知を Discord 側で扱うため）
+    let snooze = status::StatusSnooze::default();
     let (status_tx, status_rx) = mpsc::channel(1);
+    let mut status_txs = vec![StatusSubscriber {
+        tx: status_tx,
+        snoozable: false,
+    }];
+
+    // Matrix にもステータスを通知する場合は、同じ結果を別のチャンネルにも送る
+    if let Some(matrix_config) = config
+        .matrix
+        .as_ref()
+        .filter(|matrix| matrix.status_room_id.is_some())
+    {
+        let notifier = matrix::MatrixStatusNotifier::new(matrix_config, config.status.interval)?;
+        let (tx, rx) = mpsc::channel(1);
+        status_txs.push(StatusSubscriber {
+            tx,
+            snoozable: true,
+        });
+        tokio::spawn(notifier.run(rx));
+    }
+
+    // LINE に通知する場合も、同じ結果を別のチャンネルで受け取る
+    if let Some(line_config) = config.line.clone() {
+        let notifier = line::LineNotifier::new(line_config)?;
+        let (tx, rx) = mpsc::channel(1);
+        status_txs.push(StatusSubscriber {
+            tx,
+            snoozable: true,
+        });
+        tokio::spawn(notifier.run(rx));
+    }
+
+    // メールでは長時間のオフラインだけを通知するため、同じ結果を別のチャンネルで受け取る
+    // （重大なイベントなので一時停止中も通知する）
+    let email_notifier = match &config.email {
+        Some(email_config) => {
+            let notifier = Arc::new(email::EmailNotifier::new(
+                email_config,
+                config.scheduler.timezone,
+            )?);
+            let (tx, rx) = mpsc::channel(1);
+            status_txs.push(StatusSubscriber {
+                tx,
+                snoozable: false,
+            });
+            tokio::spawn(notifier.clone().run(rx));
+            let started = notifier.clone();
+            tokio::spawn(async move { started.send(&email::CriticalEvent::BotStarted).await });
+            Some(notifier)
+        }
+        None => None,
+    };
+
+    if config.telegram.is_some() {
+        let telegram = telegram::TelegramFrontend::new(config.clone(), bot_store.clone())?;
+        tokio::spawn(telegram.run());
+    }
 
     let servers = config.servers.clone();
     let interval = config.status.interval;
-    tokio::spawn(run_status_monitor(servers, interval, status_tx));
+    tokio::spawn(run_status_monitor(
+        servers,
+        interval,
+        status_txs,
+        snooze.clone(),
+    ));
+
+    let result = tokio::select! {
+        result = discord::run(
+            config,
+            bot_store,
+            status_rx,
+            email_notifier.clone(),
+            snooze,
+        ) => result,
+        () = shutdown_signal() => {
+            info!("Shutdown signal received");
+            Ok(())
+        }
+    };
 
-    discord::run(config, status_rx).await
+    if let Some(notifier) = &email_notifier {
+        let error = result.as_ref().err().map(|e| format!("{:#}", e));
+        notifier
+            .send(&email::CriticalEvent::BotStopped { error })
+            .await;
+    }
+    result
+}
+
+/// サーバーのインベントリを標準出力に書き出す。
+async fn export_inventory(config: &config::Config, format: inventory::ExportFormat) -> Result<()> {
+    let store = store::BotStore::connect(config.state_database_url()).await?;
+    let history = inventory::InventoryHistory::load(&store, &config.servers).await?;
+
+    let rows = inventory::collect(config, &history).await;
+    print!("{}", inventory::render(&rows, format)?);
+    Ok(())
+}
+
+/// 設定ファイルを現在の形式に移行し、変更した内容を標準出力に書き出す。
+fn migrate_config(path: &Path, dry_run: bool) -> Result<()> {
+    let report = migrate_config_file(path, dry_run).context("Failed to migrate configuration")?;
+    if report.changes.is_empty() {
+        println!("{} is already up to date", path.display());
+        return Ok(());
+    }
+
+    for change in &report.changes {
+        println!("- {}", change);
+    }
+    match &report.backup {
+        Some(backup) => println!(
+            "Migrated {} (backup: {}). Comments are not kept, see the backup for the original file",
+            path.display(),
+            backup.display()
+        ),
+        None => println!("Dry run, {} was not changed", path.display()),
+    }
+    Ok(())
+}
+
+/// Ctrl+C または SIGTERM を受け取るまで待つ。
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// ステータス結果の送信先。
+struct StatusSubscriber {
+    tx: mpsc::Sender<Vec<status::ServerStatus>>,
+    /// 通知の一時停止中は送らないか
+    snoozable: bool,
 }
 
 /// サーバーステータスを定期的にチェックし、結果をチャンネルに送信するループを実行する。
@@ -64,19 +284,28 @@ async fn main() -> Result<()> {
 /// # Arguments
 /// * `servers` - 監視対象のサーバー設定リスト
 /// * `interval` - チェック間隔
-/// * `tx` - ステータス結果を送信するチャンネル
+/// * `subscribers` - ステータス結果の送信先（通知先ごと）
+/// * `snooze` - 通知の一時停止の状態
 async fn run_status_monitor(
     servers: Vec<config::ServerConfig>,
     interval: Duration,
-    tx: mpsc::Sender<Vec<status::ServerStatus>>,
+    subscribers: Vec<StatusSubscriber>,
+    snooze: status::StatusSnooze,
 ) {
-    let ping_timeout = Duration::from_secs(5);
-
     info!(interval = ?interval, "Starting status monitor");
 
     loop {
-        let statuses = status::check_servers(&servers, ping_timeout).await;
-        if tx.send(statuses).await.is_err() {
+        let statuses = status::check_servers(&servers, status::PING_TIMEOUT).await;
+        let snoozed = snooze.is_snoozed(chrono::Utc::now());
+        let mut delivered = false;
+        for subscriber in &subscribers {
+            if snoozed && subscriber.snoozable {
+                delivered |= !subscriber.tx.is_closed();
+                continue;
+            }
+            delivered |= subscriber.tx.send(statuses.clone()).await.is_ok();
+        }
+        if !delivered {
             break;
         }
         tokio::time::sleep(interval).await;
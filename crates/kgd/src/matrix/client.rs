@@ -0,0 +1,273 @@
+//! Matrix の Client-Server API のクライアント。
+//!
+//! Bot に必要なイベントの取得（`/sync`）・メッセージの送信・メディアの URL と認証の解決だけを扱う。
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use reqwest::Url;
+use serde::Deserialize;
+
+/// sync のタイムアウトに加えて HTTP リクエストを待つ時間。
+const HTTP_TIMEOUT_MARGIN: Duration = Duration::from_secs(30);
+
+/// Matrix のホームサーバーに接続するクライアント。
+pub struct MatrixClient {
+    http_client: reqwest::Client,
+    /// ホームサーバーの URL
+    homeserver_url: Url,
+    /// アクセストークン
+    access_token: String,
+    /// メッセージ送信のトランザクション ID の連番
+    next_txn_id: AtomicU64,
+}
+
+impl MatrixClient {
+    /// 新しい MatrixClient を作成する。
+    ///
+    /// # Arguments
+    /// * `homeserver_url` - ホームサーバーの URL
+    /// * `access_token` - アクセストークン
+    /// * `sync_timeout` - `/sync` の long polling で待つ時間
+    pub fn new(homeserver_url: &str, access_token: &str, sync_timeout: Duration) -> Result<Self> {
+        let homeserver_url = Url::parse(homeserver_url).context("Invalid Matrix homeserver URL")?;
+        let http_client = reqwest::Client::builder()
+            .timeout(sync_timeout + HTTP_TIMEOUT_MARGIN)
+            .user_agent("kgd-bot/1.0")
+            .build()
+            .context("Failed to create HTTP client for Matrix")?;
+
+        Ok(Self {
+            http_client,
+            homeserver_url,
+            access_token: access_token.to_string(),
+            next_txn_id: AtomicU64::new(0),
+        })
+    }
+
+    /// アクセストークンのユーザー ID を取得する。
+    pub async fn whoami(&self) -> Result<String> {
+        #[derive(Deserialize)]
+        struct WhoAmI {
+            user_id: String,
+        }
+
+        let url = self.endpoint(&["account", "whoami"])?;
+        let response: WhoAmI = self.send(self.http_client.get(url)).await?;
+        Ok(response.user_id)
+    }
+
+    /// 前回の取得以降のイベントを取得する。
+    ///
+    /// # Arguments
+    /// * `since` - 前回の取得で得た `next_batch`（None の場合は最新の状態だけを取得する）
+    /// * `filter` - 取得するイベントのフィルター（JSON）
+    /// * `timeout` - 新しいイベントがない場合に待つ時間
+    pub async fn sync(
+        &self,
+        since: Option<&str>,
+        filter: &str,
+        timeout: Duration,
+    ) -> Result<SyncResponse> {
+        let mut url = self.endpoint(&["sync"])?;
+        url.query_pairs_mut()
+            .append_pair("filter", filter)
+            .append_pair("timeout", &timeout.as_millis().to_string());
+        if let Some(since) = since {
+            url.query_pairs_mut().append_pair("since", since);
+        }
+        self.send(self.http_client.get(url)).await
+    }
+
+    /// ルームのイベントを 1 件取得する。
+    pub async fn get_event(&self, room_id: &str, event_id: &str) -> Result<RoomEvent> {
+        let url = self.endpoint(&["rooms", room_id, "event", event_id])?;
+        self.send(self.http_client.get(url)).await
+    }
+
+    /// ルームに通知メッセージ（`m.notice`）を送信する。
+    pub async fn send_notice(&self, room_id: &str, body: &str) -> Result<()> {
+        let txn_id = format!(
+            "kgd-{}-{}",
+            std::process::id(),
+            self.next_txn_id.fetch_add(1, Ordering::Relaxed)
+        );
+        let url = self.endpoint(&["rooms", room_id, "send", "m.room.message", &txn_id])?;
+        let content = serde_json::json!({
+            "msgtype": "m.notice",
+            "body": body,
+        });
+        let _: serde_json::Value = self.send(self.http_client.put(url).json(&content)).await?;
+        Ok(())
+    }
+
+    /// `mxc://` の URL を、ダウンロードできる HTTP の URL に変換する。
+    ///
+    /// 認証付きメディア（`/_matrix/client/v1/media/download`）の URL を返すため、
+    /// ダウンロードには [`Self::media_authorization`] のヘッダーが必要になる。
+    pub fn media_download_url(&self, mxc_url: &str) -> Option<String> {
+        let (server_name, media_id) = mxc_url.strip_prefix("mxc://")?.split_once('/')?;
+        let mut url = self.media_base_url().ok()?;
+        url.path_segments_mut()
+            .ok()?
+            .extend(["download", server_name, media_id]);
+        Some(url.to_string())
+    }
+
+    /// メディアのダウンロードに付ける `Authorization` ヘッダーの値を返す。
+    pub fn media_authorization(&self) -> String {
+        format!("Bearer {}", self.access_token)
+    }
+
+    /// 認証付きメディアのエンドポイントの URL（末尾の `/` なし）を返す。
+    pub fn media_base_url(&self) -> Result<Url> {
+        let mut url = self.homeserver_url.clone();
+        url.path_segments_mut()
+            .map_err(|()| anyhow::anyhow!("Matrix homeserver URL cannot be a base URL"))?
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v1", "media"]);
+        Ok(url)
+    }
+
+    /// Client-Server API のエンドポイントの URL を作る（パスの各要素はエスケープする）。
+    fn endpoint(&self, segments: &[&str]) -> Result<Url> {
+        let mut url = self.homeserver_url.clone();
+        url.path_segments_mut()
+            .map_err(|()| anyhow::anyhow!("Matrix homeserver URL cannot be a base URL"))?
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3"])
+            .extend(segments);
+        Ok(url)
+    }
+
+    /// 認証を付けてリクエストを送り、JSON のレスポンスを返す。
+    async fn send<T: serde::de::DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T> {
+        let response = request
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .context("Matrix request failed")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Matrix API error: {} {}", status, body);
+        }
+
+        response
+            .json()
+            .await
+            .context("Failed to parse Matrix response")
+    }
+}
+
+/// `/sync` のレスポンス。
+#[derive(Debug, Deserialize)]
+pub struct SyncResponse {
+    /// 次回の取得に使うトークン
+    pub next_batch: String,
+    /// ルームごとのイベント
+    #[serde(default)]
+    pub rooms: SyncRooms,
+}
+
+/// `/sync` のルームごとのイベント。
+#[derive(Debug, Default, Deserialize)]
+pub struct SyncRooms {
+    /// 参加しているルーム
+    #[serde(default)]
+    pub join: HashMap<String, JoinedRoom>,
+}
+
+/// 参加しているルームのイベント。
+#[derive(Debug, Default, Deserialize)]
+pub struct JoinedRoom {
+    /// タイムラインのイベント
+    #[serde(default)]
+    pub timeline: Timeline,
+}
+
+/// ルームのタイムライン。
+#[derive(Debug, Default, Deserialize)]
+pub struct Timeline {
+    /// 古い順のイベント
+    #[serde(default)]
+    pub events: Vec<RoomEvent>,
+}
+
+/// ルームのイベント。
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoomEvent {
+    /// イベントの種類（`m.room.message` など）
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// イベント ID
+    pub event_id: String,
+    /// 送信者のユーザー ID
+    pub sender: String,
+    /// 送信日時（UNIX ミリ秒）
+    pub origin_server_ts: i64,
+    /// イベントの内容
+    #[serde(default)]
+    pub content: serde_json::Value,
+    /// 取り消したイベントの ID（`m.room.redaction` のみ、古いルームバージョン）
+    #[serde(default)]
+    pub redacts: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_escapes_segments() {
+        let client = MatrixClient::new(
+            "https://matrix.example.org/",
+            "token",
+            Duration::from_secs(30),
+        )
+        .unwrap();
+        assert_eq!(
+            client
+                .endpoint(&["rooms", "!room:example.org", "event", "$event/1"])
+                .unwrap()
+                .as_str(),
+            "https://matrix.example.org/_matrix/client/v3/rooms/!room:example.org/event/$event%2F1"
+        );
+    }
+
+    #[test]
+    fn test_media_authorization() {
+        let client = MatrixClient::new(
+            "https://matrix.example.org",
+            "token",
+            Duration::from_secs(30),
+        )
+        .unwrap();
+        assert_eq!(client.media_authorization(), "Bearer token");
+    }
+
+    #[test]
+    fn test_media_download_url() {
+        let client = MatrixClient::new(
+            "https://matrix.example.org",
+            "token",
+            Duration::from_secs(30),
+        )
+        .unwrap();
+        assert_eq!(
+            client
+                .media_download_url("mxc://example.org/AbCdEf")
+                .as_deref(),
+            Some("https://matrix.example.org/_matrix/client/v1/media/download/example.org/AbCdEf")
+        );
+        assert_eq!(client.media_download_url("https://example.org/a"), None);
+    }
+}
@@ -0,0 +1,111 @@
+//! Matrix のルームでサーバーステータスの通知と日報の同期を行う。
+//!
+//! ステータスの通知はステータスモニターの結果をルームに送るだけで、日報機能を含めずにビルドしても使える。
+//! 日報の同期（`MatrixDiarySync`）は日報機能が有効な場合だけ使える。
+
+#[cfg_attr(not(feature = "diary"), allow(dead_code))]
+mod client;
+#[cfg(feature = "diary")]
+mod source;
+#[cfg(feature = "diary")]
+mod sync;
+
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::{command::format_statuses, config::MatrixConfig, status::ServerStatus};
+
+use self::client::MatrixClient;
+#[cfg(feature = "diary")]
+pub use self::sync::MatrixDiarySync;
+
+/// サーバーステータスを Matrix のルームに通知する。
+pub struct MatrixStatusNotifier {
+    /// Matrix API クライアント
+    client: MatrixClient,
+    /// 通知するルームの ID
+    room_id: String,
+    /// ステータスチェック間隔（通知文に表示する）
+    interval: Duration,
+}
+
+impl MatrixStatusNotifier {
+    /// 新しい MatrixStatusNotifier を作成する。
+    ///
+    /// # Arguments
+    /// * `config` - Matrix の設定（`status_room_id` が必要）
+    /// * `interval` - ステータスチェック間隔
+    pub fn new(config: &MatrixConfig, interval: Duration) -> Result<Self> {
+        let room_id = config
+            .status_room_id
+            .clone()
+            .context("matrix.status_room_id is not configured")?;
+        Ok(Self {
+            client: create_client(config)?,
+            room_id,
+            interval,
+        })
+    }
+
+    /// ステータスモニターの結果をルームに通知し続ける。
+    pub async fn run(self, mut rx: mpsc::Receiver<Vec<ServerStatus>>) {
+        info!(room_id = %self.room_id, "Matrix status notifications started");
+        while let Some(statuses) = rx.recv().await {
+            let body = status_notice(&statuses, self.interval);
+            if let Err(e) = self.client.send_notice(&self.room_id, &body).await {
+                error!(error = %e, room_id = %self.room_id, "Failed to send Matrix status message");
+            }
+        }
+    }
+}
+
+/// 設定から Matrix API クライアントを作成する。
+fn create_client(config: &MatrixConfig) -> Result<MatrixClient> {
+    MatrixClient::new(
+        &config.homeserver_url,
+        &config.access_token,
+        config.sync_timeout,
+    )
+}
+
+/// サーバーステータスの通知文を作る。
+fn status_notice(statuses: &[ServerStatus], interval: Duration) -> String {
+    format!(
+        "{}\nUpdated every {}",
+        format_statuses(statuses),
+        humantime::format_duration(interval)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ping::PingResult;
+
+    #[test]
+    fn test_status_notice() {
+        let statuses = vec![
+            ServerStatus {
+                name: "Main Server".to_string(),
+                online: true,
+                ping: PingResult::default(),
+                ports: vec![],
+                health: None,
+            },
+            ServerStatus {
+                name: "Storage Server".to_string(),
+                online: false,
+                ping: PingResult::default(),
+                ports: vec![],
+                health: None,
+            },
+        ];
+        assert_eq!(
+            status_notice(&statuses, Duration::from_secs(300)),
+            "Server Status\nMain Server: Online\nStorage Server: Offline\nUpdated every 5m"
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Matrix のイベントを日報の同期元として扱うためのアダプター。
+//!
+//! 日報のストアはメッセージやスレッドを Discord と同じ数値 ID で管理するため、
+//! Matrix の文字列の ID から数値 ID を求める。
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use kgd_diary::{Mention, MessageSource, SourceAttachment, SourceMessage};
+use sha2::{Digest as _, Sha256};
+
+use super::client::{MatrixClient, RoomEvent};
+
+/// メッセージ ID のうち、イベント ID のハッシュに使う下位ビット数。
+const MESSAGE_ID_HASH_BITS: u32 = 20;
+
+/// Matrix のイベントから求めたメッセージ ID を返す。
+///
+/// Discord の snowflake と同じく投稿順に増加するよう、上位ビットに送信日時（ミリ秒）を、
+/// 下位ビットにイベント ID のハッシュを置く。
+pub fn matrix_message_id(event_id: &str, origin_server_ts: i64) -> u64 {
+    let hash = stable_hash(event_id) & ((1 << MESSAGE_ID_HASH_BITS) - 1);
+    (origin_server_ts.max(0) as u64) << MESSAGE_ID_HASH_BITS | hash
+}
+
+/// ルームとその日付に対応する日報エントリのスレッド ID を返す。
+pub fn matrix_thread_id(room_id: &str, date: NaiveDate) -> u64 {
+    stable_hash(&format!("{}/{}", room_id, date))
+}
+
+/// ルームの日報エントリをまとめるフォーラムチャンネル ID の代わりの ID を返す。
+pub fn matrix_room_key(room_id: &str) -> u64 {
+    stable_hash(room_id)
+}
+
+/// Matrix の投稿イベントを同期エンジンに渡す形式に変換する。
+///
+/// 本文・添付ファイルを持たないイベントや、編集（`m.replace`）のイベントは None を返す。
+pub fn source_message(
+    client: &MatrixClient,
+    event: &RoomEvent,
+    thread_id: u64,
+) -> Option<SourceMessage> {
+    if event.kind != "m.room.message" || replaced_event_id(event).is_some() {
+        return None;
+    }
+    message_from_content(
+        client,
+        &event.content,
+        matrix_message_id(&event.event_id, event.origin_server_ts),
+        thread_id,
+        event,
+    )
+}
+
+/// 編集イベントを、編集後の内容を持つ元のメッセージに変換する。
+///
+/// `original` は編集された元のイベント（メッセージ ID と投稿日時は元のイベントから求める）。
+pub fn edited_message(
+    client: &MatrixClient,
+    edit: &RoomEvent,
+    original: &RoomEvent,
+    thread_id: u64,
+) -> Option<SourceMessage> {
+    message_from_content(
+        client,
+        edit.content.get("m.new_content")?,
+        matrix_message_id(&original.event_id, original.origin_server_ts),
+        thread_id,
+        original,
+    )
+}
+
+/// 編集イベントの場合、編集された元のイベント ID を返す。
+pub fn replaced_event_id(event: &RoomEvent) -> Option<&str> {
+    let relates_to = event.content.get("m.relates_to")?;
+    if relates_to["rel_type"].as_str()? != "m.replace" {
+        return None;
+    }
+    relates_to["event_id"].as_str()
+}
+
+/// 取り消し（`m.room.redaction`）イベントの場合、取り消された元のイベント ID を返す。
+pub fn redacted_event_id(event: &RoomEvent) -> Option<&str> {
+    if event.kind != "m.room.redaction" {
+        return None;
+    }
+    event
+        .redacts
+        .as_deref()
+        .or_else(|| event.content["redacts"].as_str())
+}
+
+/// Matrix を同期元とする [`MessageSource`] の実装。
+#[derive(Clone)]
+pub struct MatrixSource {
+    /// ホームサーバーの認証付きメディアのエンドポイントの URL
+    media_base_url: String,
+    /// メディアのダウンロードに付ける `Authorization` ヘッダーの値
+    media_authorization: String,
+}
+
+impl MatrixSource {
+    /// クライアントのホームサーバーからメディアをダウンロードする MatrixSource を作成する。
+    pub fn new(client: &MatrixClient) -> anyhow::Result<Self> {
+        Ok(Self {
+            media_base_url: client.media_base_url()?.to_string(),
+            media_authorization: client.media_authorization(),
+        })
+    }
+}
+
+impl MessageSource for MatrixSource {
+    fn posted_at(&self, message_id: u64) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp_millis((message_id >> MESSAGE_ID_HASH_BITS) as i64)
+    }
+
+    async fn resolve_mentions(
+        &self,
+        _message: &SourceMessage,
+        _mentions: &[Mention],
+    ) -> HashMap<Mention, String> {
+        // Matrix の本文は Discord 形式のメンションを含まない
+        HashMap::new()
+    }
+
+    fn attachment_authorization(&self, attachment: &SourceAttachment) -> Option<String> {
+        // アクセストークンはホームサーバーのメディアのダウンロードにだけ付ける
+        attachment
+            .url
+            .strip_prefix(&self.media_base_url)
+            .is_some_and(|path| path.starts_with('/'))
+            .then(|| self.media_authorization.clone())
+    }
+}
+
+/// メッセージの内容（`content` または `m.new_content`）から同期するメッセージを作る。
+fn message_from_content(
+    client: &MatrixClient,
+    content: &serde_json::Value,
+    id: u64,
+    thread_id: u64,
+    event: &RoomEvent,
+) -> Option<SourceMessage> {
+    let body = content["body"].as_str().unwrap_or_default();
+    let (content, attachments) = match content["msgtype"].as_str()? {
+        "m.text" | "m.notice" => (body.to_string(), Vec::new()),
+        "m.emote" => (format!("* {}", body), Vec::new()),
+        "m.image" | "m.file" | "m.video" | "m.audio" => {
+            let url = client.media_download_url(content["url"].as_str()?)?;
+            let filename = content["filename"].as_str().unwrap_or(body).to_string();
+            // filename がある場合の body はキャプション
+            let caption = if content["filename"].is_string() && body != filename {
+                body.to_string()
+            } else {
+                String::new()
+            };
+            let attachment = SourceAttachment {
+                filename,
+                url,
+                // サイズが不明なメディアは上限を確認できないため、上限を超えているものとして扱う
+                size: content["info"]["size"].as_u64().unwrap_or(u64::MAX),
+                description: None,
+            };
+            (caption, vec![attachment])
+        }
+        _ => return None,
+    };
+
+    Some(SourceMessage {
+        id,
+        thread_id,
+        author_id: stable_hash(&event.sender),
+        posted_at: DateTime::from_timestamp_millis(event.origin_server_ts).unwrap_or_default(),
+        content,
+        attachments,
+        mention_names: HashMap::new(),
+    })
+}
+
+/// 文字列から、再起動しても変わらない 63 ビットの ID を求める。
+///
+/// データベースには符号付き整数で保存するため、最上位ビットは使わない。
+fn stable_hash(value: &str) -> u64 {
+    let digest = Sha256::digest(value.as_bytes());
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(bytes) & (i64::MAX as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn client() -> MatrixClient {
+        MatrixClient::new(
+            "https://matrix.example.org",
+            "token",
+            Duration::from_secs(30),
+        )
+        .unwrap()
+    }
+
+    fn event(kind: &str, event_id: &str, content: serde_json::Value) -> RoomEvent {
+        RoomEvent {
+            kind: kind.to_string(),
+            event_id: event_id.to_string(),
+            sender: "@alice:example.org".to_string(),
+            origin_server_ts: 1_700_000_000_000,
+            content,
+            redacts: None,
+        }
+    }
+
+    #[test]
+    fn test_matrix_message_id_is_ordered_by_time() {
+        let earlier = matrix_message_id("$zzz", 1_700_000_000_000);
+        let later = matrix_message_id("$aaa", 1_700_000_000_001);
+        assert!(earlier < later);
+        assert!(later <= i64::MAX as u64);
+        assert_eq!(
+            MatrixSource::new(&client()).unwrap().posted_at(earlier),
+            DateTime::from_timestamp_millis(1_700_000_000_000)
+        );
+    }
+
+    #[test]
+    fn test_matrix_thread_id_per_room_and_date() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        let next_date = NaiveDate::from_ymd_opt(2026, 1, 3).unwrap();
+        assert_eq!(
+            matrix_thread_id("!a:example.org", date),
+            matrix_thread_id("!a:example.org", date)
+        );
+        assert_ne!(
+            matrix_thread_id("!a:example.org", date),
+            matrix_thread_id("!a:example.org", next_date)
+        );
+        assert_ne!(
+            matrix_thread_id("!a:example.org", date),
+            matrix_thread_id("!b:example.org", date)
+        );
+    }
+
+    #[test]
+    fn test_source_message_text_and_image() {
+        let client = client();
+        let text = event(
+            "m.room.message",
+            "$text",
+            serde_json::json!({ "msgtype": "m.text", "body": "おはよう" }),
+        );
+        let message = source_message(&client, &text, 1).unwrap();
+        assert_eq!(message.content, "おはよう");
+        assert_eq!(message.thread_id, 1);
+        assert!(message.attachments.is_empty());
+
+        let image = event(
+            "m.room.message",
+            "$image",
+            serde_json::json!({
+                "msgtype": "m.image",
+                "body": "朝ごはん",
+                "filename": "IMG_0001.jpg",
+                "url": "mxc://example.org/abc",
+                "info": { "size": 1234 }
+            }),
+        );
+        let message = source_message(&client, &image, 1).unwrap();
+        assert_eq!(message.content, "朝ごはん");
+        assert_eq!(
+            message.attachments,
+            vec![SourceAttachment {
+                filename: "IMG_0001.jpg".to_string(),
+                url: "https://matrix.example.org/_matrix/client/v1/media/download/example.org/abc"
+                    .to_string(),
+                size: 1234,
+                description: None,
+            }]
+        );
+
+        let source = MatrixSource::new(&client).unwrap();
+        assert_eq!(
+            source
+                .attachment_authorization(&message.attachments[0])
+                .as_deref(),
+            Some("Bearer token")
+        );
+        let external = SourceAttachment {
+            url: "https://example.org/_matrix/client/v1/media/download/example.org/abc".to_string(),
+            ..message.attachments[0].clone()
+        };
+        assert_eq!(source.attachment_authorization(&external), None);
+    }
+
+    #[test]
+    fn test_source_message_without_media_size_is_oversized() {
+        let file = event(
+            "m.room.message",
+            "$file",
+            serde_json::json!({
+                "msgtype": "m.file",
+                "body": "backup.tar",
+                "url": "mxc://example.org/def"
+            }),
+        );
+        let message = source_message(&client(), &file, 1).unwrap();
+        assert_eq!(message.attachments.len(), 1);
+        assert_eq!(message.attachments[0].size, u64::MAX);
+    }
+
+    #[test]
+    fn test_edit_and_redaction() {
+        let client = client();
+        let original = event(
+            "m.room.message",
+            "$original",
+            serde_json::json!({ "msgtype": "m.text", "body": "typo" }),
+        );
+        let edit = event(
+            "m.room.message",
+            "$edit",
+            serde_json::json!({
+                "msgtype": "m.text",
+                "body": "* fixed",
+                "m.new_content": { "msgtype": "m.text", "body": "fixed" },
+                "m.relates_to": { "rel_type": "m.replace", "event_id": "$original" }
+            }),
+        );
+        // 編集イベント自体は新しいメッセージとして扱わない
+        assert!(source_message(&client, &edit, 1).is_none());
+        assert_eq!(replaced_event_id(&edit), Some("$original"));
+
+        let message = edited_message(&client, &edit, &original, 1).unwrap();
+        assert_eq!(message.content, "fixed");
+        assert_eq!(
+            message.id,
+            matrix_message_id("$original", original.origin_server_ts)
+        );
+
+        let redaction = event(
+            "m.room.redaction",
+            "$redaction",
+            serde_json::json!({ "redacts": "$original" }),
+        );
+        assert_eq!(redacted_event_id(&redaction), Some("$original"));
+        assert_eq!(redacted_event_id(&original), None);
+    }
+}
@@ -0,0 +1,303 @@
+//! Matrix のルームのメッセージを日報に同期する。
+//!
+//! Client-Server API の `/sync` でルームのイベントを取得し、投稿・編集・取り消しを
+//! [`MessageEvent`] として同期エンジンに渡す。
+//! ルームごと・日付ごとに日報エントリを作り、その日の `[diary]` と同じタイトルの Notion ページに同期する。
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use kgd_diary::{
+    DiaryEntry, DiaryStorage as _, DiaryStore, EventOutcome, MessageEvent, MessageSyncer,
+    NotionClient, PageTemplate, TempWorkspace, render_title, start_of_day_in_timezone,
+};
+use tokio::sync::Mutex;
+use tracing::{error, info};
+
+use crate::{
+    config::{Config, DiaryConfig, Feature, MatrixConfig},
+    diary::create_templated_page,
+    store::{BotStorage as _, BotStore},
+};
+
+use super::{
+    client::{MatrixClient, RoomEvent},
+    create_client,
+    source::{
+        MatrixSource, edited_message, matrix_message_id, matrix_room_key, matrix_thread_id,
+        redacted_event_id, replaced_event_id, source_message,
+    },
+};
+
+/// API の呼び出しに失敗したときに再試行するまでの待ち時間。
+const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Matrix のルームのメッセージを日報に同期する。
+pub struct MatrixDiarySync {
+    /// アプリケーション全体の設定
+    config: Config,
+    /// Matrix API クライアント
+    client: MatrixClient,
+    /// メディアのダウンロードを認証する同期元
+    source: MatrixSource,
+    /// `/sync` の再開位置を保存するストア
+    bot_store: BotStore,
+    /// 日報ストア
+    diary_store: DiaryStore,
+    /// `[diary]` の Notion クライアント
+    notion_client: Arc<NotionClient>,
+    /// 一時ファイルの作業ディレクトリ
+    temp_workspace: TempWorkspace,
+    /// 日報ページのテンプレート
+    page_template: Option<PageTemplate>,
+    /// 日報の作成処理を直列化するためのロック（Discord の作成処理と共有する）
+    diary_creation_lock: Arc<Mutex<()>>,
+}
+
+impl MatrixDiarySync {
+    /// 新しい MatrixDiarySync を作成する。
+    ///
+    /// 日報ストアや Notion クライアントは Discord のフロントエンドと共有する。
+    pub fn new(
+        config: Config,
+        bot_store: BotStore,
+        diary_store: DiaryStore,
+        notion_client: Arc<NotionClient>,
+        temp_workspace: TempWorkspace,
+        page_template: Option<PageTemplate>,
+        diary_creation_lock: Arc<Mutex<()>>,
+    ) -> Result<Self> {
+        let matrix_config = config.matrix.as_ref().context("Matrix is not configured")?;
+        let client = create_client(matrix_config)?;
+        let source = MatrixSource::new(&client)?;
+
+        Ok(Self {
+            config,
+            client,
+            source,
+            bot_store,
+            diary_store,
+            notion_client,
+            temp_workspace,
+            page_template,
+            diary_creation_lock,
+        })
+    }
+
+    fn matrix_config(&self) -> &MatrixConfig {
+        self.config
+            .matrix
+            .as_ref()
+            .expect("MatrixDiarySync is created only with Matrix configured")
+    }
+
+    /// 日報に同期するルームのイベントを取得し続ける。
+    ///
+    /// 前回の `/sync` の位置を保存しておき、停止中に届いたイベントも再起動後に同期する。
+    /// 初めて起動したときは、起動前のイベントは同期しない。
+    pub async fn run(self) {
+        let matrix_config = self.matrix_config();
+        if matrix_config.diary_room_ids.is_empty() {
+            return;
+        }
+
+        let user_id = loop {
+            match self.client.whoami().await {
+                Ok(user_id) => break user_id,
+                Err(e) => {
+                    error!(error = %e, "Failed to fetch Matrix user, retrying");
+                    tokio::time::sleep(RETRY_INTERVAL).await;
+                }
+            }
+        };
+        info!(
+            user_id = %user_id,
+            rooms = matrix_config.diary_room_ids.len(),
+            "Matrix diary sync started"
+        );
+
+        let filter = sync_filter(&matrix_config.diary_room_ids);
+        let mut since = match self.bot_store.get_matrix_sync_token(&user_id).await {
+            Ok(since) => since,
+            Err(e) => {
+                error!(error = %e, "Failed to load Matrix sync token, skipping missed events");
+                None
+            }
+        };
+        loop {
+            // 初回は次回以降の取得位置を得るだけにするため待たない
+            let timeout = if since.is_some() {
+                matrix_config.sync_timeout
+            } else {
+                Duration::ZERO
+            };
+            let response = match self.client.sync(since.as_deref(), &filter, timeout).await {
+                Ok(response) => response,
+                Err(e) => {
+                    error!(error = %e, "Failed to sync Matrix events, retrying");
+                    tokio::time::sleep(RETRY_INTERVAL).await;
+                    continue;
+                }
+            };
+
+            if since.is_some() {
+                for (room_id, room) in &response.rooms.join {
+                    if !matrix_config.diary_room_ids.contains(room_id) {
+                        continue;
+                    }
+                    for event in room.timeline.events.iter().filter(|e| e.sender != user_id) {
+                        if let Err(e) = self.handle_room_event(room_id, event).await {
+                            error!(
+                                error = %e,
+                                room_id = %room_id,
+                                event_id = %event.event_id,
+                                "Failed to sync Matrix event to Notion"
+                            );
+                        }
+                    }
+                }
+            }
+            if let Err(e) = self
+                .bot_store
+                .set_matrix_sync_token(&user_id, &response.next_batch)
+                .await
+            {
+                error!(error = %e, "Failed to save Matrix sync token");
+            }
+            since = Some(response.next_batch);
+        }
+    }
+
+    /// ルームのイベントを日報に反映する。
+    async fn handle_room_event(&self, room_id: &str, event: &RoomEvent) -> Result<()> {
+        let message_event = if let Some(original_id) = redacted_event_id(event) {
+            let original = self.client.get_event(room_id, original_id).await?;
+            MessageEvent::Deleted {
+                thread_id: self.thread_id(room_id, original.origin_server_ts)?,
+                message_id: matrix_message_id(&original.event_id, original.origin_server_ts),
+            }
+        } else if let Some(original_id) = replaced_event_id(event) {
+            let original = self.client.get_event(room_id, original_id).await?;
+            let thread_id = self.thread_id(room_id, original.origin_server_ts)?;
+            let Some(message) = edited_message(&self.client, event, &original, thread_id) else {
+                return Ok(());
+            };
+            MessageEvent::Updated(message)
+        } else {
+            let thread_id = self.thread_id(room_id, event.origin_server_ts)?;
+            let Some(message) = source_message(&self.client, event, thread_id) else {
+                return Ok(());
+            };
+            self.ensure_diary_entry(room_id, message.posted_at).await?;
+            MessageEvent::Created(message)
+        };
+
+        let syncer = MessageSyncer::new(
+            self.source.clone(),
+            self.notion_client.as_ref(),
+            &self.diary_store,
+            &self.config.diary.sync_options(&self.config.features),
+            &self.temp_workspace,
+        )?;
+        match syncer.handle_event(&message_event).await? {
+            EventOutcome::Created(result) if result.synced => {
+                info!(
+                    room_id = %room_id,
+                    event_id = %event.event_id,
+                    blocks = result.block_count(),
+                    "Matrix message synced to Notion"
+                );
+            }
+            EventOutcome::Updated(true) | EventOutcome::Deleted(true) => {
+                info!(
+                    room_id = %room_id,
+                    event_id = %event.event_id,
+                    "Matrix message change applied to Notion"
+                );
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// ルームのその日の日報エントリがなければ作成する。
+    ///
+    /// 同じタイトルの Notion ページ（Discord の日報など）があればそのページを使う。
+    async fn ensure_diary_entry(&self, room_id: &str, posted_at: DateTime<Utc>) -> Result<()> {
+        let diary_config = &self.config.diary;
+        let local_date = local_date(posted_at, diary_config);
+        let thread_id = matrix_thread_id(room_id, local_date);
+
+        let _creation_guard = self.diary_creation_lock.lock().await;
+        if self.diary_store.get_by_thread(thread_id).await?.is_some() {
+            return Ok(());
+        }
+
+        let page_title = render_title(&diary_config.page_title_format, local_date);
+        let (page_id, page_url) = match self
+            .notion_client
+            .find_diary_page_by_title(&page_title)
+            .await
+            .context("Failed to search Notion page")?
+        {
+            Some(page) => page,
+            None => create_templated_page(
+                &self.notion_client,
+                &page_title,
+                local_date,
+                self.config.features.is_enabled(Feature::PageSummary),
+                self.page_template.as_ref(),
+            )
+            .await
+            .context("Failed to create Notion page")?,
+        };
+
+        let entry = DiaryEntry {
+            thread_id,
+            forum_channel_id: matrix_room_key(room_id),
+            page_id,
+            page_url,
+            date: start_of_day_in_timezone(local_date, &diary_config.timezone)?,
+            created_at: Utc::now(),
+            closed_at: None,
+        };
+        self.diary_store.insert(&entry).await?;
+
+        info!(room_id = %room_id, date = %local_date, thread_id, "Matrix diary entry created");
+
+        Ok(())
+    }
+
+    /// イベントの送信日時から、ルームのその日の日報エントリのスレッド ID を求める。
+    fn thread_id(&self, room_id: &str, origin_server_ts: i64) -> Result<u64> {
+        let posted_at =
+            DateTime::from_timestamp_millis(origin_server_ts).context("Invalid event timestamp")?;
+        Ok(matrix_thread_id(
+            room_id,
+            local_date(posted_at, &self.config.diary),
+        ))
+    }
+}
+
+/// 日報のタイムゾーンでの日付を返す。
+fn local_date(posted_at: DateTime<Utc>, diary_config: &DiaryConfig) -> NaiveDate {
+    posted_at.with_timezone(&diary_config.timezone).date_naive()
+}
+
+/// 日報に同期するルームの投稿・取り消しだけを取得する `/sync` のフィルターを返す。
+fn sync_filter(room_ids: &[String]) -> String {
+    serde_json::json!({
+        "presence": { "types": [] },
+        "account_data": { "types": [] },
+        "room": {
+            "rooms": room_ids,
+            "state": { "types": [] },
+            "ephemeral": { "types": [] },
+            "account_data": { "types": [] },
+            "timeline": { "types": ["m.room.message", "m.room.redaction"] }
+        }
+    })
+    .to_string()
+}
@@ -3,25 +3,107 @@
 use std::{net::IpAddr, time::Duration};
 
 use surge_ping::{Client, Config, PingIdentifier, PingSequence};
+use tokio::time::Instant;
 
-/// 指定されたIPアドレスにICMP pingを送信し、到達可能かどうかを判定する。
+/// 応答を受け取ってから次の ping を送るまでの間隔。
+const PING_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 複数回の ping の結果。
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PingResult {
+    /// 送信した回数
+    pub sent: u16,
+    /// 応答を受け取った回数
+    pub received: u16,
+    /// 応答時間の最小値（応答が無い場合は None）
+    pub rtt_min: Option<Duration>,
+    /// 応答時間の平均値（応答が無い場合は None）
+    pub rtt_avg: Option<Duration>,
+    /// 応答時間の最大値（応答が無い場合は None）
+    pub rtt_max: Option<Duration>,
+}
+
+impl PingResult {
+    /// 送信した回数と受け取った応答の応答時間から結果を作る。
+    fn from_rtts(sent: u16, rtts: &[Duration]) -> Self {
+        let received = rtts.len() as u16;
+        Self {
+            sent,
+            received,
+            rtt_min: rtts.iter().min().copied(),
+            rtt_avg: (received > 0).then(|| rtts.iter().sum::<Duration>() / u32::from(received)),
+            rtt_max: rtts.iter().max().copied(),
+        }
+    }
+
+    /// 1 回でも応答があったかどうかを返す。
+    pub fn is_reachable(&self) -> bool {
+        self.received > 0
+    }
+
+    /// パケットロス率（%）を返す（送信していない場合は 100%）。
+    pub fn packet_loss(&self) -> f64 {
+        if self.sent == 0 {
+            return 100.0;
+        }
+        f64::from(self.sent - self.received) / f64::from(self.sent) * 100.0
+    }
+
+    /// 応答時間とパケットロス率を表示用の文字列にする（応答が無い場合は None）。
+    ///
+    /// 例: `12.3 ms (10.1–15.0), 33% loss`（パケットロスが無い場合は loss を省く）
+    pub fn format_latency(&self) -> Option<String> {
+        let millis = |rtt: Duration| rtt.as_secs_f64() * 1000.0;
+        let mut text = format!(
+            "{:.1} ms ({:.1}–{:.1})",
+            millis(self.rtt_avg?),
+            millis(self.rtt_min?),
+            millis(self.rtt_max?)
+        );
+        if self.received < self.sent {
+            text.push_str(&format!(", {:.0}% loss", self.packet_loss()));
+        }
+        Some(text)
+    }
+}
+
+/// 指定されたIPアドレスにICMP pingを最大 `count` 回送信し、応答時間とパケットロス率を返す。
+///
+/// 応答の無いサーバーで待ち時間が回数分に延びないよう、全体で `timeout` を過ぎたら残りは送らない。
 ///
 /// # Arguments
 /// * `addr` - pingを送信する対象のIPアドレス
-/// * `timeout` - 応答を待機する最大時間
+/// * `timeout` - すべての ping の応答を待機する最大時間
+/// * `count` - 送信する最大の回数
 ///
 /// # Returns
-/// サーバーが応答した場合は `true`、タイムアウトまたはエラーの場合は `false`
-pub async fn ping(addr: IpAddr, timeout: Duration) -> bool {
+/// 送信した回数と応答時間。クライアントを作成できなかった場合は 1 回も応答が無かったものとして扱う
+pub async fn ping(addr: IpAddr, timeout: Duration, count: u16) -> PingResult {
     let client = match Client::new(&Config::default()) {
         Ok(client) => client,
-        Err(_) => return false,
+        Err(_) => return PingResult::from_rtts(count, &[]),
     };
 
     let mut pinger = client.pinger(addr, PingIdentifier(rand_id())).await;
-    pinger.timeout(timeout);
+    let deadline = Instant::now() + timeout;
 
-    pinger.ping(PingSequence(0), &[]).await.is_ok()
+    let mut sent = 0;
+    let mut rtts = Vec::with_capacity(usize::from(count));
+    for sequence in 0..count {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        pinger.timeout(remaining);
+        sent += 1;
+        if let Ok((_, rtt)) = pinger.ping(PingSequence(sequence), &[]).await {
+            rtts.push(rtt);
+            if sequence + 1 < count {
+                tokio::time::sleep(PING_INTERVAL.min(remaining)).await;
+            }
+        }
+    }
+    PingResult::from_rtts(sent, &rtts)
 }
 
 /// ping識別子として使用するランダムなIDを生成する。
@@ -34,3 +116,31 @@ fn rand_id() -> u16 {
         .unwrap_or_default();
     (duration.as_nanos() & 0xFFFF) as u16
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ping_result() {
+        let result = PingResult::from_rtts(
+            3,
+            &[Duration::from_micros(10_100), Duration::from_micros(15_100)],
+        );
+        assert!(result.is_reachable());
+        assert_eq!(result.rtt_avg, Some(Duration::from_micros(12_600)));
+        assert_eq!(
+            result.format_latency().as_deref(),
+            Some("12.6 ms (10.1–15.1), 33% loss")
+        );
+
+        let result = PingResult::from_rtts(3, &[Duration::from_millis(2); 3]);
+        assert_eq!(result.packet_loss(), 0.0);
+        assert_eq!(result.format_latency().as_deref(), Some("2.0 ms (2.0–2.0)"));
+
+        let result = PingResult::from_rtts(3, &[]);
+        assert!(!result.is_reachable());
+        assert_eq!(result.packet_loss(), 100.0);
+        assert_eq!(result.format_latency(), None);
+    }
+}
@@ -0,0 +1,393 @@
+//! cron 形式のスケジュールで定期実行ジョブを管理する。
+//!
+//! ジョブの実行自体は呼び出し側が行い、ここでは実行すべきジョブの判定と次回実行日時の計算を扱う。
+
+use std::{collections::HashMap, str::FromStr};
+
+use anyhow::{Context as _, Error, Result, bail};
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+
+use crate::config::{JobAction, SchedulerConfig};
+
+/// 次回実行日時を探す最大の日数（2 月 29 日だけに一致するスケジュールも見つけられる長さ）。
+const MAX_SEARCH_DAYS: u64 = 366 * 8;
+
+/// cron 形式のスケジュール（分 時 日 月 曜日）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    /// 一致する分（0〜59 のビット）
+    minutes: u64,
+    /// 一致する時（0〜23 のビット）
+    hours: u64,
+    /// 一致する日（1〜31 のビット）
+    days: u64,
+    /// 一致する月（1〜12 のビット）
+    months: u64,
+    /// 一致する曜日（0〜6 のビット、0 が日曜日）
+    weekdays: u64,
+    /// 日が `*` 以外で指定されているか
+    days_restricted: bool,
+    /// 曜日が `*` 以外で指定されているか
+    weekdays_restricted: bool,
+}
+
+/// 設定から作成した定期実行ジョブ。
+#[derive(Debug, Clone)]
+pub struct ScheduledJob {
+    /// ジョブ名
+    pub name: String,
+    /// 設定に記述されたスケジュール
+    pub schedule_text: String,
+    /// スケジュール
+    pub schedule: CronSchedule,
+    /// 実行する処理
+    pub action: JobAction,
+}
+
+/// 定期実行ジョブの一覧と、実行すべきジョブの判定に使う情報。
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    /// スケジュールを解釈するタイムゾーン
+    timezone: Tz,
+    /// ジョブの一覧（設定の記述順）
+    jobs: Vec<ScheduledJob>,
+    /// スケジューラーを起動した日時（一度も実行していないジョブの起点）
+    started_at: DateTime<Utc>,
+}
+
+impl CronSchedule {
+    /// 指定した日時より後で、スケジュールに一致する最初の日時を返す。
+    ///
+    /// タイムゾーンの夏時間の切り替えで存在しない時刻は飛ばす。
+    pub fn next_after(&self, after: DateTime<Utc>, timezone: &Tz) -> Option<DateTime<Utc>> {
+        let start = after.with_timezone(timezone).naive_local();
+        let mut date = start.date();
+
+        for _ in 0..MAX_SEARCH_DAYS {
+            if self.matches_date(date) {
+                for hour in (0..24).filter(|hour| bit_is_set(self.hours, *hour)) {
+                    for minute in (0..60).filter(|minute| bit_is_set(self.minutes, *minute)) {
+                        let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+                        let Some(candidate) = date
+                            .and_time(time)
+                            .and_local_timezone(*timezone)
+                            .earliest()
+                            .map(|time| time.to_utc())
+                        else {
+                            continue;
+                        };
+                        if candidate > after {
+                            return Some(candidate);
+                        }
+                    }
+                }
+            }
+            date = date.checked_add_days(Days::new(1))?;
+        }
+
+        None
+    }
+
+    /// 日付がスケジュールの日・月・曜日に一致するかどうかを返す。
+    ///
+    /// 日と曜日の両方が指定されている場合は、cron と同じくどちらかに一致すればよい。
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !bit_is_set(self.months, date.month()) {
+            return false;
+        }
+
+        let day = bit_is_set(self.days, date.day());
+        let weekday = bit_is_set(self.weekdays, date.weekday().num_days_from_sunday());
+        if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expression = match s.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            expression => expression,
+        };
+
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let &[minute, hour, day, month, weekday] = fields.as_slice() else {
+            bail!(
+                "Cron schedule must have 5 fields (minute hour day month weekday): {}",
+                s
+            );
+        };
+
+        // 曜日の 7 は日曜日（0）として扱う
+        let weekdays = parse_field(weekday, 0, 7).context("Invalid weekday field")?;
+        let weekdays = (weekdays | (weekdays >> 7)) & 0x7f;
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59).context("Invalid minute field")?,
+            hours: parse_field(hour, 0, 23).context("Invalid hour field")?,
+            days: parse_field(day, 1, 31).context("Invalid day field")?,
+            months: parse_field(month, 1, 12).context("Invalid month field")?,
+            weekdays,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+}
+
+impl Scheduler {
+    /// 設定からスケジューラーを作成する。
+    ///
+    /// スケジュールの書式が正しくない場合や、ジョブ名が重複している場合はエラーとして返す。
+    pub fn new(config: &SchedulerConfig, started_at: DateTime<Utc>) -> Result<Self> {
+        let mut jobs: Vec<ScheduledJob> = Vec::new();
+        for job in &config.jobs {
+            if jobs.iter().any(|existing| existing.name == job.name) {
+                bail!("Duplicate scheduled job name: {}", job.name);
+            }
+            let schedule = job
+                .schedule
+                .parse()
+                .with_context(|| format!("Invalid schedule for job '{}'", job.name))?;
+            jobs.push(ScheduledJob {
+                name: job.name.clone(),
+                schedule_text: job.schedule.clone(),
+                schedule,
+                action: job.action.clone(),
+            });
+        }
+
+        Ok(Self {
+            timezone: config.timezone,
+            jobs,
+            started_at,
+        })
+    }
+
+    /// ジョブの一覧を返す。
+    pub fn jobs(&self) -> &[ScheduledJob] {
+        &self.jobs
+    }
+
+    /// ジョブの次回実行日時を返す。
+    ///
+    /// `last_run_at` は最後に実行した日時で、一度も実行していない場合はスケジューラーの起動日時を起点にする。
+    pub fn next_run(
+        &self,
+        job: &ScheduledJob,
+        last_run_at: Option<DateTime<Utc>>,
+    ) -> Option<DateTime<Utc>> {
+        job.schedule
+            .next_after(last_run_at.unwrap_or(self.started_at), &self.timezone)
+    }
+
+    /// 実行すべきジョブの一覧を返す。
+    ///
+    /// 停止中に複数回分の実行日時を過ぎていても、ジョブは 1 回だけ実行する。
+    pub fn due_jobs(
+        &self,
+        last_runs: &HashMap<String, DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Vec<&ScheduledJob> {
+        self.jobs
+            .iter()
+            .filter(|job| {
+                self.next_run(job, last_runs.get(&job.name).copied())
+                    .is_some_and(|next| next <= now)
+            })
+            .collect()
+    }
+}
+
+/// cron の 1 フィールドを、一致する値のビットに変換する。
+///
+/// `*`、`5`、`1-5`、`*/15`、`10-50/10` とそのカンマ区切りのリストに対応する。
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let mut bits = 0;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .with_context(|| format!("Invalid step: {}", part))?;
+                if step == 0 {
+                    bail!("Step must be greater than 0: {}", part);
+                }
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, part)?, parse_value(end, part)?)
+        } else {
+            let value = parse_value(range, part)?;
+            // "5/10" のように開始値だけにステップを付けた場合は最大値まで繰り返す
+            (value, if step.is_some() { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            bail!("Value out of range {}-{}: {}", min, max, part);
+        }
+
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+fn parse_value(value: &str, part: &str) -> Result<u32> {
+    value
+        .parse()
+        .with_context(|| format!("Invalid value: {}", part))
+}
+
+fn bit_is_set(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone as _;
+
+    use crate::config::JobConfig;
+
+    use super::*;
+
+    fn tokyo(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        chrono_tz::Asia::Tokyo
+            .with_ymd_and_hms(y, m, d, h, min, 0)
+            .unwrap()
+            .to_utc()
+    }
+
+    fn next(schedule: &str, after: DateTime<Utc>) -> DateTime<Utc> {
+        schedule
+            .parse::<CronSchedule>()
+            .unwrap()
+            .next_after(after, &chrono_tz::Asia::Tokyo)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_cron_schedule() {
+        assert!("0 7 * * *".parse::<CronSchedule>().is_ok());
+        assert!("*/15 9-18 * * 1-5".parse::<CronSchedule>().is_ok());
+        assert!("0 0 1,15 * 7".parse::<CronSchedule>().is_ok());
+        assert!("@daily".parse::<CronSchedule>().is_ok());
+
+        assert!("0 7 * *".parse::<CronSchedule>().is_err());
+        assert!("60 7 * * *".parse::<CronSchedule>().is_err());
+        assert!("0 7 0 * *".parse::<CronSchedule>().is_err());
+        assert!("*/0 * * * *".parse::<CronSchedule>().is_err());
+        assert!("0 18-9 * * *".parse::<CronSchedule>().is_err());
+        assert!("@reboot".parse::<CronSchedule>().is_err());
+    }
+
+    #[test]
+    fn test_next_after() {
+        // 2025-01-06 は月曜日
+        let monday_noon = tokyo(2025, 1, 6, 12, 0);
+        assert_eq!(next("0 7 * * *", monday_noon), tokyo(2025, 1, 7, 7, 0));
+        assert_eq!(next("*/15 * * * *", monday_noon), tokyo(2025, 1, 6, 12, 15));
+        assert_eq!(
+            next("0 9 * * 1-5", tokyo(2025, 1, 10, 10, 0)),
+            tokyo(2025, 1, 13, 9, 0)
+        );
+        assert_eq!(
+            next("0 0 * * 0", monday_noon),
+            next("0 0 * * 7", monday_noon)
+        );
+        assert_eq!(next("@monthly", monday_noon), tokyo(2025, 2, 1, 0, 0));
+        assert_eq!(next("0 0 29 2 *", monday_noon), tokyo(2028, 2, 29, 0, 0));
+    }
+
+    #[test]
+    fn test_next_after_matches_day_or_weekday() {
+        // 日と曜日の両方を指定した場合は、どちらかに一致すればよい（2025-01-10 は金曜日）
+        assert_eq!(
+            next("0 0 15 * 5", tokyo(2025, 1, 6, 12, 0)),
+            tokyo(2025, 1, 10, 0, 0)
+        );
+        assert_eq!(
+            next("0 0 15 * 5", tokyo(2025, 1, 13, 12, 0)),
+            tokyo(2025, 1, 15, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_due_jobs_runs_missed_job_once() {
+        let config = SchedulerConfig {
+            timezone: chrono_tz::Asia::Tokyo,
+            jobs: vec![
+                JobConfig {
+                    name: "morning".to_string(),
+                    schedule: "0 7 * * *".to_string(),
+                    action: JobAction::DiaryNew,
+                },
+                JobConfig {
+                    name: "hourly".to_string(),
+                    schedule: "@hourly".to_string(),
+                    action: JobAction::AutoClose,
+                },
+            ],
+            ..SchedulerConfig::default()
+        };
+        let started_at = tokyo(2025, 1, 6, 6, 30);
+        let scheduler = Scheduler::new(&config, started_at).unwrap();
+
+        // 起動後に初めて実行日時を迎えたジョブだけが対象になる
+        let names = |due: Vec<&ScheduledJob>| {
+            due.into_iter()
+                .map(|job| job.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert!(
+            scheduler
+                .due_jobs(&HashMap::new(), tokyo(2025, 1, 6, 6, 45))
+                .is_empty()
+        );
+        assert_eq!(
+            names(scheduler.due_jobs(&HashMap::new(), tokyo(2025, 1, 6, 7, 0))),
+            vec!["morning", "hourly"]
+        );
+
+        // 停止中に何日分も過ぎていても、記録があれば 1 回だけ対象になる
+        let last_runs = HashMap::from([
+            ("morning".to_string(), tokyo(2025, 1, 1, 7, 0)),
+            ("hourly".to_string(), tokyo(2025, 1, 6, 7, 0)),
+        ]);
+        assert_eq!(
+            names(scheduler.due_jobs(&last_runs, tokyo(2025, 1, 6, 7, 30))),
+            vec!["morning"]
+        );
+    }
+
+    #[test]
+    fn test_scheduler_rejects_duplicate_names() {
+        let job = JobConfig {
+            name: "morning".to_string(),
+            schedule: "0 7 * * *".to_string(),
+            action: JobAction::DiaryNew,
+        };
+        let config = SchedulerConfig {
+            timezone: chrono_tz::Asia::Tokyo,
+            jobs: vec![job.clone(), job],
+            ..SchedulerConfig::default()
+        };
+        assert!(Scheduler::new(&config, Utc::now()).is_err());
+    }
+}
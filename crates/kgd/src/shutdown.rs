@@ -0,0 +1,246 @@
+//! SSH でリモートのサーバーをシャットダウン・再起動・サスペンドする。
+//!
+//! `ssh` コマンドでサーバーに接続し、操作ごとに設定したコマンドを実行する。
+//! パスワードの入力や未知のホスト鍵の確認で止まらないよう、BatchMode で接続する。
+
+use std::{ffi::OsString, process::Stdio, time::Duration};
+
+use anyhow::{Context as _, Result};
+use tokio::process::Command;
+use tracing::info;
+
+use crate::config::{Config, ServerConfig, ShutdownConfig};
+
+/// SSH の接続を待つ秒数。
+const SSH_CONNECT_TIMEOUT_SECS: u32 = 10;
+
+/// 応答の無くなったサーバーとの接続を切るまでの keepalive の間隔（秒）。
+///
+/// サスペンドしたサーバーは接続を閉じずに止まるため、応答が無くなったら切断する。
+const SSH_SERVER_ALIVE_INTERVAL_SECS: u32 = 5;
+
+/// コマンドの完了を待つ時間。
+const POWER_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 接続が切断されたときの ssh の終了コード。
+const SSH_CONNECTION_ERROR: i32 = 255;
+
+/// SSH で行う電源の操作。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerAction {
+    /// シャットダウン
+    Shutdown,
+    /// 再起動
+    Reboot,
+    /// サスペンド
+    Suspend,
+}
+
+impl PowerAction {
+    /// すべての操作。
+    pub const ALL: [PowerAction; 3] = [
+        PowerAction::Shutdown,
+        PowerAction::Reboot,
+        PowerAction::Suspend,
+    ];
+
+    /// コマンド名を返す。
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerAction::Shutdown => "shutdown",
+            PowerAction::Reboot => "reboot",
+            PowerAction::Suspend => "suspend",
+        }
+    }
+
+    /// コマンド名から操作を取得する。
+    pub fn from_name(name: &str) -> Option<PowerAction> {
+        PowerAction::ALL
+            .into_iter()
+            .find(|action| action.name() == name)
+    }
+
+    /// ボタンなどに表示する操作の名前を返す。
+    pub fn label(self) -> &'static str {
+        match self {
+            PowerAction::Shutdown => "Shut down",
+            PowerAction::Reboot => "Reboot",
+            PowerAction::Suspend => "Suspend",
+        }
+    }
+
+    /// サーバー上で実行するコマンドを返す。
+    fn command(self, config: &ShutdownConfig) -> &str {
+        match self {
+            PowerAction::Shutdown => &config.command,
+            PowerAction::Reboot => &config.reboot_command,
+            PowerAction::Suspend => &config.suspend_command,
+        }
+    }
+}
+
+/// SSH で実行したコマンドの結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerOutcome {
+    /// ssh の終了コード（シグナルで終了した場合は None）
+    pub exit_code: Option<i32>,
+    /// ssh とコマンドの標準エラー出力
+    pub stderr: String,
+    /// コマンドの完了前に接続が切れたかどうか
+    pub disconnected: bool,
+}
+
+impl PowerOutcome {
+    /// 操作が始まったかどうかを返す。
+    ///
+    /// 電源の操作が始まってコマンドの完了前に接続が切れた場合も成功として扱う。
+    pub fn is_success(&self) -> bool {
+        self.exit_code == Some(0) || self.disconnected
+    }
+}
+
+/// サーバーに SSH で接続し、操作のコマンドを実行して結果を返す。
+///
+/// ssh を実行できなかった場合やタイムアウトした場合はエラーを返す。
+pub async fn send_power_action(
+    server: &ServerConfig,
+    config: &ShutdownConfig,
+    action: PowerAction,
+) -> Result<PowerOutcome> {
+    let child = Command::new("ssh")
+        .args(ssh_args(server, config, action))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Failed to run ssh")?;
+
+    let output = tokio::time::timeout(POWER_COMMAND_TIMEOUT, child.wait_with_output())
+        .await
+        .with_context(|| format!("ssh timed out after {:?}", POWER_COMMAND_TIMEOUT))?
+        .context("Failed to wait for ssh")?;
+
+    let exit_code = output.status.code();
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    let disconnected = exit_code == Some(SSH_CONNECTION_ERROR)
+        && (stderr.contains("closed by remote host") || stderr.contains("not responding"));
+    Ok(PowerOutcome {
+        exit_code,
+        stderr,
+        disconnected,
+    })
+}
+
+/// 名前で指定したサーバーに SSH で接続し、電源の操作のコマンドを実行する。
+///
+/// コマンドの終了コードに関わらず、実行できた場合は結果を返す。
+pub async fn power_server<'a>(
+    config: &'a Config,
+    name: &str,
+    action: PowerAction,
+) -> Result<(&'a ServerConfig, PowerOutcome)> {
+    let server = config
+        .find_server(name)
+        .with_context(|| format!("Server '{}' not found", name))?;
+    let shutdown = server
+        .shutdown
+        .as_ref()
+        .with_context(|| format!("Shutdown is not configured for '{}'", server.name))?;
+    let outcome = send_power_action(server, shutdown, action)
+        .await
+        .with_context(|| format!("Failed to run {} command", action.name()))?;
+    info!(
+        server = %server.name,
+        action = action.name(),
+        exit_code = ?outcome.exit_code,
+        "Power command sent"
+    );
+    Ok((server, outcome))
+}
+
+/// 操作のコマンドを実行する ssh の引数を作成する。
+fn ssh_args(server: &ServerConfig, config: &ShutdownConfig, action: PowerAction) -> Vec<OsString> {
+    let host = config.host.as_deref().unwrap_or(&server.ip_address);
+    let mut args: Vec<OsString> = vec![
+        "-o".into(),
+        "BatchMode=yes".into(),
+        "-o".into(),
+        format!("ConnectTimeout={}", SSH_CONNECT_TIMEOUT_SECS).into(),
+        "-o".into(),
+        format!("ServerAliveInterval={}", SSH_SERVER_ALIVE_INTERVAL_SECS).into(),
+        "-o".into(),
+        "ServerAliveCountMax=2".into(),
+        "-p".into(),
+        config.port.to_string().into(),
+    ];
+    if let Some(identity_file) = &config.identity_file {
+        args.push("-i".into());
+        args.push(identity_file.into());
+    }
+    args.push(format!("{}@{}", config.user, host).into());
+    args.push(action.command(config).into());
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_ssh_args() {
+        let server = ServerConfig::default();
+        let mut config = ShutdownConfig {
+            host: None,
+            port: 2222,
+            user: "kgd".to_string(),
+            identity_file: Some(PathBuf::from("/etc/kgd/id_ed25519")),
+            command: "sudo systemctl poweroff".to_string(),
+            reboot_command: "sudo systemctl reboot".to_string(),
+            suspend_command: "sudo systemctl suspend".to_string(),
+        };
+        assert_eq!(
+            ssh_args(&server, &config, PowerAction::Shutdown),
+            [
+                "-o",
+                "BatchMode=yes",
+                "-o",
+                "ConnectTimeout=10",
+                "-o",
+                "ServerAliveInterval=5",
+                "-o",
+                "ServerAliveCountMax=2",
+                "-p",
+                "2222",
+                "-i",
+                "/etc/kgd/id_ed25519",
+                "kgd@192.168.1.100",
+                "sudo systemctl poweroff",
+            ]
+        );
+
+        config.host = Some("nas.local".to_string());
+        config.identity_file = None;
+        let args = ssh_args(&server, &config, PowerAction::Reboot);
+        assert!(!args.contains(&OsString::from("-i")));
+        assert_eq!(args[args.len() - 2], "kgd@nas.local");
+        assert_eq!(args[args.len() - 1], "sudo systemctl reboot");
+    }
+
+    #[test]
+    fn test_power_outcome_is_success() {
+        let outcome = |exit_code, disconnected| PowerOutcome {
+            exit_code,
+            stderr: String::new(),
+            disconnected,
+        };
+        assert!(outcome(Some(0), false).is_success());
+        assert!(outcome(Some(255), true).is_success());
+        assert!(!outcome(Some(1), false).is_success());
+        assert!(!outcome(None, false).is_success());
+        assert_eq!(PowerAction::from_name("reboot"), Some(PowerAction::Reboot));
+        assert_eq!(PowerAction::from_name("poweroff"), None);
+    }
+}
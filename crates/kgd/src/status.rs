@@ -1,46 +1,407 @@
 //! サーバーステータスの監視機能を提供する。
 //!
 //! 設定されたサーバー一覧に対してpingを実行し、オンライン/オフライン状態を取得する。
+//! `check_ports` を設定したサーバーは、ポートごとに TCP で接続できるかも確認する。
+//! `health_url` を設定したサーバーは、ヘルスチェックの URL が 200 を返すかも確認する。
 
-use std::{net::IpAddr, time::Duration};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use tracing::info;
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use tokio::net::TcpStream;
+use tracing::{info, warn};
 
-use crate::{config::ServerConfig, ping::ping};
+use crate::{
+    config::ServerConfig,
+    ping::{PingResult, ping},
+};
+
+/// 各サーバーへの ping の待機時間。
+pub const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// ステータスチェックで各サーバーに ping を送る回数。
+pub const PING_COUNT: u16 = 3;
+
+/// 各ポートへの TCP 接続の待機時間。
+pub const PORT_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// ヘルスチェックの URL の応答の待機時間。
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// サーバーのステータス情報を表す構造体。
+#[derive(Clone)]
 pub struct ServerStatus {
     /// サーバー名
     pub name: String,
     /// オンライン状態 (`true`: オンライン, `false`: オフライン)
     pub online: bool,
+    /// ping の応答時間とパケットロス率
+    pub ping: PingResult,
+    /// `check_ports` に設定したポートの開閉（オフラインのサーバーは確認しないため空）
+    pub ports: Vec<PortStatus>,
+    /// ヘルスチェックの結果（`health_url` が未設定か、オフラインのサーバーは None）
+    pub health: Option<HealthStatus>,
+}
+
+#[cfg(test)]
+impl ServerStatus {
+    /// テスト用に、ping・ポート・ヘルスチェックの結果を持たないステータスを作成する。
+    pub fn for_test(name: &str, online: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            online,
+            ping: PingResult::default(),
+            ports: vec![],
+            health: None,
+        }
+    }
+}
+
+/// TCP ポートの開閉の状態を表す構造体。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortStatus {
+    /// ポート番号
+    pub port: u16,
+    /// TCP で接続できたかどうか
+    pub open: bool,
+}
+
+/// ヘルスチェックの URL の応答を表す構造体。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthStatus {
+    /// HTTP ステータスコード（応答が無かった場合は None）
+    pub status_code: Option<u16>,
+    /// 応答までの時間（応答が無かった場合は None）
+    pub response_time: Option<Duration>,
+}
+
+impl HealthStatus {
+    /// ヘルスチェックが 200 を返したかどうかを返す。
+    pub fn is_healthy(&self) -> bool {
+        self.status_code == Some(200)
+    }
+
+    /// ステータスコードと応答時間を表示用の文字列にする。
+    pub fn format(&self) -> String {
+        let mark = if self.is_healthy() { "✅" } else { "❌" };
+        match (self.status_code, self.response_time) {
+            (Some(code), Some(elapsed)) => format!(
+                "Health: {} {} ({:.0} ms)",
+                code,
+                mark,
+                elapsed.as_secs_f64() * 1000.0
+            ),
+            _ => format!("Health: no response {}", mark),
+        }
+    }
 }
 
 /// 複数のサーバーに対してpingを実行し、それぞれのステータスを取得する。
 ///
+/// サーバーの台数だけ待ち時間が延びないよう、すべてのサーバーを並行して確認する。
+/// 各サーバーに最大 [`PING_COUNT`] 回送信し、1 回でも応答があればオンラインとする。
+/// オンラインのサーバーには、`check_ports` のポートに TCP で接続できるかと、
+/// `health_url` が 200 を返すかも確認する。
+///
 /// # Arguments
 /// * `servers` - チェック対象のサーバー設定リスト
-/// * `timeout` - 各サーバーへのping待機時間
+/// * `timeout` - 各サーバーへのping待機時間（送信する回数に関わらず、サーバーごとの合計）
 ///
 /// # Returns
 /// 各サーバーのステータス情報のリスト
 pub async fn check_servers(servers: &[ServerConfig], timeout: Duration) -> Vec<ServerStatus> {
     info!("Checking server status");
 
-    let mut results = Vec::with_capacity(servers.len());
+    let http_client = servers
+        .iter()
+        .any(|server| server.health_url.is_some())
+        .then(health_check_client)
+        .flatten();
 
-    for server in servers {
-        let online = match server.ip_address.parse::<IpAddr>() {
-            Ok(ip) => ping(ip, timeout).await,
-            Err(_) => false,
-        };
+    join_all(
+        servers
+            .iter()
+            .map(|server| check_server(server, timeout, http_client.as_ref())),
+    )
+    .await
+}
 
-        info!(server = %server.name, online, "Server status checked");
-        results.push(ServerStatus {
-            name: server.name.clone(),
-            online,
-        });
+/// 1 台のサーバーのステータスを取得する。
+async fn check_server(
+    server: &ServerConfig,
+    timeout: Duration,
+    http_client: Option<&reqwest::Client>,
+) -> ServerStatus {
+    let ping = match server.ip_address.parse::<IpAddr>() {
+        Ok(ip) => ping(ip, timeout, PING_COUNT).await,
+        Err(_) => PingResult::default(),
+    };
+    let online = ping.is_reachable();
+    let ports = match server.ip_address.parse::<IpAddr>() {
+        Ok(ip) if online => check_ports(ip, &server.check_ports, PORT_CHECK_TIMEOUT).await,
+        _ => Vec::new(),
+    };
+    let health = match (&server.health_url, http_client) {
+        (Some(url), Some(client)) if online => Some(check_health(client, url).await),
+        (Some(_), None) if online => Some(HealthStatus {
+            status_code: None,
+            response_time: None,
+        }),
+        _ => None,
+    };
+
+    info!(
+        server = %server.name,
+        online,
+        rtt_avg = ?ping.rtt_avg,
+        packet_loss = ping.packet_loss(),
+        closed_ports = ?ports.iter().filter(|port| !port.open).map(|port| port.port).collect::<Vec<_>>(),
+        health_status = ?health.as_ref().map(|health| health.status_code),
+        "Server status checked"
+    );
+    ServerStatus {
+        name: server.name.clone(),
+        online,
+        ping,
+        ports,
+        health,
     }
+}
 
+/// 各ポートに TCP で接続できるかを順に確認する。
+///
+/// `timeout` までに接続できなかった場合や、接続を拒否された場合は閉じているとする。
+pub async fn check_ports(ip: IpAddr, ports: &[u16], timeout: Duration) -> Vec<PortStatus> {
+    let mut results = Vec::with_capacity(ports.len());
+    for &port in ports {
+        let connect = TcpStream::connect(SocketAddr::new(ip, port));
+        let open = matches!(tokio::time::timeout(timeout, connect).await, Ok(Ok(_)));
+        results.push(PortStatus { port, open });
+    }
     results
 }
+
+/// ヘルスチェックに使う HTTP クライアントを作成する（作成できなかった場合は None を返す）。
+fn health_check_client() -> Option<reqwest::Client> {
+    match reqwest::Client::builder()
+        .timeout(HEALTH_CHECK_TIMEOUT)
+        .user_agent("kgd-bot/1.0")
+        .build()
+    {
+        Ok(client) => Some(client),
+        Err(e) => {
+            warn!(error = %e, "Failed to create HTTP client for health checks");
+            None
+        }
+    }
+}
+
+/// ヘルスチェックの URL を GET し、ステータスコードと応答時間を返す。
+///
+/// 接続できなかった場合やタイムアウトした場合は、応答が無かったものとして扱う。
+pub async fn check_health(client: &reqwest::Client, url: &str) -> HealthStatus {
+    let started = Instant::now();
+    match client.get(url).send().await {
+        Ok(response) => HealthStatus {
+            status_code: Some(response.status().as_u16()),
+            response_time: Some(started.elapsed()),
+        },
+        Err(e) => {
+            info!(error = %e, url, "Health check request failed");
+            HealthStatus {
+                status_code: None,
+                response_time: None,
+            }
+        }
+    }
+}
+
+/// ポートの開閉を表示用の文字列にする（ポートを確認していなければ None を返す）。
+pub fn format_ports(ports: &[PortStatus]) -> Option<String> {
+    if ports.is_empty() {
+        return None;
+    }
+    let ports = ports
+        .iter()
+        .map(|port| format!("{} {}", port.port, if port.open { "✅" } else { "❌" }))
+        .collect::<Vec<_>>()
+        .join(" · ");
+    Some(format!("Ports: {}", ports))
+}
+
+/// サーバーが ping に応答するまで `interval` ごとに確認し、応答するまでの経過時間を返す。
+///
+/// `timeout` までに応答しなかった場合や、IP アドレスが不正な場合は None を返す。
+pub async fn wait_until_online(
+    server: &ServerConfig,
+    interval: Duration,
+    timeout: Duration,
+) -> Option<Duration> {
+    let ip = server.ip_address.parse::<IpAddr>().ok()?;
+    let started = Instant::now();
+
+    while started.elapsed() < timeout {
+        tokio::time::sleep(interval).await;
+        let remaining = timeout.saturating_sub(started.elapsed());
+        if ping(ip, interval.min(PING_TIMEOUT).min(remaining), 1)
+            .await
+            .is_reachable()
+        {
+            let elapsed = started.elapsed();
+            info!(server = %server.name, elapsed = ?elapsed, "Server came online");
+            return Some(elapsed);
+        }
+    }
+
+    info!(server = %server.name, timeout = ?timeout, "Server did not come online");
+    None
+}
+
+/// 前回の結果から状態が変わったサーバーを返す。
+///
+/// 前回の結果にないサーバー（起動直後や設定の追加直後）は、オフラインの場合だけ変わったとみなす。
+pub fn status_changes<'a>(
+    previous: &HashMap<String, bool>,
+    statuses: &'a [ServerStatus],
+) -> Vec<&'a ServerStatus> {
+    statuses
+        .iter()
+        .filter(|status| match previous.get(&status.name) {
+            Some(&online) => online != status.online,
+            None => !status.online,
+        })
+        .collect()
+}
+
+/// ステータス通知の一時停止の状態。
+///
+/// 定期的なステータスの投稿と、重大でない状態変化の通知で共有する。
+#[derive(Clone, Default)]
+pub struct StatusSnooze {
+    /// 通知を再開する日時
+    until: Arc<Mutex<Option<DateTime<Utc>>>>,
+}
+
+impl StatusSnooze {
+    /// 指定した日時まで通知を一時停止する。
+    pub fn snooze_until(&self, until: DateTime<Utc>) {
+        *self.until.lock().unwrap() = Some(until);
+    }
+
+    /// 通知を一時停止しているかどうかを返す。
+    pub fn is_snoozed(&self, now: DateTime<Utc>) -> bool {
+        self.until.lock().unwrap().is_some_and(|until| now < until)
+    }
+
+    /// 一時停止の期限が過ぎていれば解除し、解除した場合は true を返す。
+    pub fn take_expired(&self, now: DateTime<Utc>) -> bool {
+        let mut until = self.until.lock().unwrap();
+        if until.is_some_and(|until| now >= until) {
+            *until = None;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check_ports() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open_port = listener.local_addr().unwrap().port();
+        // 閉じたポートとして、一度確保してから解放したポートを使う
+        let closed_port = {
+            let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            closed.local_addr().unwrap().port()
+        };
+
+        let ports = check_ports(
+            IpAddr::from([127, 0, 0, 1]),
+            &[open_port, closed_port],
+            Duration::from_secs(1),
+        )
+        .await;
+        assert_eq!(
+            ports,
+            vec![
+                PortStatus {
+                    port: open_port,
+                    open: true
+                },
+                PortStatus {
+                    port: closed_port,
+                    open: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_health() {
+        let health = HealthStatus {
+            status_code: Some(200),
+            response_time: Some(Duration::from_micros(35_400)),
+        };
+        assert!(health.is_healthy());
+        assert_eq!(health.format(), "Health: 200 ✅ (35 ms)");
+
+        let health = HealthStatus {
+            status_code: Some(503),
+            response_time: Some(Duration::from_millis(120)),
+        };
+        assert!(!health.is_healthy());
+        assert_eq!(health.format(), "Health: 503 ❌ (120 ms)");
+
+        let health = HealthStatus {
+            status_code: None,
+            response_time: None,
+        };
+        assert_eq!(health.format(), "Health: no response ❌");
+    }
+
+    #[test]
+    fn test_format_ports() {
+        assert_eq!(format_ports(&[]), None);
+        assert_eq!(
+            format_ports(&[
+                PortStatus {
+                    port: 22,
+                    open: true
+                },
+                PortStatus {
+                    port: 5432,
+                    open: false
+                },
+            ])
+            .unwrap(),
+            "Ports: 22 ✅ · 5432 ❌"
+        );
+    }
+
+    #[test]
+    fn test_status_snooze() {
+        let now = Utc::now();
+        let snooze = StatusSnooze::default();
+        assert!(!snooze.is_snoozed(now));
+        assert!(!snooze.take_expired(now));
+
+        snooze.snooze_until(now + chrono::Duration::hours(2));
+        assert!(snooze.is_snoozed(now));
+        assert!(!snooze.take_expired(now));
+
+        let later = now + chrono::Duration::hours(2);
+        assert!(!snooze.is_snoozed(later));
+        assert!(snooze.take_expired(later));
+        // 再開は一度だけ
+        assert!(!snooze.take_expired(later));
+    }
+}
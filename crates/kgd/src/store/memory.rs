@@ -0,0 +1,383 @@
+//! メモリ上に保存するストア。
+//!
+//! データベースを用意せずに動かすときに使う。再起動すると内容は消える。
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use super::{BotStorage, JobRun, JobRunRecord, UptimeSpan, WakeRecord, WakeRequester};
+
+/// メモリ上に保存するストア。
+///
+/// クローンしたストアは同じ内容を共有する。
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    state: Arc<Mutex<State>>,
+}
+
+/// ストアの内容。PostgreSQL のテーブルに対応する。
+#[derive(Default)]
+struct State {
+    /// ステータス通知を再開する日時
+    status_snooze: Option<DateTime<Utc>>,
+    /// 編集で更新し続けるステータスのメッセージの ID
+    status_dashboard_message: Option<u64>,
+    /// Matrix のユーザー ID ごとの `/sync` の再開位置
+    #[cfg_attr(not(feature = "diary"), allow(dead_code))]
+    matrix_sync_tokens: HashMap<String, String>,
+    /// ジョブ名ごとの最後の実行記録
+    job_runs: HashMap<String, JobRun>,
+    /// 定期実行ジョブの実行履歴（ジョブ名付き）
+    job_run_history: Vec<(String, JobRunRecord)>,
+    /// サーバーに Wake-on-LAN のパケットを送った記録（記録した順）
+    wakes: Vec<WakeRecord>,
+    /// サーバーがオンラインだった期間（記録した順）
+    uptime: Vec<UptimeSpan>,
+}
+
+impl MemoryStore {
+    /// 空のストアを作成する。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ストアの内容をロックする。
+    fn state(&self) -> MutexGuard<'_, State> {
+        // 他のスレッドがパニックしても内容は壊れていないため、そのまま使う
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl BotStorage for MemoryStore {
+    async fn get_status_snooze(&self) -> Result<Option<DateTime<Utc>>> {
+        Ok(self.state().status_snooze)
+    }
+
+    async fn set_status_snooze(&self, snoozed_until: DateTime<Utc>) -> Result<()> {
+        self.state().status_snooze = Some(snoozed_until);
+        Ok(())
+    }
+
+    async fn clear_status_snooze(&self) -> Result<()> {
+        self.state().status_snooze = None;
+        Ok(())
+    }
+
+    async fn get_status_dashboard_message(&self) -> Result<Option<u64>> {
+        Ok(self.state().status_dashboard_message)
+    }
+
+    async fn set_status_dashboard_message(&self, message_id: u64) -> Result<()> {
+        self.state().status_dashboard_message = Some(message_id);
+        Ok(())
+    }
+
+    async fn get_matrix_sync_token(&self, user_id: &str) -> Result<Option<String>> {
+        Ok(self.state().matrix_sync_tokens.get(user_id).cloned())
+    }
+
+    async fn set_matrix_sync_token(&self, user_id: &str, next_batch: &str) -> Result<()> {
+        self.state()
+            .matrix_sync_tokens
+            .insert(user_id.to_string(), next_batch.to_string());
+        Ok(())
+    }
+
+    async fn get_job_runs(&self) -> Result<Vec<JobRun>> {
+        Ok(self.state().job_runs.values().cloned().collect())
+    }
+
+    async fn record_job_run(
+        &self,
+        name: &str,
+        started_at: DateTime<Utc>,
+        finished_at: DateTime<Utc>,
+        error: Option<&str>,
+    ) -> Result<i32> {
+        let mut state = self.state();
+        state.job_run_history.push((
+            name.to_string(),
+            JobRunRecord {
+                started_at,
+                finished_at,
+                error: error.map(str::to_string),
+            },
+        ));
+
+        let previous_failures = state
+            .job_runs
+            .get(name)
+            .map_or(0, |run| run.consecutive_failures);
+        let consecutive_failures = match error {
+            Some(_) => previous_failures.saturating_add(1),
+            None => 0,
+        };
+        state.job_runs.insert(
+            name.to_string(),
+            JobRun {
+                name: name.to_string(),
+                last_run_at: started_at,
+                last_error: error.map(str::to_string),
+                consecutive_failures,
+            },
+        );
+        Ok(consecutive_failures)
+    }
+
+    async fn get_job_run_history(&self, name: &str, limit: i64) -> Result<Vec<JobRunRecord>> {
+        let mut history: Vec<JobRunRecord> = self
+            .state()
+            .job_run_history
+            .iter()
+            .filter(|(job_name, _)| job_name == name)
+            .map(|(_, record)| record.clone())
+            .collect();
+        history.sort_by_key(|record| std::cmp::Reverse(record.started_at));
+        history.truncate(usize::try_from(limit).unwrap_or(0));
+        Ok(history)
+    }
+
+    async fn record_wake(
+        &self,
+        server_name: &str,
+        requested_by: WakeRequester,
+        sent_at: DateTime<Utc>,
+    ) -> Result<i64> {
+        let mut state = self.state();
+        let id = state.wakes.len() as i64 + 1;
+        state.wakes.push(WakeRecord {
+            id,
+            server_name: server_name.to_string(),
+            requested_by,
+            sent_at,
+            online_at: None,
+        });
+        Ok(id)
+    }
+
+    async fn record_wake_online(&self, wake_id: i64, online_at: DateTime<Utc>) -> Result<()> {
+        if let Some(wake) = self
+            .state()
+            .wakes
+            .iter_mut()
+            .find(|wake| wake.id == wake_id && wake.online_at.is_none())
+        {
+            wake.online_at = Some(online_at);
+        }
+        Ok(())
+    }
+
+    async fn get_wake_history(&self, server_name: &str, limit: i64) -> Result<Vec<WakeRecord>> {
+        // 同じ日時の記録は後から記録したものを先にする
+        let mut history: Vec<WakeRecord> = self
+            .state()
+            .wakes
+            .iter()
+            .rev()
+            .filter(|wake| wake.server_name == server_name)
+            .cloned()
+            .collect();
+        history.sort_by_key(|wake| std::cmp::Reverse(wake.sent_at));
+        history.truncate(usize::try_from(limit).unwrap_or(0));
+        Ok(history)
+    }
+
+    async fn record_server_online(
+        &self,
+        server_name: &str,
+        seen_at: DateTime<Utc>,
+        max_gap: Duration,
+    ) -> Result<()> {
+        let mut state = self.state();
+        let last = state
+            .uptime
+            .iter_mut()
+            .filter(|span| span.server_name == server_name)
+            .max_by_key(|span| span.last_seen_at);
+        match last {
+            Some(span) if (seen_at - span.last_seen_at).to_std().unwrap_or_default() <= max_gap => {
+                span.last_seen_at = seen_at;
+            }
+            _ => state.uptime.push(UptimeSpan {
+                server_name: server_name.to_string(),
+                started_at: seen_at,
+                last_seen_at: seen_at,
+            }),
+        }
+        Ok(())
+    }
+
+    async fn get_uptime_spans(&self, since: Option<DateTime<Utc>>) -> Result<Vec<UptimeSpan>> {
+        let mut spans: Vec<UptimeSpan> = self
+            .state()
+            .uptime
+            .iter()
+            .filter(|span| since.is_none_or(|since| span.last_seen_at >= since))
+            .cloned()
+            .collect();
+        spans.sort_by_key(|span| span.started_at);
+        Ok(spans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_matrix_sync_token() {
+        let store = MemoryStore::new();
+        assert_eq!(
+            store
+                .get_matrix_sync_token("@kgd:example.org")
+                .await
+                .unwrap(),
+            None
+        );
+        store
+            .set_matrix_sync_token("@kgd:example.org", "s1")
+            .await
+            .unwrap();
+        store
+            .set_matrix_sync_token("@kgd:example.org", "s2")
+            .await
+            .unwrap();
+        assert_eq!(
+            store
+                .get_matrix_sync_token("@kgd:example.org")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("s2")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_job_run() {
+        let store = MemoryStore::new();
+        let now = Utc::now();
+        assert_eq!(
+            store
+                .record_job_run("job", now, now, Some("boom"))
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            store
+                .record_job_run("job", now, now, Some("boom"))
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            store.record_job_run("job", now, now, None).await.unwrap(),
+            0
+        );
+        assert_eq!(store.get_job_run_history("job", 2).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_wake_history() {
+        let store = MemoryStore::new();
+        let sent_at = Utc::now();
+        let first = store
+            .record_wake("Main Server", WakeRequester::Discord(1), sent_at)
+            .await
+            .unwrap();
+        store
+            .record_wake("Storage Server", WakeRequester::Schedule, sent_at)
+            .await
+            .unwrap();
+        let second = store
+            .record_wake(
+                "Main Server",
+                WakeRequester::Telegram(7),
+                sent_at + chrono::Duration::hours(1),
+            )
+            .await
+            .unwrap();
+
+        let online_at = sent_at + chrono::Duration::seconds(58);
+        store.record_wake_online(first, online_at).await.unwrap();
+        // 最初にオンラインになった日時だけを記録する
+        store
+            .record_wake_online(first, online_at + chrono::Duration::minutes(5))
+            .await
+            .unwrap();
+
+        let history = store.get_wake_history("Main Server", 10).await.unwrap();
+        assert_eq!(
+            history.iter().map(|wake| wake.id).collect::<Vec<_>>(),
+            vec![second, first]
+        );
+        assert_eq!(history[0].boot_time(), None);
+        assert_eq!(history[0].requested_by, WakeRequester::Telegram(7));
+        assert_eq!(history[1].requested_by, WakeRequester::Discord(1));
+        assert_eq!(
+            history[1].boot_time(),
+            Some(std::time::Duration::from_secs(58))
+        );
+        assert_eq!(
+            store
+                .get_wake_history("Main Server", 1)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_uptime_spans() {
+        let store = MemoryStore::new();
+        let start = Utc::now();
+        let minutes = |minutes| start + chrono::Duration::minutes(minutes);
+        let max_gap = Duration::from_secs(10 * 60);
+
+        for seen_at in [minutes(0), minutes(5), minutes(10)] {
+            store
+                .record_server_online("Main Server", seen_at, max_gap)
+                .await
+                .unwrap();
+        }
+        store
+            .record_server_online("Storage Server", minutes(5), max_gap)
+            .await
+            .unwrap();
+        // 確認の間隔が空いた場合は新しい期間にする
+        store
+            .record_server_online("Main Server", minutes(60), max_gap)
+            .await
+            .unwrap();
+        store
+            .record_server_online("Main Server", minutes(65), max_gap)
+            .await
+            .unwrap();
+
+        let spans = store.get_uptime_spans(None).await.unwrap();
+        let main: Vec<_> = spans
+            .iter()
+            .filter(|span| span.server_name == "Main Server")
+            .collect();
+        assert_eq!(main.len(), 2);
+        assert_eq!(main[0].duration_since(None), Duration::from_secs(10 * 60));
+        assert_eq!(
+            main[0].duration_since(Some(minutes(8))),
+            Duration::from_secs(2 * 60)
+        );
+        assert_eq!(main[1].duration_since(None), Duration::from_secs(5 * 60));
+
+        let recent = store.get_uptime_spans(Some(minutes(30))).await.unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].started_at, minutes(60));
+    }
+}
@@ -0,0 +1,333 @@
+//! ボット自身の状態（定期実行ジョブの実行記録・ステータス通知・サーバーの起動記録など）を永続化するストア。
+//!
+//! 日報の機能とは独立しており、`diary` フィーチャーを無効にしたビルドでも使う。
+//! 保存先ごとの実装は [`BotStorage`] を実装する。通常は PostgreSQL に保存し（`PostgresStore`）、
+//! データベースを用意しない場合はメモリ上に保存する（[`MemoryStore`]）。
+//! PostgreSQL の実装は `postgres` フィーチャーが有効な場合だけ含める。
+
+mod memory;
+#[cfg(feature = "postgres")]
+mod postgres;
+
+use std::{future::Future, time::Duration};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+pub use memory::MemoryStore;
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresStore;
+
+/// インメモリのストアを使う場合に指定するデータベース URL のスキーム。
+pub const MEMORY_URL_SCHEME: &str = "memory:";
+
+/// 定期実行ジョブの実行記録。
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "postgres", derive(sqlx::FromRow))]
+pub struct JobRun {
+    /// ジョブ名
+    pub name: String,
+    /// 最後に実行した日時
+    pub last_run_at: DateTime<Utc>,
+    /// 最後の実行で発生したエラーの内容（成功時は `None`）
+    pub last_error: Option<String>,
+    /// 連続して失敗した回数（成功すると 0 に戻る）
+    pub consecutive_failures: i32,
+}
+
+/// 定期実行ジョブの 1 回分の実行履歴。
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "postgres", derive(sqlx::FromRow))]
+pub struct JobRunRecord {
+    /// 実行を開始した日時
+    pub started_at: DateTime<Utc>,
+    /// 実行を終了した日時
+    pub finished_at: DateTime<Utc>,
+    /// 発生したエラーの内容（成功時は `None`）
+    pub error: Option<String>,
+}
+
+/// Wake-on-LAN のパケットを送った依頼元。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WakeRequester {
+    /// 定期実行ジョブ
+    Schedule,
+    /// Discord のユーザー ID
+    Discord(u64),
+    /// Telegram のユーザー ID
+    Telegram(i64),
+}
+
+/// サーバーに Wake-on-LAN のパケットを送った記録。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WakeRecord {
+    /// 記録の ID
+    pub id: i64,
+    /// サーバー名
+    pub server_name: String,
+    /// パケットを送った依頼元
+    pub requested_by: WakeRequester,
+    /// パケットを送った日時
+    pub sent_at: DateTime<Utc>,
+    /// 送った後に最初にオンラインになった日時（起動を確認できていない場合は None）
+    pub online_at: Option<DateTime<Utc>>,
+}
+
+impl WakeRecord {
+    /// パケットを送ってから起動するまでにかかった時間を返す（起動を確認できていない場合は None）。
+    pub fn boot_time(&self) -> Option<Duration> {
+        let elapsed = self.online_at? - self.sent_at;
+        Some(elapsed.to_std().unwrap_or_default())
+    }
+}
+
+/// ステータスチェックでサーバーがオンラインだった期間。
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "postgres", derive(sqlx::FromRow))]
+pub struct UptimeSpan {
+    /// サーバー名
+    pub server_name: String,
+    /// オンラインを最初に確認した日時
+    pub started_at: DateTime<Utc>,
+    /// オンラインを最後に確認した日時
+    pub last_seen_at: DateTime<Utc>,
+}
+
+impl UptimeSpan {
+    /// 指定した日時以降にオンラインだった時間を返す（`since` が None の場合は期間全体）。
+    pub fn duration_since(&self, since: Option<DateTime<Utc>>) -> Duration {
+        let started_at = since.map_or(self.started_at, |since| self.started_at.max(since));
+        (self.last_seen_at - started_at)
+            .to_std()
+            .unwrap_or_default()
+    }
+}
+
+/// ステータス通知の一時停止・定期実行ジョブの実行記録・サーバーの起動記録などの保存先。
+pub trait BotStorage: Sync {
+    /// ステータス通知を再開する日時を取得する（一時停止していない場合は None）。
+    fn get_status_snooze(&self) -> impl Future<Output = Result<Option<DateTime<Utc>>>> + Send;
+
+    /// ステータス通知を指定した日時まで一時停止する。
+    fn set_status_snooze(
+        &self,
+        snoozed_until: DateTime<Utc>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// ステータス通知の一時停止を解除する。
+    fn clear_status_snooze(&self) -> impl Future<Output = Result<()>> + Send;
+
+    /// 編集で更新し続けるステータスのメッセージの ID を取得する（まだ投稿していない場合は None）。
+    fn get_status_dashboard_message(&self) -> impl Future<Output = Result<Option<u64>>> + Send;
+
+    /// 編集で更新し続けるステータスのメッセージの ID を保存する。
+    fn set_status_dashboard_message(
+        &self,
+        message_id: u64,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Matrix の `/sync` を再開する位置を取得する（まだ同期していない場合は None）。
+    ///
+    /// # Arguments
+    /// * `user_id` - Bot の Matrix ユーザー ID
+    #[cfg_attr(not(feature = "diary"), allow(dead_code))]
+    fn get_matrix_sync_token(
+        &self,
+        user_id: &str,
+    ) -> impl Future<Output = Result<Option<String>>> + Send;
+
+    /// Matrix の `/sync` で得た `next_batch` を、次回の再開位置として保存する。
+    #[cfg_attr(not(feature = "diary"), allow(dead_code))]
+    fn set_matrix_sync_token(
+        &self,
+        user_id: &str,
+        next_batch: &str,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// 定期実行ジョブの実行記録を全件取得する。
+    fn get_job_runs(&self) -> impl Future<Output = Result<Vec<JobRun>>> + Send;
+
+    /// 定期実行ジョブの実行を履歴に追加し、最後の実行として記録する。
+    ///
+    /// 成功した場合は `error` に `None` を渡す。
+    ///
+    /// # Returns
+    /// 今回の実行を含めた連続失敗回数（成功した場合は 0）
+    fn record_job_run(
+        &self,
+        name: &str,
+        started_at: DateTime<Utc>,
+        finished_at: DateTime<Utc>,
+        error: Option<&str>,
+    ) -> impl Future<Output = Result<i32>> + Send;
+
+    /// 定期実行ジョブの実行履歴を新しい順に取得する。
+    fn get_job_run_history(
+        &self,
+        name: &str,
+        limit: i64,
+    ) -> impl Future<Output = Result<Vec<JobRunRecord>>> + Send;
+
+    /// サーバーに Wake-on-LAN のパケットを送ったことを記録し、記録の ID を返す。
+    fn record_wake(
+        &self,
+        server_name: &str,
+        requested_by: WakeRequester,
+        sent_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<i64>> + Send;
+
+    /// パケットを送った後にサーバーが最初にオンラインになった日時を記録する（記録済みの場合は何もしない）。
+    fn record_wake_online(
+        &self,
+        wake_id: i64,
+        online_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// サーバーに Wake-on-LAN のパケットを送った記録を新しい順に取得する。
+    fn get_wake_history(
+        &self,
+        server_name: &str,
+        limit: i64,
+    ) -> impl Future<Output = Result<Vec<WakeRecord>>> + Send;
+
+    /// サーバーがオンラインだったことを記録する。
+    ///
+    /// 最後の期間の確認から `max_gap` 以内であればその期間を延ばし、そうでなければ新しい期間を始める。
+    fn record_server_online(
+        &self,
+        server_name: &str,
+        seen_at: DateTime<Utc>,
+        max_gap: Duration,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// 指定した日時以降にオンラインだった期間をすべてのサーバーについて取得する（None の場合は全期間）。
+    fn get_uptime_spans(
+        &self,
+        since: Option<DateTime<Utc>>,
+    ) -> impl Future<Output = Result<Vec<UptimeSpan>>> + Send;
+}
+
+/// 設定で選んだ保存先のストア。
+///
+/// データベース URL に応じて `PostgresStore` か [`MemoryStore`] を使う。
+#[derive(Clone)]
+pub enum BotStore {
+    /// PostgreSQL に保存する
+    #[cfg(feature = "postgres")]
+    Postgres(PostgresStore),
+    /// メモリ上に保存する（再起動すると消える）
+    Memory(MemoryStore),
+}
+
+impl BotStore {
+    /// データベース URL に応じたストアを作成する。
+    ///
+    /// `memory:` の場合はメモリ上に保存し、それ以外は PostgreSQL に接続してマイグレーションを実行する。
+    /// `postgres` フィーチャーを含めずにビルドした場合は、URL に関わらずメモリ上に保存する。
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        if database_url.trim() == MEMORY_URL_SCHEME {
+            tracing::warn!("Using in-memory bot state store, data will be lost on restart");
+            return Ok(Self::Memory(MemoryStore::new()));
+        }
+        #[cfg(feature = "postgres")]
+        {
+            Ok(Self::Postgres(PostgresStore::connect(database_url).await?))
+        }
+        #[cfg(not(feature = "postgres"))]
+        {
+            tracing::warn!(
+                "PostgreSQL support is not built in, using in-memory bot state store instead"
+            );
+            Ok(Self::Memory(MemoryStore::new()))
+        }
+    }
+}
+
+/// 保存先のストアのメソッドを呼び出す。
+macro_rules! dispatch {
+    ($self:ident, $method:ident($($arg:expr),*)) => {
+        match $self {
+            #[cfg(feature = "postgres")]
+            BotStore::Postgres(store) => store.$method($($arg),*).await,
+            BotStore::Memory(store) => store.$method($($arg),*).await,
+        }
+    };
+}
+
+impl BotStorage for BotStore {
+    async fn get_status_snooze(&self) -> Result<Option<DateTime<Utc>>> {
+        dispatch!(self, get_status_snooze())
+    }
+
+    async fn set_status_snooze(&self, snoozed_until: DateTime<Utc>) -> Result<()> {
+        dispatch!(self, set_status_snooze(snoozed_until))
+    }
+
+    async fn clear_status_snooze(&self) -> Result<()> {
+        dispatch!(self, clear_status_snooze())
+    }
+
+    async fn get_status_dashboard_message(&self) -> Result<Option<u64>> {
+        dispatch!(self, get_status_dashboard_message())
+    }
+
+    async fn set_status_dashboard_message(&self, message_id: u64) -> Result<()> {
+        dispatch!(self, set_status_dashboard_message(message_id))
+    }
+
+    async fn get_matrix_sync_token(&self, user_id: &str) -> Result<Option<String>> {
+        dispatch!(self, get_matrix_sync_token(user_id))
+    }
+
+    async fn set_matrix_sync_token(&self, user_id: &str, next_batch: &str) -> Result<()> {
+        dispatch!(self, set_matrix_sync_token(user_id, next_batch))
+    }
+
+    async fn get_job_runs(&self) -> Result<Vec<JobRun>> {
+        dispatch!(self, get_job_runs())
+    }
+
+    async fn record_job_run(
+        &self,
+        name: &str,
+        started_at: DateTime<Utc>,
+        finished_at: DateTime<Utc>,
+        error: Option<&str>,
+    ) -> Result<i32> {
+        dispatch!(self, record_job_run(name, started_at, finished_at, error))
+    }
+
+    async fn get_job_run_history(&self, name: &str, limit: i64) -> Result<Vec<JobRunRecord>> {
+        dispatch!(self, get_job_run_history(name, limit))
+    }
+
+    async fn record_wake(
+        &self,
+        server_name: &str,
+        requested_by: WakeRequester,
+        sent_at: DateTime<Utc>,
+    ) -> Result<i64> {
+        dispatch!(self, record_wake(server_name, requested_by, sent_at))
+    }
+
+    async fn record_wake_online(&self, wake_id: i64, online_at: DateTime<Utc>) -> Result<()> {
+        dispatch!(self, record_wake_online(wake_id, online_at))
+    }
+
+    async fn get_wake_history(&self, server_name: &str, limit: i64) -> Result<Vec<WakeRecord>> {
+        dispatch!(self, get_wake_history(server_name, limit))
+    }
+
+    async fn record_server_online(
+        &self,
+        server_name: &str,
+        seen_at: DateTime<Utc>,
+        max_gap: Duration,
+    ) -> Result<()> {
+        dispatch!(self, record_server_online(server_name, seen_at, max_gap))
+    }
+
+    async fn get_uptime_spans(&self, since: Option<DateTime<Utc>>) -> Result<Vec<UptimeSpan>> {
+        dispatch!(self, get_uptime_spans(since))
+    }
+}
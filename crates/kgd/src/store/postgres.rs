@@ -0,0 +1,354 @@
+//! PostgreSQL に保存するストア。
+
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool, postgres::PgPoolOptions};
+
+use super::{BotStorage, JobRun, JobRunRecord, UptimeSpan, WakeRecord, WakeRequester};
+
+/// `server_wakes.frontend` に記録する、WOL を送った依頼元の名前。
+const FRONTEND_SCHEDULE: &str = "schedule";
+const FRONTEND_DISCORD: &str = "discord";
+const FRONTEND_TELEGRAM: &str = "telegram";
+
+/// PostgreSQL に保存するストア。
+#[derive(Clone)]
+pub struct PostgresStore {
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    /// データベースに接続し、マイグレーションを実行する。
+    ///
+    /// 日報と同じデータベースを使う場合は日報のマイグレーションも同じ履歴に記録されるため、
+    /// このストアに無いマイグレーションは無視する。
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url)
+            .await
+            .context("Failed to connect to database")?;
+
+        // マイグレーションを実行
+        let mut migrator = sqlx::migrate!("./migrations");
+        migrator.set_ignore_missing(true);
+        migrator
+            .run(&pool)
+            .await
+            .context("Failed to run migrations")?;
+
+        Ok(Self { pool })
+    }
+}
+
+impl BotStorage for PostgresStore {
+    async fn get_status_snooze(&self) -> Result<Option<DateTime<Utc>>> {
+        sqlx::query_scalar("SELECT snoozed_until FROM status_snooze")
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch status snooze")
+    }
+
+    async fn set_status_snooze(&self, snoozed_until: DateTime<Utc>) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO status_snooze (snoozed_until)
+            VALUES ($1)
+            ON CONFLICT (id) DO UPDATE SET snoozed_until = EXCLUDED.snoozed_until
+            "#,
+        )
+        .bind(snoozed_until)
+        .execute(&self.pool)
+        .await
+        .context("Failed to set status snooze")?;
+        Ok(())
+    }
+
+    async fn clear_status_snooze(&self) -> Result<()> {
+        sqlx::query("DELETE FROM status_snooze")
+            .execute(&self.pool)
+            .await
+            .context("Failed to clear status snooze")?;
+        Ok(())
+    }
+
+    async fn get_status_dashboard_message(&self) -> Result<Option<u64>> {
+        let message_id: Option<i64> = sqlx::query_scalar("SELECT message_id FROM status_dashboard")
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch status dashboard message")?;
+        Ok(message_id.map(|message_id| message_id as u64))
+    }
+
+    async fn set_status_dashboard_message(&self, message_id: u64) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO status_dashboard (message_id)
+            VALUES ($1)
+            ON CONFLICT (id) DO UPDATE SET message_id = EXCLUDED.message_id
+            "#,
+        )
+        .bind(message_id as i64)
+        .execute(&self.pool)
+        .await
+        .context("Failed to set status dashboard message")?;
+        Ok(())
+    }
+
+    async fn get_matrix_sync_token(&self, user_id: &str) -> Result<Option<String>> {
+        sqlx::query_scalar("SELECT next_batch FROM matrix_sync_tokens WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch Matrix sync token")
+    }
+
+    async fn set_matrix_sync_token(&self, user_id: &str, next_batch: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO matrix_sync_tokens (user_id, next_batch)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET next_batch = EXCLUDED.next_batch
+            "#,
+        )
+        .bind(user_id)
+        .bind(next_batch)
+        .execute(&self.pool)
+        .await
+        .context("Failed to set Matrix sync token")?;
+        Ok(())
+    }
+
+    async fn get_job_runs(&self) -> Result<Vec<JobRun>> {
+        sqlx::query_as(
+            r#"
+            SELECT name, last_run_at, last_error, consecutive_failures
+            FROM scheduled_job_runs
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch scheduled job runs")
+    }
+
+    async fn record_job_run(
+        &self,
+        name: &str,
+        started_at: DateTime<Utc>,
+        finished_at: DateTime<Utc>,
+        error: Option<&str>,
+    ) -> Result<i32> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO scheduled_job_run_history (name, started_at, finished_at, error)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(name)
+        .bind(started_at)
+        .bind(finished_at)
+        .bind(error)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to insert scheduled job run history")?;
+
+        let consecutive_failures: i32 = sqlx::query_scalar(
+            r#"
+            INSERT INTO scheduled_job_runs (name, last_run_at, last_error, consecutive_failures)
+            VALUES ($1, $2, $3, CASE WHEN $3::TEXT IS NULL THEN 0 ELSE 1 END)
+            ON CONFLICT (name) DO UPDATE SET
+                last_run_at = EXCLUDED.last_run_at,
+                last_error = EXCLUDED.last_error,
+                consecutive_failures = CASE
+                    WHEN EXCLUDED.last_error IS NULL THEN 0
+                    ELSE scheduled_job_runs.consecutive_failures + 1
+                END
+            RETURNING consecutive_failures
+            "#,
+        )
+        .bind(name)
+        .bind(started_at)
+        .bind(error)
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to record scheduled job run")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(consecutive_failures)
+    }
+
+    async fn get_job_run_history(&self, name: &str, limit: i64) -> Result<Vec<JobRunRecord>> {
+        sqlx::query_as(
+            r#"
+            SELECT started_at, finished_at, error
+            FROM scheduled_job_run_history
+            WHERE name = $1
+            ORDER BY started_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(name)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch scheduled job run history")
+    }
+
+    async fn record_wake(
+        &self,
+        server_name: &str,
+        requested_by: WakeRequester,
+        sent_at: DateTime<Utc>,
+    ) -> Result<i64> {
+        let (frontend, user_id) = match requested_by {
+            WakeRequester::Schedule => (FRONTEND_SCHEDULE, None),
+            WakeRequester::Discord(user_id) => (FRONTEND_DISCORD, Some(user_id as i64)),
+            WakeRequester::Telegram(user_id) => (FRONTEND_TELEGRAM, Some(user_id)),
+        };
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO server_wakes (server_name, user_id, frontend, sent_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
+            "#,
+        )
+        .bind(server_name)
+        .bind(user_id)
+        .bind(frontend)
+        .bind(sent_at)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to record server wake")
+    }
+
+    async fn record_wake_online(&self, wake_id: i64, online_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE server_wakes
+            SET online_at = $2
+            WHERE id = $1 AND online_at IS NULL
+            "#,
+        )
+        .bind(wake_id)
+        .bind(online_at)
+        .execute(&self.pool)
+        .await
+        .context("Failed to record server online time")?;
+        Ok(())
+    }
+
+    async fn get_wake_history(&self, server_name: &str, limit: i64) -> Result<Vec<WakeRecord>> {
+        let rows: Vec<WakeRow> = sqlx::query_as(
+            r#"
+            SELECT id, server_name, user_id, frontend, sent_at, online_at
+            FROM server_wakes
+            WHERE server_name = $1
+            ORDER BY sent_at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(server_name)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch server wake history")?;
+
+        Ok(rows.into_iter().map(WakeRecord::from).collect())
+    }
+
+    async fn record_server_online(
+        &self,
+        server_name: &str,
+        seen_at: DateTime<Utc>,
+        max_gap: Duration,
+    ) -> Result<()> {
+        let max_gap = chrono::Duration::from_std(max_gap).context("Invalid uptime gap")?;
+        let extended = sqlx::query(
+            r#"
+            UPDATE server_uptime
+            SET last_seen_at = $2
+            WHERE id = (
+                SELECT id FROM server_uptime
+                WHERE server_name = $1
+                ORDER BY last_seen_at DESC, id DESC
+                LIMIT 1
+            )
+            AND last_seen_at >= $3
+            "#,
+        )
+        .bind(server_name)
+        .bind(seen_at)
+        .bind(seen_at - max_gap)
+        .execute(&self.pool)
+        .await
+        .context("Failed to extend server uptime")?
+        .rows_affected();
+
+        if extended == 0 {
+            sqlx::query(
+                r#"
+                INSERT INTO server_uptime (server_name, started_at, last_seen_at)
+                VALUES ($1, $2, $2)
+                "#,
+            )
+            .bind(server_name)
+            .bind(seen_at)
+            .execute(&self.pool)
+            .await
+            .context("Failed to record server uptime")?;
+        }
+        Ok(())
+    }
+
+    async fn get_uptime_spans(&self, since: Option<DateTime<Utc>>) -> Result<Vec<UptimeSpan>> {
+        sqlx::query_as(
+            r#"
+            SELECT server_name, started_at, last_seen_at
+            FROM server_uptime
+            WHERE $1::TIMESTAMPTZ IS NULL OR last_seen_at >= $1
+            ORDER BY started_at
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch server uptime")
+    }
+}
+
+/// `server_wakes` の 1 行（依頼元をユーザー ID とフロントエンドの列で表すため、[`WakeRecord`] に変換して返す）。
+#[derive(FromRow)]
+struct WakeRow {
+    id: i64,
+    server_name: String,
+    user_id: Option<i64>,
+    frontend: Option<String>,
+    sent_at: DateTime<Utc>,
+    online_at: Option<DateTime<Utc>>,
+}
+
+impl From<WakeRow> for WakeRecord {
+    fn from(row: WakeRow) -> Self {
+        Self {
+            id: row.id,
+            server_name: row.server_name,
+            // フロントエンドを記録する前の行は、ユーザー ID があれば Discord から送ったもの
+            requested_by: match (row.frontend.as_deref(), row.user_id) {
+                (Some(FRONTEND_TELEGRAM), Some(user_id)) => WakeRequester::Telegram(user_id),
+                (_, Some(user_id)) => WakeRequester::Discord(user_id as u64),
+                (_, None) => WakeRequester::Schedule,
+            },
+            sent_at: row.sent_at,
+            online_at: row.online_at,
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! Telegram の Bot でコマンドを受け付ける。
+//!
+//! Discord が使えないときの予備として、Bot API の long polling（`getUpdates`）で受け取った
+//! `/wol`・`/status` を Discord と同じ権限の確認で実行する。
+//! 電源の操作は確認の手順を挟めないため、Telegram からは受け付けない。
+
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use serde::Deserialize;
+use tracing::{error, info, warn};
+
+use crate::{
+    command::{self, Command},
+    config::{Config, TelegramConfig},
+    store::{BotStore, WakeRequester},
+};
+
+/// Bot API のベース URL。
+const BOT_API_URL: &str = "https://api.telegram.org";
+
+/// long polling の待ち時間に加えて HTTP リクエストを待つ時間。
+const HTTP_TIMEOUT_MARGIN: Duration = Duration::from_secs(30);
+
+/// API の呼び出しに失敗したときに再試行するまでの待ち時間。
+const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Telegram の Bot のフロントエンド。
+pub struct TelegramFrontend {
+    /// アプリケーション全体の設定
+    config: Config,
+    /// WOL を送った記録を保存するストア
+    bot_store: BotStore,
+    http_client: reqwest::Client,
+}
+
+impl TelegramFrontend {
+    /// 新しい TelegramFrontend を作成する。
+    ///
+    /// Telegram の Bot は誰でもメッセージを送れるため、Discord と違って管理者が空の場合は
+    /// 全員に許可せず、エラーにする。
+    pub fn new(config: Config, bot_store: BotStore) -> Result<Self> {
+        let telegram_config = config
+            .telegram
+            .as_ref()
+            .context("Telegram is not configured")?;
+        anyhow::ensure!(
+            !telegram_config.admins.is_empty(),
+            "telegram.admins is empty: anyone who finds the bot could run commands, list your Telegram user IDs"
+        );
+        let http_client = reqwest::Client::builder()
+            .timeout(telegram_config.poll_timeout + HTTP_TIMEOUT_MARGIN)
+            .user_agent("kgd-bot/1.0")
+            .build()
+            .context("Failed to create HTTP client for Telegram")?;
+
+        Ok(Self {
+            config,
+            bot_store,
+            http_client,
+        })
+    }
+
+    fn telegram_config(&self) -> &TelegramConfig {
+        self.config
+            .telegram
+            .as_ref()
+            .expect("TelegramFrontend is created only with Telegram configured")
+    }
+
+    /// コマンドを受け付け続ける。
+    ///
+    /// 起動前に送られたコマンドは実行しない。
+    pub async fn run(self) {
+        if let Err(e) = self.register_commands().await {
+            warn!(error = %e, "Failed to register Telegram commands");
+        }
+        info!("Telegram command frontend started");
+
+        let mut offset: Option<i64> = None;
+        loop {
+            // 初回は最後の更新だけを待たずに取得し、それより前の更新を読み捨てる
+            let (request_offset, timeout) = match offset {
+                Some(offset) => (offset, self.telegram_config().poll_timeout),
+                None => (-1, Duration::ZERO),
+            };
+            let updates = match self.get_updates(request_offset, timeout).await {
+                Ok(updates) => updates,
+                Err(e) => {
+                    error!(error = %e, "Failed to fetch Telegram updates, retrying");
+                    tokio::time::sleep(RETRY_INTERVAL).await;
+                    continue;
+                }
+            };
+
+            let is_first = offset.is_none();
+            offset = Some(
+                updates
+                    .iter()
+                    .map(|update| update.update_id + 1)
+                    .max()
+                    .unwrap_or(offset.unwrap_or(0)),
+            );
+            if is_first {
+                continue;
+            }
+
+            for message in updates.into_iter().filter_map(|update| update.message) {
+                if let Err(e) = self.handle_message(&message).await {
+                    error!(error = %e, chat_id = message.chat.id, "Failed to handle Telegram command");
+                }
+            }
+        }
+    }
+
+    /// メッセージのコマンドを実行し、結果を返信する。
+    async fn handle_message(&self, message: &TelegramMessage) -> Result<()> {
+        let Some(text) = &message.text else {
+            return Ok(());
+        };
+        let command = match Command::parse_text(text) {
+            Ok(Some(command)) => command,
+            Ok(None) => return Ok(()),
+            Err(e) => return self.send_message(message.chat.id, &e.to_string()).await,
+        };
+
+        let user_id = message.from.as_ref().map(|user| user.id);
+        let Some(user_id) =
+            user_id.filter(|id| command::is_authorized(&self.telegram_config().admins, id))
+        else {
+            warn!(user_id, "Unauthorized access attempt");
+            return self
+                .send_message(message.chat.id, "You are not authorized to use this bot.")
+                .await;
+        };
+
+        let requested_by = WakeRequester::Telegram(user_id);
+        let reply =
+            match command::execute(&self.config, &self.bot_store, &command, requested_by).await {
+                Ok(reply) => reply,
+                Err(e) => {
+                    error!(error = %e, command = ?command, "Failed to execute Telegram command");
+                    format!("Error: {:#}", e)
+                }
+            };
+        self.send_message(message.chat.id, &reply).await
+    }
+
+    /// コマンドの一覧を Telegram に登録する（入力時の候補に表示される）。
+    async fn register_commands(&self) -> Result<()> {
+        let body = serde_json::json!({
+            "commands": [
+                { "command": "wol", "description": "Wake up a server using Wake-on-LAN" },
+                { "command": "status", "description": "Check the server status" },
+            ]
+        });
+        let _: bool = self.call("setMyCommands", &body).await?;
+        Ok(())
+    }
+
+    /// 前回の取得以降の更新を取得する。
+    async fn get_updates(&self, offset: i64, timeout: Duration) -> Result<Vec<TelegramUpdate>> {
+        let body = serde_json::json!({
+            "offset": offset,
+            "timeout": timeout.as_secs(),
+            "allowed_updates": ["message"],
+        });
+        self.call("getUpdates", &body).await
+    }
+
+    /// チャットにメッセージを送信する。
+    async fn send_message(&self, chat_id: i64, text: &str) -> Result<()> {
+        let body = serde_json::json!({
+            "chat_id": chat_id,
+            "text": text,
+        });
+        let _: serde_json::Value = self.call("sendMessage", &body).await?;
+        Ok(())
+    }
+
+    /// Bot API のメソッドを呼び出し、結果を返す。
+    async fn call<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        body: &serde_json::Value,
+    ) -> Result<T> {
+        let url = format!(
+            "{}/bot{}/{}",
+            BOT_API_URL,
+            self.telegram_config().bot_token,
+            method
+        );
+        // URL にトークンを含むため、エラーには URL を含めない
+        let response: TelegramResponse<T> = self
+            .http_client
+            .post(url)
+            .json(body)
+            .send()
+            .await
+            .map_err(reqwest::Error::without_url)
+            .context("Telegram request failed")?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)
+            .context("Failed to parse Telegram response")?;
+
+        if !response.ok {
+            anyhow::bail!(
+                "Telegram API error: {}",
+                response.description.unwrap_or_default()
+            );
+        }
+        response
+            .result
+            .with_context(|| format!("Telegram {} returned no result", method))
+    }
+}
+
+/// Bot API のレスポンス。
+#[derive(Debug, Deserialize)]
+struct TelegramResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+/// Bot が受け取った更新。
+#[derive(Debug, Deserialize)]
+struct TelegramUpdate {
+    update_id: i64,
+    message: Option<TelegramMessage>,
+}
+
+/// チャットのメッセージ。
+#[derive(Debug, Deserialize)]
+struct TelegramMessage {
+    /// 送信者（チャンネルの投稿などでは None）
+    from: Option<TelegramUser>,
+    chat: TelegramChat,
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TelegramUser {
+    id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TelegramChat {
+    id: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    #[test]
+    fn test_parse_updates() {
+        let response: TelegramResponse<Vec<TelegramUpdate>> =
+            serde_json::from_value(serde_json::json!({
+                "ok": true,
+                "result": [{
+                    "update_id": 10,
+                    "message": {
+                        "message_id": 1,
+                        "from": { "id": 42, "is_bot": false, "first_name": "A" },
+                        "chat": { "id": -100, "type": "group" },
+                        "date": 1700000000,
+                        "text": "/wol@kgd_bot Main Server"
+                    }
+                }, {
+                    "update_id": 11
+                }]
+            }))
+            .unwrap();
+
+        let updates = response.result.unwrap();
+        let message = updates[0].message.as_ref().unwrap();
+        assert_eq!(message.from.as_ref().unwrap().id, 42);
+        assert_eq!(message.chat.id, -100);
+        assert_eq!(
+            Command::parse_text(message.text.as_deref().unwrap()).unwrap(),
+            Some(Command::Wol {
+                server: "Main Server".to_string()
+            })
+        );
+        assert!(updates[1].message.is_none());
+    }
+
+    #[test]
+    fn test_new_requires_admins() {
+        let mut config = Config {
+            telegram: Some(TelegramConfig {
+                bot_token: "token".to_string(),
+                admins: vec![],
+                poll_timeout: Duration::from_secs(30),
+            }),
+            ..toml::from_str(include_str!("../../../config.example.toml")).unwrap()
+        };
+        let store = BotStore::Memory(MemoryStore::new());
+        assert!(TelegramFrontend::new(config.clone(), store.clone()).is_err());
+
+        config.telegram.as_mut().unwrap().admins = vec![42];
+        assert!(TelegramFrontend::new(config, store).is_ok());
+    }
+}
@@ -0,0 +1,139 @@
+//! GitHub のリリースから kgd の新しいバージョンを確認する機能を提供する。
+
+use std::{sync::Mutex, time::Duration};
+
+use anyhow::{Context as _, Result};
+use semver::Version;
+use serde::Deserialize;
+
+use crate::version;
+
+/// GitHub API のリクエストのタイムアウト。
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 実行中より新しいリリース。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    /// バージョン
+    pub version: Version,
+    /// リリースノートのページの URL
+    pub url: String,
+}
+
+/// kgd の新しいリリースを確認する構造体。
+pub struct UpdateChecker {
+    http_client: reqwest::Client,
+    /// リリースを確認する GitHub のリポジトリ（`owner/repo`）
+    repository: String,
+    /// 実行中のバージョン
+    current: Version,
+    /// 最後に確認できた新しいリリース
+    available: Mutex<Option<Release>>,
+}
+
+impl UpdateChecker {
+    /// 新しい UpdateChecker を作成する。
+    pub fn new(repository: impl Into<String>) -> Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent("kgd-bot/1.0")
+            .build()
+            .context("Failed to create HTTP client for update check")?;
+        let current = Version::parse(version::VERSION).context("Invalid kgd version")?;
+
+        Ok(Self {
+            http_client,
+            repository: repository.into(),
+            current,
+            available: Mutex::new(None),
+        })
+    }
+
+    /// 最新のリリースを確認する。
+    ///
+    /// 実行中より新しく、前回までに見つけていないリリースの場合だけ返す（通知を 1 回にするため）。
+    pub async fn check(&self) -> Result<Option<Release>> {
+        let response = self
+            .http_client
+            .get(format!(
+                "https://api.github.com/repos/{}/releases/latest",
+                self.repository
+            ))
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .send()
+            .await
+            .context("HTTP request failed")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("GitHub API error: {}", response.status());
+        }
+
+        let latest: GitHubRelease = response
+            .json()
+            .await
+            .context("Failed to parse GitHub release")?;
+        let Some(release) = newer_release(&self.current, latest) else {
+            return Ok(None);
+        };
+
+        let mut available = self.available.lock().unwrap();
+        if available.as_ref() == Some(&release) {
+            return Ok(None);
+        }
+        *available = Some(release.clone());
+        Ok(Some(release))
+    }
+
+    /// 確認できた新しいリリースを返す。
+    pub fn available_update(&self) -> Option<Release> {
+        self.available.lock().unwrap().clone()
+    }
+}
+
+/// GitHub のリリース。
+#[derive(Debug, Deserialize)]
+struct GitHubRelease {
+    /// タグ名（`v1.2.3` 形式）
+    tag_name: String,
+    /// リリースのページの URL
+    html_url: String,
+}
+
+/// リリースが実行中のバージョンより新しければ返す。
+///
+/// バージョンとして解釈できないタグは無視する。
+fn newer_release(current: &Version, release: GitHubRelease) -> Option<Release> {
+    let tag = release.tag_name.trim_start_matches('v');
+    let version = Version::parse(tag).ok()?;
+    (version > *current).then_some(Release {
+        version,
+        url: release.html_url,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag_name: &str) -> GitHubRelease {
+        GitHubRelease {
+            tag_name: tag_name.to_string(),
+            html_url: format!("https://github.com/ekuinox/kgd/releases/tag/{}", tag_name),
+        }
+    }
+
+    #[test]
+    fn test_newer_release() {
+        let current = Version::new(0, 3, 0);
+        assert_eq!(
+            newer_release(&current, release("v0.4.1")),
+            Some(Release {
+                version: Version::new(0, 4, 1),
+                url: "https://github.com/ekuinox/kgd/releases/tag/v0.4.1".to_string(),
+            })
+        );
+        assert_eq!(newer_release(&current, release("0.3.0")), None);
+        assert_eq!(newer_release(&current, release("v0.2.9")), None);
+        assert_eq!(newer_release(&current, release("nightly")), None);
+    }
+}